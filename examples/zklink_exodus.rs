@@ -0,0 +1,62 @@
+use advanced_circuit_component::testing::{create_test_artifacts_with_optimized_gate, Bn256};
+use base64::prelude::*;
+use zklink_oracle::{
+    franklin_crypto::bellman::plonk::better_better_cs::cs::Circuit,
+    redstone::{
+        witness::{DataPackage, DataPoint, SignedPackageWitness},
+        PriceOracle,
+    },
+    zklink::{MockExodusCircuit, OracleRound},
+};
+
+/// Wire the output of a RedStone oracle proof into a mocked zkLink exodus circuit. Run with
+/// `cargo run --example zklink_exodus`.
+fn main() -> anyhow::Result<()> {
+    let data_package = DataPackage::new(
+        vec![
+            DataPoint::new("BTC", "20000"),
+            DataPoint::new("ETH", "1000"),
+        ],
+        1654353400000u64,
+    );
+    let signature: [u8; 65] = BASE64_STANDARD
+        .decode("NX5yd/Cs8HzVdNchrM59uOoSst7n9KK5Ou9pA6S5GTM0RwghGlFjA0S+SVfb85ipg4HzUTKATBZSqPXlWldEEhw=")?
+        .try_into()
+        .unwrap();
+    let guardians = [data_package.recover_signer(&signature)?];
+    let oracle = PriceOracle::<Bn256, 1, 2>::new_shared(
+        ["BTC".to_string(), "ETH".to_string()],
+        vec![[SignedPackageWitness::new(
+            data_package,
+            signature,
+            guardians[0],
+        )]],
+        guardians,
+    )?;
+
+    // 1. Prove prices by the oracle circuit, whose only public input is the commitment.
+    let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+    oracle.synthesize(&mut cs)?;
+    assert!(cs.is_satisfied());
+    println!("oracle commitment: {}", oracle.commitment);
+
+    // 2. Hand over the committed prices to zkLink as packed bytes.
+    let opening = oracle.opening()?;
+    for round in opening.rounds.iter() {
+        let bytes = round.to_bytes()?;
+        println!("packed round: 0x{}", hex::encode(&bytes));
+        assert_eq!(&OracleRound::from_bytes(&bytes)?, round);
+    }
+
+    // 3. Exit with the ETH price in the zkLink circuit, which shares the oracle commitment.
+    let exodus = MockExodusCircuit {
+        opening,
+        commitment: oracle.commitment,
+        feed_index: 1,
+    };
+    let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+    exodus.synthesize(&mut cs)?;
+    assert!(cs.is_satisfied());
+    println!("exodus circuit contains {} gates", cs.n());
+    Ok(())
+}
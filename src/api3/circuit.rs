@@ -0,0 +1,145 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{ff::Field, Engine};
+use advanced_circuit_component::franklin_crypto::{
+    bellman::{
+        plonk::better_better_cs::{
+            cs::{Circuit, ConstraintSystem, Gate, GateInternal},
+            gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
+        },
+        SynthesisError,
+    },
+    plonk::circuit::{allocated_num::Num, boolean::Boolean, custom_rescue_gate::Rescue5CustomGate},
+};
+use num_bigint::BigUint;
+
+use crate::{
+    chainlink::fr_from_bigint,
+    gadgets::{
+        ethereum::Address,
+        poseidon::{circuit_poseidon_hash, poseidon_hash},
+        rescue::circuit_rescue_hash,
+    },
+    utils::{self, fr_from_biguint, num_from_be_bytes},
+};
+
+use super::signed_data::{AllocatedSignedData, SignedData};
+
+/// Circuit verifying data of beacons signed by an Airnode.
+///
+/// Public input is the commitment `poseidon(airnode, (templateId[..16], templateId[16..],
+/// timestamp, value) of each signed data)`, where negative values are encoded as `r - |value|`.
+/// Beacon ids are derived from the airnode and template ids as
+/// [`SignedData::beacon_id`](super::SignedData::beacon_id).
+#[derive(Debug, Clone)]
+pub struct Api3PriceOracle<E: Engine> {
+    pub signed_data: Vec<SignedData>,
+    pub airnode: [u8; 20],
+    pub commitment: E::Fr,
+}
+
+impl<E: Engine> Api3PriceOracle<E> {
+    pub fn new(signed_data: Vec<SignedData>, airnode: [u8; 20]) -> Result<Self, anyhow::Error> {
+        let mut input = vec![fr_from_biguint::<E>(&BigUint::from_bytes_be(&airnode))?];
+        for data in signed_data.iter() {
+            let value = data.check(&airnode)?;
+            input.push(fr_from_biguint::<E>(&BigUint::from_bytes_be(
+                &data.template_id[..16],
+            ))?);
+            input.push(fr_from_biguint::<E>(&BigUint::from_bytes_be(
+                &data.template_id[16..],
+            ))?);
+            input.push(fr_from_biguint::<E>(&BigUint::from(data.timestamp))?);
+            input.push(fr_from_bigint::<E>(&value)?);
+        }
+        Ok(Self {
+            signed_data,
+            airnode,
+            commitment: poseidon_hash::<E>(&input),
+        })
+    }
+}
+
+impl<E: Engine> Circuit<E> for Api3PriceOracle<E> {
+    type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
+
+    fn synthesize<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<(), SynthesisError> {
+        utils::add_bitwise_logic_and_range_table(cs)?;
+        let temp_variable = Num::alloc(cs, Some(E::Fr::one()))?;
+        circuit_rescue_hash(cs, &[temp_variable])?; // Just to standardize the proof format
+
+        let airnode = Address::from_address_witness(cs, &self.airnode)?;
+        let mut is_valid = Boolean::constant(true);
+        let mut input = vec![airnode.inner().to_num_unchecked(cs)?];
+        for data in self.signed_data.iter() {
+            let data = AllocatedSignedData::from_witness(cs, data)?;
+            let (is_current_valid, verified) = data.verify(cs, &airnode)?;
+            is_valid = Boolean::and(cs, &is_valid, &is_current_valid)?;
+            input.push(num_from_be_bytes(cs, &data.template_id[..16])?);
+            input.push(num_from_be_bytes(cs, &data.template_id[16..])?);
+            input.push(verified.timestamp);
+            input.push(verified.value);
+        }
+        Boolean::enforce_equal(cs, &is_valid, &Boolean::constant(true))?;
+
+        let commitment = circuit_poseidon_hash(cs, &input)?;
+        let expected_commitment = Num::alloc(cs, Some(self.commitment))?;
+        expected_commitment.enforce_equal(cs, &commitment)?;
+        expected_commitment.get_variable().inputize(cs)?;
+        Ok(())
+    }
+
+    fn declare_used_gates() -> Result<Vec<Box<dyn GateInternal<E>>>, SynthesisError> {
+        Ok(vec![
+            Self::MainGate::default().into_internal(),
+            Rescue5CustomGate.into_internal(), // Just to standardize the proof format
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::bellman::{pairing::ff::Field, plonk::better_better_cs::cs::Circuit},
+        testing::{create_test_artifacts_with_optimized_gate, Bn256},
+    };
+
+    use super::Api3PriceOracle;
+    use crate::{api3::SignedData, utils::signer::RedstoneSigner};
+
+    #[test]
+    fn test_api3_price_oracle() -> anyhow::Result<()> {
+        let airnode = RedstoneSigner::new(0x33);
+        let sign = |template: u8, value: i128| {
+            let mut data = [if value < 0 { 0xff } else { 0 }; 32];
+            data[16..].copy_from_slice(&value.to_be_bytes());
+            let mut signed_data = SignedData {
+                template_id: [template; 32],
+                timestamp: 1_700_000_000,
+                data,
+                signature: [0u8; 65],
+            };
+            signed_data.signature = airnode.sign_hash(&signed_data.message_hash());
+            signed_data
+        };
+        let signed_data = vec![
+            sign(1, 2_530_120_000_000_000_000_000),
+            sign(2, 42_800_000_000_000_000_000_000),
+            sign(3, -5_000_000_000_000_000),
+        ];
+
+        let circuit = Api3PriceOracle::<Bn256>::new(signed_data.clone(), airnode.address())?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        println!("Roughly {} gates", cs.n());
+
+        let mut tampered = circuit;
+        tampered.commitment.add_assign(&Field::one());
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        tampered.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+
+        let other = RedstoneSigner::new(0x34).address();
+        assert!(Api3PriceOracle::<Bn256>::new(signed_data, other).is_err());
+        Ok(())
+    }
+}
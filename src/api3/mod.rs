@@ -0,0 +1,5 @@
+mod circuit;
+mod signed_data;
+
+pub use circuit::*;
+pub use signed_data::*;
@@ -0,0 +1,254 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    utils::u64_to_fe,
+    vm::{partitioner::smart_and, primitives::uint256::UInt256},
+};
+use num_bigint::{BigInt, BigUint};
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    gadgets::{
+        ecdsa::Signature,
+        ethereum::{circuit_eth_signed_message_hash, eth_signed_message_hash, Address},
+        keccak256,
+    },
+    redstone::{witness::recover_signer, SIGNATURE_BS},
+    utils::{fr_from_biguint, num_from_be_bytes},
+};
+
+// Number of bits of beacon values, which are `int224`
+pub const BEACON_VALUE_BITS: usize = 224;
+// Number of leading bytes of the encoded `int256` that sign-extend `int224`
+const BEACON_VALUE_SIGN_EXTENSION_BS: usize = 32 - BEACON_VALUE_BITS / 8;
+
+/// Data signed by an Airnode for the beacon of `templateId`, as accepted by
+/// `Api3ServerV1.updateBeaconWithSignedData`. The signature is over the Ethereum signed message of
+/// `keccak256(templateId || timestamp || data)`, where `timestamp` is `uint256` and `data` is an
+/// ABI-encoded `int256` value fitting `int224`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedData {
+    pub template_id: [u8; 32],
+    pub timestamp: u32,
+    pub data: [u8; 32],
+    pub signature: [u8; SIGNATURE_BS],
+}
+
+impl SignedData {
+    /// Id of the beacon updated by the data, which is `keccak256(airnode || templateId)`.
+    pub fn beacon_id(&self, airnode: &[u8; 20]) -> [u8; 32] {
+        Keccak256::new()
+            .chain_update(airnode)
+            .chain_update(self.template_id)
+            .finalize()
+            .into()
+    }
+
+    /// Hash whose signature is checked.
+    pub fn message_hash(&self) -> [u8; 32] {
+        let mut timestamp = [0u8; 32];
+        timestamp[28..].copy_from_slice(&self.timestamp.to_be_bytes());
+        let hash = Keccak256::new()
+            .chain_update(self.template_id)
+            .chain_update(timestamp)
+            .chain_update(self.data)
+            .finalize();
+        eth_signed_message_hash(&hash.into())
+    }
+
+    /// Decode the value from data, which must fit `int224`.
+    pub fn value(&self) -> anyhow::Result<BigInt> {
+        let value = BigInt::from_signed_bytes_be(&self.data);
+        if value.bits() >= BEACON_VALUE_BITS as u64 {
+            anyhow::bail!("{} overflows int224", value)
+        }
+        Ok(value)
+    }
+
+    /// Check the data is signed by `airnode` and return the decoded value.
+    pub fn check(&self, airnode: &[u8; 20]) -> anyhow::Result<BigInt> {
+        let value = self.value()?;
+        let signer = recover_signer(&self.message_hash(), &self.signature)?;
+        if signer != *airnode {
+            anyhow::bail!(
+                "data of template 0x{} is signed by 0x{}, expect airnode 0x{}",
+                hex::encode(self.template_id),
+                hex::encode(signer),
+                hex::encode(airnode)
+            )
+        }
+        Ok(value)
+    }
+}
+
+/// Circuit representation of [`SignedData`].
+#[derive(Debug, Clone)]
+pub struct AllocatedSignedData<E: Engine> {
+    pub template_id: [Byte<E>; 32],
+    pub timestamp: [Byte<E>; 4],
+    pub data: [Byte<E>; 32],
+    pub signature: Signature<E>,
+}
+
+/// Signed data verified by [`AllocatedSignedData::verify`].
+#[derive(Debug, Clone, Copy)]
+pub struct VerifiedData<E: Engine> {
+    pub timestamp: Num<E>,
+    /// Value of the beacon, where negative values are encoded as `r - |value|`
+    pub value: Num<E>,
+}
+
+impl<E: Engine> AllocatedSignedData<E> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &SignedData,
+    ) -> Result<Self, SynthesisError> {
+        let mut alloc_bytes = |bytes: &[u8]| {
+            bytes
+                .iter()
+                .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let template_id = alloc_bytes(&witness.template_id)?.try_into().unwrap();
+        let timestamp = alloc_bytes(&witness.timestamp.to_be_bytes())?
+            .try_into()
+            .unwrap();
+        let data = alloc_bytes(&witness.data)?.try_into().unwrap();
+        let mut signature = witness.signature;
+        if signature[64] >= 27 {
+            signature[64] -= 27;
+        }
+        let signature = Signature::from_bytes_witness(cs, &signature)?;
+        Ok(Self {
+            template_id,
+            timestamp,
+            data,
+            signature,
+        })
+    }
+
+    /// Same as [`SignedData::message_hash`].
+    pub fn message_hash<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<[Byte<E>; 32], SynthesisError> {
+        let mut bytes = self.template_id.to_vec();
+        bytes.extend([Byte::zero(); 28]);
+        bytes.extend(self.timestamp);
+        bytes.extend(self.data);
+        let hash = keccak256::digest(cs, &bytes)?;
+        circuit_eth_signed_message_hash(cs, &hash)
+    }
+
+    /// Same as [`SignedData::check`]: check the data is signed by `airnode` and its value fits
+    /// `int224`. Return the verified data, which is only meaningful if the check passes.
+    pub fn verify<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        airnode: &Address<E>,
+    ) -> Result<(Boolean, VerifiedData<E>), SynthesisError> {
+        let message_hash = self.message_hash(cs)?;
+        let message_hash = UInt256::from_be_bytes_fixed(cs, &message_hash)?;
+        let (successful, (x, y)) = self.signature.ecrecover(cs, &message_hash)?;
+        let (x, y) = (
+            x.into_be_bytes(cs)?.try_into().unwrap(),
+            y.into_be_bytes(cs)?.try_into().unwrap(),
+        );
+        let address = Address::from_pubkey(cs, &x, &y)?;
+        let is_signed = airnode.equals(cs, &address)?;
+
+        // data = sign extension || int224, where the sign extension is 0x00 or 0xff
+        let low = num_from_be_bytes(cs, &self.data[BEACON_VALUE_SIGN_EXTENSION_BS..])?;
+        let is_negative = self.data[BEACON_VALUE_SIGN_EXTENSION_BS]
+            .inner
+            .into_bits_le(cs, Some(8))?[7];
+        let extension =
+            Num::from_boolean_is(is_negative).mul(cs, &Num::Constant(u64_to_fe(0xff)))?;
+        let mut is_valid = vec![is_signed, successful];
+        for byte in self.data[..BEACON_VALUE_SIGN_EXTENSION_BS].iter() {
+            is_valid.push(byte.inner.equals(cs, &extension)?);
+        }
+        let two_pow_224 = fr_from_biguint::<E>(&(BigUint::from(1u8) << BEACON_VALUE_BITS))?;
+        let offset = Num::from_boolean_is(is_negative).mul(cs, &Num::Constant(two_pow_224))?;
+        let data = VerifiedData {
+            timestamp: num_from_be_bytes(cs, &self.timestamp)?,
+            value: low.sub(cs, &offset)?,
+        };
+        Ok((smart_and(cs, &is_valid)?, data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::franklin_crypto::bellman::{
+        pairing::bn256::Bn256, SynthesisError,
+    };
+    use num_bigint::BigInt;
+
+    use super::{AllocatedSignedData, SignedData};
+    use crate::{
+        chainlink::fr_from_bigint,
+        gadgets::ethereum::Address,
+        utils::{signer::RedstoneSigner, testing::create_test_constraint_system},
+    };
+
+    fn encode(value: i128) -> [u8; 32] {
+        let mut data = [if value < 0 { 0xff } else { 0 }; 32];
+        data[16..].copy_from_slice(&value.to_be_bytes());
+        data
+    }
+
+    #[test]
+    fn test_signed_data() -> Result<(), SynthesisError> {
+        let airnode = RedstoneSigner::new(0x33);
+        let sign = |value: i128| {
+            let mut signed_data = SignedData {
+                template_id: [5u8; 32],
+                timestamp: 1_700_000_000,
+                data: encode(value),
+                signature: [0u8; 65],
+            };
+            signed_data.signature = airnode.sign_hash(&signed_data.message_hash());
+            signed_data
+        };
+        let positive = sign(2_530_120_000_000_000_000_000);
+        let negative = sign(-42);
+        let mut overflowed = sign(0);
+        overflowed.data[3] = 1;
+        overflowed.signature = airnode.sign_hash(&overflowed.message_hash());
+        assert!(positive.check(&airnode.address()).is_ok());
+        assert!(positive
+            .check(&RedstoneSigner::new(0x34).address())
+            .is_err());
+        assert!(overflowed.check(&airnode.address()).is_err());
+        let mut tampered = positive.clone();
+        tampered.timestamp += 1;
+        assert!(tampered.check(&airnode.address()).is_err());
+
+        for (signed_data, expected) in [
+            (positive, true),
+            (negative, true),
+            (overflowed, false),
+            (tampered, false),
+        ] {
+            let cs = &mut create_test_constraint_system()?;
+            let address = Address::from_address_witness(cs, &airnode.address())?;
+            let allocated = AllocatedSignedData::<Bn256>::from_witness(cs, &signed_data)?;
+            let (is_valid, verified) = allocated.verify(cs, &address)?;
+            assert_eq!(is_valid.get_value(), Some(expected));
+            if expected {
+                let value = BigInt::from_signed_bytes_be(&signed_data.data);
+                assert_eq!(
+                    verified.value.get_value(),
+                    Some(fr_from_bigint::<Bn256>(&value).unwrap())
+                );
+            }
+            assert!(cs.is_satisfied());
+        }
+        Ok(())
+    }
+}
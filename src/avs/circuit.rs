@@ -0,0 +1,161 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{ff::Field, Engine};
+use advanced_circuit_component::franklin_crypto::{
+    bellman::{
+        plonk::better_better_cs::{
+            cs::{Circuit, ConstraintSystem, Gate, GateInternal},
+            gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
+        },
+        SynthesisError,
+    },
+    plonk::circuit::{allocated_num::Num, boolean::Boolean, custom_rescue_gate::Rescue5CustomGate},
+};
+use num_bigint::BigUint;
+
+use crate::{
+    gadgets::{
+        poseidon::{circuit_poseidon_hash, poseidon_hash},
+        rescue::circuit_rescue_hash,
+    },
+    utils::{self, fr_from_biguint},
+};
+
+use super::{
+    registry::{circuit_registry_root, registry_root, AllocatedOperator, Operator},
+    report::{AllocatedAttestation, Attestation},
+};
+
+/// Circuit checking price reports are attested by operators of an AVS holding more than 2/3 of the
+/// stake of the committed registry, up to one pairing check of the BLS signature per report.
+///
+/// Public input is the commitment `poseidon(registry root, (feedId[..16], feedId[16..], price,
+/// timestamp, lhs, rhs, apk_g2 in 16-byte chunks) of each report)`, where the registry root is
+/// [`registry_root`] and `(lhs, rhs)` is [`Attestation::pairing_inputs`]. Pairings are not
+/// computed in circuit, so reports are signed only if consumers check `e(lhs, g2) == e(rhs,
+/// apk_g2)` of each committed report, see
+/// [`verify_pairing_inputs`](crate::gadgets::bn254::verify_pairing_inputs).
+#[derive(Debug, Clone)]
+pub struct AvsPriceOracle<E: Engine> {
+    pub operators: Vec<Operator>,
+    pub attestations: Vec<Attestation>,
+    pub commitment: E::Fr,
+}
+
+impl<E: Engine> AvsPriceOracle<E> {
+    pub fn new(
+        operators: Vec<Operator>,
+        attestations: Vec<Attestation>,
+    ) -> Result<Self, anyhow::Error> {
+        let mut input = vec![registry_root::<E>(&operators)?];
+        for attestation in attestations.iter() {
+            attestation.check(&operators)?;
+            let report = &attestation.report;
+            input.push(fr_from_biguint::<E>(&BigUint::from_bytes_be(
+                &report.feed_id[..16],
+            ))?);
+            input.push(fr_from_biguint::<E>(&BigUint::from_bytes_be(
+                &report.feed_id[16..],
+            ))?);
+            input.push(fr_from_biguint::<E>(&BigUint::from(report.price))?);
+            input.push(fr_from_biguint::<E>(&BigUint::from(report.timestamp))?);
+            let (lhs, rhs) = attestation.pairing_inputs()?;
+            let pairing_check = [&lhs[..], &rhs[..], &attestation.apk_g2[..]].concat();
+            for chunk in pairing_check.chunks_exact(16) {
+                input.push(fr_from_biguint::<E>(&BigUint::from_bytes_be(chunk))?);
+            }
+        }
+        Ok(Self {
+            operators,
+            attestations,
+            commitment: poseidon_hash::<E>(&input),
+        })
+    }
+}
+
+impl<E: Engine> Circuit<E> for AvsPriceOracle<E> {
+    type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
+
+    fn synthesize<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<(), SynthesisError> {
+        utils::add_bitwise_logic_and_range_table(cs)?;
+        let temp_variable = Num::alloc(cs, Some(E::Fr::one()))?;
+        circuit_rescue_hash(cs, &[temp_variable])?; // Just to standardize the proof format
+
+        let operators = self
+            .operators
+            .iter()
+            .map(|operator| AllocatedOperator::from_witness(cs, operator))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut input = vec![circuit_registry_root(cs, &operators)?];
+        let mut is_valid = Boolean::constant(true);
+        for attestation in self.attestations.iter() {
+            let attestation = AllocatedAttestation::from_witness(cs, attestation)?;
+            let (is_signed_by_quorum, _) = attestation.check_signers(cs, &operators)?;
+            is_valid = Boolean::and(cs, &is_valid, &is_signed_by_quorum)?;
+            let is_reduced = attestation.check_pairing_inputs(cs)?;
+            is_valid = Boolean::and(cs, &is_valid, &is_reduced)?;
+            input.extend(attestation.report(cs)?);
+            input.extend(attestation.pairing_check(cs)?);
+        }
+        Boolean::enforce_equal(cs, &is_valid, &Boolean::constant(true))?;
+
+        let commitment = circuit_poseidon_hash(cs, &input)?;
+        let expected_commitment = Num::alloc(cs, Some(self.commitment))?;
+        expected_commitment.enforce_equal(cs, &commitment)?;
+        expected_commitment.get_variable().inputize(cs)?;
+        Ok(())
+    }
+
+    fn declare_used_gates() -> Result<Vec<Box<dyn GateInternal<E>>>, SynthesisError> {
+        Ok(vec![
+            Self::MainGate::default().into_internal(),
+            Rescue5CustomGate.into_internal(), // Just to standardize the proof format
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::bellman::{pairing::ff::Field, plonk::better_better_cs::cs::Circuit},
+        testing::{create_test_artifacts_with_optimized_gate, Bn256},
+    };
+
+    use super::AvsPriceOracle;
+    use crate::{
+        gadgets::bn254::{g1_from_bytes, g2_from_bytes, verify_pairing_inputs},
+        utils::{signer::BlsSigner, testing::avs_attestation},
+    };
+
+    #[test]
+    fn test_avs_price_oracle() -> anyhow::Result<()> {
+        let signers = BlsSigner::generate(4);
+        let stakes = [10, 20, 30, 40];
+        let (operators, attestation) =
+            avs_attestation(&signers, &stakes, &[true, false, true, true]);
+        let circuit = AvsPriceOracle::<Bn256>::new(operators.clone(), vec![attestation])?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        println!("Roughly {} gates", cs.n());
+
+        // Committed pairing inputs pass the pairing check left to consumers
+        let attestation = &circuit.attestations[0];
+        let (lhs, rhs) = attestation.pairing_inputs()?;
+        assert!(verify_pairing_inputs(
+            &g1_from_bytes(&lhs)?,
+            &g1_from_bytes(&rhs)?,
+            &g2_from_bytes(&attestation.apk_g2)?
+        ));
+
+        let mut tampered = circuit;
+        tampered.commitment.add_assign(&Field::one());
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        tampered.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+
+        // The signature must match the bitmap
+        let (_, mut forged) = avs_attestation(&signers, &stakes, &[true, false, true, true]);
+        forged.signer_bitmap = vec![true, true, true, true];
+        assert!(AvsPriceOracle::<Bn256>::new(operators, vec![forged]).is_err());
+        Ok(())
+    }
+}
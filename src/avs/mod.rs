@@ -0,0 +1,7 @@
+mod circuit;
+mod registry;
+mod report;
+
+pub use circuit::*;
+pub use registry::*;
+pub use report::*;
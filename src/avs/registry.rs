@@ -0,0 +1,119 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::allocated_num::Num,
+    },
+};
+use num_bigint::BigUint;
+
+use crate::{
+    gadgets::{
+        bn254::{g1_from_bytes, G1_POINT_BS},
+        poseidon::{circuit_poseidon_hash, poseidon_hash},
+    },
+    utils::{fr_from_biguint, num_from_be_bytes},
+};
+
+/// Operator of an AVS registry, whose BLS public key in G1 is `x || y` in big-endian.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Operator {
+    pub pubkey: [u8; G1_POINT_BS],
+    pub stake: u64,
+}
+
+/// Root of the operator registry, i.e. `poseidon(x[..16], x[16..], y[..16], y[16..], stake of
+/// each operator)`. Public keys must be valid G1 points.
+pub fn registry_root<E: Engine>(operators: &[Operator]) -> anyhow::Result<E::Fr> {
+    let mut input = vec![];
+    for operator in operators {
+        g1_from_bytes(&operator.pubkey)?;
+        for chunk in operator.pubkey.chunks_exact(16) {
+            input.push(fr_from_biguint::<E>(&BigUint::from_bytes_be(chunk))?);
+        }
+        input.push(fr_from_biguint::<E>(&BigUint::from(operator.stake))?);
+    }
+    Ok(poseidon_hash::<E>(&input))
+}
+
+/// Circuit representation of [`Operator`].
+#[derive(Debug, Clone)]
+pub struct AllocatedOperator<E: Engine> {
+    pub pubkey: [Byte<E>; G1_POINT_BS],
+    pub stake: Num<E>,
+}
+
+impl<E: Engine> AllocatedOperator<E> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &Operator,
+    ) -> Result<Self, SynthesisError> {
+        let mut pubkey = [Byte::zero(); G1_POINT_BS];
+        for (byte, b) in pubkey.iter_mut().zip(witness.pubkey.iter()) {
+            *byte = Byte::from_u8_witness(cs, Some(*b))?;
+        }
+        let stake = witness
+            .stake
+            .to_be_bytes()
+            .iter()
+            .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            pubkey,
+            stake: num_from_be_bytes(cs, &stake)?,
+        })
+    }
+}
+
+/// Same as [`registry_root`], except that public keys are checked by
+/// [`circuit_check_aggregate_g1`](crate::gadgets::bn254::circuit_check_aggregate_g1).
+pub fn circuit_registry_root<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    operators: &[AllocatedOperator<E>],
+) -> Result<Num<E>, SynthesisError> {
+    let mut input = vec![];
+    for operator in operators {
+        for chunk in operator.pubkey.chunks_exact(16) {
+            input.push(num_from_be_bytes(cs, chunk)?);
+        }
+        input.push(operator.stake);
+    }
+    circuit_poseidon_hash(cs, &input)
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::franklin_crypto::bellman::{
+        pairing::bn256::Bn256, SynthesisError,
+    };
+
+    use super::{circuit_registry_root, registry_root, AllocatedOperator, Operator};
+    use crate::utils::{signer::BlsSigner, testing::create_test_constraint_system};
+
+    #[test]
+    fn test_registry_root() -> Result<(), SynthesisError> {
+        let operators = BlsSigner::generate(3)
+            .iter()
+            .zip([100, 200, 300])
+            .map(|(signer, stake)| Operator {
+                pubkey: signer.pubkey(),
+                stake,
+            })
+            .collect::<Vec<_>>();
+        let expected = registry_root::<Bn256>(&operators).unwrap();
+        let mut invalid = operators.clone();
+        invalid[1].pubkey[63] ^= 1;
+        assert!(registry_root::<Bn256>(&invalid).is_err());
+
+        let cs = &mut create_test_constraint_system()?;
+        let operators = operators
+            .iter()
+            .map(|operator| AllocatedOperator::from_witness(cs, operator))
+            .collect::<Result<Vec<_>, _>>()?;
+        let root = circuit_registry_root(cs, &operators)?;
+        assert_eq!(root.get_value(), Some(expected));
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+}
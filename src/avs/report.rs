@@ -0,0 +1,336 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{
+            allocated_num::Num,
+            boolean::{AllocatedBit, Boolean},
+        },
+    },
+    glue::prepacked_long_comparison,
+    utils::u64_to_fe,
+    vm::partitioner::smart_and,
+};
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    gadgets::{
+        bn254::{
+            aggregate_g1, circuit_check_aggregate_g1, circuit_check_pairing_inputs, g1_from_bytes,
+            g1_to_bytes, g2_from_bytes, pairing_inputs, verify_aggregate_signature, G1_POINT_BS,
+            G2_POINT_BS,
+        },
+        keccak256,
+    },
+    utils::{new_synthesis_error, num_from_be_bytes},
+};
+
+use super::registry::{AllocatedOperator, Operator};
+
+// Number of bytes of `abi.encode(feedId, price, timestamp)`
+pub const REPORT_BS: usize = 3 * 32;
+// Enough bits to hold 3 times the total stake of up to 2^14 operators
+const STAKE_BITS: usize = 80;
+
+/// Price report attested by operators of an AVS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PriceReport {
+    pub feed_id: [u8; 32],
+    pub price: u128,
+    pub timestamp: u64,
+}
+
+impl PriceReport {
+    /// `abi.encode(feedId, uint256 price, uint256 timestamp)`.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = self.feed_id.to_vec();
+        bytes.extend([0u8; 16]);
+        bytes.extend(self.price.to_be_bytes());
+        bytes.extend([0u8; 24]);
+        bytes.extend(self.timestamp.to_be_bytes());
+        bytes
+    }
+
+    /// Hash signed by operators, i.e. `keccak256(abi.encode(report))`.
+    pub fn hash(&self) -> [u8; 32] {
+        Keccak256::digest(self.encode()).into()
+    }
+}
+
+/// Report with the BLS signature aggregated from operators flagged in `signer_bitmap`, whose
+/// public keys sum to `apk` in G1 and `apk_g2` in G2.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attestation {
+    pub report: PriceReport,
+    pub signer_bitmap: Vec<bool>,
+    pub apk: [u8; G1_POINT_BS],
+    pub apk_g2: [u8; G2_POINT_BS],
+    pub signature: [u8; G1_POINT_BS],
+}
+
+impl Attestation {
+    /// Check the report is signed by operators in the bitmap, which hold more than 2/3 of the
+    /// stake of `operators`, and return the signed stake.
+    pub fn check(&self, operators: &[Operator]) -> anyhow::Result<u128> {
+        if self.signer_bitmap.len() != operators.len() {
+            anyhow::bail!(
+                "bitmap of {} bits for {} operators",
+                self.signer_bitmap.len(),
+                operators.len()
+            )
+        }
+        let mut signers = vec![];
+        let mut signed_stake = 0u128;
+        for (operator, _) in operators
+            .iter()
+            .zip(self.signer_bitmap.iter())
+            .filter(|(_, is_signer)| **is_signer)
+        {
+            signers.push(g1_from_bytes(&operator.pubkey)?);
+            signed_stake += operator.stake as u128;
+        }
+        if g1_to_bytes(&aggregate_g1(&signers)) != self.apk {
+            anyhow::bail!("apk 0x{} is not the sum of signers", hex::encode(self.apk))
+        }
+        let apk = g1_from_bytes(&self.apk)?;
+        let apk_g2 = g2_from_bytes(&self.apk_g2)?;
+        let signature = g1_from_bytes(&self.signature)?;
+        if !verify_aggregate_signature(&self.report.hash(), &apk, &apk_g2, &signature) {
+            anyhow::bail!(
+                "report 0x{} is not signed by apk 0x{}",
+                hex::encode(self.report.hash()),
+                hex::encode(self.apk)
+            )
+        }
+        let total_stake: u128 = operators.iter().map(|o| o.stake as u128).sum();
+        if signed_stake * 3 <= total_stake * 2 {
+            anyhow::bail!(
+                "stake {} of {} is not more than 2/3",
+                signed_stake,
+                total_stake
+            )
+        }
+        Ok(signed_stake)
+    }
+
+    /// [`pairing_inputs`] `(lhs, rhs)` of the signature, which consumers check against `apk_g2`.
+    pub fn pairing_inputs(&self) -> anyhow::Result<([u8; G1_POINT_BS], [u8; G1_POINT_BS])> {
+        let (lhs, rhs) = pairing_inputs(
+            &self.report.hash(),
+            &g1_from_bytes(&self.apk)?,
+            &g2_from_bytes(&self.apk_g2)?,
+            &g1_from_bytes(&self.signature)?,
+        );
+        Ok((g1_to_bytes(&lhs), g1_to_bytes(&rhs)))
+    }
+}
+
+fn alloc_bytes<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    bytes: &[u8],
+) -> Result<Vec<Byte<E>>, SynthesisError> {
+    bytes
+        .iter()
+        .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+        .collect()
+}
+
+/// Circuit representation of [`Attestation`] with its [`Attestation::pairing_inputs`] `lhs` and
+/// `rhs`.
+#[derive(Debug, Clone)]
+pub struct AllocatedAttestation<E: Engine> {
+    pub feed_id: [Byte<E>; 32],
+    pub price: [Byte<E>; 16],
+    pub timestamp: [Byte<E>; 8],
+    pub signer_bitmap: Vec<Boolean>,
+    pub apk: [Byte<E>; G1_POINT_BS],
+    pub apk_g2: [Byte<E>; G2_POINT_BS],
+    pub signature: [Byte<E>; G1_POINT_BS],
+    pub lhs: [Byte<E>; G1_POINT_BS],
+    pub rhs: [Byte<E>; G1_POINT_BS],
+}
+
+impl<E: Engine> AllocatedAttestation<E> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &Attestation,
+    ) -> Result<Self, SynthesisError> {
+        let report = &witness.report;
+        let signer_bitmap = witness
+            .signer_bitmap
+            .iter()
+            .map(|b| Ok(Boolean::Is(AllocatedBit::alloc(cs, Some(*b))?)))
+            .collect::<Result<Vec<_>, SynthesisError>>()?;
+        let (lhs, rhs) = witness
+            .pairing_inputs()
+            .map_err(|e| new_synthesis_error(e.to_string()))?;
+        Ok(Self {
+            feed_id: alloc_bytes(cs, &report.feed_id)?.try_into().unwrap(),
+            price: alloc_bytes(cs, &report.price.to_be_bytes())?
+                .try_into()
+                .unwrap(),
+            timestamp: alloc_bytes(cs, &report.timestamp.to_be_bytes())?
+                .try_into()
+                .unwrap(),
+            signer_bitmap,
+            apk: alloc_bytes(cs, &witness.apk)?.try_into().unwrap(),
+            apk_g2: alloc_bytes(cs, &witness.apk_g2)?.try_into().unwrap(),
+            signature: alloc_bytes(cs, &witness.signature)?.try_into().unwrap(),
+            lhs: alloc_bytes(cs, &lhs)?.try_into().unwrap(),
+            rhs: alloc_bytes(cs, &rhs)?.try_into().unwrap(),
+        })
+    }
+
+    /// Same as [`PriceReport::hash`].
+    pub fn report_hash<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<[Byte<E>; 32], SynthesisError> {
+        let mut bytes = self.feed_id.to_vec();
+        bytes.extend([Byte::zero(); 16]);
+        bytes.extend(self.price);
+        bytes.extend([Byte::zero(); 24]);
+        bytes.extend(self.timestamp);
+        assert_eq!(bytes.len(), REPORT_BS);
+        keccak256::digest(cs, &bytes)
+    }
+
+    /// Check `apk` is the sum of public keys of operators in the bitmap, which hold more than 2/3
+    /// of the stake. Return the signed stake, which is only meaningful if the check passes.
+    pub fn check_signers<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        operators: &[AllocatedOperator<E>],
+    ) -> Result<(Boolean, Num<E>), SynthesisError> {
+        if self.signer_bitmap.len() != operators.len() {
+            return Err(new_synthesis_error(format!(
+                "bitmap of {} bits for {} operators",
+                self.signer_bitmap.len(),
+                operators.len()
+            )));
+        }
+        let pubkeys = operators.iter().map(|o| o.pubkey).collect::<Vec<_>>();
+        let is_apk_valid =
+            circuit_check_aggregate_g1(cs, &pubkeys, &self.signer_bitmap, &self.apk)?;
+
+        let mut total_stake = Num::zero();
+        let mut signed_stake = Num::zero();
+        for (operator, is_signer) in operators.iter().zip(self.signer_bitmap.iter()) {
+            total_stake = total_stake.add(cs, &operator.stake)?;
+            let stake = Num::conditionally_select(cs, is_signer, &operator.stake, &Num::zero())?;
+            signed_stake = signed_stake.add(cs, &stake)?;
+        }
+        // signed stake * 3 > total stake * 2
+        let tripled = signed_stake.mul(cs, &Num::Constant(u64_to_fe(3)))?;
+        let doubled = total_stake.mul(cs, &Num::Constant(u64_to_fe(2)))?;
+        let (_, is_quorum) = prepacked_long_comparison(cs, &[tripled], &[doubled], &[STAKE_BITS])?;
+        Ok((smart_and(cs, &[is_apk_valid, is_quorum])?, signed_stake))
+    }
+
+    /// Check `lhs` and `rhs` are pairing inputs of the signature of the report, see
+    /// [`circuit_check_pairing_inputs`]. The signature is not verified until consumers check the
+    /// pairing of [`Self::pairing_check`].
+    pub fn check_pairing_inputs<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<Boolean, SynthesisError> {
+        let hash = self.report_hash(cs)?;
+        circuit_check_pairing_inputs(
+            cs,
+            &hash,
+            &self.apk,
+            &self.apk_g2,
+            &self.signature,
+            &self.lhs,
+            &self.rhs,
+        )
+    }
+
+    /// `lhs`, `rhs` and `apk_g2` in 16-byte chunks, whose pairing check is left to consumers.
+    pub fn pairing_check<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<Vec<Num<E>>, SynthesisError> {
+        let bytes = [&self.lhs[..], &self.rhs[..], &self.apk_g2[..]].concat();
+        bytes
+            .chunks_exact(16)
+            .map(|chunk| num_from_be_bytes(cs, chunk))
+            .collect()
+    }
+
+    /// Feed id in two 16-byte halves, price and timestamp.
+    pub fn report<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<[Num<E>; 4], SynthesisError> {
+        Ok([
+            num_from_be_bytes(cs, &self.feed_id[..16])?,
+            num_from_be_bytes(cs, &self.feed_id[16..])?,
+            num_from_be_bytes(cs, &self.price)?,
+            num_from_be_bytes(cs, &self.timestamp)?,
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte,
+        franklin_crypto::bellman::{pairing::bn256::Bn256, SynthesisError},
+    };
+
+    use super::AllocatedAttestation;
+    use crate::{
+        avs::AllocatedOperator,
+        utils::{
+            fr_from_biguint,
+            signer::BlsSigner,
+            testing::{avs_attestation, create_test_constraint_system},
+        },
+    };
+
+    #[test]
+    fn test_attestation() -> Result<(), SynthesisError> {
+        let signers = BlsSigner::generate(4);
+        let (operators, attestation) =
+            avs_attestation(&signers, &[10, 20, 30, 40], &[false, true, true, true]);
+        assert_eq!(attestation.check(&operators).unwrap(), 90);
+
+        let mut tampered = attestation.clone();
+        tampered.report.price += 1;
+        assert!(tampered.check(&operators).is_err());
+        // Signers of 2/3 of the stake are not enough
+        let (_, minority) =
+            avs_attestation(&signers, &[10, 20, 30, 40], &[true, true, true, false]);
+        assert!(minority.check(&operators).is_err());
+
+        for (attestation, expected) in [(attestation, true), (minority, false)] {
+            let cs = &mut create_test_constraint_system()?;
+            let operators = operators
+                .iter()
+                .map(|operator| AllocatedOperator::from_witness(cs, operator))
+                .collect::<Result<Vec<_>, _>>()?;
+            let allocated = AllocatedAttestation::<Bn256>::from_witness(cs, &attestation)?;
+            let (is_valid, signed_stake) = allocated.check_signers(cs, &operators)?;
+            // Pairing inputs are independent of the stake
+            assert_eq!(allocated.check_pairing_inputs(cs)?.get_value(), Some(true));
+            let mut swapped = allocated.clone();
+            std::mem::swap(&mut swapped.lhs, &mut swapped.rhs);
+            assert_eq!(swapped.check_pairing_inputs(cs)?.get_value(), Some(false));
+            assert_eq!(is_valid.get_value(), Some(expected));
+            let stake = if expected { 90u64 } else { 60 };
+            assert_eq!(
+                signed_stake.get_value(),
+                Some(fr_from_biguint::<Bn256>(&stake.into())?)
+            );
+            let hash = allocated.report_hash(cs)?;
+            assert_eq!(
+                Byte::get_byte_value_multiple(&hash).unwrap(),
+                attestation.report.hash()
+            );
+            assert!(cs.is_satisfied());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,253 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{ff::Field, Engine};
+use advanced_circuit_component::{
+    franklin_crypto::{
+        bellman::{
+            plonk::better_better_cs::{
+                cs::{Circuit, ConstraintSystem, Gate, GateInternal},
+                gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
+            },
+            SynthesisError,
+        },
+        plonk::circuit::{
+            allocated_num::Num, boolean::Boolean, custom_rescue_gate::Rescue5CustomGate,
+        },
+    },
+    glue::prepacked_long_comparison,
+    utils::u64_to_fe,
+    vm::partitioner::smart_and,
+};
+use num_bigint::BigUint;
+
+use crate::{
+    gadgets::{
+        poseidon::{circuit_poseidon_hash, poseidon_hash},
+        rescue::circuit_rescue_hash,
+    },
+    utils::{self, fr_from_biguint, num_from_be_bytes},
+};
+
+use super::{
+    circuit_validator_set_hash, validator_set_hash, AllocatedOracleProof, AllocatedOracleResult,
+    AllocatedValidator, AllocatedVote, OracleProof, OracleResult, Validator, Vote,
+};
+
+// Enough bits to hold 3 times the total voting power of up to 2^14 validators
+const VOTING_POWER_BITS: usize = 80;
+
+/// Circuit verifying an oracle result of BandChain, which is proven to be included in a block
+/// precommitted by more than 2/3 of the voting power of the committed validator set.
+///
+/// `votes[i]` is the precommit of `validators[i]`, or `None` if it is absent. Public input is the
+/// commitment `poseidon(validator set hash, height, oracle script id, request id, resolve time,
+/// multiplier, (symbol, rate) of each symbol)`, where the validator set hash is
+/// [`validator_set_hash`] and symbols are committed as feed ids.
+#[derive(Debug, Clone)]
+pub struct BandPriceOracle<E: Engine> {
+    pub result: OracleResult,
+    pub proof: OracleProof,
+    pub validators: Vec<Validator>,
+    pub votes: Vec<Option<Vote>>,
+    pub commitment: E::Fr,
+}
+
+impl<E: Engine> BandPriceOracle<E> {
+    pub fn new(
+        result: OracleResult,
+        proof: OracleProof,
+        validators: Vec<Validator>,
+        votes: Vec<Option<Vote>>,
+    ) -> Result<Self, anyhow::Error> {
+        let (_, multiplier, _) = result.check()?;
+        if validators.len() != votes.len() {
+            anyhow::bail!("{} votes of {} validators", votes.len(), validators.len())
+        }
+        let block_hash = proof.block_hash(&result);
+        let mut height = None;
+        let mut signed_power = 0u128;
+        for (validator, vote) in validators.iter().zip(votes.iter()) {
+            let vote = match vote {
+                Some(vote) => vote,
+                None => continue,
+            };
+            let vote_height = vote.check(validator, &block_hash)?;
+            if *height.get_or_insert(vote_height) != vote_height {
+                anyhow::bail!("precommits at heights {:?} and {}", height, vote_height)
+            }
+            signed_power += validator.voting_power as u128;
+        }
+        let total_power: u128 = validators.iter().map(|v| v.voting_power as u128).sum();
+        if signed_power * 3 <= total_power * 2 {
+            anyhow::bail!(
+                "voting power {} of {} is not more than 2/3",
+                signed_power,
+                total_power
+            )
+        }
+        let height = height.expect("quorum has at least one vote");
+
+        let mut input = vec![
+            validator_set_hash::<E>(&validators)?,
+            fr_from_biguint::<E>(&BigUint::from(height))?,
+            fr_from_biguint::<E>(&BigUint::from(result.oracle_script_id))?,
+            fr_from_biguint::<E>(&BigUint::from(result.request_id))?,
+            fr_from_biguint::<E>(&BigUint::from(result.resolve_time))?,
+            fr_from_biguint::<E>(&BigUint::from(multiplier))?,
+        ];
+        input.extend(result.prices::<E>()?);
+        Ok(Self {
+            result,
+            proof,
+            validators,
+            votes,
+            commitment: poseidon_hash::<E>(&input),
+        })
+    }
+}
+
+impl<E: Engine> Circuit<E> for BandPriceOracle<E> {
+    type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
+
+    fn synthesize<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<(), SynthesisError> {
+        utils::add_bitwise_logic_and_range_table(cs)?;
+        let temp_variable = Num::alloc(cs, Some(E::Fr::one()))?;
+        circuit_rescue_hash(cs, &[temp_variable])?; // Just to standardize the proof format
+
+        let validators = self
+            .validators
+            .iter()
+            .map(|validator| AllocatedValidator::from_witness(cs, validator))
+            .collect::<Result<Vec<_>, _>>()?;
+        let validator_set_hash = circuit_validator_set_hash(cs, &validators)?;
+
+        let result = AllocatedOracleResult::from_witness(cs, &self.result)?;
+        let proof = AllocatedOracleProof::from_witness(cs, &self.proof)?;
+        let block_hash = proof.block_hash(cs, &result)?;
+
+        let mut is_valid = vec![];
+        let mut height: Option<Num<E>> = None;
+        let mut total_power = Num::zero();
+        let mut signed_power = Num::zero();
+        for (validator, vote) in validators.iter().zip(self.votes.iter()) {
+            total_power = total_power.add(cs, &validator.voting_power)?;
+            let vote = match vote {
+                Some(vote) => AllocatedVote::from_witness(cs, vote)?,
+                None => continue,
+            };
+            let (is_vote_valid, vote_height) = vote.verify(cs, validator, &block_hash)?;
+            is_valid.push(is_vote_valid);
+            match height {
+                Some(height) => height.enforce_equal(cs, &vote_height)?,
+                None => height = Some(vote_height),
+            }
+            signed_power = signed_power.add(cs, &validator.voting_power)?;
+        }
+        let height = height.ok_or_else(|| utils::new_synthesis_error("no precommit is present"))?;
+
+        // signed power * 3 > total power * 2
+        let signed_power = signed_power.mul(cs, &Num::Constant(u64_to_fe(3)))?;
+        let total_power = total_power.mul(cs, &Num::Constant(u64_to_fe(2)))?;
+        let (_, is_quorum) =
+            prepacked_long_comparison(cs, &[signed_power], &[total_power], &[VOTING_POWER_BITS])?;
+        is_valid.push(is_quorum);
+        let is_valid = smart_and(cs, &is_valid)?;
+        Boolean::enforce_equal(cs, &is_valid, &Boolean::constant(true))?;
+
+        let mut input = vec![
+            validator_set_hash,
+            height,
+            result.oracle_script_id,
+            num_from_be_bytes(cs, &result.request_id)?,
+            result.resolve_time,
+            result.multiplier,
+        ];
+        input.extend(result.prices());
+        let commitment = circuit_poseidon_hash(cs, &input)?;
+        let expected_commitment = Num::alloc(cs, Some(self.commitment))?;
+        expected_commitment.enforce_equal(cs, &commitment)?;
+        expected_commitment.get_variable().inputize(cs)?;
+        Ok(())
+    }
+
+    fn declare_used_gates() -> Result<Vec<Box<dyn GateInternal<E>>>, SynthesisError> {
+        Ok(vec![
+            Self::MainGate::default().into_internal(),
+            Rescue5CustomGate.into_internal(), // Just to standardize the proof format
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::bellman::{pairing::ff::Field, plonk::better_better_cs::cs::Circuit},
+        testing::{create_test_artifacts_with_optimized_gate, Bn256},
+    };
+
+    use super::BandPriceOracle;
+    use crate::{
+        band::{MerkleStep, OracleProof, Validator},
+        utils::testing::{band_oracle_result, band_vote, Ed25519Signer},
+    };
+
+    #[test]
+    fn test_band_price_oracle() -> anyhow::Result<()> {
+        let result = band_oracle_result(
+            20_000_000,
+            &["BTC", "ETH"],
+            &[43_000_000_000_000, 2_300_000_000_000],
+        );
+        let proof = OracleProof {
+            version: 19_999_999,
+            iavl_path: vec![],
+            store_path: vec![MerkleStep {
+                is_data_on_right: true,
+                sibling: [3u8; 32],
+            }],
+            header_path: vec![MerkleStep {
+                is_data_on_right: false,
+                sibling: [4u8; 32],
+            }],
+        };
+        let block_hash = proof.block_hash(&result);
+        let signers = Ed25519Signer::generate(3);
+        let validators = signers
+            .iter()
+            .zip([30, 30, 40])
+            .map(|(signer, voting_power)| Validator {
+                pubkey: signer.pubkey(),
+                voting_power,
+            })
+            .collect::<Vec<_>>();
+        let votes = vec![
+            None,
+            Some(band_vote(&signers[1], 20_000_001, &block_hash)),
+            Some(band_vote(&signers[2], 20_000_001, &block_hash)),
+        ];
+
+        let circuit = BandPriceOracle::<Bn256>::new(
+            result.clone(),
+            proof.clone(),
+            validators.clone(),
+            votes.clone(),
+        )?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        println!("Roughly {} gates", cs.n());
+
+        let mut tampered = circuit;
+        tampered.commitment.add_assign(&Field::one());
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        tampered.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+
+        // 2/3 of the voting power is not enough
+        let mut votes = votes;
+        votes[1] = None;
+        votes[0] = Some(band_vote(&signers[0], 20_000_001, &block_hash));
+        let mut validators = validators;
+        validators[2].voting_power = 30;
+        assert!(BandPriceOracle::<Bn256>::new(result, proof, validators, votes).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,9 @@
+mod circuit;
+mod proof;
+mod result;
+mod vote;
+
+pub use circuit::*;
+pub use proof::*;
+pub use result::*;
+pub use vote::*;
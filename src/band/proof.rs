@@ -0,0 +1,384 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{
+            allocated_num::Num,
+            boolean::{AllocatedBit, Boolean},
+        },
+    },
+};
+use sha2::{Digest, Sha256};
+
+use crate::gadgets::sha256;
+
+use super::result::{encode_varint_signed, AllocatedOracleResult, OracleResult};
+
+// Key of the oracle store in the multistore of BandChain
+pub const ORACLE_STORE_KEY: &[u8] = b"oracle";
+// Prefix of keys of oracle results in the oracle store
+pub const RESULT_KEY_PREFIX: u8 = 0xff;
+
+/// `sha256(0x00 || data)`, i.e. leaf hash of Tendermint simple Merkle trees.
+pub fn leaf_hash(data: &[u8]) -> [u8; 32] {
+    Sha256::new()
+        .chain_update([0u8])
+        .chain_update(data)
+        .finalize()
+        .into()
+}
+
+/// `sha256(0x01 || left || right)`, i.e. inner hash of Tendermint simple Merkle trees.
+pub fn inner_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    Sha256::new()
+        .chain_update([1u8])
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+        .into()
+}
+
+/// Step of a simple Merkle proof from a node to its parent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleStep {
+    pub is_data_on_right: bool,
+    pub sibling: [u8; 32],
+}
+
+impl MerkleStep {
+    pub fn parent_hash(&self, child: &[u8; 32]) -> [u8; 32] {
+        if self.is_data_on_right {
+            inner_hash(&self.sibling, child)
+        } else {
+            inner_hash(child, &self.sibling)
+        }
+    }
+}
+
+/// Step of an IAVL proof from a node to its parent, which hashes
+/// `height || size || version || 0x20 || left || 0x20 || right` with zigzag varints.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IavlMerklePath {
+    pub is_data_on_right: bool,
+    pub subtree_height: u8,
+    pub subtree_size: u64,
+    pub subtree_version: u64,
+    pub sibling: [u8; 32],
+}
+
+impl IavlMerklePath {
+    fn prefix(&self) -> Vec<u8> {
+        let mut prefix = encode_varint_signed(self.subtree_height as i64);
+        prefix.extend(encode_varint_signed(self.subtree_size as i64));
+        prefix.extend(encode_varint_signed(self.subtree_version as i64));
+        prefix
+    }
+
+    pub fn parent_hash(&self, child: &[u8; 32]) -> [u8; 32] {
+        let (left, right) = if self.is_data_on_right {
+            (&self.sibling, child)
+        } else {
+            (child, &self.sibling)
+        };
+        Sha256::new()
+            .chain_update(self.prefix())
+            .chain_update([32u8])
+            .chain_update(left)
+            .chain_update([32u8])
+            .chain_update(right)
+            .finalize()
+            .into()
+    }
+}
+
+/// Proof of an oracle result against the hash of a BandChain block, i.e. the IAVL proof in the
+/// oracle store, the simple Merkle proof of the oracle store in the multistore whose root is the
+/// app hash, and the simple Merkle proof of the app hash in the block header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OracleProof {
+    /// Version of the IAVL leaf, i.e. the height at which the result is written
+    pub version: u64,
+    pub iavl_path: Vec<IavlMerklePath>,
+    pub store_path: Vec<MerkleStep>,
+    pub header_path: Vec<MerkleStep>,
+}
+
+impl OracleProof {
+    /// IAVL leaf of the result, which is `height = 0 || size = 1 || version ||
+    /// len(key) || key || 0x20 || sha256(encoded result)`.
+    pub fn iavl_leaf(&self, result: &OracleResult) -> Vec<u8> {
+        let mut leaf = vec![0, 2];
+        leaf.extend(encode_varint_signed(self.version as i64));
+        leaf.push(9);
+        leaf.push(RESULT_KEY_PREFIX);
+        leaf.extend(result.request_id.to_be_bytes());
+        leaf.push(32);
+        leaf.extend(Sha256::digest(result.encode()));
+        leaf
+    }
+
+    /// Hash of the block proven to include `result`.
+    pub fn block_hash(&self, result: &OracleResult) -> [u8; 32] {
+        let leaf: [u8; 32] = Sha256::digest(self.iavl_leaf(result)).into();
+        let iavl_root = self
+            .iavl_path
+            .iter()
+            .fold(leaf, |node, step| step.parent_hash(&node));
+
+        let mut store_leaf = vec![ORACLE_STORE_KEY.len() as u8];
+        store_leaf.extend(ORACLE_STORE_KEY);
+        store_leaf.push(32);
+        store_leaf.extend(Sha256::digest(iavl_root));
+        let app_hash = self
+            .store_path
+            .iter()
+            .fold(leaf_hash(&store_leaf), |node, step| step.parent_hash(&node));
+
+        let mut header_leaf = vec![0x0a, 32];
+        header_leaf.extend(app_hash);
+        self.header_path
+            .iter()
+            .fold(leaf_hash(&header_leaf), |node, step| {
+                step.parent_hash(&node)
+            })
+    }
+}
+
+fn constant_bytes<E: Engine>(bytes: &[u8]) -> Vec<Byte<E>> {
+    bytes.iter().map(|b| Byte::constant(*b)).collect()
+}
+
+fn alloc_hash<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    hash: &[u8; 32],
+) -> Result<[Byte<E>; 32], SynthesisError> {
+    let mut bytes = [Byte::zero(); 32];
+    for (byte, b) in bytes.iter_mut().zip(hash) {
+        *byte = Byte::from_u8_witness(cs, Some(*b))?;
+    }
+    Ok(bytes)
+}
+
+/// `(left, right)` of `child` and `sibling`, where `child` is on the right if `is_data_on_right`.
+fn order<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    is_data_on_right: &Boolean,
+    child: &[Byte<E>; 32],
+    sibling: &[Byte<E>; 32],
+) -> Result<(Vec<Byte<E>>, Vec<Byte<E>>), SynthesisError> {
+    let (mut left, mut right) = (vec![], vec![]);
+    for (c, s) in child.iter().zip(sibling.iter()) {
+        let l = Num::conditionally_select(cs, is_data_on_right, &s.inner, &c.inner)?;
+        let r = Num::conditionally_select(cs, is_data_on_right, &c.inner, &s.inner)?;
+        left.push(Byte::from_num_unconstrained(cs, l));
+        right.push(Byte::from_num_unconstrained(cs, r));
+    }
+    Ok((left, right))
+}
+
+/// Circuit representation of [`MerkleStep`].
+#[derive(Debug, Clone)]
+pub struct AllocatedMerkleStep<E: Engine> {
+    pub is_data_on_right: Boolean,
+    pub sibling: [Byte<E>; 32],
+}
+
+impl<E: Engine> AllocatedMerkleStep<E> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &MerkleStep,
+    ) -> Result<Self, SynthesisError> {
+        Ok(Self {
+            is_data_on_right: Boolean::Is(AllocatedBit::alloc(cs, Some(witness.is_data_on_right))?),
+            sibling: alloc_hash(cs, &witness.sibling)?,
+        })
+    }
+
+    pub fn parent_hash<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        child: &[Byte<E>; 32],
+    ) -> Result<[Byte<E>; 32], SynthesisError> {
+        let (left, right) = order(cs, &self.is_data_on_right, child, &self.sibling)?;
+        let mut preimage = vec![Byte::constant(1)];
+        preimage.extend(left);
+        preimage.extend(right);
+        sha256::digest(cs, &preimage)
+    }
+}
+
+/// Circuit representation of [`IavlMerklePath`]. The varints of height, size and version are only
+/// hashed, so they are kept as bytes.
+#[derive(Debug, Clone)]
+pub struct AllocatedIavlMerklePath<E: Engine> {
+    pub is_data_on_right: Boolean,
+    pub prefix: Vec<Byte<E>>,
+    pub sibling: [Byte<E>; 32],
+}
+
+impl<E: Engine> AllocatedIavlMerklePath<E> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &IavlMerklePath,
+    ) -> Result<Self, SynthesisError> {
+        Ok(Self {
+            is_data_on_right: Boolean::Is(AllocatedBit::alloc(cs, Some(witness.is_data_on_right))?),
+            prefix: witness
+                .prefix()
+                .iter()
+                .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+                .collect::<Result<Vec<_>, _>>()?,
+            sibling: alloc_hash(cs, &witness.sibling)?,
+        })
+    }
+
+    pub fn parent_hash<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        child: &[Byte<E>; 32],
+    ) -> Result<[Byte<E>; 32], SynthesisError> {
+        let (left, right) = order(cs, &self.is_data_on_right, child, &self.sibling)?;
+        let mut preimage = self.prefix.clone();
+        preimage.push(Byte::constant(32));
+        preimage.extend(left);
+        preimage.push(Byte::constant(32));
+        preimage.extend(right);
+        sha256::digest(cs, &preimage)
+    }
+}
+
+/// Circuit representation of [`OracleProof`].
+#[derive(Debug, Clone)]
+pub struct AllocatedOracleProof<E: Engine> {
+    pub version: Vec<Byte<E>>,
+    pub iavl_path: Vec<AllocatedIavlMerklePath<E>>,
+    pub store_path: Vec<AllocatedMerkleStep<E>>,
+    pub header_path: Vec<AllocatedMerkleStep<E>>,
+}
+
+impl<E: Engine> AllocatedOracleProof<E> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &OracleProof,
+    ) -> Result<Self, SynthesisError> {
+        let version = encode_varint_signed(witness.version as i64)
+            .iter()
+            .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let iavl_path = witness
+            .iavl_path
+            .iter()
+            .map(|step| AllocatedIavlMerklePath::from_witness(cs, step))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut alloc_steps = |steps: &[MerkleStep]| {
+            steps
+                .iter()
+                .map(|step| AllocatedMerkleStep::from_witness(cs, step))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let store_path = alloc_steps(&witness.store_path)?;
+        let header_path = alloc_steps(&witness.header_path)?;
+        Ok(Self {
+            version,
+            iavl_path,
+            store_path,
+            header_path,
+        })
+    }
+
+    /// Same as [`OracleProof::block_hash`].
+    pub fn block_hash<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        result: &AllocatedOracleResult<E>,
+    ) -> Result<[Byte<E>; 32], SynthesisError> {
+        let mut leaf = constant_bytes(&[0, 2]);
+        leaf.extend(self.version.iter().copied());
+        leaf.extend(constant_bytes(&[9, RESULT_KEY_PREFIX]));
+        leaf.extend(result.request_id);
+        leaf.push(Byte::constant(32));
+        leaf.extend(sha256::digest(cs, &result.encoded)?);
+        let mut node = sha256::digest(cs, &leaf)?;
+        for step in self.iavl_path.iter() {
+            node = step.parent_hash(cs, &node)?;
+        }
+
+        let mut store_leaf = constant_bytes(&[0, ORACLE_STORE_KEY.len() as u8]);
+        store_leaf.extend(constant_bytes(ORACLE_STORE_KEY));
+        store_leaf.push(Byte::constant(32));
+        store_leaf.extend(sha256::digest(cs, &node)?);
+        let mut node = sha256::digest(cs, &store_leaf)?;
+        for step in self.store_path.iter() {
+            node = step.parent_hash(cs, &node)?;
+        }
+
+        let mut header_leaf = constant_bytes(&[0, 0x0a, 32]);
+        header_leaf.extend(node);
+        let mut node = sha256::digest(cs, &header_leaf)?;
+        for step in self.header_path.iter() {
+            node = step.parent_hash(cs, &node)?;
+        }
+        Ok(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte,
+        franklin_crypto::bellman::{pairing::bn256::Bn256, SynthesisError},
+    };
+
+    use super::{AllocatedOracleProof, IavlMerklePath, MerkleStep, OracleProof};
+    use crate::{
+        band::AllocatedOracleResult,
+        utils::testing::{band_oracle_result, create_test_constraint_system},
+    };
+
+    #[test]
+    fn test_oracle_proof() -> Result<(), SynthesisError> {
+        let result = band_oracle_result(20_000_000, &["BTC"], &[43_000_000_000_000]);
+        let proof = OracleProof {
+            version: 9_000_000,
+            iavl_path: vec![
+                IavlMerklePath {
+                    is_data_on_right: false,
+                    subtree_height: 1,
+                    subtree_size: 2,
+                    subtree_version: 9_000_000,
+                    sibling: [1u8; 32],
+                },
+                IavlMerklePath {
+                    is_data_on_right: true,
+                    subtree_height: 2,
+                    subtree_size: 4,
+                    subtree_version: 9_000_001,
+                    sibling: [2u8; 32],
+                },
+            ],
+            store_path: vec![MerkleStep {
+                is_data_on_right: true,
+                sibling: [3u8; 32],
+            }],
+            header_path: vec![MerkleStep {
+                is_data_on_right: false,
+                sibling: [4u8; 32],
+            }],
+        };
+        let block_hash = proof.block_hash(&result);
+        let mut tampered = proof.clone();
+        tampered.iavl_path[1].is_data_on_right = false;
+        assert_ne!(tampered.block_hash(&result), block_hash);
+
+        let cs = &mut create_test_constraint_system()?;
+        let allocated_result = AllocatedOracleResult::<Bn256>::from_witness(cs, &result)?;
+        let allocated_proof = AllocatedOracleProof::from_witness(cs, &proof)?;
+        let n = cs.n();
+        let hash = allocated_proof.block_hash(cs, &allocated_result)?;
+        println!("Roughly {} gates", cs.n() - n);
+        assert_eq!(Byte::get_byte_value_multiple(&hash).unwrap(), block_hash);
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+}
@@ -0,0 +1,380 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{ff::Field, Engine};
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{
+            allocated_num::Num, boolean::Boolean, linear_combination::LinearCombination,
+        },
+    },
+    utils::u64_to_fe,
+};
+use num_bigint::BigUint;
+
+use crate::utils::{fr_from_biguint, new_synthesis_error, num_from_be_bytes};
+
+// `RESOLVE_STATUS_SUCCESS` of `oracle.v1.ResolveStatus`
+pub const RESOLVE_STATUS_SUCCESS: u64 = 1;
+// Symbols are committed as feed ids, which must fit a field element
+pub const MAX_SYMBOL_BS: usize = 31;
+
+/// Protobuf varint of `value`.
+pub fn encode_varint(mut value: u64) -> Vec<u8> {
+    let mut bytes = vec![];
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+    bytes
+}
+
+/// Protobuf varint of signed `value` in zigzag encoding, which IAVL uses for heights, sizes and
+/// versions.
+pub fn encode_varint_signed(value: i64) -> Vec<u8> {
+    encode_varint(((value << 1) ^ (value >> 63)) as u64)
+}
+
+fn read<const N: usize>(bytes: &[u8], offset: &mut usize) -> anyhow::Result<[u8; N]> {
+    let chunk = bytes
+        .get(*offset..*offset + N)
+        .ok_or_else(|| anyhow::anyhow!("OBI data ends at {}", bytes.len()))?;
+    *offset += N;
+    Ok(chunk.try_into()?)
+}
+
+/// Oracle result of a BandChain request, i.e. `oracle.v1.Result`, whose protobuf encoding is
+/// stored in the oracle IAVL tree under key `0xff || requestId`.
+///
+/// Calldata and result are OBI-encoded input `{symbols: []string, multiplier: u64}` and output
+/// `{rates: []u64}` of the standard price oracle script, where rates are prices multiplied by
+/// `multiplier`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OracleResult {
+    pub client_id: String,
+    pub oracle_script_id: u64,
+    pub calldata: Vec<u8>,
+    pub ask_count: u64,
+    pub min_count: u64,
+    pub request_id: u64,
+    pub ans_count: u64,
+    pub request_time: u64,
+    pub resolve_time: u64,
+    pub resolve_status: u64,
+    pub result: Vec<u8>,
+}
+
+fn encode_bytes_field(bytes: &mut Vec<u8>, number: u8, field: &[u8]) {
+    if !field.is_empty() {
+        bytes.push(number << 3 | 2);
+        bytes.extend(encode_varint(field.len() as u64));
+        bytes.extend(field);
+    }
+}
+
+fn encode_varint_field(bytes: &mut Vec<u8>, number: u8, value: u64) {
+    if value != 0 {
+        bytes.push(number << 3);
+        bytes.extend(encode_varint(value));
+    }
+}
+
+impl OracleResult {
+    /// Field numbers and values of varint fields following calldata.
+    fn varint_fields(&self) -> [(u8, u64); 7] {
+        [
+            (4, self.ask_count),
+            (5, self.min_count),
+            (6, self.request_id),
+            (7, self.ans_count),
+            (8, self.request_time),
+            (9, self.resolve_time),
+            (10, self.resolve_status),
+        ]
+    }
+
+    /// Protobuf encoding, which omits default values as proto3 does.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        encode_bytes_field(&mut bytes, 1, self.client_id.as_bytes());
+        encode_varint_field(&mut bytes, 2, self.oracle_script_id);
+        encode_bytes_field(&mut bytes, 3, &self.calldata);
+        for (number, value) in self.varint_fields() {
+            encode_varint_field(&mut bytes, number, value);
+        }
+        encode_bytes_field(&mut bytes, 11, &self.result);
+        bytes
+    }
+
+    /// Decode calldata into symbols and the multiplier.
+    pub fn decode_calldata(&self) -> anyhow::Result<(Vec<String>, u64)> {
+        let offset = &mut 0;
+        let count = u32::from_be_bytes(read(&self.calldata, offset)?);
+        let mut symbols = vec![];
+        for _ in 0..count {
+            let len = u32::from_be_bytes(read(&self.calldata, offset)?) as usize;
+            let symbol = self
+                .calldata
+                .get(*offset..*offset + len)
+                .ok_or_else(|| anyhow::anyhow!("symbol overflows calldata"))?;
+            *offset += len;
+            symbols.push(String::from_utf8(symbol.to_vec())?);
+        }
+        let multiplier = u64::from_be_bytes(read(&self.calldata, offset)?);
+        if *offset != self.calldata.len() {
+            anyhow::bail!(
+                "{} trailing bytes of calldata",
+                self.calldata.len() - *offset
+            )
+        }
+        Ok((symbols, multiplier))
+    }
+
+    /// Decode result into rates.
+    pub fn decode_rates(&self) -> anyhow::Result<Vec<u64>> {
+        let offset = &mut 0;
+        let count = u32::from_be_bytes(read(&self.result, offset)?);
+        let rates = (0..count)
+            .map(|_| Ok(u64::from_be_bytes(read(&self.result, offset)?)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        if *offset != self.result.len() {
+            anyhow::bail!("{} trailing bytes of result", self.result.len() - *offset)
+        }
+        Ok(rates)
+    }
+
+    /// Check the request is resolved successfully with a rate of each symbol, and return the
+    /// symbols as feed ids, the multiplier and rates.
+    pub fn check(&self) -> anyhow::Result<(Vec<BigUint>, u64, Vec<u64>)> {
+        if self.resolve_status != RESOLVE_STATUS_SUCCESS {
+            anyhow::bail!(
+                "request {} is resolved with status {}",
+                self.request_id,
+                self.resolve_status
+            )
+        }
+        let (symbols, multiplier) = self.decode_calldata()?;
+        let rates = self.decode_rates()?;
+        if symbols.len() != rates.len() {
+            anyhow::bail!("{} rates of {} symbols", rates.len(), symbols.len())
+        }
+        if let Some(symbol) = symbols.iter().find(|s| s.len() > MAX_SYMBOL_BS) {
+            anyhow::bail!("symbol {} is longer than {} bytes", symbol, MAX_SYMBOL_BS)
+        }
+        let feed_ids = symbols
+            .iter()
+            .map(|s| BigUint::from_bytes_be(s.as_bytes()))
+            .collect();
+        Ok((feed_ids, multiplier, rates))
+    }
+
+    /// Field elements of `(symbol, rate)` of each symbol, i.e. prices committed in the same order
+    /// as [`AllocatedOracleResult::prices`].
+    pub fn prices<E: Engine>(&self) -> anyhow::Result<Vec<E::Fr>> {
+        let (feed_ids, _, rates) = self.check()?;
+        let mut prices = vec![];
+        for (feed_id, rate) in feed_ids.iter().zip(rates) {
+            prices.push(fr_from_biguint::<E>(feed_id)?);
+            prices.push(fr_from_biguint::<E>(&BigUint::from(rate))?);
+        }
+        Ok(prices)
+    }
+}
+
+/// Varint of `value` in circuit, whose length is fixed by the witness at synthesis. Continuation
+/// bits are enforced, so that the returned value is bound to the returned bytes.
+pub fn circuit_varint<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    value: u64,
+) -> Result<(Num<E>, Vec<Byte<E>>), SynthesisError> {
+    let witness = encode_varint(value);
+    let mut bytes = vec![];
+    let mut lc = LinearCombination::zero();
+    let mut coeff = E::Fr::one();
+    for (i, b) in witness.iter().enumerate() {
+        let byte = Byte::from_u8_witness(cs, Some(*b))?;
+        let bits = byte.inner.into_bits_le(cs, Some(8))?;
+        Boolean::enforce_equal(cs, &bits[7], &Boolean::constant(i + 1 < witness.len()))?;
+        for bit in bits[..7].iter() {
+            lc.add_assign_boolean_with_coeff(bit, coeff);
+            coeff.double();
+        }
+        bytes.push(byte);
+    }
+    Ok((lc.into_num(cs)?, bytes))
+}
+
+fn constant_bytes<E: Engine>(bytes: &[u8]) -> Vec<Byte<E>> {
+    bytes.iter().map(|b| Byte::constant(*b)).collect()
+}
+
+fn alloc_bytes<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    bytes: &[u8],
+) -> Result<Vec<Byte<E>>, SynthesisError> {
+    bytes
+        .iter()
+        .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+        .collect()
+}
+
+fn push_bytes_field<E: Engine>(encoded: &mut Vec<Byte<E>>, number: u8, field: Vec<Byte<E>>) {
+    if !field.is_empty() {
+        encoded.push(Byte::constant(number << 3 | 2));
+        encoded.extend(constant_bytes(&encode_varint(field.len() as u64)));
+        encoded.extend(field);
+    }
+}
+
+fn push_varint_field<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    encoded: &mut Vec<Byte<E>>,
+    number: u8,
+    value: u64,
+) -> Result<Num<E>, SynthesisError> {
+    if value == 0 {
+        return Ok(Num::zero());
+    }
+    encoded.push(Byte::constant(number << 3));
+    let (value, bytes) = circuit_varint(cs, value)?;
+    encoded.extend(bytes);
+    Ok(value)
+}
+
+/// Circuit representation of [`OracleResult`], which is built from the committed fields, so that
+/// they are bound to the protobuf encoding. The layout, i.e. lengths of varints and strings, is
+/// fixed by the witness at synthesis.
+#[derive(Debug, Clone)]
+pub struct AllocatedOracleResult<E: Engine> {
+    pub encoded: Vec<Byte<E>>,
+    pub oracle_script_id: Num<E>,
+    /// Request id in big-endian, which is the key of the result in the oracle store
+    pub request_id: [Byte<E>; 8],
+    pub resolve_time: Num<E>,
+    pub multiplier: Num<E>,
+    pub symbols: Vec<Num<E>>,
+    pub rates: Vec<Num<E>>,
+}
+
+impl<E: Engine> AllocatedOracleResult<E> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &OracleResult,
+    ) -> Result<Self, SynthesisError> {
+        let decoded = witness.check().and_then(|_| {
+            let (symbols, multiplier) = witness.decode_calldata()?;
+            Ok((symbols, multiplier, witness.decode_rates()?))
+        });
+        let (symbols, multiplier, rates) =
+            decoded.map_err(|e| new_synthesis_error(e.to_string()))?;
+
+        let mut encoded = vec![];
+        let client_id = alloc_bytes(cs, witness.client_id.as_bytes())?;
+        push_bytes_field(&mut encoded, 1, client_id);
+        let oracle_script_id = push_varint_field(cs, &mut encoded, 2, witness.oracle_script_id)?;
+
+        // calldata = count || (len || symbol) of each symbol || multiplier
+        let mut calldata = constant_bytes(&(symbols.len() as u32).to_be_bytes());
+        let mut symbol_nums = vec![];
+        for symbol in symbols.iter() {
+            calldata.extend(constant_bytes(&(symbol.len() as u32).to_be_bytes()));
+            let symbol = alloc_bytes(cs, symbol.as_bytes())?;
+            symbol_nums.push(num_from_be_bytes(cs, &symbol)?);
+            calldata.extend(symbol);
+        }
+        let multiplier_bytes = alloc_bytes(cs, &multiplier.to_be_bytes())?;
+        let multiplier = num_from_be_bytes(cs, &multiplier_bytes)?;
+        calldata.extend(multiplier_bytes);
+        push_bytes_field(&mut encoded, 3, calldata);
+
+        let mut varints = vec![];
+        for (number, value) in witness.varint_fields() {
+            varints.push(push_varint_field(cs, &mut encoded, number, value)?);
+        }
+        let (request_id, resolve_time, resolve_status) = (varints[2], varints[5], varints[6]);
+        resolve_status.enforce_equal(cs, &Num::Constant(u64_to_fe(RESOLVE_STATUS_SUCCESS)))?;
+
+        // result = count || rate of each symbol
+        let mut result = constant_bytes(&(rates.len() as u32).to_be_bytes());
+        let mut rate_nums = vec![];
+        for rate in rates.iter() {
+            let rate = alloc_bytes(cs, &rate.to_be_bytes())?;
+            rate_nums.push(num_from_be_bytes(cs, &rate)?);
+            result.extend(rate);
+        }
+        push_bytes_field(&mut encoded, 11, result);
+
+        // The result is keyed by the request id in big-endian
+        let request_id_bytes = alloc_bytes(cs, &witness.request_id.to_be_bytes())?;
+        num_from_be_bytes(cs, &request_id_bytes)?.enforce_equal(cs, &request_id)?;
+
+        Ok(Self {
+            encoded,
+            oracle_script_id,
+            request_id: request_id_bytes.try_into().unwrap(),
+            resolve_time,
+            multiplier,
+            symbols: symbol_nums,
+            rates: rate_nums,
+        })
+    }
+
+    /// `(symbol, rate)` of each symbol.
+    pub fn prices(&self) -> Vec<Num<E>> {
+        self.symbols
+            .iter()
+            .zip(self.rates.iter())
+            .flat_map(|(symbol, rate)| [*symbol, *rate])
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte,
+        franklin_crypto::bellman::{pairing::bn256::Bn256, SynthesisError},
+    };
+    use num_bigint::BigUint;
+
+    use super::AllocatedOracleResult;
+    use crate::utils::{
+        fr_from_biguint,
+        testing::{band_oracle_result, create_test_constraint_system},
+    };
+
+    #[test]
+    fn test_oracle_result() -> Result<(), SynthesisError> {
+        let result = band_oracle_result(
+            20_000_000,
+            &["BTC", "ETH"],
+            &[43_000_000_000_000, 2_300_000_000_000],
+        );
+        let encoded = result.encode();
+        // client_id, then oracle_script_id = 360
+        assert_eq!(&encoded[..11], b"\x0a\x06zklink\x10\xe8\x02");
+        assert!(result.check().is_ok());
+        let mut failed = result.clone();
+        failed.resolve_status = 2;
+        assert!(failed.check().is_err());
+
+        let cs = &mut create_test_constraint_system()?;
+        let allocated = AllocatedOracleResult::<Bn256>::from_witness(cs, &result)?;
+        assert_eq!(
+            Byte::get_byte_value_multiple(&allocated.encoded).unwrap(),
+            encoded
+        );
+        assert_eq!(
+            allocated.symbols[1].get_value(),
+            Some(fr_from_biguint::<Bn256>(&BigUint::from_bytes_be(b"ETH")).unwrap())
+        );
+        let prices = allocated
+            .prices()
+            .iter()
+            .map(|p| p.get_value().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(prices, result.prices::<Bn256>().unwrap());
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+}
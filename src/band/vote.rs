@@ -0,0 +1,262 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    utils::u64_to_fe,
+    vm::partitioner::smart_and,
+};
+use num_bigint::BigUint;
+
+use crate::{
+    gadgets::{
+        ed25519::{self, verify_signature, PUBKEY_BS, SIGNATURE_BS},
+        poseidon::{circuit_poseidon_hash, poseidon_hash},
+    },
+    utils::{fr_from_biguint, new_synthesis_error, num_from_be_bytes},
+};
+
+// `type = SIGNED_MSG_TYPE_PRECOMMIT` and the tag of `height` in `CanonicalVote`
+const VOTE_PREFIX_HEAD: [u8; 3] = [0x08, 0x02, 0x11];
+// The tag of `block_id` in `CanonicalVote`, followed by its length and the tag and length of `hash`
+const VOTE_PREFIX_TAIL: [u8; 4] = [0x22, 0x48, 0x0a, 0x20];
+
+/// Validator of BandChain, which signs votes with its ed25519 key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Validator {
+    pub pubkey: [u8; PUBKEY_BS],
+    pub voting_power: u64,
+}
+
+/// `poseidon(pubkey[..16], pubkey[16..], voting power of each validator)`.
+pub fn validator_set_hash<E: Engine>(validators: &[Validator]) -> anyhow::Result<E::Fr> {
+    let mut input = vec![];
+    for validator in validators {
+        input.push(fr_from_biguint::<E>(&BigUint::from_bytes_be(
+            &validator.pubkey[..16],
+        ))?);
+        input.push(fr_from_biguint::<E>(&BigUint::from_bytes_be(
+            &validator.pubkey[16..],
+        ))?);
+        input.push(fr_from_biguint::<E>(&BigUint::from(
+            validator.voting_power,
+        ))?);
+    }
+    Ok(poseidon_hash::<E>(&input))
+}
+
+/// Precommit of a validator for a block, whose sign bytes are the length-delimited protobuf of
+/// `CanonicalVote`, i.e. `signed_data_prefix || block hash || signed_data_suffix`.
+///
+/// The prefix is the length, the vote type, the height in fixed64, the round if not zero, and tags
+/// of the block id and its hash. The suffix is the part set header, the timestamp and the chain id,
+/// which are only signed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Vote {
+    pub signed_data_prefix: Vec<u8>,
+    pub signed_data_suffix: Vec<u8>,
+    pub signature: [u8; SIGNATURE_BS],
+}
+
+impl Vote {
+    pub fn sign_bytes(&self, block_hash: &[u8; 32]) -> Vec<u8> {
+        let mut bytes = self.signed_data_prefix.clone();
+        bytes.extend(block_hash);
+        bytes.extend(&self.signed_data_suffix);
+        bytes
+    }
+
+    /// Offset of the vote type in the prefix, i.e. the length of the leading varint.
+    fn head_offset(&self) -> usize {
+        self.signed_data_prefix
+            .iter()
+            .position(|b| b & 0x80 == 0)
+            .map(|i| i + 1)
+            .unwrap_or(0)
+    }
+
+    /// Height of the precommit.
+    pub fn height(&self) -> anyhow::Result<u64> {
+        let prefix = &self.signed_data_prefix;
+        let head = self.head_offset();
+        if prefix.len() < head + VOTE_PREFIX_HEAD.len() + 8 + VOTE_PREFIX_TAIL.len()
+            || prefix[head..head + VOTE_PREFIX_HEAD.len()] != VOTE_PREFIX_HEAD
+            || prefix[prefix.len() - VOTE_PREFIX_TAIL.len()..] != VOTE_PREFIX_TAIL
+        {
+            anyhow::bail!("0x{} is not a precommit prefix", hex::encode(prefix))
+        }
+        let offset = head + VOTE_PREFIX_HEAD.len();
+        Ok(u64::from_le_bytes(prefix[offset..offset + 8].try_into()?))
+    }
+
+    /// Check the vote is a precommit of `block_hash` signed by `validator`, and return its height.
+    pub fn check(&self, validator: &Validator, block_hash: &[u8; 32]) -> anyhow::Result<u64> {
+        let height = self.height()?;
+        if !verify_signature(
+            &validator.pubkey,
+            &self.sign_bytes(block_hash),
+            &self.signature,
+        ) {
+            anyhow::bail!(
+                "precommit of block 0x{} is not signed by 0x{}",
+                hex::encode(block_hash),
+                hex::encode(validator.pubkey)
+            )
+        }
+        Ok(height)
+    }
+}
+
+fn alloc_bytes<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    bytes: &[u8],
+) -> Result<Vec<Byte<E>>, SynthesisError> {
+    bytes
+        .iter()
+        .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+        .collect()
+}
+
+/// Circuit representation of [`Validator`].
+#[derive(Debug, Clone)]
+pub struct AllocatedValidator<E: Engine> {
+    pub pubkey: [Byte<E>; PUBKEY_BS],
+    pub voting_power: Num<E>,
+}
+
+impl<E: Engine> AllocatedValidator<E> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &Validator,
+    ) -> Result<Self, SynthesisError> {
+        let pubkey = alloc_bytes(cs, &witness.pubkey)?.try_into().unwrap();
+        let voting_power = alloc_bytes(cs, &witness.voting_power.to_be_bytes())?;
+        Ok(Self {
+            pubkey,
+            voting_power: num_from_be_bytes(cs, &voting_power)?,
+        })
+    }
+}
+
+/// Same as [`validator_set_hash`].
+pub fn circuit_validator_set_hash<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    validators: &[AllocatedValidator<E>],
+) -> Result<Num<E>, SynthesisError> {
+    let mut input = vec![];
+    for validator in validators {
+        input.push(num_from_be_bytes(cs, &validator.pubkey[..16])?);
+        input.push(num_from_be_bytes(cs, &validator.pubkey[16..])?);
+        input.push(validator.voting_power);
+    }
+    circuit_poseidon_hash(cs, &input)
+}
+
+/// Circuit representation of [`Vote`]. Lengths of the prefix and the suffix are fixed by the
+/// witness at synthesis.
+#[derive(Debug, Clone)]
+pub struct AllocatedVote<E: Engine> {
+    pub signed_data_prefix: Vec<Byte<E>>,
+    pub signed_data_suffix: Vec<Byte<E>>,
+    pub signature: [Byte<E>; SIGNATURE_BS],
+    head_offset: usize,
+}
+
+impl<E: Engine> AllocatedVote<E> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &Vote,
+    ) -> Result<Self, SynthesisError> {
+        Ok(Self {
+            signed_data_prefix: alloc_bytes(cs, &witness.signed_data_prefix)?,
+            signed_data_suffix: alloc_bytes(cs, &witness.signed_data_suffix)?,
+            signature: alloc_bytes(cs, &witness.signature)?.try_into().unwrap(),
+            head_offset: witness.head_offset(),
+        })
+    }
+
+    /// Same as [`Vote::check`]. Return whether the vote is a precommit of `block_hash` signed by
+    /// `validator`, and its height, which is only meaningful if the check passes.
+    pub fn verify<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        validator: &AllocatedValidator<E>,
+        block_hash: &[Byte<E>; 32],
+    ) -> Result<(Boolean, Num<E>), SynthesisError> {
+        let prefix = &self.signed_data_prefix;
+        let head = self.head_offset;
+        let height_offset = head + VOTE_PREFIX_HEAD.len();
+        let tail_offset = prefix
+            .len()
+            .checked_sub(VOTE_PREFIX_TAIL.len())
+            .filter(|offset| *offset >= height_offset + 8)
+            .ok_or_else(|| new_synthesis_error(format!("prefix of {} bytes", prefix.len())))?;
+        let mut is_valid = vec![];
+        let expected = VOTE_PREFIX_HEAD
+            .iter()
+            .zip(prefix[head..].iter())
+            .chain(VOTE_PREFIX_TAIL.iter().zip(prefix[tail_offset..].iter()));
+        for (b, byte) in expected {
+            is_valid.push(
+                byte.inner
+                    .equals(cs, &Num::Constant(u64_to_fe(*b as u64)))?,
+            );
+        }
+        let mut height = prefix[height_offset..height_offset + 8].to_vec();
+        height.reverse();
+        let height = num_from_be_bytes(cs, &height)?;
+
+        let mut sign_bytes = prefix.clone();
+        sign_bytes.extend(block_hash);
+        sign_bytes.extend(self.signed_data_suffix.iter().copied());
+        is_valid.push(ed25519::verify(
+            cs,
+            &validator.pubkey,
+            &sign_bytes,
+            &self.signature,
+        )?);
+        Ok((smart_and(cs, &is_valid)?, height))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte,
+        franklin_crypto::bellman::{pairing::bn256::Bn256, SynthesisError},
+        utils::u64_to_fe,
+    };
+
+    use super::{AllocatedValidator, AllocatedVote, Validator};
+    use crate::utils::{
+        signer::Ed25519Signer,
+        testing::{band_vote, create_test_constraint_system},
+    };
+
+    #[test]
+    fn test_vote() -> Result<(), SynthesisError> {
+        let signer = Ed25519Signer::new(0x07);
+        let validator = Validator {
+            pubkey: signer.pubkey(),
+            voting_power: 1_000,
+        };
+        let block_hash = [0x5au8; 32];
+        let vote = band_vote(&signer, 19_000_000, &block_hash);
+        assert_eq!(vote.check(&validator, &block_hash).unwrap(), 19_000_000);
+        assert!(vote.check(&validator, &[0u8; 32]).is_err());
+
+        for (hash, expected) in [(block_hash, true), ([0u8; 32], false)] {
+            let cs = &mut create_test_constraint_system()?;
+            let validator = AllocatedValidator::<Bn256>::from_witness(cs, &validator)?;
+            let vote = AllocatedVote::from_witness(cs, &vote)?;
+            let hash = hash.map(Byte::constant);
+            let (is_valid, height) = vote.verify(cs, &validator, &hash)?;
+            assert_eq!(is_valid.get_value(), Some(expected));
+            assert_eq!(height.get_value(), Some(u64_to_fe(19_000_000)));
+            assert!(cs.is_satisfied());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,134 @@
+use advanced_circuit_component::testing::{create_test_artifacts_with_optimized_gate, Bn256};
+use sha3::{Digest, Keccak256};
+use zklink_oracle::{
+    franklin_crypto::bellman::plonk::better_better_cs::cs::{Circuit, ConstraintSystem},
+    gadgets::{ethereum::Address, keccak160::native_merkle_root},
+    pyth::{
+        check_guardian_quorum, AmortizedPriceOracle, ParsedVaa, PYTHNET_EMITTER_ADDRESS,
+        PYTHNET_EMITTER_CHAIN,
+    },
+    pythnet_sdk::wire::{from_slice, v1::MerklePriceUpdate},
+    utils::{add_bitwise_logic_and_range_table, signer::RedstoneSigner},
+};
+
+// Depth of pyth merkle tree, which is the depth supported by price update circuits
+const MERKLE_DEPTH: usize = 10;
+// (number of guardians, number of signatures) of measured VAAs
+const SIGNATURE_CASES: [(u8, usize); 3] = [(1, 1), (19, 13), (19, 19)];
+const FEED_CASES: [usize; 4] = [1, 2, 5, 10];
+
+/// ETH/USD price feed message with a synthetic merkle path, and the root it is proven against.
+fn price_update() -> (Vec<u8>, [u8; 20]) {
+    let mut message = vec![0u8];
+    message.extend(
+        hex::decode("e62df6c8b4a85fe1a67db44dc12de5db330f7ac66b72dc658afedf0f4a415b43").unwrap(),
+    );
+    message.extend(3652890574272i64.to_be_bytes());
+    message.extend(1122941430u64.to_be_bytes());
+    message.extend((-8i32).to_be_bytes());
+    message.extend(1700581367i64.to_be_bytes());
+    message.extend(1700581367i64.to_be_bytes());
+    message.extend(3671402700000i64.to_be_bytes());
+    message.extend(1527607570u64.to_be_bytes());
+    let path = (0..MERKLE_DEPTH as u8)
+        .map(|i| [i + 1; 20])
+        .collect::<Vec<_>>();
+    let root = native_merkle_root(&message, &path);
+
+    let mut update = (message.len() as u16).to_be_bytes().to_vec();
+    update.extend(message);
+    update.push(MERKLE_DEPTH as u8);
+    update.extend(path.concat());
+    (update, root)
+}
+
+/// Accumulator VAA carrying `root`, signed by the first `num_signatures` of `signers`.
+fn accumulator_vaa(root: &[u8; 20], signers: &[RedstoneSigner], num_signatures: usize) -> Vec<u8> {
+    let mut body = vec![0u8; 8];
+    body.extend(PYTHNET_EMITTER_CHAIN.to_be_bytes());
+    body.extend(*PYTHNET_EMITTER_ADDRESS);
+    body.extend([0u8; 9]);
+    body.extend(b"AUWV");
+    body.push(0);
+    body.extend([0u8; 12]);
+    body.extend(root);
+    let hash: [u8; 32] = Keccak256::digest(Keccak256::digest(&body)).into();
+
+    let mut vaa = vec![1, 0, 0, 0, 3, num_signatures as u8];
+    for (i, signer) in signers[..num_signatures].iter().enumerate() {
+        let mut signature = signer.sign_hash(&hash);
+        signature[64] -= 27;
+        vaa.push(i as u8);
+        vaa.extend(signature);
+    }
+    vaa.extend(body);
+    vaa
+}
+
+fn report(name: &str, gates: usize) {
+    println!(
+        "| {} | {} | 2^{} |",
+        name,
+        gates,
+        gates.next_power_of_two().trailing_zeros()
+    );
+}
+
+/// Report gate counts of wormhole and pyth verification on synthetic witnesses, e.g.
+/// `cargo run --release --features gate-report --bin pyth-gates`.
+fn main() -> anyhow::Result<()> {
+    let (update, root) = price_update();
+    let update = from_slice::<byteorder::BE, MerklePriceUpdate>(&update)?;
+    let signers = RedstoneSigner::generate(19);
+
+    println!("| Case | Gates | Domain |");
+    println!("| --- | --- | --- |");
+    for (num_guardians, num_signatures) in SIGNATURE_CASES {
+        let signers = &signers[..num_guardians as usize];
+        let vaa = accumulator_vaa(&root, signers, num_signatures);
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        add_bitwise_logic_and_range_table(&mut cs)?;
+        let vaa = ParsedVaa::from_bytes_witness(&mut cs, &vaa, num_signatures)?;
+        let guardian_set = signers
+            .iter()
+            .map(|s| Address::from_address_witness(&mut cs, &s.address()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let n = cs.n();
+        let is_valid = check_guardian_quorum(&mut cs, &vaa, &guardian_set)?;
+        assert_eq!(is_valid.get_value(), Some(true));
+        report(
+            &format!(
+                "guardian quorum, {} of {} signatures",
+                num_signatures, num_guardians
+            ),
+            cs.n() - n,
+        );
+    }
+
+    let guardian_set = signers.iter().map(|s| s.address()).collect::<Vec<_>>();
+    let vaa = accumulator_vaa(&root, &signers, 13);
+    let mut last = None;
+    for num_feeds in FEED_CASES {
+        let circuit = AmortizedPriceOracle::<Bn256>::new(
+            vaa.clone(),
+            13,
+            vec![update.clone(); num_feeds],
+            guardian_set.clone(),
+        )?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        report(
+            &format!("amortized oracle, 13 of 19 signatures, {} feeds", num_feeds),
+            cs.n(),
+        );
+        if let Some((last_feeds, last_gates)) = last {
+            report(
+                &format!("  per feed, {} to {} feeds", last_feeds, num_feeds),
+                (cs.n() - last_gates) / (num_feeds - last_feeds),
+            );
+        }
+        last = Some((num_feeds, cs.n()));
+    }
+    Ok(())
+}
@@ -0,0 +1,226 @@
+use serde::Serialize;
+use zklink_oracle::{
+    franklin_crypto::bellman::pairing::{
+        bn256::{Bn256, Fr},
+        ff::{PrimeField, PrimeFieldRepr},
+    },
+    redstone::{
+        witness::{DataPackage, DataPoint, SignedPackageWitness},
+        PriceOracle, RedstoneConfig,
+    },
+    utils::signer::RedstoneSigner,
+};
+
+#[derive(Serialize)]
+struct PackageVector {
+    timestamp: u64,
+    hash: String,
+    signature: String,
+    recovered_signer: String,
+}
+
+#[derive(Serialize)]
+struct RoundVector {
+    payload: String,
+    packages: Vec<PackageVector>,
+}
+
+#[derive(Serialize)]
+struct CommittedRound {
+    publish_time: u64,
+    prices: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Vector {
+    name: &'static str,
+    feed_ids: Vec<String>,
+    guardians: Vec<String>,
+    config: RedstoneConfig,
+    latest_only: bool,
+    output_commitment: bool,
+    rounds: Vec<RoundVector>,
+    committed_rounds: Vec<CommittedRound>,
+    guardian_set_hash: String,
+    commitment: String,
+    public_inputs: Vec<String>,
+}
+
+/// Options of a vector in the matrix
+#[derive(Default)]
+struct Options {
+    config: RedstoneConfig,
+    latest_only: bool,
+    output_commitment: bool,
+}
+
+fn fr_to_hex(fr: &Fr) -> anyhow::Result<String> {
+    let mut bytes = vec![];
+    fr.into_repr().write_be(&mut bytes)?;
+    Ok(format!("0x{}", hex::encode(bytes)))
+}
+
+fn vector<const NUM_SIGNATURES: usize, const NUM_FEEDS: usize>(
+    name: &'static str,
+    feed_ids: [&str; NUM_FEEDS],
+    rounds: Vec<[DataPackage; NUM_SIGNATURES]>,
+    options: Options,
+) -> anyhow::Result<Vector> {
+    let signers = RedstoneSigner::generate(NUM_SIGNATURES as u8);
+    let guardians: [[u8; 20]; NUM_SIGNATURES] = std::array::from_fn(|i| signers[i].address());
+    let signed_packages_batch = rounds
+        .into_iter()
+        .map(|packages| {
+            let mut signers = signers.iter();
+            packages.map(|package| signers.next().unwrap().sign_witness(package))
+        })
+        .collect::<Vec<[SignedPackageWitness; NUM_SIGNATURES]>>();
+
+    let rounds = signed_packages_batch
+        .iter()
+        .map(|signed_packages| {
+            let packages = signed_packages
+                .iter()
+                .map(|witness| {
+                    Ok(PackageVector {
+                        timestamp: witness.package.timestamp,
+                        hash: hex::encode(witness.package.keccak256_hash()),
+                        signature: hex::encode(witness.signature),
+                        recovered_signer: hex::encode(
+                            witness.package.recover_signer(&witness.signature)?,
+                        ),
+                    })
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            Ok(RoundVector {
+                payload: DataPackage::to_hex_payload(signed_packages)?,
+                packages,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let mut oracle = PriceOracle::<Bn256, NUM_SIGNATURES, NUM_FEEDS>::new_shared_with_config(
+        feed_ids.map(|feed_id| feed_id.to_string()),
+        signed_packages_batch,
+        guardians,
+        options.config.clone(),
+    )?;
+    if options.latest_only {
+        oracle = oracle.with_latest_only()?;
+    }
+    if options.output_commitment {
+        oracle = oracle.with_output_commitment();
+    }
+    let committed_rounds = oracle
+        .opening()?
+        .rounds
+        .into_iter()
+        .map(|round| CommittedRound {
+            publish_time: round.publish_time,
+            prices: round.prices.iter().map(|p| p.price.to_string()).collect(),
+        })
+        .collect();
+    Ok(Vector {
+        name,
+        feed_ids: feed_ids.map(|feed_id| feed_id.to_string()).to_vec(),
+        guardians: guardians.iter().map(hex::encode).collect(),
+        config: options.config,
+        latest_only: options.latest_only,
+        output_commitment: options.output_commitment,
+        rounds,
+        committed_rounds,
+        guardian_set_hash: fr_to_hex(&oracle.public_input_data.guardian_set_hash)?,
+        commitment: fr_to_hex(&oracle.commitment)?,
+        public_inputs: oracle
+            .public_inputs()?
+            .iter()
+            .map(fr_to_hex)
+            .collect::<anyhow::Result<Vec<_>>>()?,
+    })
+}
+
+fn package(data_points: &[(&str, &str)], timestamp: u64) -> DataPackage {
+    DataPackage::new(
+        data_points
+            .iter()
+            .map(|(feed_id, value)| DataPoint::new(feed_id, value))
+            .collect(),
+        timestamp,
+    )
+}
+
+/// Emit golden test vectors of RedStone circuits as JSON, e.g.
+/// `cargo run --features golden-vectors --bin redstone-vectors > vectors.json`.
+fn main() -> anyhow::Result<()> {
+    let btc_eth = [("BTC", "20000"), ("ETH", "1000")];
+    let vectors = vec![
+        vector(
+            "single-signer",
+            ["BTC", "ETH"],
+            vec![[package(&btc_eth, 1654353400000)]],
+            Options::default(),
+        )?,
+        vector(
+            "three-signers-two-rounds",
+            ["BTC", "ETH"],
+            vec![
+                [
+                    package(&[("BTC", "20000"), ("ETH", "1000")], 1654353400000),
+                    package(&[("BTC", "20010"), ("ETH", "1001")], 1654353400000),
+                    package(&[("BTC", "19990"), ("ETH", "999.5")], 1654353400000),
+                ],
+                [
+                    package(&[("BTC", "20100"), ("ETH", "1010")], 1654353410000),
+                    package(&[("BTC", "20110"), ("ETH", "1011")], 1654353410000),
+                    package(&[("BTC", "20090"), ("ETH", "1009")], 1654353410000),
+                ],
+            ],
+            Options::default(),
+        )?,
+        vector(
+            "quorum",
+            ["BTC", "ETH"],
+            vec![[
+                package(&btc_eth, 1654353400000),
+                package(&btc_eth, 1654353400000),
+                package(&[("BTC", "20000")], 1654353400000),
+            ]],
+            Options {
+                config: RedstoneConfig {
+                    quorum: Some(2),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )?,
+        vector(
+            "latest-only",
+            ["BTC"],
+            vec![
+                [package(&[("BTC", "20000")], 1654353400000)],
+                [package(&[("BTC", "20100")], 1654353410000)],
+            ],
+            Options {
+                latest_only: true,
+                ..Default::default()
+            },
+        )?,
+        vector(
+            "output-commitment",
+            ["BTC", "ETH"],
+            vec![[package(&btc_eth, 1654353400000)]],
+            Options {
+                output_commitment: true,
+                ..Default::default()
+            },
+        )?,
+        vector(
+            "heartbeat",
+            [],
+            vec![[package(&[], 1654353400000)]],
+            Options::default(),
+        )?,
+    ];
+    println!("{}", serde_json::to_string_pretty(&vectors)?);
+    Ok(())
+}
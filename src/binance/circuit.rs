@@ -0,0 +1,309 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{
+    ff::{Field, PrimeField},
+    Engine,
+};
+use advanced_circuit_component::{
+    franklin_crypto::{
+        bellman::{
+            plonk::better_better_cs::{
+                cs::{Circuit, ConstraintSystem, Gate, GateInternal},
+                gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
+            },
+            SynthesisError,
+        },
+        plonk::circuit::{
+            allocated_num::Num, boolean::Boolean, custom_rescue_gate::Rescue5CustomGate,
+        },
+    },
+    glue::prepacked_long_comparison,
+};
+use num_bigint::BigUint;
+
+use crate::{
+    gadgets::{
+        ethereum::Address,
+        poseidon::{circuit_poseidon_hash, poseidon_hash},
+    },
+    oracle::{self, circuit_summarize_prices, outputs_commitment, SignedPriceOracle},
+    redstone::witness::guardian_set_hash,
+    utils::{fr_from_biguint, new_synthesis_error},
+    witness::{OracleOutputData, OraclePricesSummarize, PricesSummarize, PublicInputData},
+};
+
+use super::feed::{AllocatedSignedBinanceFeed, SignedBinanceFeed};
+
+/// Circuit verifying batches of Binance Oracle feeds of `NUM_PRICES` symbols, each of which is
+/// signed by `THRESHOLD` of `signers`. Outputs are summarized in the same way as
+/// [`crate::redstone::PriceOracle`], so that it is verified behind [`SignedPriceOracle`] like any
+/// other provider.
+///
+/// The guardian set is `signers`. Feed ids are symbols, prices are rescaled from
+/// [`BINANCE_PRICE_DECIMALS`](super::BINANCE_PRICE_DECIMALS) to the committed precision, and the
+/// publish time of a batch is the timestamp of its first feed in milliseconds.
+#[derive(Debug, Clone)]
+pub struct BinancePriceOracle<E: Engine, const THRESHOLD: usize, const NUM_PRICES: usize> {
+    pub feeds_batch: Vec<[SignedBinanceFeed; NUM_PRICES]>,
+    pub signers: Vec<[u8; 20]>,
+    pub public_input_data: PublicInputData<E>,
+    pub commitment: E::Fr,
+}
+
+impl<E: Engine, const THRESHOLD: usize, const NUM_PRICES: usize>
+    BinancePriceOracle<E, THRESHOLD, NUM_PRICES>
+{
+    pub fn new(
+        feeds_batch: Vec<[SignedBinanceFeed; NUM_PRICES]>,
+        signers: Vec<[u8; 20]>,
+    ) -> Result<Self, anyhow::Error> {
+        if THRESHOLD > signers.len() {
+            anyhow::bail!("threshold {} exceeds {} signers", THRESHOLD, signers.len())
+        }
+        let mut last_publish_time = 0;
+        let mut prices_commitments = vec![];
+        for feeds in feeds_batch.iter() {
+            let mut prices_commitment_members = vec![];
+            for signed in feeds.iter() {
+                signed.check(&signers, THRESHOLD)?;
+                prices_commitment_members.push(fr_from_biguint::<E>(&signed.feed.feed_id())?);
+                prices_commitment_members.push(fr_from_biguint::<E>(&signed.feed.price())?);
+            }
+            let current_publish_time = feeds.first().map(|f| f.feed.publish_time()).unwrap_or(0);
+            if current_publish_time < last_publish_time {
+                anyhow::bail!(
+                    "publish time is not increasing: {} <= {}",
+                    current_publish_time,
+                    last_publish_time
+                )
+            }
+            last_publish_time = current_publish_time;
+            prices_commitments.push(poseidon_hash::<E>(&prices_commitment_members));
+        }
+
+        let guardian_set_hash = guardian_set_hash::<E>(&signers)?;
+        let earliest_publish_time = feeds_batch
+            .first()
+            .and_then(|feeds| feeds.first())
+            .map(|f| f.feed.publish_time())
+            .unwrap_or_default();
+        let earliest_publish_time = fr_from_biguint::<E>(&BigUint::from(earliest_publish_time))?;
+
+        let prices_num = E::Fr::from_str(&prices_commitments.len().to_string()).unwrap();
+        let mut prices_commitment_base_sum = E::Fr::zero();
+        let mut prices_commitment = E::Fr::zero();
+        for (i, mut commitment) in prices_commitments.into_iter().enumerate() {
+            Field::add_assign(&mut prices_commitment_base_sum, &commitment);
+            let coef = E::Fr::from_str(&format!("{}", i)).unwrap();
+            Field::mul_assign(&mut commitment, &coef);
+            Field::add_assign(&mut prices_commitment, &commitment);
+        }
+
+        let public_input_data = PublicInputData {
+            guardian_set_hash,
+            prices_summarize: PricesSummarize {
+                commitment: prices_commitment,
+                num: prices_num,
+                commitment_base_sum: prices_commitment_base_sum,
+            },
+            earliest_publish_time,
+        };
+        Ok(Self {
+            commitment: outputs_commitment(&public_input_data),
+            public_input_data,
+            feeds_batch,
+            signers,
+        })
+    }
+}
+
+/// Circuit representation of signed feeds of [`BinancePriceOracle`] and its signers.
+pub struct AllocatedBinancePriceOracle<E: Engine> {
+    pub feeds_batch: Vec<Vec<AllocatedSignedBinanceFeed<E>>>,
+    pub signers: Vec<Address<E>>,
+}
+
+impl<E: Engine, const THRESHOLD: usize, const NUM_PRICES: usize> SignedPriceOracle<E>
+    for BinancePriceOracle<E, THRESHOLD, NUM_PRICES>
+{
+    type Witness = Vec<[SignedBinanceFeed; NUM_PRICES]>;
+    type Circuit = AllocatedBinancePriceOracle<E>;
+
+    fn witness(&self) -> &Self::Witness {
+        &self.feeds_batch
+    }
+
+    fn alloc<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<Self::Circuit, SynthesisError> {
+        let mut feeds_batch = vec![];
+        for feeds in self.feeds_batch.iter() {
+            let feeds = feeds
+                .iter()
+                .map(|f| AllocatedSignedBinanceFeed::from_witness(cs, f))
+                .collect::<Result<Vec<_>, _>>()?;
+            feeds_batch.push(feeds);
+        }
+        let signers = self
+            .signers
+            .iter()
+            .map(|a| Address::from_address_witness(cs, a))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(AllocatedBinancePriceOracle {
+            feeds_batch,
+            signers,
+        })
+    }
+
+    fn verify<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        circuit: &Self::Circuit,
+    ) -> Result<(Boolean, OracleOutputData<E>), SynthesisError> {
+        let mut is_valid = Boolean::constant(true);
+        let mut last_publish_time = Num::zero();
+        let mut earliest_publish_time = Num::zero();
+        let mut prices_commitments = vec![];
+        for (i, feeds) in circuit.feeds_batch.iter().enumerate() {
+            let mut prices_commitment_members = vec![];
+            let mut publish_time = Num::zero();
+            for (j, feed) in feeds.iter().enumerate() {
+                // The threshold is fixed by the shape of circuit, not by the witness
+                if feed.signatures.len() != THRESHOLD {
+                    return Err(new_synthesis_error(format!(
+                        "{} signatures, expect threshold {}",
+                        feed.signatures.len(),
+                        THRESHOLD
+                    )));
+                }
+                let (is_current_valid, feed) = feed.verify(cs, &circuit.signers)?;
+                is_valid = Boolean::and(cs, &is_valid, &is_current_valid)?;
+                prices_commitment_members.push(feed.feed_id);
+                prices_commitment_members.push(feed.price);
+                if j == 0 {
+                    publish_time = feed.publish_time;
+                }
+            }
+            let (is_equal, is_greater) =
+                prepacked_long_comparison(cs, &[publish_time], &[last_publish_time], &[64])?;
+            let is_increasing = Boolean::or(cs, &is_equal, &is_greater)?;
+            is_valid = Boolean::and(cs, &is_valid, &is_increasing)?;
+            last_publish_time = publish_time;
+            if i == 0 {
+                earliest_publish_time = publish_time;
+            }
+            prices_commitments.push(circuit_poseidon_hash(cs, &prices_commitment_members)?);
+        }
+
+        let (prices_commitment, prices_num, prices_commitment_base_sum) =
+            circuit_summarize_prices(cs, &prices_commitments)?;
+        let signers = circuit
+            .signers
+            .iter()
+            .map(|s| s.inner().to_num_unchecked(cs))
+            .collect::<Result<Vec<_>, _>>()?;
+        let guardian_set_hash = circuit_poseidon_hash(cs, &signers)?;
+        Ok((
+            is_valid,
+            OracleOutputData {
+                guardian_set_hash,
+                earliest_publish_time,
+                prices_summarize: OraclePricesSummarize {
+                    commitment: prices_commitment,
+                    num: prices_num,
+                    commitment_base_sum: prices_commitment_base_sum,
+                },
+            },
+        ))
+    }
+
+    fn outputs(&self) -> &PublicInputData<E> {
+        &self.public_input_data
+    }
+}
+
+impl<E: Engine, const THRESHOLD: usize, const NUM_PRICES: usize> Circuit<E>
+    for BinancePriceOracle<E, THRESHOLD, NUM_PRICES>
+{
+    type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
+
+    fn synthesize<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<(), SynthesisError> {
+        oracle::synthesize(cs, self)
+    }
+
+    fn declare_used_gates() -> Result<Vec<Box<dyn GateInternal<E>>>, SynthesisError> {
+        Ok(vec![
+            Self::MainGate::default().into_internal(),
+            Rescue5CustomGate.into_internal(), // Just to standardize the proof format
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::bellman::{pairing::ff::Field, plonk::better_better_cs::cs::Circuit},
+        testing::{create_test_artifacts_with_optimized_gate, Bn256},
+    };
+
+    use super::BinancePriceOracle;
+    use crate::{
+        binance::{BinanceFeed, BinanceSignature, SignedBinanceFeed},
+        oracle::{outputs_commitment, SignedPriceOracle},
+        utils::signer::RedstoneSigner,
+    };
+
+    #[test]
+    fn test_binance_price_oracle() -> anyhow::Result<()> {
+        let keys = [0x71, 0x72, 0x73].map(RedstoneSigner::new);
+        let signers = keys.iter().map(|k| k.address()).collect::<Vec<_>>();
+        let sign = |symbol: &str, timestamp: u64, price: u128, indices: [u8; 2]| {
+            let feed = BinanceFeed {
+                symbol: symbol.to_string(),
+                price,
+                timestamp,
+            };
+            let signatures = indices
+                .iter()
+                .map(|i| BinanceSignature {
+                    signer_index: *i,
+                    signature: keys[*i as usize].sign_hash(&feed.message_hash()),
+                })
+                .collect();
+            SignedBinanceFeed { feed, signatures }
+        };
+        let feeds_batch = vec![
+            [
+                sign("BNB/USD", 1705311690, 31_245_000_000, [0, 1]),
+                sign("BTC/USD", 1705311690, 4_280_000_000_000, [1, 2]),
+            ],
+            [
+                sign("BNB/USD", 1705311691, 31_250_000_000, [0, 2]),
+                sign("BTC/USD", 1705311691, 4_281_000_000_000, [0, 1]),
+            ],
+        ];
+        let circuit = BinancePriceOracle::<Bn256, 2, 2>::new(feeds_batch.clone(), signers.clone())?;
+        assert_eq!(outputs_commitment(circuit.outputs()), circuit.commitment);
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        println!("Roughly {} gates", cs.n());
+
+        let mut tampered = circuit;
+        tampered
+            .public_input_data
+            .earliest_publish_time
+            .add_assign(&Field::one());
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        tampered.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+
+        // Feeds must be signed by the threshold of signers, and batches in order of publish time
+        assert!(
+            BinancePriceOracle::<Bn256, 3, 2>::new(feeds_batch.clone(), signers.clone()).is_err()
+        );
+        assert!(
+            BinancePriceOracle::<Bn256, 2, 2>::new(feeds_batch.clone(), signers[1..].to_vec())
+                .is_err()
+        );
+        let reversed = feeds_batch.into_iter().rev().collect();
+        assert!(BinancePriceOracle::<Bn256, 2, 2>::new(reversed, signers).is_err());
+        Ok(())
+    }
+}
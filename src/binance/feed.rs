@@ -0,0 +1,345 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    glue::prepacked_long_comparison,
+    utils::u64_to_fe,
+    vm::{partitioner::smart_and, primitives::uint256::UInt256},
+};
+use num_bigint::BigUint;
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    decimals::scale_factor,
+    gadgets::{
+        ecdsa::Signature,
+        ethereum::{circuit_eth_signed_message_hash, eth_signed_message_hash, Address},
+        keccak256,
+    },
+    redstone::{witness::recover_signer, SIGNATURE_BS},
+    utils::{fr_from_biguint, new_synthesis_error, num_from_be_bytes},
+};
+
+// Max number of bytes of symbols, which are committed as feed ids of 15 bytes
+pub const MAX_SYMBOL_BS: usize = 15;
+// Precision of prices of Binance Oracle
+pub const BINANCE_PRICE_DECIMALS: usize = 8;
+// Number of bytes of a feed message, i.e. three ABI words
+pub const BINANCE_FEED_MESSAGE_BS: usize = 3 * 32;
+
+/// Price of a symbol reported by Binance Oracle, whose message is `abi.encode(bytes32 symbol,
+/// uint256 price, uint256 timestamp)` with the symbol left-aligned.
+///
+/// Prices carry [`BINANCE_PRICE_DECIMALS`] decimals, and timestamps are in seconds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BinanceFeed {
+    pub symbol: String,
+    pub price: u128,
+    pub timestamp: u64,
+}
+
+impl BinanceFeed {
+    pub fn message(&self) -> Vec<u8> {
+        let mut message = vec![0u8; BINANCE_FEED_MESSAGE_BS];
+        let symbol = self.symbol.as_bytes();
+        message[..symbol.len()].copy_from_slice(symbol);
+        message[48..64].copy_from_slice(&self.price.to_be_bytes());
+        message[88..96].copy_from_slice(&self.timestamp.to_be_bytes());
+        message
+    }
+
+    /// Hash signed by signers, i.e. Ethereum signed message hash of `keccak256(message)`.
+    pub fn message_hash(&self) -> [u8; 32] {
+        eth_signed_message_hash(&Keccak256::digest(self.message()).into())
+    }
+
+    /// Publish time in milliseconds, which is the precision of RedStone timestamps.
+    pub fn publish_time(&self) -> u64 {
+        self.timestamp * 1000
+    }
+
+    /// Price rescaled by [`scale_factor`] to the precision of RedStone prices.
+    pub fn price(&self) -> BigUint {
+        BigUint::from(self.price) * scale_factor(BINANCE_PRICE_DECIMALS)
+    }
+
+    /// Symbol left-aligned in 15 bytes and prefixed with a zero byte, in the same way as RedStone
+    /// feed ids are committed.
+    pub fn feed_id(&self) -> BigUint {
+        let symbol = self.symbol.as_bytes();
+        let mut bytes = [0u8; MAX_SYMBOL_BS + 1];
+        bytes[1..1 + symbol.len()].copy_from_slice(symbol);
+        BigUint::from_bytes_be(&bytes)
+    }
+}
+
+/// Signature of a feed by the signer at `signer_index` of the signer set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BinanceSignature {
+    pub signer_index: u8,
+    pub signature: [u8; SIGNATURE_BS],
+}
+
+/// Feed signed by a threshold of the signer set of Binance Oracle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedBinanceFeed {
+    pub feed: BinanceFeed,
+    pub signatures: Vec<BinanceSignature>,
+}
+
+impl SignedBinanceFeed {
+    /// Check the feed is signed by `threshold` of `signers`. Signer indices must be strictly
+    /// increasing, so that no signer is counted twice.
+    pub fn check(&self, signers: &[[u8; 20]], threshold: usize) -> anyhow::Result<()> {
+        let symbol = &self.feed.symbol;
+        if symbol.is_empty() || symbol.len() > MAX_SYMBOL_BS || symbol.as_bytes().contains(&0) {
+            anyhow::bail!("unsupported symbol {:?}", symbol)
+        }
+        if threshold == 0 || self.signatures.len() != threshold {
+            anyhow::bail!(
+                "price of {} has {} signatures, expect {}",
+                symbol,
+                self.signatures.len(),
+                threshold
+            )
+        }
+        let message_hash = self.feed.message_hash();
+        let mut last_index = None;
+        for signature in self.signatures.iter() {
+            if matches!(last_index, Some(last) if signature.signer_index <= last) {
+                anyhow::bail!("signer indices of {} are not increasing", symbol)
+            }
+            last_index = Some(signature.signer_index);
+            let signer = recover_signer(&message_hash, &signature.signature)?;
+            if signers.get(signature.signer_index as usize) != Some(&signer) {
+                anyhow::bail!(
+                    "price of {} is signed by non-signer 0x{}",
+                    symbol,
+                    hex::encode(signer)
+                )
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Circuit representation of [`SignedBinanceFeed`]. The length of the symbol and the number of
+/// signatures are fixed by the witness.
+#[derive(Debug, Clone)]
+pub struct AllocatedSignedBinanceFeed<E: Engine> {
+    pub symbol: Vec<Byte<E>>,
+    pub price: [Byte<E>; 16],
+    pub timestamp: [Byte<E>; 8],
+    pub signer_indices: Vec<Byte<E>>,
+    pub signatures: Vec<Signature<E>>,
+}
+
+/// Feed verified by [`AllocatedSignedBinanceFeed::verify`], in the shape of RedStone prices.
+#[derive(Debug, Clone, Copy)]
+pub struct VerifiedBinanceFeed<E: Engine> {
+    pub feed_id: Num<E>,
+    /// Price in [`TARGET_NUM_VALUE_DECIMALS`](crate::redstone::TARGET_NUM_VALUE_DECIMALS)
+    pub price: Num<E>,
+    /// Publish time in milliseconds
+    pub publish_time: Num<E>,
+}
+
+impl<E: Engine> AllocatedSignedBinanceFeed<E> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &SignedBinanceFeed,
+    ) -> Result<Self, SynthesisError> {
+        let symbol = &witness.feed.symbol;
+        if symbol.is_empty() || symbol.len() > MAX_SYMBOL_BS {
+            return Err(new_synthesis_error(format!(
+                "unsupported symbol {:?}",
+                symbol
+            )));
+        }
+        let mut alloc_bytes = |bytes: &[u8]| {
+            bytes
+                .iter()
+                .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let symbol = alloc_bytes(symbol.as_bytes())?;
+        let price = alloc_bytes(&witness.feed.price.to_be_bytes())?
+            .try_into()
+            .unwrap();
+        let timestamp = alloc_bytes(&witness.feed.timestamp.to_be_bytes())?
+            .try_into()
+            .unwrap();
+        let signer_indices = alloc_bytes(
+            &witness
+                .signatures
+                .iter()
+                .map(|s| s.signer_index)
+                .collect::<Vec<_>>(),
+        )?;
+        let signatures = witness
+            .signatures
+            .iter()
+            .map(|s| {
+                let mut signature = s.signature;
+                if signature[64] >= 27 {
+                    signature[64] -= 27;
+                }
+                Signature::from_bytes_witness(cs, &signature)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            symbol,
+            price,
+            timestamp,
+            signer_indices,
+            signatures,
+        })
+    }
+
+    /// Same as [`BinanceFeed::message_hash`].
+    pub fn message_hash<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<[Byte<E>; 32], SynthesisError> {
+        let mut bytes = self.symbol.clone();
+        bytes.resize(48, Byte::zero());
+        bytes.extend(self.price);
+        bytes.extend([Byte::zero(); 24]);
+        bytes.extend(self.timestamp);
+        let hash = keccak256::digest(cs, &bytes)?;
+        circuit_eth_signed_message_hash(cs, &hash)
+    }
+
+    /// Same as [`SignedBinanceFeed::check`]: check the feed is signed by as many of `signers` as
+    /// its signatures, and its symbol has no zero byte. Return the verified feed, which is only
+    /// meaningful if the check passes.
+    pub fn verify<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        signers: &[Address<E>],
+    ) -> Result<(Boolean, VerifiedBinanceFeed<E>), SynthesisError> {
+        let mut is_valid = vec![];
+        let message_hash = self.message_hash(cs)?;
+        let message_hash = UInt256::from_be_bytes_fixed(cs, &message_hash)?;
+        let mut last_index: Option<Num<E>> = None;
+        for (index, signature) in self.signer_indices.iter().zip(self.signatures.iter()) {
+            if let Some(last_index) = last_index {
+                let (_, is_increasing) =
+                    prepacked_long_comparison(cs, &[index.inner], &[last_index], &[8])?;
+                is_valid.push(is_increasing);
+            }
+            last_index = Some(index.inner);
+
+            // Select the signer at index, which must be in range
+            let mut is_in_range = Boolean::constant(false);
+            let mut signer = UInt256::zero();
+            for (i, candidate) in signers.iter().enumerate() {
+                let is_selected =
+                    Num::equals(cs, &index.inner, &Num::Constant(u64_to_fe(i as u64)))?;
+                signer =
+                    UInt256::conditionally_select(cs, &is_selected, &candidate.inner(), &signer)?;
+                is_in_range = Boolean::or(cs, &is_in_range, &is_selected)?;
+            }
+            is_valid.push(is_in_range);
+
+            let (successful, (x, y)) = signature.ecrecover(cs, &message_hash)?;
+            let (x, y) = (
+                x.into_be_bytes(cs)?.try_into().unwrap(),
+                y.into_be_bytes(cs)?.try_into().unwrap(),
+            );
+            let address = Address::from_pubkey(cs, &x, &y)?;
+            is_valid.push(Address::new(signer).equals(cs, &address)?);
+            is_valid.push(successful);
+        }
+        for byte in self.symbol.iter() {
+            is_valid.push(byte.inner.equals(cs, &Num::zero())?.not());
+        }
+
+        let mut feed_id = self.symbol.clone();
+        feed_id.resize(MAX_SYMBOL_BS, Byte::zero());
+        // Prices below 2^128 never overflow the field when rescaled
+        let scale = Num::Constant(fr_from_biguint::<E>(&scale_factor(BINANCE_PRICE_DECIMALS))?);
+        let price = num_from_be_bytes(cs, &self.price)?.mul(cs, &scale)?;
+        let timestamp = num_from_be_bytes(cs, &self.timestamp)?;
+        let feed = VerifiedBinanceFeed {
+            feed_id: num_from_be_bytes(cs, &feed_id)?,
+            price,
+            publish_time: timestamp.mul(cs, &Num::Constant(u64_to_fe(1000)))?,
+        };
+        Ok((smart_and(cs, &is_valid)?, feed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::franklin_crypto::bellman::{
+        pairing::bn256::Bn256, SynthesisError,
+    };
+    use num_bigint::BigUint;
+
+    use super::{AllocatedSignedBinanceFeed, BinanceFeed, BinanceSignature, SignedBinanceFeed};
+    use crate::{
+        gadgets::ethereum::Address,
+        utils::{fr_from_biguint, signer::RedstoneSigner, testing::create_test_constraint_system},
+    };
+
+    #[test]
+    fn test_signed_binance_feed() -> Result<(), SynthesisError> {
+        let keys = [0x71, 0x72, 0x73].map(RedstoneSigner::new);
+        let signers = keys.iter().map(|k| k.address()).collect::<Vec<_>>();
+        let feed = BinanceFeed {
+            symbol: "BNB/USD".to_string(),
+            price: 31_245_000_000,
+            timestamp: 1_700_000_000,
+        };
+        let sign = |indices: &[u8]| SignedBinanceFeed {
+            feed: feed.clone(),
+            signatures: indices
+                .iter()
+                .map(|i| BinanceSignature {
+                    signer_index: *i,
+                    signature: keys[*i as usize].sign_hash(&feed.message_hash()),
+                })
+                .collect(),
+        };
+        let signed = sign(&[0, 2]);
+        assert_eq!(signed.feed.message().len(), 96);
+        assert_eq!(
+            signed.feed.price(),
+            BigUint::from(312_450_000_000_000_000_000u128)
+        );
+        signed.check(&signers, 2).unwrap();
+        assert!(signed.check(&signers, 3).is_err());
+        assert!(signed.check(&signers[..2], 2).is_err());
+        // Signers are counted once
+        let duplicated = sign(&[2, 2]);
+        assert!(duplicated.check(&signers, 2).is_err());
+        let mut misplaced = signed.clone();
+        misplaced.signatures[1].signer_index = 1;
+        assert!(misplaced.check(&signers, 2).is_err());
+
+        for (signed, expected) in [(signed, true), (duplicated, false), (misplaced, false)] {
+            let cs = &mut create_test_constraint_system()?;
+            let signers = signers
+                .iter()
+                .map(|s| Address::from_address_witness(cs, s))
+                .collect::<Result<Vec<_>, _>>()?;
+            let allocated = AllocatedSignedBinanceFeed::<Bn256>::from_witness(cs, &signed)?;
+            let (is_valid, verified) = allocated.verify(cs, &signers)?;
+            assert_eq!(is_valid.get_value(), Some(expected));
+            assert_eq!(
+                verified.feed_id.get_value(),
+                Some(fr_from_biguint::<Bn256>(&signed.feed.feed_id())?)
+            );
+            assert_eq!(
+                verified.price.get_value(),
+                Some(fr_from_biguint::<Bn256>(&signed.feed.price())?)
+            );
+            assert!(cs.is_satisfied());
+        }
+        Ok(())
+    }
+}
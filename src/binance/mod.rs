@@ -0,0 +1,5 @@
+mod circuit;
+mod feed;
+
+pub use circuit::*;
+pub use feed::*;
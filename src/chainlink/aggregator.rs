@@ -0,0 +1,417 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{ff::Field, Engine};
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{
+            plonk::better_better_cs::{
+                cs::{Circuit, ConstraintSystem, Gate, GateInternal},
+                gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
+            },
+            SynthesisError,
+        },
+        plonk::circuit::{
+            allocated_num::Num, boolean::Boolean, custom_rescue_gate::Rescue5CustomGate,
+        },
+    },
+    utils::u64_to_fe,
+    vm::partitioner::smart_and,
+};
+use num_bigint::{BigInt, BigUint};
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    gadgets::{
+        keccak256,
+        mpt::{circuit_storage_value, storage_value, verify_proof, AllocatedMptProof},
+        poseidon::{circuit_poseidon_hash, poseidon_hash},
+        rescue::circuit_rescue_hash,
+        storage::{AllocatedContractStorage, ContractStorage},
+    },
+    utils::{self, fr_from_biguint, num_from_be_bytes},
+};
+
+use super::report::{circuit_int192, enforce_after_previous, fr_from_bigint, int_word};
+
+// Offset of `latestAggregatorRoundId` in the slot of `s_hotVars`, which packs `(bytes16
+// latestConfigDigest, uint40 latestEpochAndRound, uint8 threshold, uint32 latestAggregatorRoundId)`
+// from the low-order bytes
+const HOT_VARS_ROUND_ID_OFFSET: usize = 6;
+
+/// Slots of `s_hotVars` and the `s_transmissions` mapping in the storage of a Chainlink OCR2
+/// aggregator, which depend on the deployed version of the contract.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AggregatorSlots {
+    pub hot_vars: u64,
+    pub transmissions: u64,
+}
+
+/// Transmission of a Chainlink OCR2 aggregator, packed in one slot as `transmissionTimestamp ||
+/// observationsTimestamp || answer` in big-endian, where `answer` is `int192`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transmission {
+    pub answer: BigInt,
+    pub observations_timestamp: u32,
+    pub transmission_timestamp: u32,
+}
+
+impl Transmission {
+    pub fn to_slot(&self) -> [u8; 32] {
+        let mut slot = int_word(&self.answer);
+        slot[..4].copy_from_slice(&self.transmission_timestamp.to_be_bytes());
+        slot[4..8].copy_from_slice(&self.observations_timestamp.to_be_bytes());
+        slot
+    }
+
+    pub fn from_slot(slot: &[u8; 32]) -> Self {
+        Self {
+            answer: BigInt::from_signed_bytes_be(&slot[8..]),
+            observations_timestamp: u32::from_be_bytes(slot[4..8].try_into().unwrap()),
+            transmission_timestamp: u32::from_be_bytes(slot[..4].try_into().unwrap()),
+        }
+    }
+}
+
+/// Latest transmission of a Chainlink OCR2 aggregator, i.e.
+/// `s_transmissions[s_hotVars.latestAggregatorRoundId]` read by `latestAnswer` and
+/// `latestTimestamp`, proven by storage proofs of `s_hotVars` and the transmission.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AggregatorAnswer {
+    pub hot_vars: [u8; 32],
+    pub transmission: Transmission,
+    pub hot_vars_proof: Vec<Vec<u8>>,
+    pub transmission_proof: Vec<Vec<u8>>,
+}
+
+impl AggregatorAnswer {
+    /// `latestAggregatorRoundId` in `s_hotVars`.
+    pub fn round_id(&self) -> u32 {
+        let offset = HOT_VARS_ROUND_ID_OFFSET;
+        u32::from_be_bytes(self.hot_vars[offset..offset + 4].try_into().unwrap())
+    }
+
+    /// Keys of `s_hotVars` and `s_transmissions[roundId]` in the storage trie.
+    pub fn storage_keys(&self, slots: &AggregatorSlots) -> ([u8; 32], [u8; 32]) {
+        let mut hot_vars_slot = [0u8; 32];
+        hot_vars_slot[24..].copy_from_slice(&slots.hot_vars.to_be_bytes());
+        let mut round_id_and_slot = [0u8; 64];
+        round_id_and_slot[28..32].copy_from_slice(&self.round_id().to_be_bytes());
+        round_id_and_slot[56..].copy_from_slice(&slots.transmissions.to_be_bytes());
+        let transmission_slot = Keccak256::digest(round_id_and_slot);
+        (
+            Keccak256::digest(hot_vars_slot).into(),
+            Keccak256::digest(transmission_slot).into(),
+        )
+    }
+
+    /// Check `s_hotVars` and the transmission of its latest round are proven against
+    /// `storage_root`.
+    pub fn check(&self, storage_root: &[u8; 32], slots: &AggregatorSlots) -> anyhow::Result<()> {
+        let (hot_vars_key, transmission_key) = self.storage_keys(slots);
+        let hot_vars = storage_value(&verify_proof(
+            storage_root,
+            &hot_vars_key,
+            &self.hot_vars_proof,
+        )?)?;
+        if hot_vars != self.hot_vars {
+            anyhow::bail!(
+                "hot vars are 0x{}, expect 0x{}",
+                hex::encode(hot_vars),
+                hex::encode(self.hot_vars)
+            )
+        }
+        let transmission = storage_value(&verify_proof(
+            storage_root,
+            &transmission_key,
+            &self.transmission_proof,
+        )?)?;
+        if Transmission::from_slot(&transmission) != self.transmission {
+            anyhow::bail!(
+                "transmission of round {} is 0x{}, expect {:?}",
+                self.round_id(),
+                hex::encode(transmission),
+                self.transmission
+            )
+        }
+        Ok(())
+    }
+}
+
+/// Circuit representation of [`AggregatorAnswer`].
+#[derive(Debug, Clone)]
+pub struct AllocatedAggregatorAnswer<E: Engine> {
+    pub hot_vars_proof: AllocatedMptProof<E>,
+    pub transmission_proof: AllocatedMptProof<E>,
+}
+
+/// Latest answer verified by [`AllocatedAggregatorAnswer::verify`]. The answer is `int192`, where
+/// negative values are encoded as `r - |value|`.
+#[derive(Debug, Clone, Copy)]
+pub struct VerifiedAggregatorAnswer<E: Engine> {
+    pub round_id: Num<E>,
+    pub answer: Num<E>,
+    pub observations_timestamp: Num<E>,
+    pub transmission_timestamp: Num<E>,
+}
+
+impl<E: Engine> AllocatedAggregatorAnswer<E> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &AggregatorAnswer,
+        slots: &AggregatorSlots,
+    ) -> Result<Self, SynthesisError> {
+        let (hot_vars_key, transmission_key) = witness.storage_keys(slots);
+        Ok(Self {
+            hot_vars_proof: AllocatedMptProof::from_witness(
+                cs,
+                &hot_vars_key,
+                &witness.hot_vars_proof,
+            )?,
+            transmission_proof: AllocatedMptProof::from_witness(
+                cs,
+                &transmission_key,
+                &witness.transmission_proof,
+            )?,
+        })
+    }
+
+    /// Same as [`AggregatorAnswer::check`], where slots are in 8 big-endian bytes. Return the
+    /// verified answer, which is only meaningful if the check passes.
+    pub fn verify<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        storage_root: &[Byte<E>; 32],
+        hot_vars_slot: &[Byte<E>; 8],
+        transmissions_slot: &[Byte<E>; 8],
+    ) -> Result<(Boolean, VerifiedAggregatorAnswer<E>), SynthesisError> {
+        let mut bytes = vec![Byte::zero(); 24];
+        bytes.extend(hot_vars_slot);
+        let hot_vars_key = keccak256::digest(cs, &bytes)?;
+        let (is_hot_vars_proven, hot_vars) =
+            self.hot_vars_proof
+                .verify(cs, storage_root, &hot_vars_key)?;
+        let (is_hot_vars_valid, hot_vars) = circuit_storage_value(cs, &hot_vars)?;
+        let round_id = &hot_vars[HOT_VARS_ROUND_ID_OFFSET..HOT_VARS_ROUND_ID_OFFSET + 4];
+
+        let mut bytes = vec![Byte::zero(); 28];
+        bytes.extend(round_id);
+        bytes.extend([Byte::zero(); 24]);
+        bytes.extend(transmissions_slot);
+        let transmission_slot = keccak256::digest(cs, &bytes)?;
+        let transmission_key = keccak256::digest(cs, &transmission_slot)?;
+        let (is_transmission_proven, transmission) =
+            self.transmission_proof
+                .verify(cs, storage_root, &transmission_key)?;
+        let (is_transmission_valid, transmission) = circuit_storage_value(cs, &transmission)?;
+        let (_, answer, _) = circuit_int192(cs, &transmission[8..])?;
+
+        let answer = VerifiedAggregatorAnswer {
+            round_id: num_from_be_bytes(cs, round_id)?,
+            answer,
+            observations_timestamp: num_from_be_bytes(cs, &transmission[4..8])?,
+            transmission_timestamp: num_from_be_bytes(cs, &transmission[..4])?,
+        };
+        let is_valid = smart_and(
+            cs,
+            &[
+                is_hot_vars_proven,
+                is_hot_vars_valid,
+                is_transmission_proven,
+                is_transmission_valid,
+            ],
+        )?;
+        Ok((is_valid, answer))
+    }
+}
+
+/// Circuit proving `latestAnswer` and `latestTimestamp` of a Chainlink OCR2 aggregator by Ethereum
+/// storage proofs against a block, where `slots` locate `s_hotVars` and `s_transmissions` in its
+/// storage.
+///
+/// Public input is the commitment `poseidon(blockHash[..16], blockHash[16..], aggregator, hot vars
+/// slot, transmissions slot, round id, answer, transmission timestamp)`, where a negative answer is
+/// encoded as `r - |answer|`. Consumers must check the committed block hash is canonical, e.g. by
+/// `blockhash`.
+///
+/// If `previous_round_id` is set, it follows as a public input, and the round id must be strictly
+/// greater than it, so that answers proven in sequence never go backwards.
+#[derive(Debug, Clone)]
+pub struct AggregatorPriceOracle<E: Engine> {
+    pub storage: ContractStorage,
+    pub slots: AggregatorSlots,
+    pub answer: AggregatorAnswer,
+    pub commitment: E::Fr,
+    pub previous_round_id: Option<u32>,
+}
+
+impl<E: Engine> AggregatorPriceOracle<E> {
+    pub fn new(
+        storage: ContractStorage,
+        slots: AggregatorSlots,
+        answer: AggregatorAnswer,
+    ) -> Result<Self, anyhow::Error> {
+        answer.check(&storage.storage_root()?, &slots)?;
+        let block_hash = storage.block_hash();
+        let commitment = poseidon_hash::<E>(&[
+            fr_from_biguint::<E>(&BigUint::from_bytes_be(&block_hash[..16]))?,
+            fr_from_biguint::<E>(&BigUint::from_bytes_be(&block_hash[16..]))?,
+            fr_from_biguint::<E>(&BigUint::from_bytes_be(&storage.address))?,
+            fr_from_biguint::<E>(&BigUint::from(slots.hot_vars))?,
+            fr_from_biguint::<E>(&BigUint::from(slots.transmissions))?,
+            fr_from_biguint::<E>(&BigUint::from(answer.round_id()))?,
+            fr_from_bigint::<E>(&answer.transmission.answer)?,
+            fr_from_biguint::<E>(&BigUint::from(answer.transmission.transmission_timestamp))?,
+        ]);
+        Ok(Self {
+            storage,
+            slots,
+            answer,
+            commitment,
+            previous_round_id: None,
+        })
+    }
+
+    /// Require the round id of the answer to be strictly greater than `previous`, e.g. the one of
+    /// the last proven answer.
+    pub fn with_previous_round_id(mut self, previous: u32) -> Result<Self, anyhow::Error> {
+        if self.answer.round_id() <= previous {
+            anyhow::bail!(
+                "round {} is not after previous round {}",
+                self.answer.round_id(),
+                previous
+            )
+        }
+        self.previous_round_id = Some(previous);
+        Ok(self)
+    }
+
+    /// Public inputs of the circuit, which are the commitment followed by the previous round id if
+    /// set.
+    pub fn public_inputs(&self) -> Vec<E::Fr> {
+        let mut inputs = vec![self.commitment];
+        if let Some(previous) = self.previous_round_id {
+            inputs.push(u64_to_fe(previous as u64));
+        }
+        inputs
+    }
+}
+
+impl<E: Engine> Circuit<E> for AggregatorPriceOracle<E> {
+    type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
+
+    fn synthesize<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<(), SynthesisError> {
+        utils::add_bitwise_logic_and_range_table(cs)?;
+        let temp_variable = Num::alloc(cs, Some(E::Fr::one()))?;
+        circuit_rescue_hash(cs, &[temp_variable])?; // Just to standardize the proof format
+
+        let storage = AllocatedContractStorage::from_witness(cs, &self.storage)?;
+        let (is_storage_valid, block_hash, storage_root) = storage.verify(cs)?;
+        let mut slots = [[Byte::zero(); 8]; 2];
+        for (bytes, slot) in slots
+            .iter_mut()
+            .zip([self.slots.hot_vars, self.slots.transmissions])
+        {
+            for (byte, b) in bytes.iter_mut().zip(slot.to_be_bytes()) {
+                *byte = Byte::from_u8_witness(cs, Some(b))?;
+            }
+        }
+        let answer = AllocatedAggregatorAnswer::from_witness(cs, &self.answer, &self.slots)?;
+        let (is_answer_valid, answer) = answer.verify(cs, &storage_root, &slots[0], &slots[1])?;
+        let is_valid = Boolean::and(cs, &is_storage_valid, &is_answer_valid)?;
+        Boolean::enforce_equal(cs, &is_valid, &Boolean::constant(true))?;
+
+        let input = [
+            num_from_be_bytes(cs, &block_hash[..16])?,
+            num_from_be_bytes(cs, &block_hash[16..])?,
+            num_from_be_bytes(cs, &storage.address)?,
+            num_from_be_bytes(cs, &slots[0])?,
+            num_from_be_bytes(cs, &slots[1])?,
+            answer.round_id,
+            answer.answer,
+            answer.transmission_timestamp,
+        ];
+        let commitment = circuit_poseidon_hash(cs, &input)?;
+        let expected_commitment = Num::alloc(cs, Some(self.commitment))?;
+        expected_commitment.enforce_equal(cs, &commitment)?;
+        expected_commitment.get_variable().inputize(cs)?;
+        if let Some(previous) = self.previous_round_id {
+            enforce_after_previous(cs, &answer.round_id, previous as u64, 32)?;
+        }
+        Ok(())
+    }
+
+    fn declare_used_gates() -> Result<Vec<Box<dyn GateInternal<E>>>, SynthesisError> {
+        Ok(vec![
+            Self::MainGate::default().into_internal(),
+            Rescue5CustomGate.into_internal(), // Just to standardize the proof format
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::bellman::{pairing::ff::Field, plonk::better_better_cs::cs::Circuit},
+        testing::{create_test_artifacts_with_optimized_gate, Bn256},
+    };
+    use num_bigint::BigInt;
+
+    use super::{AggregatorPriceOracle, AggregatorSlots, Transmission};
+    use crate::utils::testing::chainlink_aggregator_storage;
+
+    #[test]
+    fn test_aggregator_price_oracle() -> anyhow::Result<()> {
+        let slots = AggregatorSlots {
+            hot_vars: 43,
+            transmissions: 44,
+        };
+        let transmission = Transmission {
+            answer: BigInt::from(-253_012_000_000i64),
+            observations_timestamp: 1_700_000_000,
+            transmission_timestamp: 1_700_000_012,
+        };
+        assert_eq!(
+            Transmission::from_slot(&transmission.to_slot()),
+            transmission
+        );
+        let (storage, answer) = chainlink_aggregator_storage(&slots, 7_429, transmission);
+        assert_eq!(answer.round_id(), 7_429);
+        let circuit = AggregatorPriceOracle::<Bn256>::new(storage.clone(), slots, answer.clone())?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        println!("Roughly {} gates", cs.n());
+
+        // Answers never go backwards once proofs are chained
+        let chained = circuit.clone().with_previous_round_id(7_428)?;
+        assert_eq!(chained.public_inputs().len(), 2);
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        chained.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        assert!(circuit.clone().with_previous_round_id(7_429).is_err());
+
+        let tampers: [fn(&mut AggregatorPriceOracle<Bn256>); 2] = [
+            |c| c.commitment.add_assign(&Field::one()),
+            |c| c.previous_round_id = Some(7_429),
+        ];
+        for tamper in tampers {
+            let mut tampered = circuit.clone();
+            tamper(&mut tampered);
+            let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+            tampered.synthesize(&mut cs)?;
+            assert!(!cs.is_satisfied());
+        }
+
+        // Answers are proven at the slots of the aggregator
+        let moved = AggregatorSlots {
+            transmissions: 45,
+            ..slots
+        };
+        assert!(
+            AggregatorPriceOracle::<Bn256>::new(storage.clone(), moved, answer.clone()).is_err()
+        );
+        let mut forged = answer;
+        forged.transmission.answer += 1;
+        assert!(AggregatorPriceOracle::<Bn256>::new(storage, slots, forged).is_err());
+        Ok(())
+    }
+}
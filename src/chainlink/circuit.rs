@@ -0,0 +1,259 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{ff::Field, Engine};
+use advanced_circuit_component::franklin_crypto::{
+    bellman::{
+        plonk::better_better_cs::{
+            cs::{Circuit, ConstraintSystem, Gate, GateInternal},
+            gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
+        },
+        SynthesisError,
+    },
+    plonk::circuit::{allocated_num::Num, boolean::Boolean, custom_rescue_gate::Rescue5CustomGate},
+};
+use num_bigint::{BigInt, BigUint};
+
+use crate::{
+    gadgets::{
+        ethereum::Address,
+        poseidon::{circuit_poseidon_hash, poseidon_hash},
+        rescue::circuit_rescue_hash,
+    },
+    redstone::witness::guardian_set_hash,
+    utils::{self, fr_from_biguint, num_from_be_bytes},
+};
+
+use super::report::{
+    config_digest_inputs, enforce_after_previous, enforce_config_digest, fr_from_bigint,
+    AllocatedSignedReport, SignedReport,
+};
+
+// Number of bits of epoch and round, which are packed as `uint40`
+const EPOCH_AND_ROUND_BITS: usize = 40;
+
+/// Circuit proving the median observation of a Chainlink OCR2 report signed by `f + 1` oracles of
+/// an aggregator, whose signing addresses are `signers`.
+///
+/// Public inputs are the commitment `poseidon(signer set hash, config digest[..16], config
+/// digest[16..], epoch and round, observations timestamp, median)`, where the signer set hash is
+/// computed in the same way as guardian set hash and a negative median is encoded as
+/// `r - |median|`, followed by `config_digest` of the aggregator, to which the report is bound.
+///
+/// If `previous_epoch_and_round` is set, it follows as a public input, and the epoch and round of
+/// the report must be strictly greater than it, so that consumers chaining proofs never accept an
+/// older report.
+#[derive(Debug, Clone)]
+pub struct Ocr2PriceOracle<E: Engine> {
+    pub signed_report: SignedReport,
+    pub signers: Vec<[u8; 20]>,
+    pub f: usize,
+    pub config_digest: [u8; 32],
+    pub median: BigInt,
+    pub commitment: E::Fr,
+    pub previous_epoch_and_round: Option<u64>,
+}
+
+impl<E: Engine> Ocr2PriceOracle<E> {
+    pub fn new(
+        signed_report: SignedReport,
+        signers: Vec<[u8; 20]>,
+        f: usize,
+        config_digest: [u8; 32],
+    ) -> Result<Self, anyhow::Error> {
+        if signed_report.context.config_digest != config_digest {
+            anyhow::bail!(
+                "report is signed under config digest 0x{}, expect 0x{}",
+                hex::encode(signed_report.context.config_digest),
+                hex::encode(config_digest)
+            )
+        }
+        let report = signed_report.check(&signers, f)?;
+        let median = report
+            .median()
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("report has no observation"))?;
+        let context = &signed_report.context;
+        let commitment = poseidon_hash::<E>(&[
+            guardian_set_hash::<E>(&signers)?,
+            fr_from_biguint::<E>(&BigUint::from_bytes_be(&context.config_digest[..16]))?,
+            fr_from_biguint::<E>(&BigUint::from_bytes_be(&context.config_digest[16..]))?,
+            fr_from_biguint::<E>(&BigUint::from(context.epoch_and_round()))?,
+            fr_from_biguint::<E>(&BigUint::from(report.observations_timestamp))?,
+            fr_from_bigint::<E>(&median)?,
+        ]);
+        Ok(Self {
+            signed_report,
+            signers,
+            f,
+            config_digest,
+            median,
+            commitment,
+            previous_epoch_and_round: None,
+        })
+    }
+
+    /// Require the epoch and round of the report to be strictly greater than `previous`, e.g. the
+    /// one of the last proven report.
+    pub fn with_previous_epoch_and_round(mut self, previous: u64) -> Result<Self, anyhow::Error> {
+        let epoch_and_round = self.signed_report.context.epoch_and_round();
+        if epoch_and_round <= previous {
+            anyhow::bail!(
+                "epoch and round {} is not after previous {}",
+                epoch_and_round,
+                previous
+            )
+        }
+        self.previous_epoch_and_round = Some(previous);
+        Ok(self)
+    }
+
+    /// Public inputs of the circuit, which are the commitment followed by the config digest split
+    /// by [`config_digest_inputs`] and the previous epoch and round if set.
+    pub fn public_inputs(&self) -> anyhow::Result<Vec<E::Fr>> {
+        let mut inputs = vec![self.commitment];
+        inputs.extend(config_digest_inputs::<E>(&self.config_digest)?);
+        if let Some(previous) = self.previous_epoch_and_round {
+            inputs.push(fr_from_biguint::<E>(&BigUint::from(previous))?);
+        }
+        Ok(inputs)
+    }
+}
+
+impl<E: Engine> Circuit<E> for Ocr2PriceOracle<E> {
+    type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
+
+    fn synthesize<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<(), SynthesisError> {
+        utils::add_bitwise_logic_and_range_table(cs)?;
+        let temp_variable = Num::alloc(cs, Some(E::Fr::one()))?;
+        circuit_rescue_hash(cs, &[temp_variable])?; // Just to standardize the proof format
+
+        let signers = self
+            .signers
+            .iter()
+            .map(|s| Address::from_address_witness(cs, s))
+            .collect::<Result<Vec<_>, _>>()?;
+        let signed_report = AllocatedSignedReport::from_witness(cs, &self.signed_report)?;
+        let (is_valid, report) = signed_report.verify(cs, &signers, self.f)?;
+        Boolean::enforce_equal(cs, &is_valid, &Boolean::constant(true))?;
+
+        let signer_nums = signers
+            .iter()
+            .map(|s| s.inner().to_num_unchecked(cs))
+            .collect::<Result<Vec<_>, _>>()?;
+        let input = [
+            circuit_poseidon_hash(cs, &signer_nums)?,
+            num_from_be_bytes(cs, &report.config_digest[..16])?,
+            num_from_be_bytes(cs, &report.config_digest[16..])?,
+            report.epoch_and_round,
+            report.observations_timestamp,
+            report.median,
+        ];
+        let commitment = circuit_poseidon_hash(cs, &input)?;
+        let expected_commitment = Num::alloc(cs, Some(self.commitment))?;
+        expected_commitment.enforce_equal(cs, &commitment)?;
+        expected_commitment.get_variable().inputize(cs)?;
+        enforce_config_digest(cs, &report.config_digest, &self.config_digest)?;
+        if let Some(previous) = self.previous_epoch_and_round {
+            enforce_after_previous(cs, &report.epoch_and_round, previous, EPOCH_AND_ROUND_BITS)?;
+        }
+        Ok(())
+    }
+
+    fn declare_used_gates() -> Result<Vec<Box<dyn GateInternal<E>>>, SynthesisError> {
+        Ok(vec![
+            Self::MainGate::default().into_internal(),
+            Rescue5CustomGate.into_internal(), // Just to standardize the proof format
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::bellman::{pairing::ff::Field, plonk::better_better_cs::cs::Circuit},
+        testing::{create_test_artifacts_with_optimized_gate, Bn256},
+    };
+    use num_bigint::BigInt;
+
+    use super::Ocr2PriceOracle;
+    use crate::{
+        chainlink::{report_digest, Ocr2Report, ReportContext, SignedReport},
+        utils::signer::RedstoneSigner,
+    };
+
+    #[test]
+    fn test_ocr2_price_oracle() -> anyhow::Result<()> {
+        let oracles = RedstoneSigner::generate(7);
+        let signers = oracles.iter().map(|o| o.address()).collect::<Vec<_>>();
+        let report = Ocr2Report {
+            observations_timestamp: 1_700_000_000,
+            raw_observers: [0u8; 32],
+            observations: [364_100, 364_150, 364_200, 364_210, 364_300]
+                .map(BigInt::from)
+                .to_vec(),
+            juels_per_fee_coin: BigInt::from(0),
+        }
+        .to_bytes();
+        let context = ReportContext {
+            config_digest: [9u8; 32],
+            epoch: 42,
+            round: 1,
+            extra_hash: [0u8; 32],
+        };
+        let digest = report_digest(&report, &context);
+        let signed_report = SignedReport {
+            report,
+            context,
+            signatures: [1u8, 4, 6]
+                .map(|i| (i, oracles[i as usize].sign_hash(&digest)))
+                .to_vec(),
+        };
+
+        let circuit =
+            Ocr2PriceOracle::<Bn256>::new(signed_report.clone(), signers.clone(), 2, [9u8; 32])?;
+        assert_eq!(circuit.median, BigInt::from(364_200));
+        assert_eq!(circuit.public_inputs()?.len(), 3);
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        println!("Roughly {} gates", cs.n());
+
+        // Reports never go backwards once proofs are chained
+        let chained = circuit.clone().with_previous_epoch_and_round(42 << 8)?;
+        assert_eq!(chained.public_inputs()?.len(), 4);
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        chained.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        assert!(circuit
+            .clone()
+            .with_previous_epoch_and_round((42 << 8) | 1)
+            .is_err());
+        let mut replayed = chained;
+        replayed.previous_epoch_and_round = Some((42 << 8) | 1);
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        replayed.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+
+        let mut tampered = circuit.clone();
+        tampered.commitment.add_assign(&Field::one());
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        tampered.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+
+        // The report is not replayed into the instance of another config
+        let mut replayed = circuit;
+        replayed.config_digest = [8u8; 32];
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        replayed.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+        assert!(Ocr2PriceOracle::<Bn256>::new(
+            signed_report.clone(),
+            signers.clone(),
+            2,
+            [8u8; 32]
+        )
+        .is_err());
+
+        // f + 1 signatures are required
+        assert!(Ocr2PriceOracle::<Bn256>::new(signed_report, signers, 1, [9u8; 32]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,9 @@
+mod aggregator;
+mod circuit;
+mod report;
+mod streams;
+
+pub use aggregator::*;
+pub use circuit::*;
+pub use report::*;
+pub use streams::*;
@@ -0,0 +1,619 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{ff::Field, Engine};
+use advanced_circuit_component::{
+    circuit_structures::byte::{Byte, IntoBytes as _},
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    glue::prepacked_long_comparison,
+    utils::u64_to_fe,
+    vm::{partitioner::smart_and, primitives::uint256::UInt256},
+};
+use num_bigint::{BigInt, BigUint, Sign};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    gadgets::{ecdsa::Signature, ethereum::Address, keccak256},
+    redstone::witness::recover_signer,
+    utils::{fr_from_biguint, new_synthesis_error, num_from_be_bytes},
+};
+
+// Number of bytes of an ABI word
+pub const WORD_BS: usize = 32;
+// Number of head words of an ABI-encoded report, i.e. observations timestamp, raw observers,
+// offset of observations and juels per fee coin
+pub const REPORT_HEAD_WORDS: usize = 4;
+// Number of bytes of the report context, i.e. config digest, epoch and round, and extra hash
+pub const REPORT_CONTEXT_BS: usize = 3 * WORD_BS;
+// Number of bits of observations, which are `int192`
+pub const OBSERVATION_BITS: usize = 192;
+// Number of leading bytes of an observation word that sign-extend `int192`
+const OBSERVATION_SIGN_EXTENSION_BS: usize = WORD_BS - OBSERVATION_BITS / 8;
+
+/// Number of bytes of an ABI-encoded report with `num_observations` observations.
+pub fn report_len(num_observations: usize) -> usize {
+    (REPORT_HEAD_WORDS + 1 + num_observations) * WORD_BS
+}
+
+/// Median report of Chainlink OCR2, which is ABI-encoded as `(uint32 observationsTimestamp,
+/// bytes32 rawObservers, int192[] observations, int192 juelsPerFeeCoin)`. Observations are sorted
+/// by the transmitting oracle and the median is the middle one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ocr2Report {
+    pub observations_timestamp: u32,
+    pub raw_observers: [u8; 32],
+    pub observations: Vec<BigInt>,
+    pub juels_per_fee_coin: BigInt,
+}
+
+impl Ocr2Report {
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        if bytes.len() < report_len(0) || bytes.len() % WORD_BS != 0 {
+            anyhow::bail!("invalid report length {}", bytes.len())
+        }
+        let word = |i: usize| &bytes[i * WORD_BS..(i + 1) * WORD_BS];
+        let num_observations = bytes.len() / WORD_BS - REPORT_HEAD_WORDS - 1;
+        if BigUint::from_bytes_be(word(0)) > BigUint::from(u32::MAX)
+            || BigUint::from_bytes_be(word(2)) != BigUint::from(REPORT_HEAD_WORDS * WORD_BS)
+            || BigUint::from_bytes_be(word(4)) != BigUint::from(num_observations)
+        {
+            anyhow::bail!("report is not ABI-encoded median report")
+        }
+        let observations = (0..num_observations)
+            .map(|i| int192_from_word(word(REPORT_HEAD_WORDS + 1 + i)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Self {
+            observations_timestamp: u32::from_be_bytes(word(0)[28..].try_into().unwrap()),
+            raw_observers: word(1).try_into().unwrap(),
+            observations,
+            juels_per_fee_coin: int192_from_word(word(3))?,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0u8; 28];
+        bytes.extend(self.observations_timestamp.to_be_bytes());
+        bytes.extend(self.raw_observers);
+        bytes.extend(uint_word(REPORT_HEAD_WORDS * WORD_BS));
+        bytes.extend(int_word(&self.juels_per_fee_coin));
+        bytes.extend(uint_word(self.observations.len()));
+        for observation in self.observations.iter() {
+            bytes.extend(int_word(observation));
+        }
+        bytes
+    }
+
+    /// Median observation, as picked by OCR2 aggregator contracts.
+    pub fn median(&self) -> Option<&BigInt> {
+        self.observations.get(self.observations.len() / 2)
+    }
+}
+
+pub(super) fn int192_from_word(word: &[u8]) -> anyhow::Result<BigInt> {
+    let value = BigInt::from_signed_bytes_be(word);
+    if value.bits() >= OBSERVATION_BITS as u64 {
+        anyhow::bail!("{} overflows int192", value)
+    }
+    Ok(value)
+}
+
+pub(super) fn uint_word(value: usize) -> [u8; WORD_BS] {
+    let mut word = [0u8; WORD_BS];
+    word[WORD_BS - 8..].copy_from_slice(&(value as u64).to_be_bytes());
+    word
+}
+
+pub(super) fn int_word(value: &BigInt) -> [u8; WORD_BS] {
+    let bytes = value.to_signed_bytes_be();
+    let fill = if value.sign() == Sign::Minus { 0xff } else { 0 };
+    let mut word = [fill; WORD_BS];
+    word[WORD_BS - bytes.len()..].copy_from_slice(&bytes);
+    word
+}
+
+/// Field element of a signed integer, where negative values are encoded as `r - |value|`.
+pub fn fr_from_bigint<E: Engine>(value: &BigInt) -> anyhow::Result<E::Fr> {
+    let abs = fr_from_biguint::<E>(value.magnitude())?;
+    Ok(if value.sign() == Sign::Minus {
+        let mut negated = E::Fr::zero();
+        negated.sub_assign(&abs);
+        negated
+    } else {
+        abs
+    })
+}
+
+/// Context of an OCR2 report, which is signed together with the report.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReportContext {
+    pub config_digest: [u8; 32],
+    pub epoch: u32,
+    pub round: u8,
+    pub extra_hash: [u8; 32],
+}
+
+impl ReportContext {
+    /// `uint40` packing epoch and round, as stored by OCR2 aggregator contracts.
+    pub fn epoch_and_round(&self) -> u64 {
+        ((self.epoch as u64) << 8) | self.round as u64
+    }
+
+    /// `bytes32[3] reportContext` passed to `transmit`.
+    pub fn to_bytes(&self) -> [u8; REPORT_CONTEXT_BS] {
+        let mut bytes = [0u8; REPORT_CONTEXT_BS];
+        bytes[..WORD_BS].copy_from_slice(&self.config_digest);
+        bytes[2 * WORD_BS - 5..2 * WORD_BS - 1].copy_from_slice(&self.epoch.to_be_bytes());
+        bytes[2 * WORD_BS - 1] = self.round;
+        bytes[2 * WORD_BS..].copy_from_slice(&self.extra_hash);
+        bytes
+    }
+}
+
+/// Digest signed by oracles, which is `keccak256(keccak256(report) || reportContext)`.
+pub fn report_digest(report: &[u8], context: &ReportContext) -> [u8; 32] {
+    let report_hash = Keccak256::digest(report);
+    Keccak256::digest([&report_hash[..], &context.to_bytes()].concat()).into()
+}
+
+/// OCR2 report with signatures of oracles. Each signature is `r || s || v` and goes with the
+/// index of its signer in the oracle set of the aggregator.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedReport {
+    pub report: Vec<u8>,
+    pub context: ReportContext,
+    #[serde(with = "signatures_serde")]
+    pub signatures: Vec<(u8, [u8; 65])>,
+}
+
+mod signatures_serde {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        signatures: &[(u8, [u8; 65])],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        signatures
+            .iter()
+            .map(|(index, signature)| (*index, signature.to_vec()))
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<(u8, [u8; 65])>, D::Error> {
+        Vec::<(u8, Vec<u8>)>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(index, signature)| {
+                let len = signature.len();
+                signature
+                    .try_into()
+                    .map(|signature| (index, signature))
+                    .map_err(|_| serde::de::Error::invalid_length(len, &"65"))
+            })
+            .collect()
+    }
+}
+
+impl SignedReport {
+    /// Check the report is signed by exactly `f + 1` distinct oracles of `signers` in increasing
+    /// order of indices, carries more than `2f` sorted observations, and return the parsed report.
+    pub fn check(&self, signers: &[[u8; 20]], f: usize) -> anyhow::Result<Ocr2Report> {
+        self.check_signatures(signers, f)?;
+        let report = Ocr2Report::from_bytes(&self.report)?;
+        if report.observations.len() <= 2 * f {
+            anyhow::bail!(
+                "{} observations are not more than 2f = {}",
+                report.observations.len(),
+                2 * f
+            )
+        }
+        if report.observations.windows(2).any(|w| w[0] > w[1]) {
+            anyhow::bail!("observations are not sorted")
+        }
+        Ok(report)
+    }
+
+    /// Check the report is signed by exactly `f + 1` distinct oracles of `signers` in increasing
+    /// order of indices, whatever the format of the report is.
+    pub fn check_signatures(&self, signers: &[[u8; 20]], f: usize) -> anyhow::Result<()> {
+        if self.signatures.len() != f + 1 {
+            anyhow::bail!("expect {} signatures, got {}", f + 1, self.signatures.len())
+        }
+        let digest = report_digest(&self.report, &self.context);
+        let mut last_index = None;
+        for (index, signature) in self.signatures.iter() {
+            if matches!(last_index, Some(last) if *index <= last) {
+                anyhow::bail!("signer indices are not increasing")
+            }
+            last_index = Some(*index);
+            let signer = recover_signer(&digest, signature)?;
+            if signers.get(*index as usize) != Some(&signer) {
+                anyhow::bail!("report is signed by non-oracle 0x{}", hex::encode(signer))
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Circuit representation of [`SignedReport`].
+#[derive(Debug, Clone)]
+pub struct AllocatedSignedReport<E: Engine> {
+    pub report: Vec<Byte<E>>,
+    pub context: [Byte<E>; REPORT_CONTEXT_BS],
+    pub signer_indices: Vec<Byte<E>>,
+    pub signatures: Vec<Signature<E>>,
+}
+
+/// Report verified by [`AllocatedSignedReport::verify`].
+#[derive(Debug, Clone, Copy)]
+pub struct VerifiedReport<E: Engine> {
+    pub config_digest: [Byte<E>; 32],
+    pub epoch_and_round: Num<E>,
+    pub observations_timestamp: Num<E>,
+    /// Median observation, where negative values are encoded as `r - |value|`
+    pub median: Num<E>,
+}
+
+impl<E: Engine> AllocatedSignedReport<E> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &SignedReport,
+    ) -> Result<Self, SynthesisError> {
+        if witness.report.len() < report_len(0) || witness.report.len() % WORD_BS != 0 {
+            return Err(new_synthesis_error(format!(
+                "invalid report length {}",
+                witness.report.len()
+            )));
+        }
+        let report = witness
+            .report
+            .iter()
+            .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let context = witness
+            .context
+            .to_bytes()
+            .iter()
+            .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+            .collect::<Result<Vec<_>, _>>()?
+            .try_into()
+            .unwrap();
+        let mut signer_indices = vec![];
+        let mut signatures = vec![];
+        for (index, signature) in witness.signatures.iter() {
+            let mut signature = *signature;
+            if signature[64] >= 27 {
+                signature[64] -= 27;
+            }
+            signer_indices.push(Byte::from_u8_witness(cs, Some(*index))?);
+            signatures.push(Signature::from_bytes_witness(cs, &signature)?);
+        }
+        Ok(Self {
+            report,
+            context,
+            signer_indices,
+            signatures,
+        })
+    }
+
+    pub fn num_observations(&self) -> usize {
+        self.report.len() / WORD_BS - REPORT_HEAD_WORDS - 1
+    }
+
+    /// Same as [`report_digest`].
+    pub fn digest<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<[Byte<E>; 32], SynthesisError> {
+        let report_hash = keccak256::digest(cs, &self.report)?;
+        let mut bytes = report_hash.to_vec();
+        bytes.extend_from_slice(&self.context);
+        keccak256::digest(cs, &bytes)
+    }
+
+    /// Same as [`SignedReport::check`]: check the report is signed by exactly `f + 1` distinct
+    /// oracles of `signers`, and its observations are sorted and more than `2f`. Return the
+    /// verified report, which is only meaningful if the check passes.
+    ///
+    /// It fails to synthesize if the number of signatures or observations mismatches `f`, as
+    /// both are fixed by the allocated report.
+    pub fn verify<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        signers: &[Address<E>],
+        f: usize,
+    ) -> Result<(Boolean, VerifiedReport<E>), SynthesisError> {
+        let num_observations = self.num_observations();
+        if num_observations <= 2 * f {
+            return Err(new_synthesis_error(format!(
+                "{} observations are not more than 2f = {}",
+                num_observations,
+                2 * f
+            )));
+        }
+        let mut is_valid = vec![self.check_signatures(cs, signers, f)?];
+
+        // Head words are fixed except observations timestamp, raw observers and juels per fee coin
+        let word = |i: usize| &self.report[i * WORD_BS..(i + 1) * WORD_BS];
+        for byte in word(0)[..28].iter() {
+            is_valid.push(byte.inner.equals(cs, &Num::zero())?);
+        }
+        for (i, expected) in [
+            (2, REPORT_HEAD_WORDS * WORD_BS),
+            (REPORT_HEAD_WORDS, num_observations),
+        ] {
+            let value = num_from_be_bytes(cs, word(i))?;
+            let expected = Num::Constant(u64_to_fe(expected as u64));
+            is_valid.push(value.equals(cs, &expected)?);
+        }
+
+        let mut values = vec![];
+        let mut last_biased: Option<Num<E>> = None;
+        for i in 0..num_observations {
+            let (is_int192, value, biased) =
+                circuit_int192_from_word(cs, word(REPORT_HEAD_WORDS + 1 + i).try_into().unwrap())?;
+            is_valid.push(is_int192);
+            if let Some(last_biased) = last_biased {
+                let (_, is_decreasing) =
+                    prepacked_long_comparison(cs, &[last_biased], &[biased], &[OBSERVATION_BITS])?;
+                is_valid.push(is_decreasing.not());
+            }
+            last_biased = Some(biased);
+            values.push(value);
+        }
+
+        let report = VerifiedReport {
+            config_digest: self.context[..WORD_BS].try_into().unwrap(),
+            epoch_and_round: num_from_be_bytes(cs, &self.context[2 * WORD_BS - 5..2 * WORD_BS])?,
+            observations_timestamp: num_from_be_bytes(cs, &word(0)[28..])?,
+            median: values[num_observations / 2],
+        };
+        Ok((smart_and(cs, &is_valid)?, report))
+    }
+
+    /// Same as [`SignedReport::check_signatures`]. Signer indices must be strictly increasing, so
+    /// that no oracle is counted twice, and each signature must be recovered to the signer at its
+    /// index. It fails to synthesize if the number of signatures is not `f + 1`.
+    pub fn check_signatures<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        signers: &[Address<E>],
+        f: usize,
+    ) -> Result<Boolean, SynthesisError> {
+        if self.signatures.len() != f + 1 {
+            return Err(new_synthesis_error(format!(
+                "expect {} signatures, got {}",
+                f + 1,
+                self.signatures.len()
+            )));
+        }
+        let digest = self.digest(cs)?;
+        let msg_hash = UInt256::from_be_bytes_fixed(cs, &digest)?;
+
+        // Epoch and round are packed into the last 5 bytes of their word
+        let mut is_valid = vec![];
+        for byte in self.context[WORD_BS..2 * WORD_BS - 5].iter() {
+            is_valid.push(byte.inner.equals(cs, &Num::zero())?);
+        }
+        let mut last_index: Option<Num<E>> = None;
+        for (index, signature) in self.signer_indices.iter().zip(self.signatures.iter()) {
+            if let Some(last_index) = last_index {
+                let (_, is_increasing) =
+                    prepacked_long_comparison(cs, &[index.inner], &[last_index], &[8])?;
+                is_valid.push(is_increasing);
+            }
+            last_index = Some(index.inner);
+
+            let mut is_in_range = Boolean::constant(false);
+            let mut signer = UInt256::zero();
+            for (i, candidate) in signers.iter().enumerate() {
+                let is_selected =
+                    Num::equals(cs, &index.inner, &Num::Constant(u64_to_fe(i as u64)))?;
+                signer =
+                    UInt256::conditionally_select(cs, &is_selected, &candidate.inner(), &signer)?;
+                is_in_range = Boolean::or(cs, &is_in_range, &is_selected)?;
+            }
+            is_valid.push(is_in_range);
+
+            let (successful, (x, y)) = signature.ecrecover(cs, &msg_hash)?;
+            let (x, y) = (
+                x.into_be_bytes(cs)?.try_into().unwrap(),
+                y.into_be_bytes(cs)?.try_into().unwrap(),
+            );
+            let address = Address::from_pubkey(cs, &x, &y)?;
+            is_valid.push(Address::new(signer).equals(cs, &address)?);
+            is_valid.push(successful);
+        }
+        smart_and(cs, &is_valid)
+    }
+}
+
+/// Split a config digest into `(hi, lo)` field elements of 16 bytes each, which are public inputs
+/// of circuits verifying Chainlink reports.
+pub fn config_digest_inputs<E: Engine>(config_digest: &[u8; 32]) -> anyhow::Result<[E::Fr; 2]> {
+    Ok([
+        fr_from_biguint::<E>(&BigUint::from_bytes_be(&config_digest[..16]))?,
+        fr_from_biguint::<E>(&BigUint::from_bytes_be(&config_digest[16..]))?,
+    ])
+}
+
+/// Allocate `config_digest` as public inputs in the same way as [`config_digest_inputs`], and
+/// enforce it equals the config digest `signed` with the report. It separates reports of different
+/// feeds and configurations, which are never replayed into a circuit instance of another one.
+pub fn enforce_config_digest<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    signed: &[Byte<E>; 32],
+    config_digest: &[u8; 32],
+) -> Result<(), SynthesisError> {
+    let inputs = config_digest_inputs::<E>(config_digest).map_err(new_synthesis_error)?;
+    for (half, input) in signed.chunks_exact(16).zip(inputs) {
+        let half = num_from_be_bytes(cs, half)?;
+        let expected = Num::alloc(cs, Some(input))?;
+        expected.enforce_equal(cs, &half)?;
+        expected.get_variable().inputize(cs)?;
+    }
+    Ok(())
+}
+
+/// Allocate `previous` as a public input, and enforce `value` of `bits` bits is strictly greater
+/// than it, so that a sequence of proofs, each of which supplies the value proven by the last one,
+/// never goes backwards.
+pub fn enforce_after_previous<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    value: &Num<E>,
+    previous: u64,
+    bits: usize,
+) -> Result<(), SynthesisError> {
+    let previous = Num::alloc(cs, Some(u64_to_fe(previous)))?;
+    previous.get_variable().inputize(cs)?;
+    let (_, is_greater) = prepacked_long_comparison(cs, &[*value], &[previous], &[bits])?;
+    Boolean::enforce_equal(cs, &is_greater, &Boolean::constant(true))
+}
+
+/// Decode an `int192` from its sign-extended ABI word. Returns whether the word is properly
+/// sign-extended, the value where negative values are encoded as `r - |value|`, and the value
+/// biased by `2^191`, which is unsigned and keeps the order of values.
+pub(super) fn circuit_int192_from_word<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    word: &[Byte<E>; WORD_BS],
+) -> Result<(Boolean, Num<E>, Num<E>), SynthesisError> {
+    let (is_negative, value, biased) = circuit_int192(cs, &word[OBSERVATION_SIGN_EXTENSION_BS..])?;
+    let extension = Num::from_boolean_is(is_negative).mul(cs, &Num::Constant(u64_to_fe(0xff)))?;
+    let mut is_extended = vec![];
+    for byte in word[..OBSERVATION_SIGN_EXTENSION_BS].iter() {
+        is_extended.push(byte.inner.equals(cs, &extension)?);
+    }
+    Ok((smart_and(cs, &is_extended)?, value, biased))
+}
+
+/// Decode an `int192` from its 24 big-endian bytes in two's complement. Returns whether it is
+/// negative, the value and the biased value in the same way as [`circuit_int192_from_word`].
+pub(super) fn circuit_int192<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    bytes: &[Byte<E>],
+) -> Result<(Boolean, Num<E>, Num<E>), SynthesisError> {
+    let low = num_from_be_bytes(cs, bytes)?;
+    let is_negative = bytes[0].inner.into_bits_le(cs, Some(8))?[7];
+    let two_pow_191 = fr_from_biguint::<E>(&(BigUint::from(1u8) << (OBSERVATION_BITS - 1)))?;
+    let two_pow_192 = fr_from_biguint::<E>(&(BigUint::from(1u8) << OBSERVATION_BITS))?;
+    let offset = Num::from_boolean_is(is_negative).mul(cs, &Num::Constant(two_pow_192))?;
+    let value = low.sub(cs, &offset)?;
+    let biased = value.add(cs, &Num::Constant(two_pow_191))?;
+    Ok((is_negative, value, biased))
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::franklin_crypto::bellman::{
+        pairing::bn256::Bn256, SynthesisError,
+    };
+    use num_bigint::BigInt;
+
+    use super::{
+        fr_from_bigint, report_digest, AllocatedSignedReport, Ocr2Report, ReportContext,
+        SignedReport,
+    };
+    use crate::{
+        gadgets::ethereum::Address,
+        utils::{signer::RedstoneSigner, testing::create_test_constraint_system},
+    };
+
+    fn signed_report(observations: &[i64], signers: &[(u8, &RedstoneSigner)]) -> SignedReport {
+        let report = Ocr2Report {
+            observations_timestamp: 1_700_000_000,
+            raw_observers: [3u8; 32],
+            observations: observations.iter().map(|o| BigInt::from(*o)).collect(),
+            juels_per_fee_coin: BigInt::from(-1),
+        };
+        let context = ReportContext {
+            config_digest: [1u8; 32],
+            epoch: 7,
+            round: 2,
+            extra_hash: [0u8; 32],
+        };
+        let report = report.to_bytes();
+        let digest = report_digest(&report, &context);
+        SignedReport {
+            report,
+            context,
+            signatures: signers
+                .iter()
+                .map(|(index, signer)| (*index, signer.sign_hash(&digest)))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_ocr2_report() -> Result<(), SynthesisError> {
+        let oracles = RedstoneSigner::generate(4);
+        let addresses = oracles.iter().map(|o| o.address()).collect::<Vec<_>>();
+        let observations = [-5, 100, 300];
+        let valid = signed_report(&observations, &[(0, &oracles[0]), (2, &oracles[2])]);
+        let parsed = valid.check(&addresses, 1).unwrap();
+        assert_eq!(Ocr2Report::from_bytes(&valid.report).unwrap(), parsed);
+        assert_eq!(parsed.median(), Some(&BigInt::from(100)));
+
+        let cases = [
+            (valid, true),
+            // Signed by a non-oracle at index 1
+            (
+                signed_report(&observations, &[(0, &oracles[0]), (1, &oracles[2])]),
+                false,
+            ),
+            // Signer indices are not increasing
+            (
+                signed_report(&observations, &[(2, &oracles[2]), (0, &oracles[0])]),
+                false,
+            ),
+            // Observations are not sorted
+            (
+                signed_report(&[100, -5, 300], &[(0, &oracles[0]), (2, &oracles[2])]),
+                false,
+            ),
+        ];
+        for (signed, expected) in cases {
+            assert_eq!(signed.check(&addresses, 1).is_ok(), expected);
+            let cs = &mut create_test_constraint_system()?;
+            let signers = addresses
+                .iter()
+                .map(|a| Address::from_address_witness(cs, a))
+                .collect::<Result<Vec<_>, _>>()?;
+            let report = AllocatedSignedReport::from_witness(cs, &signed)?;
+            let n = cs.n();
+            let (is_valid, verified) = report.verify(cs, &signers, 1)?;
+            println!("Roughly {} gates", cs.n() - n);
+            assert_eq!(is_valid.get_value(), Some(expected));
+            assert!(cs.is_satisfied());
+            if expected {
+                assert_eq!(
+                    verified.median.get_value(),
+                    Some(fr_from_bigint::<Bn256>(&BigInt::from(100)).unwrap())
+                );
+                assert_eq!(
+                    verified.epoch_and_round.get_value(),
+                    Some(fr_from_bigint::<Bn256>(&BigInt::from(7 * 256 + 2)).unwrap())
+                );
+            }
+        }
+
+        // Negative median
+        let signed = signed_report(&[-300, -5, 100], &[(1, &oracles[1]), (3, &oracles[3])]);
+        let cs = &mut create_test_constraint_system()?;
+        let signers = addresses
+            .iter()
+            .map(|a| Address::from_address_witness(cs, a))
+            .collect::<Result<Vec<_>, _>>()?;
+        let report = AllocatedSignedReport::from_witness(cs, &signed)?;
+        let (is_valid, verified) = report.verify(cs, &signers, 1)?;
+        assert_eq!(is_valid.get_value(), Some(true));
+        assert_eq!(
+            verified.median.get_value(),
+            Some(fr_from_bigint::<Bn256>(&BigInt::from(-5)).unwrap())
+        );
+        // Not enough signatures for f = 2
+        assert!(report.verify(cs, &signers, 2).is_err());
+        Ok(())
+    }
+}
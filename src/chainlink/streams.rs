@@ -0,0 +1,457 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{ff::Field, Engine};
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{
+            plonk::better_better_cs::{
+                cs::{Circuit, ConstraintSystem, Gate, GateInternal},
+                gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
+            },
+            SynthesisError,
+        },
+        plonk::circuit::{
+            allocated_num::Num, boolean::Boolean, custom_rescue_gate::Rescue5CustomGate,
+        },
+    },
+    glue::prepacked_long_comparison,
+    utils::u64_to_fe,
+    vm::partitioner::smart_and,
+};
+use num_bigint::{BigInt, BigUint};
+
+use crate::{
+    gadgets::{
+        ethereum::Address,
+        poseidon::{circuit_poseidon_hash, poseidon_hash},
+        rescue::circuit_rescue_hash,
+    },
+    redstone::witness::guardian_set_hash,
+    utils::{self, fr_from_biguint, new_synthesis_error, num_from_be_bytes},
+};
+
+use super::report::{
+    circuit_int192_from_word, config_digest_inputs, enforce_after_previous, enforce_config_digest,
+    fr_from_bigint, int192_from_word, int_word, AllocatedSignedReport, SignedReport, WORD_BS,
+};
+
+// Number of ABI words of a Data Streams report of schema v3
+pub const STREAMS_REPORT_WORDS: usize = 9;
+// Schema version of Data Streams reports, which is the first 2 bytes of feed id
+pub const STREAMS_REPORT_VERSION: u16 = 3;
+// Number of bits of fees, which are `uint192`
+const FEE_BITS: usize = 192;
+
+/// Report of Chainlink Data Streams (formerly Mercury) of schema v3, which is ABI-encoded as
+/// `(bytes32 feedId, uint32 validFromTimestamp, uint32 observationsTimestamp, uint192 nativeFee,
+/// uint192 linkFee, uint32 expiresAt, int192 price, int192 bid, int192 ask)`. `price` is the
+/// benchmark price, i.e. the mid price between bid and ask.
+///
+/// It is signed by the DON in the same way as OCR2 reports, see [`SignedReport`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamsReport {
+    pub feed_id: [u8; 32],
+    pub valid_from_timestamp: u32,
+    pub observations_timestamp: u32,
+    pub native_fee: BigUint,
+    pub link_fee: BigUint,
+    pub expires_at: u32,
+    pub price: BigInt,
+    pub bid: BigInt,
+    pub ask: BigInt,
+}
+
+impl StreamsReport {
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        if bytes.len() != STREAMS_REPORT_WORDS * WORD_BS {
+            anyhow::bail!("invalid report length {}", bytes.len())
+        }
+        let word = |i: usize| &bytes[i * WORD_BS..(i + 1) * WORD_BS];
+        let uint = |i: usize, bits: usize| -> anyhow::Result<BigUint> {
+            let value = BigUint::from_bytes_be(word(i));
+            if value.bits() > bits as u64 {
+                anyhow::bail!("word {} overflows uint{}", i, bits)
+            }
+            Ok(value)
+        };
+        let uint32 = |i: usize| -> anyhow::Result<u32> {
+            uint(i, 32)?;
+            Ok(u32::from_be_bytes(
+                word(i)[WORD_BS - 4..].try_into().unwrap(),
+            ))
+        };
+        Ok(Self {
+            feed_id: word(0).try_into().unwrap(),
+            valid_from_timestamp: uint32(1)?,
+            observations_timestamp: uint32(2)?,
+            native_fee: uint(3, FEE_BITS)?,
+            link_fee: uint(4, FEE_BITS)?,
+            expires_at: uint32(5)?,
+            price: int192_from_word(word(6))?,
+            bid: int192_from_word(word(7))?,
+            ask: int192_from_word(word(8))?,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let uint = |value: &BigUint| {
+            let bytes = value.to_bytes_be();
+            let mut word = [0u8; WORD_BS];
+            word[WORD_BS - bytes.len()..].copy_from_slice(&bytes);
+            word
+        };
+        let mut bytes = self.feed_id.to_vec();
+        bytes.extend(uint(&BigUint::from(self.valid_from_timestamp)));
+        bytes.extend(uint(&BigUint::from(self.observations_timestamp)));
+        bytes.extend(uint(&self.native_fee));
+        bytes.extend(uint(&self.link_fee));
+        bytes.extend(uint(&BigUint::from(self.expires_at)));
+        bytes.extend(int_word(&self.price));
+        bytes.extend(int_word(&self.bid));
+        bytes.extend(int_word(&self.ask));
+        bytes
+    }
+
+    /// Schema version encoded in feed id.
+    pub fn version(&self) -> u16 {
+        u16::from_be_bytes([self.feed_id[0], self.feed_id[1]])
+    }
+}
+
+/// Check the Data Streams report is signed by exactly `f + 1` distinct oracles of `signers`, is of
+/// schema v3 and valid from no later than its observations, and return the parsed report.
+pub fn check_streams_report(
+    signed_report: &SignedReport,
+    signers: &[[u8; 20]],
+    f: usize,
+) -> anyhow::Result<StreamsReport> {
+    signed_report.check_signatures(signers, f)?;
+    let report = StreamsReport::from_bytes(&signed_report.report)?;
+    if report.version() != STREAMS_REPORT_VERSION {
+        anyhow::bail!(
+            "unsupported report schema v{}, expect v{}",
+            report.version(),
+            STREAMS_REPORT_VERSION
+        )
+    }
+    if report.valid_from_timestamp > report.observations_timestamp {
+        anyhow::bail!(
+            "report is valid from {} after its observations at {}",
+            report.valid_from_timestamp,
+            report.observations_timestamp
+        )
+    }
+    Ok(report)
+}
+
+/// Data Streams report verified by [`verify_streams_report`]. Prices are `int192`, where negative
+/// values are encoded as `r - |value|`.
+#[derive(Debug, Clone, Copy)]
+pub struct VerifiedStreamsReport<E: Engine> {
+    pub config_digest: [Byte<E>; 32],
+    pub feed_id: [Byte<E>; 32],
+    pub valid_from_timestamp: Num<E>,
+    pub observations_timestamp: Num<E>,
+    pub expires_at: Num<E>,
+    pub price: Num<E>,
+    pub bid: Num<E>,
+    pub ask: Num<E>,
+}
+
+/// Same as [`check_streams_report`]. Return the verified report, which is only meaningful if the
+/// check passes. It fails to synthesize if the report is not as long as a v3 report.
+pub fn verify_streams_report<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    signed_report: &AllocatedSignedReport<E>,
+    signers: &[Address<E>],
+    f: usize,
+) -> Result<(Boolean, VerifiedStreamsReport<E>), SynthesisError> {
+    if signed_report.report.len() != STREAMS_REPORT_WORDS * WORD_BS {
+        return Err(new_synthesis_error(format!(
+            "invalid report length {}",
+            signed_report.report.len()
+        )));
+    }
+    let mut is_valid = vec![signed_report.check_signatures(cs, signers, f)?];
+    let word = |i: usize| &signed_report.report[i * WORD_BS..(i + 1) * WORD_BS];
+
+    let version = num_from_be_bytes(cs, &word(0)[..2])?;
+    let expected_version = Num::Constant(u64_to_fe(STREAMS_REPORT_VERSION as u64));
+    is_valid.push(version.equals(cs, &expected_version)?);
+    // Leading bytes of unsigned words are zero
+    for (i, bits) in [(1, 32), (2, 32), (3, FEE_BITS), (4, FEE_BITS), (5, 32)] {
+        for byte in word(i)[..WORD_BS - bits / 8].iter() {
+            is_valid.push(byte.inner.equals(cs, &Num::zero())?);
+        }
+    }
+    let valid_from_timestamp = num_from_be_bytes(cs, &word(1)[WORD_BS - 4..])?;
+    let observations_timestamp = num_from_be_bytes(cs, &word(2)[WORD_BS - 4..])?;
+    let (_, is_later) = prepacked_long_comparison(
+        cs,
+        &[valid_from_timestamp],
+        &[observations_timestamp],
+        &[32],
+    )?;
+    is_valid.push(is_later.not());
+
+    let mut prices = vec![];
+    for i in 6..STREAMS_REPORT_WORDS {
+        let (is_int192, value, _) = circuit_int192_from_word(cs, word(i).try_into().unwrap())?;
+        is_valid.push(is_int192);
+        prices.push(value);
+    }
+
+    let report = VerifiedStreamsReport {
+        config_digest: signed_report.context[..WORD_BS].try_into().unwrap(),
+        feed_id: word(0).try_into().unwrap(),
+        valid_from_timestamp,
+        observations_timestamp,
+        expires_at: num_from_be_bytes(cs, &word(5)[WORD_BS - 4..])?,
+        price: prices[0],
+        bid: prices[1],
+        ask: prices[2],
+    };
+    Ok((smart_and(cs, &is_valid)?, report))
+}
+
+/// Circuit proving the benchmark price of a Chainlink Data Streams report signed by `f + 1`
+/// oracles of the DON, whose signing addresses are `signers`.
+///
+/// Public inputs are the commitment `poseidon(signer set hash, config digest[..16], config
+/// digest[16..], feed id[..16], feed id[16..], valid from timestamp, observations timestamp,
+/// price)`, where the signer set hash is computed in the same way as guardian set hash and a
+/// negative price is encoded as `r - |price|`, followed by `config_digest` of the DON, to which
+/// the report is bound.
+///
+/// If `previous_observations_timestamp` is set, it follows as a public input, and the observations
+/// timestamp of the report must be strictly greater than it.
+#[derive(Debug, Clone)]
+pub struct StreamsPriceOracle<E: Engine> {
+    pub signed_report: SignedReport,
+    pub signers: Vec<[u8; 20]>,
+    pub f: usize,
+    pub config_digest: [u8; 32],
+    pub report: StreamsReport,
+    pub commitment: E::Fr,
+    pub previous_observations_timestamp: Option<u32>,
+}
+
+impl<E: Engine> StreamsPriceOracle<E> {
+    pub fn new(
+        signed_report: SignedReport,
+        signers: Vec<[u8; 20]>,
+        f: usize,
+        config_digest: [u8; 32],
+    ) -> Result<Self, anyhow::Error> {
+        if signed_report.context.config_digest != config_digest {
+            anyhow::bail!(
+                "report is signed under config digest 0x{}, expect 0x{}",
+                hex::encode(signed_report.context.config_digest),
+                hex::encode(config_digest)
+            )
+        }
+        let report = check_streams_report(&signed_report, &signers, f)?;
+        let config_digest = &signed_report.context.config_digest;
+        let commitment = poseidon_hash::<E>(&[
+            guardian_set_hash::<E>(&signers)?,
+            fr_from_biguint::<E>(&BigUint::from_bytes_be(&config_digest[..16]))?,
+            fr_from_biguint::<E>(&BigUint::from_bytes_be(&config_digest[16..]))?,
+            fr_from_biguint::<E>(&BigUint::from_bytes_be(&report.feed_id[..16]))?,
+            fr_from_biguint::<E>(&BigUint::from_bytes_be(&report.feed_id[16..]))?,
+            fr_from_biguint::<E>(&BigUint::from(report.valid_from_timestamp))?,
+            fr_from_biguint::<E>(&BigUint::from(report.observations_timestamp))?,
+            fr_from_bigint::<E>(&report.price)?,
+        ]);
+        Ok(Self {
+            signed_report,
+            signers,
+            f,
+            config_digest,
+            report,
+            commitment,
+            previous_observations_timestamp: None,
+        })
+    }
+
+    /// Require the observations timestamp of the report to be strictly greater than `previous`,
+    /// e.g. the one of the last proven report.
+    pub fn with_previous_observations_timestamp(
+        mut self,
+        previous: u32,
+    ) -> Result<Self, anyhow::Error> {
+        if self.report.observations_timestamp <= previous {
+            anyhow::bail!(
+                "observations timestamp {} is not after previous {}",
+                self.report.observations_timestamp,
+                previous
+            )
+        }
+        self.previous_observations_timestamp = Some(previous);
+        Ok(self)
+    }
+
+    /// Public inputs of the circuit, which are the commitment followed by the config digest split
+    /// by [`config_digest_inputs`] and the previous observations timestamp if set.
+    pub fn public_inputs(&self) -> anyhow::Result<Vec<E::Fr>> {
+        let mut inputs = vec![self.commitment];
+        inputs.extend(config_digest_inputs::<E>(&self.config_digest)?);
+        if let Some(previous) = self.previous_observations_timestamp {
+            inputs.push(fr_from_biguint::<E>(&BigUint::from(previous))?);
+        }
+        Ok(inputs)
+    }
+}
+
+impl<E: Engine> Circuit<E> for StreamsPriceOracle<E> {
+    type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
+
+    fn synthesize<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<(), SynthesisError> {
+        utils::add_bitwise_logic_and_range_table(cs)?;
+        let temp_variable = Num::alloc(cs, Some(E::Fr::one()))?;
+        circuit_rescue_hash(cs, &[temp_variable])?; // Just to standardize the proof format
+
+        let signers = self
+            .signers
+            .iter()
+            .map(|s| Address::from_address_witness(cs, s))
+            .collect::<Result<Vec<_>, _>>()?;
+        let signed_report = AllocatedSignedReport::from_witness(cs, &self.signed_report)?;
+        let (is_valid, report) = verify_streams_report(cs, &signed_report, &signers, self.f)?;
+        Boolean::enforce_equal(cs, &is_valid, &Boolean::constant(true))?;
+
+        let signer_nums = signers
+            .iter()
+            .map(|s| s.inner().to_num_unchecked(cs))
+            .collect::<Result<Vec<_>, _>>()?;
+        let input = [
+            circuit_poseidon_hash(cs, &signer_nums)?,
+            num_from_be_bytes(cs, &report.config_digest[..16])?,
+            num_from_be_bytes(cs, &report.config_digest[16..])?,
+            num_from_be_bytes(cs, &report.feed_id[..16])?,
+            num_from_be_bytes(cs, &report.feed_id[16..])?,
+            report.valid_from_timestamp,
+            report.observations_timestamp,
+            report.price,
+        ];
+        let commitment = circuit_poseidon_hash(cs, &input)?;
+        let expected_commitment = Num::alloc(cs, Some(self.commitment))?;
+        expected_commitment.enforce_equal(cs, &commitment)?;
+        expected_commitment.get_variable().inputize(cs)?;
+        enforce_config_digest(cs, &report.config_digest, &self.config_digest)?;
+        if let Some(previous) = self.previous_observations_timestamp {
+            enforce_after_previous(cs, &report.observations_timestamp, previous as u64, 32)?;
+        }
+        Ok(())
+    }
+
+    fn declare_used_gates() -> Result<Vec<Box<dyn GateInternal<E>>>, SynthesisError> {
+        Ok(vec![
+            Self::MainGate::default().into_internal(),
+            Rescue5CustomGate.into_internal(), // Just to standardize the proof format
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::bellman::{pairing::ff::Field, plonk::better_better_cs::cs::Circuit},
+        testing::{create_test_artifacts_with_optimized_gate, Bn256},
+    };
+    use num_bigint::{BigInt, BigUint};
+
+    use super::{StreamsPriceOracle, StreamsReport};
+    use crate::{
+        chainlink::{report_digest, ReportContext, SignedReport},
+        utils::signer::RedstoneSigner,
+    };
+
+    fn signed_report(report: &StreamsReport, oracles: &[RedstoneSigner]) -> SignedReport {
+        let report = report.to_bytes();
+        let context = ReportContext {
+            config_digest: [5u8; 32],
+            epoch: 1000,
+            round: 3,
+            extra_hash: [0u8; 32],
+        };
+        let digest = report_digest(&report, &context);
+        SignedReport {
+            report,
+            context,
+            signatures: [0u8, 2]
+                .map(|i| (i, oracles[i as usize].sign_hash(&digest)))
+                .to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_streams_price_oracle() -> anyhow::Result<()> {
+        let oracles = RedstoneSigner::generate(4);
+        let signers = oracles.iter().map(|o| o.address()).collect::<Vec<_>>();
+        let mut feed_id = [0xabu8; 32];
+        feed_id[..2].copy_from_slice(&3u16.to_be_bytes());
+        let report = StreamsReport {
+            feed_id,
+            valid_from_timestamp: 1_700_000_000,
+            observations_timestamp: 1_700_000_001,
+            native_fee: BigUint::from(1_000u32),
+            link_fee: BigUint::from(2_000u32),
+            expires_at: 1_700_086_401,
+            price: BigInt::from(3_652_890_574_272_000_000_000i128),
+            bid: BigInt::from(3_652_880_000_000_000_000_000i128),
+            ask: BigInt::from(3_652_900_000_000_000_000_000i128),
+        };
+        let signed = signed_report(&report, &oracles);
+        assert_eq!(StreamsReport::from_bytes(&signed.report)?, report);
+
+        let circuit =
+            StreamsPriceOracle::<Bn256>::new(signed.clone(), signers.clone(), 1, [5u8; 32])?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        println!("Roughly {} gates", cs.n());
+
+        let chained = circuit
+            .clone()
+            .with_previous_observations_timestamp(1_700_000_000)?;
+        assert_eq!(chained.public_inputs()?.len(), 4);
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        chained.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        assert!(circuit
+            .clone()
+            .with_previous_observations_timestamp(1_700_000_001)
+            .is_err());
+
+        let tampers: [fn(&mut StreamsPriceOracle<Bn256>); 3] = [
+            |c| c.commitment.add_assign(&Field::one()),
+            |c| c.config_digest = [6u8; 32],
+            |c| c.previous_observations_timestamp = Some(1_700_000_001),
+        ];
+        for tamper in tampers {
+            let mut circuit = circuit.clone();
+            tamper(&mut circuit);
+            let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+            circuit.synthesize(&mut cs)?;
+            assert!(!cs.is_satisfied());
+        }
+
+        // Unsupported schema and inverted timestamps are rejected
+        let mut v2 = report.clone();
+        v2.feed_id[1] = 2;
+        let mut inverted = report.clone();
+        inverted.valid_from_timestamp = inverted.observations_timestamp + 1;
+        for invalid in [v2, inverted] {
+            let signed = signed_report(&invalid, &oracles);
+            assert!(
+                StreamsPriceOracle::<Bn256>::new(signed, signers.clone(), 1, [5u8; 32]).is_err()
+            );
+        }
+        // f + 1 signatures and the config digest of the DON are required
+        assert!(
+            StreamsPriceOracle::<Bn256>::new(signed.clone(), signers.clone(), 2, [5u8; 32])
+                .is_err()
+        );
+        assert!(StreamsPriceOracle::<Bn256>::new(signed, signers, 1, [6u8; 32]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,152 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{ff::Field, Engine};
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{
+            plonk::better_better_cs::{
+                cs::{Circuit, ConstraintSystem, Gate, GateInternal},
+                gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
+            },
+            SynthesisError,
+        },
+        plonk::circuit::{
+            allocated_num::Num, boolean::Boolean, custom_rescue_gate::Rescue5CustomGate,
+        },
+    },
+};
+use num_bigint::BigUint;
+
+use crate::{
+    gadgets::{
+        poseidon::{circuit_poseidon_hash, poseidon_hash},
+        rescue::circuit_rescue_hash,
+        schnorr::SchnorrPublicKey,
+    },
+    utils::{self, fr_from_biguint, num_from_be_bytes},
+};
+
+use super::poke::{circuit_pubkey_hash, pubkey_hash, AllocatedPoke, Poke};
+
+/// Circuit proving the value of a Chronicle Scribe oracle `wat` in a poke, which is signed by
+/// feeds with an aggregated Schnorr signature.
+///
+/// Public input is the commitment `poseidon(pubkey hash, wat[..16], wat[16..], age, val)`, where
+/// pubkey hash is [`pubkey_hash`](super::pubkey_hash) of the aggregated public key of signing
+/// feeds. Scribe contracts aggregate public keys of lifted feeds in the same way, so the verifier
+/// is able to recompute it from the feeds it trusts.
+#[derive(Debug, Clone)]
+pub struct ChroniclePriceOracle<E: Engine> {
+    pub poke: Poke,
+    pub wat: [u8; 32],
+    pub pubkey: [u8; 64],
+    pub commitment: E::Fr,
+}
+
+impl<E: Engine> ChroniclePriceOracle<E> {
+    pub fn new(poke: Poke, wat: [u8; 32], feeds: &[[u8; 64]]) -> Result<Self, anyhow::Error> {
+        let pubkey = poke.check(&wat, feeds)?;
+        let commitment = poseidon_hash::<E>(&[
+            pubkey_hash::<E>(&pubkey)?,
+            fr_from_biguint::<E>(&BigUint::from_bytes_be(&wat[..16]))?,
+            fr_from_biguint::<E>(&BigUint::from_bytes_be(&wat[16..]))?,
+            fr_from_biguint::<E>(&BigUint::from(poke.age))?,
+            fr_from_biguint::<E>(&BigUint::from(poke.val))?,
+        ]);
+        Ok(Self {
+            poke,
+            wat,
+            pubkey,
+            commitment,
+        })
+    }
+}
+
+impl<E: Engine> Circuit<E> for ChroniclePriceOracle<E> {
+    type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
+
+    fn synthesize<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<(), SynthesisError> {
+        utils::add_bitwise_logic_and_range_table(cs)?;
+        let temp_variable = Num::alloc(cs, Some(E::Fr::one()))?;
+        circuit_rescue_hash(cs, &[temp_variable])?; // Just to standardize the proof format
+
+        let pubkey = SchnorrPublicKey::from_witness(cs, &self.pubkey)?;
+        let wat = self
+            .wat
+            .iter()
+            .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+            .collect::<Result<Vec<_>, _>>()?
+            .try_into()
+            .unwrap();
+        let poke = AllocatedPoke::from_witness(cs, &self.poke)?;
+        let (is_valid, poke) = poke.verify(cs, &wat, &pubkey)?;
+        Boolean::enforce_equal(cs, &is_valid, &Boolean::constant(true))?;
+
+        let input = [
+            circuit_pubkey_hash(cs, &pubkey)?,
+            num_from_be_bytes(cs, &wat[..16])?,
+            num_from_be_bytes(cs, &wat[16..])?,
+            poke.age,
+            poke.val,
+        ];
+        let commitment = circuit_poseidon_hash(cs, &input)?;
+        let expected_commitment = Num::alloc(cs, Some(self.commitment))?;
+        expected_commitment.enforce_equal(cs, &commitment)?;
+        expected_commitment.get_variable().inputize(cs)?;
+        Ok(())
+    }
+
+    fn declare_used_gates() -> Result<Vec<Box<dyn GateInternal<E>>>, SynthesisError> {
+        Ok(vec![
+            Self::MainGate::default().into_internal(),
+            Rescue5CustomGate.into_internal(), // Just to standardize the proof format
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::bellman::{pairing::ff::Field, plonk::better_better_cs::cs::Circuit},
+        testing::{create_test_artifacts_with_optimized_gate, Bn256},
+    };
+
+    use super::ChroniclePriceOracle;
+    use crate::{
+        chronicle::Poke,
+        utils::signer::{RedstoneSigner, SchnorrSigner},
+    };
+
+    #[test]
+    fn test_chronicle_price_oracle() -> anyhow::Result<()> {
+        let feeds = RedstoneSigner::generate(5);
+        let pubkeys = feeds.iter().map(|f| f.pubkey()).collect::<Vec<_>>();
+        let mut wat = [0u8; 32];
+        wat[..7].copy_from_slice(b"BTC/USD");
+        let mut poke = Poke {
+            val: 43_000_000_000_000_000_000_000,
+            age: 1_700_000_000,
+            signature: [0u8; 32],
+            commitment: [0u8; 20],
+        };
+        // Any subset of feeds signs on behalf of their aggregated key
+        let signing = [&feeds[0], &feeds[2], &feeds[4]].map(Clone::clone);
+        (poke.signature, poke.commitment) =
+            SchnorrSigner::aggregate(&signing).sign(&poke.message(&wat));
+        let signing_pubkeys = [pubkeys[0], pubkeys[2], pubkeys[4]];
+
+        let circuit = ChroniclePriceOracle::<Bn256>::new(poke.clone(), wat, &signing_pubkeys)?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        println!("Roughly {} gates", cs.n());
+
+        let mut tampered = circuit;
+        tampered.commitment.add_assign(&Field::one());
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        tampered.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+
+        assert!(ChroniclePriceOracle::<Bn256>::new(poke, wat, &pubkeys).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,5 @@
+mod circuit;
+mod poke;
+
+pub use circuit::*;
+pub use poke::*;
@@ -0,0 +1,218 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+};
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    gadgets::{
+        ethereum::{circuit_eth_signed_message_hash, eth_signed_message_hash, Address},
+        keccak256,
+        poseidon::{circuit_poseidon_hash, poseidon_hash},
+        schnorr::{aggregate_pubkeys, verify_signature, SchnorrPublicKey},
+    },
+    utils::{fr_from_biguint, num_from_be_bytes},
+};
+
+/// Poke of Chronicle's Scribe oracle, which updates the value of oracle `wat` to `val` at `age`.
+/// It is signed by a set of feeds with an aggregated Schnorr `signature`, whose nonce point has
+/// address `commitment`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Poke {
+    pub val: u128,
+    pub age: u32,
+    pub signature: [u8; 32],
+    pub commitment: [u8; 20],
+}
+
+impl Poke {
+    /// Message signed by feeds, which is `keccak256("\x19Ethereum Signed Message:\n32" ||
+    /// keccak256(val || age || wat))` as `Scribe.constructPokeMessage`.
+    pub fn message(&self, wat: &[u8; 32]) -> [u8; 32] {
+        let hash = Keccak256::new()
+            .chain_update(self.val.to_be_bytes())
+            .chain_update(self.age.to_be_bytes())
+            .chain_update(wat)
+            .finalize();
+        eth_signed_message_hash(&hash.into())
+    }
+
+    /// Check the poke of oracle `wat` is signed by feeds of public keys `feeds`, and return their
+    /// aggregated public key.
+    pub fn check(&self, wat: &[u8; 32], feeds: &[[u8; 64]]) -> anyhow::Result<[u8; 64]> {
+        if feeds.is_empty() {
+            anyhow::bail!("poke is signed by no feed")
+        }
+        let pubkey = aggregate_pubkeys(feeds)?;
+        if !verify_signature(
+            &pubkey,
+            &self.message(wat),
+            &self.signature,
+            &self.commitment,
+        ) {
+            anyhow::bail!("invalid schnorr signature of poke")
+        }
+        Ok(pubkey)
+    }
+}
+
+/// Hash of Schnorr public key `x || y`, which is `poseidon(x[..16], x[16..], parity of y)`.
+pub fn pubkey_hash<E: Engine>(pubkey: &[u8; 64]) -> anyhow::Result<E::Fr> {
+    Ok(poseidon_hash::<E>(&[
+        fr_from_biguint::<E>(&BigUint::from_bytes_be(&pubkey[..16]))?,
+        fr_from_biguint::<E>(&BigUint::from_bytes_be(&pubkey[16..32]))?,
+        fr_from_biguint::<E>(&BigUint::from(pubkey[63] & 1))?,
+    ]))
+}
+
+/// Same as [`pubkey_hash`].
+pub fn circuit_pubkey_hash<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    pubkey: &SchnorrPublicKey<E>,
+) -> Result<Num<E>, SynthesisError> {
+    let input = [
+        num_from_be_bytes(cs, &pubkey.x[..16])?,
+        num_from_be_bytes(cs, &pubkey.x[16..])?,
+        Num::from_boolean_is(pubkey.y_parity),
+    ];
+    circuit_poseidon_hash(cs, &input)
+}
+
+/// Circuit representation of [`Poke`].
+#[derive(Debug, Clone)]
+pub struct AllocatedPoke<E: Engine> {
+    pub val: [Byte<E>; 16],
+    pub age: [Byte<E>; 4],
+    pub signature: [Byte<E>; 32],
+    pub commitment: Address<E>,
+}
+
+/// Poke verified by [`AllocatedPoke::verify`].
+#[derive(Debug, Clone, Copy)]
+pub struct VerifiedPoke<E: Engine> {
+    pub val: Num<E>,
+    pub age: Num<E>,
+}
+
+impl<E: Engine> AllocatedPoke<E> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &Poke,
+    ) -> Result<Self, SynthesisError> {
+        let mut alloc_bytes = |bytes: &[u8]| {
+            bytes
+                .iter()
+                .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let val = alloc_bytes(&witness.val.to_be_bytes())?.try_into().unwrap();
+        let age = alloc_bytes(&witness.age.to_be_bytes())?.try_into().unwrap();
+        let signature = alloc_bytes(&witness.signature)?.try_into().unwrap();
+        let commitment = Address::from_address_witness(cs, &witness.commitment)?;
+        Ok(Self {
+            val,
+            age,
+            signature,
+            commitment,
+        })
+    }
+
+    /// Same as [`Poke::message`].
+    pub fn message<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        wat: &[Byte<E>; 32],
+    ) -> Result<[Byte<E>; 32], SynthesisError> {
+        let mut bytes = self.val.to_vec();
+        bytes.extend_from_slice(&self.age);
+        bytes.extend_from_slice(wat);
+        let hash = keccak256::digest(cs, &bytes)?;
+        circuit_eth_signed_message_hash(cs, &hash)
+    }
+
+    /// Same as [`Poke::check`] given the aggregated public key of feeds: check the poke of oracle
+    /// `wat` is signed by `pubkey`. Return the verified poke, which is only meaningful if the check
+    /// passes.
+    pub fn verify<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        wat: &[Byte<E>; 32],
+        pubkey: &SchnorrPublicKey<E>,
+    ) -> Result<(Boolean, VerifiedPoke<E>), SynthesisError> {
+        let message = self.message(cs, wat)?;
+        let is_valid = pubkey.verify(cs, &message, &self.signature, &self.commitment)?;
+        let poke = VerifiedPoke {
+            val: num_from_be_bytes(cs, &self.val)?,
+            age: num_from_be_bytes(cs, &self.age)?,
+        };
+        Ok((is_valid, poke))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte,
+        franklin_crypto::bellman::{pairing::bn256::Bn256, SynthesisError},
+    };
+
+    use super::{AllocatedPoke, Poke};
+    use crate::{
+        gadgets::schnorr::SchnorrPublicKey,
+        utils::{
+            fr_from_biguint,
+            signer::{RedstoneSigner, SchnorrSigner},
+            testing::create_test_constraint_system,
+        },
+    };
+
+    #[test]
+    fn test_poke() -> Result<(), SynthesisError> {
+        let feeds = RedstoneSigner::generate(4);
+        let pubkeys = feeds.iter().map(|f| f.pubkey()).collect::<Vec<_>>();
+        let mut wat = [0u8; 32];
+        wat[..7].copy_from_slice(b"ETH/USD");
+        let mut poke = Poke {
+            val: 3_641_500_000_000_000_000_000,
+            age: 1_700_000_000,
+            signature: [0u8; 32],
+            commitment: [0u8; 20],
+        };
+        (poke.signature, poke.commitment) =
+            SchnorrSigner::aggregate(&feeds).sign(&poke.message(&wat));
+        let pubkey = poke.check(&wat, &pubkeys).unwrap();
+
+        // Signatures are bound to the signing feeds, the oracle and the value
+        assert!(poke.check(&wat, &pubkeys[1..]).is_err());
+        assert!(poke.check(&[1u8; 32], &pubkeys).is_err());
+        let mut tampered = poke.clone();
+        tampered.val += 1;
+        assert!(tampered.check(&wat, &pubkeys).is_err());
+
+        for (poke, expected) in [(poke, true), (tampered, false)] {
+            let cs = &mut create_test_constraint_system()?;
+            let wat = wat
+                .iter()
+                .map(|b| Byte::<Bn256>::from_u8_witness(cs, Some(*b)))
+                .collect::<Result<Vec<_>, _>>()?
+                .try_into()
+                .unwrap();
+            let pubkey = SchnorrPublicKey::from_witness(cs, &pubkey)?;
+            let allocated = AllocatedPoke::from_witness(cs, &poke)?;
+            let (is_valid, verified) = allocated.verify(cs, &wat, &pubkey)?;
+            assert_eq!(is_valid.get_value(), Some(expected));
+            assert_eq!(
+                verified.val.get_value(),
+                Some(fr_from_biguint::<Bn256>(&poke.val.into()).unwrap())
+            );
+            assert!(cs.is_satisfied());
+        }
+        Ok(())
+    }
+}
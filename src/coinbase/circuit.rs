@@ -0,0 +1,268 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{
+    ff::{Field, PrimeField},
+    Engine,
+};
+use advanced_circuit_component::{
+    franklin_crypto::{
+        bellman::{
+            plonk::better_better_cs::{
+                cs::{Circuit, ConstraintSystem, Gate, GateInternal},
+                gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
+            },
+            SynthesisError,
+        },
+        plonk::circuit::{
+            allocated_num::Num, boolean::Boolean, custom_rescue_gate::Rescue5CustomGate,
+        },
+    },
+    glue::prepacked_long_comparison,
+};
+use num_bigint::BigUint;
+
+use crate::{
+    gadgets::{
+        ethereum::Address,
+        poseidon::{circuit_poseidon_hash, poseidon_hash},
+        rescue::circuit_rescue_hash,
+    },
+    redstone::witness::guardian_set_hash,
+    utils::{self, fr_from_biguint},
+    witness::{PricesSummarize, PublicInputData},
+};
+
+use super::message::{AllocatedOpenOracleMessage, SignedOpenOracleMessage};
+
+/// Circuit verifying batches of Open Oracle prices of `NUM_PRICES` symbols signed by `reporter`,
+/// e.g. [`COINBASE_REPORTER`](super::COINBASE_REPORTER), whose public input is computed in the same
+/// way as [`crate::redstone::PriceOracle`], so that consumers verify prices of either provider
+/// alike.
+///
+/// The guardian set is `[reporter]`. Feed ids are symbols, prices are rescaled from
+/// [`COINBASE_PRICE_DECIMALS`](super::COINBASE_PRICE_DECIMALS) to the committed precision, and the
+/// publish time of a batch is the timestamp of its first message in milliseconds.
+#[derive(Debug, Clone)]
+pub struct CoinbasePriceOracle<E: Engine, const NUM_PRICES: usize> {
+    pub messages_batch: Vec<[SignedOpenOracleMessage; NUM_PRICES]>,
+    pub reporter: [u8; 20],
+    pub public_input_data: PublicInputData<E>,
+    pub commitment: E::Fr,
+}
+
+impl<E: Engine, const NUM_PRICES: usize> CoinbasePriceOracle<E, NUM_PRICES> {
+    pub fn new(
+        messages_batch: Vec<[SignedOpenOracleMessage; NUM_PRICES]>,
+        reporter: [u8; 20],
+    ) -> Result<Self, anyhow::Error> {
+        let mut last_publish_time = 0;
+        let mut prices_commitments = vec![];
+        for messages in messages_batch.iter() {
+            let mut prices_commitment_members = vec![];
+            for message in messages.iter() {
+                message.check(&reporter)?;
+                prices_commitment_members.push(fr_from_biguint::<E>(&message.feed_id())?);
+                prices_commitment_members.push(fr_from_biguint::<E>(&message.price())?);
+            }
+            let current_publish_time = messages.first().map(|v| v.publish_time()).unwrap_or(0);
+            if current_publish_time < last_publish_time {
+                anyhow::bail!(
+                    "publish time is not increasing: {} <= {}",
+                    current_publish_time,
+                    last_publish_time
+                )
+            }
+            last_publish_time = current_publish_time;
+            prices_commitments.push(poseidon_hash::<E>(&prices_commitment_members));
+        }
+
+        let guardian_set_hash = guardian_set_hash::<E>(&[reporter])?;
+        let earliest_publish_time = messages_batch
+            .first()
+            .and_then(|messages| messages.first())
+            .map(|v| v.publish_time())
+            .unwrap_or_default();
+        let earliest_publish_time = fr_from_biguint::<E>(&BigUint::from(earliest_publish_time))?;
+
+        let prices_num = E::Fr::from_str(&prices_commitments.len().to_string()).unwrap();
+        let mut prices_commitment_base_sum = E::Fr::zero();
+        let mut prices_commitment = E::Fr::zero();
+        for (i, mut commitment) in prices_commitments.into_iter().enumerate() {
+            Field::add_assign(&mut prices_commitment_base_sum, &commitment);
+            let coef = E::Fr::from_str(&format!("{}", i)).unwrap();
+            Field::mul_assign(&mut commitment, &coef);
+            Field::add_assign(&mut prices_commitment, &commitment);
+        }
+
+        let commitment = poseidon_hash::<E>(&[
+            guardian_set_hash,
+            prices_commitment,
+            earliest_publish_time,
+            prices_num,
+            prices_commitment_base_sum,
+        ]);
+        Ok(Self {
+            messages_batch,
+            reporter,
+            public_input_data: PublicInputData {
+                guardian_set_hash,
+                prices_summarize: PricesSummarize {
+                    commitment: prices_commitment,
+                    num: prices_num,
+                    commitment_base_sum: prices_commitment_base_sum,
+                },
+                earliest_publish_time,
+            },
+            commitment,
+        })
+    }
+}
+
+impl<E: Engine, const NUM_PRICES: usize> Circuit<E> for CoinbasePriceOracle<E, NUM_PRICES> {
+    type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
+
+    fn synthesize<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<(), SynthesisError> {
+        utils::add_bitwise_logic_and_range_table(cs)?;
+        let temp_variable = Num::alloc(cs, Some(E::Fr::one()))?;
+        circuit_rescue_hash(cs, &[temp_variable])?; // Just to standardize the proof format
+
+        let reporter = Address::from_address_witness(cs, &self.reporter)?;
+        let mut is_valid = Boolean::constant(true);
+        let mut last_publish_time = Num::zero();
+        let mut earliest_publish_time = Num::zero();
+        let mut prices_commitments = vec![];
+        for (i, messages) in self.messages_batch.iter().enumerate() {
+            let mut prices_commitment_members = vec![];
+            let mut publish_time = Num::zero();
+            for (j, message) in messages.iter().enumerate() {
+                let message = AllocatedOpenOracleMessage::from_witness(cs, message)?;
+                let (is_current_valid, message) = message.verify(cs, &reporter)?;
+                is_valid = Boolean::and(cs, &is_valid, &is_current_valid)?;
+                prices_commitment_members.push(message.feed_id);
+                prices_commitment_members.push(message.price);
+                if j == 0 {
+                    publish_time = message.publish_time;
+                }
+            }
+            let (is_equal, is_greater) =
+                prepacked_long_comparison(cs, &[publish_time], &[last_publish_time], &[64])?;
+            let is_increasing = Boolean::or(cs, &is_equal, &is_greater)?;
+            is_valid = Boolean::and(cs, &is_valid, &is_increasing)?;
+            last_publish_time = publish_time;
+            if i == 0 {
+                earliest_publish_time = publish_time;
+            }
+            prices_commitments.push(circuit_poseidon_hash(cs, &prices_commitment_members)?);
+        }
+        Boolean::enforce_equal(cs, &is_valid, &Boolean::constant(true))?;
+
+        let mut prices_commitment_base_sum = Num::zero();
+        let mut prices_commitment = Num::zero();
+        let prices_num = prices_commitments.len();
+        for (i, commitment) in prices_commitments.into_iter().enumerate() {
+            prices_commitment_base_sum = prices_commitment_base_sum.add(cs, &commitment)?;
+            let coef = E::Fr::from_str(&format!("{}", i)).unwrap();
+            let x = commitment.mul(cs, &Num::Constant(coef))?;
+            prices_commitment = prices_commitment.add(cs, &x)?;
+        }
+        let prices_num = Num::Constant(E::Fr::from_str(&prices_num.to_string()).unwrap());
+
+        let reporter = reporter.inner().to_num_unchecked(cs)?;
+        let guardian_set_hash = circuit_poseidon_hash(cs, &[reporter])?;
+        let commitment = circuit_poseidon_hash(
+            cs,
+            &[
+                guardian_set_hash,
+                prices_commitment,
+                earliest_publish_time,
+                prices_num,
+                prices_commitment_base_sum,
+            ],
+        )?;
+        let expected_commitment = Num::alloc(cs, Some(self.commitment))?;
+        expected_commitment.enforce_equal(cs, &commitment)?;
+        expected_commitment.get_variable().inputize(cs)?;
+        Ok(())
+    }
+
+    fn declare_used_gates() -> Result<Vec<Box<dyn GateInternal<E>>>, SynthesisError> {
+        Ok(vec![
+            Self::MainGate::default().into_internal(),
+            Rescue5CustomGate.into_internal(), // Just to standardize the proof format
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::bellman::plonk::better_better_cs::cs::Circuit,
+        testing::{create_test_artifacts_with_optimized_gate, Bn256},
+    };
+
+    use super::CoinbasePriceOracle;
+    use crate::{
+        coinbase::{OpenOracleMessage, SignedOpenOracleMessage},
+        redstone::{
+            witness::{DataPackage, DataPoint},
+            PriceOracle,
+        },
+        utils::signer::RedstoneSigner,
+    };
+
+    #[test]
+    fn test_coinbase_price_oracle() -> anyhow::Result<()> {
+        let reporter = RedstoneSigner::new(0x61);
+        let sign = |symbol: &str, timestamp: u64, price: u64| {
+            let mut signed = SignedOpenOracleMessage {
+                message: OpenOracleMessage {
+                    timestamp,
+                    symbol: symbol.to_string(),
+                    price,
+                },
+                signature: [0u8; 65],
+            };
+            signed.signature = reporter.sign_hash(&signed.message_hash());
+            signed
+        };
+        let messages_batch = vec![
+            [
+                sign("ETH", 1705311690, 2_530_120_000),
+                sign("BTC", 1705311690, 42_800_000_000),
+            ],
+            [
+                sign("ETH", 1705311691, 2_530_250_000),
+                sign("BTC", 1705311691, 42_810_000_000),
+            ],
+        ];
+        let circuit =
+            CoinbasePriceOracle::<Bn256, 2>::new(messages_batch.clone(), reporter.address())?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        println!("Roughly {} gates", cs.n());
+
+        // Public input is the same as RedStone of the same signer, feeds and prices
+        let redstone_batch = [
+            (1705311690000, ["2530.12", "42800"]),
+            (1705311691000, ["2530.25", "42810"]),
+        ]
+        .map(|(timestamp, prices)| {
+            [("ETH", prices[0]), ("BTC", prices[1])].map(|(feed_id, price)| {
+                let package = DataPackage::new(vec![DataPoint::new(feed_id, price)], timestamp);
+                [reporter.sign_witness(package)]
+            })
+        })
+        .to_vec();
+        let redstone = PriceOracle::<Bn256, 1, 2>::new(redstone_batch, [reporter.address()])?;
+        assert_eq!(redstone.commitment, circuit.commitment);
+
+        // Messages must be signed by the reporter, and batches in order of publish time
+        assert!(CoinbasePriceOracle::<Bn256, 2>::new(
+            messages_batch.clone(),
+            RedstoneSigner::new(0x62).address()
+        )
+        .is_err());
+        let reversed = messages_batch.into_iter().rev().collect();
+        assert!(CoinbasePriceOracle::<Bn256, 2>::new(reversed, reporter.address()).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,363 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    utils::u64_to_fe,
+    vm::{partitioner::smart_and, primitives::uint256::UInt256},
+};
+use num_bigint::BigUint;
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    decimals::scale_factor,
+    gadgets::{
+        ecdsa::Signature,
+        ethereum::{circuit_eth_signed_message_hash, eth_signed_message_hash, Address},
+        keccak256,
+    },
+    redstone::{witness::recover_signer, SIGNATURE_BS},
+    utils::{fr_from_biguint, new_synthesis_error, num_from_be_bytes},
+};
+
+// Max number of bytes of symbols, which are committed as feed ids of 15 bytes
+pub const MAX_SYMBOL_BS: usize = 15;
+// Precision of prices reported by Coinbase
+pub const COINBASE_PRICE_DECIMALS: usize = 6;
+// Number of bytes of an ABI-encoded message whose kind and symbol fit in one word
+pub const OPEN_ORACLE_MESSAGE_BS: usize = 8 * 32;
+// Kind of messages carrying prices
+const PRICES_KIND: &[u8] = b"prices";
+// Address of the Coinbase reporter, i.e. 0xfCEAdAFab14d46e20144F48824d0C09B1a03F2BC
+pub const COINBASE_REPORTER: [u8; 20] = [
+    0xfc, 0xea, 0xda, 0xfa, 0xb1, 0x4d, 0x46, 0xe2, 0x01, 0x44, 0xf4, 0x88, 0x24, 0xd0, 0xc0, 0x9b,
+    0x1a, 0x03, 0xf2, 0xbc,
+];
+
+fn word(value: u64) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[24..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+/// Price in the Open Oracle format, i.e. `abi.encode(string kind, uint64 timestamp, string key,
+/// uint64 value)` with kind `prices`, as accepted by `OpenOraclePriceData.put`.
+///
+/// Prices carry [`COINBASE_PRICE_DECIMALS`] decimals, and timestamps are in seconds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpenOracleMessage {
+    pub timestamp: u64,
+    pub symbol: String,
+    pub price: u64,
+}
+
+impl OpenOracleMessage {
+    /// ABI-encoded message in [`OPEN_ORACLE_MESSAGE_BS`] bytes, where the symbol is at most 32
+    /// bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.extend(word(4 * 32));
+        bytes.extend(word(self.timestamp));
+        bytes.extend(word(6 * 32));
+        bytes.extend(word(self.price));
+        for string in [PRICES_KIND, self.symbol.as_bytes()] {
+            let mut padded = [0u8; 32];
+            padded[..string.len()].copy_from_slice(string);
+            bytes.extend(word(string.len() as u64));
+            bytes.extend(padded);
+        }
+        bytes
+    }
+
+    /// Decode a message of kind `prices`, which must be encoded as [`Self::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        if bytes.len() != OPEN_ORACLE_MESSAGE_BS {
+            anyhow::bail!("invalid message length {}", bytes.len())
+        }
+        let word_at = |i: usize| &bytes[i * 32..(i + 1) * 32];
+        let uint64_at = |i: usize| -> anyhow::Result<u64> {
+            let word = word_at(i);
+            if word[..24].iter().any(|b| *b != 0) {
+                anyhow::bail!("word {} 0x{} overflows uint64", i, hex::encode(word))
+            }
+            Ok(u64::from_be_bytes(word[24..].try_into().unwrap()))
+        };
+        let len = uint64_at(6)? as usize;
+        if len > 32 {
+            anyhow::bail!("symbol of {} bytes is unsupported", len)
+        }
+        let message = Self {
+            timestamp: uint64_at(1)?,
+            symbol: String::from_utf8(word_at(7)[..len].to_vec())?,
+            price: uint64_at(3)?,
+        };
+        if message.to_bytes() != bytes {
+            anyhow::bail!("message 0x{} is not of kind prices", hex::encode(bytes))
+        }
+        Ok(message)
+    }
+}
+
+/// Open Oracle message signed by a reporter, whose signature is checked over
+/// `keccak256(message)` as an Ethereum signed message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedOpenOracleMessage {
+    pub message: OpenOracleMessage,
+    pub signature: [u8; SIGNATURE_BS],
+}
+
+impl SignedOpenOracleMessage {
+    /// Parse a message and its signature as published by the Coinbase API, where the signature
+    /// is `abi.encode(bytes32 r, bytes32 s, uint8 v)`.
+    pub fn from_api(message: &[u8], signature: &[u8]) -> anyhow::Result<Self> {
+        if signature.len() != 3 * 32 || signature[64..95].iter().any(|b| *b != 0) {
+            anyhow::bail!("invalid signature 0x{}", hex::encode(signature))
+        }
+        let mut rsv = [0u8; SIGNATURE_BS];
+        rsv[..64].copy_from_slice(&signature[..64]);
+        rsv[64] = signature[95];
+        Ok(Self {
+            message: OpenOracleMessage::from_bytes(message)?,
+            signature: rsv,
+        })
+    }
+
+    /// Hash whose signature is checked, i.e. Ethereum signed message hash of `keccak256(message)`.
+    pub fn message_hash(&self) -> [u8; 32] {
+        eth_signed_message_hash(&Keccak256::digest(self.message.to_bytes()).into())
+    }
+
+    /// Publish time in milliseconds, which is the precision of RedStone timestamps.
+    pub fn publish_time(&self) -> u64 {
+        self.message.timestamp * 1000
+    }
+
+    /// Price rescaled by [`scale_factor`] to the precision of RedStone prices.
+    pub fn price(&self) -> BigUint {
+        BigUint::from(self.message.price) * scale_factor(COINBASE_PRICE_DECIMALS)
+    }
+
+    /// Symbol left-aligned in 15 bytes and prefixed with a zero byte, in the same way as RedStone
+    /// feed ids are committed.
+    pub fn feed_id(&self) -> BigUint {
+        let symbol = self.message.symbol.as_bytes();
+        let mut bytes = [0u8; MAX_SYMBOL_BS + 1];
+        bytes[1..1 + symbol.len()].copy_from_slice(symbol);
+        BigUint::from_bytes_be(&bytes)
+    }
+
+    /// Check the message is signed by `reporter`, and its symbol is supported.
+    pub fn check(&self, reporter: &[u8; 20]) -> anyhow::Result<()> {
+        let symbol = &self.message.symbol;
+        if symbol.is_empty() || symbol.len() > MAX_SYMBOL_BS || symbol.as_bytes().contains(&0) {
+            anyhow::bail!("unsupported symbol {:?}", symbol)
+        }
+        let recovered = recover_signer(&self.message_hash(), &self.signature)?;
+        if recovered != *reporter {
+            anyhow::bail!(
+                "price of {} is signed by 0x{}, expect 0x{}",
+                symbol,
+                hex::encode(recovered),
+                hex::encode(reporter)
+            )
+        }
+        Ok(())
+    }
+}
+
+/// Circuit representation of [`SignedOpenOracleMessage`]. The length of the symbol is fixed by
+/// the witness, so that the message has constant layout.
+#[derive(Debug, Clone)]
+pub struct AllocatedOpenOracleMessage<E: Engine> {
+    pub timestamp: [Byte<E>; 8],
+    pub symbol: Vec<Byte<E>>,
+    pub price: [Byte<E>; 8],
+    pub signature: Signature<E>,
+}
+
+/// Message verified by [`AllocatedOpenOracleMessage::verify`], in the shape of RedStone prices.
+#[derive(Debug, Clone, Copy)]
+pub struct VerifiedOpenOracleMessage<E: Engine> {
+    pub feed_id: Num<E>,
+    /// Price in [`TARGET_NUM_VALUE_DECIMALS`](crate::redstone::TARGET_NUM_VALUE_DECIMALS)
+    pub price: Num<E>,
+    /// Publish time in milliseconds
+    pub publish_time: Num<E>,
+}
+
+impl<E: Engine> AllocatedOpenOracleMessage<E> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &SignedOpenOracleMessage,
+    ) -> Result<Self, SynthesisError> {
+        let symbol = &witness.message.symbol;
+        if symbol.is_empty() || symbol.len() > MAX_SYMBOL_BS {
+            return Err(new_synthesis_error(format!(
+                "unsupported symbol {:?}",
+                symbol
+            )));
+        }
+        let mut alloc_bytes = |bytes: &[u8]| {
+            bytes
+                .iter()
+                .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let timestamp = alloc_bytes(&witness.message.timestamp.to_be_bytes())?
+            .try_into()
+            .unwrap();
+        let symbol = alloc_bytes(symbol.as_bytes())?;
+        let price = alloc_bytes(&witness.message.price.to_be_bytes())?
+            .try_into()
+            .unwrap();
+        let mut signature = witness.signature;
+        if signature[64] >= 27 {
+            signature[64] -= 27;
+        }
+        let signature = Signature::from_bytes_witness(cs, &signature)?;
+        Ok(Self {
+            timestamp,
+            symbol,
+            price,
+            signature,
+        })
+    }
+
+    /// Same as [`SignedOpenOracleMessage::message_hash`].
+    pub fn message_hash<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<[Byte<E>; 32], SynthesisError> {
+        let constant_word = |value: u64| word(value).map(Byte::constant);
+        let mut bytes = constant_word(4 * 32).to_vec();
+        bytes.extend([Byte::zero(); 24]);
+        bytes.extend(self.timestamp);
+        bytes.extend(constant_word(6 * 32));
+        bytes.extend([Byte::zero(); 24]);
+        bytes.extend(self.price);
+        bytes.extend(constant_word(PRICES_KIND.len() as u64));
+        let mut kind = PRICES_KIND.to_vec();
+        kind.resize(32, 0);
+        bytes.extend(kind.into_iter().map(Byte::constant));
+        bytes.extend(constant_word(self.symbol.len() as u64));
+        bytes.extend(self.symbol.iter());
+        bytes.resize(OPEN_ORACLE_MESSAGE_BS, Byte::zero());
+        let hash = keccak256::digest(cs, &bytes)?;
+        circuit_eth_signed_message_hash(cs, &hash)
+    }
+
+    /// Same as [`SignedOpenOracleMessage::check`]: check the message is signed by `reporter` and
+    /// its symbol has no zero byte. Return the verified message, which is only meaningful if the
+    /// check passes.
+    pub fn verify<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        reporter: &Address<E>,
+    ) -> Result<(Boolean, VerifiedOpenOracleMessage<E>), SynthesisError> {
+        let mut is_valid = vec![];
+        let message_hash = self.message_hash(cs)?;
+        let message_hash = UInt256::from_be_bytes_fixed(cs, &message_hash)?;
+        let (successful, (x, y)) = self.signature.ecrecover(cs, &message_hash)?;
+        let (x, y) = (
+            x.into_be_bytes(cs)?.try_into().unwrap(),
+            y.into_be_bytes(cs)?.try_into().unwrap(),
+        );
+        let address = Address::from_pubkey(cs, &x, &y)?;
+        is_valid.push(reporter.equals(cs, &address)?);
+        is_valid.push(successful);
+        for byte in self.symbol.iter() {
+            is_valid.push(byte.inner.equals(cs, &Num::zero())?.not());
+        }
+
+        let mut feed_id = self.symbol.clone();
+        feed_id.resize(MAX_SYMBOL_BS, Byte::zero());
+        // Prices below 2^64 never overflow the field when rescaled
+        let scale = Num::Constant(fr_from_biguint::<E>(&scale_factor(
+            COINBASE_PRICE_DECIMALS,
+        ))?);
+        let price = num_from_be_bytes(cs, &self.price)?.mul(cs, &scale)?;
+        let timestamp = num_from_be_bytes(cs, &self.timestamp)?;
+        let message = VerifiedOpenOracleMessage {
+            feed_id: num_from_be_bytes(cs, &feed_id)?,
+            price,
+            publish_time: timestamp.mul(cs, &Num::Constant(u64_to_fe(1000)))?,
+        };
+        Ok((smart_and(cs, &is_valid)?, message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::franklin_crypto::bellman::{
+        pairing::bn256::Bn256, SynthesisError,
+    };
+    use num_bigint::BigUint;
+
+    use super::{
+        AllocatedOpenOracleMessage, OpenOracleMessage, SignedOpenOracleMessage,
+        OPEN_ORACLE_MESSAGE_BS,
+    };
+    use crate::{
+        gadgets::ethereum::Address,
+        utils::{fr_from_biguint, signer::RedstoneSigner, testing::create_test_constraint_system},
+    };
+
+    #[test]
+    fn test_open_oracle_message() -> Result<(), SynthesisError> {
+        let reporter = RedstoneSigner::new(0x61);
+        let message = OpenOracleMessage {
+            timestamp: 1_700_000_000,
+            symbol: "ETH".to_string(),
+            price: 2_530_120_000,
+        };
+        let bytes = message.to_bytes();
+        assert_eq!(bytes.len(), OPEN_ORACLE_MESSAGE_BS);
+        assert_eq!(OpenOracleMessage::from_bytes(&bytes).unwrap(), message);
+        let mut other_kind = bytes.clone();
+        other_kind[160] = b'P';
+        assert!(OpenOracleMessage::from_bytes(&other_kind).is_err());
+
+        let mut signed = SignedOpenOracleMessage {
+            message,
+            signature: [0u8; 65],
+        };
+        signed.signature = reporter.sign_hash(&signed.message_hash());
+        let mut api_signature = signed.signature[..64].to_vec();
+        api_signature.extend([0u8; 31]);
+        api_signature.push(signed.signature[64]);
+        assert_eq!(
+            SignedOpenOracleMessage::from_api(&bytes, &api_signature).unwrap(),
+            signed
+        );
+        assert_eq!(
+            signed.price(),
+            BigUint::from(2_530_120_000_000_000_000_000u128)
+        );
+        signed.check(&reporter.address()).unwrap();
+        assert!(signed.check(&RedstoneSigner::new(0x62).address()).is_err());
+
+        let mut tampered = signed.clone();
+        tampered.message.price += 1;
+        assert!(tampered.check(&reporter.address()).is_err());
+
+        for (signed, expected) in [(signed, true), (tampered, false)] {
+            let cs = &mut create_test_constraint_system()?;
+            let address = Address::from_address_witness(cs, &reporter.address())?;
+            let allocated = AllocatedOpenOracleMessage::<Bn256>::from_witness(cs, &signed)?;
+            let (is_valid, verified) = allocated.verify(cs, &address)?;
+            assert_eq!(is_valid.get_value(), Some(expected));
+            assert_eq!(
+                verified.feed_id.get_value(),
+                Some(fr_from_biguint::<Bn256>(&signed.feed_id())?)
+            );
+            assert_eq!(
+                verified.price.get_value(),
+                Some(fr_from_biguint::<Bn256>(&signed.price())?)
+            );
+            assert!(cs.is_satisfied());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,5 @@
+mod circuit;
+mod message;
+
+pub use circuit::*;
+pub use message::*;
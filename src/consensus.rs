@@ -0,0 +1,423 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{ff::Field, Engine};
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{
+            plonk::better_better_cs::{
+                cs::{Circuit, ConstraintSystem, Gate, GateInternal},
+                gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
+            },
+            SynthesisError,
+        },
+        plonk::circuit::{
+            allocated_num::Num, boolean::Boolean, custom_rescue_gate::Rescue5CustomGate,
+        },
+    },
+    utils::u64_to_fe,
+    vm::{partitioner::smart_and, primitives::uint256::UInt256},
+};
+use num_bigint::BigUint;
+use pythnet_sdk::{
+    messages::Message,
+    wire::{from_slice, v1::MerklePriceUpdate},
+};
+
+use crate::{
+    gadgets::{
+        ethereum::Address,
+        poseidon::{circuit_poseidon_hash, poseidon_hash},
+        rescue::circuit_rescue_hash,
+    },
+    pyth::{
+        normalize_price, verify_price_update, verify_vaa_root, AmortizedPriceOracle, ParsedVaa,
+        PriceUpdate,
+    },
+    redstone::{
+        circuit::{blend_within_tolerance, median, normalize_value},
+        cross_oracle::{source_price, synthesize_source},
+        feed_id_bytes,
+        witness::{self, convert_string_to_bytes32, guardian_set_hash, SignedPackageWitness},
+        RedstoneConfig, ValidationPolicy, MAX_TOLERANCE_BPS,
+    },
+    stork::{
+        AllocatedStorkUpdate, SignedStorkUpdate, MAX_ASSET_ID_BS, QUANTIZED_VALUE_BS,
+        QUANTIZED_VALUE_DECIMALS,
+    },
+    utils::{self, fr_from_biguint},
+};
+
+/// Price of `feed_id` signed by `NUM_SIGNATURES` guardians of a RedStone data service.
+#[derive(Debug, Clone)]
+pub struct RedstoneSource<const NUM_SIGNATURES: usize> {
+    pub feed_id: String,
+    pub signed_packages: [SignedPackageWitness; NUM_SIGNATURES],
+    pub guardians: [[u8; 20]; NUM_SIGNATURES],
+}
+
+/// Price of `feed_id` proven by a pyth accumulator VAA signed by `guardian_set`.
+#[derive(Debug, Clone)]
+pub struct PythSource {
+    pub feed_id: [u8; 32],
+    pub vaa: Vec<u8>,
+    pub num_signatures: usize,
+    pub price_update: MerklePriceUpdate,
+    pub guardian_set: Vec<[u8; 20]>,
+}
+
+/// Price of `asset_id` signed by Stork `signer`.
+#[derive(Debug, Clone)]
+pub struct StorkSource {
+    pub asset_id: String,
+    pub update: SignedStorkUpdate,
+    pub signer: [u8; 20],
+}
+
+/// Circuit proving the price of one asset from RedStone, Pyth and Stork, which must agree
+/// pairwise within `tolerance_bps` basis points. The consensus price is the median of the three
+/// normalized prices, so that no single provider moves it beyond the others.
+///
+/// Feed ids of every provider are fixed by circuit. The only public input is the commitment
+/// `poseidon(redstone_guardian_set_hash, pyth_guardian_set_hash, stork_signer_hash, feed_id,
+/// median_price, redstone_publish_time, pyth_publish_time, stork_publish_time)`, where `feed_id`
+/// is the RedStone feed id truncated to 15 bytes, the signer hash is the guardian set hash of
+/// `[signer]`, and publish times are in the unit of each provider, i.e. milliseconds except for
+/// pyth in seconds.
+#[derive(Debug, Clone)]
+pub struct ConsensusPriceOracle<E: Engine, const NUM_REDSTONE: usize> {
+    pub tolerance_bps: u64,
+    pub redstone: RedstoneSource<NUM_REDSTONE>,
+    pub pyth: PythSource,
+    pub stork: StorkSource,
+    pub config: RedstoneConfig,
+    pub median_price: BigUint,
+    pub commitment: E::Fr,
+}
+
+impl<E: Engine, const NUM_REDSTONE: usize> ConsensusPriceOracle<E, NUM_REDSTONE> {
+    pub fn new(
+        tolerance_bps: u64,
+        redstone: RedstoneSource<NUM_REDSTONE>,
+        pyth: PythSource,
+        stork: StorkSource,
+    ) -> Result<Self, anyhow::Error> {
+        Self::new_with_config(
+            tolerance_bps,
+            redstone,
+            pyth,
+            stork,
+            RedstoneConfig::default(),
+        )
+    }
+
+    /// Same as [`Self::new`], but prices are normalized to `target_num_value_decimals` of
+    /// `config`, which also parameterizes RedStone packages.
+    pub fn new_with_config(
+        tolerance_bps: u64,
+        redstone: RedstoneSource<NUM_REDSTONE>,
+        pyth: PythSource,
+        stork: StorkSource,
+        config: RedstoneConfig,
+    ) -> Result<Self, anyhow::Error> {
+        if tolerance_bps > MAX_TOLERANCE_BPS {
+            anyhow::bail!(
+                "tolerance {} bps exceeds {}",
+                tolerance_bps,
+                MAX_TOLERANCE_BPS
+            )
+        }
+        let target_decimals = config.target_num_value_decimals;
+        let policy =
+            ValidationPolicy::new(vec![redstone.feed_id.clone()]).with_config(config.clone());
+        let redstone_price = source_price(&redstone.signed_packages, &redstone.guardians, &policy)?;
+
+        AmortizedPriceOracle::<E>::new(
+            pyth.vaa.clone(),
+            pyth.num_signatures,
+            vec![pyth.price_update.clone()],
+            pyth.guardian_set.clone(),
+        )?;
+        let message: Vec<u8> = pyth.price_update.message.clone().into();
+        let price_feed = match from_slice::<byteorder::BE, Message>(&message)? {
+            Message::PriceFeedMessage(price_feed) => price_feed,
+            _ => anyhow::bail!("invalid price feed message"),
+        };
+        if price_feed.feed_id != pyth.feed_id {
+            anyhow::bail!(
+                "pyth price of 0x{}, expect 0x{}",
+                hex::encode(price_feed.feed_id),
+                hex::encode(pyth.feed_id)
+            )
+        }
+        let pyth_price = normalize_price(price_feed.price, price_feed.exponent, target_decimals)?;
+
+        stork.update.check(&stork.signer)?;
+        if stork.update.asset_id != stork.asset_id {
+            anyhow::bail!(
+                "stork price of {}, expect {}",
+                stork.update.asset_id,
+                stork.asset_id
+            )
+        }
+        let stork_price = witness::normalize_value(
+            stork.update.quantized_value.magnitude(),
+            QUANTIZED_VALUE_DECIMALS,
+            target_decimals,
+        );
+
+        let prices = [redstone_price, pyth_price, stork_price];
+        for (i, j) in [(0, 1), (0, 2), (1, 2)] {
+            if witness::blend_within_tolerance(&prices[i], &prices[j], tolerance_bps).is_none() {
+                anyhow::bail!(
+                    "prices {} and {} of {} deviate more than {} bps",
+                    prices[i],
+                    prices[j],
+                    redstone.feed_id,
+                    tolerance_bps
+                )
+            }
+        }
+        let median_price = witness::median(&prices).unwrap();
+
+        let commitment = poseidon_hash::<E>(&[
+            guardian_set_hash::<E>(&redstone.guardians)?,
+            guardian_set_hash::<E>(&pyth.guardian_set)?,
+            guardian_set_hash::<E>(&[stork.signer])?,
+            fr_from_biguint::<E>(&BigUint::from_bytes_be(&feed_id_bytes(&redstone.feed_id)))?,
+            fr_from_biguint::<E>(&median_price)?,
+            fr_from_biguint::<E>(&BigUint::from(
+                redstone.signed_packages[0].package.timestamp,
+            ))?,
+            fr_from_biguint::<E>(&BigUint::from(price_feed.publish_time as u64))?,
+            fr_from_biguint::<E>(&BigUint::from(stork.update.publish_time()))?,
+        ]);
+        Ok(Self {
+            tolerance_bps,
+            redstone,
+            pyth,
+            stork,
+            config,
+            median_price,
+            commitment,
+        })
+    }
+}
+
+/// Check that `bytes` equal the constant `expected`.
+fn equals_constant<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    bytes: &[Byte<E>],
+    expected: &[u8],
+) -> Result<Boolean, SynthesisError> {
+    let is_equal = bytes
+        .iter()
+        .zip(expected.iter())
+        .map(|(byte, b)| Num::equals(cs, &byte.inner, &Num::Constant(u64_to_fe(*b as u64))))
+        .collect::<Result<Vec<_>, _>>()?;
+    smart_and(cs, &is_equal)
+}
+
+impl<E: Engine, const NUM_REDSTONE: usize> Circuit<E> for ConsensusPriceOracle<E, NUM_REDSTONE> {
+    type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
+
+    fn synthesize<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<(), SynthesisError> {
+        utils::add_bitwise_logic_and_range_table(cs)?;
+        let temp_variable = Num::alloc(cs, Some(E::Fr::one()))?;
+        circuit_rescue_hash(cs, &[temp_variable])?; // Just to standardize the proof format
+        let target_decimals = self.config.target_num_value_decimals;
+
+        // Feed id is fixed by circuit
+        let feed_id = convert_string_to_bytes32(&self.redstone.feed_id).map(Byte::<E>::constant);
+        let (redstone_guardian_set_hash, redstone_price, redstone_publish_time) =
+            synthesize_source(
+                cs,
+                &self.redstone.signed_packages,
+                &self.redstone.guardians,
+                &feed_id,
+                &self.config,
+            )?;
+
+        let mut is_valid = vec![];
+        let guardian_set = self
+            .pyth
+            .guardian_set
+            .iter()
+            .map(|g| Address::from_address_witness(cs, g))
+            .collect::<Result<Vec<_>, _>>()?;
+        let guardian_nums = guardian_set
+            .iter()
+            .map(|g| g.inner().to_num_unchecked(cs))
+            .collect::<Result<Vec<_>, _>>()?;
+        let pyth_guardian_set_hash = circuit_poseidon_hash(cs, &guardian_nums)?;
+        let vaa = ParsedVaa::from_bytes_witness(cs, &self.pyth.vaa, self.pyth.num_signatures)?;
+        let (is_root_valid, root) = verify_vaa_root(cs, &vaa, &guardian_set)?;
+        let price_update =
+            PriceUpdate::<E>::from_price_update_witness(cs, self.pyth.price_update.clone())?;
+        let (is_update_valid, pyth_price) = verify_price_update(cs, &root, &price_update, None)?;
+        let is_feed_id_valid = equals_constant(cs, &pyth_price.feed_id, &self.pyth.feed_id)?;
+        is_valid.extend([is_root_valid, is_update_valid, is_feed_id_valid]);
+        let pyth_publish_time = pyth_price.publish_time;
+        let pyth_price = pyth_price.normalized_price(cs, target_decimals)?;
+
+        let signer = Address::from_address_witness(cs, &self.stork.signer)?;
+        let update = AllocatedStorkUpdate::from_witness(cs, &self.stork.update)?;
+        let (is_update_valid, verified) = update.verify(cs, &signer)?;
+        let asset_id = {
+            let mut bytes = [0u8; MAX_ASSET_ID_BS];
+            let asset_id = self.stork.asset_id.as_bytes();
+            bytes[..asset_id.len()].copy_from_slice(asset_id);
+            Num::Constant(fr_from_biguint::<E>(&BigUint::from_bytes_be(&bytes))?)
+        };
+        let is_asset_id_valid = Num::equals(cs, &verified.feed_id, &asset_id)?;
+        is_valid.extend([is_update_valid, is_asset_id_valid]);
+        let stork_price = {
+            let mut bytes = [Byte::zero(); 32];
+            bytes[32 - QUANTIZED_VALUE_BS..].copy_from_slice(&update.quantized_value);
+            let price = UInt256::from_be_bytes_fixed(cs, &bytes)?;
+            normalize_value(cs, &price, QUANTIZED_VALUE_DECIMALS, target_decimals)?
+        };
+        let signer = signer.inner().to_num_unchecked(cs)?;
+        let stork_signer_hash = circuit_poseidon_hash(cs, &[signer])?;
+
+        let prices = [redstone_price, pyth_price, stork_price];
+        for (i, j) in [(0, 1), (0, 2), (1, 2)] {
+            let (is_consistent, _) =
+                blend_within_tolerance(cs, &prices[i], &prices[j], self.tolerance_bps)?;
+            is_valid.push(is_consistent);
+        }
+        let is_valid = smart_and(cs, &is_valid)?;
+        Boolean::enforce_equal(cs, &is_valid, &Boolean::constant(true))?;
+        let median_price = median(cs, &prices)?.to_num_unchecked(cs)?;
+
+        let feed_id_num = Num::Constant(fr_from_biguint::<E>(&BigUint::from_bytes_be(
+            &feed_id_bytes(&self.redstone.feed_id),
+        ))?);
+        let commitment = circuit_poseidon_hash(
+            cs,
+            &[
+                redstone_guardian_set_hash,
+                pyth_guardian_set_hash,
+                stork_signer_hash,
+                feed_id_num,
+                median_price,
+                redstone_publish_time,
+                pyth_publish_time,
+                verified.publish_time,
+            ],
+        )?;
+        let expected_commitment = Num::alloc(cs, Some(self.commitment))?;
+        expected_commitment.enforce_equal(cs, &commitment)?;
+        expected_commitment.get_variable().inputize(cs)?;
+        Ok(())
+    }
+
+    fn declare_used_gates() -> Result<Vec<Box<dyn GateInternal<E>>>, SynthesisError> {
+        Ok(vec![
+            Self::MainGate::default().into_internal(),
+            Rescue5CustomGate.into_internal(), // Just to standardize the proof format
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::bellman::plonk::better_better_cs::cs::Circuit,
+        testing::{create_test_artifacts_with_optimized_gate, Bn256},
+    };
+    use num_bigint::{BigInt, BigUint};
+    use pythnet_sdk::{
+        messages::Message,
+        wire::{from_slice, v1::MerklePriceUpdate},
+    };
+
+    use super::{ConsensusPriceOracle, PythSource, RedstoneSource, StorkSource};
+    use crate::{
+        pyth::GUARDIAN_SET,
+        redstone::witness::{DataPackage, DataPoint},
+        stork::SignedStorkUpdate,
+        utils::testing::{pyth_price_update, wormhole_vaa, RedstoneSigner},
+    };
+
+    #[test]
+    fn test_consensus_price_oracle() -> anyhow::Result<()> {
+        let price_update =
+            from_slice::<byteorder::BE, MerklePriceUpdate>(&hex::decode(pyth_price_update())?)?;
+        let message: Vec<u8> = price_update.message.clone().into();
+        let price_feed = match from_slice::<byteorder::BE, Message>(&message)? {
+            Message::PriceFeedMessage(price_feed) => price_feed,
+            _ => unreachable!(),
+        };
+        assert_eq!(price_feed.exponent, -8);
+        let pyth = PythSource {
+            feed_id: price_feed.feed_id,
+            vaa: hex::decode(wormhole_vaa())?,
+            num_signatures: 13,
+            price_update,
+            guardian_set: GUARDIAN_SET.to_vec(),
+        };
+
+        // RedStone agrees with pyth, while Stork is 0.2% higher
+        let pyth_price = price_feed.price as u64;
+        let value = format!(
+            "{}.{:08}",
+            pyth_price / 100_000_000,
+            pyth_price % 100_000_000
+        );
+        let signers = RedstoneSigner::generate(3);
+        let redstone = RedstoneSource {
+            feed_id: "BTC".to_string(),
+            signed_packages: [signers[0].sign_witness(DataPackage::new(
+                vec![DataPoint::new("BTC", value)],
+                price_feed.publish_time as u64 * 1000,
+            ))],
+            guardians: [signers[0].address()],
+        };
+        let stork = |deviation_bps: u64| {
+            let price = BigUint::from(pyth_price) * BigUint::from(10u64).pow(10);
+            let mut update = SignedStorkUpdate {
+                asset_id: "BTCUSD".to_string(),
+                timestamp_ns: price_feed.publish_time as u64 * 1_000_000_000,
+                quantized_value: BigInt::from(&price + &price * deviation_bps / 10_000u64),
+                publisher_merkle_root: [3u8; 32],
+                value_compute_alg_hash: [4u8; 32],
+                signature: [0u8; 65],
+            };
+            update.signature = signers[2].sign_hash(&update.message_hash(&signers[2].address()));
+            StorkSource {
+                asset_id: "BTCUSD".to_string(),
+                update,
+                signer: signers[2].address(),
+            }
+        };
+
+        let circuit =
+            ConsensusPriceOracle::<Bn256, 1>::new(50, redstone.clone(), pyth.clone(), stork(20))?;
+        assert_eq!(
+            circuit.median_price,
+            BigUint::from(pyth_price) * BigUint::from(10u64).pow(10)
+        );
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+
+        // A divergent provider is rejected
+        assert!(ConsensusPriceOracle::<Bn256, 1>::new(
+            50,
+            redstone.clone(),
+            pyth.clone(),
+            stork(100)
+        )
+        .is_err());
+        let mut circuit = circuit;
+        circuit.tolerance_bps = 10;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+
+        // Feed ids are checked
+        let mut stork = stork(20);
+        stork.asset_id = "ETHUSD".to_string();
+        assert!(ConsensusPriceOracle::<Bn256, 1>::new(50, redstone, pyth, stork).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,179 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    franklin_crypto::bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+    vm::primitives::uint256::UInt256,
+};
+use num_bigint::BigUint;
+
+use crate::{
+    binance::BINANCE_PRICE_DECIMALS,
+    coinbase::COINBASE_PRICE_DECIMALS,
+    dia::DIA_VALUE_DECIMALS,
+    redstone::{circuit, witness, DEFAULT_NUM_VALUE_DECIMALS, TARGET_NUM_VALUE_DECIMALS},
+    stork::QUANTIZED_VALUE_DECIMALS,
+    switchboard::RESULT_DECIMALS,
+    utils::new_synthesis_error,
+};
+
+/// Provider of values, whose decimals are looked up by [`feed_decimals`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Provider {
+    Api3,
+    Band,
+    Binance,
+    Chainlink,
+    Chronicle,
+    Coinbase,
+    Dia,
+    Pyth,
+    Redstone,
+    Stork,
+    Supra,
+    Switchboard,
+    Tellor,
+}
+
+/// Decimals of values of a feed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decimals {
+    /// Every value carries the same decimals.
+    Fixed(usize),
+    /// Each value carries its own decimals, e.g. Pyth exponents, Supra decimals and Band
+    /// multipliers, which are normalized by gadgets of the provider.
+    Carried,
+}
+
+impl Provider {
+    /// Decimals of feeds of the provider unless listed in [`FEED_DECIMALS`].
+    pub const fn decimals(&self) -> Decimals {
+        match self {
+            Provider::Api3 => Decimals::Fixed(18),
+            Provider::Band => Decimals::Carried,
+            Provider::Binance => Decimals::Fixed(BINANCE_PRICE_DECIMALS),
+            Provider::Chainlink => Decimals::Fixed(8),
+            Provider::Chronicle => Decimals::Fixed(18),
+            Provider::Coinbase => Decimals::Fixed(COINBASE_PRICE_DECIMALS),
+            Provider::Dia => Decimals::Fixed(DIA_VALUE_DECIMALS),
+            Provider::Pyth => Decimals::Carried,
+            Provider::Redstone => Decimals::Fixed(DEFAULT_NUM_VALUE_DECIMALS),
+            Provider::Stork => Decimals::Fixed(QUANTIZED_VALUE_DECIMALS),
+            Provider::Supra => Decimals::Carried,
+            Provider::Switchboard => Decimals::Fixed(RESULT_DECIMALS),
+            Provider::Tellor => Decimals::Fixed(18),
+        }
+    }
+}
+
+/// Feeds whose decimals differ from the convention of their provider, e.g. Chainlink feeds quoted
+/// in ETH, which carry 18 decimals instead of 8.
+pub const FEED_DECIMALS: &[(Provider, &str, usize)] = &[
+    (Provider::Chainlink, "BTC / ETH", 18),
+    (Provider::Chainlink, "LINK / ETH", 18),
+    (Provider::Chainlink, "USDC / ETH", 18),
+    (Provider::Chainlink, "USDT / ETH", 18),
+];
+
+/// Decimals of values of `feed` of `provider`.
+pub fn feed_decimals(provider: Provider, feed: &str) -> Decimals {
+    FEED_DECIMALS
+        .iter()
+        .find(|(p, f, _)| *p == provider && *f == feed)
+        .map(|(_, _, decimals)| Decimals::Fixed(*decimals))
+        .unwrap_or(provider.decimals())
+}
+
+/// Factor rescaling values of `decimals` up to [`TARGET_NUM_VALUE_DECIMALS`], for providers whose
+/// values are scaled by constant multiplication in circuit.
+pub fn scale_factor(decimals: usize) -> BigUint {
+    assert!(decimals <= TARGET_NUM_VALUE_DECIMALS);
+    BigUint::from(10u32).pow((TARGET_NUM_VALUE_DECIMALS - decimals) as u32)
+}
+
+/// Rescale `value` of `feed` of `provider` to [`TARGET_NUM_VALUE_DECIMALS`], rounding down if the
+/// precision is reduced. Fails if decimals of the feed are carried by values.
+pub fn normalize(provider: Provider, feed: &str, value: &BigUint) -> anyhow::Result<BigUint> {
+    match feed_decimals(provider, feed) {
+        Decimals::Fixed(decimals) => Ok(witness::normalize_value(
+            value,
+            decimals,
+            TARGET_NUM_VALUE_DECIMALS,
+        )),
+        Decimals::Carried => anyhow::bail!("decimals of {:?} feed {} are carried", provider, feed),
+    }
+}
+
+/// Same as [`normalize`]. Circuit is unsatisfied if the rescaled value overflows 256 bits.
+pub fn circuit_normalize<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    provider: Provider,
+    feed: &str,
+    value: &UInt256<E>,
+) -> Result<UInt256<E>, SynthesisError> {
+    match feed_decimals(provider, feed) {
+        Decimals::Fixed(decimals) => {
+            circuit::normalize_value(cs, value, decimals, TARGET_NUM_VALUE_DECIMALS)
+        }
+        Decimals::Carried => Err(new_synthesis_error(format!(
+            "decimals of {:?} feed {} are carried",
+            provider, feed
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::bellman::{pairing::bn256::Bn256, SynthesisError},
+        vm::primitives::uint256::UInt256,
+    };
+    use num_bigint::BigUint;
+
+    use super::{circuit_normalize, feed_decimals, normalize, Decimals, Provider};
+    use crate::utils::testing::create_test_constraint_system;
+
+    #[test]
+    fn test_normalize() -> Result<(), SynthesisError> {
+        assert_eq!(
+            feed_decimals(Provider::Chainlink, "ETH / USD"),
+            Decimals::Fixed(8)
+        );
+        assert_eq!(
+            feed_decimals(Provider::Chainlink, "USDC / ETH"),
+            Decimals::Fixed(18)
+        );
+        assert_eq!(feed_decimals(Provider::Pyth, "ETH/USD"), Decimals::Carried);
+        assert!(normalize(Provider::Pyth, "ETH/USD", &BigUint::from(1u32)).is_err());
+
+        for (provider, feed, value, expected) in [
+            (
+                Provider::Coinbase,
+                "ETH",
+                2_530_120_000u128,
+                2_530_120_000u128 * 10u128.pow(12),
+            ),
+            (
+                Provider::Chainlink,
+                "ETH / USD",
+                253_012_000_000,
+                253_012_000_000 * 10u128.pow(10),
+            ),
+            (
+                Provider::Chainlink,
+                "USDC / ETH",
+                395_000_000_000_000,
+                395_000_000_000_000,
+            ),
+        ] {
+            let value = BigUint::from(value);
+            let normalized = normalize(provider, feed, &value).unwrap();
+            assert_eq!(normalized, BigUint::from(expected));
+
+            let cs = &mut create_test_constraint_system()?;
+            let value = UInt256::<Bn256>::alloc_from_witness(cs, Some(value))?;
+            let circuit_normalized = circuit_normalize(cs, provider, feed, &value)?;
+            assert_eq!(circuit_normalized.get_value(), Some(normalized));
+            assert!(cs.is_satisfied());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,5 @@
+mod circuit;
+mod value;
+
+pub use circuit::*;
+pub use value::*;
@@ -0,0 +1,260 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    utils::u64_to_fe,
+    vm::{partitioner::smart_and, primitives::uint256::UInt256},
+};
+use num_bigint::BigUint;
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    decimals::scale_factor,
+    gadgets::{
+        ecdsa::Signature,
+        ethereum::{circuit_eth_signed_message_hash, eth_signed_message_hash, Address},
+        keccak256,
+    },
+    redstone::{witness::recover_signer, SIGNATURE_BS},
+    utils::{fr_from_biguint, new_synthesis_error, num_from_be_bytes},
+};
+
+// Max number of bytes of keys, which are committed as feed ids of 15 bytes
+pub const MAX_KEY_BS: usize = 15;
+// Precision of values of DIA oracles
+pub const DIA_VALUE_DECIMALS: usize = 8;
+
+/// Value of a key signed by a DIA key, i.e. the arguments of `DIAOracleV2.setValue(string key,
+/// uint128 value, uint128 timestamp)` whose signature is checked over
+/// `keccak256(key || value || timestamp)` as an Ethereum signed message.
+///
+/// Values carry [`DIA_VALUE_DECIMALS`] decimals, and timestamps are in seconds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedDiaValue {
+    pub key: String,
+    pub value: u128,
+    pub timestamp: u64,
+    pub signature: [u8; SIGNATURE_BS],
+}
+
+impl SignedDiaValue {
+    /// `abi.encodePacked(key, uint128 value, uint128 timestamp)`.
+    pub fn message(&self) -> Vec<u8> {
+        let mut message = self.key.as_bytes().to_vec();
+        message.extend(self.value.to_be_bytes());
+        message.extend((self.timestamp as u128).to_be_bytes());
+        message
+    }
+
+    /// Hash whose signature is checked, i.e. Ethereum signed message hash of `keccak256(message)`.
+    pub fn message_hash(&self) -> [u8; 32] {
+        eth_signed_message_hash(&Keccak256::digest(self.message()).into())
+    }
+
+    /// Publish time in milliseconds, which is the precision of RedStone timestamps.
+    pub fn publish_time(&self) -> u64 {
+        self.timestamp * 1000
+    }
+
+    /// Value rescaled by [`scale_factor`] to the precision of RedStone prices.
+    pub fn price(&self) -> BigUint {
+        BigUint::from(self.value) * scale_factor(DIA_VALUE_DECIMALS)
+    }
+
+    /// Key left-aligned in 15 bytes and prefixed with a zero byte, in the same way as RedStone
+    /// feed ids are committed.
+    pub fn feed_id(&self) -> BigUint {
+        let mut bytes = [0u8; MAX_KEY_BS + 1];
+        bytes[1..1 + self.key.len()].copy_from_slice(self.key.as_bytes());
+        BigUint::from_bytes_be(&bytes)
+    }
+
+    /// Check the value is signed by `signer`, and its key is supported.
+    pub fn check(&self, signer: &[u8; 20]) -> anyhow::Result<()> {
+        let len = self.key.len();
+        if len == 0 || len > MAX_KEY_BS || self.key.as_bytes().contains(&0) {
+            anyhow::bail!("unsupported key {:?}", self.key)
+        }
+        let recovered = recover_signer(&self.message_hash(), &self.signature)?;
+        if recovered != *signer {
+            anyhow::bail!(
+                "value of {} is signed by 0x{}, expect 0x{}",
+                self.key,
+                hex::encode(recovered),
+                hex::encode(signer)
+            )
+        }
+        Ok(())
+    }
+}
+
+/// Circuit representation of [`SignedDiaValue`].
+#[derive(Debug, Clone)]
+pub struct AllocatedDiaValue<E: Engine> {
+    pub key: Vec<Byte<E>>,
+    pub value: [Byte<E>; 16],
+    pub timestamp: [Byte<E>; 8],
+    pub signature: Signature<E>,
+}
+
+/// Value verified by [`AllocatedDiaValue::verify`], in the shape of RedStone prices.
+#[derive(Debug, Clone, Copy)]
+pub struct VerifiedDiaValue<E: Engine> {
+    pub feed_id: Num<E>,
+    /// Value in [`TARGET_NUM_VALUE_DECIMALS`](crate::redstone::TARGET_NUM_VALUE_DECIMALS)
+    pub price: Num<E>,
+    /// Publish time in milliseconds
+    pub publish_time: Num<E>,
+}
+
+impl<E: Engine> AllocatedDiaValue<E> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &SignedDiaValue,
+    ) -> Result<Self, SynthesisError> {
+        let len = witness.key.len();
+        if len == 0 || len > MAX_KEY_BS {
+            return Err(new_synthesis_error(format!(
+                "unsupported key {:?}",
+                witness.key
+            )));
+        }
+        let mut alloc_bytes = |bytes: &[u8]| {
+            bytes
+                .iter()
+                .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let key = alloc_bytes(witness.key.as_bytes())?;
+        let value = alloc_bytes(&witness.value.to_be_bytes())?
+            .try_into()
+            .unwrap();
+        let timestamp = alloc_bytes(&witness.timestamp.to_be_bytes())?
+            .try_into()
+            .unwrap();
+        let mut signature = witness.signature;
+        if signature[64] >= 27 {
+            signature[64] -= 27;
+        }
+        let signature = Signature::from_bytes_witness(cs, &signature)?;
+        Ok(Self {
+            key,
+            value,
+            timestamp,
+            signature,
+        })
+    }
+
+    /// Same as [`SignedDiaValue::message_hash`].
+    pub fn message_hash<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<[Byte<E>; 32], SynthesisError> {
+        let mut bytes = self.key.clone();
+        bytes.extend(self.value);
+        bytes.extend([Byte::zero(); 8]);
+        bytes.extend(self.timestamp);
+        let hash = keccak256::digest(cs, &bytes)?;
+        circuit_eth_signed_message_hash(cs, &hash)
+    }
+
+    /// Same as [`SignedDiaValue::check`]: check the value is signed by `signer` and its key has no
+    /// zero byte. Return the verified value, which is only meaningful if the check passes.
+    pub fn verify<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        signer: &Address<E>,
+    ) -> Result<(Boolean, VerifiedDiaValue<E>), SynthesisError> {
+        let mut is_valid = vec![];
+        let message_hash = self.message_hash(cs)?;
+        let message_hash = UInt256::from_be_bytes_fixed(cs, &message_hash)?;
+        let (successful, (x, y)) = self.signature.ecrecover(cs, &message_hash)?;
+        let (x, y) = (
+            x.into_be_bytes(cs)?.try_into().unwrap(),
+            y.into_be_bytes(cs)?.try_into().unwrap(),
+        );
+        let address = Address::from_pubkey(cs, &x, &y)?;
+        is_valid.push(signer.equals(cs, &address)?);
+        is_valid.push(successful);
+        for byte in self.key.iter() {
+            is_valid.push(byte.inner.equals(cs, &Num::zero())?.not());
+        }
+
+        let mut feed_id = self.key.clone();
+        feed_id.resize(MAX_KEY_BS, Byte::zero());
+        // Values below 2^128 never overflow the field when rescaled
+        let scale = Num::Constant(fr_from_biguint::<E>(&scale_factor(DIA_VALUE_DECIMALS))?);
+        let price = num_from_be_bytes(cs, &self.value)?.mul(cs, &scale)?;
+        let timestamp = num_from_be_bytes(cs, &self.timestamp)?;
+        let value = VerifiedDiaValue {
+            feed_id: num_from_be_bytes(cs, &feed_id)?,
+            price,
+            publish_time: timestamp.mul(cs, &Num::Constant(u64_to_fe(1000)))?,
+        };
+        Ok((smart_and(cs, &is_valid)?, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::franklin_crypto::bellman::{
+        pairing::bn256::Bn256, SynthesisError,
+    };
+    use num_bigint::BigUint;
+
+    use super::{AllocatedDiaValue, SignedDiaValue};
+    use crate::{
+        gadgets::ethereum::Address,
+        utils::{fr_from_biguint, signer::RedstoneSigner, testing::create_test_constraint_system},
+    };
+
+    #[test]
+    fn test_dia_value() -> Result<(), SynthesisError> {
+        let dia = RedstoneSigner::new(0x51);
+        let mut value = SignedDiaValue {
+            key: "BTC/USD".to_string(),
+            value: 4_312_345_000_000,
+            timestamp: 1_700_000_000,
+            signature: [0u8; 65],
+        };
+        value.signature = dia.sign_hash(&value.message_hash());
+        assert_eq!(value.message().len(), 7 + 32);
+        assert_eq!(
+            value.price(),
+            BigUint::from(43_123_450_000_000_000_000_000u128)
+        );
+        value.check(&dia.address()).unwrap();
+        assert!(value.check(&RedstoneSigner::new(0x52).address()).is_err());
+
+        let mut tampered = value.clone();
+        tampered.value += 1;
+        assert!(tampered.check(&dia.address()).is_err());
+
+        for (value, expected) in [(value, true), (tampered, false)] {
+            let cs = &mut create_test_constraint_system()?;
+            let signer = Address::from_address_witness(cs, &dia.address())?;
+            let allocated = AllocatedDiaValue::<Bn256>::from_witness(cs, &value)?;
+            let (is_valid, verified) = allocated.verify(cs, &signer)?;
+            assert_eq!(is_valid.get_value(), Some(expected));
+            assert_eq!(
+                verified.feed_id.get_value(),
+                Some(fr_from_biguint::<Bn256>(&value.feed_id())?)
+            );
+            assert_eq!(
+                verified.price.get_value(),
+                Some(fr_from_biguint::<Bn256>(&value.price())?)
+            );
+            assert_eq!(
+                verified.publish_time.get_value(),
+                Some(fr_from_biguint::<Bn256>(&BigUint::from(
+                    value.publish_time()
+                ))?)
+            );
+            assert!(cs.is_satisfied());
+        }
+        Ok(())
+    }
+}
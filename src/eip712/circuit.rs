@@ -0,0 +1,194 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{ff::Field, Engine};
+use advanced_circuit_component::franklin_crypto::{
+    bellman::{
+        plonk::better_better_cs::{
+            cs::{Circuit, ConstraintSystem, Gate, GateInternal},
+            gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
+        },
+        SynthesisError,
+    },
+    plonk::circuit::{allocated_num::Num, boolean::Boolean, custom_rescue_gate::Rescue5CustomGate},
+};
+use num_bigint::BigUint;
+
+use crate::{
+    gadgets::{
+        ethereum::Address,
+        poseidon::{circuit_poseidon_hash, poseidon_hash},
+        rescue::circuit_rescue_hash,
+    },
+    utils::{self, fr_from_biguint, num_from_be_bytes},
+};
+
+use super::typed_data::{AllocatedSignedTypedData, SignedTypedData, TypedDataSchema};
+
+/// Circuit verifying EIP-712 typed data of `schema` signed by `signer`, so that feeds signing
+/// bespoke structs, e.g. NAV reports of funds, are proven by describing their schema as constants.
+///
+/// Public input is the commitment `poseidon(signer, domain separator[..16], domain
+/// separator[16..], type hash[..16], type hash[16..], (value[..16], value[16..]) of each member of
+/// each signed data)`, from which consumers decode members in the same way as Solidity.
+#[derive(Debug, Clone)]
+pub struct Eip712PriceOracle<E: Engine> {
+    pub schema: TypedDataSchema,
+    pub signed_data: Vec<SignedTypedData>,
+    pub signer: [u8; 20],
+    pub commitment: E::Fr,
+}
+
+impl<E: Engine> Eip712PriceOracle<E> {
+    pub fn new(
+        schema: TypedDataSchema,
+        signed_data: Vec<SignedTypedData>,
+        signer: [u8; 20],
+    ) -> Result<Self, anyhow::Error> {
+        let domain_separator = schema.domain.separator();
+        let type_hash = schema.type_hash();
+        let mut input = vec![fr_from_biguint::<E>(&BigUint::from_bytes_be(&signer))?];
+        for chunk in [
+            &domain_separator[..16],
+            &domain_separator[16..],
+            &type_hash[..16],
+            &type_hash[16..],
+        ] {
+            input.push(fr_from_biguint::<E>(&BigUint::from_bytes_be(chunk))?);
+        }
+        for data in signed_data.iter() {
+            data.check(&schema, &signer)?;
+            for value in data.values.iter() {
+                input.push(fr_from_biguint::<E>(&BigUint::from_bytes_be(&value[..16]))?);
+                input.push(fr_from_biguint::<E>(&BigUint::from_bytes_be(&value[16..]))?);
+            }
+        }
+        Ok(Self {
+            schema,
+            signed_data,
+            signer,
+            commitment: poseidon_hash::<E>(&input),
+        })
+    }
+}
+
+impl<E: Engine> Circuit<E> for Eip712PriceOracle<E> {
+    type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
+
+    fn synthesize<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<(), SynthesisError> {
+        utils::add_bitwise_logic_and_range_table(cs)?;
+        let temp_variable = Num::alloc(cs, Some(E::Fr::one()))?;
+        circuit_rescue_hash(cs, &[temp_variable])?; // Just to standardize the proof format
+
+        let signer = Address::from_address_witness(cs, &self.signer)?;
+        let mut input = vec![signer.inner().to_num_unchecked(cs)?];
+        for hash in [self.schema.domain.separator(), self.schema.type_hash()] {
+            input.push(Num::Constant(fr_from_biguint::<E>(
+                &BigUint::from_bytes_be(&hash[..16]),
+            )?));
+            input.push(Num::Constant(fr_from_biguint::<E>(
+                &BigUint::from_bytes_be(&hash[16..]),
+            )?));
+        }
+        let mut is_valid = Boolean::constant(true);
+        for data in self.signed_data.iter() {
+            let data = AllocatedSignedTypedData::from_witness(cs, data)?;
+            let is_current_valid = data.verify(cs, &self.schema, &signer)?;
+            is_valid = Boolean::and(cs, &is_valid, &is_current_valid)?;
+            for value in data.values.iter() {
+                input.push(num_from_be_bytes(cs, &value[..16])?);
+                input.push(num_from_be_bytes(cs, &value[16..])?);
+            }
+        }
+        Boolean::enforce_equal(cs, &is_valid, &Boolean::constant(true))?;
+
+        let commitment = circuit_poseidon_hash(cs, &input)?;
+        let expected_commitment = Num::alloc(cs, Some(self.commitment))?;
+        expected_commitment.enforce_equal(cs, &commitment)?;
+        expected_commitment.get_variable().inputize(cs)?;
+        Ok(())
+    }
+
+    fn declare_used_gates() -> Result<Vec<Box<dyn GateInternal<E>>>, SynthesisError> {
+        Ok(vec![
+            Self::MainGate::default().into_internal(),
+            Rescue5CustomGate.into_internal(), // Just to standardize the proof format
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::bellman::{pairing::ff::Field, plonk::better_better_cs::cs::Circuit},
+        testing::{create_test_artifacts_with_optimized_gate, Bn256},
+    };
+
+    use super::Eip712PriceOracle;
+    use crate::{
+        eip712::{Domain, FieldType, SignedTypedData, TypedDataSchema},
+        utils::signer::RedstoneSigner,
+    };
+
+    const NAV: TypedDataSchema = TypedDataSchema {
+        domain: Domain {
+            name: "Fund NAV Oracle",
+            version: "2",
+            chain_id: 1,
+            verifying_contract: [0x77; 20],
+        },
+        primary_type: "NavReport",
+        fields: &[
+            ("fundId", FieldType::FixedBytes(32)),
+            ("nav", FieldType::Uint(256)),
+            ("reportedAt", FieldType::Uint(64)),
+        ],
+    };
+
+    #[test]
+    fn test_eip712_price_oracle() -> anyhow::Result<()> {
+        let signer = RedstoneSigner::new(0x51);
+        let sign = |fund: u8, nav: u128, reported_at: u64| {
+            let mut values = vec![[fund; 32], [0u8; 32], [0u8; 32]];
+            values[1][16..].copy_from_slice(&nav.to_be_bytes());
+            values[2][24..].copy_from_slice(&reported_at.to_be_bytes());
+            let mut signed = SignedTypedData {
+                values,
+                signature: [0u8; 65],
+            };
+            signed.signature = signer.sign_hash(&signed.message_hash(&NAV));
+            signed
+        };
+        let signed_data = vec![
+            sign(1, 1_024_500_000_000_000_000, 1_700_000_000),
+            sign(2, 998_100_000_000_000_000, 1_700_000_060),
+        ];
+
+        let circuit = Eip712PriceOracle::<Bn256>::new(NAV, signed_data.clone(), signer.address())?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        println!("Roughly {} gates", cs.n());
+
+        let mut tampered = circuit;
+        tampered.commitment.add_assign(&Field::one());
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        tampered.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+
+        // Signatures are bound to the domain of the schema
+        let other_domain = TypedDataSchema {
+            domain: Domain {
+                chain_id: 10,
+                ..NAV.domain
+            },
+            ..NAV
+        };
+        assert!(Eip712PriceOracle::<Bn256>::new(
+            other_domain,
+            signed_data.clone(),
+            signer.address()
+        )
+        .is_err());
+        let other = RedstoneSigner::new(0x52).address();
+        assert!(Eip712PriceOracle::<Bn256>::new(NAV, signed_data, other).is_err());
+        Ok(())
+    }
+}
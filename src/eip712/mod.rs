@@ -0,0 +1,5 @@
+mod circuit;
+mod typed_data;
+
+pub use circuit::*;
+pub use typed_data::*;
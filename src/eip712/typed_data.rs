@@ -0,0 +1,395 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    utils::u64_to_fe,
+    vm::{partitioner::smart_and, primitives::uint256::UInt256},
+};
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    gadgets::{
+        ecdsa::Signature,
+        eip712::{self, constant_word, DOMAIN_TYPE, TYPED_DATA_PREFIX},
+        ethereum::Address,
+    },
+    redstone::{witness::recover_signer, SIGNATURE_BS},
+    utils::new_synthesis_error,
+};
+
+/// Type of a struct member, which is encoded in one word by `encodeData` of EIP-712, i.e. one of
+/// the atomic types. Sizes are in bits for integers and in bytes for fixed-size bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldType {
+    Uint(usize),
+    Int(usize),
+    Address,
+    Bool,
+    FixedBytes(usize),
+}
+
+impl FieldType {
+    pub fn name(&self) -> String {
+        match self {
+            Self::Uint(bits) => format!("uint{}", bits),
+            Self::Int(bits) => format!("int{}", bits),
+            Self::Address => "address".to_string(),
+            Self::Bool => "bool".to_string(),
+            Self::FixedBytes(len) => format!("bytes{}", len),
+        }
+    }
+
+    /// Check the size is allowed by Solidity.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let is_valid = match self {
+            Self::Uint(bits) | Self::Int(bits) => *bits > 0 && *bits <= 256 && bits % 8 == 0,
+            Self::Address | Self::Bool => true,
+            Self::FixedBytes(len) => *len > 0 && *len <= 32,
+        };
+        if !is_valid {
+            anyhow::bail!("invalid type {}", self.name())
+        }
+        Ok(())
+    }
+
+    /// Check `word` is a canonical encoding of the type, i.e. integers are zero-padded or
+    /// sign-extended on the left and fixed-size bytes are zero-padded on the right.
+    pub fn check_word(&self, word: &[u8; 32]) -> anyhow::Result<()> {
+        let is_valid = match self {
+            Self::Uint(bits) => word[..32 - bits / 8].iter().all(|b| *b == 0),
+            Self::Int(bits) => {
+                let extension = if word[32 - bits / 8] >= 0x80 { 0xff } else { 0 };
+                word[..32 - bits / 8].iter().all(|b| *b == extension)
+            }
+            Self::Address => word[..12].iter().all(|b| *b == 0),
+            Self::Bool => word[..31].iter().all(|b| *b == 0) && word[31] <= 1,
+            Self::FixedBytes(len) => word[*len..].iter().all(|b| *b == 0),
+        };
+        if !is_valid {
+            anyhow::bail!("0x{} is not {}", hex::encode(word), self.name())
+        }
+        Ok(())
+    }
+}
+
+/// EIP-712 domain of a signer, which has all of `name`, `version`, `chainId` and
+/// `verifyingContract`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Domain {
+    pub name: &'static str,
+    pub version: &'static str,
+    pub chain_id: u64,
+    pub verifying_contract: [u8; 20],
+}
+
+impl Domain {
+    /// Domain separator, i.e. `hashStruct(domain)`.
+    pub fn separator(&self) -> [u8; 32] {
+        let mut chain_id = [0u8; 32];
+        chain_id[24..].copy_from_slice(&self.chain_id.to_be_bytes());
+        let mut verifying_contract = [0u8; 32];
+        verifying_contract[12..].copy_from_slice(&self.verifying_contract);
+        Keccak256::new()
+            .chain_update(Keccak256::digest(DOMAIN_TYPE))
+            .chain_update(Keccak256::digest(self.name))
+            .chain_update(Keccak256::digest(self.version))
+            .chain_update(chain_id)
+            .chain_update(verifying_contract)
+            .finalize()
+            .into()
+    }
+}
+
+/// Layout of EIP-712 typed data signed by a feed, which consumers describe as constants, e.g.
+/// `Price(bytes32 feedId,int256 value,uint64 timestamp)` in the domain of the feed. Only structs of
+/// atomic members are supported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TypedDataSchema {
+    pub domain: Domain,
+    pub primary_type: &'static str,
+    pub fields: &'static [(&'static str, FieldType)],
+}
+
+impl TypedDataSchema {
+    /// `encodeType` of the primary type.
+    pub fn encode_type(&self) -> String {
+        let fields = self
+            .fields
+            .iter()
+            .map(|(name, ty)| format!("{} {}", ty.name(), name))
+            .collect::<Vec<_>>();
+        format!("{}({})", self.primary_type, fields.join(","))
+    }
+
+    pub fn type_hash(&self) -> [u8; 32] {
+        Keccak256::digest(self.encode_type()).into()
+    }
+
+    /// Check the schema has members and all of their types are valid.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.fields.is_empty() {
+            anyhow::bail!("type {} has no member", self.primary_type)
+        }
+        for (_, ty) in self.fields.iter() {
+            ty.validate()?;
+        }
+        Ok(())
+    }
+}
+
+/// Typed data of a schema signed by a feed, where `values` are encoded members in the order of
+/// [`TypedDataSchema::fields`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedTypedData {
+    pub values: Vec<[u8; 32]>,
+    pub signature: [u8; SIGNATURE_BS],
+}
+
+impl SignedTypedData {
+    /// `hashStruct(message)`, i.e. `keccak256(typeHash || encoded members)`.
+    pub fn struct_hash(&self, schema: &TypedDataSchema) -> [u8; 32] {
+        let mut hasher = Keccak256::new_with_prefix(schema.type_hash());
+        for value in self.values.iter() {
+            hasher.update(value);
+        }
+        hasher.finalize().into()
+    }
+
+    /// Hash whose signature is checked, i.e. `keccak256("\x19\x01" || domainSeparator ||
+    /// hashStruct(message))`.
+    pub fn message_hash(&self, schema: &TypedDataSchema) -> [u8; 32] {
+        Keccak256::new_with_prefix(TYPED_DATA_PREFIX)
+            .chain_update(schema.domain.separator())
+            .chain_update(self.struct_hash(schema))
+            .finalize()
+            .into()
+    }
+
+    /// Check values are canonically encoded members of `schema` and signed by `signer`.
+    pub fn check(&self, schema: &TypedDataSchema, signer: &[u8; 20]) -> anyhow::Result<()> {
+        schema.validate()?;
+        if self.values.len() != schema.fields.len() {
+            anyhow::bail!(
+                "{} values for {} members of {}",
+                self.values.len(),
+                schema.fields.len(),
+                schema.primary_type
+            )
+        }
+        for (value, (_, ty)) in self.values.iter().zip(schema.fields.iter()) {
+            ty.check_word(value)?;
+        }
+        let recovered = recover_signer(&self.message_hash(schema), &self.signature)?;
+        if recovered != *signer {
+            anyhow::bail!(
+                "{} is signed by 0x{}, expect 0x{}",
+                schema.primary_type,
+                hex::encode(recovered),
+                hex::encode(signer)
+            )
+        }
+        Ok(())
+    }
+}
+
+/// Circuit representation of [`SignedTypedData`].
+#[derive(Debug, Clone)]
+pub struct AllocatedSignedTypedData<E: Engine> {
+    pub values: Vec<[Byte<E>; 32]>,
+    pub signature: Signature<E>,
+}
+
+impl<E: Engine> AllocatedSignedTypedData<E> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &SignedTypedData,
+    ) -> Result<Self, SynthesisError> {
+        let mut values = vec![];
+        for value in witness.values.iter() {
+            let mut bytes = [Byte::zero(); 32];
+            for (byte, b) in bytes.iter_mut().zip(value.iter()) {
+                *byte = Byte::from_u8_witness(cs, Some(*b))?;
+            }
+            values.push(bytes);
+        }
+        let mut signature = witness.signature;
+        if signature[64] >= 27 {
+            signature[64] -= 27;
+        }
+        let signature = Signature::from_bytes_witness(cs, &signature)?;
+        Ok(Self { values, signature })
+    }
+
+    /// Same as [`SignedTypedData::message_hash`], where the domain separator and the type hash are
+    /// constants of the circuit.
+    pub fn message_hash<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        schema: &TypedDataSchema,
+    ) -> Result<[Byte<E>; 32], SynthesisError> {
+        let type_hash = constant_word(&schema.type_hash());
+        let struct_hash = eip712::hash_struct(cs, &type_hash, &self.values)?;
+        let separator = constant_word(&schema.domain.separator());
+        eip712::typed_data_hash(cs, &separator, &struct_hash)
+    }
+
+    /// Same as [`SignedTypedData::check`]. Return whether values are canonically encoded members
+    /// of `schema` and signed by `signer`. It fails to synthesize if the number of values differs
+    /// from the members of `schema`.
+    pub fn verify<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        schema: &TypedDataSchema,
+        signer: &Address<E>,
+    ) -> Result<Boolean, SynthesisError> {
+        if self.values.len() != schema.fields.len() {
+            return Err(new_synthesis_error(format!(
+                "{} values for {} members of {}",
+                self.values.len(),
+                schema.fields.len(),
+                schema.primary_type
+            )));
+        }
+        let mut is_valid = vec![];
+        for (value, (_, ty)) in self.values.iter().zip(schema.fields.iter()) {
+            is_valid.extend(circuit_check_word(cs, ty, value)?);
+        }
+
+        let message_hash = self.message_hash(cs, schema)?;
+        let message_hash = UInt256::from_be_bytes_fixed(cs, &message_hash)?;
+        let (successful, (x, y)) = self.signature.ecrecover(cs, &message_hash)?;
+        let (x, y) = (
+            x.into_be_bytes(cs)?.try_into().unwrap(),
+            y.into_be_bytes(cs)?.try_into().unwrap(),
+        );
+        let address = Address::from_pubkey(cs, &x, &y)?;
+        is_valid.push(successful);
+        is_valid.push(signer.equals(cs, &address)?);
+        smart_and(cs, &is_valid)
+    }
+}
+
+/// Same as [`FieldType::check_word`]. Return flags which are all set if the check passes.
+fn circuit_check_word<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    ty: &FieldType,
+    word: &[Byte<E>; 32],
+) -> Result<Vec<Boolean>, SynthesisError> {
+    let (padding, extension) = match ty {
+        FieldType::Uint(bits) => (&word[..32 - bits / 8], Num::zero()),
+        FieldType::Int(bits) if *bits == 256 => (&word[..0], Num::zero()),
+        FieldType::Int(bits) => {
+            let is_negative = word[32 - bits / 8].inner.into_bits_le(cs, Some(8))?[7];
+            let extension =
+                Num::from_boolean_is(is_negative).mul(cs, &Num::Constant(u64_to_fe(0xff)))?;
+            (&word[..32 - bits / 8], extension)
+        }
+        FieldType::Address => (&word[..12], Num::zero()),
+        FieldType::Bool => (&word[..31], Num::zero()),
+        FieldType::FixedBytes(len) => (&word[*len..], Num::zero()),
+    };
+    let mut flags = vec![];
+    for byte in padding.iter() {
+        flags.push(Num::equals(cs, &byte.inner, &extension)?);
+    }
+    if *ty == FieldType::Bool {
+        let is_false = Num::equals(cs, &word[31].inner, &Num::zero())?;
+        let is_true = Num::equals(cs, &word[31].inner, &Num::one())?;
+        flags.push(Boolean::or(cs, &is_false, &is_true)?);
+    }
+    Ok(flags)
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::franklin_crypto::bellman::{
+        pairing::bn256::Bn256, SynthesisError,
+    };
+
+    use super::{AllocatedSignedTypedData, Domain, FieldType, SignedTypedData, TypedDataSchema};
+    use crate::{
+        gadgets::ethereum::Address,
+        utils::{signer::RedstoneSigner, testing::create_test_constraint_system},
+    };
+
+    const MAIL: TypedDataSchema = TypedDataSchema {
+        domain: Domain {
+            name: "Ether Mail",
+            version: "1",
+            chain_id: 1,
+            verifying_contract: [0xcc; 20],
+        },
+        primary_type: "Mail",
+        fields: &[
+            ("from", FieldType::Address),
+            ("amount", FieldType::Int(64)),
+            ("paid", FieldType::Bool),
+            ("memo", FieldType::FixedBytes(4)),
+        ],
+    };
+
+    #[test]
+    fn test_signed_typed_data() -> Result<(), SynthesisError> {
+        // Domain separator of the example of EIP-712
+        assert_eq!(
+            hex::encode(MAIL.domain.separator()),
+            "f2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f"
+        );
+        assert_eq!(
+            MAIL.encode_type(),
+            "Mail(address from,int64 amount,bool paid,bytes4 memo)"
+        );
+
+        let signer = RedstoneSigner::new(0x42);
+        let sign = |values: Vec<[u8; 32]>| {
+            let mut signed = SignedTypedData {
+                values,
+                signature: [0u8; 65],
+            };
+            signed.signature = signer.sign_hash(&signed.message_hash(&MAIL));
+            signed
+        };
+        let mut from = [0u8; 32];
+        from[12..].copy_from_slice(&[0xcd; 20]);
+        let mut amount = [0xffu8; 32];
+        amount[24..].copy_from_slice(&(-42i64).to_be_bytes());
+        let mut paid = [0u8; 32];
+        paid[31] = 1;
+        let mut memo = [0u8; 32];
+        memo[..4].copy_from_slice(b"navs");
+        let valid = sign(vec![from, amount, paid, memo]);
+        valid.check(&MAIL, &signer.address()).unwrap();
+        assert!(valid
+            .check(&MAIL, &RedstoneSigner::new(0x43).address())
+            .is_err());
+        let mut not_extended = amount;
+        not_extended[0] = 0;
+        let mut not_bool = paid;
+        not_bool[31] = 2;
+        let mut not_padded = memo;
+        not_padded[4] = 1;
+        let invalid = [
+            sign(vec![from, not_extended, paid, memo]),
+            sign(vec![from, amount, not_bool, memo]),
+            sign(vec![from, amount, paid, not_padded]),
+        ];
+        for signed in invalid.iter() {
+            assert!(signed.check(&MAIL, &signer.address()).is_err());
+        }
+
+        let mut cases = vec![(valid, true)];
+        cases.extend(invalid.into_iter().map(|signed| (signed, false)));
+        for (signed, expected) in cases {
+            let cs = &mut create_test_constraint_system()?;
+            let address = Address::from_address_witness(cs, &signer.address())?;
+            let allocated = AllocatedSignedTypedData::<Bn256>::from_witness(cs, &signed)?;
+            let is_valid = allocated.verify(cs, &MAIL, &address)?;
+            assert_eq!(is_valid.get_value(), Some(expected));
+            assert!(cs.is_satisfied());
+        }
+        Ok(())
+    }
+}
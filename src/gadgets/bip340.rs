@@ -0,0 +1,155 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    vm::{partitioner::smart_and, primitives::uint256::UInt256},
+};
+use secp256k1::{schnorr::Signature, Message, XOnlyPublicKey, SECP256K1};
+use sha2::{Digest, Sha256};
+
+use super::{
+    schnorr::{recover_combination, secp256k1_order},
+    sha256,
+};
+
+pub const PUBKEY_BS: usize = 32;
+pub const SIGNATURE_BS: usize = 64;
+const CHALLENGE_TAG: &[u8] = b"BIP0340/challenge";
+
+/// `sha256(sha256(tag) || sha256(tag) || data)` of BIP-340.
+pub fn tagged_hash(tag: &[u8], data: &[u8]) -> [u8; 32] {
+    let tag = Sha256::digest(tag);
+    Sha256::new()
+        .chain_update(tag)
+        .chain_update(tag)
+        .chain_update(data)
+        .finalize()
+        .into()
+}
+
+/// Verify BIP-340 `signature` of `message` against the x-only `pubkey`.
+pub fn verify_signature(
+    pubkey: &[u8; PUBKEY_BS],
+    message: &[u8; 32],
+    signature: &[u8; SIGNATURE_BS],
+) -> bool {
+    let (Ok(pubkey), Ok(signature)) = (
+        XOnlyPublicKey::from_slice(pubkey),
+        Signature::from_slice(signature),
+    ) else {
+        return false;
+    };
+    SECP256K1
+        .verify_schnorr(&signature, &Message::from_digest(*message), &pubkey)
+        .is_ok()
+}
+
+/// Same as [`verify_signature`].
+///
+/// The nonce point `s * G - e * P` is recovered by `ecrecover` as in
+/// [`super::schnorr::SchnorrPublicKey::verify`], and then must have x coordinate `r` and even y
+/// coordinate. Public keys whose x coordinate is at least the group order are rejected, which
+/// occur with probability about 2^-128.
+pub fn verify<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    pubkey: &[Byte<E>; PUBKEY_BS],
+    message: &[Byte<E>; 32],
+    signature: &[Byte<E>; SIGNATURE_BS],
+) -> Result<Boolean, SynthesisError> {
+    let (r, s) = signature.split_at(32);
+    let tag = Sha256::digest(CHALLENGE_TAG).map(Byte::constant);
+    let preimage = [&tag[..], &tag[..], r, &pubkey[..], &message[..]].concat();
+    let challenge = sha256::digest(cs, &preimage)?;
+
+    let order = UInt256::constant(secp256k1_order());
+    let x = UInt256::from_be_bytes_fixed(cs, pubkey)?;
+    let s = UInt256::from_be_bytes_fixed(cs, s.try_into().unwrap())?;
+    let challenge = UInt256::from_be_bytes_fixed(cs, &challenge)?;
+    let (_, is_x_in_range) = x.sub(cs, &order)?;
+    let (_, is_s_in_range) = s.sub(cs, &order)?;
+    let (is_recovered, (qx, qy)) = recover_combination(cs, &x, &Byte::zero(), &s, &challenge)?;
+
+    let mut is_valid = vec![is_x_in_range, is_s_in_range, is_recovered];
+    for (byte, expected) in qx.into_be_bytes(cs)?.iter().zip(r) {
+        is_valid.push(Num::equals(cs, &byte.inner, &expected.inner)?);
+    }
+    let qy = qy.into_be_bytes(cs)?;
+    let is_y_odd = qy[31].inner.into_bits_le(cs, Some(8))?[0];
+    is_valid.push(is_y_odd.not());
+    smart_and(cs, &is_valid)
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte,
+        franklin_crypto::bellman::{
+            pairing::bn256::Bn256, plonk::better_better_cs::cs::ConstraintSystem, SynthesisError,
+        },
+    };
+
+    use super::{tagged_hash, verify, verify_signature};
+    use crate::utils::{signer::Bip340Signer, testing::create_test_constraint_system};
+
+    fn allocate<CS: ConstraintSystem<Bn256>, const N: usize>(
+        cs: &mut CS,
+        bytes: &[u8; N],
+    ) -> Result<[Byte<Bn256>; N], SynthesisError> {
+        let mut allocated = [Byte::zero(); N];
+        for (byte, b) in allocated.iter_mut().zip(bytes) {
+            *byte = Byte::from_u8_witness(cs, Some(*b))?;
+        }
+        Ok(allocated)
+    }
+
+    #[test]
+    fn test_bip340() -> Result<(), SynthesisError> {
+        // Test vector 0 of BIP-340
+        let pubkey: [u8; 32] =
+            hex::decode("F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        let signature: [u8; 64] = hex::decode("E907831F80848D1069A5371B402410364BDF1C5F8307B0084C55F1CE2DCA821525F66A4A85EA8B71E482A74F382D2CE5EBEEE8FDB2172F477DF4900D310536C0").unwrap().try_into().unwrap();
+        assert!(verify_signature(&pubkey, &[0u8; 32], &signature));
+        let mut data = signature[..32].to_vec();
+        data.extend(pubkey);
+        data.extend([0u8; 32]);
+        assert_eq!(
+            hex::encode(tagged_hash(b"BIP0340/challenge", &data)),
+            "6bb6b93a91f2ecc0cd924f4f9baabb5e6eb21745bb00f2cebdaac908bb5d86ce"
+        );
+
+        let signer = Bip340Signer::new(3);
+        let message = [7u8; 32];
+        let other = signer.sign(&[8u8; 32]);
+        assert!(verify_signature(
+            &signer.pubkey(),
+            &message,
+            &signer.sign(&message)
+        ));
+        assert!(!verify_signature(&signer.pubkey(), &message, &other));
+
+        let cases = [
+            (pubkey, [0u8; 32], signature, true),
+            (signer.pubkey(), message, signer.sign(&message), true),
+            (signer.pubkey(), message, other, false),
+            (pubkey, message, signature, false),
+        ];
+        for (pubkey, message, signature, expected) in cases {
+            let cs = &mut create_test_constraint_system()?;
+            let n = cs.n();
+            let pubkey = allocate(cs, &pubkey)?;
+            let message = allocate(cs, &message)?;
+            let signature = allocate(cs, &signature)?;
+            let is_valid = verify(cs, &pubkey, &message, &signature)?;
+            println!("Roughly {} gates", cs.n() - n);
+            assert_eq!(is_valid.get_value(), Some(expected));
+            assert!(cs.is_satisfied());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,243 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{ff::Field, Engine};
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{boolean::Boolean, linear_combination::LinearCombination},
+    },
+};
+
+use super::sha512::{add, rotr, word_constant, Word, WORD_BITS};
+
+pub const WIDTH_HASH_BYTES: usize = 32;
+pub const BLOCK_BYTES: usize = 128;
+const ROUNDS: usize = 12;
+
+const IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+const SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+fn word_from_le_bytes<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    bytes: &[Byte<E>],
+) -> Result<Word, SynthesisError> {
+    let mut word = [Boolean::constant(false); WORD_BITS];
+    for (i, byte) in bytes.iter().enumerate() {
+        let bits = byte.inner.into_bits_le(cs, Some(8))?;
+        word[i * 8..(i + 1) * 8].copy_from_slice(&bits);
+    }
+    Ok(word)
+}
+
+fn word_into_le_bytes<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    word: &Word,
+) -> Result<[Byte<E>; 8], SynthesisError> {
+    let mut bytes = [Byte::zero(); 8];
+    for (i, bits) in word.chunks_exact(8).enumerate() {
+        let mut lc = LinearCombination::zero();
+        let mut coeff = E::Fr::one();
+        for bit in bits {
+            lc.add_assign_boolean_with_coeff(bit, coeff);
+            coeff.double();
+        }
+        let num = lc.into_num(cs)?;
+        bytes[i] = Byte::from_num_unconstrained(cs, num);
+    }
+    Ok(bytes)
+}
+
+fn xor<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    a: &Word,
+    b: &Word,
+) -> Result<Word, SynthesisError> {
+    let mut out = [Boolean::constant(false); WORD_BITS];
+    for i in 0..WORD_BITS {
+        out[i] = Boolean::xor(cs, &a[i], &b[i])?;
+    }
+    Ok(out)
+}
+
+// Mixing function G on v[a], v[b], v[c], v[d] with message words x and y
+#[allow(clippy::too_many_arguments)]
+fn mix<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    v: &mut [Word; 16],
+    a: usize,
+    b: usize,
+    c: usize,
+    d: usize,
+    x: &Word,
+    y: &Word,
+) -> Result<(), SynthesisError> {
+    v[a] = add(cs, &[&v[a], &v[b], x], 0)?;
+    v[d] = rotr(&xor(cs, &v[d], &v[a])?, 32);
+    v[c] = add(cs, &[&v[c], &v[d]], 0)?;
+    v[b] = rotr(&xor(cs, &v[b], &v[c])?, 24);
+    v[a] = add(cs, &[&v[a], &v[b], y], 0)?;
+    v[d] = rotr(&xor(cs, &v[d], &v[a])?, 16);
+    v[c] = add(cs, &[&v[c], &v[d]], 0)?;
+    v[b] = rotr(&xor(cs, &v[b], &v[c])?, 63);
+    Ok(())
+}
+
+// Compress `block` into `state`, where `counter` is the number of bytes hashed so far including
+// the block, and `is_last` flags the final block.
+fn compress<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    state: &[Word; 8],
+    block: &[Byte<E>],
+    counter: u128,
+    is_last: bool,
+) -> Result<[Word; 8], SynthesisError> {
+    let mut m = Vec::with_capacity(16);
+    for chunk in block.chunks_exact(8) {
+        m.push(word_from_le_bytes(cs, chunk)?);
+    }
+
+    let mut v = [[Boolean::constant(false); WORD_BITS]; 16];
+    v[..8].copy_from_slice(state);
+    let mut iv = IV;
+    iv[4] ^= counter as u64;
+    iv[5] ^= (counter >> 64) as u64;
+    if is_last {
+        iv[6] = !iv[6];
+    }
+    for (word, value) in v[8..].iter_mut().zip(iv) {
+        *word = word_constant(value);
+    }
+
+    for round in 0..ROUNDS {
+        let s = &SIGMA[round % 10];
+        mix(cs, &mut v, 0, 4, 8, 12, &m[s[0]], &m[s[1]])?;
+        mix(cs, &mut v, 1, 5, 9, 13, &m[s[2]], &m[s[3]])?;
+        mix(cs, &mut v, 2, 6, 10, 14, &m[s[4]], &m[s[5]])?;
+        mix(cs, &mut v, 3, 7, 11, 15, &m[s[6]], &m[s[7]])?;
+        mix(cs, &mut v, 0, 5, 10, 15, &m[s[8]], &m[s[9]])?;
+        mix(cs, &mut v, 1, 6, 11, 12, &m[s[10]], &m[s[11]])?;
+        mix(cs, &mut v, 2, 7, 8, 13, &m[s[12]], &m[s[13]])?;
+        mix(cs, &mut v, 3, 4, 9, 14, &m[s[14]], &m[s[15]])?;
+    }
+
+    let mut next = *state;
+    for (i, word) in next.iter_mut().enumerate() {
+        let t = xor(cs, word, &v[i])?;
+        *word = xor(cs, &t, &v[i + 8])?;
+    }
+    Ok(next)
+}
+
+// Unkeyed BLAKE2b digest of `bytes` truncated to `len` bytes
+fn hash<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    bytes: &[Byte<E>],
+    len: usize,
+) -> Result<Vec<Byte<E>>, SynthesisError> {
+    assert!(len > 0 && len <= 64);
+    let mut state = IV;
+    state[0] ^= 0x01010000 ^ len as u64;
+    let mut state = state.map(word_constant);
+
+    // The last block is padded with zeros, and the empty input is hashed as one zero block
+    let num_blocks = ((bytes.len() + BLOCK_BYTES - 1) / BLOCK_BYTES).max(1);
+    let mut padded = bytes.to_vec();
+    padded.resize(num_blocks * BLOCK_BYTES, Byte::zero());
+    for (i, block) in padded.chunks_exact(BLOCK_BYTES).enumerate() {
+        let is_last = i + 1 == num_blocks;
+        let counter = if is_last {
+            bytes.len()
+        } else {
+            (i + 1) * BLOCK_BYTES
+        };
+        state = compress(cs, &state, block, counter as u128, is_last)?;
+    }
+
+    let mut digest = Vec::with_capacity(64);
+    for word in state.iter() {
+        digest.extend(word_into_le_bytes(cs, word)?);
+    }
+    digest.truncate(len);
+    Ok(digest)
+}
+
+/// BLAKE2b-256 digest of `bytes`. The length of `bytes` is fixed at synthesis, so the padding is
+/// constant.
+pub fn digest<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    bytes: &[Byte<E>],
+) -> Result<[Byte<E>; WIDTH_HASH_BYTES], SynthesisError> {
+    let digest = hash(cs, bytes, WIDTH_HASH_BYTES)?;
+    Ok(digest.try_into().expect("digest is 32 bytes"))
+}
+
+/// Same as [`digest`], but BLAKE2b-512.
+pub fn digest512<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    bytes: &[Byte<E>],
+) -> Result<[Byte<E>; 64], SynthesisError> {
+    let digest = hash(cs, bytes, 64)?;
+    Ok(digest.try_into().expect("digest is 64 bytes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte, franklin_crypto::bellman::SynthesisError,
+    };
+
+    use super::{digest, digest512};
+    use crate::utils::testing::create_test_constraint_system;
+
+    #[test]
+    fn test_blake2b() -> Result<(), SynthesisError> {
+        let mut cs = create_test_constraint_system()?;
+        let cs = &mut cs;
+        let block = (0..128).collect::<Vec<u8>>();
+        let overflow = (0..129).collect::<Vec<u8>>();
+        let cases = [
+            (&b""[..], "0e5751c026e543b2e8ab2eb06099daa1d1e5df47778f7787faab45cdf12fe3a8", "786a02f742015903c6c6fd852552d272912f4740e15847618a86e217f71f5419d25e1031afee585313896444934eb04b903a685b1448b755d56f701afe9be2ce"),
+            (&b"abc"[..], "bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319", "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d17d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"),
+            // A full block is not followed by an empty one
+            (&block[..], "c3582f71ebb2be66fa5dd750f80baae97554f3b015663c8be377cfcb2488c1d1", "2319e3789c47e2daa5fe807f61bec2a1a6537fa03f19ff32e87eecbfd64b7e0e8ccff439ac333b040f19b0c4ddd11a61e24ac1fe0f10a039806c5dcc0da3d115"),
+            (&overflow[..], "f7f3c46ba2564ff4c4c162da1f5b605f9f1c4aa6a20652a9f9a337c1a2f5b9c9", "f59711d44a031d5f97a9413c065d1e614c417ede998590325f49bad2fd444d3e4418be19aec4e11449ac1a57207898bc57d76a1bcf3566292c20c683a5c4648f"),
+        ];
+        for (input, expected, expected512) in cases {
+            let n = cs.n();
+            let input_bytes = input
+                .iter()
+                .map(|b| Byte::from_u8_witness(cs, Some(*b)).unwrap())
+                .collect::<Vec<_>>();
+            let hash = digest(cs, &input_bytes)?;
+            let hash = Byte::get_byte_value_multiple(&hash).unwrap();
+            assert_eq!(hex::encode(hash), expected);
+            println!("Roughly {} gates", cs.n() - n);
+
+            let hash = digest512(cs, &input_bytes)?;
+            let hash = Byte::get_byte_value_multiple(&hash).unwrap();
+            assert_eq!(hex::encode(hash), expected512);
+        }
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+}
@@ -0,0 +1,578 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{
+    bn256::{Bn256, Fq, Fq2, Fr, G1Affine, G2Affine, G1, G2},
+    ff::{Field, PrimeField, PrimeFieldRepr, SqrtField},
+    CurveAffine, CurveProjective, Engine,
+};
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{
+            allocated_num::Num,
+            bigint_new::{bigint::repr_to_biguint, FieldElement, RnsParameters},
+            boolean::{AllocatedBit, Boolean},
+        },
+    },
+    vm::{partitioner::smart_and, primitives::uint256::UInt256},
+};
+use num_bigint::BigUint;
+use sha3::{Digest, Keccak256};
+
+use super::{
+    ecdsa::{uint256_inner, CHUNK_BITLEN},
+    keccak256,
+};
+
+pub const G1_POINT_BS: usize = 64;
+pub const G2_POINT_BS: usize = 128;
+const B_COEF: u64 = 3;
+// Increments tried by hashing to G1 in circuit, each of which fails with probability 1/2
+const MAX_HASH_TO_G1_TRIES: usize = 64;
+const FQ_MODULUS: &str =
+    "21888242871839275222246405745257275088696311157297823662689037894645226208583";
+// Seed of the point which aggregation starts from, so that no addition meets the identity
+const OFFSET_SEED: &[u8] = b"zklink-oracle/bn254/aggregation-offset";
+
+fn fq_from_be_bytes(bytes: &[u8]) -> anyhow::Result<Fq> {
+    let mut repr = <Fq as PrimeField>::Repr::default();
+    repr.read_be(bytes)?;
+    Ok(Fq::from_repr(repr)?)
+}
+
+fn fq_to_be_bytes(fq: &Fq) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    fq.into_repr().write_be(&mut bytes[..]).unwrap();
+    bytes
+}
+
+fn fq_from_biguint(value: &BigUint) -> Fq {
+    Fq::from_str(&value.to_string()).unwrap()
+}
+
+fn base_field_modulus() -> BigUint {
+    FQ_MODULUS.parse().unwrap()
+}
+
+/// G1 point of `x || y` in big-endian, which must be on the curve and not the identity.
+pub fn g1_from_bytes(bytes: &[u8; G1_POINT_BS]) -> anyhow::Result<G1Affine> {
+    let x = fq_from_be_bytes(&bytes[..32])?;
+    let y = fq_from_be_bytes(&bytes[32..])?;
+    Ok(G1Affine::from_xy_checked(x, y)?)
+}
+
+pub fn g1_to_bytes(point: &G1Affine) -> [u8; G1_POINT_BS] {
+    let (x, y) = point.into_xy_unchecked();
+    let mut bytes = [0u8; G1_POINT_BS];
+    bytes[..32].copy_from_slice(&fq_to_be_bytes(&x));
+    bytes[32..].copy_from_slice(&fq_to_be_bytes(&y));
+    bytes
+}
+
+/// G2 point of `x.c1 || x.c0 || y.c1 || y.c0` in big-endian, which is the layout of EVM pairing
+/// precompiles.
+pub fn g2_from_bytes(bytes: &[u8; G2_POINT_BS]) -> anyhow::Result<G2Affine> {
+    let x = Fq2 {
+        c1: fq_from_be_bytes(&bytes[..32])?,
+        c0: fq_from_be_bytes(&bytes[32..64])?,
+    };
+    let y = Fq2 {
+        c1: fq_from_be_bytes(&bytes[64..96])?,
+        c0: fq_from_be_bytes(&bytes[96..])?,
+    };
+    Ok(G2Affine::from_xy_checked(x, y)?)
+}
+
+pub fn g2_to_bytes(point: &G2Affine) -> [u8; G2_POINT_BS] {
+    let (x, y) = point.into_xy_unchecked();
+    let mut bytes = [0u8; G2_POINT_BS];
+    for (i, fq) in [x.c1, x.c0, y.c1, y.c0].iter().enumerate() {
+        bytes[i * 32..(i + 1) * 32].copy_from_slice(&fq_to_be_bytes(fq));
+    }
+    bytes
+}
+
+/// Map a 32-byte hash to G1 by try-and-increment, in the same way as EigenLayer `BN254.hashToG1`.
+pub fn hash_to_g1(hash: &[u8; 32]) -> G1Affine {
+    let modulus = base_field_modulus();
+    let mut x = fq_from_biguint(&(BigUint::from_bytes_be(hash) % modulus));
+    loop {
+        let mut beta = x;
+        beta.square();
+        beta.mul_assign(&x);
+        beta.add_assign(&fq_from_biguint(&BigUint::from(B_COEF)));
+        if let Some(y) = beta.sqrt() {
+            return G1Affine::from_xy_checked(x, y).expect("point is on the curve");
+        }
+        x.add_assign(&Fq::one());
+    }
+}
+
+/// Sum of G1 points.
+pub fn aggregate_g1(points: &[G1Affine]) -> G1Affine {
+    let mut sum = G1::zero();
+    for point in points {
+        sum.add_assign_mixed(point);
+    }
+    sum.into_affine()
+}
+
+/// Sum of G2 points.
+pub fn aggregate_g2(points: &[G2Affine]) -> G2Affine {
+    let mut sum = G2::zero();
+    for point in points {
+        sum.add_assign_mixed(point);
+    }
+    sum.into_affine()
+}
+
+/// Verify the aggregated `signature` of `hash` by keys whose sum is `apk` in G1 and `apk_g2` in
+/// G2, i.e. `e(signature, g2) == e(H(hash), apk_g2)` and `e(apk, g2) == e(g1, apk_g2)`.
+pub fn verify_aggregate_signature(
+    hash: &[u8; 32],
+    apk: &G1Affine,
+    apk_g2: &G2Affine,
+    signature: &G1Affine,
+) -> bool {
+    let g1 = G1Affine::one();
+    let g2 = G2Affine::one();
+    Bn256::pairing(*signature, g2) == Bn256::pairing(hash_to_g1(hash), *apk_g2)
+        && Bn256::pairing(*apk, g2) == Bn256::pairing(g1, *apk_g2)
+}
+
+/// Random linear combination `gamma = keccak256(hash || apk || apk_g2 || signature)` of the two
+/// equations of [`verify_aggregate_signature`], in the same way as EigenLayer.
+fn gamma(hash: &[u8; 32], apk: &G1Affine, apk_g2: &G2Affine, signature: &G1Affine) -> BigUint {
+    let digest = Keccak256::new()
+        .chain_update(hash)
+        .chain_update(g1_to_bytes(apk))
+        .chain_update(g2_to_bytes(apk_g2))
+        .chain_update(g1_to_bytes(signature))
+        .finalize();
+    BigUint::from_bytes_be(&digest)
+}
+
+fn g1_mul(point: &G1Affine, scalar: &BigUint) -> G1Affine {
+    let modulus = repr_to_biguint::<Fr>(&Fr::char());
+    let scalar = Fr::from_str(&(scalar % modulus).to_string()).unwrap();
+    point.mul(scalar).into_affine()
+}
+
+/// Points `lhs = signature + [gamma]apk` and `rhs = H(hash) + [gamma]g1`, which reduce
+/// [`verify_aggregate_signature`] to `e(lhs, -g2) * e(rhs, apk_g2) == 1`.
+pub fn pairing_inputs(
+    hash: &[u8; 32],
+    apk: &G1Affine,
+    apk_g2: &G2Affine,
+    signature: &G1Affine,
+) -> (G1Affine, G1Affine) {
+    let gamma = gamma(hash, apk, apk_g2, signature);
+    let lhs = aggregate_g1(&[*signature, g1_mul(apk, &gamma)]);
+    let rhs = aggregate_g1(&[hash_to_g1(hash), g1_mul(&G1Affine::one(), &gamma)]);
+    (lhs, rhs)
+}
+
+/// Check `e(lhs, g2) == e(rhs, apk_g2)` of [`pairing_inputs`].
+pub fn verify_pairing_inputs(lhs: &G1Affine, rhs: &G1Affine, apk_g2: &G2Affine) -> bool {
+    Bn256::pairing(*lhs, G2Affine::one()) == Bn256::pairing(*rhs, *apk_g2)
+}
+
+fn offset_point() -> G1Affine {
+    hash_to_g1(&Keccak256::digest(OFFSET_SEED).into())
+}
+
+#[derive(Clone)]
+struct Point<'a, E: Engine> {
+    x: FieldElement<'a, E, Fq>,
+    y: FieldElement<'a, E, Fq>,
+}
+
+impl<'a, E: Engine> Point<'a, E> {
+    fn constant(point: &G1Affine, rns: &'a RnsParameters<E, Fq>) -> Self {
+        let (x, y) = point.into_xy_unchecked();
+        Self {
+            x: FieldElement::constant(x, rns),
+            y: FieldElement::constant(y, rns),
+        }
+    }
+
+    /// Point of `x || y` in big-endian. Returns whether the coordinates are canonical and the
+    /// point is on the curve, and the point.
+    fn alloc<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        bytes: &[Byte<E>; G1_POINT_BS],
+        rns: &'a RnsParameters<E, Fq>,
+    ) -> Result<(Boolean, Self), SynthesisError> {
+        let mut is_valid = vec![];
+        let mut coordinates = vec![];
+        for half in bytes.chunks_exact(32) {
+            let value = UInt256::from_be_bytes_fixed(cs, half.try_into().unwrap())?;
+            let (_, is_canonical) = value.sub(cs, &UInt256::constant(base_field_modulus()))?;
+            is_valid.push(is_canonical);
+            let value = value.mask(cs, &is_canonical)?;
+            let limbs = uint256_inner(cs, &value)?
+                .into_iter()
+                .map(|x| x.inner)
+                .collect::<Vec<Num<E>>>();
+            coordinates
+                .push(unsafe { FieldElement::alloc_from_limbs_unchecked(cs, &limbs, rns, true)? });
+        }
+        let point = Self {
+            x: coordinates[0].clone(),
+            y: coordinates[1].clone(),
+        };
+
+        // y^2 == x^3 + 3
+        let mut lhs = point.y.mul(cs, &point.y)?;
+        let b = FieldElement::constant(fq_from_biguint(&BigUint::from(B_COEF)), rns);
+        let mut rhs = point.x.mul(cs, &point.x)?.mul(cs, &point.x)?.add(cs, &b)?;
+        is_valid.push(FieldElement::equals(cs, &mut lhs, &mut rhs)?);
+        Ok((smart_and(cs, &is_valid)?, point))
+    }
+
+    /// Sum of points whose x coordinates differ, which the prover fails to synthesize otherwise.
+    fn add_unequal<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        other: &Self,
+    ) -> Result<Self, SynthesisError> {
+        let lambda = other
+            .y
+            .sub(cs, &self.y)?
+            .div(cs, &other.x.sub(cs, &self.x)?)?;
+        let x = lambda
+            .mul(cs, &lambda)?
+            .sub(cs, &self.x)?
+            .sub(cs, &other.x)?;
+        let y = lambda.mul(cs, &self.x.sub(cs, &x)?)?.sub(cs, &self.y)?;
+        Ok(Self { x, y })
+    }
+
+    fn double<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<Self, SynthesisError> {
+        // G1 has prime order, so that y is never zero
+        let x2 = self.x.mul(cs, &self.x)?;
+        let lambda = x2
+            .add(cs, &x2)?
+            .add(cs, &x2)?
+            .div(cs, &self.y.add(cs, &self.y)?)?;
+        let x = lambda
+            .mul(cs, &lambda)?
+            .sub(cs, &self.x)?
+            .sub(cs, &self.x)?;
+        let y = lambda.mul(cs, &self.x.sub(cs, &x)?)?.sub(cs, &self.y)?;
+        Ok(Self { x, y })
+    }
+
+    /// `[scalar]self` of little-endian bits by double-and-add from the offset point, which fails
+    /// to synthesize in the same cases as [`Self::add_unequal`].
+    fn mul<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        scalar: &[Boolean],
+        rns: &'a RnsParameters<E, Fq>,
+    ) -> Result<Self, SynthesisError> {
+        let offset = offset_point();
+        let mut acc = Point::constant(&offset, rns);
+        for bit in scalar.iter().rev() {
+            acc = acc.double(cs)?;
+            let sum = acc.add_unequal(cs, self)?;
+            acc = Point::select(cs, bit, &sum, &acc)?;
+        }
+        // Subtract the offset doubled once for each bit
+        let mut shifted = offset.into_projective();
+        for _ in 0..scalar.len() {
+            shifted.double();
+        }
+        shifted.negate();
+        acc.add_unequal(cs, &Point::constant(&shifted.into_affine(), rns))
+    }
+
+    fn select<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        flag: &Boolean,
+        p: &Self,
+        q: &Self,
+    ) -> Result<Self, SynthesisError> {
+        Ok(Self {
+            x: FieldElement::conditionally_select(cs, flag, &p.x, &q.x)?,
+            y: FieldElement::conditionally_select(cs, flag, &p.y, &q.y)?,
+        })
+    }
+
+    /// Same as [`hash_to_g1`]. Returns whether a point is found within [`MAX_HASH_TO_G1_TRIES`]
+    /// increments and the point.
+    ///
+    /// Increments before the point are proven to be non-residues by a square root of their
+    /// negations, as `-1` is a non-residue. The y coordinate is `beta^((p + 1) / 4)` as in
+    /// `BN254.hashToG1`, which is told apart from its negation by being a residue itself.
+    fn hash_to_g1<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        hash: &[Byte<E>; 32],
+        rns: &'a RnsParameters<E, Fq>,
+    ) -> Result<(Boolean, Self), SynthesisError> {
+        // The hash is less than 6p, so that five subtractions reduce it
+        let modulus = UInt256::constant(base_field_modulus());
+        let mut x = UInt256::from_be_bytes_fixed(cs, hash)?;
+        for _ in 0..5 {
+            let (diff, is_less) = x.sub(cs, &modulus)?;
+            x = UInt256::conditionally_select(cs, &is_less, &x, &diff)?;
+        }
+        let limbs = uint256_inner(cs, &x)?
+            .into_iter()
+            .map(|x| x.inner)
+            .collect::<Vec<Num<E>>>();
+        let mut x = unsafe { FieldElement::alloc_from_limbs_unchecked(cs, &limbs, rns, true)? };
+
+        let b_coef = fq_from_biguint(&BigUint::from(B_COEF));
+        let b = FieldElement::constant(b_coef, rns);
+        let one = FieldElement::constant(Fq::one(), rns);
+        let mut x_witness = Byte::get_byte_value_multiple(hash)
+            .map(|hash| fq_from_biguint(&(BigUint::from_bytes_be(&hash) % base_field_modulus())));
+        let mut is_found = Boolean::constant(false);
+        let mut is_valid = vec![];
+        let mut point = Point::constant(&G1Affine::one(), rns);
+        for _ in 0..MAX_HASH_TO_G1_TRIES {
+            let beta = x.mul(cs, &x)?.mul(cs, &x)?.add(cs, &b)?;
+            // Witness (is_residue, root of beta or -beta, root of the root)
+            let witness = x_witness.map(|x| {
+                let mut beta = x;
+                beta.square();
+                beta.mul_assign(&x);
+                beta.add_assign(&b_coef);
+                match beta.sqrt() {
+                    Some(y) => (true, y, y.sqrt().unwrap_or_else(Fq::zero)),
+                    None => {
+                        beta.negate();
+                        (false, beta.sqrt().expect("-1 is a non-residue"), Fq::zero())
+                    }
+                }
+            });
+            let is_residue = Boolean::Is(AllocatedBit::alloc(
+                cs,
+                witness.map(|(is_residue, _, _)| is_residue),
+            )?);
+            let (y, _) = FieldElement::alloc_ext(cs, witness.map(|(_, y, _)| y), rns)?;
+            let (t, _) = FieldElement::alloc_ext(cs, witness.map(|(_, _, t)| t), rns)?;
+
+            // y^2 == beta if residue else -beta
+            let minus_beta = beta.negate(cs)?;
+            let mut expected =
+                FieldElement::conditionally_select(cs, &is_residue, &beta, &minus_beta)?;
+            let mut y_squared = y.mul(cs, &y)?;
+            let is_root = FieldElement::equals(cs, &mut y_squared, &mut expected)?;
+            // t^2 == y if residue
+            let mut t_squared = t.mul(cs, &t)?;
+            let mut y_expected =
+                FieldElement::conditionally_select(cs, &is_residue, &y, &t_squared)?;
+            let is_principal = FieldElement::equals(cs, &mut t_squared, &mut y_expected)?;
+            // Tries after the point are unconstrained
+            let is_try_valid = Boolean::and(cs, &is_root, &is_principal)?;
+            is_valid.push(Boolean::or(cs, &is_try_valid, &is_found)?);
+
+            let is_point = Boolean::and(cs, &is_residue, &is_found.not())?;
+            point = Point::select(cs, &is_point, &Point { x: x.clone(), y }, &point)?;
+            is_found = Boolean::or(cs, &is_found, &is_point)?;
+            x = x.add(cs, &one)?;
+            x_witness = x_witness.map(|mut x| {
+                x.add_assign(&Fq::one());
+                x
+            });
+        }
+        is_valid.push(is_found);
+        Ok((smart_and(cs, &is_valid)?, point))
+    }
+}
+
+/// Check `apk` is the sum of `pubkeys` selected by `bitmap`, and all of them are valid G1 points
+/// in the layout of [`g1_from_bytes`].
+///
+/// The sum starts from a fixed offset point, so that additions never meet the identity. Keys
+/// whose x coordinate collides with an intermediate sum fail to synthesize, which takes solving a
+/// discrete logarithm to craft.
+pub fn circuit_check_aggregate_g1<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    pubkeys: &[[Byte<E>; G1_POINT_BS]],
+    bitmap: &[Boolean],
+    apk: &[Byte<E>; G1_POINT_BS],
+) -> Result<Boolean, SynthesisError> {
+    assert_eq!(pubkeys.len(), bitmap.len());
+    let rns = RnsParameters::<E, Fq>::new_optimal(cs, CHUNK_BITLEN);
+    let offset = Point::constant(&offset_point(), &rns);
+    let mut is_valid = vec![];
+    let mut acc = offset.clone();
+    for (pubkey, bit) in pubkeys.iter().zip(bitmap.iter()) {
+        let (is_pubkey_valid, pubkey) = Point::alloc(cs, pubkey, &rns)?;
+        is_valid.push(is_pubkey_valid);
+        let sum = acc.add_unequal(cs, &pubkey)?;
+        acc = Point::select(cs, bit, &sum, &acc)?;
+    }
+    let (is_apk_valid, apk) = Point::alloc(cs, apk, &rns)?;
+    is_valid.push(is_apk_valid);
+    // acc == offset + apk
+    let mut expected = offset.add_unequal(cs, &apk)?;
+    let is_x_equal = FieldElement::equals(cs, &mut acc.x, &mut expected.x)?;
+    let is_y_equal = FieldElement::equals(cs, &mut acc.y, &mut expected.y)?;
+    is_valid.extend([is_x_equal, is_y_equal]);
+    smart_and(cs, &is_valid)
+}
+
+/// Check `lhs` and `rhs` are [`pairing_inputs`] of `signature`, `hash`, `apk` and `apk_g2`. G1
+/// points are in the layout of [`g1_from_bytes`] and `apk_g2` in the layout of [`g2_from_bytes`].
+///
+/// No pairing is computed in circuit, so this does not verify the signature. It is valid only if
+/// consumers also check [`verify_pairing_inputs`], e.g. with the EVM pairing precompile, which
+/// also checks `apk_g2`. Circuits calling this must commit `lhs`, `rhs` and `apk_g2`.
+///
+/// Hashing to G1 fails for hashes which take more than [`MAX_HASH_TO_G1_TRIES`] increments, i.e.
+/// with probability 2^-64.
+pub fn circuit_check_pairing_inputs<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    hash: &[Byte<E>; 32],
+    apk: &[Byte<E>; G1_POINT_BS],
+    apk_g2: &[Byte<E>; G2_POINT_BS],
+    signature: &[Byte<E>; G1_POINT_BS],
+    lhs: &[Byte<E>; G1_POINT_BS],
+    rhs: &[Byte<E>; G1_POINT_BS],
+) -> Result<Boolean, SynthesisError> {
+    let rns = RnsParameters::<E, Fq>::new_optimal(cs, CHUNK_BITLEN);
+    let preimage = [&hash[..], &apk[..], &apk_g2[..], &signature[..]].concat();
+    let gamma = keccak256::digest(cs, &preimage)?;
+    // G1 has order r, so that gamma needs no reduction modulo r
+    let mut gamma_bits = vec![];
+    for byte in gamma.iter().rev() {
+        gamma_bits.extend(byte.inner.into_bits_le(cs, Some(8))?);
+    }
+
+    let (is_apk_valid, apk) = Point::alloc(cs, apk, &rns)?;
+    let (is_signature_valid, signature) = Point::alloc(cs, signature, &rns)?;
+    let (is_lhs_valid, mut lhs) = Point::alloc(cs, lhs, &rns)?;
+    let (is_rhs_valid, mut rhs) = Point::alloc(cs, rhs, &rns)?;
+    let (is_hashed, hash) = Point::hash_to_g1(cs, hash, &rns)?;
+    let mut is_valid = vec![
+        is_apk_valid,
+        is_signature_valid,
+        is_lhs_valid,
+        is_rhs_valid,
+        is_hashed,
+    ];
+
+    let g1 = Point::constant(&G1Affine::one(), &rns);
+    let mut expected_lhs = signature.add_unequal(cs, &apk.mul(cs, &gamma_bits, &rns)?)?;
+    let mut expected_rhs = hash.add_unequal(cs, &g1.mul(cs, &gamma_bits, &rns)?)?;
+    for (actual, expected) in [(&mut lhs, &mut expected_lhs), (&mut rhs, &mut expected_rhs)] {
+        is_valid.push(FieldElement::equals(cs, &mut actual.x, &mut expected.x)?);
+        is_valid.push(FieldElement::equals(cs, &mut actual.y, &mut expected.y)?);
+    }
+    smart_and(cs, &is_valid)
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte,
+        franklin_crypto::{
+            bellman::{
+                pairing::bn256::Bn256, plonk::better_better_cs::cs::ConstraintSystem,
+                SynthesisError,
+            },
+            plonk::circuit::boolean::Boolean,
+        },
+    };
+
+    use super::{
+        aggregate_g1, aggregate_g2, g1_from_bytes, g1_to_bytes, g2_from_bytes, g2_to_bytes,
+        pairing_inputs, verify_aggregate_signature, verify_pairing_inputs,
+    };
+    use crate::utils::{signer::BlsSigner, testing::create_test_constraint_system};
+
+    fn alloc_point<CS: ConstraintSystem<Bn256>, const N: usize>(
+        cs: &mut CS,
+        bytes: &[u8; N],
+    ) -> Result<[Byte<Bn256>; N], SynthesisError> {
+        let mut point = [Byte::zero(); N];
+        for (byte, b) in point.iter_mut().zip(bytes) {
+            *byte = Byte::from_u8_witness(cs, Some(*b))?;
+        }
+        Ok(point)
+    }
+
+    #[test]
+    fn test_aggregate_g1() -> anyhow::Result<()> {
+        let signers = BlsSigner::generate(3);
+        let hash = [0x42u8; 32];
+        let selected = [&signers[0], &signers[2]];
+        let apk = aggregate_g1(&[
+            g1_from_bytes(&selected[0].pubkey())?,
+            g1_from_bytes(&selected[1].pubkey())?,
+        ]);
+        let apk_g2 = aggregate_g2(&[
+            g2_from_bytes(&selected[0].pubkey_g2())?,
+            g2_from_bytes(&selected[1].pubkey_g2())?,
+        ]);
+        let signature = aggregate_g1(&[
+            g1_from_bytes(&selected[0].sign(&hash))?,
+            g1_from_bytes(&selected[1].sign(&hash))?,
+        ]);
+        assert!(verify_aggregate_signature(&hash, &apk, &apk_g2, &signature));
+        assert!(!verify_aggregate_signature(
+            &[0u8; 32], &apk, &apk_g2, &signature
+        ));
+
+        let other = signers[0].pubkey();
+        for (apk, expected) in [(g1_to_bytes(&apk), true), (other, false)] {
+            let cs = &mut create_test_constraint_system()?;
+            let pubkeys = signers
+                .iter()
+                .map(|s| alloc_point(cs, &s.pubkey()))
+                .collect::<Result<Vec<_>, _>>()?;
+            let bitmap = [true, false, true].map(Boolean::constant);
+            let apk = alloc_point(cs, &apk)?;
+            let is_valid = super::circuit_check_aggregate_g1(cs, &pubkeys, &bitmap, &apk)?;
+            assert_eq!(is_valid.get_value(), Some(expected));
+            assert!(cs.is_satisfied());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_pairing_inputs() -> anyhow::Result<()> {
+        let signers = BlsSigner::generate(2);
+        let hash = [0x42u8; 32];
+        let pubkeys = signers
+            .iter()
+            .map(|s| g1_from_bytes(&s.pubkey()))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let pubkeys_g2 = signers
+            .iter()
+            .map(|s| g2_from_bytes(&s.pubkey_g2()))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let signatures = signers
+            .iter()
+            .map(|s| g1_from_bytes(&s.sign(&hash)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let apk = aggregate_g1(&pubkeys);
+        let apk_g2 = aggregate_g2(&pubkeys_g2);
+        let signature = aggregate_g1(&signatures);
+        let (lhs, rhs) = pairing_inputs(&hash, &apk, &apk_g2, &signature);
+        assert!(verify_pairing_inputs(&lhs, &rhs, &apk_g2));
+        let (other_lhs, other_rhs) = pairing_inputs(&[0u8; 32], &apk, &apk_g2, &signature);
+        assert!(!verify_pairing_inputs(&other_lhs, &other_rhs, &apk_g2));
+
+        for (lhs, rhs, expected) in [(lhs, rhs, true), (rhs, lhs, false)] {
+            let cs = &mut create_test_constraint_system()?;
+            let n = cs.n();
+            let hash = alloc_point(cs, &hash)?;
+            let apk = alloc_point(cs, &g1_to_bytes(&apk))?;
+            let apk_g2 = alloc_point(cs, &g2_to_bytes(&apk_g2))?;
+            let signature = alloc_point(cs, &g1_to_bytes(&signature))?;
+            let lhs = alloc_point(cs, &g1_to_bytes(&lhs))?;
+            let rhs = alloc_point(cs, &g1_to_bytes(&rhs))?;
+            let is_valid = super::circuit_check_pairing_inputs(
+                cs, &hash, &apk, &apk_g2, &signature, &lhs, &rhs,
+            )?;
+            println!("Roughly {} gates", cs.n() - n);
+            assert_eq!(is_valid.get_value(), Some(expected));
+            assert!(cs.is_satisfied());
+        }
+        Ok(())
+    }
+}
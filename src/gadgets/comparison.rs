@@ -0,0 +1,213 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    vm::primitives::uint256::UInt256,
+};
+use num_bigint::BigUint;
+
+use crate::utils::{fr_from_biguint, num_into_be_bytes};
+
+/// Compare big-endian integers `a` and `b`, returning `(a == b, a > b)` like
+/// `prepacked_long_comparison`.
+pub fn compare<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    a: &UInt256<E>,
+    b: &UInt256<E>,
+) -> Result<(Boolean, Boolean), SynthesisError> {
+    let is_equal = UInt256::equals(cs, a, b)?;
+    let (_, is_less) = a.sub(cs, b)?;
+    let is_greater = Boolean::and(cs, &is_less.not(), &is_equal.not())?;
+    Ok((is_equal, is_greater))
+}
+
+/// Check `a < b` by the borrow of `a - b`, i.e. a single subtraction as `le`, `gt` and `ge`.
+pub fn lt<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    a: &UInt256<E>,
+    b: &UInt256<E>,
+) -> Result<Boolean, SynthesisError> {
+    let (_, borrow) = a.sub(cs, b)?;
+    Ok(borrow)
+}
+
+/// Check `a <= b`.
+pub fn le<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    a: &UInt256<E>,
+    b: &UInt256<E>,
+) -> Result<Boolean, SynthesisError> {
+    Ok(lt(cs, b, a)?.not())
+}
+
+/// Check `a > b`.
+pub fn gt<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    a: &UInt256<E>,
+    b: &UInt256<E>,
+) -> Result<Boolean, SynthesisError> {
+    lt(cs, b, a)
+}
+
+/// Check `a >= b`.
+pub fn ge<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    a: &UInt256<E>,
+    b: &UInt256<E>,
+) -> Result<Boolean, SynthesisError> {
+    Ok(lt(cs, a, b)?.not())
+}
+
+/// Check `min <= value <= max`.
+pub fn is_within_bounds<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    value: &UInt256<E>,
+    min: &UInt256<E>,
+    max: &UInt256<E>,
+) -> Result<Boolean, SynthesisError> {
+    let is_above_min = ge(cs, value, min)?;
+    let is_below_max = le(cs, value, max)?;
+    Boolean::and(cs, &is_above_min, &is_below_max)
+}
+
+/// Threshold supplied via public inputs rather than fixed by circuit.
+///
+/// A 256-bit threshold does not fit a field element, so it is inputized as two 128-bit limbs
+/// `(hi, lo)`, which are re-expanded to bytes with constraints to build `value`.
+#[derive(Clone, Debug)]
+pub struct PublicThreshold<E: Engine> {
+    pub hi: Num<E>,
+    pub lo: Num<E>,
+    pub value: UInt256<E>,
+}
+
+impl<E: Engine> PublicThreshold<E> {
+    pub fn alloc_as_input<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: Option<BigUint>,
+    ) -> Result<Self, SynthesisError> {
+        let (hi, lo) = match witness.as_ref().map(Self::split) {
+            Some(Ok((hi, lo))) => (Some(hi), Some(lo)),
+            Some(Err(e)) => return Err(e),
+            None => (None, None),
+        };
+        let hi = Num::alloc(cs, hi)?;
+        let lo = Num::alloc(cs, lo)?;
+        hi.get_variable().inputize(cs)?;
+        lo.get_variable().inputize(cs)?;
+
+        let mut bytes = num_into_be_bytes(cs, &hi, 16)?;
+        bytes.extend(num_into_be_bytes(cs, &lo, 16)?);
+        let value = UInt256::from_be_bytes_fixed(cs, &bytes.try_into().unwrap())?;
+        Ok(Self { hi, lo, value })
+    }
+
+    /// Split threshold into the `(hi, lo)` field elements that are supplied as public inputs.
+    pub fn split(threshold: &BigUint) -> Result<(E::Fr, E::Fr), SynthesisError> {
+        let mask = (BigUint::from(1u8) << 128u32) - 1u8;
+        let hi = fr_from_biguint::<E>(&((threshold >> 128u32) & &mask))?;
+        let lo = fr_from_biguint::<E>(&(threshold & &mask))?;
+        Ok((hi, lo))
+    }
+
+    /// Compare `value` against the threshold, returning `(value == threshold, value > threshold)`.
+    pub fn compare<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        value: &UInt256<E>,
+    ) -> Result<(Boolean, Boolean), SynthesisError> {
+        compare(cs, value, &self.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::bellman::SynthesisError, vm::primitives::uint256::UInt256,
+    };
+    use num_bigint::BigUint;
+
+    use super::{ge, gt, is_within_bounds, le, lt, PublicThreshold};
+    use crate::utils::testing::create_test_constraint_system;
+
+    #[test]
+    fn test_public_threshold() -> Result<(), SynthesisError> {
+        let threshold = (BigUint::from(1u8) << 200u32) + 36248807380u64;
+        let cases = [
+            (threshold.clone(), (true, false)),
+            (&threshold + 1u8, (false, true)),
+            (&threshold - 1u8, (false, false)),
+            (BigUint::from(1u8) << 128u32, (false, false)),
+        ];
+        for (value, (expected_equal, expected_greater)) in cases {
+            let cs = &mut create_test_constraint_system()?;
+            let public_threshold = PublicThreshold::alloc_as_input(cs, Some(threshold.clone()))?;
+            assert_eq!(public_threshold.value.get_value().unwrap(), threshold);
+            let value = UInt256::alloc_from_witness(cs, Some(value))?;
+            let (is_equal, is_greater) = public_threshold.compare(cs, &value)?;
+            assert!(cs.is_satisfied());
+            assert_eq!(is_equal.get_value().unwrap(), expected_equal);
+            assert_eq!(is_greater.get_value().unwrap(), expected_greater);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_ordering() -> Result<(), SynthesisError> {
+        let max = (BigUint::from(1u8) << 256u32) - 1u8;
+        let values = [
+            BigUint::from(0u8),
+            BigUint::from(1u8),
+            BigUint::from(36248807380u64),
+            (BigUint::from(1u8) << 128u32) + 1u8,
+            max,
+        ];
+        let cs = &mut create_test_constraint_system()?;
+        let allocated = values
+            .iter()
+            .map(|v| UInt256::alloc_from_witness(cs, Some(v.clone())))
+            .collect::<Result<Vec<_>, _>>()?;
+        let n = cs.n();
+        for (a, x) in values.iter().zip(&allocated) {
+            for (b, y) in values.iter().zip(&allocated) {
+                assert_eq!(lt(cs, x, y)?.get_value(), Some(a < b));
+                assert_eq!(le(cs, x, y)?.get_value(), Some(a <= b));
+                assert_eq!(gt(cs, x, y)?.get_value(), Some(a > b));
+                assert_eq!(ge(cs, x, y)?.get_value(), Some(a >= b));
+            }
+        }
+        println!(
+            "Roughly {} gates",
+            (cs.n() - n) / (4 * values.len() * values.len())
+        );
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_within_bounds() -> Result<(), SynthesisError> {
+        let (min, max) = (BigUint::from(100u64), BigUint::from(1u8) << 200u32);
+        let cases = [
+            (min.clone(), true),
+            (max.clone(), true),
+            (BigUint::from(36248807380u64), true),
+            (BigUint::from(0u8), false),
+            (BigUint::from(99u8), false),
+            (&max + 1u8, false),
+        ];
+        for (value, expected) in cases {
+            let cs = &mut create_test_constraint_system()?;
+            let value = UInt256::alloc_from_witness(cs, Some(value))?;
+            let (min, max) = (
+                UInt256::constant(min.clone()),
+                UInt256::constant(max.clone()),
+            );
+            let is_within = is_within_bounds(cs, &value, &min, &max)?;
+            assert!(cs.is_satisfied());
+            assert_eq!(is_within.get_value().unwrap(), expected);
+        }
+        Ok(())
+    }
+}
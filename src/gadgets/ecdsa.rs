@@ -34,7 +34,7 @@ use num_bigint::BigUint;
 use crate::utils::new_synthesis_error;
 
 // UInt256.inner is private so I have to use this hack
-fn uint256_inner<E: Engine, CS: ConstraintSystem<E>>(
+pub(crate) fn uint256_inner<E: Engine, CS: ConstraintSystem<E>>(
     cs: &mut CS,
     uint256: &UInt256<E>,
 ) -> Result<[UInt64<E>; 4], SynthesisError> {
@@ -46,7 +46,12 @@ fn uint256_inner<E: Engine, CS: ConstraintSystem<E>>(
     Ok(inner)
 }
 
-fn convert_uint256_to_field_element<'a, E: Engine, F: PrimeField, CS: ConstraintSystem<E>>(
+pub(crate) fn convert_uint256_to_field_element<
+    'a,
+    E: Engine,
+    F: PrimeField,
+    CS: ConstraintSystem<E>,
+>(
     cs: &mut CS,
     elem: &UInt256<E>,
     rns_strategy: &'a RnsParameters<E, F>,
@@ -65,7 +70,7 @@ fn convert_uint256_to_field_element<'a, E: Engine, F: PrimeField, CS: Constraint
     FieldElement::conditionally_select(cs, &is_zero, &FieldElement::one(rns_strategy), &fe)
 }
 
-const CHUNK_BITLEN: usize = 64;
+pub(crate) const CHUNK_BITLEN: usize = 64;
 const SECP_B_COEF: u64 = 7;
 const EXCEPTION_FLAGS_ARR_LEN: usize = 4;
 const X_POWERS_ARR_LEN: usize = 256;
@@ -120,6 +125,19 @@ impl<E: Engine> Signature<E> {
         );
         Self::alloc_from_witness(cs, Some(witness))
     }
+
+    /// Create a signature from 65 allocated bytes in the same format as [`Self::from_bytes_witness`],
+    /// so that the signature is bound to the bytes it is sliced from.
+    pub fn from_bytes<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        bytes: &[Byte<E>; 65],
+    ) -> Result<Self, SynthesisError> {
+        let r = UInt256::from_be_bytes_fixed(cs, bytes[..32].try_into().unwrap())?;
+        let s = UInt256::from_be_bytes_fixed(cs, bytes[32..64].try_into().unwrap())?;
+        let recid =
+            UInt32::from_bytes_le(cs, &[bytes[64], Byte::zero(), Byte::zero(), Byte::zero()])?;
+        Ok(Self { r, s, recid })
+    }
 }
 
 impl<E: Engine> CSWitnessable<E> for Signature<E> {
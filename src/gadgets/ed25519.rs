@@ -0,0 +1,338 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{
+            allocated_num::Num, bigint_new::FieldElement, bigint_new::RnsParameters,
+            boolean::Boolean, linear_combination::LinearCombination,
+        },
+    },
+    vm::{partitioner::smart_and, primitives::uint256::UInt256},
+};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use ff::{Field, PrimeField};
+use num_bigint::BigUint;
+
+use super::{
+    ecdsa::{uint256_inner, CHUNK_BITLEN},
+    sha512,
+};
+
+/// Base field of curve25519, i.e. integers modulo `2^255 - 19`.
+#[derive(PrimeField)]
+#[PrimeFieldModulus = "57896044618658097711785492504343953926634992332820282019728792003956564819949"]
+#[PrimeFieldGenerator = "2"]
+pub struct Fq(FqRepr);
+
+pub const PUBKEY_BS: usize = 32;
+pub const SIGNATURE_BS: usize = 64;
+
+// -121665 / 121666
+const D: &str = "37095705934669439343138083508754565189542113879843219016388785533085940283555";
+const BASE_X: &str =
+    "15112221349535400772501151409588531511454012693041857206046113283949847762202";
+const BASE_Y: &str =
+    "46316835694926478169428394003475163141307993866256225615783033603165251855960";
+// 2^252 + 27742317777372353535851937790883648493
+const ORDER: &str = "7237005577332262213973186563042994240857116359379907606001950938285454250989";
+
+fn field_modulus() -> BigUint {
+    (BigUint::from(1u8) << 255u32) - BigUint::from(19u8)
+}
+
+fn fq(value: &BigUint) -> Fq {
+    Fq::from_str(&value.to_string()).unwrap()
+}
+
+/// Recover `x` from `y` and the sign of `x` as RFC 8032 does, or `None` if `y` is not on the
+/// curve.
+fn recover_x(y: &BigUint, is_odd: bool) -> Option<BigUint> {
+    let p = field_modulus();
+    let d = D.parse::<BigUint>().unwrap();
+    let y2 = y * y % &p;
+    let u = (&y2 + &p - 1u8) % &p;
+    let v = (d * y2 + 1u8) % &p;
+    let x2 = u * v.modpow(&(&p - 2u8), &p) % &p;
+    let mut x = x2.modpow(&((&p + 3u8) >> 3u32), &p);
+    if &x * &x % &p != x2 {
+        let sqrt_minus_one = BigUint::from(2u8).modpow(&((&p - 1u8) >> 2u32), &p);
+        x = x * sqrt_minus_one % &p;
+    }
+    if &x * &x % &p != x2 || (x2 == BigUint::default() && is_odd) {
+        return None;
+    }
+    if x.bit(0) != is_odd {
+        x = &p - x;
+    }
+    Some(x)
+}
+
+/// Verify `signature` of `message` by `pubkey` natively, in the same way as [`verify`].
+pub fn verify_signature(
+    pubkey: &[u8; PUBKEY_BS],
+    message: &[u8],
+    signature: &[u8; SIGNATURE_BS],
+) -> bool {
+    VerifyingKey::from_bytes(pubkey)
+        .map(|key| {
+            key.verify(message, &Signature::from_bytes(signature))
+                .is_ok()
+        })
+        .unwrap_or(false)
+}
+
+#[derive(Clone)]
+struct Point<'a, E: Engine> {
+    x: FieldElement<'a, E, Fq>,
+    y: FieldElement<'a, E, Fq>,
+}
+
+struct Curve<'a, E: Engine> {
+    rns: &'a RnsParameters<E, Fq>,
+    d: FieldElement<'a, E, Fq>,
+}
+
+impl<'a, E: Engine> Curve<'a, E> {
+    fn constant(&self, value: &BigUint) -> FieldElement<'a, E, Fq> {
+        FieldElement::constant(fq(value), self.rns)
+    }
+
+    fn identity(&self) -> Point<'a, E> {
+        Point {
+            x: self.constant(&BigUint::from(0u8)),
+            y: self.constant(&BigUint::from(1u8)),
+        }
+    }
+
+    fn base(&self) -> Point<'a, E> {
+        Point {
+            x: self.constant(&BASE_X.parse().unwrap()),
+            y: self.constant(&BASE_Y.parse().unwrap()),
+        }
+    }
+
+    /// Complete addition law of `-x^2 + y^2 = 1 + d x^2 y^2`.
+    fn add<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        p: &Point<'a, E>,
+        q: &Point<'a, E>,
+    ) -> Result<Point<'a, E>, SynthesisError> {
+        let x1y2 = p.x.mul(cs, &q.y)?;
+        let y1x2 = p.y.mul(cs, &q.x)?;
+        let x1x2 = p.x.mul(cs, &q.x)?;
+        let y1y2 = p.y.mul(cs, &q.y)?;
+        let t = x1x2.mul(cs, &y1y2)?.mul(cs, &self.d)?;
+        let one = self.constant(&BigUint::from(1u8));
+        let x = x1y2.add(cs, &y1x2)?.div(cs, &one.add(cs, &t)?)?;
+        let y = y1y2.add(cs, &x1x2)?.div(cs, &one.sub(cs, &t)?)?;
+        Ok(Point { x, y })
+    }
+
+    fn select<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        flag: &Boolean,
+        p: &Point<'a, E>,
+        q: &Point<'a, E>,
+    ) -> Result<Point<'a, E>, SynthesisError> {
+        Ok(Point {
+            x: FieldElement::conditionally_select(cs, flag, &p.x, &q.x)?,
+            y: FieldElement::conditionally_select(cs, flag, &p.y, &q.y)?,
+        })
+    }
+
+    /// Field element of 32 little-endian bytes. Returns whether the bytes are canonical, i.e.
+    /// less than the modulus, and the element, which is zero if they are not.
+    fn alloc_canonical<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        bytes: &[Byte<E>; 32],
+    ) -> Result<(Boolean, FieldElement<'a, E, Fq>), SynthesisError> {
+        let mut be_bytes = *bytes;
+        be_bytes.reverse();
+        let value = UInt256::from_be_bytes_fixed(cs, &be_bytes)?;
+        let (_, is_canonical) = value.sub(cs, &UInt256::constant(field_modulus()))?;
+        let value = value.mask(cs, &is_canonical)?;
+        let limbs = uint256_inner(cs, &value)?
+            .into_iter()
+            .map(|x| x.inner)
+            .collect::<Vec<Num<E>>>();
+        let fe = unsafe { FieldElement::alloc_from_limbs_unchecked(cs, &limbs, self.rns, true)? };
+        Ok((is_canonical, fe))
+    }
+
+    /// Decode a point from `y` in little-endian with the sign of `x` in the top bit. Returns
+    /// whether the encoding is valid and the point, which is the identity if it is not.
+    fn decompress<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        bytes: &[Byte<E>; 32],
+    ) -> Result<(Boolean, Point<'a, E>), SynthesisError> {
+        let top_bits = bytes[31].inner.into_bits_le(cs, Some(8))?;
+        let is_odd = top_bits[7];
+        let mut lc = LinearCombination::zero();
+        let mut coeff = E::Fr::one();
+        for bit in top_bits[..7].iter() {
+            lc.add_assign_boolean_with_coeff(bit, coeff);
+            coeff.double();
+        }
+        let top = lc.into_num(cs)?;
+        let mut y_bytes = *bytes;
+        y_bytes[31] = Byte::from_num_unconstrained(cs, top);
+        let (is_y_canonical, y) = self.alloc_canonical(cs, &y_bytes)?;
+
+        let x_witness = match (Byte::get_byte_value_multiple(&y_bytes), is_odd.get_value()) {
+            (Some(y), Some(is_odd)) => Some(
+                recover_x(&BigUint::from_bytes_le(&y), is_odd)
+                    .map(|x| {
+                        let mut x = x.to_bytes_le();
+                        x.resize(32, 0);
+                        x
+                    })
+                    .unwrap_or_else(|| vec![0; 32]),
+            ),
+            _ => None,
+        };
+        let mut x_bytes = [Byte::zero(); 32];
+        for (i, byte) in x_bytes.iter_mut().enumerate() {
+            *byte = Byte::from_u8_witness(cs, x_witness.as_ref().map(|x| x[i]))?;
+        }
+        let (is_x_canonical, x) = self.alloc_canonical(cs, &x_bytes)?;
+        let is_x_odd = x_bytes[0].inner.into_bits_le(cs, Some(8))?[0];
+        let is_sign_matched = Boolean::xor(cs, &is_x_odd, &is_odd)?.not();
+
+        // x^2 (d y^2 + 1) == y^2 - 1
+        let one = self.constant(&BigUint::from(1u8));
+        let y2 = y.square(cs)?;
+        let mut lhs = x
+            .square(cs)?
+            .mul(cs, &y2.mul(cs, &self.d)?.add(cs, &one)?)?;
+        let mut rhs = y2.sub(cs, &one)?;
+        let is_on_curve = FieldElement::equals(cs, &mut lhs, &mut rhs)?;
+
+        let is_valid = smart_and(
+            cs,
+            &[is_y_canonical, is_x_canonical, is_sign_matched, is_on_curve],
+        )?;
+        let point = self.select(cs, &is_valid, &Point { x, y }, &self.identity())?;
+        Ok((is_valid, point))
+    }
+}
+
+fn bits_le<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    bytes: &[Byte<E>],
+) -> Result<Vec<Boolean>, SynthesisError> {
+    let mut bits = vec![];
+    for byte in bytes {
+        bits.extend(byte.inner.into_bits_le(cs, Some(8))?);
+    }
+    Ok(bits)
+}
+
+/// Verify `signature = R || S` of `message` by `pubkey = A`, i.e. `[S]B == R + [k]A` where
+/// `k = SHA512(R || A || message)`. The length of `message` is fixed at synthesis.
+///
+/// `k` is not reduced modulo the group order, which gives the same point as long as `A` is in
+/// the prime-order subgroup, e.g. a key generated honestly.
+pub fn verify<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    pubkey: &[Byte<E>; PUBKEY_BS],
+    message: &[Byte<E>],
+    signature: &[Byte<E>; SIGNATURE_BS],
+) -> Result<Boolean, SynthesisError> {
+    let rns = RnsParameters::<E, Fq>::new_optimal(cs, CHUNK_BITLEN);
+    let curve = Curve {
+        rns: &rns,
+        d: FieldElement::constant(fq(&D.parse().unwrap()), &rns),
+    };
+    let (is_pubkey_valid, a) = curve.decompress(cs, pubkey)?;
+    let r_bytes: [Byte<E>; 32] = signature[..32].try_into().unwrap();
+    let (is_r_valid, r) = curve.decompress(cs, &r_bytes)?;
+
+    let mut s_bytes: [Byte<E>; 32] = signature[32..].try_into().unwrap();
+    let s_bits = bits_le(cs, &s_bytes)?;
+    s_bytes.reverse();
+    let s = UInt256::from_be_bytes_fixed(cs, &s_bytes)?;
+    let (_, is_s_canonical) = s.sub(cs, &UInt256::constant(ORDER.parse().unwrap()))?;
+
+    let mut preimage = r_bytes.to_vec();
+    preimage.extend_from_slice(pubkey);
+    preimage.extend_from_slice(message);
+    let k = sha512::digest(cs, &preimage)?;
+    let k_bits = bits_le(cs, &k)?;
+
+    // [S]B + [k](-A) by double-and-add over both scalars at once
+    let base = curve.base();
+    let minus_a = Point {
+        x: a.x.negate(cs)?,
+        y: a.y,
+    };
+    let identity = curve.identity();
+    let mut acc = curve.identity();
+    for i in (0..k_bits.len()).rev() {
+        acc = curve.add(cs, &acc, &acc)?;
+        if i < s_bits.len() {
+            let addend = curve.select(cs, &s_bits[i], &base, &identity)?;
+            acc = curve.add(cs, &acc, &addend)?;
+        }
+        let addend = curve.select(cs, &k_bits[i], &minus_a, &identity)?;
+        acc = curve.add(cs, &acc, &addend)?;
+    }
+    let (mut r_x, mut r_y) = (r.x, r.y);
+    let is_x_equal = FieldElement::equals(cs, &mut acc.x, &mut r_x)?;
+    let is_y_equal = FieldElement::equals(cs, &mut acc.y, &mut r_y)?;
+
+    smart_and(
+        cs,
+        &[
+            is_pubkey_valid,
+            is_r_valid,
+            is_s_canonical,
+            is_x_equal,
+            is_y_equal,
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte, franklin_crypto::bellman::SynthesisError,
+    };
+
+    use super::{verify, verify_signature};
+    use crate::utils::{signer::Ed25519Signer, testing::create_test_constraint_system};
+
+    #[test]
+    fn test_ed25519() -> Result<(), SynthesisError> {
+        let signer = Ed25519Signer::new(0x42);
+        let message = b"hello world";
+        let signature = signer.sign(message);
+        assert!(verify_signature(&signer.pubkey(), message, &signature));
+        let mut tampered = signature;
+        tampered[40] ^= 1;
+        assert!(!verify_signature(&signer.pubkey(), message, &tampered));
+
+        for (signature, expected) in [(signature, true), (tampered, false)] {
+            let cs = &mut create_test_constraint_system()?;
+            let mut alloc = |bytes: &[u8]| {
+                bytes
+                    .iter()
+                    .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+                    .collect::<Result<Vec<_>, _>>()
+            };
+            let pubkey = alloc(&signer.pubkey())?.try_into().unwrap();
+            let message = alloc(message)?;
+            let signature = alloc(&signature)?.try_into().unwrap();
+            let n = cs.n();
+            let is_valid = verify(cs, &pubkey, &message, &signature)?;
+            println!("Roughly {} gates", cs.n() - n);
+            assert_eq!(is_valid.get_value(), Some(expected));
+            assert!(cs.is_satisfied());
+        }
+        Ok(())
+    }
+}
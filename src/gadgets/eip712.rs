@@ -0,0 +1,241 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    circuit_structures::byte::{Byte, IntoBytes},
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    utils::u64_to_fe,
+    vm::primitives::uint256::UInt256,
+};
+use sha3::{Digest, Keccak256};
+
+use super::{ethereum::Address, keccak256};
+
+pub const WORD_BS: usize = 32;
+/// Type of the domain which has all of `name`, `version`, `chainId` and `verifyingContract`.
+pub const DOMAIN_TYPE: &str =
+    "EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)";
+/// Prefix of typed data hashes, i.e. `\x19` followed by version `\x01`.
+pub const TYPED_DATA_PREFIX: [u8; 2] = [0x19, 0x01];
+
+/// Encoded member, which is big-endian and not checked to be canonical.
+pub type Word<E> = [Byte<E>; WORD_BS];
+
+/// Constant word of `value`, e.g. a type hash.
+pub fn constant_word<E: Engine>(value: &[u8; WORD_BS]) -> Word<E> {
+    value.map(Byte::constant)
+}
+
+/// `uintN` of big-endian `bytes`, which are zero-padded on the left.
+pub fn encode_uint<E: Engine>(bytes: &[Byte<E>]) -> Word<E> {
+    assert!(bytes.len() <= WORD_BS);
+    let mut word = [Byte::zero(); WORD_BS];
+    word[WORD_BS - bytes.len()..].copy_from_slice(bytes);
+    word
+}
+
+/// Same as [`encode_uint`], but of a 256-bit integer.
+pub fn encode_uint256<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    value: &UInt256<E>,
+) -> Result<Word<E>, SynthesisError> {
+    Ok(value.into_be_bytes(cs)?.try_into().unwrap())
+}
+
+/// `intN` of big-endian two's complement `bytes`, which are sign-extended on the left.
+pub fn encode_int<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    bytes: &[Byte<E>],
+) -> Result<Word<E>, SynthesisError> {
+    assert!(!bytes.is_empty() && bytes.len() <= WORD_BS);
+    let is_negative = bytes[0].inner.into_bits_le(cs, Some(8))?[7];
+    let extension = Num::from_boolean_is(is_negative).mul(cs, &Num::Constant(u64_to_fe(0xff)))?;
+    let extension = Byte::from_num_unconstrained(cs, extension);
+    let mut word = [extension; WORD_BS];
+    word[WORD_BS - bytes.len()..].copy_from_slice(bytes);
+    Ok(word)
+}
+
+pub fn encode_address<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    address: &Address<E>,
+) -> Result<Word<E>, SynthesisError> {
+    encode_uint256(cs, &address.inner())
+}
+
+pub fn encode_bool<E: Engine, CS: ConstraintSystem<E>>(cs: &mut CS, value: &Boolean) -> Word<E> {
+    let mut word = [Byte::zero(); WORD_BS];
+    word[WORD_BS - 1] = Byte::from_num_unconstrained(cs, Num::from_boolean_is(*value));
+    word
+}
+
+/// `bytesN` of `bytes`, which are zero-padded on the right.
+pub fn encode_fixed_bytes<E: Engine>(bytes: &[Byte<E>]) -> Word<E> {
+    assert!(!bytes.is_empty() && bytes.len() <= WORD_BS);
+    let mut word = [Byte::zero(); WORD_BS];
+    word[..bytes.len()].copy_from_slice(bytes);
+    word
+}
+
+/// `bytes` or `string`, i.e. the hash of its content, whose length is fixed at synthesis.
+pub fn encode_bytes<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    bytes: &[Byte<E>],
+) -> Result<Word<E>, SynthesisError> {
+    keccak256::digest(cs, bytes)
+}
+
+/// `hashStruct(s)`, i.e. `keccak256(typeHash || encodeData(s))`, where `members` are encoded by
+/// the helpers above.
+pub fn hash_struct<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    type_hash: &Word<E>,
+    members: &[Word<E>],
+) -> Result<Word<E>, SynthesisError> {
+    let mut bytes = type_hash.to_vec();
+    for member in members.iter() {
+        bytes.extend(member);
+    }
+    keccak256::digest(cs, &bytes)
+}
+
+/// Type hash of `encodeType`, e.g. `Mail(address from,uint256 amount)`.
+pub fn type_hash<E: Engine>(encode_type: &str) -> Word<E> {
+    constant_word(&Keccak256::digest(encode_type).into())
+}
+
+/// Domain separator of [`DOMAIN_TYPE`], where `name` and `version` are the constant strings.
+pub fn domain_separator<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    name: &str,
+    version: &str,
+    chain_id: &UInt256<E>,
+    verifying_contract: &Address<E>,
+) -> Result<Word<E>, SynthesisError> {
+    let members = [
+        constant_word(&Keccak256::digest(name).into()),
+        constant_word(&Keccak256::digest(version).into()),
+        encode_uint256(cs, chain_id)?,
+        encode_address(cs, verifying_contract)?,
+    ];
+    hash_struct(cs, &type_hash(DOMAIN_TYPE), &members)
+}
+
+/// Signed hash `keccak256("\x19\x01" || domainSeparator || hashStruct(message))`.
+pub fn typed_data_hash<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    domain_separator: &Word<E>,
+    struct_hash: &Word<E>,
+) -> Result<Word<E>, SynthesisError> {
+    let mut bytes = TYPED_DATA_PREFIX.map(Byte::constant).to_vec();
+    bytes.extend(domain_separator);
+    bytes.extend(struct_hash);
+    keccak256::digest(cs, &bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte,
+        franklin_crypto::{
+            bellman::{
+                pairing::bn256::Bn256, plonk::better_better_cs::cs::ConstraintSystem,
+                SynthesisError,
+            },
+            plonk::circuit::boolean::Boolean,
+        },
+        traits::CSAllocatable,
+        vm::primitives::uint256::UInt256,
+    };
+    use num_bigint::BigUint;
+
+    use super::{
+        domain_separator, encode_address, encode_bool, encode_bytes, encode_fixed_bytes,
+        encode_int, encode_uint, hash_struct, type_hash, typed_data_hash,
+    };
+    use crate::{gadgets::ethereum::Address, utils::testing::create_test_constraint_system};
+
+    fn allocate<CS: ConstraintSystem<Bn256>>(
+        cs: &mut CS,
+        bytes: &[u8],
+    ) -> Result<Vec<Byte<Bn256>>, SynthesisError> {
+        bytes
+            .iter()
+            .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+            .collect()
+    }
+
+    fn hash_person<CS: ConstraintSystem<Bn256>>(
+        cs: &mut CS,
+        name: &[u8],
+        wallet: &str,
+    ) -> Result<[Byte<Bn256>; 32], SynthesisError> {
+        let name = encode_bytes(cs, &allocate(cs, name)?)?;
+        let wallet = hex::decode(wallet).unwrap();
+        let wallet = Address::from_bytes(cs, &allocate(cs, &wallet)?.try_into().unwrap())?;
+        let wallet = encode_address(cs, &wallet)?;
+        let type_hash = type_hash("Person(string name,address wallet)");
+        hash_struct(cs, &type_hash, &[name, wallet])
+    }
+
+    #[test]
+    fn test_typed_data_hash() -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        // Example of EIP-712, i.e. `Mail(Person from,Person to,string contents)` signed in the
+        // domain of "Ether Mail", where `Person(string name,address wallet)`
+        let contract = hex::decode("CcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC").unwrap();
+        let contract = Address::from_bytes(cs, &allocate(cs, &contract)?.try_into().unwrap())?;
+        let chain_id = UInt256::alloc_from_witness(cs, Some(BigUint::from(1u64)))?;
+        let separator = domain_separator(cs, "Ether Mail", "1", &chain_id, &contract)?;
+        assert_eq!(
+            hex::encode(Byte::get_byte_value_multiple(&separator).unwrap()),
+            "f2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f"
+        );
+
+        let from = hash_person(cs, b"Cow", "CD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826")?;
+        let to = hash_person(cs, b"Bob", "bBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB")?;
+        let contents = encode_bytes(cs, &allocate(cs, b"Hello, Bob!")?)?;
+        let mail_type = "Mail(Person from,Person to,string contents)\
+                         Person(string name,address wallet)";
+        let mail = hash_struct(cs, &type_hash(mail_type), &[from, to, contents])?;
+        assert_eq!(
+            hex::encode(Byte::get_byte_value_multiple(&mail).unwrap()),
+            "c52c0ee5d84264471806290a3f2c4cecfc5490626bf912d01f240d7a274b371e"
+        );
+        let hash = typed_data_hash(cs, &separator, &mail)?;
+        assert_eq!(
+            hex::encode(Byte::get_byte_value_multiple(&hash).unwrap()),
+            "be609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2"
+        );
+
+        let words = [
+            (
+                encode_uint(&allocate(cs, &[0x12, 0x34])?),
+                format!("{:0>64}", "1234"),
+            ),
+            (
+                encode_int(cs, &allocate(cs, &[0xff, 0xd6])?)?,
+                format!("{:f>64}", "ffd6"),
+            ),
+            (
+                encode_int(cs, &allocate(cs, &[0x00, 0x2a])?)?,
+                format!("{:0>64}", "2a"),
+            ),
+            (
+                encode_bool(cs, &Boolean::constant(true)),
+                format!("{:0>64}", "01"),
+            ),
+            (
+                encode_fixed_bytes(&allocate(cs, b"navs")?),
+                format!("{:0<64}", "6e617673"),
+            ),
+        ];
+        for (word, expected) in words {
+            let word = Byte::get_byte_value_multiple(&word).unwrap();
+            assert_eq!(hex::encode(word), expected);
+        }
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+}
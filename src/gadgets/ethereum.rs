@@ -5,9 +5,13 @@ use advanced_circuit_component::{
     circuit_structures::byte::{Byte, IntoBytes},
     franklin_crypto::{
         bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
-        plonk::circuit::boolean::Boolean,
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    utils::u64_to_fe,
+    vm::{
+        partitioner::{smart_and, smart_or},
+        primitives::uint256::UInt256,
     },
-    vm::primitives::uint256::UInt256,
 };
 use num::traits::{FromBytes, ToBytes};
 use num_bigint::BigUint;
@@ -115,6 +119,290 @@ impl<E: Engine> Address<E> {
     }
 }
 
+// Prefix of Ethereum signed messages of 32 bytes, see EIP-191
+pub const ETH_SIGNED_MESSAGE_PREFIX: &[u8; 28] = b"\x19Ethereum Signed Message:\n32";
+
+/// Hash of Ethereum signed message of the 32-bytes `hash`, which is
+/// `keccak256("\x19Ethereum Signed Message:\n32" || hash)` like `toEthSignedMessageHash`.
+pub fn eth_signed_message_hash(hash: &[u8; 32]) -> [u8; 32] {
+    use sha3::Digest as _;
+    sha3::Keccak256::new()
+        .chain_update(ETH_SIGNED_MESSAGE_PREFIX)
+        .chain_update(hash)
+        .finalize()
+        .into()
+}
+
+/// Same as [`eth_signed_message_hash`].
+pub fn circuit_eth_signed_message_hash<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    hash: &[Byte<E>; 32],
+) -> Result<[Byte<E>; 32], SynthesisError> {
+    let mut bytes = ETH_SIGNED_MESSAGE_PREFIX.map(Byte::constant).to_vec();
+    bytes.extend_from_slice(hash);
+    super::keccak256::digest(cs, &bytes)
+}
+
+// Offset of difficulty in RLP-encoded block header. All fields before it (parentHash, ommersHash,
+// beneficiary, stateRoot, transactionsRoot, receiptsRoot and logsBloom) have fixed length.
+const BLOCK_HEADER_DIFFICULTY_OFFSET: usize = 448;
+// Offset of stateRoot in RLP-encoded block header
+const BLOCK_HEADER_STATE_ROOT_OFFSET: usize = 91;
+// Offset of receiptsRoot in RLP-encoded block header
+const BLOCK_HEADER_RECEIPTS_ROOT_OFFSET: usize = 157;
+// Number of numeric fields between logsBloom and timestamp: difficulty, number, gasLimit and gasUsed
+const BLOCK_HEADER_NUM_FIELDS_BEFORE_TIMESTAMP: usize = 4;
+// Max byte size of numeric fields up to timestamp
+const BLOCK_HEADER_MAX_NUM_BS: usize = 8;
+// RLP prefixes of fields before difficulty, in (offset, prefix) pairs
+const BLOCK_HEADER_FIXED_PREFIXES: [(usize, u8); 9] = [
+    (3, 0xa0),   // parentHash
+    (36, 0xa0),  // ommersHash
+    (69, 0x94),  // beneficiary
+    (90, 0xa0),  // stateRoot
+    (123, 0xa0), // transactionsRoot
+    (156, 0xa0), // receiptsRoot
+    (189, 0xb9), // logsBloom
+    (190, 0x01),
+    (191, 0x00),
+];
+
+/// Circuit representation of RLP-encoded Ethereum block header.
+///
+/// The length of header is fixed by witness, which is 500+ bytes for all mainnet blocks.
+#[derive(Debug, Clone)]
+pub struct BlockHeader<E: Engine> {
+    pub bytes: Vec<Byte<E>>,
+}
+
+/// Fields of a block header verified by [`BlockHeader::verify`].
+#[derive(Debug, Clone, Copy)]
+pub struct VerifiedBlockHeader<E: Engine> {
+    pub state_root: [Byte<E>; 32],
+    pub receipts_root: [Byte<E>; 32],
+    pub timestamp: Num<E>,
+}
+
+impl<E: Engine> BlockHeader<E> {
+    pub fn from_rlp_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &[u8],
+    ) -> Result<Self, SynthesisError> {
+        if witness.len() <= BLOCK_HEADER_DIFFICULTY_OFFSET || witness.len() > u16::MAX as usize {
+            return Err(new_synthesis_error(format!(
+                "invalid block header length {}",
+                witness.len()
+            )));
+        }
+        let bytes = witness
+            .iter()
+            .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let header = Self { bytes };
+        header.enforce_fixed_layout(cs)?;
+        Ok(header)
+    }
+
+    /// Enforce the list prefix and prefixes of fixed-length fields.
+    fn enforce_fixed_layout<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<(), SynthesisError> {
+        let payload_len = (self.bytes.len() - 3) as u16;
+        let mut prefixes = vec![(0, 0xf9)];
+        prefixes.extend(
+            payload_len
+                .to_be_bytes()
+                .into_iter()
+                .enumerate()
+                .map(|(i, b)| (i + 1, b)),
+        );
+        prefixes.extend(BLOCK_HEADER_FIXED_PREFIXES);
+        for (offset, prefix) in prefixes {
+            self.bytes[offset]
+                .inner
+                .enforce_equal(cs, &Num::Constant(u64_to_fe(prefix as u64)))?;
+        }
+        Ok(())
+    }
+
+    /// Block hash, i.e. keccak256 of RLP-encoded header.
+    pub fn hash<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<[Byte<E>; 32], SynthesisError> {
+        super::keccak256::digest(cs, &self.bytes)
+    }
+
+    /// State root of block, whose offset is fixed.
+    pub fn state_root(&self) -> [Byte<E>; 32] {
+        let offset = BLOCK_HEADER_STATE_ROOT_OFFSET;
+        self.bytes[offset..offset + 32].try_into().unwrap()
+    }
+
+    /// Receipts root of block, whose offset is fixed.
+    pub fn receipts_root(&self) -> [Byte<E>; 32] {
+        let offset = BLOCK_HEADER_RECEIPTS_ROOT_OFFSET;
+        self.bytes[offset..offset + 32].try_into().unwrap()
+    }
+
+    /// Check the header hashes to the committed `block_hash`. Return whether it does and the
+    /// fields of header, which are only meaningful if the check passes.
+    pub fn verify<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        block_hash: &[Byte<E>; 32],
+    ) -> Result<(Boolean, VerifiedBlockHeader<E>), SynthesisError> {
+        let hash = self.hash(cs)?;
+        let is_equal = hash
+            .iter()
+            .zip(block_hash.iter())
+            .map(|(a, b)| Num::equals(cs, &a.inner, &b.inner))
+            .collect::<Result<Vec<_>, _>>()?;
+        let is_valid = smart_and(cs, &is_equal)?;
+        let header = VerifiedBlockHeader {
+            state_root: self.state_root(),
+            receipts_root: self.receipts_root(),
+            timestamp: self.timestamp(cs)?,
+        };
+        Ok((is_valid, header))
+    }
+
+    /// Decode timestamp of block, by walking through RLP items from difficulty to gasUsed.
+    pub fn timestamp<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<Num<E>, SynthesisError> {
+        let mut offset = Num::Constant(u64_to_fe(BLOCK_HEADER_DIFFICULTY_OFFSET as u64));
+        for i in 0..BLOCK_HEADER_NUM_FIELDS_BEFORE_TIMESTAMP {
+            let window = self.offset_window(i);
+            let prefix = select_byte(cs, &self.bytes, &offset, window)?;
+            let (is_single_byte, len_flags) = decode_num_prefix(cs, &prefix)?;
+            // The item is a single byte if prefix < 0x80, otherwise prefix is followed by
+            // (prefix - 0x80) bytes
+            let item_len = {
+                let len = prefix.sub(cs, &Num::Constant(u64_to_fe(0x80)))?;
+                let len = len.add(cs, &Num::one())?;
+                Num::conditionally_select(cs, &is_single_byte, &Num::one(), &len)?
+            };
+            let mut flags = vec![is_single_byte];
+            flags.extend(len_flags);
+            let is_valid = smart_or(cs, &flags)?;
+            Boolean::enforce_equal(cs, &is_valid, &Boolean::constant(true))?;
+            offset = offset.add(cs, &item_len)?;
+        }
+
+        let window = self.offset_window(BLOCK_HEADER_NUM_FIELDS_BEFORE_TIMESTAMP);
+        let prefix = select_byte(cs, &self.bytes, &offset, window.clone())?;
+        let (is_single_byte, len_flags) = decode_num_prefix(cs, &prefix)?;
+        // Timestamp is always larger than 0x80 so that it can not be single byte or empty.
+        Boolean::enforce_equal(cs, &is_single_byte, &Boolean::constant(false))?;
+        Boolean::enforce_equal(cs, &len_flags[0], &Boolean::constant(false))?;
+        let is_valid = smart_or(cs, &len_flags)?;
+        Boolean::enforce_equal(cs, &is_valid, &Boolean::constant(true))?;
+
+        let mut timestamp = Num::zero();
+        for i in 0..BLOCK_HEADER_MAX_NUM_BS {
+            // The i-th byte is included if timestamp length > i
+            let is_included = smart_or(cs, &len_flags[i + 1..])?;
+            let byte_offset = offset.add(cs, &Num::Constant(u64_to_fe(i as u64 + 1)))?;
+            let byte_window = window.start + i + 1..window.end + i + 1;
+            let byte = select_byte(cs, &self.bytes, &byte_offset, byte_window)?;
+            let shifted = timestamp.mul(cs, &Num::Constant(u64_to_fe(256)))?;
+            let shifted = shifted.add(cs, &byte)?;
+            timestamp = Num::conditionally_select(cs, &is_included, &shifted, &timestamp)?;
+        }
+        Ok(timestamp)
+    }
+
+    /// Range of possible offsets of the i-th field after logsBloom.
+    fn offset_window(&self, i: usize) -> std::ops::Range<usize> {
+        let start = BLOCK_HEADER_DIFFICULTY_OFFSET + i;
+        let end = BLOCK_HEADER_DIFFICULTY_OFFSET + i * (1 + BLOCK_HEADER_MAX_NUM_BS) + 1;
+        start..end.min(self.bytes.len())
+    }
+}
+
+/// Select byte at `offset`, which must be in `window`.
+fn select_byte<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    bytes: &[Byte<E>],
+    offset: &Num<E>,
+    window: std::ops::Range<usize>,
+) -> Result<Num<E>, SynthesisError> {
+    let mut selected = Num::zero();
+    let mut is_in_window = Boolean::constant(false);
+    for (i, byte) in bytes.iter().enumerate().take(window.end).skip(window.start) {
+        let is_current = Num::equals(cs, offset, &Num::Constant(u64_to_fe(i as u64)))?;
+        let value = Num::conditionally_select(cs, &is_current, &byte.inner, &Num::zero())?;
+        selected = selected.add(cs, &value)?;
+        is_in_window = Boolean::or(cs, &is_in_window, &is_current)?;
+    }
+    Boolean::enforce_equal(cs, &is_in_window, &Boolean::constant(true))?;
+    Ok(selected)
+}
+
+/// Decode RLP prefix of a numeric field no longer than 8 bytes. Returns whether it's a single byte
+/// item (prefix < 0x80), and flags of whether prefix is `0x80 + i` for i in 0..=8.
+fn decode_num_prefix<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    prefix: &Num<E>,
+) -> Result<(Boolean, Vec<Boolean>), SynthesisError> {
+    let bits = prefix.into_bits_le(cs, Some(8))?;
+    let is_single_byte = bits[7].not();
+    let len_flags = (0..=BLOCK_HEADER_MAX_NUM_BS)
+        .map(|i| Num::equals(cs, prefix, &Num::Constant(u64_to_fe(0x80 + i as u64))))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((is_single_byte, len_flags))
+}
+
+/// State root of RLP-encoded Ethereum block header, in the same way as [`BlockHeader::state_root`].
+pub fn block_header_state_root(header: &[u8]) -> anyhow::Result<[u8; 32]> {
+    if header.len() <= BLOCK_HEADER_DIFFICULTY_OFFSET {
+        anyhow::bail!("block header too short")
+    }
+    let offset = BLOCK_HEADER_STATE_ROOT_OFFSET;
+    Ok(header[offset..offset + 32].try_into().unwrap())
+}
+
+/// Receipts root of RLP-encoded Ethereum block header, in the same way as
+/// [`BlockHeader::receipts_root`].
+pub fn block_header_receipts_root(header: &[u8]) -> anyhow::Result<[u8; 32]> {
+    if header.len() <= BLOCK_HEADER_DIFFICULTY_OFFSET {
+        anyhow::bail!("block header too short")
+    }
+    let offset = BLOCK_HEADER_RECEIPTS_ROOT_OFFSET;
+    Ok(header[offset..offset + 32].try_into().unwrap())
+}
+
+/// Decode timestamp from RLP-encoded Ethereum block header, in the same way as [`BlockHeader::timestamp`].
+pub fn block_header_timestamp(header: &[u8]) -> anyhow::Result<u64> {
+    let prefix_at = |offset: usize| {
+        header
+            .get(offset)
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("block header too short"))
+    };
+    let mut offset = BLOCK_HEADER_DIFFICULTY_OFFSET;
+    for _ in 0..BLOCK_HEADER_NUM_FIELDS_BEFORE_TIMESTAMP {
+        let prefix = prefix_at(offset)?;
+        offset += match prefix {
+            0x00..=0x7f => 1,
+            0x80..=0x88 => 1 + (prefix - 0x80) as usize,
+            _ => anyhow::bail!("invalid prefix {:#x} at {}", prefix, offset),
+        };
+    }
+    let prefix = prefix_at(offset)?;
+    if !(0x81..=0x88).contains(&prefix) {
+        anyhow::bail!("invalid timestamp prefix {:#x} at {}", prefix, offset)
+    }
+    let len = (prefix - 0x80) as usize;
+    let bytes = header
+        .get(offset + 1..offset + 1 + len)
+        .ok_or_else(|| anyhow::anyhow!("block header too short"))?;
+    Ok(bytes.iter().fold(0u64, |acc, b| (acc << 8) | *b as u64))
+}
+
 #[cfg(test)]
 mod tests {
     use advanced_circuit_component::{
@@ -123,8 +411,14 @@ mod tests {
     };
 
     use crate::{
-        gadgets::ethereum::Address,
-        utils::{new_synthesis_error, testing::create_test_constraint_system},
+        gadgets::ethereum::{
+            block_header_receipts_root, block_header_state_root, block_header_timestamp, Address,
+            BlockHeader,
+        },
+        utils::{
+            new_synthesis_error,
+            testing::{block_header_rlp, bytes_assert_eq, create_test_constraint_system},
+        },
     };
 
     #[test]
@@ -190,4 +484,40 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_block_header() -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let header = hex::decode(block_header_rlp()).unwrap();
+        let expected_timestamp = block_header_timestamp(&header).unwrap();
+        assert_eq!(expected_timestamp, 1705311700);
+
+        let block_header = BlockHeader::from_rlp_witness(cs, &header)?;
+        let timestamp = block_header.timestamp(cs)?;
+        assert_eq!(
+            timestamp.get_value().unwrap(),
+            advanced_circuit_component::utils::u64_to_fe(expected_timestamp)
+        );
+        let hash = block_header.hash(cs)?;
+        use sha3::Digest as _;
+        let expected_hash = sha3::Keccak256::new_with_prefix(&header).finalize();
+        bytes_assert_eq(&hash, hex::encode(expected_hash));
+
+        for (block_hash, expected) in [(expected_hash.into(), true), ([0u8; 32], false)] {
+            let block_hash = block_hash.map(|b: u8| Byte::from_u8_witness(cs, Some(b)).unwrap());
+            let (is_valid, verified) = block_header.verify(cs, &block_hash)?;
+            assert_eq!(is_valid.get_value(), Some(expected));
+            assert_eq!(verified.timestamp.get_value(), timestamp.get_value());
+            bytes_assert_eq(
+                &verified.state_root,
+                hex::encode(block_header_state_root(&header).unwrap()),
+            );
+            bytes_assert_eq(
+                &verified.receipts_root,
+                hex::encode(block_header_receipts_root(&header).unwrap()),
+            );
+        }
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
 }
@@ -0,0 +1,166 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::boolean::Boolean,
+    },
+    vm::primitives::uint256::UInt256,
+};
+use num_bigint::BigUint;
+
+use super::uint256::{div_rem, mul_div_rem};
+
+/// Maximal decimals, i.e. `10^77 < 2^256 < 10^78`.
+pub const MAX_DECIMALS: usize = 77;
+
+fn pow10(exponent: usize) -> BigUint {
+    BigUint::from(10u32).pow(exponent as u32)
+}
+
+/// Unsigned decimal `value / 10^decimals`, whose decimals are fixed at synthesis.
+#[derive(Debug, Clone, Copy)]
+pub struct FixedPoint<E: Engine> {
+    pub value: UInt256<E>,
+    pub decimals: usize,
+}
+
+impl<E: Engine> FixedPoint<E> {
+    pub fn new(value: UInt256<E>, decimals: usize) -> Self {
+        assert!(decimals <= MAX_DECIMALS);
+        Self { value, decimals }
+    }
+
+    pub fn constant(value: BigUint, decimals: usize) -> Self {
+        Self::new(UInt256::constant(value), decimals)
+    }
+
+    /// Rescale to `decimals`, rounding down if the precision is reduced.
+    pub fn rescale<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        decimals: usize,
+    ) -> Result<Self, SynthesisError> {
+        assert!(decimals <= MAX_DECIMALS);
+        let value = if decimals == self.decimals {
+            self.value
+        } else if decimals > self.decimals {
+            let factor = UInt256::constant(pow10(decimals - self.decimals));
+            let one = UInt256::constant(BigUint::from(1u64));
+            mul_div_rem(cs, &self.value, &factor, &one)?.0
+        } else {
+            let divisor = UInt256::constant(pow10(self.decimals - decimals));
+            div_rem(cs, &self.value, &divisor)?.0
+        };
+        Ok(Self { value, decimals })
+    }
+
+    /// Rescale both to the larger decimals of them.
+    fn align<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        other: &Self,
+    ) -> Result<(Self, Self), SynthesisError> {
+        let decimals = self.decimals.max(other.decimals);
+        Ok((self.rescale(cs, decimals)?, other.rescale(cs, decimals)?))
+    }
+
+    /// Sum in the larger decimals of both.
+    pub fn add<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        other: &Self,
+    ) -> Result<Self, SynthesisError> {
+        let (a, b) = self.align(cs, other)?;
+        let (value, of) = a.value.add(cs, &b.value)?;
+        Boolean::enforce_equal(cs, &of, &Boolean::constant(false))?;
+        Ok(Self::new(value, a.decimals))
+    }
+
+    /// Difference in the larger decimals of both.
+    pub fn sub<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        other: &Self,
+    ) -> Result<Self, SynthesisError> {
+        let (a, b) = self.align(cs, other)?;
+        let (value, borrow) = a.value.sub(cs, &b.value)?;
+        Boolean::enforce_equal(cs, &borrow, &Boolean::constant(false))?;
+        Ok(Self::new(value, a.decimals))
+    }
+
+    /// Product in decimals of `self`, rounding down.
+    pub fn mul<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        other: &Self,
+    ) -> Result<Self, SynthesisError> {
+        let divisor = UInt256::constant(pow10(other.decimals));
+        let (value, _) = mul_div_rem(cs, &self.value, &other.value, &divisor)?;
+        Ok(Self::new(value, self.decimals))
+    }
+
+    /// Quotient in decimals of `self`, rounding down. Circuit is unsatisfied if `other` is zero.
+    pub fn div<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        other: &Self,
+    ) -> Result<Self, SynthesisError> {
+        let factor = UInt256::constant(pow10(other.decimals));
+        let (value, _) = mul_div_rem(cs, &self.value, &factor, &other.value)?;
+        Ok(Self::new(value, self.decimals))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::bellman::SynthesisError, traits::CSAllocatable,
+        vm::primitives::uint256::UInt256,
+    };
+    use num_bigint::BigUint;
+
+    use super::FixedPoint;
+    use crate::utils::testing::create_test_constraint_system;
+
+    #[test]
+    fn test_fixed_point() -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let n = cs.n();
+        // 2500.5 with 8 decimals, and 0.000321 with 18 decimals
+        let eth = UInt256::alloc_from_witness(cs, Some(BigUint::from(250_050_000_000u64)))?;
+        let eth = FixedPoint::new(eth, 8);
+        let btc = BigUint::from(321_000_000_000_000u64);
+        let btc = FixedPoint::new(UInt256::alloc_from_witness(cs, Some(btc))?, 18);
+
+        let cases = [
+            (eth.rescale(cs, 6)?, 2_500_500_000u64, 6),
+            (eth.rescale(cs, 10)?, 25_005_000_000_000, 10),
+            (btc.rescale(cs, 5)?, 32, 5),
+            (eth.mul(cs, &btc)?, 80_266_050, 8),
+            (
+                eth.div(cs, &FixedPoint::constant(4u64.into(), 0))?,
+                62_512_500_000,
+                8,
+            ),
+            (btc.div(cs, &eth)?, 128_374_325_134, 18),
+        ];
+        for (result, value, decimals) in cases {
+            assert_eq!(result.value.get_value(), Some(BigUint::from(value)));
+            assert_eq!(result.decimals, decimals);
+        }
+        let sum = eth.add(cs, &btc)?;
+        let expected = BigUint::from(2_500_500_321u64) * BigUint::from(10u64).pow(12);
+        assert_eq!(sum.value.get_value(), Some(expected));
+        assert_eq!(sum.decimals, 18);
+        let difference = eth.sub(cs, &btc)?;
+        let expected = BigUint::from(2_500_499_679u64) * BigUint::from(10u64).pow(12);
+        assert_eq!(difference.value.get_value(), Some(expected));
+        println!("Roughly {} gates", cs.n() - n);
+        assert!(cs.is_satisfied());
+
+        // Negative difference
+        btc.sub(cs, &eth)?;
+        assert!(!cs.is_satisfied());
+        Ok(())
+    }
+}
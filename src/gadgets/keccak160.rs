@@ -9,11 +9,42 @@ use advanced_circuit_component::{
     glue::prepacked_long_comparison,
 };
 use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use sha3::{Digest, Keccak256};
 
 use crate::utils::{new_synthesis_error, num_from_be_bytes};
 
 pub const WIDTH_HASH_BYTES: usize = 20;
 pub type Hash<E> = [Byte<E>; WIDTH_HASH_BYTES];
+// Prefix of leaf preimages, which separates leaves from nodes
+pub const MERKLE_LEAF_PREFIX: u8 = 0;
+// Prefix of node preimages
+pub const MERKLE_NODE_PREFIX: u8 = 1;
+
+/// Native keccak160, i.e. the first 20 bytes of keccak256.
+pub fn native_digest(bytes: &[u8]) -> [u8; WIDTH_HASH_BYTES] {
+    Keccak256::digest(bytes)[..WIDTH_HASH_BYTES]
+        .try_into()
+        .unwrap()
+}
+
+/// Native computation of the merkle root from `item` and its path, in the same way as
+/// [`MerkleRoot::check`]. It lets provers check a price update belongs to the root of a VAA
+/// before synthesis.
+pub fn native_merkle_root(
+    item: &[u8],
+    path: &[[u8; WIDTH_HASH_BYTES]],
+) -> [u8; WIDTH_HASH_BYTES] {
+    let mut current = native_digest(&[&[MERKLE_LEAF_PREFIX], item].concat());
+    for node in path.iter() {
+        let (l, r) = if current <= *node {
+            (current, *node)
+        } else {
+            (*node, current)
+        };
+        current = native_digest(&[&[MERKLE_NODE_PREFIX][..], &l, &r].concat());
+    }
+    current
+}
 
 fn hash_from_slice<E: Engine>(bytes: &[Byte<E>]) -> Result<Hash<E>, SynthesisError> {
     bytes.try_into().map_err(|_| {
@@ -83,7 +114,7 @@ impl<E: Engine> MerkleRoot<E> {
         cs: &mut CS,
         item: &[Byte<E>],
     ) -> Result<Hash<E>, SynthesisError> {
-        let mut bytes = vec![Byte::zero()];
+        let mut bytes = vec![Byte::constant(MERKLE_LEAF_PREFIX)];
         bytes.extend_from_slice(item);
         digest(cs, &bytes)
     }
@@ -118,7 +149,7 @@ impl<E: Engine> MerkleRoot<E> {
         };
         // https://github.com/pyth-network/pyth-crosschain/blob/245cc231fd0acd5d91757ab29f474237c2a606aa/pythnet/pythnet_sdk/src/accumulators/merkle.rs#L201-L207
         let mut bytes = [Byte::zero(); 1 + WIDTH_HASH_BYTES * 2];
-        bytes[0] = Byte::<E>::constant(1);
+        bytes[0] = Byte::<E>::constant(MERKLE_NODE_PREFIX);
         bytes[1..WIDTH_HASH_BYTES + 1].copy_from_slice(&l[..]);
         bytes[WIDTH_HASH_BYTES + 1..].copy_from_slice(&r[..]);
         digest(cs, &bytes)
@@ -221,13 +252,24 @@ mod tests {
             .map(|h| hex_to_hash(cs, h));
             MerklePath::new(nodes)
         };
-        let item = hex_to_bytes(cs, "0007ad7b4a7662d19a6bc675f6b467172d2f3947fa653ca97555a9b2023640662800000000152f9dbf00000000000796fafffffff800000000655ccff700000000655ccff70000000015718f26000000000008745c");
+        let item_hex = "0007ad7b4a7662d19a6bc675f6b467172d2f3947fa653ca97555a9b2023640662800000000152f9dbf00000000000796fafffffff800000000655ccff700000000655ccff70000000015718f26000000000008745c";
+        let item = hex_to_bytes(cs, item_hex);
         let n = cs.n();
         let valid = merkle_root.check(cs, &merkle_path, &item)?;
         Boolean::enforce_equal(cs, &valid, &Boolean::constant(true))?;
         let n = cs.n() - n;
         println!("Roughly {} gates", n);
         assert!(cs.is_satisfied());
+
+        // Native computation gets the same root
+        let native_path = merkle_path
+            .0
+            .map(|hash| Byte::get_byte_value_multiple(&hash).unwrap().try_into().unwrap());
+        let native_root = super::native_merkle_root(&hex::decode(item_hex).unwrap(), &native_path);
+        assert_eq!(
+            hex::encode(native_root),
+            "095bb7e5fa374ea08603a6698123d99101547a50"
+        );
         Ok(())
     }
 }
@@ -1,15 +1,56 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{ff::Field, Engine};
 use advanced_circuit_component::{
     circuit_structures::byte::Byte,
     franklin_crypto::{
         bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
         plonk::circuit::{
+            allocated_num::Num, boolean::Boolean,
             hashes_with_tables::keccak::gadgets::Keccak256Gadget,
+            linear_combination::LinearCombination,
             tables::RANGE_CHECK_SINGLE_APPLICATION_TABLE_NAME,
         },
     },
     scheduler::block_header::keccak_output_into_bytes,
+    utils::u64_to_fe,
+    vm::partitioner::smart_or,
 };
-use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+
+use super::sha512::{word_constant, Word, WORD_BITS};
+
+const RATE_BYTES: usize = 136;
+const LANES: usize = 25;
+
+const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808a,
+    0x8000000080008000,
+    0x000000000000808b,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008a,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000a,
+    0x000000008000808b,
+    0x800000000000008b,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800a,
+    0x800000008000000a,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+// Left rotations of lanes (x, y) indexed by x + 5 * y
+const ROTATIONS: [usize; LANES] = [
+    0, 1, 62, 28, 27, 36, 44, 6, 55, 20, 3, 10, 43, 25, 39, 41, 45, 15, 21, 8, 18, 2, 61, 56, 14,
+];
 
 // cost about 26000 gates for each block
 pub fn digest<E: Engine, CS: ConstraintSystem<E>>(
@@ -30,12 +71,241 @@ pub fn digest<E: Engine, CS: ConstraintSystem<E>>(
     Ok(digest)
 }
 
+fn rotl(word: &Word, n: usize) -> Word {
+    let mut out = *word;
+    for (i, bit) in out.iter_mut().enumerate() {
+        *bit = word[(i + WORD_BITS - n) % WORD_BITS];
+    }
+    out
+}
+
+fn xor<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    a: &Word,
+    b: &Word,
+) -> Result<Word, SynthesisError> {
+    let mut out = [Boolean::constant(false); WORD_BITS];
+    for i in 0..WORD_BITS {
+        out[i] = Boolean::xor(cs, &a[i], &b[i])?;
+    }
+    Ok(out)
+}
+
+// Keccak-f[1600] on lanes of little-endian bits
+fn keccak_f<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    state: &[Word; LANES],
+) -> Result<[Word; LANES], SynthesisError> {
+    let mut a = *state;
+    for round_constant in ROUND_CONSTANTS {
+        // theta
+        let mut c = [word_constant(0); 5];
+        for (x, column) in c.iter_mut().enumerate() {
+            *column = a[x];
+            for y in 1..5 {
+                *column = xor(cs, column, &a[x + 5 * y])?;
+            }
+        }
+        for x in 0..5 {
+            let d = xor(cs, &c[(x + 4) % 5], &rotl(&c[(x + 1) % 5], 1))?;
+            for y in 0..5 {
+                a[x + 5 * y] = xor(cs, &a[x + 5 * y], &d)?;
+            }
+        }
+        // rho and pi
+        let mut b = [word_constant(0); LANES];
+        for x in 0..5 {
+            for y in 0..5 {
+                b[y + 5 * ((2 * x + 3 * y) % 5)] = rotl(&a[x + 5 * y], ROTATIONS[x + 5 * y]);
+            }
+        }
+        // chi
+        for x in 0..5 {
+            for y in 0..5 {
+                let (b1, b2) = (&b[(x + 1) % 5 + 5 * y], &b[(x + 2) % 5 + 5 * y]);
+                for i in 0..WORD_BITS {
+                    let t = Boolean::and(cs, &b1[i].not(), &b2[i])?;
+                    a[x + 5 * y][i] = Boolean::xor(cs, &b[x + 5 * y][i], &t)?;
+                }
+            }
+        }
+        // iota
+        for (i, bit) in a[0].iter_mut().enumerate() {
+            if (round_constant >> i) & 1 == 1 {
+                *bit = bit.not();
+            }
+        }
+    }
+    Ok(a)
+}
+
+/// Keccak256 digest of the first `len` bytes of `bytes`, where `len` is a witness up to
+/// `bytes.len()`, so that inputs of variable lengths, e.g. VAAs and their payloads, are hashed
+/// by one circuit. Padding is applied at the position of `len`, and the state is taken after the
+/// block holding the padding. Circuit is unsatisfied if `len` exceeds `bytes.len()`.
+///
+/// The permutation is synthesized with boolean gates instead of tables, which costs roughly
+/// 160000 gates for each block up to `bytes.len()`, so [`digest`] is preferred for fixed lengths.
+pub fn digest_variable_length<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    bytes: &[Byte<E>],
+    len: &Num<E>,
+) -> Result<[Byte<E>; 32], SynthesisError> {
+    let max_len = bytes.len();
+    let padded_len = (max_len / RATE_BYTES + 1) * RATE_BYTES;
+    // Flags of whether each position is `len`, exactly one of which is set
+    let mut is_end = Vec::with_capacity(padded_len);
+    for i in 0..padded_len {
+        let flag = if i <= max_len {
+            Num::equals(cs, len, &Num::Constant(u64_to_fe(i as u64)))?
+        } else {
+            Boolean::constant(false)
+        };
+        is_end.push(flag);
+    }
+    let is_len_valid = smart_or(cs, &is_end)?;
+    Boolean::enforce_equal(cs, &is_len_valid, &Boolean::constant(true))?;
+
+    let mut state = [word_constant(0); LANES];
+    let mut digest = [Num::zero(); 32];
+    let mut is_past_end = Boolean::constant(false);
+    for (k, flags) in is_end.chunks_exact(RATE_BYTES).enumerate() {
+        let is_last_block = smart_or(cs, flags)?;
+        let mut block = Vec::with_capacity(RATE_BYTES);
+        for (j, flag) in flags.iter().enumerate() {
+            is_past_end = Boolean::or(cs, &is_past_end, flag)?;
+            let byte = bytes
+                .get(k * RATE_BYTES + j)
+                .map_or(Num::zero(), |b| b.inner);
+            let byte = Num::conditionally_select(cs, &is_past_end, &Num::zero(), &byte)?;
+            // Padding 0x01 at `len` and 0x80 at the end of its block
+            let mut lc = LinearCombination::zero();
+            lc.add_assign_number_with_coeff(&byte, E::Fr::one());
+            lc.add_assign_boolean_with_coeff(flag, E::Fr::one());
+            if j == RATE_BYTES - 1 {
+                lc.add_assign_boolean_with_coeff(&is_last_block, u64_to_fe(0x80));
+            }
+            block.push(lc.into_num(cs)?);
+        }
+        absorb_block(cs, &mut state, &block)?;
+        for (byte, value) in digest.iter_mut().zip(squeeze(cs, &state)?) {
+            *byte = Num::conditionally_select(cs, &is_last_block, &value, byte)?;
+        }
+    }
+    Ok(digest.map(|num| Byte::from_num_unconstrained(cs, num)))
+}
+
+/// Keccak256 sponge absorbing bytes in segments, e.g. fields of a message as they are parsed.
+/// Lengths of segments are fixed at synthesis, so the padding is constant. A state may be cloned
+/// after absorbing a common prefix, which is then permuted once for all messages sharing it.
+///
+/// The permutation costs the same as in [`digest_variable_length`], so one call of [`digest`] is
+/// cheaper unless prefixes are shared.
+#[derive(Clone)]
+pub struct KeccakState<E: Engine> {
+    lanes: [Word; LANES],
+    // Absorbed bytes not filling a block yet
+    buffer: Vec<Byte<E>>,
+}
+
+impl<E: Engine> Default for KeccakState<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E: Engine> KeccakState<E> {
+    pub fn new() -> Self {
+        Self {
+            lanes: [word_constant(0); LANES],
+            buffer: Vec::with_capacity(RATE_BYTES),
+        }
+    }
+
+    /// Absorb `bytes` after bytes absorbed so far, permuting every filled block.
+    pub fn absorb<CS: ConstraintSystem<E>>(
+        &mut self,
+        cs: &mut CS,
+        bytes: &[Byte<E>],
+    ) -> Result<(), SynthesisError> {
+        for byte in bytes {
+            self.buffer.push(*byte);
+            if self.buffer.len() == RATE_BYTES {
+                let block = self.buffer.iter().map(|b| b.inner).collect::<Vec<_>>();
+                absorb_block(cs, &mut self.lanes, &block)?;
+                self.buffer.clear();
+            }
+        }
+        Ok(())
+    }
+
+    /// Pad absorbed bytes and squeeze the digest, which equals [`digest`] of all absorbed bytes.
+    pub fn finalize<CS: ConstraintSystem<E>>(
+        mut self,
+        cs: &mut CS,
+    ) -> Result<[Byte<E>; 32], SynthesisError> {
+        let mut padding = vec![0u8; RATE_BYTES - self.buffer.len()];
+        padding[0] |= 0x01;
+        *padding.last_mut().unwrap() |= 0x80;
+        let mut block = self.buffer.iter().map(|b| b.inner).collect::<Vec<_>>();
+        block.extend(
+            padding
+                .into_iter()
+                .map(|b| Num::Constant(u64_to_fe(b as u64))),
+        );
+        absorb_block(cs, &mut self.lanes, &block)?;
+        let digest = squeeze(cs, &self.lanes)?;
+        Ok(digest.map(|num| Byte::from_num_unconstrained(cs, num)))
+    }
+}
+
+// XOR a block of byte values into the rate and permute
+fn absorb_block<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    state: &mut [Word; LANES],
+    block: &[Num<E>],
+) -> Result<(), SynthesisError> {
+    assert_eq!(block.len(), RATE_BYTES);
+    for (lane, chunk) in state.iter_mut().zip(block.chunks_exact(8)) {
+        let mut word = [Boolean::constant(false); WORD_BITS];
+        for (i, byte) in chunk.iter().enumerate() {
+            let bits = byte.into_bits_le(cs, Some(8))?;
+            word[i * 8..(i + 1) * 8].copy_from_slice(&bits);
+        }
+        *lane = xor(cs, lane, &word)?;
+    }
+    *state = keccak_f(cs, state)?;
+    Ok(())
+}
+
+// Byte values of the first 32 bytes of the state
+fn squeeze<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    state: &[Word; LANES],
+) -> Result<[Num<E>; 32], SynthesisError> {
+    let mut digest = [Num::zero(); 32];
+    let bits = state[..4].concat();
+    for (byte, bits) in digest.iter_mut().zip(bits.chunks_exact(8)) {
+        let mut lc = LinearCombination::zero();
+        let mut coeff = E::Fr::one();
+        for bit in bits {
+            lc.add_assign_boolean_with_coeff(bit, coeff);
+            coeff.double();
+        }
+        *byte = lc.into_num(cs)?;
+    }
+    Ok(digest)
+}
+
 #[cfg(test)]
 mod tests {
     use advanced_circuit_component::{
-        circuit_structures::byte::Byte, franklin_crypto::bellman::SynthesisError,
+        circuit_structures::byte::Byte,
+        franklin_crypto::{bellman::SynthesisError, plonk::circuit::allocated_num::Num},
+        utils::u64_to_fe,
     };
 
+    use super::KeccakState;
     use crate::utils::testing::create_test_constraint_system;
 
     #[test]
@@ -59,4 +329,65 @@ mod tests {
         assert!(cs.is_satisfied());
         Ok(())
     }
+
+    #[test]
+    fn test_keccak256_variable_length() -> Result<(), SynthesisError> {
+        let input = (0..150).map(|i| i as u8).collect::<Vec<_>>();
+        // Lengths of 135 and 136 bytes are the longest fitting one block and the shortest
+        // requiring an extra padding block
+        for len in [0, 11, 135, 136, 150] {
+            let cs = &mut create_test_constraint_system()?;
+            let input_bytes = input
+                .iter()
+                .map(|b| Byte::from_u8_witness(cs, Some(*b)).unwrap())
+                .collect::<Vec<_>>();
+            let expected = super::digest(cs, &input_bytes[..len])?;
+            let len = Num::alloc(cs, Some(u64_to_fe(len as u64)))?;
+            let digest = super::digest_variable_length(cs, &input_bytes, &len)?;
+            assert_eq!(
+                Byte::get_byte_value_multiple(&digest),
+                Byte::get_byte_value_multiple(&expected)
+            );
+            assert!(cs.is_satisfied());
+        }
+
+        let cs = &mut create_test_constraint_system()?;
+        let input_bytes = input
+            .iter()
+            .map(|b| Byte::from_u8_witness(cs, Some(*b)).unwrap())
+            .collect::<Vec<_>>();
+        let len = Num::alloc(cs, Some(u64_to_fe(input.len() as u64 + 1)))?;
+        super::digest_variable_length(cs, &input_bytes, &len)?;
+        assert!(!cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_keccak_state() -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let prefix = [7u8; 150]
+            .iter()
+            .map(|b| Byte::from_u8_witness(cs, Some(*b)).unwrap())
+            .collect::<Vec<_>>();
+        let mut state = KeccakState::new();
+        state.absorb(cs, &prefix[..100])?;
+        state.absorb(cs, &prefix[100..])?;
+        // The prefix is absorbed once and shared by messages of 0, 1 and 122 more bytes, where
+        // 122 bytes end exactly at the boundary of a block
+        for suffix_len in [0, 1, 121, 122] {
+            let suffix = (0..suffix_len)
+                .map(|i| Byte::from_u8_witness(cs, Some(i as u8)).unwrap())
+                .collect::<Vec<_>>();
+            let mut message = state.clone();
+            message.absorb(cs, &suffix)?;
+            let digest = message.finalize(cs)?;
+            let expected = super::digest(cs, &[prefix.clone(), suffix].concat())?;
+            assert_eq!(
+                Byte::get_byte_value_multiple(&digest),
+                Byte::get_byte_value_multiple(&expected)
+            );
+        }
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
 }
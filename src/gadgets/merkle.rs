@@ -0,0 +1,482 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, Field, SynthesisError},
+        plonk::circuit::{
+            allocated_num::Num,
+            boolean::{AllocatedBit, Boolean},
+            linear_combination::LinearCombination,
+        },
+    },
+    utils::u64_to_fe,
+    vm::partitioner::{smart_and, smart_or},
+};
+
+/// Proof of keccak256 trees, whose nodes hash `keccak256(left || right)` with
+/// [`super::keccak256::digest`].
+pub type KeccakMerkleProof<E, const DEPTH: usize> = MerkleProof<E, 32, DEPTH>;
+
+/// Native computation of the root from `leaf` at `index` and its `siblings` from the bottom, in
+/// the same way as [`MerkleProof::root`].
+pub fn native_root<const BS: usize>(
+    hash: impl Fn(&[u8]) -> [u8; BS],
+    leaf: &[u8; BS],
+    siblings: &[[u8; BS]],
+    index: u64,
+) -> [u8; BS] {
+    let mut current = *leaf;
+    for (i, sibling) in siblings.iter().enumerate() {
+        current = if (index >> i) & 1 == 1 {
+            hash(&[&sibling[..], &current[..]].concat())
+        } else {
+            hash(&[&current[..], &sibling[..]].concat())
+        };
+    }
+    current
+}
+
+/// Circuit representation of the proof of a leaf in a tree of depth `DEPTH`.
+#[derive(Debug, Clone, Copy)]
+pub struct MerkleProof<E: Engine, const BS: usize, const DEPTH: usize> {
+    /// Siblings from the bottom
+    pub siblings: [[Byte<E>; BS]; DEPTH],
+    /// Bits of the leaf index from the lowest, i.e. whether the node is on the right
+    pub path: [Boolean; DEPTH],
+}
+
+impl<E: Engine, const BS: usize, const DEPTH: usize> MerkleProof<E, BS, DEPTH> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        siblings: &[[u8; BS]; DEPTH],
+        index: u64,
+    ) -> Result<Self, SynthesisError> {
+        let mut allocated = [[Byte::zero(); BS]; DEPTH];
+        for (hash, sibling) in allocated.iter_mut().zip(siblings) {
+            for (byte, b) in hash.iter_mut().zip(sibling) {
+                *byte = Byte::from_u8_witness(cs, Some(*b))?;
+            }
+        }
+        let index = Num::alloc(cs, Some(u64_to_fe(index)))?;
+        Self::from_index(cs, allocated, &index)
+    }
+
+    /// Proof of the leaf at `index`. Circuit is unsatisfied if `index` has more than `DEPTH` bits.
+    pub fn from_index<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        siblings: [[Byte<E>; BS]; DEPTH],
+        index: &Num<E>,
+    ) -> Result<Self, SynthesisError> {
+        let path = index.into_bits_le(cs, Some(DEPTH))?;
+        Ok(Self {
+            siblings,
+            path: path.try_into().unwrap(),
+        })
+    }
+
+    /// Root of the tree where `leaf` is at the position of the proof.
+    pub fn root<CS, H>(
+        &self,
+        cs: &mut CS,
+        hash: H,
+        leaf: &[Byte<E>; BS],
+    ) -> Result<[Byte<E>; BS], SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+        H: Fn(&mut CS, &[Byte<E>]) -> Result<[Byte<E>; BS], SynthesisError>,
+    {
+        let mut current = *leaf;
+        for (sibling, is_right) in self.siblings.iter().zip(self.path.iter()) {
+            let mut preimage = vec![Byte::zero(); 2 * BS];
+            for (i, (c, s)) in current.iter().zip(sibling.iter()).enumerate() {
+                let l = Num::conditionally_select(cs, is_right, &s.inner, &c.inner)?;
+                let r = Num::conditionally_select(cs, is_right, &c.inner, &s.inner)?;
+                preimage[i] = Byte::from_num_unconstrained(cs, l);
+                preimage[BS + i] = Byte::from_num_unconstrained(cs, r);
+            }
+            current = hash(cs, &preimage)?;
+        }
+        Ok(current)
+    }
+
+    /// Check if `leaf` is at the position of the proof in the tree of `root`.
+    pub fn check<CS, H>(
+        &self,
+        cs: &mut CS,
+        hash: H,
+        leaf: &[Byte<E>; BS],
+        root: &[Byte<E>; BS],
+    ) -> Result<Boolean, SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+        H: Fn(&mut CS, &[Byte<E>]) -> Result<[Byte<E>; BS], SynthesisError>,
+    {
+        let computed = self.root(cs, hash, leaf)?;
+        let mut is_equal = vec![];
+        for (a, b) in computed.iter().zip(root.iter()) {
+            is_equal.push(Num::equals(cs, &a.inner, &b.inner)?);
+        }
+        smart_and(cs, &is_equal)
+    }
+}
+
+/// Native generation of the multiproof of leaves at `indices` in the tree of `levels` from the
+/// bottom, i.e. the siblings not computed from the leaves and the flags of
+/// [`MerkleMultiproof`].
+pub fn native_multiproof<const BS: usize>(
+    levels: &[Vec<[u8; BS]>],
+    indices: &[u64],
+) -> (Vec<[u8; BS]>, Vec<bool>) {
+    let depth = levels.len() - 1;
+    let node = |g: u64| {
+        let height = depth - (63 - g.leading_zeros() as usize);
+        levels[height][(g - (1 << (depth - height))) as usize]
+    };
+    let mut indices = indices.to_vec();
+    indices.sort();
+    indices.dedup();
+    let mut queue = indices
+        .iter()
+        .map(|index| (1 << depth) + index)
+        .collect::<std::collections::VecDeque<u64>>();
+    let (mut proof, mut flags) = (vec![], vec![]);
+    while let Some(g) = queue.pop_front() {
+        if g == 1 {
+            break;
+        }
+        if g & 1 == 0 && queue.front() == Some(&(g + 1)) {
+            queue.pop_front();
+            flags.push(true);
+        } else {
+            proof.push(node(g ^ 1));
+            flags.push(false);
+        }
+        queue.push_back(g >> 1);
+    }
+    (proof, flags)
+}
+
+/// Native computation of the root from `leaves` of ascending indices and their multiproof, in
+/// the same way as [`MerkleMultiproof::root`]. Return `None` if the multiproof is invalid.
+pub fn native_multiproof_root<const BS: usize>(
+    hash: impl Fn(&[u8]) -> [u8; BS],
+    depth: usize,
+    leaves: &[(u64, [u8; BS])],
+    proof: &[[u8; BS]],
+    flags: &[bool],
+) -> Option<[u8; BS]> {
+    let mut queue = leaves
+        .iter()
+        .map(|(index, leaf)| ((1u64 << depth) + index, *leaf))
+        .collect::<std::collections::VecDeque<_>>();
+    let mut proof = proof.iter();
+    for flag in flags {
+        let (g, a) = queue.pop_front()?;
+        let b = if *flag {
+            let (sibling, b) = queue.pop_front()?;
+            if g & 1 == 1 || sibling != g + 1 {
+                return None;
+            }
+            b
+        } else {
+            *proof.next()?
+        };
+        let (l, r) = if g & 1 == 1 { (b, a) } else { (a, b) };
+        queue.push_back((g >> 1, hash(&[l, r].concat())));
+    }
+    match (queue.pop_front(), proof.next()) {
+        (Some((1, root)), None) if queue.is_empty() => Some(root),
+        _ => None,
+    }
+}
+
+/// Circuit representation of the multiproof of several leaves in a tree of depth `DEPTH`, whose
+/// sizes are fixed at synthesis.
+///
+/// Nodes are processed in a queue, which initially has the leaves of ascending indices. Each step
+/// pops a node, pairs it with its sibling which is the next node in the queue if the flag of the
+/// step is set and the next hash of `proof` otherwise, and pushes their parent. The root is the
+/// last node. Nodes carry their generalized indices, i.e. 1 for the root and `2g` and `2g + 1` for
+/// children of `g`, which determine the order of siblings and bind leaves to their indices.
+#[derive(Debug, Clone)]
+pub struct MerkleMultiproof<E: Engine, const BS: usize, const DEPTH: usize> {
+    pub proof: Vec<[Byte<E>; BS]>,
+    pub flags: Vec<Boolean>,
+}
+
+impl<E: Engine, const BS: usize, const DEPTH: usize> MerkleMultiproof<E, BS, DEPTH> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        proof: &[[u8; BS]],
+        flags: &[bool],
+    ) -> Result<Self, SynthesisError> {
+        let mut allocated = vec![];
+        for hash in proof.iter() {
+            let mut bytes = [Byte::zero(); BS];
+            for (byte, b) in bytes.iter_mut().zip(hash) {
+                *byte = Byte::from_u8_witness(cs, Some(*b))?;
+            }
+            allocated.push(bytes);
+        }
+        let flags = flags
+            .iter()
+            .map(|flag| Ok(Boolean::Is(AllocatedBit::alloc(cs, Some(*flag))?)))
+            .collect::<Result<Vec<_>, SynthesisError>>()?;
+        Ok(Self {
+            proof: allocated,
+            flags,
+        })
+    }
+
+    /// Root of the tree where `leaves` are at their indices, which must be ascending. Return
+    /// whether the multiproof is valid and the root, which is only meaningful if the check passes.
+    /// Circuit is unsatisfied if an index has more than `DEPTH` bits.
+    pub fn root<CS, H>(
+        &self,
+        cs: &mut CS,
+        hash: H,
+        leaves: &[(Num<E>, [Byte<E>; BS])],
+    ) -> Result<(Boolean, [Byte<E>; BS]), SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+        H: Fn(&mut CS, &[Byte<E>]) -> Result<[Byte<E>; BS], SynthesisError>,
+    {
+        assert!(!leaves.is_empty());
+        assert_eq!(self.flags.len() + 1, leaves.len() + self.proof.len());
+        let mut queue = vec![];
+        for (index, leaf) in leaves.iter() {
+            index.into_bits_le(cs, Some(DEPTH))?;
+            let g = index.add(cs, &Num::Constant(u64_to_fe(1 << DEPTH)))?;
+            queue.push((g, leaf.map(|byte| byte.inner)));
+        }
+        let proof = self
+            .proof
+            .iter()
+            .map(|hash| (Num::zero(), hash.map(|byte| byte.inner)))
+            .collect::<Vec<_>>();
+
+        let mut is_valid = vec![];
+        let (mut next, mut next_proof) = (Num::zero(), Num::zero());
+        for flag in self.flags.iter() {
+            let mut is_next = vec![];
+            for k in 0..queue.len() {
+                let k = Num::Constant(u64_to_fe(k as u64));
+                is_next.push(Num::equals(cs, &next, &k)?);
+            }
+            let mut is_next_proof = vec![];
+            for k in 0..proof.len() {
+                let k = Num::Constant(u64_to_fe(k as u64));
+                is_next_proof.push(Num::equals(cs, &next_proof, &k)?);
+            }
+            // The sibling in the queue is right after the node
+            let last = queue.len() - 1;
+            let (g, node) = select_node(cs, &is_next, &queue)?;
+            let (sibling_g, queued) = select_node(cs, &is_next[..last], &queue[1..])?;
+            let (_, proved) = select_node(cs, &is_next_proof, &proof)?;
+            let has_queued = any(cs, &is_next[..last])?;
+            let has_proved = any(cs, &is_next_proof)?;
+            let has_sibling = Boolean::conditionally_select(cs, flag, &has_queued, &has_proved)?;
+            is_valid.push(smart_or(cs, &is_next)?);
+            is_valid.push(has_sibling);
+
+            let bits = g.into_bits_le(cs, Some(DEPTH + 1))?;
+            let is_right = bits[0];
+            let mut parent = LinearCombination::zero();
+            let mut coeff = E::Fr::one();
+            for bit in bits[1..].iter() {
+                parent.add_assign_boolean_with_coeff(bit, coeff);
+                coeff.double();
+            }
+            let next_g = g.add(cs, &Num::one())?;
+            let is_sibling = Num::equals(cs, &sibling_g, &next_g)?;
+            let is_sibling = Boolean::and(cs, &is_sibling, &is_right.not())?;
+            is_valid.push(Boolean::or(cs, &flag.not(), &is_sibling)?);
+
+            let mut preimage = vec![Byte::zero(); 2 * BS];
+            for i in 0..BS {
+                let sibling = Num::conditionally_select(cs, flag, &queued[i], &proved[i])?;
+                let l = Num::conditionally_select(cs, &is_right, &sibling, &node[i])?;
+                let r = Num::conditionally_select(cs, &is_right, &node[i], &sibling)?;
+                preimage[i] = Byte::from_num_unconstrained(cs, l);
+                preimage[BS + i] = Byte::from_num_unconstrained(cs, r);
+            }
+            let parent_hash = hash(cs, &preimage)?;
+            queue.push((parent.into_num(cs)?, parent_hash.map(|byte| byte.inner)));
+
+            let flag = Num::from_boolean_is(*flag);
+            next = next.add(cs, &flag)?;
+            next = next.add(cs, &Num::one())?;
+            next_proof = next_proof.add(cs, &Num::one())?;
+            next_proof = next_proof.sub(cs, &flag)?;
+        }
+        // All nodes but the root are consumed
+        let (root_g, root) = queue.last().unwrap();
+        let last = Num::Constant(u64_to_fe(queue.len() as u64 - 1));
+        is_valid.push(Num::equals(cs, &next, &last)?);
+        is_valid.push(Num::equals(cs, root_g, &Num::one())?);
+        let mut bytes = [Byte::zero(); BS];
+        for (byte, num) in bytes.iter_mut().zip(root.iter()) {
+            *byte = Byte::from_num_unconstrained(cs, *num);
+        }
+        Ok((smart_and(cs, &is_valid)?, bytes))
+    }
+
+    /// Check if `leaves` are at their indices in the tree of `root`.
+    pub fn check<CS, H>(
+        &self,
+        cs: &mut CS,
+        hash: H,
+        leaves: &[(Num<E>, [Byte<E>; BS])],
+        root: &[Byte<E>; BS],
+    ) -> Result<Boolean, SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+        H: Fn(&mut CS, &[Byte<E>]) -> Result<[Byte<E>; BS], SynthesisError>,
+    {
+        let (is_valid, computed) = self.root(cs, hash, leaves)?;
+        let mut is_equal = vec![is_valid];
+        for (a, b) in computed.iter().zip(root.iter()) {
+            is_equal.push(Num::equals(cs, &a.inner, &b.inner)?);
+        }
+        smart_and(cs, &is_equal)
+    }
+}
+
+/// Whether any of `flags` is set, which is false if there are none.
+fn any<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    flags: &[Boolean],
+) -> Result<Boolean, SynthesisError> {
+    if flags.is_empty() {
+        return Ok(Boolean::constant(false));
+    }
+    smart_or(cs, flags)
+}
+
+/// Node of `nodes` at the set flag of `is_selected`, or zeros if none is set.
+fn select_node<E: Engine, CS: ConstraintSystem<E>, const BS: usize>(
+    cs: &mut CS,
+    is_selected: &[Boolean],
+    nodes: &[(Num<E>, [Num<E>; BS])],
+) -> Result<(Num<E>, [Num<E>; BS]), SynthesisError> {
+    let mut g = LinearCombination::zero();
+    let mut node = vec![LinearCombination::zero(); BS];
+    for (flag, (node_g, hash)) in is_selected.iter().zip(nodes.iter()) {
+        let selected = Num::conditionally_select(cs, flag, node_g, &Num::zero())?;
+        g.add_assign_number_with_coeff(&selected, E::Fr::one());
+        for (lc, num) in node.iter_mut().zip(hash.iter()) {
+            let selected = Num::conditionally_select(cs, flag, num, &Num::zero())?;
+            lc.add_assign_number_with_coeff(&selected, E::Fr::one());
+        }
+    }
+    let mut hash = [Num::zero(); BS];
+    for (num, lc) in hash.iter_mut().zip(node) {
+        *num = lc.into_num(cs)?;
+    }
+    Ok((g.into_num(cs)?, hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte,
+        franklin_crypto::{bellman::SynthesisError, plonk::circuit::allocated_num::Num},
+        utils::u64_to_fe,
+    };
+    use sha3::{Digest, Keccak256};
+
+    use super::{
+        native_multiproof, native_multiproof_root, native_root, KeccakMerkleProof, MerkleMultiproof,
+    };
+    use crate::{gadgets::keccak256, utils::testing::create_test_constraint_system};
+
+    fn keccak(bytes: &[u8]) -> [u8; 32] {
+        Keccak256::digest(bytes).into()
+    }
+
+    // Tree of 8 leaves built level by level
+    fn levels() -> Vec<Vec<[u8; 32]>> {
+        let leaves = (0u8..8).map(|i| keccak(&[i])).collect::<Vec<_>>();
+        let mut levels = vec![leaves];
+        while levels.last().unwrap().len() > 1 {
+            let level = levels.last().unwrap();
+            let parents = level.chunks(2).map(|pair| keccak(&pair.concat())).collect();
+            levels.push(parents);
+        }
+        levels
+    }
+
+    #[test]
+    fn test_merkle_proof() -> Result<(), SynthesisError> {
+        let levels = levels();
+        let root = levels[3][0];
+        let index = 5;
+        let siblings: [[u8; 32]; 3] = [0, 1, 2].map(|h| levels[h][(index >> h) ^ 1]);
+        let leaf = levels[0][index];
+        assert_eq!(native_root(keccak, &leaf, &siblings, index as u64), root);
+
+        let cases = [
+            (leaf, index, true),
+            (leaf, 4, false),
+            (levels[0][4], 5, false),
+        ];
+        for (leaf, index, expected) in cases {
+            let cs = &mut create_test_constraint_system()?;
+            let n = cs.n();
+            let proof = KeccakMerkleProof::<_, 3>::from_witness(cs, &siblings, index as u64)?;
+            let leaf = leaf.map(|b| Byte::from_u8_witness(cs, Some(b)).unwrap());
+            let allocated_root = root.map(|b| Byte::from_u8_witness(cs, Some(b)).unwrap());
+            let is_valid = proof.check(cs, keccak256::digest, &leaf, &allocated_root)?;
+            println!("Roughly {} gates", cs.n() - n);
+            assert_eq!(is_valid.get_value(), Some(expected));
+            assert!(cs.is_satisfied());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_merkle_multiproof() -> Result<(), SynthesisError> {
+        let levels = levels();
+        let root = levels[3][0];
+        let indices = [1u64, 2, 3, 6];
+        let (proof, flags) = native_multiproof(&levels, &indices);
+        assert_eq!(proof.len(), 3);
+        assert_eq!(flags, [false, true, false, true, false, true]);
+        let leaves = indices.map(|i| (i, levels[0][i as usize]));
+        let native = native_multiproof_root(keccak, 3, &leaves, &proof, &flags);
+        assert_eq!(native, Some(root));
+        let (all, all_flags) = native_multiproof(&levels, &[0, 1, 2, 3, 4, 5, 6, 7]);
+        assert!(all.is_empty());
+        assert_eq!(all_flags, [true; 7]);
+
+        let mut moved = leaves;
+        moved[3].0 = 7;
+        let mut swapped = leaves;
+        swapped[1].1 = levels[0][3];
+        swapped[2].1 = levels[0][2];
+        assert_ne!(
+            native_multiproof_root(keccak, 3, &moved, &proof, &flags),
+            Some(root)
+        );
+        let cases = [(leaves, true), (moved, false), (swapped, false)];
+        for (leaves, expected) in cases {
+            let cs = &mut create_test_constraint_system()?;
+            let n = cs.n();
+            let multiproof = MerkleMultiproof::<_, 32, 3>::from_witness(cs, &proof, &flags)?;
+            let leaves = leaves
+                .iter()
+                .map(|(index, leaf)| {
+                    let index = Num::alloc(cs, Some(u64_to_fe(*index)))?;
+                    let leaf = leaf.map(|b| Byte::from_u8_witness(cs, Some(b)).unwrap());
+                    Ok((index, leaf))
+                })
+                .collect::<Result<Vec<_>, SynthesisError>>()?;
+            let allocated_root = root.map(|b| Byte::from_u8_witness(cs, Some(b)).unwrap());
+            let is_valid = multiproof.check(cs, keccak256::digest, &leaves, &allocated_root)?;
+            println!("Roughly {} gates", cs.n() - n);
+            assert_eq!(is_valid.get_value(), Some(expected));
+            assert!(cs.is_satisfied());
+        }
+        Ok(())
+    }
+}
@@ -1,6 +1,26 @@
+pub mod bip340;
+pub mod blake2b;
+pub mod bn254;
+pub mod comparison;
 pub mod ecdsa;
+pub mod ed25519;
+pub mod eip712;
 pub mod ethereum;
+pub mod fixed_point;
 pub mod keccak160;
 pub mod keccak256;
+pub mod merkle;
+pub mod mpt;
 pub mod poseidon;
 pub mod rescue;
+pub mod rlp;
+pub mod ripemd160;
+pub mod schnorr;
+pub mod secp256r1;
+pub mod sha256;
+pub mod sha512;
+pub mod signed;
+pub mod smt;
+pub mod ssz;
+pub mod storage;
+pub mod uint256;
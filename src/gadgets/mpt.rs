@@ -0,0 +1,743 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, Field, SynthesisError},
+        plonk::circuit::{
+            allocated_num::Num, boolean::Boolean, linear_combination::LinearCombination,
+        },
+    },
+    utils::u64_to_fe,
+    vm::partitioner::{smart_and, smart_or},
+};
+use sha3::{Digest, Keccak256};
+
+use super::rlp::{decode_item, RlpItem, RlpList};
+use crate::utils::new_synthesis_error;
+
+// Number of nibbles of trie keys
+const KEY_NIBBLES: usize = 64;
+
+/// Layout of a node on the path of a key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum NodeKind {
+    /// Offsets of hashes of non-empty children
+    Branch { children: [Option<usize>; 16] },
+    /// Offset of the compact path, its number of nibbles and offset of the child hash
+    Extension {
+        path: usize,
+        nibbles: usize,
+        child: usize,
+    },
+    /// Offset of the compact path, its number of nibbles, and offset and length of the value
+    Leaf {
+        path: usize,
+        nibbles: usize,
+        value: usize,
+        len: usize,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct NodeLayout {
+    kind: NodeKind,
+    /// Index of the first key nibble consumed by the node
+    depth: usize,
+    /// RLP prefixes in (offset, prefix) pairs
+    prefixes: Vec<(usize, u8)>,
+    /// Offsets of single byte items, which are below 0x80
+    single_bytes: Vec<usize>,
+}
+
+/// Decode an RLP list spanning all of `bytes`. Return the prefix of the list and offsets, payload
+/// offsets and lengths of its items.
+fn rlp_list(bytes: &[u8]) -> anyhow::Result<(Vec<(usize, u8)>, Vec<(usize, usize, usize)>)> {
+    let (payload, len, is_list) = decode_item(bytes, 0)?;
+    if !is_list || payload + len != bytes.len() {
+        anyhow::bail!("invalid RLP list 0x{}", hex::encode(bytes))
+    }
+    let prefixes = bytes[..payload].iter().copied().enumerate().collect();
+    let mut items = vec![];
+    let mut offset = payload;
+    while offset < bytes.len() {
+        let (item_payload, item_len, is_list) = decode_item(bytes, offset)?;
+        if is_list {
+            anyhow::bail!("inline node at {} is not supported", offset)
+        }
+        items.push((offset, item_payload, item_len));
+        offset = item_payload + item_len;
+    }
+    Ok((prefixes, items))
+}
+
+/// Decode a compact-encoded path. Return whether it's a leaf path and its nibbles.
+fn compact_path(bytes: &[u8]) -> anyhow::Result<(bool, Vec<u8>)> {
+    let first = bytes
+        .first()
+        .copied()
+        .ok_or_else(|| anyhow::anyhow!("empty path"))?;
+    let flag = first >> 4;
+    if flag > 3 || (flag & 1 == 0 && first & 0x0f != 0) {
+        anyhow::bail!("invalid path prefix {:#x}", first)
+    }
+    let mut nibbles = if flag & 1 == 1 {
+        vec![first & 0x0f]
+    } else {
+        vec![]
+    };
+    for b in &bytes[1..] {
+        nibbles.extend([b >> 4, b & 0x0f]);
+    }
+    Ok((flag & 2 == 2, nibbles))
+}
+
+fn key_nibbles(key: &[u8; 32]) -> Vec<u8> {
+    key.iter().flat_map(|b| [b >> 4, b & 0x0f]).collect()
+}
+
+/// Walk nodes along the path of `key`, without checking hashes.
+fn proof_layout(key: &[u8; 32], proof: &[Vec<u8>]) -> anyhow::Result<Vec<NodeLayout>> {
+    let key = key_nibbles(key);
+    let mut depth = 0;
+    let mut layouts = vec![];
+    for (i, node) in proof.iter().enumerate() {
+        let is_last = i + 1 == proof.len();
+        let (mut prefixes, items) = rlp_list(node)?;
+        let mut single_bytes = vec![];
+        for (offset, payload, len) in items.iter() {
+            if offset == payload {
+                single_bytes.push(*offset);
+            } else {
+                prefixes.extend(
+                    node[*offset..*payload]
+                        .iter()
+                        .enumerate()
+                        .map(|(j, b)| (offset + j, *b)),
+                );
+            }
+            if *len != 0 && *len != 32 && items.len() == 17 {
+                anyhow::bail!("inline child at {} is not supported", offset)
+            }
+        }
+        let (kind, consumed) = match items.len() {
+            17 => {
+                if is_last || depth >= KEY_NIBBLES {
+                    anyhow::bail!("branch node at depth {} ends the proof", depth)
+                }
+                if items[16].2 != 0 {
+                    anyhow::bail!("branch node with value is not supported")
+                }
+                let mut children = [None; 16];
+                for (child, (_, payload, len)) in children.iter_mut().zip(items.iter()) {
+                    if *len == 32 {
+                        *child = Some(*payload);
+                    }
+                }
+                if children[key[depth] as usize].is_none() {
+                    anyhow::bail!("key is absent at depth {}", depth)
+                }
+                (NodeKind::Branch { children }, 1)
+            }
+            2 => {
+                let (_, path, path_len) = items[0];
+                let (is_leaf, nibbles) = compact_path(&node[path..path + path_len])?;
+                let consumed = nibbles.len();
+                if depth + consumed > KEY_NIBBLES || key[depth..depth + consumed] != nibbles[..] {
+                    anyhow::bail!("key is absent at depth {}", depth)
+                }
+                if is_leaf != is_last || (is_leaf && depth + consumed != KEY_NIBBLES) {
+                    anyhow::bail!("unexpected node kind at depth {}", depth)
+                }
+                let (_, value, len) = items[1];
+                let kind = if is_leaf {
+                    NodeKind::Leaf {
+                        path,
+                        nibbles: consumed,
+                        value,
+                        len,
+                    }
+                } else if len == 32 {
+                    NodeKind::Extension {
+                        path,
+                        nibbles: consumed,
+                        child: value,
+                    }
+                } else {
+                    anyhow::bail!("inline child at depth {} is not supported", depth)
+                };
+                (kind, consumed)
+            }
+            n => anyhow::bail!("invalid node of {} items", n),
+        };
+        layouts.push(NodeLayout {
+            kind,
+            depth,
+            prefixes,
+            single_bytes,
+        });
+        depth += consumed;
+    }
+    if layouts.is_empty() {
+        anyhow::bail!("empty proof")
+    }
+    Ok(layouts)
+}
+
+/// Verify the proof of `key` against `root`, whose nodes are ordered from the root. Return the
+/// value of the key.
+pub fn verify_proof(root: &[u8; 32], key: &[u8; 32], proof: &[Vec<u8>]) -> anyhow::Result<Vec<u8>> {
+    let layouts = proof_layout(key, proof)?;
+    let nibbles = key_nibbles(key);
+    let mut expected = *root;
+    let mut value = vec![];
+    for (layout, node) in layouts.iter().zip(proof.iter()) {
+        let hash: [u8; 32] = Keccak256::digest(node).into();
+        if hash != expected {
+            anyhow::bail!(
+                "node at depth {} hashes to 0x{}, expect 0x{}",
+                layout.depth,
+                hex::encode(hash),
+                hex::encode(expected)
+            )
+        }
+        let child = match layout.kind {
+            NodeKind::Branch { children } => children[nibbles[layout.depth] as usize],
+            NodeKind::Extension { child, .. } => Some(child),
+            NodeKind::Leaf {
+                value: offset, len, ..
+            } => {
+                value = node[offset..offset + len].to_vec();
+                None
+            }
+        };
+        if let Some(child) = child {
+            expected.copy_from_slice(&node[child..child + 32]);
+        }
+    }
+    Ok(value)
+}
+
+/// Storage root of an account, i.e. the third item of `rlp([nonce, balance, storageRoot,
+/// codeHash])`.
+pub fn account_storage_root(account: &[u8]) -> anyhow::Result<[u8; 32]> {
+    let (_, items) = rlp_list(account)?;
+    match items.get(2) {
+        Some((_, payload, 32)) if items.len() == 4 && items[3].2 == 32 => {
+            Ok(account[*payload..payload + 32].try_into().unwrap())
+        }
+        _ => anyhow::bail!("invalid account 0x{}", hex::encode(account)),
+    }
+}
+
+/// Storage value in 32 bytes, decoded from the RLP-encoded value without leading zeros.
+pub fn storage_value(value: &[u8]) -> anyhow::Result<[u8; 32]> {
+    let (payload, len, is_list) = decode_item(value, 0)?;
+    if is_list || len > 32 || payload + len != value.len() {
+        anyhow::bail!("invalid storage value 0x{}", hex::encode(value))
+    }
+    let mut bytes = [0u8; 32];
+    bytes[32 - len..].copy_from_slice(&value[payload..]);
+    Ok(bytes)
+}
+
+/// Split a byte into big-endian nibbles.
+fn circuit_nibbles<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    byte: &Byte<E>,
+) -> Result<[Num<E>; 2], SynthesisError> {
+    let bits = byte.inner.into_bits_le(cs, Some(8))?;
+    let mut nibbles = [Num::zero(); 2];
+    for (nibble, bits) in nibbles.iter_mut().rev().zip(bits.chunks(4)) {
+        for (i, bit) in bits.iter().enumerate() {
+            let bit = Num::from_boolean_is(*bit).mul(cs, &Num::Constant(u64_to_fe(1 << i)))?;
+            *nibble = nibble.add(cs, &bit)?;
+        }
+    }
+    Ok(nibbles)
+}
+
+/// Circuit representation of an inclusion proof, whose layout is fixed by the witness.
+#[derive(Debug, Clone)]
+pub struct AllocatedMptProof<E: Engine> {
+    pub nodes: Vec<Vec<Byte<E>>>,
+    layouts: Vec<NodeLayout>,
+}
+
+impl<E: Engine> AllocatedMptProof<E> {
+    /// Allocate the proof of `key`, whose nodes must be on the path of the key.
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        key: &[u8; 32],
+        proof: &[Vec<u8>],
+    ) -> Result<Self, SynthesisError> {
+        let layouts = proof_layout(key, proof).map_err(new_synthesis_error)?;
+        let nodes = proof
+            .iter()
+            .map(|node| {
+                node.iter()
+                    .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { nodes, layouts })
+    }
+
+    /// Same as [`verify_proof`]. Return whether the proof is valid and the value, which is only
+    /// meaningful if the check passes.
+    pub fn verify<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        root: &[Byte<E>; 32],
+        key: &[Byte<E>; 32],
+    ) -> Result<(Boolean, Vec<Byte<E>>), SynthesisError> {
+        let mut key_nibbles = vec![];
+        for byte in key.iter() {
+            key_nibbles.extend(circuit_nibbles(cs, byte)?);
+        }
+        let mut is_valid = vec![];
+        let mut expected = *root;
+        let mut value = vec![];
+        for (layout, node) in self.layouts.iter().zip(self.nodes.iter()) {
+            let hash = super::keccak256::digest(cs, node)?;
+            for (a, b) in hash.iter().zip(expected.iter()) {
+                is_valid.push(Num::equals(cs, &a.inner, &b.inner)?);
+            }
+            for (offset, prefix) in layout.prefixes.iter() {
+                let prefix = Num::Constant(u64_to_fe(*prefix as u64));
+                is_valid.push(Num::equals(cs, &node[*offset].inner, &prefix)?);
+            }
+            for offset in layout.single_bytes.iter() {
+                let bits = node[*offset].inner.into_bits_le(cs, Some(8))?;
+                is_valid.push(bits[7].not());
+            }
+
+            let (path, nibbles, is_leaf) = match layout.kind {
+                NodeKind::Branch { children } => {
+                    let nibble = key_nibbles[layout.depth];
+                    let mut is_present = vec![];
+                    let mut child = [Num::zero(); 32];
+                    for (i, offset) in children.iter().enumerate() {
+                        let offset = match offset {
+                            Some(offset) => *offset,
+                            None => continue,
+                        };
+                        let is_current =
+                            Num::equals(cs, &nibble, &Num::Constant(u64_to_fe(i as u64)))?;
+                        for (c, b) in child.iter_mut().zip(node[offset..offset + 32].iter()) {
+                            let b = Num::conditionally_select(cs, &is_current, &b.inner, c)?;
+                            *c = b;
+                        }
+                        is_present.push(is_current);
+                    }
+                    is_valid.push(smart_or(cs, &is_present)?);
+                    for (e, c) in expected.iter_mut().zip(child) {
+                        *e = Byte::from_num_unconstrained(cs, c);
+                    }
+                    continue;
+                }
+                NodeKind::Extension {
+                    path,
+                    nibbles,
+                    child,
+                } => {
+                    expected.copy_from_slice(&node[child..child + 32]);
+                    (path, nibbles, false)
+                }
+                NodeKind::Leaf {
+                    path,
+                    nibbles,
+                    value: offset,
+                    len,
+                } => {
+                    value = node[offset..offset + len].to_vec();
+                    (path, nibbles, true)
+                }
+            };
+            // Compact path is the flag nibble, a zero nibble if the path is even and the path
+            let is_odd = nibbles % 2 == 1;
+            let mut path_nibbles = vec![];
+            for byte in node[path..path + nibbles / 2 + 1].iter() {
+                path_nibbles.extend(circuit_nibbles(cs, byte)?);
+            }
+            let flag = (if is_leaf { 2 } else { 0 }) + is_odd as u64;
+            is_valid.push(Num::equals(
+                cs,
+                &path_nibbles[0],
+                &Num::Constant(u64_to_fe(flag)),
+            )?);
+            let path_nibbles = if is_odd {
+                &path_nibbles[1..]
+            } else {
+                is_valid.push(Num::equals(cs, &path_nibbles[1], &Num::zero())?);
+                &path_nibbles[2..]
+            };
+            let key = &key_nibbles[layout.depth..layout.depth + nibbles];
+            for (a, b) in path_nibbles.iter().zip(key.iter()) {
+                is_valid.push(Num::equals(cs, a, b)?);
+            }
+        }
+        Ok((smart_and(cs, &is_valid)?, value))
+    }
+}
+
+/// Circuit representation of an inclusion proof of at most `max_nodes` nodes of at most `max_len`
+/// bytes each. Unlike [`AllocatedMptProof`], nodes are decoded in circuit with [`RlpList`], so the
+/// circuit only depends on the bounds rather than the key and the proof.
+#[derive(Debug, Clone)]
+pub struct PaddedMptProof<E: Engine> {
+    /// Nodes from the root, which are zero-padded to the maximal length
+    pub nodes: Vec<Vec<Byte<E>>>,
+    pub lens: Vec<Num<E>>,
+    pub num_nodes: Num<E>,
+}
+
+impl<E: Engine> PaddedMptProof<E> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        proof: &[Vec<u8>],
+        max_nodes: usize,
+        max_len: usize,
+    ) -> Result<Self, SynthesisError> {
+        if proof.len() > max_nodes || proof.iter().any(|node| node.len() > max_len) {
+            return Err(new_synthesis_error(format!(
+                "proof exceeds {} nodes of {} bytes",
+                max_nodes, max_len
+            )));
+        }
+        let mut nodes = vec![];
+        let mut lens = vec![];
+        for i in 0..max_nodes {
+            let node = proof.get(i).map(|node| &node[..]).unwrap_or(&[]);
+            let mut bytes = node.to_vec();
+            bytes.resize(max_len, 0);
+            nodes.push(
+                bytes
+                    .iter()
+                    .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+                    .collect::<Result<Vec<_>, _>>()?,
+            );
+            lens.push(Num::alloc(cs, Some(u64_to_fe(node.len() as u64)))?);
+        }
+        let num_nodes = Num::alloc(cs, Some(u64_to_fe(proof.len() as u64)))?;
+        Ok(Self {
+            nodes,
+            lens,
+            num_nodes,
+        })
+    }
+
+    /// Same as [`verify_proof`]. Return whether the proof is valid and the value in `max_len` bytes
+    /// and its length, which are only meaningful if the check passes.
+    pub fn verify<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        root: &[Byte<E>; 32],
+        key: &[Byte<E>; 32],
+        max_len: usize,
+    ) -> Result<(Boolean, Vec<Byte<E>>, Num<E>), SynthesisError> {
+        let mut key_nibbles = vec![];
+        for byte in key.iter() {
+            key_nibbles.extend(circuit_nibbles(cs, byte)?);
+        }
+        let mut is_valid = vec![];
+        let mut expected = root.map(|byte| byte.inner);
+        let mut depth = Num::zero();
+        let mut value = vec![Num::zero(); max_len];
+        let mut value_len = Num::zero();
+        let mut is_past_end = Boolean::constant(false);
+        for (i, (node, len)) in self.nodes.iter().zip(self.lens.iter()).enumerate() {
+            let is_end = Num::equals(cs, &self.num_nodes, &Num::Constant(u64_to_fe(i as u64)))?;
+            is_past_end = Boolean::or(cs, &is_past_end, &is_end)?;
+            let is_present = is_past_end.not();
+            let last = Num::Constant(u64_to_fe(i as u64 + 1));
+            let is_last = Num::equals(cs, &self.num_nodes, &last)?;
+
+            let mut checks = vec![];
+            let hash = super::keccak256::digest_variable_length(cs, node, len)?;
+            for (a, b) in hash.iter().zip(expected.iter()) {
+                checks.push(Num::equals(cs, &a.inner, b)?);
+            }
+            let (is_list_valid, list) = RlpList::decode_variable_length(cs, node, len, 17)?;
+            checks.push(is_list_valid);
+            let is_branch = Num::equals(cs, &list.num_items, &Num::Constant(u64_to_fe(17)))?;
+            let is_short = Num::equals(cs, &list.num_items, &Num::Constant(u64_to_fe(2)))?;
+
+            // Branch node, whose child at the key nibble is hashed
+            let mut is_depth = vec![];
+            for j in 0..KEY_NIBBLES {
+                let j = Num::Constant(u64_to_fe(j as u64));
+                is_depth.push(Num::equals(cs, &depth, &j)?);
+            }
+            let nibble = select_num(cs, &is_depth, &key_nibbles)?;
+            let mut is_nibble = vec![];
+            for k in 0..16 {
+                let k = Num::Constant(u64_to_fe(k));
+                is_nibble.push(Num::equals(cs, &nibble, &k)?);
+            }
+            let child = select_item(cs, &is_nibble, &list.items[..16])?;
+            let (_, branch_child) = RlpList::item_bytes(cs, node, &child, 32)?;
+            let branch_checks = [
+                is_branch,
+                smart_or(cs, &is_depth)?,
+                Num::equals(cs, &child.len, &Num::Constant(u64_to_fe(32)))?,
+                child.is_list.not(),
+                is_last.not(),
+            ];
+            let is_branch_valid = smart_and(cs, &branch_checks)?;
+
+            // Extension or leaf node, whose compact path is the flag nibble, a zero nibble if the
+            // path is even and the path
+            let [path_item, next_item] = [list.items[0], list.items[1]];
+            let (path_fits, path) = RlpList::item_bytes(cs, node, &path_item, KEY_NIBBLES / 2 + 1)?;
+            let mut nibbles = vec![];
+            for byte in path.iter() {
+                nibbles.extend(circuit_nibbles(cs, byte)?);
+            }
+            let flag = nibbles[0].into_bits_le(cs, Some(4))?;
+            let (is_odd, is_leaf) = (flag[0], flag[1]);
+            let is_even_padded = Num::equals(cs, &nibbles[1], &Num::zero())?;
+            let mut short_checks = vec![
+                is_short,
+                path_fits,
+                path_item.is_list.not(),
+                Num::equals(cs, &path_item.len, &Num::zero())?.not(),
+                flag[2].not(),
+                flag[3].not(),
+                Boolean::or(cs, &is_odd, &is_even_padded)?,
+                next_item.is_list.not(),
+            ];
+            // Number of nibbles of the path, i.e. `2 * (len - 1) + is_odd`
+            let count = path_item.len.add(cs, &path_item.len)?;
+            let count = count.add(cs, &Num::from_boolean_is(is_odd))?;
+            let count = count.sub(cs, &Num::Constant(u64_to_fe(2)))?;
+            let mut is_past_path = Boolean::constant(false);
+            for j in 0..KEY_NIBBLES {
+                let is_path_end = Num::equals(cs, &count, &Num::Constant(u64_to_fe(j as u64)))?;
+                is_past_path = Boolean::or(cs, &is_past_path, &is_path_end)?;
+                let path_nibble =
+                    Num::conditionally_select(cs, &is_odd, &nibbles[1 + j], &nibbles[2 + j])?;
+                let key_nibble = select_num(cs, &is_depth[..KEY_NIBBLES - j], &key_nibbles[j..])?;
+                let is_equal = Num::equals(cs, &path_nibble, &key_nibble)?;
+                short_checks.push(Boolean::or(cs, &is_past_path, &is_equal)?);
+            }
+            let is_full = Num::equals(cs, &count, &Num::Constant(u64_to_fe(KEY_NIBBLES as u64)))?;
+            short_checks.push(Boolean::or(cs, &is_past_path, &is_full)?);
+            let next_depth = depth.add(cs, &count)?;
+            let is_key_end = Num::equals(
+                cs,
+                &next_depth,
+                &Num::Constant(u64_to_fe(KEY_NIBBLES as u64)),
+            )?;
+            let is_hashed = Num::equals(cs, &next_item.len, &Num::Constant(u64_to_fe(32)))?;
+            let leaf_checks = smart_and(cs, &[is_last, is_key_end])?;
+            let extension_checks = smart_and(cs, &[is_last.not(), is_hashed])?;
+            short_checks.push(Boolean::conditionally_select(
+                cs,
+                &is_leaf,
+                &leaf_checks,
+                &extension_checks,
+            )?);
+            let (_, extension_child) = RlpList::item_bytes(cs, node, &next_item, 32)?;
+            let (value_fits, leaf_value) = RlpList::item_bytes(cs, node, &next_item, max_len)?;
+            short_checks.push(Boolean::or(cs, &is_leaf.not(), &value_fits)?);
+            let is_short_valid = smart_and(cs, &short_checks)?;
+
+            checks.push(Boolean::or(cs, &is_branch_valid, &is_short_valid)?);
+            let is_node_valid = smart_and(cs, &checks)?;
+            is_valid.push(Boolean::or(cs, &is_present.not(), &is_node_valid)?);
+
+            let branch_depth = depth.add(cs, &Num::one())?;
+            let next_depth = Num::conditionally_select(cs, &is_branch, &branch_depth, &next_depth)?;
+            depth = Num::conditionally_select(cs, &is_present, &next_depth, &depth)?;
+            for (e, (b, x)) in expected
+                .iter_mut()
+                .zip(branch_child.iter().zip(extension_child.iter()))
+            {
+                let child = Num::conditionally_select(cs, &is_branch, &b.inner, &x.inner)?;
+                *e = Num::conditionally_select(cs, &is_present, &child, e)?;
+            }
+            for (v, byte) in value.iter_mut().zip(leaf_value.iter()) {
+                *v = Num::conditionally_select(cs, &is_last, &byte.inner, v)?;
+            }
+            value_len = Num::conditionally_select(cs, &is_last, &next_item.len, &value_len)?;
+        }
+        is_valid.push(Num::equals(cs, &self.num_nodes, &Num::zero())?.not());
+        let max_nodes = Num::Constant(u64_to_fe(self.nodes.len() as u64));
+        let is_full = Num::equals(cs, &self.num_nodes, &max_nodes)?;
+        is_valid.push(Boolean::or(cs, &is_past_end, &is_full)?);
+        let value = value
+            .into_iter()
+            .map(|v| Byte::from_num_unconstrained(cs, v))
+            .collect();
+        Ok((smart_and(cs, &is_valid)?, value, value_len))
+    }
+}
+
+/// Number of `values` at the set flag of `is_selected`, or zero if none is set.
+fn select_num<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    is_selected: &[Boolean],
+    values: &[Num<E>],
+) -> Result<Num<E>, SynthesisError> {
+    let mut lc = LinearCombination::zero();
+    for (flag, value) in is_selected.iter().zip(values.iter()) {
+        let value = Num::conditionally_select(cs, flag, value, &Num::zero())?;
+        lc.add_assign_number_with_coeff(&value, E::Fr::one());
+    }
+    lc.into_num(cs)
+}
+
+/// Item of `items` at the set flag of `is_selected`, which is absent if none is set.
+fn select_item<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    is_selected: &[Boolean],
+    items: &[RlpItem<E>],
+) -> Result<RlpItem<E>, SynthesisError> {
+    let offsets = items.iter().map(|item| item.offset).collect::<Vec<_>>();
+    let lens = items.iter().map(|item| item.len).collect::<Vec<_>>();
+    let (mut is_list, mut is_present) = (vec![], vec![]);
+    for (flag, item) in is_selected.iter().zip(items.iter()) {
+        is_list.push(Boolean::and(cs, flag, &item.is_list)?);
+        is_present.push(Boolean::and(cs, flag, &item.is_present)?);
+    }
+    Ok(RlpItem {
+        offset: select_num(cs, is_selected, &offsets)?,
+        len: select_num(cs, is_selected, &lens)?,
+        is_list: smart_or(cs, &is_list)?,
+        is_present: smart_or(cs, &is_present)?,
+    })
+}
+
+/// Same as [`account_storage_root`]. The last two items of accounts are always 32-byte hashes.
+pub fn circuit_account_storage_root<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    account: &[Byte<E>],
+) -> Result<(Boolean, [Byte<E>; 32]), SynthesisError> {
+    let len = account.len();
+    if len < 66 {
+        return Err(new_synthesis_error(format!(
+            "invalid account of {} bytes",
+            len
+        )));
+    }
+    let prefix = Num::Constant(u64_to_fe(0xa0));
+    let is_valid = [
+        Num::equals(cs, &account[len - 66].inner, &prefix)?,
+        Num::equals(cs, &account[len - 33].inner, &prefix)?,
+    ];
+    let root = account[len - 65..len - 33].try_into().unwrap();
+    Ok((smart_and(cs, &is_valid)?, root))
+}
+
+/// Same as [`storage_value`], where the length of the value is fixed by the witness.
+pub fn circuit_storage_value<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    value: &[Byte<E>],
+) -> Result<(Boolean, [Byte<E>; 32]), SynthesisError> {
+    let len = value.len();
+    if len == 0 || len > 33 {
+        return Err(new_synthesis_error(format!(
+            "invalid storage value of {} bytes",
+            len
+        )));
+    }
+    let mut bytes = [Byte::zero(); 32];
+    let is_valid = if len == 1 {
+        bytes[31] = value[0];
+        let bits = value[0].inner.into_bits_le(cs, Some(8))?;
+        bits[7].not()
+    } else {
+        bytes[33 - len..].copy_from_slice(&value[1..]);
+        let prefix = Num::Constant(u64_to_fe(0x80 + len as u64 - 1));
+        Num::equals(cs, &value[0].inner, &prefix)?
+    };
+    Ok((is_valid, bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte,
+        franklin_crypto::bellman::{pairing::bn256::Bn256, SynthesisError},
+        utils::u64_to_fe,
+    };
+    use sha3::{Digest, Keccak256};
+
+    use super::{
+        circuit_storage_value, storage_value, verify_proof, AllocatedMptProof, PaddedMptProof,
+    };
+    use crate::utils::testing::{create_test_constraint_system, mpt_proof};
+
+    #[test]
+    fn test_mpt_proof() -> Result<(), SynthesisError> {
+        let entries = (0u8..20)
+            .map(|i| {
+                let key = Keccak256::digest([i]).into();
+                let value = if i == 0 { vec![0x41] } else { vec![0x82, i, 1] };
+                (key, value)
+            })
+            .collect::<Vec<_>>();
+        let (root, proof) = mpt_proof(&entries, &entries[0].0);
+        let value = verify_proof(&root, &entries[0].0, &proof).unwrap();
+        assert_eq!(value, vec![0x41]);
+        let (_, other) = mpt_proof(&entries, &entries[7].0);
+        let other_value = verify_proof(&root, &entries[7].0, &other).unwrap();
+        assert_eq!(other_value, vec![0x82, 7, 1]);
+        assert!(verify_proof(&root, &entries[7].0, &proof).is_err());
+        let mut tampered = other.clone();
+        *tampered.last_mut().unwrap().last_mut().unwrap() ^= 1;
+        assert!(verify_proof(&root, &entries[7].0, &tampered).is_err());
+
+        for (key, proof, value, expected) in [
+            (entries[0].0, proof, &value, true),
+            (entries[7].0, other, &other_value, true),
+            (entries[7].0, tampered, &other_value, false),
+        ] {
+            let cs = &mut create_test_constraint_system()?;
+            let root = root.map(|b| Byte::<Bn256>::from_u8_witness(cs, Some(b)).unwrap());
+            let key_bytes = key.map(|b| Byte::<Bn256>::from_u8_witness(cs, Some(b)).unwrap());
+            let allocated = AllocatedMptProof::from_witness(cs, &key, &proof)?;
+            let (is_valid, allocated_value) = allocated.verify(cs, &root, &key_bytes)?;
+            assert_eq!(is_valid.get_value(), Some(expected));
+            let (is_value_valid, allocated_value) = circuit_storage_value(cs, &allocated_value)?;
+            assert_eq!(is_value_valid.get_value(), Some(true));
+            if expected {
+                assert_eq!(
+                    Byte::get_byte_value_multiple(&allocated_value).unwrap(),
+                    storage_value(value).unwrap()
+                );
+            }
+            assert!(cs.is_satisfied());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_padded_mpt_proof() -> Result<(), SynthesisError> {
+        let entries = (0u8..20)
+            .map(|i| (Keccak256::digest([i]).into(), vec![0x82, i, 1]))
+            .collect::<Vec<_>>();
+        let (root, proof) = mpt_proof(&entries, &entries[7].0);
+        let max_len = proof.iter().map(|node| node.len()).max().unwrap();
+        // The proof of another key walks a different path
+        for (key, expected) in [(entries[7].0, true), (entries[8].0, false)] {
+            let cs = &mut create_test_constraint_system()?;
+            let n = cs.n();
+            let root = root.map(|b| Byte::<Bn256>::from_u8_witness(cs, Some(b)).unwrap());
+            let key = key.map(|b| Byte::<Bn256>::from_u8_witness(cs, Some(b)).unwrap());
+            let padded = PaddedMptProof::from_witness(cs, &proof, proof.len() + 1, max_len)?;
+            let (is_valid, value, len) = padded.verify(cs, &root, &key, 4)?;
+            println!("Roughly {} gates", cs.n() - n);
+            assert_eq!(is_valid.get_value(), Some(expected));
+            if expected {
+                assert_eq!(len.get_value(), Some(u64_to_fe(3)));
+                assert_eq!(
+                    Byte::get_byte_value_multiple(&value).unwrap(),
+                    vec![0x82, 7, 1, 0]
+                );
+            }
+            assert!(cs.is_satisfied());
+        }
+        Ok(())
+    }
+}
@@ -1,14 +1,23 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
 use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
     franklin_crypto::{
         bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
         plonk::circuit::allocated_num::Num,
     },
     rescue_poseidon::{CircuitGenericSponge, GenericSponge, PoseidonParams},
 };
-use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use num_bigint::BigUint;
+
+use crate::utils::{fr_from_biguint, num_from_be_bytes};
+
 const WIDTH: usize = 3;
 const RATE: usize = 2;
+/// Bytes packed into each element by [`poseidon_hash_bytes`], so that a 32-byte value is hashed
+/// as its high and low halves as elsewhere in the crate.
+pub const BYTES_PER_ELEMENT: usize = 16;
 
+/// Same as [`poseidon_hash`].
 pub fn circuit_poseidon_hash<E: Engine, CS: ConstraintSystem<E>>(
     cs: &mut CS,
     input: &[Num<E>],
@@ -17,7 +26,81 @@ pub fn circuit_poseidon_hash<E: Engine, CS: ConstraintSystem<E>>(
     Ok(CircuitGenericSponge::hash_num(cs, input, &params, None)?[0])
 }
 
+/// Poseidon hash of field elements.
 pub fn poseidon_hash<E: Engine>(input: &[E::Fr]) -> E::Fr {
     let params = PoseidonParams::<E, RATE, WIDTH>::default();
     GenericSponge::hash(input, &params, None)[0]
 }
+
+/// Same as [`poseidon_hash_bytes`].
+pub fn circuit_poseidon_hash_bytes<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    bytes: &[Byte<E>],
+) -> Result<Num<E>, SynthesisError> {
+    let mut input = vec![];
+    for chunk in bytes.chunks(BYTES_PER_ELEMENT) {
+        input.push(num_from_be_bytes(cs, chunk)?);
+    }
+    circuit_poseidon_hash(cs, &input)
+}
+
+/// Poseidon hash of `bytes` packed big-endian into elements of [`BYTES_PER_ELEMENT`] bytes, where
+/// the last element may be shorter. Lengths are not hashed, so inputs must have fixed lengths.
+pub fn poseidon_hash_bytes<E: Engine>(bytes: &[u8]) -> E::Fr {
+    let input = bytes
+        .chunks(BYTES_PER_ELEMENT)
+        .map(|chunk| fr_from_biguint::<E>(&BigUint::from_bytes_be(chunk)).expect("fits the field"))
+        .collect::<Vec<_>>();
+    poseidon_hash::<E>(&input)
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte,
+        franklin_crypto::{
+            bellman::{pairing::bn256::Bn256, SynthesisError},
+            plonk::circuit::allocated_num::Num,
+        },
+        utils::u64_to_fe,
+    };
+
+    use super::{
+        circuit_poseidon_hash, circuit_poseidon_hash_bytes, poseidon_hash, poseidon_hash_bytes,
+    };
+    use crate::utils::testing::create_test_constraint_system;
+
+    #[test]
+    fn test_poseidon_hash() -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let input = [1u64, 2, 3].map(u64_to_fe);
+        let expected = poseidon_hash::<Bn256>(&input);
+        let nums = input
+            .iter()
+            .map(|fe| Num::alloc(cs, Some(*fe)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let n = cs.n();
+        let hash = circuit_poseidon_hash(cs, &nums)?;
+        println!("Roughly {} gates", cs.n() - n);
+        assert_eq!(hash.get_value(), Some(expected));
+
+        // 32 bytes are hashed as their halves
+        let mut bytes = [0u8; 32];
+        bytes[15] = 1;
+        bytes[31] = 2;
+        assert_eq!(
+            poseidon_hash_bytes::<Bn256>(&bytes),
+            poseidon_hash::<Bn256>(&[u64_to_fe(1), u64_to_fe(2)])
+        );
+        let bytes = b"zklink oracle poseidon bytes hashing";
+        let expected = poseidon_hash_bytes::<Bn256>(bytes);
+        let bytes = bytes
+            .iter()
+            .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let hash = circuit_poseidon_hash_bytes(cs, &bytes)?;
+        assert_eq!(hash.get_value(), Some(expected));
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+}
@@ -0,0 +1,263 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{ff::Field, Engine};
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{boolean::Boolean, linear_combination::LinearCombination},
+    },
+};
+
+use super::sha256::{self, add, rotr, word_constant, Word, WORD_BITS};
+
+pub const WIDTH_HASH_BYTES: usize = 20;
+const BLOCK_BYTES: usize = 64;
+const STEPS: usize = 80;
+
+const IV: [u32; 5] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+
+// Constants of every 16 steps of the left and the right line
+const K: [u32; 5] = [0x00000000, 0x5a827999, 0x6ed9eba1, 0x8f1bbcdc, 0xa953fd4e];
+const K_PRIME: [u32; 5] = [0x50a28be6, 0x5c4dd124, 0x6d703ef3, 0x7a6d76e9, 0x00000000];
+
+// Message words selected at each step of the left and the right line
+const R: [usize; STEPS] = [
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 7, 4, 13, 1, 10, 6, 15, 3, 12, 0, 9, 5,
+    2, 14, 11, 8, 3, 10, 14, 4, 9, 15, 8, 1, 2, 7, 0, 6, 13, 11, 5, 12, 1, 9, 11, 10, 0, 8, 12, 4,
+    13, 3, 7, 15, 14, 5, 6, 2, 4, 0, 5, 9, 7, 12, 2, 10, 14, 1, 3, 8, 11, 6, 15, 13,
+];
+const R_PRIME: [usize; STEPS] = [
+    5, 14, 7, 0, 9, 2, 11, 4, 13, 6, 15, 8, 1, 10, 3, 12, 6, 11, 3, 7, 0, 13, 5, 10, 14, 15, 8, 12,
+    4, 9, 1, 2, 15, 5, 1, 3, 7, 14, 6, 9, 11, 8, 12, 2, 10, 0, 4, 13, 8, 6, 4, 1, 3, 11, 15, 0, 5,
+    12, 2, 13, 9, 7, 10, 14, 12, 15, 10, 4, 1, 5, 8, 7, 6, 2, 13, 14, 0, 3, 9, 11,
+];
+
+// Left rotations at each step of the left and the right line
+const S: [usize; STEPS] = [
+    11, 14, 15, 12, 5, 8, 7, 9, 11, 13, 14, 15, 6, 7, 9, 8, 7, 6, 8, 13, 11, 9, 7, 15, 7, 12, 15,
+    9, 11, 7, 13, 12, 11, 13, 6, 7, 14, 9, 13, 15, 14, 8, 13, 6, 5, 12, 7, 5, 11, 12, 14, 15, 14,
+    15, 9, 8, 9, 14, 5, 6, 8, 6, 5, 12, 9, 15, 5, 11, 6, 8, 13, 12, 5, 12, 13, 14, 11, 8, 5, 6,
+];
+const S_PRIME: [usize; STEPS] = [
+    8, 9, 9, 11, 13, 15, 15, 5, 7, 7, 8, 11, 14, 14, 12, 6, 9, 13, 15, 7, 12, 8, 9, 11, 7, 7, 12,
+    7, 6, 15, 13, 11, 9, 7, 15, 11, 8, 6, 6, 14, 12, 13, 5, 14, 13, 13, 7, 5, 15, 5, 8, 11, 14, 14,
+    6, 14, 6, 9, 12, 9, 12, 5, 15, 8, 8, 5, 12, 9, 12, 5, 14, 6, 8, 13, 6, 5, 15, 13, 11, 11,
+];
+
+fn word_from_le_bytes<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    bytes: &[Byte<E>],
+) -> Result<Word, SynthesisError> {
+    let mut word = [Boolean::constant(false); WORD_BITS];
+    for (i, byte) in bytes.iter().enumerate() {
+        let bits = byte.inner.into_bits_le(cs, Some(8))?;
+        word[i * 8..(i + 1) * 8].copy_from_slice(&bits);
+    }
+    Ok(word)
+}
+
+fn word_into_le_bytes<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    word: &Word,
+) -> Result<[Byte<E>; 4], SynthesisError> {
+    let mut bytes = [Byte::zero(); 4];
+    for (i, bits) in word.chunks_exact(8).enumerate() {
+        let mut lc = LinearCombination::zero();
+        let mut coeff = E::Fr::one();
+        for bit in bits {
+            lc.add_assign_boolean_with_coeff(bit, coeff);
+            coeff.double();
+        }
+        let num = lc.into_num(cs)?;
+        bytes[i] = Byte::from_num_unconstrained(cs, num);
+    }
+    Ok(bytes)
+}
+
+fn rotl(word: &Word, n: usize) -> Word {
+    rotr(word, WORD_BITS - n)
+}
+
+// Boolean function of step `j`, where disjoint ORs are computed as XORs
+fn f<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    j: usize,
+    x: &Word,
+    y: &Word,
+    z: &Word,
+) -> Result<Word, SynthesisError> {
+    let mut out = [Boolean::constant(false); WORD_BITS];
+    for i in 0..WORD_BITS {
+        let (x, y, z) = (&x[i], &y[i], &z[i]);
+        out[i] = match j / 16 {
+            // x ^ y ^ z
+            0 => {
+                let xy = Boolean::xor(cs, x, y)?;
+                Boolean::xor(cs, &xy, z)?
+            }
+            // (x & y) | (!x & z)
+            1 => {
+                let xy = Boolean::and(cs, x, y)?;
+                let nxz = Boolean::and(cs, &x.not(), z)?;
+                Boolean::xor(cs, &xy, &nxz)?
+            }
+            // (x | !y) ^ z
+            2 => {
+                let xny = Boolean::or(cs, x, &y.not())?;
+                Boolean::xor(cs, &xny, z)?
+            }
+            // (x & z) | (y & !z)
+            3 => {
+                let xz = Boolean::and(cs, x, z)?;
+                let ynz = Boolean::and(cs, y, &z.not())?;
+                Boolean::xor(cs, &xz, &ynz)?
+            }
+            // x ^ (y | !z)
+            _ => {
+                let ynz = Boolean::or(cs, y, &z.not())?;
+                Boolean::xor(cs, x, &ynz)?
+            }
+        };
+    }
+    Ok(out)
+}
+
+fn compress<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    state: &[Word; 5],
+    block: &[Byte<E>],
+) -> Result<[Word; 5], SynthesisError> {
+    let mut x = Vec::with_capacity(16);
+    for chunk in block.chunks_exact(4) {
+        x.push(word_from_le_bytes(cs, chunk)?);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e] = *state;
+    let [mut a_prime, mut b_prime, mut c_prime, mut d_prime, mut e_prime] = *state;
+    for j in 0..STEPS {
+        let fj = f(cs, j, &b, &c, &d)?;
+        let t = add(cs, &[&a, &fj, &x[R[j]]], K[j / 16])?;
+        let t = add(cs, &[&rotl(&t, S[j]), &e], 0)?;
+        a = e;
+        e = d;
+        d = rotl(&c, 10);
+        c = b;
+        b = t;
+
+        let fj = f(cs, STEPS - 1 - j, &b_prime, &c_prime, &d_prime)?;
+        let t = add(cs, &[&a_prime, &fj, &x[R_PRIME[j]]], K_PRIME[j / 16])?;
+        let t = add(cs, &[&rotl(&t, S_PRIME[j]), &e_prime], 0)?;
+        a_prime = e_prime;
+        e_prime = d_prime;
+        d_prime = rotl(&c_prime, 10);
+        c_prime = b_prime;
+        b_prime = t;
+    }
+
+    Ok([
+        add(cs, &[&state[1], &c, &d_prime], 0)?,
+        add(cs, &[&state[2], &d, &e_prime], 0)?,
+        add(cs, &[&state[3], &e, &a_prime], 0)?,
+        add(cs, &[&state[4], &a, &b_prime], 0)?,
+        add(cs, &[&state[0], &b, &c_prime], 0)?,
+    ])
+}
+
+/// RIPEMD-160 digest of `bytes`. The length of `bytes` is fixed at synthesis, so the padding is
+/// constant.
+pub fn digest<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    bytes: &[Byte<E>],
+) -> Result<[Byte<E>; WIDTH_HASH_BYTES], SynthesisError> {
+    let mut padded = bytes.to_vec();
+    padded.push(Byte::constant(0x80));
+    while padded.len() % BLOCK_BYTES != BLOCK_BYTES - 8 {
+        padded.push(Byte::zero());
+    }
+    let bit_len = (bytes.len() as u64) * 8;
+    padded.extend(bit_len.to_le_bytes().map(Byte::constant));
+
+    let mut state = IV.map(word_constant);
+    for block in padded.chunks_exact(BLOCK_BYTES) {
+        state = compress(cs, &state, block)?;
+    }
+
+    let mut digest = [Byte::zero(); WIDTH_HASH_BYTES];
+    for (i, word) in state.iter().enumerate() {
+        digest[i * 4..(i + 1) * 4].copy_from_slice(&word_into_le_bytes(cs, word)?);
+    }
+    Ok(digest)
+}
+
+/// Bitcoin HASH160, i.e. `RIPEMD160(SHA256(bytes))`, which derives P2PKH addresses from public
+/// keys and P2SH addresses from scripts.
+pub fn hash160<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    bytes: &[Byte<E>],
+) -> Result<[Byte<E>; WIDTH_HASH_BYTES], SynthesisError> {
+    let hash = sha256::digest(cs, bytes)?;
+    digest(cs, &hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte, franklin_crypto::bellman::SynthesisError,
+    };
+
+    use super::{digest, hash160};
+    use crate::utils::testing::create_test_constraint_system;
+
+    #[test]
+    fn test_ripemd160() -> Result<(), SynthesisError> {
+        let mut cs = create_test_constraint_system()?;
+        let cs = &mut cs;
+        // Inputs of 55 and 56 bytes are the longest fitting one block and the shortest requiring
+        // an extra padding block
+        let boundaries = [[55u8; 55].to_vec(), [56u8; 56].to_vec()];
+        let cases = [
+            (&b""[..], "9c1185a5c5e9fc54612808977ee8f548b2258d31"),
+            (&b"abc"[..], "8eb208f7e05d987a9b044a8e98c6b087f15a0bfc"),
+            (
+                &b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"[..],
+                "12a053384a9c0c88e405a06c27dcf49ada62eb2b",
+            ),
+            (
+                &boundaries[0][..],
+                "e7d6d3b966af127c14295506e97f77f9e7666aa3",
+            ),
+            (
+                &boundaries[1][..],
+                "07e1c8bba99f5f0f875692e2a5abcba9b2c0de30",
+            ),
+        ];
+        for (input, expected) in cases {
+            let n = cs.n();
+            let input_bytes = input
+                .iter()
+                .map(|b| Byte::from_u8_witness(cs, Some(*b)).unwrap())
+                .collect::<Vec<_>>();
+            let hash = digest(cs, &input_bytes)?;
+            let hash = Byte::get_byte_value_multiple(&hash).unwrap();
+            assert_eq!(hex::encode(hash), expected);
+            println!("Roughly {} gates", cs.n() - n);
+        }
+
+        // HASH160 of the compressed public key of the private key 1, i.e. the P2PKH address
+        // 1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH
+        let pubkey =
+            hex::decode("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+                .unwrap();
+        let pubkey = pubkey
+            .iter()
+            .map(|b| Byte::from_u8_witness(cs, Some(*b)).unwrap())
+            .collect::<Vec<_>>();
+        let hash = hash160(cs, &pubkey)?;
+        let hash = Byte::get_byte_value_multiple(&hash).unwrap();
+        assert_eq!(
+            hex::encode(hash),
+            "751e76e8199196d454941c45d1b3a323f1433bd6"
+        );
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+}
@@ -0,0 +1,591 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, Field, SynthesisError},
+        plonk::circuit::{
+            allocated_num::Num, boolean::Boolean, linear_combination::LinearCombination,
+        },
+    },
+    utils::u64_to_fe,
+    vm::partitioner::{smart_and, smart_or},
+};
+
+// Bits of offsets and lengths, which bound the length of lists
+const OFFSET_BITS: usize = 16;
+
+/// Decode the RLP item at `offset`. Return offset and length of its payload, and whether it's a
+/// list.
+pub fn decode_item(bytes: &[u8], offset: usize) -> anyhow::Result<(usize, usize, bool)> {
+    let byte_at = |i: usize| {
+        bytes
+            .get(i)
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("RLP item at {} is truncated", offset))
+    };
+    let prefix = byte_at(offset)?;
+    let (payload, len, is_list) = match prefix {
+        0x00..=0x7f => (offset, 1, false),
+        0x80..=0xb7 => (offset + 1, (prefix - 0x80) as usize, false),
+        0xc0..=0xf7 => (offset + 1, (prefix - 0xc0) as usize, true),
+        _ => {
+            let is_list = prefix >= 0xf8;
+            let len_len = (prefix - if is_list { 0xf7 } else { 0xb7 }) as usize;
+            if len_len > 2 {
+                anyhow::bail!("RLP item at {} is too long", offset)
+            }
+            let mut len = 0;
+            for i in 0..len_len {
+                len = (len << 8) | byte_at(offset + 1 + i)? as usize;
+            }
+            (offset + 1 + len_len, len, is_list)
+        }
+    };
+    if payload + len > bytes.len() {
+        anyhow::bail!("RLP item at {} is truncated", offset)
+    }
+    Ok((payload, len, is_list))
+}
+
+/// Decode an RLP list spanning all of `bytes`. Return payload offsets, lengths and whether they
+/// are lists of its items.
+pub fn decode_list(bytes: &[u8]) -> anyhow::Result<Vec<(usize, usize, bool)>> {
+    let (payload, len, is_list) = decode_item(bytes, 0)?;
+    if !is_list || payload + len != bytes.len() {
+        anyhow::bail!("invalid RLP list 0x{}", hex::encode(bytes))
+    }
+    let mut items = vec![];
+    let mut offset = payload;
+    while offset < bytes.len() {
+        let item = decode_item(bytes, offset)?;
+        offset = item.0 + item.1;
+        items.push(item);
+    }
+    Ok(items)
+}
+
+/// Circuit representation of an item of [`RlpList`].
+#[derive(Debug, Clone, Copy)]
+pub struct RlpItem<E: Engine> {
+    /// Offset of the payload in the list
+    pub offset: Num<E>,
+    pub len: Num<E>,
+    pub is_list: Boolean,
+    /// Whether the list has the item, otherwise the item is meaningless
+    pub is_present: Boolean,
+}
+
+/// Items of an RLP list decoded by [`RlpList::decode`].
+#[derive(Debug, Clone)]
+pub struct RlpList<E: Engine> {
+    pub items: Vec<RlpItem<E>>,
+    pub num_items: Num<E>,
+}
+
+impl<E: Engine> RlpList<E> {
+    /// Same as [`decode_list`], but of at most `max_items` items. Return whether `bytes` is a list
+    /// of at most `max_items` items and the items, which are only meaningful if the check passes.
+    pub fn decode<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        bytes: &[Byte<E>],
+        max_items: usize,
+    ) -> Result<(Boolean, Self), SynthesisError> {
+        let len = Num::Constant(u64_to_fe(bytes.len() as u64));
+        Self::decode_variable_length(cs, bytes, &len, max_items)
+    }
+
+    /// Same as [`Self::decode`], but of the first `len` bytes of `bytes`, e.g. nodes zero-padded
+    /// to a maximal length.
+    pub fn decode_variable_length<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        bytes: &[Byte<E>],
+        len: &Num<E>,
+        max_items: usize,
+    ) -> Result<(Boolean, Self), SynthesisError> {
+        assert!(!bytes.is_empty() && bytes.len() < 1 << OFFSET_BITS);
+        let end = *len;
+        let (is_supported, header) = circuit_decode_item(cs, bytes, &Num::zero())?;
+        let header_end = header.offset.add(cs, &header.len)?;
+        let mut is_valid = vec![
+            is_supported,
+            header.is_list,
+            Num::equals(cs, &header_end, &end)?,
+        ];
+
+        let mut offset = header.offset;
+        let mut is_present = Boolean::constant(true);
+        let mut items = vec![];
+        let mut num_items = Num::zero();
+        for _ in 0..max_items {
+            let is_end = Num::equals(cs, &offset, &end)?;
+            is_present = Boolean::and(cs, &is_present, &is_end.not())?;
+            let (is_supported, mut item) = circuit_decode_item(cs, bytes, &offset)?;
+            item.is_present = is_present;
+            // The item ends within the list, i.e. `end - next + 2^(OFFSET_BITS + 1)` has the top
+            // bit set
+            let next = item.offset.add(cs, &item.len)?;
+            let rest = end.sub(cs, &next)?;
+            let rest = Num::conditionally_select(cs, &is_present, &rest, &Num::zero())?;
+            let shifted = rest.add(cs, &Num::Constant(u64_to_fe(1 << (OFFSET_BITS + 1))))?;
+            let bits = shifted.into_bits_le(cs, Some(OFFSET_BITS + 2))?;
+            is_valid.push(Boolean::or(cs, &is_supported, &is_present.not())?);
+            is_valid.push(bits[OFFSET_BITS + 1]);
+
+            offset = Num::conditionally_select(cs, &is_present, &next, &offset)?;
+            num_items = num_items.add(cs, &Num::from_boolean_is(is_present))?;
+            items.push(item);
+        }
+        // All items are decoded
+        is_valid.push(Num::equals(cs, &offset, &end)?);
+        Ok((smart_and(cs, &is_valid)?, Self { items, num_items }))
+    }
+
+    /// Payload of `item` of `bytes` in `max_len` bytes, which are zero-padded on the right. Return
+    /// whether the payload fits and the bytes.
+    pub fn item_bytes<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        bytes: &[Byte<E>],
+        item: &RlpItem<E>,
+        max_len: usize,
+    ) -> Result<(Boolean, Vec<Byte<E>>), SynthesisError> {
+        let window = select_window(cs, bytes, &item.offset, max_len)?;
+        let mut is_past_end = Boolean::constant(false);
+        let mut payload = vec![];
+        for (i, byte) in window.into_iter().enumerate() {
+            let is_len = Num::equals(cs, &item.len, &Num::Constant(u64_to_fe(i as u64)))?;
+            is_past_end = Boolean::or(cs, &is_past_end, &is_len)?;
+            let byte = Num::conditionally_select(cs, &is_past_end, &Num::zero(), &byte)?;
+            payload.push(Byte::from_num_unconstrained(cs, byte));
+        }
+        let is_len = Num::equals(cs, &item.len, &Num::Constant(u64_to_fe(max_len as u64)))?;
+        let fits = Boolean::or(cs, &is_past_end, &is_len)?;
+        Ok((fits, payload))
+    }
+}
+
+/// Bytes `bytes[offset..offset + len]`, where bytes out of `bytes` are zero.
+fn select_window<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    bytes: &[Byte<E>],
+    offset: &Num<E>,
+    len: usize,
+) -> Result<Vec<Num<E>>, SynthesisError> {
+    let mut window = vec![LinearCombination::zero(); len];
+    for i in 0..bytes.len() {
+        let is_current = Num::equals(cs, offset, &Num::Constant(u64_to_fe(i as u64)))?;
+        for (j, lc) in window.iter_mut().enumerate() {
+            if let Some(byte) = bytes.get(i + j) {
+                let byte = Num::conditionally_select(cs, &is_current, &byte.inner, &Num::zero())?;
+                lc.add_assign_number_with_coeff(&byte, E::Fr::one());
+            }
+        }
+    }
+    window.into_iter().map(|lc| lc.into_num(cs)).collect()
+}
+
+/// Same as [`decode_item`]. Return whether the length of the item takes at most 2 bytes, and the
+/// item, which is present.
+fn circuit_decode_item<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    bytes: &[Byte<E>],
+    offset: &Num<E>,
+) -> Result<(Boolean, RlpItem<E>), SynthesisError> {
+    let window = select_window(cs, bytes, offset, 3)?;
+    let bits = window[0].into_bits_le(cs, Some(8))?;
+    let is_single_byte = bits[7].not();
+    let is_list = Boolean::and(cs, &bits[7], &bits[6])?;
+    // Length of short items, or 55 plus the length of length of long items
+    let mut lc = LinearCombination::zero();
+    let mut coeff = E::Fr::one();
+    for bit in bits[..6].iter() {
+        lc.add_assign_boolean_with_coeff(bit, coeff);
+        coeff.double();
+    }
+    let short_len = lc.into_num(cs)?;
+    // 0xb8..=0xbf and 0xf8..=0xff are long, whose length takes 1 + bits[0] bytes
+    let is_long = smart_and(cs, &[bits[7], bits[5], bits[4], bits[3]])?;
+    let is_too_long = Boolean::or(cs, &bits[2], &bits[1])?;
+    let is_supported = Boolean::and(cs, &is_long, &is_too_long)?.not();
+    let long_len = window[1]
+        .mul(cs, &Num::Constant(u64_to_fe(256)))?
+        .add(cs, &window[2])?;
+    let long_len = Num::conditionally_select(cs, &bits[0], &long_len, &window[1])?;
+
+    let len = Num::conditionally_select(cs, &is_long, &long_len, &short_len)?;
+    let len = Num::conditionally_select(cs, &is_single_byte, &Num::one(), &len)?;
+    let long_header = Num::from_boolean_is(bits[0]).add(cs, &Num::Constant(u64_to_fe(2)))?;
+    let header = Num::conditionally_select(cs, &is_long, &long_header, &Num::one())?;
+    let header = Num::conditionally_select(cs, &is_single_byte, &Num::zero(), &header)?;
+    let item = RlpItem {
+        offset: offset.add(cs, &header)?,
+        len,
+        is_list,
+        is_present: Boolean::constant(true),
+    };
+    Ok((is_supported, item))
+}
+
+/// RLP encoding of circuit values in a buffer of fixed capacity, whose first `len` bytes are the
+/// encoding and the rest are zeros, e.g. MPT nodes and receipts to be hashed by
+/// [`RlpEncoding::hash`].
+#[derive(Debug, Clone)]
+pub struct RlpEncoding<E: Engine> {
+    pub bytes: Vec<Byte<E>>,
+    pub len: Num<E>,
+}
+
+impl<E: Engine> RlpEncoding<E> {
+    /// Encode the first `len` bytes of `payload` as a string. Circuit is unsatisfied if `len`
+    /// exceeds `payload.len()`.
+    pub fn string<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        payload: &[Byte<E>],
+        len: &Num<E>,
+    ) -> Result<Self, SynthesisError> {
+        assert!(!payload.is_empty() && payload.len() < 1 << OFFSET_BITS);
+        let mut is_past_end = Boolean::constant(false);
+        let mut masked = vec![];
+        for (i, byte) in payload.iter().enumerate() {
+            let is_len = Num::equals(cs, len, &Num::Constant(u64_to_fe(i as u64)))?;
+            is_past_end = Boolean::or(cs, &is_past_end, &is_len)?;
+            masked.push(Num::conditionally_select(
+                cs,
+                &is_past_end,
+                &Num::zero(),
+                &byte.inner,
+            )?);
+        }
+        let is_full = Num::equals(cs, len, &Num::Constant(u64_to_fe(payload.len() as u64)))?;
+        let is_in_range = Boolean::or(cs, &is_past_end, &is_full)?;
+        Boolean::enforce_equal(cs, &is_in_range, &Boolean::constant(true))?;
+
+        // A single byte below 0x80 is its own encoding
+        let is_one = Num::equals(cs, len, &Num::one())?;
+        let is_low = payload[0].inner.into_bits_le(cs, Some(8))?[7].not();
+        let is_single_byte = Boolean::and(cs, &is_one, &is_low)?;
+        encode(cs, &masked, len, 0x80, &is_single_byte)
+    }
+
+    /// Encode the unsigned integer of big-endian `bytes` as a string without leading zeros, e.g.
+    /// nonces and balances.
+    pub fn uint<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        bytes: &[Byte<E>],
+    ) -> Result<Self, SynthesisError> {
+        // Flags of whether there are k leading zeros
+        let mut is_leading_zeros = vec![];
+        let mut is_prefix_zero = Boolean::constant(true);
+        for byte in bytes.iter() {
+            let is_zero = Num::equals(cs, &byte.inner, &Num::zero())?;
+            is_leading_zeros.push(Boolean::and(cs, &is_prefix_zero, &is_zero.not())?);
+            is_prefix_zero = Boolean::and(cs, &is_prefix_zero, &is_zero)?;
+        }
+        is_leading_zeros.push(is_prefix_zero);
+
+        let mut len = LinearCombination::zero();
+        let mut shifted = vec![LinearCombination::zero(); bytes.len()];
+        for (k, flag) in is_leading_zeros.iter().enumerate() {
+            len.add_assign_boolean_with_coeff(flag, u64_to_fe((bytes.len() - k) as u64));
+            for (lc, byte) in shifted.iter_mut().zip(bytes[k..].iter()) {
+                let byte = Num::conditionally_select(cs, flag, &byte.inner, &Num::zero())?;
+                lc.add_assign_number_with_coeff(&byte, E::Fr::one());
+            }
+        }
+        let len = len.into_num(cs)?;
+        let mut payload = vec![];
+        for lc in shifted {
+            let byte = lc.into_num(cs)?;
+            payload.push(Byte::from_num_unconstrained(cs, byte));
+        }
+        Self::string(cs, &payload, &len)
+    }
+
+    /// Encode the list of encoded `items`.
+    pub fn list<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        items: &[Self],
+    ) -> Result<Self, SynthesisError> {
+        let mut payload = vec![];
+        let mut len = Num::zero();
+        for item in items.iter() {
+            // Append the item at `len`, as bytes past `len` are zeros
+            let mut appended = payload
+                .iter()
+                .map(|byte: &Byte<E>| {
+                    let mut lc = LinearCombination::zero();
+                    lc.add_assign_number_with_coeff(&byte.inner, E::Fr::one());
+                    lc
+                })
+                .collect::<Vec<_>>();
+            appended.resize(payload.len() + item.bytes.len(), LinearCombination::zero());
+            for k in 0..=payload.len() {
+                let is_len = Num::equals(cs, &len, &Num::Constant(u64_to_fe(k as u64)))?;
+                for (lc, byte) in appended[k..].iter_mut().zip(item.bytes.iter()) {
+                    let byte = Num::conditionally_select(cs, &is_len, &byte.inner, &Num::zero())?;
+                    lc.add_assign_number_with_coeff(&byte, E::Fr::one());
+                }
+            }
+            payload = vec![];
+            for lc in appended {
+                let byte = lc.into_num(cs)?;
+                payload.push(Byte::from_num_unconstrained(cs, byte));
+            }
+            len = len.add(cs, &item.len)?;
+        }
+        assert!(payload.len() < 1 << OFFSET_BITS);
+        let payload = payload.iter().map(|byte| byte.inner).collect::<Vec<_>>();
+        encode(cs, &payload, &len, 0xc0, &Boolean::constant(false))
+    }
+
+    /// Keccak256 of the encoding, e.g. the hash of an MPT node.
+    pub fn hash<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<[Byte<E>; 32], SynthesisError> {
+        super::keccak256::digest_variable_length(cs, &self.bytes, &self.len)
+    }
+}
+
+/// Prepend the header of a string or a list to the first `len` bytes of `payload`, whose bytes
+/// past `len` are zeros. `base` is 0x80 for strings and 0xc0 for lists.
+fn encode<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    payload: &[Num<E>],
+    len: &Num<E>,
+    base: u64,
+    is_single_byte: &Boolean,
+) -> Result<RlpEncoding<E>, SynthesisError> {
+    let len_bits = len.into_bits_le(cs, Some(OFFSET_BITS))?;
+    let mut bytes = [LinearCombination::zero(), LinearCombination::zero()];
+    for (lc, bits) in bytes.iter_mut().zip(len_bits.chunks(8)) {
+        let mut coeff = E::Fr::one();
+        for bit in bits {
+            lc.add_assign_boolean_with_coeff(bit, coeff);
+            coeff.double();
+        }
+    }
+    let [lo, hi] = bytes;
+    let (lo, hi) = (lo.into_num(cs)?, hi.into_num(cs)?);
+    // Lengths above 55 take one byte below 256, and two bytes otherwise
+    let shifted = len.add(cs, &Num::Constant(u64_to_fe((1 << OFFSET_BITS) - 56)))?;
+    let is_long = shifted.into_bits_le(cs, Some(OFFSET_BITS + 1))?[OFFSET_BITS];
+    let is_long2 = smart_or(cs, &len_bits[8..])?;
+
+    let constant = |v: u64| Num::Constant(u64_to_fe(v));
+    let short = len.add(cs, &constant(base))?;
+    let headers = [
+        vec![],
+        vec![short],
+        vec![constant(base + 0x38), lo],
+        vec![constant(base + 0x39), hi, lo],
+    ];
+    let is_short = Boolean::and(cs, &is_single_byte.not(), &is_long.not())?;
+    let is_long1 = Boolean::and(cs, &is_long, &is_long2.not())?;
+    let flags = [*is_single_byte, is_short, is_long1, is_long2];
+
+    let max_header = if payload.len() >= 256 {
+        3
+    } else if payload.len() >= 56 {
+        2
+    } else {
+        1
+    };
+    let mut encoded = vec![LinearCombination::zero(); payload.len() + max_header];
+    let mut header_len = LinearCombination::zero();
+    for (k, (header, flag)) in headers
+        .iter()
+        .zip(flags.iter())
+        .enumerate()
+        .take(max_header + 1)
+    {
+        header_len.add_assign_boolean_with_coeff(flag, u64_to_fe(k as u64));
+        let candidate = header.iter().chain(payload.iter());
+        for (lc, value) in encoded.iter_mut().zip(candidate) {
+            let value = Num::conditionally_select(cs, flag, value, &Num::zero())?;
+            lc.add_assign_number_with_coeff(&value, E::Fr::one());
+        }
+    }
+    let mut bytes = vec![];
+    for lc in encoded {
+        let byte = lc.into_num(cs)?;
+        bytes.push(Byte::from_num_unconstrained(cs, byte));
+    }
+    header_len.add_assign_number_with_coeff(len, E::Fr::one());
+    Ok(RlpEncoding {
+        bytes,
+        len: header_len.into_num(cs)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte,
+        franklin_crypto::{
+            bellman::{
+                pairing::bn256::Bn256, plonk::better_better_cs::cs::ConstraintSystem,
+                SynthesisError,
+            },
+            plonk::circuit::allocated_num::Num,
+        },
+        utils::u64_to_fe,
+    };
+    use sha3::{Digest, Keccak256};
+
+    use super::{decode_list, RlpEncoding, RlpList};
+    use crate::utils::{
+        new_synthesis_error,
+        testing::{create_test_constraint_system, rlp_encode},
+    };
+
+    #[test]
+    fn test_rlp_list() -> Result<(), SynthesisError> {
+        // Items of a block header, i.e. hashes, the logs bloom and numbers
+        let mut header = vec![
+            rlp_encode(&[0x01; 32], false),
+            rlp_encode(&[0x02; 20], false),
+        ];
+        header.push(rlp_encode(&[0u8; 256], false));
+        header.extend([&[0x2a][..], &[], &[0x01, 0x21, 0xea]].map(|n| rlp_encode(n, false)));
+        let header = rlp_encode(&header.concat(), true);
+        let nested = [
+            rlp_encode(&[0x05], false),
+            rlp_encode(&[0xaa; 60], false),
+            rlp_encode(
+                &[rlp_encode(b"dog", false), rlp_encode(b"", false)].concat(),
+                true,
+            ),
+            rlp_encode(b"", false),
+        ]
+        .concat();
+        let nested = rlp_encode(&nested, true);
+        // The long string overruns the list
+        let mut overrun = nested.clone();
+        overrun[4] = 0x50;
+
+        let cases = [
+            (header, 8, true),
+            (nested.clone(), 4, true),
+            (nested, 3, false),
+            (overrun, 4, false),
+        ];
+        for (bytes, max_items, expected) in cases {
+            let cs = &mut create_test_constraint_system()?;
+            let n = cs.n();
+            let allocated = bytes
+                .iter()
+                .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+                .collect::<Result<Vec<_>, _>>()?;
+            let (is_valid, list) = RlpList::decode(cs, &allocated, max_items)?;
+            println!("Roughly {} gates", cs.n() - n);
+            assert_eq!(is_valid.get_value(), Some(expected));
+            assert!(cs.is_satisfied());
+            if !expected {
+                continue;
+            }
+
+            let items = decode_list(&bytes).map_err(new_synthesis_error)?;
+            assert_eq!(
+                list.num_items.get_value(),
+                Some(u64_to_fe(items.len() as u64))
+            );
+            for (item, (offset, len, is_list)) in list.items.iter().zip(items.iter()) {
+                assert_eq!(item.is_present.get_value(), Some(true));
+                assert_eq!(item.is_list.get_value(), Some(*is_list));
+                let (fits, payload) = RlpList::item_bytes(cs, &allocated, item, 32)?;
+                assert_eq!(fits.get_value(), Some(*len <= 32));
+                if *len <= 32 {
+                    let payload = Byte::get_byte_value_multiple(&payload).unwrap();
+                    assert_eq!(payload[..*len], bytes[*offset..offset + len]);
+                    assert!(payload[*len..].iter().all(|b| *b == 0));
+                }
+            }
+            assert!(cs.is_satisfied());
+        }
+        Ok(())
+    }
+
+    fn allocate<CS: ConstraintSystem<Bn256>>(
+        cs: &mut CS,
+        bytes: &[u8],
+    ) -> Result<Vec<Byte<Bn256>>, SynthesisError> {
+        bytes
+            .iter()
+            .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+            .collect()
+    }
+
+    fn assert_encoding(encoding: &RlpEncoding<Bn256>, expected: &[u8]) {
+        let bytes = Byte::get_byte_value_multiple(&encoding.bytes).unwrap();
+        assert_eq!(hex::encode(&bytes[..expected.len()]), hex::encode(expected));
+        assert!(bytes[expected.len()..].iter().all(|b| *b == 0));
+        assert_eq!(
+            encoding.len.get_value(),
+            Some(u64_to_fe(expected.len() as u64))
+        );
+    }
+
+    #[test]
+    fn test_rlp_encoding() -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let uints = [
+            (vec![0x00, 0x00], vec![0x80]),
+            (vec![0x00, 0x05], vec![0x05]),
+            (vec![0x00, 0x04, 0x00], vec![0x82, 0x04, 0x00]),
+        ];
+        for (value, expected) in uints {
+            let value = allocate(cs, &value)?;
+            assert_encoding(&RlpEncoding::uint(cs, &value)?, &expected);
+        }
+
+        let long = (0..300).map(|i| i as u8).collect::<Vec<_>>();
+        let strings = [
+            (b"dogs".to_vec(), 3),
+            (b"dog".to_vec(), 0),
+            (vec![0x05, 0x00], 1),
+            (vec![0x85, 0x00], 1),
+            (vec![0xaa; 60], 60),
+            (long.clone(), 256),
+        ];
+        for (payload, len) in strings {
+            let n = cs.n();
+            let allocated = allocate(cs, &payload)?;
+            let len_num = Num::Constant(u64_to_fe(len as u64));
+            let encoding = RlpEncoding::string(cs, &allocated, &len_num)?;
+            println!("Roughly {} gates", cs.n() - n);
+            assert_encoding(&encoding, &rlp_encode(&payload[..len], false));
+        }
+
+        let n = cs.n();
+        let nonce = allocate(cs, &[0x00, 0x2a])?;
+        let word = allocate(cs, &long[..32])?;
+        let dog = allocate(cs, b"dog!")?;
+        let items = [
+            RlpEncoding::uint(cs, &nonce)?,
+            RlpEncoding::string(cs, &word, &Num::Constant(u64_to_fe(32)))?,
+            RlpEncoding::list(cs, &[])?,
+        ];
+        let inner = RlpEncoding::string(cs, &dog, &Num::Constant(u64_to_fe(3)))?;
+        let inner = RlpEncoding::list(cs, &[inner])?;
+        let list = RlpEncoding::list(cs, &[items[0].clone(), inner, items[1].clone()])?;
+        let list = RlpEncoding::list(cs, &[list, items[2].clone()])?;
+        let hash = list.hash(cs)?;
+        println!("Roughly {} gates", cs.n() - n);
+
+        let inner = rlp_encode(&rlp_encode(b"dog", false), true);
+        let expected = [
+            rlp_encode(&[0x2a], false),
+            inner,
+            rlp_encode(&long[..32], false),
+        ];
+        let expected = rlp_encode(&expected.concat(), true);
+        let expected = rlp_encode(&[expected, rlp_encode(&[], true)].concat(), true);
+        assert_encoding(&list, &expected);
+        assert_eq!(
+            hex::encode(Byte::get_byte_value_multiple(&hash).unwrap()),
+            hex::encode(Keccak256::digest(&expected[..]))
+        );
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+}
@@ -0,0 +1,283 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::secp256k1::fr::Fr as Secp256Fr;
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, PrimeField, SynthesisError},
+        plonk::circuit::{
+            allocated_num::Num,
+            bigint_new::{bigint::repr_to_biguint, FieldElement, ReductionStatus, RnsParameters},
+            boolean::{AllocatedBit, Boolean},
+        },
+    },
+    traits::CSAllocatable,
+    vm::{
+        partitioner::{smart_and, smart_or},
+        primitives::{uint256::UInt256, UInt32},
+    },
+};
+use num_bigint::BigUint;
+use sha3::{Digest, Keccak256};
+
+use super::{
+    ecdsa::{convert_uint256_to_field_element, Signature, CHUNK_BITLEN},
+    ethereum::Address,
+    keccak256,
+};
+use crate::redstone::witness::recover_signer;
+
+/// Order of the secp256k1 group.
+pub fn secp256k1_order() -> BigUint {
+    repr_to_biguint::<Secp256Fr>(&Secp256Fr::char())
+}
+
+/// Big-endian 32 bytes of `value`, which must be less than `2^256`.
+pub fn be_bytes32(value: &BigUint) -> [u8; 32] {
+    let bytes = value.to_bytes_be();
+    let mut word = [0u8; 32];
+    word[32 - bytes.len()..].copy_from_slice(&bytes);
+    word
+}
+
+/// Sum of uncompressed public keys `x || y`, which is the public key verifying an aggregated
+/// Schnorr signature of all of them.
+pub fn aggregate_pubkeys(pubkeys: &[[u8; 64]]) -> anyhow::Result<[u8; 64]> {
+    let pubkeys = pubkeys
+        .iter()
+        .map(|pubkey| {
+            let mut bytes = [0x04; 65];
+            bytes[1..].copy_from_slice(pubkey);
+            secp256k1::PublicKey::from_slice(&bytes)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let aggregated = secp256k1::PublicKey::combine_keys(&pubkeys.iter().collect::<Vec<_>>())?;
+    Ok(aggregated.serialize_uncompressed()[1..].try_into()?)
+}
+
+/// Challenge of Chronicle Protocol's Schnorr scheme, which is
+/// `keccak256(pubkey.x || pubkey.yParity || message || commitment)`.
+pub fn challenge(
+    pubkey_x: &[u8; 32],
+    y_parity: u8,
+    message: &[u8; 32],
+    commitment: &[u8; 20],
+) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(pubkey_x);
+    hasher.update([y_parity]);
+    hasher.update(message);
+    hasher.update(commitment);
+    hasher.finalize().into()
+}
+
+/// Inputs `(message hash, s)` of `ecrecover` verifying a Schnorr signature, where `r` is
+/// `pubkey.x` and `v` is the parity of `pubkey.y`. They are `-(signature * pubkey.x)` and
+/// `-(challenge * pubkey.x)` modulo the group order, so that the recovered point is
+/// `signature * G - challenge * pubkey`.
+fn ecrecover_inputs(pubkey_x: &BigUint, signature: &BigUint, challenge: &BigUint) -> [BigUint; 2] {
+    let order = secp256k1_order();
+    [signature, challenge].map(|v| &order - (v * pubkey_x) % &order)
+}
+
+/// Verify Schnorr `signature` of `message` against the uncompressed public key `x || y` in the
+/// scheme of Chronicle Protocol, where `commitment` is the address of the nonce point. The check
+/// is that `signature * G - challenge * pubkey` has address `commitment`, which is done via
+/// `ecrecover` like `LibSchnorr` of Chronicle does.
+pub fn verify_signature(
+    pubkey: &[u8; 64],
+    message: &[u8; 32],
+    signature: &[u8; 32],
+    commitment: &[u8; 20],
+) -> bool {
+    if *signature == [0u8; 32] || *commitment == [0u8; 20] {
+        return false;
+    }
+    let pubkey_x: [u8; 32] = pubkey[..32].try_into().unwrap();
+    let y_parity = pubkey[63] & 1;
+    let e = challenge(&pubkey_x, y_parity, message, commitment);
+    let [msg_hash, s] = ecrecover_inputs(
+        &BigUint::from_bytes_be(&pubkey_x),
+        &BigUint::from_bytes_be(signature),
+        &BigUint::from_bytes_be(&e),
+    );
+    let mut ecdsa_signature = [0u8; 65];
+    ecdsa_signature[..32].copy_from_slice(&pubkey_x);
+    ecdsa_signature[32..64].copy_from_slice(&be_bytes32(&s));
+    ecdsa_signature[64] = y_parity;
+    matches!(
+        recover_signer(&be_bytes32(&msg_hash), &ecdsa_signature),
+        Ok(signer) if signer == *commitment
+    )
+}
+
+/// Circuit representation of a Schnorr public key, which is its x coordinate and the parity of
+/// its y coordinate.
+#[derive(Debug, Clone, Copy)]
+pub struct SchnorrPublicKey<E: Engine> {
+    pub x: [Byte<E>; 32],
+    pub y_parity: Boolean,
+}
+
+impl<E: Engine> SchnorrPublicKey<E> {
+    /// Allocate from the uncompressed public key `x || y`.
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        pubkey: &[u8; 64],
+    ) -> Result<Self, SynthesisError> {
+        let x = pubkey[..32]
+            .iter()
+            .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+            .collect::<Result<Vec<_>, _>>()?
+            .try_into()
+            .unwrap();
+        let y_parity = Boolean::Is(AllocatedBit::alloc(cs, Some(pubkey[63] & 1 == 1))?);
+        Ok(Self { x, y_parity })
+    }
+
+    /// Same as [`verify_signature`]: verify Schnorr `signature` of `message` whose nonce point
+    /// has address `commitment`.
+    pub fn verify<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        message: &[Byte<E>; 32],
+        signature: &[Byte<E>; 32],
+        commitment: &Address<E>,
+    ) -> Result<Boolean, SynthesisError> {
+        let y_parity = Byte::from_num_unconstrained(cs, Num::from_boolean_is(self.y_parity));
+        let mut preimage = self.x.to_vec();
+        preimage.push(y_parity);
+        preimage.extend_from_slice(message);
+        preimage.extend(commitment.to_bytes(cs)?);
+        let challenge = keccak256::digest(cs, &preimage)?;
+
+        let x = UInt256::from_be_bytes_fixed(cs, &self.x)?;
+        let signature = UInt256::from_be_bytes_fixed(cs, signature)?;
+        let challenge = UInt256::from_be_bytes_fixed(cs, &challenge)?;
+        let (mut is_valid, (qx, qy)) =
+            recover_combination(cs, &x, &y_parity, &signature, &challenge)?;
+        let (qx, qy) = (
+            qx.into_be_bytes(cs)?.try_into().unwrap(),
+            qy.into_be_bytes(cs)?.try_into().unwrap(),
+        );
+        let address = Address::from_pubkey(cs, &qx, &qy)?;
+        is_valid = Boolean::and(cs, &is_valid, &commitment.equals(cs, &address)?)?;
+        Ok(is_valid)
+    }
+}
+
+/// Point `signature * G - challenge * pubkey` by `ecrecover`, where `pubkey` has x coordinate `x`
+/// and y parity `y_parity`. Returns whether the recovery succeeds and the point.
+///
+/// Inputs of ecrecover are computed out of circuit and then enforced modulo the group order. Zero
+/// scalars are rejected, which occur with negligible probability.
+pub(crate) fn recover_combination<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    x: &UInt256<E>,
+    y_parity: &Byte<E>,
+    signature: &UInt256<E>,
+    challenge: &UInt256<E>,
+) -> Result<(Boolean, (UInt256<E>, UInt256<E>)), SynthesisError> {
+    let inputs = match (x.get_value(), signature.get_value(), challenge.get_value()) {
+        (Some(x), Some(signature), Some(challenge)) => {
+            ecrecover_inputs(&x, &signature, &challenge).map(Some)
+        }
+        _ => [None, None],
+    };
+    let [msg_hash, s] = inputs.map(|v| UInt256::alloc_from_witness(cs, v));
+    let (msg_hash, s) = (msg_hash?, s?);
+
+    let rns = RnsParameters::<E, Secp256Fr>::new_optimal(cs, CHUNK_BITLEN);
+    let mut exceptions = vec![];
+    let mut is_valid = vec![];
+    let x_fe = convert_uint256_to_field_element(cs, x, &rns, &mut exceptions)?;
+    for (scalar, input) in [(signature, &msg_hash), (challenge, &s)] {
+        let scalar = convert_uint256_to_field_element(cs, scalar, &rns, &mut exceptions)?;
+        let input = convert_uint256_to_field_element(cs, input, &rns, &mut exceptions)?;
+        // scalar * x + input = 0 (mod n)
+        let mut sum =
+            scalar
+                .mul(cs, &x_fe)?
+                .add_with_reduction(cs, &input, ReductionStatus::Loose)?;
+        is_valid.push(FieldElement::is_zero(&mut sum, cs)?);
+    }
+    is_valid.push(smart_or(cs, &exceptions)?.not());
+
+    let recid = UInt32::from_bytes_le(cs, &[*y_parity, Byte::zero(), Byte::zero(), Byte::zero()])?;
+    let (successful, point) = Signature { r: *x, s, recid }.ecrecover(cs, &msg_hash)?;
+    is_valid.push(successful);
+    Ok((smart_and(cs, &is_valid)?, point))
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte,
+        franklin_crypto::{
+            bellman::{
+                pairing::bn256::Bn256, plonk::better_better_cs::cs::ConstraintSystem,
+                SynthesisError,
+            },
+            plonk::circuit::boolean::Boolean,
+        },
+    };
+
+    use super::{aggregate_pubkeys, verify_signature, SchnorrPublicKey};
+    use crate::{
+        gadgets::ethereum::Address,
+        utils::{
+            signer::{RedstoneSigner, SchnorrSigner},
+            testing::create_test_constraint_system,
+        },
+    };
+
+    fn allocate<CS: ConstraintSystem<Bn256>>(
+        cs: &mut CS,
+        bytes: &[u8; 32],
+    ) -> Result<[Byte<Bn256>; 32], SynthesisError> {
+        let bytes = bytes
+            .iter()
+            .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(bytes.try_into().unwrap())
+    }
+
+    #[test]
+    fn test_schnorr() -> Result<(), SynthesisError> {
+        let signers = RedstoneSigner::generate(3);
+        let schnorr = SchnorrSigner::aggregate(&signers);
+        let pubkey = schnorr.pubkey();
+        let pubkeys = signers.iter().map(|s| s.pubkey()).collect::<Vec<_>>();
+        assert_eq!(aggregate_pubkeys(&pubkeys).unwrap(), pubkey);
+
+        let message = [7u8; 32];
+        let (signature, commitment) = schnorr.sign(&message);
+        assert!(verify_signature(&pubkey, &message, &signature, &commitment));
+        assert!(!verify_signature(
+            &pubkey,
+            &[8u8; 32],
+            &signature,
+            &commitment
+        ));
+        assert!(!verify_signature(
+            &pubkeys[0],
+            &message,
+            &signature,
+            &commitment
+        ));
+
+        for (message, expected) in [([7u8; 32], true), ([8u8; 32], false)] {
+            let cs = &mut create_test_constraint_system()?;
+            let n = cs.n();
+            let message = allocate(cs, &message)?;
+            let signature = allocate(cs, &signature)?;
+            let commitment = Address::from_address_witness(cs, &commitment)?;
+            let pubkey = SchnorrPublicKey::from_witness(cs, &pubkey)?;
+            let is_valid = pubkey.verify(cs, &message, &signature, &commitment)?;
+            assert_eq!(is_valid.get_value(), Some(expected));
+            Boolean::enforce_equal(cs, &is_valid, &Boolean::constant(expected))?;
+            println!("Roughly {} gates", cs.n() - n);
+            assert!(cs.is_satisfied());
+        }
+        Ok(())
+    }
+}
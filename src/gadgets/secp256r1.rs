@@ -0,0 +1,368 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{
+            allocated_num::Num, bigint_new::FieldElement, bigint_new::RnsParameters,
+            boolean::Boolean,
+        },
+    },
+    vm::{partitioner::smart_and, primitives::uint256::UInt256},
+};
+use ff::PrimeField;
+use num_bigint::BigUint;
+use p256::ecdsa::{signature::hazmat::PrehashVerifier, Signature, VerifyingKey};
+
+use super::{
+    ecdsa::{uint256_inner, CHUNK_BITLEN},
+    schnorr::be_bytes32,
+};
+
+/// Base field of secp256r1.
+#[derive(PrimeField)]
+#[PrimeFieldModulus = "115792089210356248762697446949407573530086143415290314195533631308867097853951"]
+#[PrimeFieldGenerator = "6"]
+pub struct Fq(FqRepr);
+
+/// Scalar field of secp256r1, i.e. integers modulo the group order.
+#[derive(PrimeField)]
+#[PrimeFieldModulus = "115792089210356248762697446949407573529996955224135760342422259061068512044369"]
+#[PrimeFieldGenerator = "7"]
+pub struct Fr(FrRepr);
+
+/// Uncompressed public key `x || y`.
+pub const PUBKEY_BS: usize = 64;
+/// Signature `r || s`.
+pub const SIGNATURE_BS: usize = 64;
+
+const B: &str = "41058363725152142129326129780047268409114441015993725554835256314039467401291";
+const BASE_X: &str =
+    "48439561293906451759052585252797914202762949526041747995844080717082404635286";
+const BASE_Y: &str =
+    "36134250956749795798585127919587881956611106672985015071877198253568414405109";
+
+const MODULUS: &str =
+    "115792089210356248762697446949407573530086143415290314195533631308867097853951";
+const ORDER: &str =
+    "115792089210356248762697446949407573529996955224135760342422259061068512044369";
+
+/// Verify `signature` of the 32-bytes message `hash` by `pubkey` natively, in the same way as
+/// [`verify`].
+pub fn verify_signature(
+    pubkey: &[u8; PUBKEY_BS],
+    hash: &[u8; 32],
+    signature: &[u8; SIGNATURE_BS],
+) -> bool {
+    let mut sec1 = vec![0x04];
+    sec1.extend_from_slice(pubkey);
+    match (
+        VerifyingKey::from_sec1_bytes(&sec1),
+        Signature::from_slice(signature),
+    ) {
+        (Ok(key), Ok(signature)) => key.verify_prehash(hash, &signature).is_ok(),
+        _ => false,
+    }
+}
+
+// Projective point (X : Y : Z), where the identity is (0 : 1 : 0)
+#[derive(Clone)]
+struct Point<'a, E: Engine> {
+    x: FieldElement<'a, E, Fq>,
+    y: FieldElement<'a, E, Fq>,
+    z: FieldElement<'a, E, Fq>,
+}
+
+struct Curve<'a, E: Engine> {
+    rns: &'a RnsParameters<E, Fq>,
+    b: FieldElement<'a, E, Fq>,
+}
+
+impl<'a, E: Engine> Curve<'a, E> {
+    fn constant(&self, value: &BigUint) -> FieldElement<'a, E, Fq> {
+        FieldElement::constant(Fq::from_str(&value.to_string()).unwrap(), self.rns)
+    }
+
+    fn identity(&self) -> Point<'a, E> {
+        Point {
+            x: self.constant(&BigUint::from(0u8)),
+            y: self.constant(&BigUint::from(1u8)),
+            z: self.constant(&BigUint::from(0u8)),
+        }
+    }
+
+    fn base(&self) -> Point<'a, E> {
+        Point {
+            x: self.constant(&BASE_X.parse().unwrap()),
+            y: self.constant(&BASE_Y.parse().unwrap()),
+            z: self.constant(&BigUint::from(1u8)),
+        }
+    }
+
+    /// Complete addition law of `y^2 = x^3 - 3x + b`, i.e. algorithm 4 of "Complete addition
+    /// formulas for prime order elliptic curves", which also doubles.
+    fn add<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        p: &Point<'a, E>,
+        q: &Point<'a, E>,
+    ) -> Result<Point<'a, E>, SynthesisError> {
+        let t0 = p.x.mul(cs, &q.x)?;
+        let t1 = p.y.mul(cs, &q.y)?;
+        let t2 = p.z.mul(cs, &q.z)?;
+        let t3 = p.x.add(cs, &p.y)?.mul(cs, &q.x.add(cs, &q.y)?)?;
+        let t3 = t3.sub(cs, &t0.add(cs, &t1)?)?;
+        let t4 = p.y.add(cs, &p.z)?.mul(cs, &q.y.add(cs, &q.z)?)?;
+        let t4 = t4.sub(cs, &t1.add(cs, &t2)?)?;
+        let x3 = p.x.add(cs, &p.z)?.mul(cs, &q.x.add(cs, &q.z)?)?;
+        let y3 = x3.sub(cs, &t0.add(cs, &t2)?)?;
+        let z3 = self.b.mul(cs, &t2)?;
+        let x3 = y3.sub(cs, &z3)?;
+        let x3 = x3.add(cs, &x3)?.add(cs, &x3)?;
+        let z3 = t1.sub(cs, &x3)?;
+        let x3 = t1.add(cs, &x3)?;
+        let y3 = self.b.mul(cs, &y3)?;
+        let t2 = t2.add(cs, &t2)?.add(cs, &t2)?;
+        let y3 = y3.sub(cs, &t2)?.sub(cs, &t0)?;
+        let y3 = y3.add(cs, &y3)?.add(cs, &y3)?;
+        let t0 = t0.add(cs, &t0)?.add(cs, &t0)?.sub(cs, &t2)?;
+        let t1 = t4.mul(cs, &y3)?;
+        let t2 = t0.mul(cs, &y3)?;
+        let y = x3.mul(cs, &z3)?.add(cs, &t2)?;
+        let x = x3.mul(cs, &t3)?.sub(cs, &t1)?;
+        let z = t4.mul(cs, &z3)?.add(cs, &t3.mul(cs, &t0)?)?;
+        Ok(Point { x, y, z })
+    }
+
+    fn select<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        flag: &Boolean,
+        p: &Point<'a, E>,
+        q: &Point<'a, E>,
+    ) -> Result<Point<'a, E>, SynthesisError> {
+        Ok(Point {
+            x: FieldElement::conditionally_select(cs, flag, &p.x, &q.x)?,
+            y: FieldElement::conditionally_select(cs, flag, &p.y, &q.y)?,
+            z: FieldElement::conditionally_select(cs, flag, &p.z, &q.z)?,
+        })
+    }
+}
+
+/// Element of `F` from `value`. Returns whether `value` is less than `modulus` of `F` and the
+/// element, which is zero if it is not.
+fn alloc_canonical<'a, E: Engine, F: PrimeField, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    rns: &'a RnsParameters<E, F>,
+    value: &UInt256<E>,
+    modulus: &BigUint,
+) -> Result<(Boolean, FieldElement<'a, E, F>), SynthesisError> {
+    let (_, is_canonical) = value.sub(cs, &UInt256::constant(modulus.clone()))?;
+    let value = value.mask(cs, &is_canonical)?;
+    let limbs = uint256_inner(cs, &value)?
+        .into_iter()
+        .map(|x| x.inner)
+        .collect::<Vec<Num<E>>>();
+    let fe = unsafe { FieldElement::alloc_from_limbs_unchecked(cs, &limbs, rns, true)? };
+    Ok((is_canonical, fe))
+}
+
+// Bits of big-endian bytes from the least significant one
+fn bits_le<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    bytes: &[Byte<E>],
+) -> Result<Vec<Boolean>, SynthesisError> {
+    let mut bits = vec![];
+    for byte in bytes.iter().rev() {
+        bits.extend(byte.inner.into_bits_le(cs, Some(8))?);
+    }
+    Ok(bits)
+}
+
+// Witness `numerator / s` modulo the group order in big-endian bytes
+fn alloc_quotient<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    numerator: &[Byte<E>],
+    s: &[Byte<E>],
+) -> Result<[Byte<E>; 32], SynthesisError> {
+    let n: BigUint = ORDER.parse().unwrap();
+    let quotient = match (
+        Byte::get_byte_value_multiple(numerator),
+        Byte::get_byte_value_multiple(s),
+    ) {
+        (Some(numerator), Some(s)) => {
+            let s = BigUint::from_bytes_be(&s) % &n;
+            let s_inv = s.modpow(&(&n - 2u8), &n);
+            Some(be_bytes32(
+                &(BigUint::from_bytes_be(&numerator) * s_inv % &n),
+            ))
+        }
+        _ => None,
+    };
+    let mut bytes = [Byte::zero(); 32];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = Byte::from_u8_witness(cs, quotient.as_ref().map(|q| q[i]))?;
+    }
+    Ok(bytes)
+}
+
+/// Verify `signature = r || s` of the 32-bytes message `hash` by `pubkey = x || y`, all in
+/// big-endian, i.e. the x-coordinate of `[z / s]G + [r / s]Q` equals `r` modulo the group order,
+/// where `z` is `hash` as an integer. `r` and `s` must be in `[1, n)` and high `s` is accepted.
+pub fn verify<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    pubkey: &[Byte<E>; PUBKEY_BS],
+    hash: &[Byte<E>; 32],
+    signature: &[Byte<E>; SIGNATURE_BS],
+) -> Result<Boolean, SynthesisError> {
+    let (p, n): (BigUint, BigUint) = (MODULUS.parse().unwrap(), ORDER.parse().unwrap());
+    let rns_q = RnsParameters::<E, Fq>::new_optimal(cs, CHUNK_BITLEN);
+    let rns_r = RnsParameters::<E, Fr>::new_optimal(cs, CHUNK_BITLEN);
+    let curve = Curve {
+        rns: &rns_q,
+        b: FieldElement::constant(Fq::from_str(B).unwrap(), &rns_q),
+    };
+    let uint = |cs: &mut CS, bytes: &[Byte<E>]| {
+        UInt256::from_be_bytes_fixed(cs, bytes.try_into().unwrap())
+    };
+
+    // y^2 == x^3 - 3x + b
+    let x = uint(cs, &pubkey[..32])?;
+    let y = uint(cs, &pubkey[32..])?;
+    let (is_x_canonical, x) = alloc_canonical(cs, &rns_q, &x, &p)?;
+    let (is_y_canonical, y) = alloc_canonical(cs, &rns_q, &y, &p)?;
+    let mut lhs = y.square(cs)?;
+    let three_x = x.add(cs, &x)?.add(cs, &x)?;
+    let mut rhs = x
+        .square(cs)?
+        .mul(cs, &x)?
+        .sub(cs, &three_x)?
+        .add(cs, &curve.b)?;
+    let is_on_curve = FieldElement::equals(cs, &mut lhs, &mut rhs)?;
+    let one = curve.constant(&BigUint::from(1u8));
+    let q = Point { x, y, z: one };
+
+    let r_uint = uint(cs, &signature[..32])?;
+    let s_uint = uint(cs, &signature[32..])?;
+    let (is_r_canonical, mut r) = alloc_canonical(cs, &rns_r, &r_uint, &n)?;
+    let (is_s_canonical, mut s) = alloc_canonical(cs, &rns_r, &s_uint, &n)?;
+    let is_r_zero = FieldElement::is_zero(&mut r, cs)?;
+    let is_s_zero = FieldElement::is_zero(&mut s, cs)?;
+
+    // z is less than 2n, so that it is reduced by one subtraction
+    let z = uint(cs, hash)?;
+    let (z_minus_n, is_z_reduced) = z.sub(cs, &UInt256::constant(n.clone()))?;
+    let z = UInt256::conditionally_select(cs, &is_z_reduced, &z, &z_minus_n)?;
+    let (_, mut z) = alloc_canonical(cs, &rns_r, &z, &n)?;
+
+    // u1 * s == z and u2 * s == r
+    let u1_bytes = alloc_quotient(cs, hash, &signature[32..])?;
+    let u2_bytes = alloc_quotient(cs, &signature[..32], &signature[32..])?;
+    let (is_u1_canonical, u1) = alloc_canonical(cs, &rns_r, &uint(cs, &u1_bytes)?, &n)?;
+    let (is_u2_canonical, u2) = alloc_canonical(cs, &rns_r, &uint(cs, &u2_bytes)?, &n)?;
+    let mut u1_s = u1.mul(cs, &s)?;
+    let mut u2_s = u2.mul(cs, &s)?;
+    let is_u1_valid = FieldElement::equals(cs, &mut u1_s, &mut z)?;
+    let is_u2_valid = FieldElement::equals(cs, &mut u2_s, &mut r)?;
+
+    // [u1]G + [u2]Q by double-and-add over both scalars at once
+    let u1_bits = bits_le(cs, &u1_bytes)?;
+    let u2_bits = bits_le(cs, &u2_bytes)?;
+    let identity = curve.identity();
+    let base = curve.base();
+    let base_plus_q = curve.add(cs, &base, &q)?;
+    let mut acc = curve.identity();
+    for i in (0..u1_bits.len()).rev() {
+        acc = curve.add(cs, &acc, &acc)?;
+        let with_q = curve.select(cs, &u1_bits[i], &base_plus_q, &q)?;
+        let without_q = curve.select(cs, &u1_bits[i], &base, &identity)?;
+        let addend = curve.select(cs, &u2_bits[i], &with_q, &without_q)?;
+        acc = curve.add(cs, &acc, &addend)?;
+    }
+
+    // The x-coordinate of the sum is either r or r + n, and the sum is not the identity
+    let is_infinity = FieldElement::is_zero(&mut acc.z, cs)?;
+    let z_inv = FieldElement::conditionally_select(cs, &is_infinity, &one, &acc.z)?;
+    let mut acc_x = acc.x.div(cs, &z_inv)?;
+    let (_, mut r_q) = alloc_canonical(cs, &rns_q, &r_uint, &p)?;
+    let (r_plus_n, is_overflowed) = r_uint.add(cs, &UInt256::constant(n))?;
+    let (is_r_plus_n_canonical, mut r_plus_n) = alloc_canonical(cs, &rns_q, &r_plus_n, &p)?;
+    let is_r_matched = FieldElement::equals(cs, &mut acc_x, &mut r_q)?;
+    let is_r_plus_n_matched = FieldElement::equals(cs, &mut acc_x, &mut r_plus_n)?;
+    let is_r_plus_n_matched = smart_and(
+        cs,
+        &[
+            is_r_plus_n_matched,
+            is_r_plus_n_canonical,
+            is_overflowed.not(),
+        ],
+    )?;
+    let is_x_matched = Boolean::or(cs, &is_r_matched, &is_r_plus_n_matched)?;
+
+    smart_and(
+        cs,
+        &[
+            is_x_canonical,
+            is_y_canonical,
+            is_on_curve,
+            is_r_canonical,
+            is_s_canonical,
+            is_r_zero.not(),
+            is_s_zero.not(),
+            is_u1_canonical,
+            is_u2_canonical,
+            is_u1_valid,
+            is_u2_valid,
+            is_infinity.not(),
+            is_x_matched,
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte, franklin_crypto::bellman::SynthesisError,
+    };
+    use sha2::{Digest, Sha256};
+
+    use super::{verify, verify_signature};
+    use crate::utils::{signer::P256Signer, testing::create_test_constraint_system};
+
+    #[test]
+    fn test_secp256r1() -> Result<(), SynthesisError> {
+        let signer = P256Signer::new(0x42);
+        let hash: [u8; 32] = Sha256::digest(b"hello world").into();
+        let signature = signer.sign_hash(&hash);
+        assert!(verify_signature(&signer.pubkey(), &hash, &signature));
+        let mut tampered = signature;
+        tampered[40] ^= 1;
+        assert!(!verify_signature(&signer.pubkey(), &hash, &tampered));
+        let mut other_hash = hash;
+        other_hash[0] ^= 1;
+        assert!(!verify_signature(&signer.pubkey(), &other_hash, &signature));
+
+        for (hash, signature, expected) in [
+            (hash, signature, true),
+            (hash, tampered, false),
+            (other_hash, signature, false),
+        ] {
+            let cs = &mut create_test_constraint_system()?;
+            let mut alloc = |bytes: &[u8]| {
+                bytes
+                    .iter()
+                    .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+                    .collect::<Result<Vec<_>, _>>()
+            };
+            let pubkey = alloc(&signer.pubkey())?.try_into().unwrap();
+            let hash = alloc(&hash)?.try_into().unwrap();
+            let signature = alloc(&signature)?.try_into().unwrap();
+            let n = cs.n();
+            let is_valid = verify(cs, &pubkey, &hash, &signature)?;
+            println!("Roughly {} gates", cs.n() - n);
+            assert_eq!(is_valid.get_value(), Some(expected));
+            assert!(cs.is_satisfied());
+        }
+        Ok(())
+    }
+}
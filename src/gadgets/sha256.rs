@@ -0,0 +1,293 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{ff::Field, Engine};
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{boolean::Boolean, linear_combination::LinearCombination},
+    },
+    utils::u64_to_fe,
+};
+
+pub const WIDTH_HASH_BYTES: usize = 32;
+const BLOCK_BYTES: usize = 64;
+pub(crate) const WORD_BITS: usize = 32;
+// Enough bits to hold the sum of up to 8 words before truncation
+const SUM_BITS: usize = WORD_BITS + 3;
+
+/// Word as little-endian bits, so that rotations and shifts cost no gates.
+pub(crate) type Word = [Boolean; WORD_BITS];
+
+const IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+pub(crate) fn word_constant(value: u32) -> Word {
+    let mut word = [Boolean::constant(false); WORD_BITS];
+    for (i, bit) in word.iter_mut().enumerate() {
+        *bit = Boolean::constant((value >> i) & 1 == 1);
+    }
+    word
+}
+
+fn word_from_be_bytes<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    bytes: &[Byte<E>],
+) -> Result<Word, SynthesisError> {
+    let mut word = [Boolean::constant(false); WORD_BITS];
+    for (i, byte) in bytes.iter().rev().enumerate() {
+        let bits = byte.inner.into_bits_le(cs, Some(8))?;
+        word[i * 8..(i + 1) * 8].copy_from_slice(&bits);
+    }
+    Ok(word)
+}
+
+fn word_into_be_bytes<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    word: &Word,
+) -> Result<[Byte<E>; 4], SynthesisError> {
+    let mut bytes = [Byte::zero(); 4];
+    for (i, bits) in word.chunks_exact(8).rev().enumerate() {
+        let mut lc = LinearCombination::zero();
+        let mut coeff = E::Fr::one();
+        for bit in bits {
+            lc.add_assign_boolean_with_coeff(bit, coeff);
+            coeff.double();
+        }
+        let num = lc.into_num(cs)?;
+        bytes[i] = Byte::from_num_unconstrained(cs, num);
+    }
+    Ok(bytes)
+}
+
+pub(crate) fn rotr(word: &Word, n: usize) -> Word {
+    let mut out = *word;
+    for (i, bit) in out.iter_mut().enumerate() {
+        *bit = word[(i + n) % WORD_BITS];
+    }
+    out
+}
+
+fn shr(word: &Word, n: usize) -> Word {
+    let mut out = [Boolean::constant(false); WORD_BITS];
+    out[..WORD_BITS - n].copy_from_slice(&word[n..]);
+    out
+}
+
+fn xor3<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    a: &Word,
+    b: &Word,
+    c: &Word,
+) -> Result<Word, SynthesisError> {
+    let mut out = [Boolean::constant(false); WORD_BITS];
+    for i in 0..WORD_BITS {
+        let ab = Boolean::xor(cs, &a[i], &b[i])?;
+        out[i] = Boolean::xor(cs, &ab, &c[i])?;
+    }
+    Ok(out)
+}
+
+// (e & f) ^ (!e & g)
+fn ch<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    e: &Word,
+    f: &Word,
+    g: &Word,
+) -> Result<Word, SynthesisError> {
+    let mut out = [Boolean::constant(false); WORD_BITS];
+    for i in 0..WORD_BITS {
+        let ef = Boolean::and(cs, &e[i], &f[i])?;
+        let ng = Boolean::and(cs, &e[i].not(), &g[i])?;
+        out[i] = Boolean::xor(cs, &ef, &ng)?;
+    }
+    Ok(out)
+}
+
+// (a & b) ^ (a & c) ^ (b & c)
+fn maj<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    a: &Word,
+    b: &Word,
+    c: &Word,
+) -> Result<Word, SynthesisError> {
+    let mut out = [Boolean::constant(false); WORD_BITS];
+    for i in 0..WORD_BITS {
+        let ab = Boolean::and(cs, &a[i], &b[i])?;
+        let ac = Boolean::and(cs, &a[i], &c[i])?;
+        let bc = Boolean::and(cs, &b[i], &c[i])?;
+        let t = Boolean::xor(cs, &ab, &ac)?;
+        out[i] = Boolean::xor(cs, &t, &bc)?;
+    }
+    Ok(out)
+}
+
+/// Sum of words and a constant modulo 2^32.
+pub(crate) fn add<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    words: &[&Word],
+    constant: u32,
+) -> Result<Word, SynthesisError> {
+    assert!(words.len() < 1 << (SUM_BITS - WORD_BITS));
+    let mut lc = LinearCombination::zero();
+    for word in words {
+        let mut coeff = E::Fr::one();
+        for bit in word.iter() {
+            lc.add_assign_boolean_with_coeff(bit, coeff);
+            coeff.double();
+        }
+    }
+    lc.add_assign_constant(u64_to_fe(constant as u64));
+    let sum = lc.into_num(cs)?;
+    let bits = sum.into_bits_le(cs, Some(SUM_BITS))?;
+    let mut out = [Boolean::constant(false); WORD_BITS];
+    out.copy_from_slice(&bits[..WORD_BITS]);
+    Ok(out)
+}
+
+fn compress<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    state: &[Word; 8],
+    block: &[Byte<E>],
+) -> Result<[Word; 8], SynthesisError> {
+    let mut w = Vec::with_capacity(64);
+    for chunk in block.chunks_exact(4) {
+        w.push(word_from_be_bytes(cs, chunk)?);
+    }
+    for t in 16..64 {
+        let s0 = xor3(
+            cs,
+            &rotr(&w[t - 15], 7),
+            &rotr(&w[t - 15], 18),
+            &shr(&w[t - 15], 3),
+        )?;
+        let s1 = xor3(
+            cs,
+            &rotr(&w[t - 2], 17),
+            &rotr(&w[t - 2], 19),
+            &shr(&w[t - 2], 10),
+        )?;
+        let wt = add(cs, &[&s1, &w[t - 7], &s0, &w[t - 16]], 0)?;
+        w.push(wt);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for t in 0..64 {
+        let s1 = xor3(cs, &rotr(&e, 6), &rotr(&e, 11), &rotr(&e, 25))?;
+        let choice = ch(cs, &e, &f, &g)?;
+        let s0 = xor3(cs, &rotr(&a, 2), &rotr(&a, 13), &rotr(&a, 22))?;
+        let majority = maj(cs, &a, &b, &c)?;
+        h = g;
+        g = f;
+        f = e;
+        e = add(cs, &[&d, &h, &s1, &choice, &w[t]], K[t])?;
+        d = c;
+        c = b;
+        b = a;
+        a = add(cs, &[&h, &s1, &choice, &w[t], &s0, &majority], K[t])?;
+    }
+
+    let mut next = *state;
+    for (i, word) in [a, b, c, d, e, f, g, h].iter().enumerate() {
+        next[i] = add(cs, &[&state[i], word], 0)?;
+    }
+    Ok(next)
+}
+
+/// SHA-256 digest of `bytes`. The length of `bytes` is fixed at synthesis, so the padding is
+/// constant.
+pub fn digest<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    bytes: &[Byte<E>],
+) -> Result<[Byte<E>; WIDTH_HASH_BYTES], SynthesisError> {
+    let mut padded = bytes.to_vec();
+    padded.push(Byte::constant(0x80));
+    while padded.len() % BLOCK_BYTES != BLOCK_BYTES - 8 {
+        padded.push(Byte::zero());
+    }
+    let bit_len = (bytes.len() as u64) * 8;
+    padded.extend(bit_len.to_be_bytes().map(Byte::constant));
+
+    let mut state = IV.map(word_constant);
+    for block in padded.chunks_exact(BLOCK_BYTES) {
+        state = compress(cs, &state, block)?;
+    }
+
+    let mut digest = [Byte::zero(); WIDTH_HASH_BYTES];
+    for (i, word) in state.iter().enumerate() {
+        digest[i * 4..(i + 1) * 4].copy_from_slice(&word_into_be_bytes(cs, word)?);
+    }
+    Ok(digest)
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte, franklin_crypto::bellman::SynthesisError,
+    };
+
+    use crate::utils::testing::create_test_constraint_system;
+
+    #[test]
+    fn test_sha256() -> Result<(), SynthesisError> {
+        let mut cs = create_test_constraint_system()?;
+        let cs = &mut cs;
+        let long = [b'a'; 100];
+        // Inputs of 55 and 56 bytes are the longest fitting one block and the shortest requiring
+        // an extra padding block, and 64 bytes are two SSZ nodes
+        let boundaries = [
+            [55u8; 55].to_vec(),
+            [56u8; 56].to_vec(),
+            [64u8; 64].to_vec(),
+        ];
+        let cases = [
+            (
+                &b""[..],
+                "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            ),
+            (
+                &b"hello world"[..],
+                "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9",
+            ),
+            (
+                &long[..],
+                "2816597888e4a0d3a36b82b83316ab32680eb8f00f8cd3b904d681246d285a0e",
+            ),
+            (
+                &boundaries[0][..],
+                "f9e3ac6e56363ff68f01e0642921f38b53a23ee735f35bbfc6fed6c25d10f9ec",
+            ),
+            (
+                &boundaries[1][..],
+                "16d5189ddf169a234580076802cc1130ab264fb05288c507711eed0ea3813609",
+            ),
+            (
+                &boundaries[2][..],
+                "0baca70e52ced8beea55d8aec7889e8ddd5ee512bb8e331c2f60e99170a3809b",
+            ),
+        ];
+        for (input, expected) in cases {
+            let n = cs.n();
+            let input_bytes = input
+                .iter()
+                .map(|b| Byte::from_u8_witness(cs, Some(*b)).unwrap())
+                .collect::<Vec<_>>();
+            let digest = super::digest(cs, &input_bytes)?;
+            let digest = Byte::get_byte_value_multiple(&digest).unwrap();
+            assert_eq!(hex::encode(digest), expected);
+            println!("Roughly {} gates", cs.n() - n);
+        }
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+}
@@ -0,0 +1,393 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{ff::Field, Engine};
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{boolean::Boolean, linear_combination::LinearCombination},
+    },
+    utils::u64_to_fe,
+};
+
+pub const WIDTH_HASH_BYTES: usize = 64;
+pub const BLOCK_BYTES: usize = 128;
+pub(crate) const WORD_BITS: usize = 64;
+// Enough bits to hold the sum of up to 8 words before truncation
+const SUM_BITS: usize = WORD_BITS + 3;
+
+/// Word as little-endian bits, so that rotations and shifts cost no gates.
+pub(crate) type Word = [Boolean; WORD_BITS];
+
+const IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+const K: [u64; 80] = [
+    0x428a2f98d728ae22,
+    0x7137449123ef65cd,
+    0xb5c0fbcfec4d3b2f,
+    0xe9b5dba58189dbbc,
+    0x3956c25bf348b538,
+    0x59f111f1b605d019,
+    0x923f82a4af194f9b,
+    0xab1c5ed5da6d8118,
+    0xd807aa98a3030242,
+    0x12835b0145706fbe,
+    0x243185be4ee4b28c,
+    0x550c7dc3d5ffb4e2,
+    0x72be5d74f27b896f,
+    0x80deb1fe3b1696b1,
+    0x9bdc06a725c71235,
+    0xc19bf174cf692694,
+    0xe49b69c19ef14ad2,
+    0xefbe4786384f25e3,
+    0x0fc19dc68b8cd5b5,
+    0x240ca1cc77ac9c65,
+    0x2de92c6f592b0275,
+    0x4a7484aa6ea6e483,
+    0x5cb0a9dcbd41fbd4,
+    0x76f988da831153b5,
+    0x983e5152ee66dfab,
+    0xa831c66d2db43210,
+    0xb00327c898fb213f,
+    0xbf597fc7beef0ee4,
+    0xc6e00bf33da88fc2,
+    0xd5a79147930aa725,
+    0x06ca6351e003826f,
+    0x142929670a0e6e70,
+    0x27b70a8546d22ffc,
+    0x2e1b21385c26c926,
+    0x4d2c6dfc5ac42aed,
+    0x53380d139d95b3df,
+    0x650a73548baf63de,
+    0x766a0abb3c77b2a8,
+    0x81c2c92e47edaee6,
+    0x92722c851482353b,
+    0xa2bfe8a14cf10364,
+    0xa81a664bbc423001,
+    0xc24b8b70d0f89791,
+    0xc76c51a30654be30,
+    0xd192e819d6ef5218,
+    0xd69906245565a910,
+    0xf40e35855771202a,
+    0x106aa07032bbd1b8,
+    0x19a4c116b8d2d0c8,
+    0x1e376c085141ab53,
+    0x2748774cdf8eeb99,
+    0x34b0bcb5e19b48a8,
+    0x391c0cb3c5c95a63,
+    0x4ed8aa4ae3418acb,
+    0x5b9cca4f7763e373,
+    0x682e6ff3d6b2b8a3,
+    0x748f82ee5defb2fc,
+    0x78a5636f43172f60,
+    0x84c87814a1f0ab72,
+    0x8cc702081a6439ec,
+    0x90befffa23631e28,
+    0xa4506cebde82bde9,
+    0xbef9a3f7b2c67915,
+    0xc67178f2e372532b,
+    0xca273eceea26619c,
+    0xd186b8c721c0c207,
+    0xeada7dd6cde0eb1e,
+    0xf57d4f7fee6ed178,
+    0x06f067aa72176fba,
+    0x0a637dc5a2c898a6,
+    0x113f9804bef90dae,
+    0x1b710b35131c471b,
+    0x28db77f523047d84,
+    0x32caab7b40c72493,
+    0x3c9ebe0a15c9bebc,
+    0x431d67c49c100d4c,
+    0x4cc5d4becb3e42b6,
+    0x597f299cfc657e2a,
+    0x5fcb6fab3ad6faec,
+    0x6c44198c4a475817,
+];
+
+pub(crate) fn word_constant(value: u64) -> Word {
+    let mut word = [Boolean::constant(false); WORD_BITS];
+    for (i, bit) in word.iter_mut().enumerate() {
+        *bit = Boolean::constant((value >> i) & 1 == 1);
+    }
+    word
+}
+
+fn word_from_be_bytes<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    bytes: &[Byte<E>],
+) -> Result<Word, SynthesisError> {
+    let mut word = [Boolean::constant(false); WORD_BITS];
+    for (i, byte) in bytes.iter().rev().enumerate() {
+        let bits = byte.inner.into_bits_le(cs, Some(8))?;
+        word[i * 8..(i + 1) * 8].copy_from_slice(&bits);
+    }
+    Ok(word)
+}
+
+fn word_into_be_bytes<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    word: &Word,
+) -> Result<[Byte<E>; 8], SynthesisError> {
+    let mut bytes = [Byte::zero(); 8];
+    for (i, bits) in word.chunks_exact(8).rev().enumerate() {
+        let mut lc = LinearCombination::zero();
+        let mut coeff = E::Fr::one();
+        for bit in bits {
+            lc.add_assign_boolean_with_coeff(bit, coeff);
+            coeff.double();
+        }
+        let num = lc.into_num(cs)?;
+        bytes[i] = Byte::from_num_unconstrained(cs, num);
+    }
+    Ok(bytes)
+}
+
+pub(crate) fn rotr(word: &Word, n: usize) -> Word {
+    let mut out = *word;
+    for (i, bit) in out.iter_mut().enumerate() {
+        *bit = word[(i + n) % WORD_BITS];
+    }
+    out
+}
+
+fn shr(word: &Word, n: usize) -> Word {
+    let mut out = [Boolean::constant(false); WORD_BITS];
+    out[..WORD_BITS - n].copy_from_slice(&word[n..]);
+    out
+}
+
+fn xor3<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    a: &Word,
+    b: &Word,
+    c: &Word,
+) -> Result<Word, SynthesisError> {
+    let mut out = [Boolean::constant(false); WORD_BITS];
+    for i in 0..WORD_BITS {
+        let ab = Boolean::xor(cs, &a[i], &b[i])?;
+        out[i] = Boolean::xor(cs, &ab, &c[i])?;
+    }
+    Ok(out)
+}
+
+// (e & f) ^ (!e & g)
+fn ch<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    e: &Word,
+    f: &Word,
+    g: &Word,
+) -> Result<Word, SynthesisError> {
+    let mut out = [Boolean::constant(false); WORD_BITS];
+    for i in 0..WORD_BITS {
+        let ef = Boolean::and(cs, &e[i], &f[i])?;
+        let ng = Boolean::and(cs, &e[i].not(), &g[i])?;
+        out[i] = Boolean::xor(cs, &ef, &ng)?;
+    }
+    Ok(out)
+}
+
+// (a & b) ^ (a & c) ^ (b & c)
+fn maj<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    a: &Word,
+    b: &Word,
+    c: &Word,
+) -> Result<Word, SynthesisError> {
+    let mut out = [Boolean::constant(false); WORD_BITS];
+    for i in 0..WORD_BITS {
+        let ab = Boolean::and(cs, &a[i], &b[i])?;
+        let ac = Boolean::and(cs, &a[i], &c[i])?;
+        let bc = Boolean::and(cs, &b[i], &c[i])?;
+        let t = Boolean::xor(cs, &ab, &ac)?;
+        out[i] = Boolean::xor(cs, &t, &bc)?;
+    }
+    Ok(out)
+}
+
+/// Sum of words and a constant modulo 2^64.
+pub(crate) fn add<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    words: &[&Word],
+    constant: u64,
+) -> Result<Word, SynthesisError> {
+    assert!(words.len() < 1 << (SUM_BITS - WORD_BITS));
+    let mut lc = LinearCombination::zero();
+    for word in words {
+        let mut coeff = E::Fr::one();
+        for bit in word.iter() {
+            lc.add_assign_boolean_with_coeff(bit, coeff);
+            coeff.double();
+        }
+    }
+    lc.add_assign_constant(u64_to_fe(constant));
+    let sum = lc.into_num(cs)?;
+    let bits = sum.into_bits_le(cs, Some(SUM_BITS))?;
+    let mut out = [Boolean::constant(false); WORD_BITS];
+    out.copy_from_slice(&bits[..WORD_BITS]);
+    Ok(out)
+}
+
+fn compress<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    state: &[Word; 8],
+    block: &[Byte<E>],
+) -> Result<[Word; 8], SynthesisError> {
+    let mut w = Vec::with_capacity(80);
+    for chunk in block.chunks_exact(8) {
+        w.push(word_from_be_bytes(cs, chunk)?);
+    }
+    for t in 16..80 {
+        let s0 = xor3(
+            cs,
+            &rotr(&w[t - 15], 1),
+            &rotr(&w[t - 15], 8),
+            &shr(&w[t - 15], 7),
+        )?;
+        let s1 = xor3(
+            cs,
+            &rotr(&w[t - 2], 19),
+            &rotr(&w[t - 2], 61),
+            &shr(&w[t - 2], 6),
+        )?;
+        let wt = add(cs, &[&s1, &w[t - 7], &s0, &w[t - 16]], 0)?;
+        w.push(wt);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for t in 0..80 {
+        let s1 = xor3(cs, &rotr(&e, 14), &rotr(&e, 18), &rotr(&e, 41))?;
+        let choice = ch(cs, &e, &f, &g)?;
+        let s0 = xor3(cs, &rotr(&a, 28), &rotr(&a, 34), &rotr(&a, 39))?;
+        let majority = maj(cs, &a, &b, &c)?;
+        h = g;
+        g = f;
+        f = e;
+        e = add(cs, &[&d, &h, &s1, &choice, &w[t]], K[t])?;
+        d = c;
+        c = b;
+        b = a;
+        a = add(cs, &[&h, &s1, &choice, &w[t], &s0, &majority], K[t])?;
+    }
+
+    let mut next = *state;
+    for (i, word) in [a, b, c, d, e, f, g, h].iter().enumerate() {
+        next[i] = add(cs, &[&state[i], word], 0)?;
+    }
+    Ok(next)
+}
+
+/// SHA-512 digest of `bytes`. The length of `bytes` is fixed at synthesis, so the padding is
+/// constant.
+pub fn digest<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    bytes: &[Byte<E>],
+) -> Result<[Byte<E>; WIDTH_HASH_BYTES], SynthesisError> {
+    let mut padded = bytes.to_vec();
+    padded.push(Byte::constant(0x80));
+    while padded.len() % BLOCK_BYTES != BLOCK_BYTES - 16 {
+        padded.push(Byte::zero());
+    }
+    let bit_len = (bytes.len() as u128) * 8;
+    padded.extend(bit_len.to_be_bytes().map(Byte::constant));
+
+    let mut state = IV.map(word_constant);
+    for block in padded.chunks_exact(BLOCK_BYTES) {
+        state = compress(cs, &state, block)?;
+    }
+    state_into_be_bytes(cs, &state)
+}
+
+/// Initial chaining value of SHA-512 as big-endian bytes.
+pub fn initial_state<E: Engine>() -> [Byte<E>; WIDTH_HASH_BYTES] {
+    let mut bytes = [Byte::zero(); WIDTH_HASH_BYTES];
+    for (i, word) in IV.iter().enumerate() {
+        for (byte, b) in bytes[i * 8..(i + 1) * 8].iter_mut().zip(word.to_be_bytes()) {
+            *byte = Byte::constant(b);
+        }
+    }
+    bytes
+}
+
+/// SHA-512 compression of one `block` into the chaining value `state`, both as big-endian bytes.
+/// [`digest`] compresses padded blocks from [`initial_state`], so messages sharing a prefix of
+/// whole blocks may compress the prefix once and continue from its chaining value.
+pub fn compress_block<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    state: &[Byte<E>; WIDTH_HASH_BYTES],
+    block: &[Byte<E>; BLOCK_BYTES],
+) -> Result<[Byte<E>; WIDTH_HASH_BYTES], SynthesisError> {
+    let mut words = [[Boolean::constant(false); WORD_BITS]; 8];
+    for (word, chunk) in words.iter_mut().zip(state.chunks_exact(8)) {
+        *word = word_from_be_bytes(cs, chunk)?;
+    }
+    let state = compress(cs, &words, block)?;
+    state_into_be_bytes(cs, &state)
+}
+
+fn state_into_be_bytes<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    state: &[Word; 8],
+) -> Result<[Byte<E>; WIDTH_HASH_BYTES], SynthesisError> {
+    let mut bytes = [Byte::zero(); WIDTH_HASH_BYTES];
+    for (i, word) in state.iter().enumerate() {
+        bytes[i * 8..(i + 1) * 8].copy_from_slice(&word_into_be_bytes(cs, word)?);
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte, franklin_crypto::bellman::SynthesisError,
+    };
+
+    use super::{compress_block, digest, initial_state, BLOCK_BYTES};
+    use crate::utils::testing::create_test_constraint_system;
+
+    #[test]
+    fn test_sha512() -> Result<(), SynthesisError> {
+        let mut cs = create_test_constraint_system()?;
+        let cs = &mut cs;
+        let boundary = [112u8; 112];
+        let cases = [
+            (&b""[..], "cf83e1357eefb8bdf1542850d66d8007d620e4050b5715dc83f4a921d36ce9ce47d0d13c5d85f2b0ff8318d2877eec2f63b931bd47417a81a538327af927da3e"),
+            (&b"hello world"[..], "309ecc489c12d6eb4cc40f50c902f2b4d0ed77ee511a7c7a9bcd3ca86d4cd86f989dd35bc5ff499670da34255b45b0cfd830e81f605dcf7dc5542e93ae9cd76f"),
+            // The shortest input requiring an extra padding block
+            (&boundary[..], "053937dda7714131d986e0368bf79bcb86c6ab580197c8316984d2435ab3336a2863bc2f799ada90bb64b19bfeb807b80e56114daab7cd42f1f30f0f161a275d"),
+        ];
+        for (input, expected) in cases {
+            let n = cs.n();
+            let input_bytes = input
+                .iter()
+                .map(|b| Byte::from_u8_witness(cs, Some(*b)).unwrap())
+                .collect::<Vec<_>>();
+            let hash = digest(cs, &input_bytes)?;
+            let hash = Byte::get_byte_value_multiple(&hash).unwrap();
+            assert_eq!(hex::encode(hash), expected);
+            println!("Roughly {} gates", cs.n() - n);
+        }
+
+        // The digest of one block is its compression from the initial state
+        let mut block = [Byte::zero(); BLOCK_BYTES];
+        for (byte, b) in block.iter_mut().zip(b"hello world") {
+            *byte = Byte::from_u8_witness(cs, Some(*b))?;
+        }
+        block[11] = Byte::constant(0x80);
+        block[BLOCK_BYTES - 1] = Byte::constant(11 * 8);
+        let compressed = compress_block(cs, &initial_state(), &block)?;
+        let expected = digest(cs, &block[..11])?;
+        assert_eq!(
+            Byte::get_byte_value_multiple(&compressed),
+            Byte::get_byte_value_multiple(&expected)
+        );
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+}
@@ -0,0 +1,252 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    glue::prepacked_long_comparison,
+    utils::u64_to_fe,
+    vm::primitives::uint256::UInt256,
+};
+use num_bigint::BigUint;
+
+use crate::utils::{fr_from_biguint, num_from_be_bytes, num_into_be_bytes};
+
+/// Signed 64-bit integer, whose two's complement `raw` is constrained to 64 bits.
+#[derive(Debug, Clone, Copy)]
+pub struct Int64<E: Engine> {
+    pub raw: Num<E>,
+    pub is_negative: Boolean,
+}
+
+impl<E: Engine> Int64<E> {
+    pub fn alloc<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: Option<i64>,
+    ) -> Result<Self, SynthesisError> {
+        let raw = Num::alloc(cs, witness.map(|v| u64_to_fe(v as u64)))?;
+        Self::from_raw(cs, raw)
+    }
+
+    /// Circuit is unsatisfied if `raw` has more than 64 bits.
+    pub fn from_raw<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        raw: Num<E>,
+    ) -> Result<Self, SynthesisError> {
+        let bits = raw.into_bits_le(cs, Some(64))?;
+        Ok(Self {
+            raw,
+            is_negative: bits[63],
+        })
+    }
+
+    pub fn from_be_bytes<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        bytes: &[Byte<E>; 8],
+    ) -> Result<Self, SynthesisError> {
+        let raw = num_from_be_bytes(cs, bytes)?;
+        Self::from_raw(cs, raw)
+    }
+
+    /// Absolute value, which is below 2^63 except `2^63` of `i64::MIN`.
+    pub fn abs<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<Num<E>, SynthesisError> {
+        let modulus = fr_from_biguint::<E>(&(BigUint::from(1u8) << 64))?;
+        let complement = Num::Constant(modulus).sub(cs, &self.raw)?;
+        Num::conditionally_select(cs, &self.is_negative, &complement, &self.raw)
+    }
+
+    /// Field element of the value, i.e. `-abs` if negative.
+    pub fn to_num<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<Num<E>, SynthesisError> {
+        let modulus = fr_from_biguint::<E>(&(BigUint::from(1u8) << 64))?;
+        let offset = Num::from_boolean_is(self.is_negative).mul(cs, &Num::Constant(modulus))?;
+        self.raw.sub(cs, &offset)
+    }
+
+    pub fn equals<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        other: &Self,
+    ) -> Result<Boolean, SynthesisError> {
+        Num::equals(cs, &self.raw, &other.raw)
+    }
+
+    /// Whether `self < other`.
+    pub fn less_than<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        other: &Self,
+    ) -> Result<Boolean, SynthesisError> {
+        // Values shifted by 2^63 are ordered as unsigned integers
+        let offset = Num::Constant(u64_to_fe(1 << 63));
+        let a = self.to_num(cs)?.add(cs, &offset)?;
+        let b = other.to_num(cs)?.add(cs, &offset)?;
+        let (_, is_greater) = prepacked_long_comparison(cs, &[b], &[a], &[64])?;
+        Ok(is_greater)
+    }
+}
+
+/// Signed 256-bit integer, whose two's complement is `raw`.
+#[derive(Debug, Clone, Copy)]
+pub struct Int256<E: Engine> {
+    pub raw: UInt256<E>,
+    pub is_negative: Boolean,
+}
+
+impl<E: Engine> Int256<E> {
+    pub fn from_raw<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        raw: UInt256<E>,
+    ) -> Result<Self, SynthesisError> {
+        let top = raw.into_be_bytes(cs)?[0];
+        let is_negative = top.inner.into_bits_le(cs, Some(8))?[7];
+        Ok(Self { raw, is_negative })
+    }
+
+    pub fn from_be_bytes<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        bytes: &[Byte<E>; 32],
+    ) -> Result<Self, SynthesisError> {
+        let raw = UInt256::from_be_bytes_fixed(cs, bytes)?;
+        Self::from_raw(cs, raw)
+    }
+
+    /// Sign extension of `value`, e.g. a Pyth price.
+    pub fn from_int64<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        value: &Int64<E>,
+    ) -> Result<Self, SynthesisError> {
+        let extension = Num::from_boolean_is(value.is_negative);
+        let extension = extension.mul(cs, &Num::Constant(u64_to_fe(0xff)))?;
+        let mut bytes = [Byte::from_num_unconstrained(cs, extension); 32];
+        bytes[24..].copy_from_slice(&num_into_be_bytes(cs, &value.raw, 8)?);
+        let raw = UInt256::from_be_bytes_fixed(cs, &bytes)?;
+        Ok(Self {
+            raw,
+            is_negative: value.is_negative,
+        })
+    }
+
+    /// Absolute value, which is `2^255` for the minimal value.
+    pub fn abs<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<UInt256<E>, SynthesisError> {
+        let zero = UInt256::constant(BigUint::from(0u64));
+        let (complement, _) = zero.sub(cs, &self.raw)?;
+        UInt256::conditionally_select(cs, &self.is_negative, &complement, &self.raw)
+    }
+
+    pub fn equals<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        other: &Self,
+    ) -> Result<Boolean, SynthesisError> {
+        UInt256::equals(cs, &self.raw, &other.raw)
+    }
+
+    /// Whether `self < other`, i.e. `self` is negative if signs differ, and otherwise `self` is
+    /// less as unsigned integers.
+    pub fn less_than<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        other: &Self,
+    ) -> Result<Boolean, SynthesisError> {
+        let (_, is_unsigned_less) = self.raw.sub(cs, &other.raw)?;
+        let is_same_sign = Boolean::xor(cs, &self.is_negative, &other.is_negative)?.not();
+        Boolean::conditionally_select(cs, &is_same_sign, &is_unsigned_less, &self.is_negative)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte,
+        franklin_crypto::bellman::{Field, SynthesisError},
+        testing::Bn256,
+        traits::CSAllocatable,
+        vm::primitives::uint256::UInt256,
+    };
+    use num_bigint::{BigInt, BigUint, Sign};
+
+    use super::{Int256, Int64};
+    use crate::utils::{fr_from_biguint, testing::create_test_constraint_system};
+
+    #[test]
+    fn test_int64() -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let n = cs.n();
+        let values = [-5i64, 3, 0, i64::MIN, i64::MAX, -1];
+        let mut allocated = vec![];
+        for value in values {
+            let bytes = value
+                .to_be_bytes()
+                .map(|b| Byte::from_u8_witness(cs, Some(b)).unwrap());
+            let int = Int64::from_be_bytes(cs, &bytes)?;
+            assert_eq!(int.is_negative.get_value(), Some(value < 0));
+            let abs = BigUint::from(value.unsigned_abs());
+            assert_eq!(
+                int.abs(cs)?.get_value(),
+                Some(fr_from_biguint::<Bn256>(&abs)?)
+            );
+            let mut expected = fr_from_biguint::<Bn256>(&abs)?;
+            if value < 0 {
+                expected.negate();
+            }
+            assert_eq!(int.to_num(cs)?.get_value(), Some(expected));
+            allocated.push(int);
+        }
+        for (a, x) in values.iter().zip(&allocated) {
+            for (b, y) in values.iter().zip(&allocated) {
+                assert_eq!(x.less_than(cs, y)?.get_value(), Some(a < b));
+                assert_eq!(x.equals(cs, y)?.get_value(), Some(a == b));
+            }
+        }
+        println!("Roughly {} gates", cs.n() - n);
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_int256() -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let n = cs.n();
+        let modulus = BigInt::from(1u8) << 256;
+        let values = [
+            BigInt::from(-5),
+            BigInt::from(3),
+            BigInt::from(0),
+            -(BigInt::from(1u8) << 255),
+            (BigInt::from(1u8) << 255) - 1,
+            BigInt::from(-1),
+        ];
+        let mut allocated = vec![];
+        for value in &values {
+            let raw = ((value + &modulus) % &modulus).to_biguint().unwrap();
+            let raw = UInt256::alloc_from_witness(cs, Some(raw))?;
+            let int = Int256::from_raw(cs, raw)?;
+            assert_eq!(
+                int.is_negative.get_value(),
+                Some(value.sign() == Sign::Minus)
+            );
+            let abs = value.magnitude().clone();
+            assert_eq!(int.abs(cs)?.get_value(), Some(abs));
+            allocated.push(int);
+        }
+        for (a, x) in values.iter().zip(&allocated) {
+            for (b, y) in values.iter().zip(&allocated) {
+                assert_eq!(x.less_than(cs, y)?.get_value(), Some(a < b));
+                assert_eq!(x.equals(cs, y)?.get_value(), Some(a == b));
+            }
+        }
+
+        // Sign extension of `i64`
+        for value in [-5i64, 7, i64::MIN] {
+            let int = Int64::alloc(cs, Some(value))?;
+            let extended = Int256::from_int64(cs, &int)?;
+            let expected = ((BigInt::from(value) + &modulus) % &modulus).to_biguint();
+            assert_eq!(extended.raw.get_value(), expected);
+            assert_eq!(extended.is_negative.get_value(), Some(value < 0));
+        }
+        println!("Roughly {} gates", cs.n() - n);
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+};
+
+use super::merkle::MerkleProof;
+
+/// Native sparse Merkle tree, which only stores non-empty nodes.
+#[derive(Debug, Clone)]
+pub struct NativeSparseMerkleTree<const BS: usize> {
+    hash: fn(&[u8]) -> [u8; BS],
+    depth: usize,
+    // Roots of empty subtrees by height
+    empty: Vec<[u8; BS]>,
+    // Nodes by height and index
+    nodes: HashMap<(usize, u64), [u8; BS]>,
+}
+
+impl<const BS: usize> NativeSparseMerkleTree<BS> {
+    pub fn new(hash: fn(&[u8]) -> [u8; BS], depth: usize) -> Self {
+        assert!(depth < 64);
+        let mut empty = vec![[0u8; BS]];
+        for height in 0..depth {
+            empty.push(hash(&[empty[height], empty[height]].concat()));
+        }
+        Self {
+            hash,
+            depth,
+            empty,
+            nodes: HashMap::new(),
+        }
+    }
+
+    fn node(&self, height: usize, index: u64) -> [u8; BS] {
+        self.nodes
+            .get(&(height, index))
+            .copied()
+            .unwrap_or(self.empty[height])
+    }
+
+    pub fn root(&self) -> [u8; BS] {
+        self.node(self.depth, 0)
+    }
+
+    pub fn get(&self, key: u64) -> [u8; BS] {
+        self.node(0, key)
+    }
+
+    /// Siblings of the slot at `key` from the bottom.
+    pub fn siblings(&self, key: u64) -> Vec<[u8; BS]> {
+        (0..self.depth)
+            .map(|height| self.node(height, (key >> height) ^ 1))
+            .collect()
+    }
+
+    /// Set the slot at `key` to `value`, which empties the slot if it's zeros.
+    pub fn insert(&mut self, key: u64, value: [u8; BS]) {
+        assert!(key >> self.depth == 0);
+        let mut current = value;
+        for height in 0..=self.depth {
+            let index = key >> height;
+            if current == self.empty[height] {
+                self.nodes.remove(&(height, index));
+            } else {
+                self.nodes.insert((height, index), current);
+            }
+            if height == self.depth {
+                break;
+            }
+            let sibling = self.node(height, index ^ 1);
+            current = if index & 1 == 1 {
+                (self.hash)(&[sibling, current].concat())
+            } else {
+                (self.hash)(&[current, sibling].concat())
+            };
+        }
+    }
+}
+
+/// Circuit representation of a sparse Merkle tree by its root.
+#[derive(Debug, Clone, Copy)]
+pub struct SparseMerkleTree<E: Engine, const BS: usize, const DEPTH: usize> {
+    pub root: [Byte<E>; BS],
+}
+
+impl<E: Engine, const BS: usize, const DEPTH: usize> SparseMerkleTree<E, BS, DEPTH> {
+    pub fn new(root: [Byte<E>; BS]) -> Self {
+        Self { root }
+    }
+
+    /// Check if the slot at `key` has `value`. Circuit is unsatisfied if `key` has more than
+    /// `DEPTH` bits.
+    pub fn contains<CS, H>(
+        &self,
+        cs: &mut CS,
+        hash: H,
+        key: &Num<E>,
+        value: &[Byte<E>; BS],
+        siblings: [[Byte<E>; BS]; DEPTH],
+    ) -> Result<Boolean, SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+        H: Fn(&mut CS, &[Byte<E>]) -> Result<[Byte<E>; BS], SynthesisError>,
+    {
+        let proof = MerkleProof::from_index(cs, siblings, key)?;
+        proof.check(cs, hash, value, &self.root)
+    }
+
+    /// Check if the slot at `key` is empty.
+    pub fn is_empty_at<CS, H>(
+        &self,
+        cs: &mut CS,
+        hash: H,
+        key: &Num<E>,
+        siblings: [[Byte<E>; BS]; DEPTH],
+    ) -> Result<Boolean, SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+        H: Fn(&mut CS, &[Byte<E>]) -> Result<[Byte<E>; BS], SynthesisError>,
+    {
+        self.contains(cs, hash, key, &[Byte::zero(); BS], siblings)
+    }
+
+    /// Set the slot at `key` from `old_value` to `new_value`, where zeros are empty slots. Return
+    /// whether the slot has `old_value` and the updated tree, which is only meaningful if the check
+    /// passes.
+    pub fn update<CS, H>(
+        &self,
+        cs: &mut CS,
+        hash: H,
+        key: &Num<E>,
+        old_value: &[Byte<E>; BS],
+        new_value: &[Byte<E>; BS],
+        siblings: [[Byte<E>; BS]; DEPTH],
+    ) -> Result<(Boolean, Self), SynthesisError>
+    where
+        CS: ConstraintSystem<E>,
+        H: Fn(&mut CS, &[Byte<E>]) -> Result<[Byte<E>; BS], SynthesisError>,
+    {
+        let proof = MerkleProof::from_index(cs, siblings, key)?;
+        let is_valid = proof.check(cs, &hash, old_value, &self.root)?;
+        let root = proof.root(cs, &hash, new_value)?;
+        Ok((is_valid, Self { root }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte,
+        franklin_crypto::{
+            bellman::{
+                pairing::bn256::Bn256, plonk::better_better_cs::cs::ConstraintSystem,
+                SynthesisError,
+            },
+            plonk::circuit::allocated_num::Num,
+        },
+        utils::u64_to_fe,
+    };
+    use sha3::{Digest, Keccak256};
+
+    use super::{NativeSparseMerkleTree, SparseMerkleTree};
+    use crate::{gadgets::keccak256, utils::testing::create_test_constraint_system};
+
+    const DEPTH: usize = 8;
+
+    fn keccak(bytes: &[u8]) -> [u8; 32] {
+        Keccak256::digest(bytes).into()
+    }
+
+    fn allocate<CS: ConstraintSystem<Bn256>>(cs: &mut CS, bytes: &[u8; 32]) -> [Byte<Bn256>; 32] {
+        bytes.map(|b| Byte::from_u8_witness(cs, Some(b)).unwrap())
+    }
+
+    fn allocate_siblings<CS: ConstraintSystem<Bn256>>(
+        cs: &mut CS,
+        tree: &NativeSparseMerkleTree<32>,
+        key: u64,
+    ) -> [[Byte<Bn256>; 32]; DEPTH] {
+        let siblings: [[u8; 32]; DEPTH] = tree.siblings(key).try_into().unwrap();
+        siblings.map(|sibling| allocate(cs, &sibling))
+    }
+
+    #[test]
+    fn test_sparse_merkle_tree() -> Result<(), SynthesisError> {
+        let mut tree = NativeSparseMerkleTree::new(keccak, DEPTH);
+        let empty_root = tree.root();
+        tree.insert(3, keccak(b"BTC/USD"));
+        tree.insert(200, keccak(b"ETH/USD"));
+        let before = tree.clone();
+        tree.insert(201, keccak(b"ZKL/USD"));
+        tree.insert(201, [0u8; 32]);
+        assert_eq!(tree.root(), before.root());
+        tree.insert(3, [0u8; 32]);
+        tree.insert(200, [0u8; 32]);
+        assert_eq!(tree.root(), empty_root);
+
+        let cs = &mut create_test_constraint_system()?;
+        let smt = SparseMerkleTree::<_, 32, DEPTH>::new(allocate(cs, &before.root()));
+        let key = |k: u64| Num::Constant(u64_to_fe(k));
+        let cases = [
+            (3, keccak(b"BTC/USD"), true),
+            (200, keccak(b"ETH/USD"), true),
+            (200, keccak(b"BTC/USD"), false),
+            (201, [0u8; 32], true),
+        ];
+        for (k, value, expected) in cases {
+            let n = cs.n();
+            let value = allocate(cs, &value);
+            let siblings = allocate_siblings(cs, &before, k);
+            let is_valid = smt.contains(cs, keccak256::digest, &key(k), &value, siblings)?;
+            println!("Roughly {} gates", cs.n() - n);
+            assert_eq!(is_valid.get_value(), Some(expected));
+        }
+        let siblings = allocate_siblings(cs, &before, 3);
+        let is_empty = smt.is_empty_at(cs, keccak256::digest, &key(3), siblings)?;
+        assert_eq!(is_empty.get_value(), Some(false));
+
+        // Insert a new key and then update it
+        let mut after = before.clone();
+        let mut smt = smt;
+        for value in [b"ZKL/USD", b"ZKL/ETH"] {
+            let old_value = allocate(cs, &after.get(201));
+            let new_value = allocate(cs, &keccak(value));
+            let siblings = allocate_siblings(cs, &after, 201);
+            let (is_valid, updated) = smt.update(
+                cs,
+                keccak256::digest,
+                &key(201),
+                &old_value,
+                &new_value,
+                siblings,
+            )?;
+            after.insert(201, keccak(value));
+            assert_eq!(is_valid.get_value(), Some(true));
+            assert_eq!(
+                Byte::get_byte_value_multiple(&updated.root).unwrap(),
+                after.root()
+            );
+            smt = updated;
+        }
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+}
@@ -0,0 +1,181 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    vm::partitioner::smart_and,
+};
+use sha2::{Digest, Sha256};
+
+use super::sha256;
+
+/// Depth of the node at generalized index `gindex`, i.e. `floor(log2(gindex))`.
+pub fn gindex_depth(gindex: u64) -> anyhow::Result<usize> {
+    if gindex == 0 {
+        anyhow::bail!("generalized index must be positive")
+    }
+    Ok(63 - gindex.leading_zeros() as usize)
+}
+
+/// Generalized index of a path through nested objects, i.e. `concat_generalized_indices` of the
+/// consensus specs.
+pub fn concat_gindices(gindices: &[u64]) -> anyhow::Result<u64> {
+    let mut concatenated = 1u64;
+    for gindex in gindices {
+        let depth = gindex_depth(*gindex)?;
+        concatenated = concatenated
+            .checked_shl(depth as u32)
+            .filter(|shifted| shifted >> depth == concatenated)
+            .ok_or_else(|| anyhow::anyhow!("generalized index overflows"))?
+            | (gindex - (1 << depth));
+    }
+    Ok(concatenated)
+}
+
+/// Root of the tree where `leaf` is at `gindex`, whose branch is ordered from the leaf.
+pub fn merkle_root(leaf: &[u8; 32], gindex: u64, branch: &[[u8; 32]]) -> anyhow::Result<[u8; 32]> {
+    let depth = gindex_depth(gindex)?;
+    if branch.len() != depth {
+        anyhow::bail!(
+            "branch of generalized index {} has {} nodes, expect {}",
+            gindex,
+            branch.len(),
+            depth
+        )
+    }
+    let mut node = *leaf;
+    for (i, sibling) in branch.iter().enumerate() {
+        let (left, right) = if (gindex >> i) & 1 == 1 {
+            (sibling, &node)
+        } else {
+            (&node, sibling)
+        };
+        node = Sha256::new_with_prefix(left)
+            .chain_update(right)
+            .finalize()
+            .into();
+    }
+    Ok(node)
+}
+
+/// Verify the proof of `leaf` at `gindex` against `root`, i.e. `is_valid_merkle_branch` of the
+/// consensus specs.
+pub fn verify_proof(
+    root: &[u8; 32],
+    leaf: &[u8; 32],
+    gindex: u64,
+    branch: &[[u8; 32]],
+) -> anyhow::Result<()> {
+    let computed = merkle_root(leaf, gindex, branch)?;
+    if computed != *root {
+        anyhow::bail!(
+            "leaf at generalized index {} is proven to 0x{}, expect 0x{}",
+            gindex,
+            hex::encode(computed),
+            hex::encode(root)
+        )
+    }
+    Ok(())
+}
+
+/// Circuit representation of the branch of an SSZ Merkle proof.
+#[derive(Debug, Clone)]
+pub struct AllocatedSszProof<E: Engine> {
+    pub branch: Vec<[Byte<E>; 32]>,
+}
+
+impl<E: Engine> AllocatedSszProof<E> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        branch: &[[u8; 32]],
+    ) -> Result<Self, SynthesisError> {
+        let branch = branch
+            .iter()
+            .map(|node| {
+                let mut bytes = [Byte::zero(); 32];
+                for (byte, b) in bytes.iter_mut().zip(node.iter()) {
+                    *byte = Byte::from_u8_witness(cs, Some(*b))?;
+                }
+                Ok(bytes)
+            })
+            .collect::<Result<Vec<_>, SynthesisError>>()?;
+        Ok(Self { branch })
+    }
+
+    /// Same as [`verify_proof`]. Return whether `leaf` at `gindex` is proven against `root`.
+    /// Circuit is unsatisfied unless `gindex` is below `2^(depth + 1)`.
+    pub fn verify<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        root: &[Byte<E>; 32],
+        leaf: &[Byte<E>; 32],
+        gindex: &Num<E>,
+    ) -> Result<Boolean, SynthesisError> {
+        let depth = self.branch.len();
+        let bits = gindex.into_bits_le(cs, Some(depth + 1))?;
+        let mut node = *leaf;
+        for (is_right, sibling) in bits.iter().zip(self.branch.iter()) {
+            // The node is the right child if the bit of its depth is set
+            let (mut left, mut right) = (vec![], vec![]);
+            for (n, s) in node.iter().zip(sibling.iter()) {
+                let l = Num::conditionally_select(cs, is_right, &s.inner, &n.inner)?;
+                let r = Num::conditionally_select(cs, is_right, &n.inner, &s.inner)?;
+                left.push(Byte::from_num_unconstrained(cs, l));
+                right.push(Byte::from_num_unconstrained(cs, r));
+            }
+            left.extend(right);
+            node = sha256::digest(cs, &left)?;
+        }
+
+        // The top bit marks the root
+        let mut is_valid = vec![bits[depth]];
+        for (a, b) in node.iter().zip(root.iter()) {
+            is_valid.push(Num::equals(cs, &a.inner, &b.inner)?);
+        }
+        smart_and(cs, &is_valid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte,
+        franklin_crypto::{
+            bellman::{pairing::bn256::Bn256, SynthesisError},
+            plonk::circuit::allocated_num::Num,
+        },
+        utils::u64_to_fe,
+    };
+
+    use super::{concat_gindices, verify_proof, AllocatedSszProof};
+    use crate::utils::testing::{create_test_constraint_system, ssz_proof};
+
+    #[test]
+    fn test_ssz_proof() -> Result<(), SynthesisError> {
+        assert_eq!(concat_gindices(&[2, 3]).unwrap(), 5);
+        assert_eq!(concat_gindices(&[11, 1, 13]).unwrap(), 8 * 11 + 5);
+        assert!(concat_gindices(&[1 << 40, 1 << 40]).is_err());
+
+        let leaves = (0u8..6).map(|i| [i; 32]).collect::<Vec<_>>();
+        let (root, branch) = ssz_proof(&leaves, 5);
+        // 6 leaves are padded to 8, so that leaf 5 is at 8 + 5
+        verify_proof(&root, &leaves[5], 13, &branch).unwrap();
+        assert!(verify_proof(&root, &leaves[5], 12, &branch).is_err());
+        assert!(verify_proof(&root, &leaves[4], 13, &branch).is_err());
+        assert!(verify_proof(&root, &leaves[5], 29, &branch).is_err());
+
+        for (leaf, gindex, expected) in [(5, 13, true), (5, 12, false), (4, 13, false)] {
+            let cs = &mut create_test_constraint_system()?;
+            let root = root.map(|b| Byte::<Bn256>::from_u8_witness(cs, Some(b)).unwrap());
+            let leaf = leaves[leaf].map(|b| Byte::from_u8_witness(cs, Some(b)).unwrap());
+            let gindex = Num::alloc(cs, Some(u64_to_fe(gindex)))?;
+            let proof = AllocatedSszProof::from_witness(cs, &branch)?;
+            let is_valid = proof.verify(cs, &root, &leaf, &gindex)?;
+            assert_eq!(is_valid.get_value(), Some(expected));
+            assert!(cs.is_satisfied());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::boolean::Boolean,
+    },
+    vm::partitioner::smart_and,
+};
+use sha3::{Digest, Keccak256};
+
+use super::{
+    ethereum::{block_header_state_root, BlockHeader},
+    keccak256,
+    mpt::{account_storage_root, circuit_account_storage_root, verify_proof, AllocatedMptProof},
+};
+
+/// Storage of the contract at `address`, proven by the proof of its account against the state
+/// root of an RLP-encoded Ethereum block header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractStorage {
+    pub block_header: Vec<u8>,
+    pub address: [u8; 20],
+    pub account_proof: Vec<Vec<u8>>,
+}
+
+impl ContractStorage {
+    /// Block hash, i.e. keccak256 of the block header.
+    pub fn block_hash(&self) -> [u8; 32] {
+        Keccak256::digest(&self.block_header).into()
+    }
+
+    /// Key of the account in the state trie, i.e. `keccak256(address)`.
+    pub fn account_key(&self) -> [u8; 32] {
+        Keccak256::digest(self.address).into()
+    }
+
+    /// Check the account proof against the state root and return the storage root of the contract.
+    pub fn storage_root(&self) -> anyhow::Result<[u8; 32]> {
+        let state_root = block_header_state_root(&self.block_header)?;
+        let account = verify_proof(&state_root, &self.account_key(), &self.account_proof)?;
+        account_storage_root(&account)
+    }
+}
+
+/// Circuit representation of [`ContractStorage`].
+#[derive(Debug, Clone)]
+pub struct AllocatedContractStorage<E: Engine> {
+    pub block_header: BlockHeader<E>,
+    pub address: [Byte<E>; 20],
+    pub account_proof: AllocatedMptProof<E>,
+}
+
+impl<E: Engine> AllocatedContractStorage<E> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &ContractStorage,
+    ) -> Result<Self, SynthesisError> {
+        let block_header = BlockHeader::from_rlp_witness(cs, &witness.block_header)?;
+        let mut address = [Byte::zero(); 20];
+        for (byte, b) in address.iter_mut().zip(witness.address.iter()) {
+            *byte = Byte::from_u8_witness(cs, Some(*b))?;
+        }
+        let account_proof =
+            AllocatedMptProof::from_witness(cs, &witness.account_key(), &witness.account_proof)?;
+        Ok(Self {
+            block_header,
+            address,
+            account_proof,
+        })
+    }
+
+    /// Same as [`ContractStorage::storage_root`]. Return whether the account proof is valid, the
+    /// block hash and the storage root, which are only meaningful if the check passes.
+    pub fn verify<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<(Boolean, [Byte<E>; 32], [Byte<E>; 32]), SynthesisError> {
+        let block_hash = self.block_header.hash(cs)?;
+        let state_root = self.block_header.state_root();
+        let account_key = keccak256::digest(cs, &self.address)?;
+        let (is_proven, account) = self.account_proof.verify(cs, &state_root, &account_key)?;
+        let (is_account_valid, storage_root) = circuit_account_storage_root(cs, &account)?;
+        let is_valid = smart_and(cs, &[is_proven, is_account_valid])?;
+        Ok((is_valid, block_hash, storage_root))
+    }
+}
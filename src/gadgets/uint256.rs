@@ -0,0 +1,223 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{ff::PrimeField, Engine};
+use advanced_circuit_component::{
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, Field, SynthesisError},
+        plonk::circuit::{
+            allocated_num::Num, boolean::Boolean, linear_combination::LinearCombination,
+        },
+    },
+    traits::CSAllocatable,
+    utils::u64_to_fe,
+    vm::primitives::uint256::UInt256,
+};
+use num_bigint::{BigInt, BigUint};
+
+use super::ecdsa::{uint256_inner, CHUNK_BITLEN};
+use crate::utils::fr_from_biguint;
+
+// Offset of carries between limbs, whose magnitudes are below 2^67
+const CARRY_OFFSET_BITS: usize = 68;
+
+fn limbs(value: &BigUint) -> [u64; 4] {
+    let mut limbs = [0u64; 4];
+    for (limb, digit) in limbs.iter_mut().zip(value.to_u64_digits()) {
+        *limb = digit;
+    }
+    limbs
+}
+
+/// Divide `n` by `d`, returning `(n / d, n % d)`. Circuit is unsatisfied if `d` is zero.
+pub fn div_rem<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    n: &UInt256<E>,
+    d: &UInt256<E>,
+) -> Result<(UInt256<E>, UInt256<E>), SynthesisError> {
+    mul_div_rem(cs, n, &UInt256::constant(BigUint::from(1u64)), d)
+}
+
+/// Divide `a * b` by `d` without overflowing the product, returning `(a * b / d, a * b % d)`.
+/// Circuit is unsatisfied if `d` is zero or the quotient overflows 256 bits.
+///
+/// It is proved that `a * b = quotient * d + remainder` over 64-bit limbs with signed carries,
+/// where `remainder < d`. Products of limbs and carries are far below the field modulus, so the
+/// equation holds over integers as well.
+pub fn mul_div_rem<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    a: &UInt256<E>,
+    b: &UInt256<E>,
+    d: &UInt256<E>,
+) -> Result<(UInt256<E>, UInt256<E>), SynthesisError> {
+    let values = [a, b, d].map(|v| v.get_value());
+    let witness = match values {
+        [Some(a), Some(b), Some(d)] => {
+            let product = &a * &b;
+            let (q, r) = if d == BigUint::from(0u64) {
+                (BigUint::from(0u64), BigUint::from(0u64))
+            } else {
+                // Truncate overflowing quotients, which fail the check anyway
+                let max = (BigUint::from(1u64) << 256) - 1u64;
+                (&product / &d & max, &product % &d)
+            };
+            Some((q, r, a, b, d))
+        }
+        _ => None,
+    };
+    let quotient = UInt256::alloc_from_witness(cs, witness.as_ref().map(|w| w.0.clone()))?;
+    let remainder = UInt256::alloc_from_witness(cs, witness.as_ref().map(|w| w.1.clone()))?;
+    let witness = witness.map(|(q, r, a, b, d)| [q, r, a, b, d].map(|v| limbs(&v)));
+
+    let q = uint256_inner(cs, &quotient)?.map(|limb| limb.inner);
+    let r = uint256_inner(cs, &remainder)?.map(|limb| limb.inner);
+    let a_limbs = uint256_inner(cs, a)?.map(|limb| limb.inner);
+    let b_limbs = uint256_inner(cs, b)?.map(|limb| limb.inner);
+    let d_limbs = uint256_inner(cs, d)?.map(|limb| limb.inner);
+    let offset = BigUint::from(1u64) << CARRY_OFFSET_BITS;
+    let offset = Num::Constant(fr_from_biguint::<E>(&offset)?);
+    let mut minus_one = E::Fr::one();
+    minus_one.negate();
+    // -2^64
+    let mut minus_base = u64_to_fe::<E::Fr>(1 << 32);
+    minus_base.square();
+    minus_base.negate();
+
+    let mut carry = Num::zero();
+    let mut carry_value = Some(BigInt::from(0));
+    for k in 0..7 {
+        // `sum(a[i] * b[k - i]) - sum(q[i] * d[k - i]) - r[k] + carry` of limb k, whose low 64
+        // bits are zeros
+        let mut lc = LinearCombination::zero();
+        for i in k.saturating_sub(3)..=k.min(3) {
+            let product = a_limbs[i].mul(cs, &b_limbs[k - i])?;
+            lc.add_assign_number_with_coeff(&product, E::Fr::one());
+            let product = q[i].mul(cs, &d_limbs[k - i])?;
+            lc.add_assign_number_with_coeff(&product, minus_one);
+        }
+        if k < 4 {
+            lc.add_assign_number_with_coeff(&r[k], minus_one);
+        }
+        lc.add_assign_number_with_coeff(&carry, E::Fr::one());
+        if k == 6 {
+            lc.into_num(cs)?.enforce_equal(cs, &Num::zero())?;
+            break;
+        }
+
+        carry_value = carry_value
+            .zip(witness.as_ref())
+            .map(|(carry, [q, r, a, b, d])| {
+                let mut sum = carry;
+                for i in k.saturating_sub(3)..=k.min(3) {
+                    sum += BigInt::from(a[i]) * BigInt::from(b[k - i]);
+                    sum -= BigInt::from(q[i]) * BigInt::from(d[k - i]);
+                }
+                if k < 4 {
+                    sum -= BigInt::from(r[k]);
+                }
+                sum >> CHUNK_BITLEN
+            });
+        let shifted = carry_value
+            .as_ref()
+            .map(|carry| {
+                let shifted = carry + (BigInt::from(1u64) << CARRY_OFFSET_BITS);
+                fr_from_biguint::<E>(&shifted.to_biguint().unwrap_or_default())
+            })
+            .transpose()?;
+        let shifted = Num::alloc(cs, shifted)?;
+        shifted.into_bits_le(cs, Some(CARRY_OFFSET_BITS + 1))?;
+        carry = shifted.sub(cs, &offset)?;
+        lc.add_assign_number_with_coeff(&carry, minus_base);
+        lc.into_num(cs)?.enforce_equal(cs, &Num::zero())?;
+    }
+
+    let (_, is_remainder_less) = remainder.sub(cs, d)?;
+    Boolean::enforce_equal(cs, &is_remainder_less, &Boolean::constant(true))?;
+    Ok((quotient, remainder))
+}
+
+/// Field element of `value`. Circuit is unsatisfied if `value` has more bits than the capacity of
+/// the field, where [`UInt256::to_num_unchecked`] wraps silently.
+pub fn to_num_checked<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    value: &UInt256<E>,
+) -> Result<Num<E>, SynthesisError> {
+    let top = uint256_inner(cs, value)?[3].inner;
+    let capacity = <E::Fr as PrimeField>::CAPACITY as usize;
+    top.into_bits_le(cs, Some(capacity - 3 * CHUNK_BITLEN))?;
+    value.to_num_unchecked(cs)
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::bellman::SynthesisError, testing::Bn256, traits::CSAllocatable,
+        vm::primitives::uint256::UInt256,
+    };
+    use num_bigint::BigUint;
+
+    use super::{div_rem, mul_div_rem, to_num_checked};
+    use crate::utils::{fr_from_biguint, testing::create_test_constraint_system};
+
+    #[test]
+    fn test_div_rem() -> Result<(), SynthesisError> {
+        let max = (BigUint::from(1u64) << 256) - 1u64;
+        let cases = [
+            (BigUint::from(100u64), BigUint::from(7u64)),
+            (BigUint::from(5u64), BigUint::from(1u64) << 200),
+            (max.clone(), BigUint::from(3u64)),
+            (max.clone(), max.clone()),
+            (max.clone(), (BigUint::from(1u64) << 129) + 12345u64),
+            (BigUint::from(0u64), BigUint::from(9u64)),
+        ];
+        for (n, d) in cases {
+            let cs = &mut create_test_constraint_system()?;
+            let allocated_n = UInt256::alloc_from_witness(cs, Some(n.clone()))?;
+            let allocated_d = UInt256::alloc_from_witness(cs, Some(d.clone()))?;
+            let n_gates = cs.n();
+            let (q, r) = div_rem(cs, &allocated_n, &allocated_d)?;
+            println!("Roughly {} gates", cs.n() - n_gates);
+            assert_eq!(q.get_value(), Some(&n / &d));
+            assert_eq!(r.get_value(), Some(&n % &d));
+            assert!(cs.is_satisfied());
+        }
+
+        // Quotients of full products
+        let (a, b, d) = (max.clone(), max.clone() - 5u64, max.clone());
+        let cs = &mut create_test_constraint_system()?;
+        let allocated =
+            [&a, &b, &d].map(|v| UInt256::alloc_from_witness(cs, Some(v.clone())).unwrap());
+        let (q, r) = mul_div_rem(cs, &allocated[0], &allocated[1], &allocated[2])?;
+        assert_eq!(q.get_value(), Some(&a * &b / &d));
+        assert_eq!(r.get_value(), Some(&a * &b % &d));
+        assert!(cs.is_satisfied());
+        // The quotient overflows
+        let cs = &mut create_test_constraint_system()?;
+        let allocated = [&a, &b, &BigUint::from(2u64)]
+            .map(|v| UInt256::alloc_from_witness(cs, Some(v.clone())).unwrap());
+        mul_div_rem(cs, &allocated[0], &allocated[1], &allocated[2])?;
+        assert!(!cs.is_satisfied());
+
+        // Zero divisor
+        let cs = &mut create_test_constraint_system()?;
+        let n = UInt256::alloc_from_witness(cs, Some(BigUint::from(1u64)))?;
+        let d = UInt256::alloc_from_witness(cs, Some(BigUint::from(0u64)))?;
+        div_rem(cs, &n, &d)?;
+        assert!(!cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_num_checked() -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let value = (BigUint::from(1u64) << 253) - 1u64;
+        let allocated = UInt256::alloc_from_witness(cs, Some(value.clone()))?;
+        let num = to_num_checked(cs, &allocated)?;
+        assert_eq!(num.get_value(), Some(fr_from_biguint::<Bn256>(&value)?));
+        assert!(cs.is_satisfied());
+
+        // Values beyond the capacity would wrap the field
+        let cs = &mut create_test_constraint_system()?;
+        let allocated = UInt256::alloc_from_witness(cs, Some(BigUint::from(1u64) << 253))?;
+        to_num_checked(cs, &allocated)?;
+        assert!(!cs.is_satisfied());
+        Ok(())
+    }
+}
@@ -1,8 +1,27 @@
 pub use advanced_circuit_component::franklin_crypto;
 pub use pythnet_sdk;
 
+pub mod api3;
+pub mod avs;
+pub mod band;
+pub mod binance;
+pub mod chainlink;
+pub mod chronicle;
+pub mod coinbase;
+pub mod consensus;
+pub mod decimals;
+pub mod dia;
+pub mod eip712;
 pub mod gadgets;
+pub mod oracle;
 pub mod pyth;
 pub mod redstone;
+pub mod registry;
+pub mod stork;
+pub mod supra;
+pub mod switchboard;
+pub mod tellor;
+pub mod uniswap;
 pub mod utils;
 pub mod witness;
+pub mod zklink;
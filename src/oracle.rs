@@ -0,0 +1,165 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{ff::Field, Engine};
+use advanced_circuit_component::franklin_crypto::{
+    bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+    plonk::circuit::{allocated_num::Num, boolean::Boolean},
+};
+
+use crate::{
+    gadgets::{
+        poseidon::{circuit_poseidon_hash, poseidon_hash},
+        rescue::circuit_rescue_hash,
+    },
+    utils,
+    witness::{OracleOutputData, PublicInputData},
+};
+
+/// Provider of signed prices whose outputs are summarized as [`PublicInputData`], so that
+/// downstream circuits are written generically over the oracle source.
+///
+/// Implementors check the witness natively when built, and [`Self::verify`] must reproduce
+/// [`Self::outputs`] in circuit.
+pub trait SignedPriceOracle<E: Engine> {
+    /// Signed prices to verify.
+    type Witness;
+    /// Circuit representation of [`Self::Witness`].
+    type Circuit;
+
+    fn witness(&self) -> &Self::Witness;
+
+    fn alloc<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<Self::Circuit, SynthesisError>;
+
+    /// Verify the allocated prices. Return whether they are valid and the outputs, which are only
+    /// meaningful if the check passes.
+    fn verify<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        circuit: &Self::Circuit,
+    ) -> Result<(Boolean, OracleOutputData<E>), SynthesisError>;
+
+    /// Outputs computed natively from the witness.
+    fn outputs(&self) -> &PublicInputData<E>;
+}
+
+/// Commitment of outputs, i.e. `poseidon(guardian set hash, prices commitment, earliest publish
+/// time, number of prices, prices commitment base sum)`, which is the public input of oracle
+/// circuits.
+pub fn outputs_commitment<E: Engine>(outputs: &PublicInputData<E>) -> E::Fr {
+    poseidon_hash::<E>(&[
+        outputs.guardian_set_hash,
+        outputs.prices_summarize.commitment,
+        outputs.earliest_publish_time,
+        outputs.prices_summarize.num,
+        outputs.prices_summarize.commitment_base_sum,
+    ])
+}
+
+/// Same as [`outputs_commitment`].
+pub fn circuit_outputs_commitment<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    outputs: &OracleOutputData<E>,
+) -> Result<Num<E>, SynthesisError> {
+    circuit_poseidon_hash(
+        cs,
+        &[
+            outputs.guardian_set_hash,
+            outputs.prices_summarize.commitment,
+            outputs.earliest_publish_time,
+            outputs.prices_summarize.num,
+            outputs.prices_summarize.commitment_base_sum,
+        ],
+    )
+}
+
+/// Summarize commitments of prices in each batch as `(sum of i * commitment_i, number of
+/// batches, sum of commitment_i)`.
+pub fn circuit_summarize_prices<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    prices_commitments: &[Num<E>],
+) -> Result<(Num<E>, Num<E>, Num<E>), SynthesisError> {
+    let mut prices_commitment_base_sum = Num::zero();
+    let mut prices_commitment = Num::zero();
+    let mut coef = E::Fr::zero();
+    for commitment in prices_commitments {
+        prices_commitment_base_sum = prices_commitment_base_sum.add(cs, commitment)?;
+        let x = commitment.mul(cs, &Num::Constant(coef))?;
+        prices_commitment = prices_commitment.add(cs, &x)?;
+        coef.add_assign(&E::Fr::one());
+    }
+    Ok((
+        prices_commitment,
+        Num::Constant(coef),
+        prices_commitment_base_sum,
+    ))
+}
+
+/// Synthesize the circuit of `oracle`: verify its prices and inputize the commitment of outputs,
+/// which must equal the one computed natively.
+pub fn synthesize<E: Engine, CS: ConstraintSystem<E>, O: SignedPriceOracle<E>>(
+    cs: &mut CS,
+    oracle: &O,
+) -> Result<(), SynthesisError> {
+    utils::add_bitwise_logic_and_range_table(cs)?;
+    let temp_variable = Num::alloc(cs, Some(E::Fr::one()))?;
+    circuit_rescue_hash(cs, &[temp_variable])?; // Just to standardize the proof format
+
+    let circuit = oracle.alloc(cs)?;
+    let (is_valid, outputs) = oracle.verify(cs, &circuit)?;
+    Boolean::enforce_equal(cs, &is_valid, &Boolean::constant(true))?;
+    let commitment = circuit_outputs_commitment(cs, &outputs)?;
+
+    let expected_commitment = Num::alloc(cs, Some(outputs_commitment(oracle.outputs())))?;
+    expected_commitment.enforce_equal(cs, &commitment)?;
+    expected_commitment.get_variable().inputize(cs)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::franklin_crypto::bellman::{
+        pairing::{bn256::Bn256, Engine},
+        SynthesisError,
+    };
+
+    use super::{circuit_outputs_commitment, outputs_commitment, SignedPriceOracle};
+    use crate::{
+        redstone::{
+            witness::{DataPackage, DataPoint},
+            PriceOracle,
+        },
+        utils::{signer::RedstoneSigner, testing::create_test_constraint_system},
+    };
+
+    fn verify_generically<O: SignedPriceOracle<Bn256>>(
+        oracle: &O,
+    ) -> Result<(bool, <Bn256 as Engine>::Fr), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let circuit = oracle.alloc(cs)?;
+        let (is_valid, outputs) = oracle.verify(cs, &circuit)?;
+        let commitment = circuit_outputs_commitment(cs, &outputs)?;
+        assert!(cs.is_satisfied());
+        Ok((
+            is_valid.get_value().unwrap(),
+            commitment.get_value().unwrap(),
+        ))
+    }
+
+    #[test]
+    fn test_signed_price_oracle() -> anyhow::Result<()> {
+        let signer = RedstoneSigner::new(0x11);
+        let signed_prices_batch = [1705311690000, 1705311700000]
+            .into_iter()
+            .map(|timestamp| {
+                let data_package = DataPackage::new(vec![DataPoint::new("ETH", "2500")], timestamp);
+                [[signer.sign_witness(data_package)]]
+            })
+            .collect();
+        let oracle = PriceOracle::<Bn256, 1, 1>::new(signed_prices_batch, [signer.address()])?;
+        assert_eq!(outputs_commitment(oracle.outputs()), oracle.commitment);
+        assert_eq!(oracle.witness().len(), 2);
+
+        let (is_valid, commitment) = verify_generically(&oracle)?;
+        assert!(is_valid);
+        assert_eq!(commitment, oracle.commitment);
+        Ok(())
+    }
+}
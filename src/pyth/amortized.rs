@@ -0,0 +1,393 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{ff::Field, Engine};
+use advanced_circuit_component::franklin_crypto::{
+    bellman::{
+        plonk::better_better_cs::{
+            cs::{Circuit, ConstraintSystem, Gate, GateInternal},
+            gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
+        },
+        SynthesisError,
+    },
+    plonk::circuit::{allocated_num::Num, boolean::Boolean, custom_rescue_gate::Rescue5CustomGate},
+};
+use advanced_circuit_component::{utils::u64_to_fe, vm::partitioner::smart_and};
+use num_bigint::BigUint;
+use pythnet_sdk::{
+    messages::Message,
+    wire::{from_slice, v1::MerklePriceUpdate},
+};
+
+use crate::{
+    gadgets::{
+        ethereum::Address,
+        keccak160::{self, native_merkle_root},
+        poseidon::{circuit_poseidon_hash, poseidon_hash},
+        rescue::circuit_rescue_hash,
+        uint256::to_num_checked,
+    },
+    redstone::witness::guardian_set_hash,
+    utils::{self, fr_from_biguint, num_from_be_bytes},
+};
+
+use super::{
+    guardian_set::guardian_set_rotation_hash,
+    normalize::normalize_price,
+    params::{PYTHNET_EMITTER_ADDRESS, PYTHNET_EMITTER_CHAIN},
+    price::PriceUpdate,
+    quorum::{check_rotating_vaa_quorum, check_vaa_quorum},
+    update::{
+        circuit_price_set_commitment, price_set_commitment, verify_price_update,
+        verify_rotating_vaa_root, verify_vaa_root,
+    },
+    wormhole::ParsedVaa,
+};
+
+// Decimals of prices committed by the circuit, which is the same as RedStone
+const TARGET_DECIMALS: usize = 18;
+
+/// Circuit proving any number of pyth price updates against one VAA, so that the guardian quorum
+/// check is done once and amortized across feeds, each of which only costs a merkle proof.
+///
+/// The number of price updates is fixed when the circuit is built. The only public input is the
+/// commitment `poseidon(guardian set hash || (feed id, price, publish time) of each update)`, where
+/// feed ids are truncated to the first 15 bytes and prices are normalized to 18 decimals.
+///
+/// If `output_commitment` is set, the commitment is absorbed with every verified price by
+/// [`price_set_commitment`], which is the only public input instead.
+///
+/// If built by [`Self::new_with_rotation`] with guardian set `N` and the next set, the VAA may be
+/// signed by either set, so that proofs keep being generated while guardians rotate. The guardian
+/// set hash of the commitment is replaced by [`guardian_set_rotation_hash`].
+#[derive(Debug, Clone)]
+pub struct AmortizedPriceOracle<E: Engine> {
+    pub vaa: Vec<u8>,
+    pub num_signatures: usize,
+    pub price_updates: Vec<MerklePriceUpdate>,
+    pub guardian_set: Vec<[u8; 20]>,
+    pub guardian_set_rotation: Option<(u32, Vec<[u8; 20]>)>,
+    pub commitment: E::Fr,
+    pub output_commitment: bool,
+}
+
+impl<E: Engine> AmortizedPriceOracle<E> {
+    pub fn new(
+        vaa: Vec<u8>,
+        num_signatures: usize,
+        price_updates: Vec<MerklePriceUpdate>,
+        guardian_set: Vec<[u8; 20]>,
+    ) -> Result<Self, anyhow::Error> {
+        Self::build(
+            vaa,
+            num_signatures,
+            price_updates,
+            guardian_set,
+            None,
+            false,
+        )
+    }
+
+    /// Same as [`Self::new`], but accept VAAs signed by either `guardian_set` at
+    /// `guardian_set_index` or `next_guardian_set` at the next index during a guardian rotation.
+    pub fn new_with_rotation(
+        vaa: Vec<u8>,
+        num_signatures: usize,
+        price_updates: Vec<MerklePriceUpdate>,
+        guardian_set_index: u32,
+        guardian_set: Vec<[u8; 20]>,
+        next_guardian_set: Vec<[u8; 20]>,
+    ) -> Result<Self, anyhow::Error> {
+        Self::build(
+            vaa,
+            num_signatures,
+            price_updates,
+            guardian_set,
+            Some((guardian_set_index, next_guardian_set)),
+            false,
+        )
+    }
+
+    /// Expose every verified `(feed_id, price, exponent, publish_time)` through one output
+    /// commitment, which consumer contracts recompute from the prices they are given.
+    pub fn with_output_commitment(self) -> Result<Self, anyhow::Error> {
+        Self::build(
+            self.vaa,
+            self.num_signatures,
+            self.price_updates,
+            self.guardian_set,
+            self.guardian_set_rotation,
+            true,
+        )
+    }
+
+    fn build(
+        vaa: Vec<u8>,
+        num_signatures: usize,
+        price_updates: Vec<MerklePriceUpdate>,
+        guardian_set: Vec<[u8; 20]>,
+        guardian_set_rotation: Option<(u32, Vec<[u8; 20]>)>,
+        output_commitment: bool,
+    ) -> Result<Self, anyhow::Error> {
+        if price_updates.is_empty() {
+            anyhow::bail!("no price update to prove")
+        }
+        let (header, body, guardian_commitment) = match &guardian_set_rotation {
+            Some((index, next_guardian_set)) => {
+                let next_index = index
+                    .checked_add(1)
+                    .ok_or_else(|| anyhow::anyhow!("guardian set index {} overflows", index))?;
+                let guardian_sets = [
+                    (*index, guardian_set.clone()),
+                    (next_index, next_guardian_set.clone()),
+                ];
+                let (_, header, body) = check_rotating_vaa_quorum(&vaa, &guardian_sets)?;
+                let commitment =
+                    guardian_set_rotation_hash::<E>(*index, &guardian_set, next_guardian_set)?;
+                (header, body, commitment)
+            }
+            None => {
+                let (header, body) = check_vaa_quorum(&vaa, &guardian_set)?;
+                (header, body, guardian_set_hash::<E>(&guardian_set)?)
+            }
+        };
+        if header.signatures.len() != num_signatures {
+            anyhow::bail!(
+                "expect {} signatures, got {}",
+                num_signatures,
+                header.signatures.len()
+            )
+        }
+        if u16::from(body.emitter_chain) != PYTHNET_EMITTER_CHAIN
+            || body.emitter_address.0 != *PYTHNET_EMITTER_ADDRESS
+        {
+            anyhow::bail!("VAA is not emitted by pyth accumulator")
+        }
+        // Merkle root is the last field of the payload
+        let payload: &[u8] = body.payload.as_ref();
+        let root = &payload[payload.len() - keccak160::WIDTH_HASH_BYTES..];
+
+        let mut input = vec![guardian_commitment];
+        let mut price_feeds = vec![];
+        for price_update in price_updates.iter() {
+            let message: Vec<u8> = price_update.message.clone().into();
+            let path = price_update
+                .proof
+                .to_bytes()
+                .chunks_exact(keccak160::WIDTH_HASH_BYTES)
+                .map(|chunk| chunk.try_into().unwrap())
+                .collect::<Vec<_>>();
+            if native_merkle_root(&message, &path) != root {
+                anyhow::bail!("price update is not included in merkle root of VAA")
+            }
+            let price_feed = if let Message::PriceFeedMessage(price_feed) =
+                from_slice::<byteorder::BE, Message>(&message)?
+            {
+                price_feed
+            } else {
+                anyhow::bail!("invalid price feed message")
+            };
+            let feed_id = BigUint::from_bytes_be(&price_feed.feed_id[..15]);
+            let price = normalize_price(price_feed.price, price_feed.exponent, TARGET_DECIMALS)?;
+            input.push(fr_from_biguint::<E>(&feed_id)?);
+            input.push(fr_from_biguint::<E>(&price)?);
+            input.push(fr_from_biguint::<E>(&BigUint::from(
+                price_feed.publish_time as u64,
+            ))?);
+            price_feeds.push(price_feed);
+        }
+        let mut commitment = poseidon_hash::<E>(&input);
+        if output_commitment {
+            commitment = price_set_commitment::<E>(&price_feeds, &[commitment])?;
+        }
+        Ok(Self {
+            vaa,
+            num_signatures,
+            price_updates,
+            guardian_set,
+            guardian_set_rotation,
+            commitment,
+            output_commitment,
+        })
+    }
+}
+
+impl<E: Engine> Circuit<E> for AmortizedPriceOracle<E> {
+    type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
+
+    fn synthesize<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<(), SynthesisError> {
+        utils::add_bitwise_logic_and_range_table(cs)?;
+        let temp_variable = Num::alloc(cs, Some(E::Fr::one()))?;
+        circuit_rescue_hash(cs, &[temp_variable])?; // Just to standardize the proof format
+
+        let guardian_set = self
+            .guardian_set
+            .iter()
+            .map(|g| Address::from_address_witness(cs, g))
+            .collect::<Result<Vec<_>, _>>()?;
+        let vaa = ParsedVaa::from_bytes_witness(cs, &self.vaa, self.num_signatures)?;
+        let guardian_nums = guardian_set
+            .iter()
+            .map(|g| g.inner().to_num_unchecked(cs))
+            .collect::<Result<Vec<_>, _>>()?;
+        let guardian_set_hash = circuit_poseidon_hash(cs, &guardian_nums)?;
+        let (is_root_valid, root, guardian_commitment) = match &self.guardian_set_rotation {
+            Some((index, next_guardian_set)) => {
+                let index = Num::alloc(cs, Some(u64_to_fe(*index as u64)))?;
+                let next_index = index.add(cs, &Num::Constant(E::Fr::one()))?;
+                let next_guardian_set = next_guardian_set
+                    .iter()
+                    .map(|g| Address::from_address_witness(cs, g))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let next_guardian_nums = next_guardian_set
+                    .iter()
+                    .map(|g| g.inner().to_num_unchecked(cs))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let next_guardian_set_hash = circuit_poseidon_hash(cs, &next_guardian_nums)?;
+                let guardian_sets = [(index, guardian_set), (next_index, next_guardian_set)];
+                let (is_root_valid, root, _) = verify_rotating_vaa_root(cs, &vaa, &guardian_sets)?;
+                let commitment =
+                    circuit_poseidon_hash(cs, &[index, guardian_set_hash, next_guardian_set_hash])?;
+                (is_root_valid, root, commitment)
+            }
+            None => {
+                let (is_root_valid, root) = verify_vaa_root(cs, &vaa, &guardian_set)?;
+                (is_root_valid, root, guardian_set_hash)
+            }
+        };
+
+        let mut input = vec![guardian_commitment];
+        let mut is_valid = vec![is_root_valid];
+        let mut prices = vec![];
+        for price_update in self.price_updates.iter() {
+            let price_update =
+                PriceUpdate::<E>::from_price_update_witness(cs, price_update.clone())?;
+            let (is_update_valid, price) = verify_price_update(cs, &root, &price_update, None)?;
+            is_valid.push(is_update_valid);
+            input.push(num_from_be_bytes(cs, &price.feed_id[..15])?);
+            let normalized_price = price.normalized_price(cs, TARGET_DECIMALS)?;
+            input.push(to_num_checked(cs, &normalized_price)?);
+            input.push(price.publish_time);
+            prices.push(price);
+        }
+        let is_valid = smart_and(cs, &is_valid)?;
+        Boolean::enforce_equal(cs, &is_valid, &Boolean::constant(true))?;
+
+        let mut commitment = circuit_poseidon_hash(cs, &input)?;
+        if self.output_commitment {
+            commitment = circuit_price_set_commitment(cs, &prices, &[commitment])?;
+        }
+        let expected_commitment = Num::alloc(cs, Some(self.commitment))?;
+        expected_commitment.enforce_equal(cs, &commitment)?;
+        expected_commitment.get_variable().inputize(cs)?;
+        Ok(())
+    }
+
+    fn declare_used_gates() -> Result<Vec<Box<dyn GateInternal<E>>>, SynthesisError> {
+        Ok(vec![
+            Self::MainGate::default().into_internal(),
+            Rescue5CustomGate.into_internal(), // Just to standardize the proof format
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::bellman::{pairing::ff::Field, plonk::better_better_cs::cs::Circuit},
+        testing::{create_test_artifacts_with_optimized_gate, Bn256},
+    };
+    use pythnet_sdk::{
+        messages::Message,
+        wire::{from_slice, v1::MerklePriceUpdate},
+    };
+
+    use super::AmortizedPriceOracle;
+    use crate::{
+        pyth::{price_set_commitment, GUARDIAN_SET},
+        utils::testing::{pyth_price_update, wormhole_vaa},
+    };
+
+    #[test]
+    fn test_amortized_price_oracle() -> anyhow::Result<()> {
+        let update = || {
+            let data = hex::decode(pyth_price_update()).unwrap();
+            from_slice::<byteorder::BE, MerklePriceUpdate>(&data).unwrap()
+        };
+        let vaa = hex::decode(wormhole_vaa()).unwrap();
+        let mut gates = vec![];
+        for num_updates in [1, 2] {
+            let circuit = AmortizedPriceOracle::<Bn256>::new(
+                vaa.clone(),
+                13,
+                vec![update(); num_updates],
+                GUARDIAN_SET.to_vec(),
+            )?;
+            let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+            circuit.synthesize(&mut cs)?;
+            assert!(cs.is_satisfied());
+            gates.push(cs.n());
+
+            let mut circuit = circuit;
+            circuit.commitment.add_assign(&Field::one());
+            let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+            circuit.synthesize(&mut cs)?;
+            assert!(!cs.is_satisfied());
+        }
+        // One more price update costs far less than verifying the VAA
+        assert!(gates[1] - gates[0] < gates[0] / 2);
+
+        let circuit = AmortizedPriceOracle::<Bn256>::new(
+            vaa.clone(),
+            13,
+            vec![update()],
+            GUARDIAN_SET.to_vec(),
+        )?;
+        let message: Vec<u8> = update().message.into();
+        let price_feed = match from_slice::<byteorder::BE, Message>(&message)? {
+            Message::PriceFeedMessage(price_feed) => price_feed,
+            _ => unreachable!(),
+        };
+        let expected = price_set_commitment::<Bn256>(&[price_feed], &[circuit.commitment])?;
+        let circuit = circuit.with_output_commitment()?;
+        assert_eq!(circuit.commitment, expected);
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+
+        // The VAA is signed by guardian set 3, which is accepted as either set of a rotation
+        let retired_set = GUARDIAN_SET[1..].to_vec();
+        for (index, guardian_set, next_guardian_set) in [
+            (3, GUARDIAN_SET.to_vec(), retired_set.clone()),
+            (2, retired_set.clone(), GUARDIAN_SET.to_vec()),
+        ] {
+            let circuit = AmortizedPriceOracle::<Bn256>::new_with_rotation(
+                vaa.clone(),
+                13,
+                vec![update()],
+                index,
+                guardian_set,
+                next_guardian_set,
+            )?
+            .with_output_commitment()?;
+            let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+            circuit.synthesize(&mut cs)?;
+            assert!(cs.is_satisfied());
+        }
+        assert!(AmortizedPriceOracle::<Bn256>::new_with_rotation(
+            vaa.clone(),
+            13,
+            vec![update()],
+            4,
+            GUARDIAN_SET.to_vec(),
+            GUARDIAN_SET.to_vec()
+        )
+        .is_err());
+
+        assert!(AmortizedPriceOracle::<Bn256>::new(
+            vaa,
+            13,
+            vec![update()],
+            GUARDIAN_SET[1..].to_vec()
+        )
+        .is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,289 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    utils::u64_to_fe,
+};
+
+use crate::{
+    gadgets::ethereum::Address,
+    utils::{new_synthesis_error, num_from_be_bytes},
+};
+
+use super::{
+    price::{PriceFeedMessage, SignedNum},
+    quorum::check_guardian_quorum,
+    wormhole::ParsedVaa,
+};
+
+const P2W_MAGIC: &[u8; 4] = b"P2WH";
+const P2W_MAJOR_VERSION: u16 = 3;
+// Header size following the size field, which only contains payload id for now
+const P2W_HEADER_SIZE: u16 = 1;
+const P2W_PAYLOAD_ID_BATCH: u8 = 2;
+const LEN_P2W_HEADER: usize = 4 + 2 + 2 + 2 + P2W_HEADER_SIZE as usize;
+const LEN_BATCH_HEADER: usize = 2 + 2;
+
+const LEN_PRODUCT_ID: usize = 32;
+const LEN_PRICE_ID: usize = 32;
+const LEN_PRICE: usize = 8;
+const LEN_CONF: usize = 8;
+const LEN_EXPONENT: usize = 4;
+const LEN_EMA_PRICE: usize = 8;
+const LEN_EMA_CONF: usize = 8;
+const LEN_STATUS: usize = 1;
+const LEN_NUM_PUBLISHERS: usize = 4;
+const LEN_MAX_NUM_PUBLISHERS: usize = 4;
+const LEN_ATTESTATION_TIME: usize = 8;
+const LEN_PUBLISH_TIME: usize = 8;
+const LEN_PREV_PUBLISH_TIME: usize = 8;
+const LEN_PREV_PRICE: usize = 8;
+const LEN_PREV_CONF: usize = 8;
+pub const LEN_PRICE_ATTESTATION: usize = LEN_PRODUCT_ID
+    + LEN_PRICE_ID
+    + LEN_PRICE
+    + LEN_CONF
+    + LEN_EXPONENT
+    + LEN_EMA_PRICE
+    + LEN_EMA_CONF
+    + LEN_STATUS
+    + LEN_NUM_PUBLISHERS
+    + LEN_MAX_NUM_PUBLISHERS
+    + LEN_ATTESTATION_TIME
+    + LEN_PUBLISH_TIME
+    + LEN_PREV_PUBLISH_TIME
+    + LEN_PREV_PRICE
+    + LEN_PREV_CONF;
+
+/// Circuit representation of legacy pyth `PriceAttestation` of P2W version 3, which is published
+/// in batches by the wormhole attester before the accumulator.
+#[derive(Debug, Clone, Copy)]
+pub struct PriceAttestation<E: Engine> {
+    pub product_id: [Byte<E>; LEN_PRODUCT_ID],
+    pub price_id: [Byte<E>; LEN_PRICE_ID],
+    pub price: [Byte<E>; LEN_PRICE],
+    pub conf: [Byte<E>; LEN_CONF],
+    pub exponent: [Byte<E>; LEN_EXPONENT],
+    pub ema_price: [Byte<E>; LEN_EMA_PRICE],
+    pub ema_conf: [Byte<E>; LEN_EMA_CONF],
+    pub status: [Byte<E>; LEN_STATUS],
+    pub num_publishers: [Byte<E>; LEN_NUM_PUBLISHERS],
+    pub max_num_publishers: [Byte<E>; LEN_MAX_NUM_PUBLISHERS],
+    pub attestation_time: [Byte<E>; LEN_ATTESTATION_TIME],
+    pub publish_time: [Byte<E>; LEN_PUBLISH_TIME],
+    pub prev_publish_time: [Byte<E>; LEN_PREV_PUBLISH_TIME],
+    pub prev_price: [Byte<E>; LEN_PREV_PRICE],
+    pub prev_conf: [Byte<E>; LEN_PREV_CONF],
+}
+
+impl<E: Engine> PriceAttestation<E> {
+    pub fn from_bytes(bytes: &[Byte<E>; LEN_PRICE_ATTESTATION]) -> Self {
+        let (product_id, rest) = bytes.split_at(LEN_PRODUCT_ID);
+        let (price_id, rest) = rest.split_at(LEN_PRICE_ID);
+        let (price, rest) = rest.split_at(LEN_PRICE);
+        let (conf, rest) = rest.split_at(LEN_CONF);
+        let (exponent, rest) = rest.split_at(LEN_EXPONENT);
+        let (ema_price, rest) = rest.split_at(LEN_EMA_PRICE);
+        let (ema_conf, rest) = rest.split_at(LEN_EMA_CONF);
+        let (status, rest) = rest.split_at(LEN_STATUS);
+        let (num_publishers, rest) = rest.split_at(LEN_NUM_PUBLISHERS);
+        let (max_num_publishers, rest) = rest.split_at(LEN_MAX_NUM_PUBLISHERS);
+        let (attestation_time, rest) = rest.split_at(LEN_ATTESTATION_TIME);
+        let (publish_time, rest) = rest.split_at(LEN_PUBLISH_TIME);
+        let (prev_publish_time, rest) = rest.split_at(LEN_PREV_PUBLISH_TIME);
+        let (prev_price, prev_conf) = rest.split_at(LEN_PREV_PRICE);
+        Self {
+            product_id: product_id.try_into().unwrap(),
+            price_id: price_id.try_into().unwrap(),
+            price: price.try_into().unwrap(),
+            conf: conf.try_into().unwrap(),
+            exponent: exponent.try_into().unwrap(),
+            ema_price: ema_price.try_into().unwrap(),
+            ema_conf: ema_conf.try_into().unwrap(),
+            status: status.try_into().unwrap(),
+            num_publishers: num_publishers.try_into().unwrap(),
+            max_num_publishers: max_num_publishers.try_into().unwrap(),
+            attestation_time: attestation_time.try_into().unwrap(),
+            publish_time: publish_time.try_into().unwrap(),
+            prev_publish_time: prev_publish_time.try_into().unwrap(),
+            prev_price: prev_price.try_into().unwrap(),
+            prev_conf: prev_conf.try_into().unwrap(),
+        }
+    }
+
+    /// Decode fields shared with accumulator messages, so that legacy prices are consumed in the
+    /// same way. Timestamps of attestations are non-negative, so they are decoded as unsigned.
+    pub fn to_message<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<PriceFeedMessage<E>, SynthesisError> {
+        Ok(PriceFeedMessage {
+            feed_id: self.price_id,
+            price: SignedNum::from_be_bytes(cs, &self.price)?,
+            conf: num_from_be_bytes(cs, &self.conf)?,
+            exponent: SignedNum::from_be_bytes(cs, &self.exponent)?,
+            publish_time: num_from_be_bytes(cs, &self.publish_time)?,
+            prev_publish_time: num_from_be_bytes(cs, &self.prev_publish_time)?,
+            ema_price: SignedNum::from_be_bytes(cs, &self.ema_price)?,
+            ema_conf: num_from_be_bytes(cs, &self.ema_conf)?,
+        })
+    }
+}
+
+/// Legacy pyth batch price attestation payload
+/// `P2W header || attestation count || attestation size || attestations`, whose attestation count
+/// is fixed by circuit. Header fields, the count and the size are enforced.
+#[derive(Debug, Clone)]
+pub struct BatchPriceAttestation<E: Engine> {
+    pub attestations: Vec<PriceAttestation<E>>,
+}
+
+impl<E: Engine> BatchPriceAttestation<E> {
+    pub fn from_bytes<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        bytes: &[Byte<E>],
+        num_attestations: usize,
+    ) -> Result<Self, SynthesisError> {
+        let expected_len =
+            LEN_P2W_HEADER + LEN_BATCH_HEADER + num_attestations * LEN_PRICE_ATTESTATION;
+        if num_attestations > u16::MAX as usize || bytes.len() != expected_len {
+            return Err(new_synthesis_error(format!(
+                "invalid batch price attestation of {} bytes with {} attestations",
+                bytes.len(),
+                num_attestations
+            )));
+        }
+        let mut expected = P2W_MAGIC.to_vec();
+        expected.extend(P2W_MAJOR_VERSION.to_be_bytes());
+        // Minor version is not enforced, which is backward compatible
+        let minor_version = expected.len()..expected.len() + 2;
+        expected.extend([0u8; 2]);
+        expected.extend(P2W_HEADER_SIZE.to_be_bytes());
+        expected.push(P2W_PAYLOAD_ID_BATCH);
+        expected.extend((num_attestations as u16).to_be_bytes());
+        expected.extend((LEN_PRICE_ATTESTATION as u16).to_be_bytes());
+        for (i, (byte, expected)) in bytes.iter().zip(expected.iter()).enumerate() {
+            if !minor_version.contains(&i) {
+                byte.inner
+                    .enforce_equal(cs, &Num::Constant(u64_to_fe(*expected as u64)))?;
+            }
+        }
+
+        let attestations = bytes[LEN_P2W_HEADER + LEN_BATCH_HEADER..]
+            .chunks_exact(LEN_PRICE_ATTESTATION)
+            .map(|chunk| PriceAttestation::from_bytes(chunk.try_into().unwrap()))
+            .collect();
+        Ok(Self { attestations })
+    }
+
+    /// Interpret payload of the VAA as batch price attestation.
+    pub fn from_vaa<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        vaa: &ParsedVaa<E>,
+        num_attestations: usize,
+    ) -> Result<Self, SynthesisError> {
+        Self::from_bytes(cs, vaa.payload()?, num_attestations)
+    }
+}
+
+/// Check the legacy VAA is signed by a quorum of `guardian_set`, and decode prices of its batch
+/// price attestation, which are only meaningful if the check passes.
+pub fn verify_batch_price_attestation<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    vaa: &ParsedVaa<E>,
+    guardian_set: &[Address<E>],
+    num_attestations: usize,
+) -> Result<(Boolean, Vec<PriceFeedMessage<E>>), SynthesisError> {
+    let is_valid = check_guardian_quorum(cs, vaa, guardian_set)?;
+    let batch = BatchPriceAttestation::from_vaa(cs, vaa, num_attestations)?;
+    let messages = batch
+        .attestations
+        .iter()
+        .map(|attestation| attestation.to_message(cs))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((is_valid, messages))
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::franklin_crypto::bellman::{
+        pairing::{bn256::Fr, ff::PrimeField},
+        SynthesisError,
+    };
+    use sha3::{Digest, Keccak256};
+
+    use super::verify_batch_price_attestation;
+    use crate::{
+        gadgets::ethereum::Address,
+        pyth::ParsedVaa,
+        utils::testing::{create_test_constraint_system, RedstoneSigner},
+    };
+
+    fn attestation(price_id: u8, price: i64, exponent: i32, publish_time: i64) -> Vec<u8> {
+        let mut bytes = vec![0xaa; 32];
+        bytes.extend([price_id; 32]);
+        bytes.extend(price.to_be_bytes());
+        bytes.extend(7u64.to_be_bytes()); // conf
+        bytes.extend(exponent.to_be_bytes());
+        bytes.extend(price.to_be_bytes()); // ema price
+        bytes.extend(7u64.to_be_bytes()); // ema conf
+        bytes.push(1); // status
+        bytes.extend(3u32.to_be_bytes());
+        bytes.extend(5u32.to_be_bytes());
+        bytes.extend(publish_time.to_be_bytes()); // attestation time
+        bytes.extend(publish_time.to_be_bytes());
+        bytes.extend((publish_time - 1).to_be_bytes());
+        bytes.extend(price.to_be_bytes()); // prev price
+        bytes.extend(7u64.to_be_bytes()); // prev conf
+        bytes
+    }
+
+    fn signed_vaa(payload: &[u8], signer: &RedstoneSigner) -> Vec<u8> {
+        let mut body = vec![0u8; 51];
+        body.extend(payload);
+        let hash: [u8; 32] = Keccak256::digest(Keccak256::digest(&body)).into();
+        let mut signature = signer.sign_hash(&hash);
+        signature[64] -= 27;
+        let mut vaa = vec![1, 0, 0, 0, 0, 1, 0];
+        vaa.extend(signature);
+        vaa.extend(body);
+        vaa
+    }
+
+    #[test]
+    fn test_batch_price_attestation() -> Result<(), SynthesisError> {
+        let signer = RedstoneSigner::new(0x11);
+        let mut payload = b"P2WH".to_vec();
+        payload.extend([0, 3, 0, 0, 0, 1, 2, 0, 2, 0, 149]);
+        payload.extend(attestation(1, 2000_0000_0000, -8, 1654353400));
+        payload.extend(attestation(2, -5, -2, 1654353401));
+
+        let cs = &mut create_test_constraint_system()?;
+        let vaa = ParsedVaa::from_bytes_witness(cs, &signed_vaa(&payload, &signer), 1)?;
+        let guardian_set = [Address::from_address_witness(cs, &signer.address())?];
+        let (is_valid, messages) = verify_batch_price_attestation(cs, &vaa, &guardian_set, 2)?;
+        assert!(is_valid.get_value().unwrap());
+        assert_eq!(
+            messages[0].price.abs.get_value(),
+            Fr::from_str("200000000000")
+        );
+        assert_eq!(messages[0].exponent.is_negative.get_value(), Some(true));
+        assert_eq!(messages[1].price.is_negative.get_value(), Some(true));
+        assert_eq!(messages[1].price.abs.get_value(), Fr::from_str("5"));
+        assert_eq!(
+            messages[1].publish_time.get_value(),
+            Fr::from_str("1654353401")
+        );
+        assert!(cs.is_satisfied());
+
+        // Payload other than batch price attestation is rejected
+        payload[10] = 1;
+        let vaa = ParsedVaa::from_bytes_witness(cs, &signed_vaa(&payload, &signer), 1)?;
+        verify_batch_price_attestation(cs, &vaa, &guardian_set, 2)?;
+        assert!(!cs.is_satisfied());
+        Ok(())
+    }
+}
@@ -37,7 +37,7 @@ use crate::{
         poseidon::{circuit_poseidon_hash, poseidon_hash},
     },
     pyth::{PriceUpdate, PriceUpdates, Vaa},
-    utils::{fr_from_biguint, new_synthesis_error},
+    utils::{fr_from_biguint, new_synthesis_error, CancellationToken},
     witness::{PricesSummarize, PublicInputData},
 };
 
@@ -49,6 +49,10 @@ use crate::gadgets::rescue::circuit_rescue_hash;
 pub use advanced_circuit_component::franklin_crypto;
 use crate::franklin_crypto::bellman::plonk::better_better_cs::gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext;
 
+/// Base64 encoded accumulator update data from hermes API /api/latest_vaas
+/// (https://hermes.pyth.network/docs/#/rest/latest_vaas), with 3 price updates.
+pub(crate) const SAMPLE_ACCUMULATOR_UPDATE_DATA: &str = "UE5BVQEAAAADuAEAAAADDQKR8EO5PyxuSK5T+gNQkaJreUwBZifEwzHpa9tpHugiM09aJtlNZ+QGacbggPbh74MLGekxLbW0L3nW0iWvpp9VAQP7Qvjz7AWngPgTQkXph4sWBNxZ//lLN1TmuddxZ85wFQqdpbC2mX8VAhRL7sER5oFsFWLzxQ1HBLWrHACe2ekWAQTz+pimoBD55XdYKhtbb4/0T01HYaHDJbL0yLgz5UTmy2DxgkEYW0AqiQeQq5kT7wwgaiS/1R2MqVHv4kKBBy4qAAZ4POFVBLBb7HktrrqZCazVQkXRX1h92E23BXK3Vjt+Sxf/ueIJJXK6PoQJKpNuGRPLJPu55O5CCeFga/4kihOZAAfmHbBMH2IiDqUxccAigMYDwFhuMN3Zjby/UiQwcccKnl1tyB6PZUjTBrz9huv+3Lb37TYZH3GLXvwPgGuy+oI2AQjiUQNmxfe/ns3lYELUcJmD0SjfC9O9t757mkWdMZyXzHULb4Z17xaBW9b0CDvKMf+gh6qqHmwBOokmNEP2Ln/WAAo+m7ccVx/M7EkPu5PXFnQt11+mixtm8/gzAXn8TR+/Ng9l/2Gx/T6iXYNgL2ErXIXiGDXxFjnUa08FcaKLgmuvAAvH0mXgEHynf85669H4swCIWlRucdhFxmMp/W9mihoeFQgXbypikATYOzLI0NV3oOCtj6ASvGecSfa4FngwkxqvAAzzY2hcw9bh2u/NU31oC9TRmon9QxkKWNLm3B6gyGVxJFurQ5kfLPHJ9JfAll/oVPlTe2PDzC9z0/Ea2vuPcB1IAQ2Xgc8lPA5CZYuY2U5rGAPUT2nov1d4aFZGDunWdte8uXISM5UEOYaENGKUkuCQn9CdXPL+nvD3nD/LPtDG+gjuAQ6+q5Uzyq307xHErRAcoVkYziIPSoGZf6Rgh0ted5pZokh5P1kzWBsJHM3ISzW3IX4slBfZweZQLMCIpcBTFR/BABD9FzYKBnUQrmi+yZIJpGNQZmxNXVQAybg8qTayhVPOGAFvQ8boVEysxiUlqLKTmI05FpmrB9ESrZMR/Fa1ULUJARL2cMOlIJ9lz4NuPdZAWyp5OONMXZtDI1nRLCMlqwXA7ApUrzUEX8vz6JTbkhEf3a0vh4EvTlv3vTRuYk3Lg6mwAGW4etIAAAAAABrhAfrtrFhR4yubI7X5QRqMK6xKrj7U3XuBHdGnLqSqcQAAAAACSzpwAUFVV1YAAAAAAAdTH/EAACcQjEIPxn/xQVV6+Fv/qiA+BGAg0v0DAFUA5i32yLSoX+GmfbRNwS3l2zMPesZrctxliv7fD0pBW0MAAAPz0SN1oAAAAABtfK+i////+AAAAABluHrSAAAAAGW4etIAAAP04O+QYAAAAABtDW3CCsxZy9+gP6FGv8mbQmMYDxz4+o9Rxgu21d4qn2QTywSAEhTyQV4Vk62iNhMB1q9Ft+zNlQa3YI7malhS5QAyq4GasWRs5jKCGD8ZH2kz65W5xL13Ok08Sxltd0uQALfhNZoUmBQQwV0jW2zRZG61XI3NLLLtWSgb1NU5YXCDZNJ+F/YHeR73m6B2st6PmXoYDyav5RjB3YtDus4ERhQ61M6CAc0bSRGmF0RCSEssboaitjoxdfw3XEl9SH3PZGFwZ282DprCaLI7AFUA5i32yLSoX+GmfbRNwS3l2zMPesZrctxliv7fD0pBW0MAAAPz0SN1oAAAAABtfK+i////+AAAAABluHrSAAAAAGW4etIAAAP04O+QYAAAAABtDW3CCsxZy9+gP6FGv8mbQmMYDxz4+o9Rxgu21d4qn2QTywSAEhTyQV4Vk62iNhMB1q9Ft+zNlQa3YI7malhS5QAyq4GasWRs5jKCGD8ZH2kz65W5xL13Ok08Sxltd0uQALfhNZoUmBQQwV0jW2zRZG61XI3NLLLtWSgb1NU5YXCDZNJ+F/YHeR73m6B2st6PmXoYDyav5RjB3YtDus4ERhQ61M6CAc0bSRGmF0RCSEssboaitjoxdfw3XEl9SH3PZGFwZ282DprCaLI7AFUA5i32yLSoX+GmfbRNwS3l2zMPesZrctxliv7fD0pBW0MAAAPz0SN1oAAAAABtfK+i////+AAAAABluHrSAAAAAGW4etIAAAP04O+QYAAAAABtDW3CCsxZy9+gP6FGv8mbQmMYDxz4+o9Rxgu21d4qn2QTywSAEhTyQV4Vk62iNhMB1q9Ft+zNlQa3YI7malhS5QAyq4GasWRs5jKCGD8ZH2kz65W5xL13Ok08Sxltd0uQALfhNZoUmBQQwV0jW2zRZG61XI3NLLLtWSgb1NU5YXCDZNJ+F/YHeR73m6B2st6PmXoYDyav5RjB3YtDus4ERhQ61M6CAc0bSRGmF0RCSEssboaitjoxdfw3XEl9SH3PZGFwZ282DprCaLI7";
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(bound = "")]
 pub struct PriceOracle<E: Engine, const NUM_PRICES: usize> {
@@ -57,6 +61,11 @@ pub struct PriceOracle<E: Engine, const NUM_PRICES: usize> {
     pub public_input_data: PublicInputData<E>,
     pub commitment: E::Fr,
     pub num_signature_to_verify: usize,
+    /// Whether EMA prices are committed alongside spot prices.
+    #[serde(default)]
+    pub ema_price: bool,
+    #[serde(skip)]
+    pub cancellation_token: CancellationToken,
 }
 
 impl<E: Engine, const NUM_PRICES: usize> PriceOracle<E, NUM_PRICES> {
@@ -64,6 +73,50 @@ impl<E: Engine, const NUM_PRICES: usize> PriceOracle<E, NUM_PRICES> {
         accumulator_update_data: Vec<AccumulatorUpdateData>,
         guardian_set: Vec<[u8; 20]>,
         num_signature_to_verify: usize,
+    ) -> Result<Self, anyhow::Error> {
+        Self::new_with_cancellation_token(
+            accumulator_update_data,
+            guardian_set,
+            num_signature_to_verify,
+            CancellationToken::new(),
+        )
+    }
+
+    /// Same as [`Self::new`], but aborts once `cancellation_token` is cancelled. The token is
+    /// kept by the circuit and checked again during synthesis.
+    pub fn new_with_cancellation_token(
+        accumulator_update_data: Vec<AccumulatorUpdateData>,
+        guardian_set: Vec<[u8; 20]>,
+        num_signature_to_verify: usize,
+        cancellation_token: CancellationToken,
+    ) -> Result<Self, anyhow::Error> {
+        Self::build(
+            accumulator_update_data,
+            guardian_set,
+            num_signature_to_verify,
+            cancellation_token,
+            false,
+        )
+    }
+
+    /// Commit to the normalized EMA price of each feed right after its spot price, for consumers
+    /// preferring EMA prices, e.g. as liquidation triggers.
+    pub fn with_ema_price(self) -> Result<Self, anyhow::Error> {
+        Self::build(
+            self.accumulator_update_data,
+            self.guardian_set,
+            self.num_signature_to_verify,
+            self.cancellation_token,
+            true,
+        )
+    }
+
+    fn build(
+        accumulator_update_data: Vec<AccumulatorUpdateData>,
+        guardian_set: Vec<[u8; 20]>,
+        num_signature_to_verify: usize,
+        cancellation_token: CancellationToken,
+        ema_price: bool,
     ) -> Result<Self, anyhow::Error> {
         let mut last_publish_time = 0;
         let mut earliest_publish_time = 0;
@@ -71,6 +124,7 @@ impl<E: Engine, const NUM_PRICES: usize> PriceOracle<E, NUM_PRICES> {
 
         let secp = Secp256k1::new();
         for data in accumulator_update_data.clone().into_iter() {
+            cancellation_token.check()?;
             let pythnet_sdk::wire::v1::Proof::WormholeMerkle { vaa, updates } = data.proof;
             if updates.len() != NUM_PRICES {
                 anyhow::bail!("expected {} prices, got {}", NUM_PRICES, updates.len())
@@ -131,13 +185,18 @@ impl<E: Engine, const NUM_PRICES: usize> PriceOracle<E, NUM_PRICES> {
                         BigUint::from_bytes_be(&bytes)
                     };
                     // normalized_price = 10^(18-real_exponent) * price
-                    let price = {
+                    let normalize = |price: i64| -> Result<BigUint, anyhow::Error> {
                         let exponent = (18 + price_feed.exponent) as u32;
                         let coefficient = BigUint::from(10u32).pow(exponent);
-                        coefficient.mul(&BigUint::try_from(price_feed.price)?)
+                        Ok(coefficient.mul(&BigUint::try_from(price)?))
                     };
+                    let price = normalize(price_feed.price)?;
                     prices_commitment_members.push(fr_from_biguint::<E>(&feed_id)?);
                     prices_commitment_members.push(fr_from_biguint::<E>(&price)?);
+                    if ema_price {
+                        let ema_price = normalize(price_feed.ema_price)?;
+                        prices_commitment_members.push(fr_from_biguint::<E>(&ema_price)?);
+                    }
                 }
                 let prices_commitment = poseidon_hash::<E>(&prices_commitment_members);
                 prices_commitments.push(prices_commitment);
@@ -204,6 +263,8 @@ impl<E: Engine, const NUM_PRICES: usize> PriceOracle<E, NUM_PRICES> {
                 earliest_publish_time,
             },
             num_signature_to_verify,
+            ema_price,
+            cancellation_token,
         })
     }
 
@@ -212,8 +273,7 @@ impl<E: Engine, const NUM_PRICES: usize> PriceOracle<E, NUM_PRICES> {
         num_signature_to_verify: usize,
     ) -> Self {
         let accumulator_update_data = {
-            // base64 encoded hex from hermes API /api/latest_vaas (https://hermes.pyth.network/docs/#/rest/latest_vaas)
-            let hex = "UE5BVQEAAAADuAEAAAADDQKR8EO5PyxuSK5T+gNQkaJreUwBZifEwzHpa9tpHugiM09aJtlNZ+QGacbggPbh74MLGekxLbW0L3nW0iWvpp9VAQP7Qvjz7AWngPgTQkXph4sWBNxZ//lLN1TmuddxZ85wFQqdpbC2mX8VAhRL7sER5oFsFWLzxQ1HBLWrHACe2ekWAQTz+pimoBD55XdYKhtbb4/0T01HYaHDJbL0yLgz5UTmy2DxgkEYW0AqiQeQq5kT7wwgaiS/1R2MqVHv4kKBBy4qAAZ4POFVBLBb7HktrrqZCazVQkXRX1h92E23BXK3Vjt+Sxf/ueIJJXK6PoQJKpNuGRPLJPu55O5CCeFga/4kihOZAAfmHbBMH2IiDqUxccAigMYDwFhuMN3Zjby/UiQwcccKnl1tyB6PZUjTBrz9huv+3Lb37TYZH3GLXvwPgGuy+oI2AQjiUQNmxfe/ns3lYELUcJmD0SjfC9O9t757mkWdMZyXzHULb4Z17xaBW9b0CDvKMf+gh6qqHmwBOokmNEP2Ln/WAAo+m7ccVx/M7EkPu5PXFnQt11+mixtm8/gzAXn8TR+/Ng9l/2Gx/T6iXYNgL2ErXIXiGDXxFjnUa08FcaKLgmuvAAvH0mXgEHynf85669H4swCIWlRucdhFxmMp/W9mihoeFQgXbypikATYOzLI0NV3oOCtj6ASvGecSfa4FngwkxqvAAzzY2hcw9bh2u/NU31oC9TRmon9QxkKWNLm3B6gyGVxJFurQ5kfLPHJ9JfAll/oVPlTe2PDzC9z0/Ea2vuPcB1IAQ2Xgc8lPA5CZYuY2U5rGAPUT2nov1d4aFZGDunWdte8uXISM5UEOYaENGKUkuCQn9CdXPL+nvD3nD/LPtDG+gjuAQ6+q5Uzyq307xHErRAcoVkYziIPSoGZf6Rgh0ted5pZokh5P1kzWBsJHM3ISzW3IX4slBfZweZQLMCIpcBTFR/BABD9FzYKBnUQrmi+yZIJpGNQZmxNXVQAybg8qTayhVPOGAFvQ8boVEysxiUlqLKTmI05FpmrB9ESrZMR/Fa1ULUJARL2cMOlIJ9lz4NuPdZAWyp5OONMXZtDI1nRLCMlqwXA7ApUrzUEX8vz6JTbkhEf3a0vh4EvTlv3vTRuYk3Lg6mwAGW4etIAAAAAABrhAfrtrFhR4yubI7X5QRqMK6xKrj7U3XuBHdGnLqSqcQAAAAACSzpwAUFVV1YAAAAAAAdTH/EAACcQjEIPxn/xQVV6+Fv/qiA+BGAg0v0DAFUA5i32yLSoX+GmfbRNwS3l2zMPesZrctxliv7fD0pBW0MAAAPz0SN1oAAAAABtfK+i////+AAAAABluHrSAAAAAGW4etIAAAP04O+QYAAAAABtDW3CCsxZy9+gP6FGv8mbQmMYDxz4+o9Rxgu21d4qn2QTywSAEhTyQV4Vk62iNhMB1q9Ft+zNlQa3YI7malhS5QAyq4GasWRs5jKCGD8ZH2kz65W5xL13Ok08Sxltd0uQALfhNZoUmBQQwV0jW2zRZG61XI3NLLLtWSgb1NU5YXCDZNJ+F/YHeR73m6B2st6PmXoYDyav5RjB3YtDus4ERhQ61M6CAc0bSRGmF0RCSEssboaitjoxdfw3XEl9SH3PZGFwZ282DprCaLI7AFUA5i32yLSoX+GmfbRNwS3l2zMPesZrctxliv7fD0pBW0MAAAPz0SN1oAAAAABtfK+i////+AAAAABluHrSAAAAAGW4etIAAAP04O+QYAAAAABtDW3CCsxZy9+gP6FGv8mbQmMYDxz4+o9Rxgu21d4qn2QTywSAEhTyQV4Vk62iNhMB1q9Ft+zNlQa3YI7malhS5QAyq4GasWRs5jKCGD8ZH2kz65W5xL13Ok08Sxltd0uQALfhNZoUmBQQwV0jW2zRZG61XI3NLLLtWSgb1NU5YXCDZNJ+F/YHeR73m6B2st6PmXoYDyav5RjB3YtDus4ERhQ61M6CAc0bSRGmF0RCSEssboaitjoxdfw3XEl9SH3PZGFwZ282DprCaLI7AFUA5i32yLSoX+GmfbRNwS3l2zMPesZrctxliv7fD0pBW0MAAAPz0SN1oAAAAABtfK+i////+AAAAABluHrSAAAAAGW4etIAAAP04O+QYAAAAABtDW3CCsxZy9+gP6FGv8mbQmMYDxz4+o9Rxgu21d4qn2QTywSAEhTyQV4Vk62iNhMB1q9Ft+zNlQa3YI7malhS5QAyq4GasWRs5jKCGD8ZH2kz65W5xL13Ok08Sxltd0uQALfhNZoUmBQQwV0jW2zRZG61XI3NLLLtWSgb1NU5YXCDZNJ+F/YHeR73m6B2st6PmXoYDyav5RjB3YtDus4ERhQ61M6CAc0bSRGmF0RCSEssboaitjoxdfw3XEl9SH3PZGFwZ282DprCaLI7";
+            let hex = SAMPLE_ACCUMULATOR_UPDATE_DATA;
             let bytes = base64::engine::general_purpose::STANDARD
                 .decode(hex)
                 .unwrap();
@@ -280,6 +340,7 @@ impl<E: Engine, const NUM_PRICES: usize> Circuit<E> for PriceOracle<E, NUM_PRICE
         let mut price_updates_batch = vec![];
         // Construct circuit variable from witness
         for accumulator_update_data in self.accumulator_update_data.clone() {
+            self.cancellation_token.check()?;
             let pythnet_sdk::wire::v1::Proof::WormholeMerkle { vaa, updates } =
                 accumulator_update_data.proof;
             let vaa = {
@@ -304,6 +365,7 @@ impl<E: Engine, const NUM_PRICES: usize> Circuit<E> for PriceOracle<E, NUM_PRICE
         let mut is_publish_time_increasing = Boolean::constant(true);
         let mut prices_commitments = vec![];
         for price_updates in price_updates_batch.iter() {
+            self.cancellation_token.check()?;
             // Check signatures in VAA
             {
                 let is_valid = price_updates.check_by_address(cs, &guardian_set)?;
@@ -322,51 +384,18 @@ impl<E: Engine, const NUM_PRICES: usize> Circuit<E> for PriceOracle<E, NUM_PRICE
                         let feed_id = UInt128::from_bytes_le(cs, &bytes)?;
                         feed_id.into_num()
                     };
-                    let price = {
-                        // real exponent = 2^32 - exponent value in be_bytes (complement format)
-                        // normalized_price = 10^(18-real_exponent) * price
-                        let power_32_of_2 = {
-                            let two = {
-                                let one = AllocatedNum::one(cs);
-                                one.add(cs, &one)?
-                            };
-                            let exp = vec![
-                                Boolean::constant(true),  // 32
-                                Boolean::constant(false), // 16
-                                Boolean::constant(false), // 8
-                                Boolean::constant(false), // 4
-                                Boolean::constant(false), // 2
-                                Boolean::constant(false), // 1
-                            ];
-                            AllocatedNum::pow(cs, &two, exp)?
-                        };
-                        let price_exponent = {
-                            let mut price_exponent = price_feed.exponent;
-                            price_exponent.reverse();
-                            UInt32::from_bytes_le(cs, &price_exponent)?.into_num()
-                        };
-                        // for complement number, the real absolute value = 2^32 - complement value
-                        let absolute_price_exponent =
-                            power_32_of_2.sub(cs, &price_exponent.get_variable())?;
-                        let normalized_price_coefficient = {
-                            let eighteen =
-                                AllocatedNum::alloc(cs, || Ok(E::Fr::from_str("18").unwrap()))?;
-                            let normalized_price_exponent =
-                                Num::Variable(eighteen.sub(cs, &absolute_price_exponent)?);
-                            let mut normalized_price_exponent =
-                                normalized_price_exponent.into_bits_le(cs, Some(64))?;
-                            normalized_price_exponent.reverse();
-                            let ten =
-                                AllocatedNum::alloc(cs, || Ok(E::Fr::from_str("10").unwrap()))?;
-                            AllocatedNum::pow(cs, &ten, &normalized_price_exponent)?
-                        };
-                        let mut price = price_feed.price;
-                        price.reverse();
-                        let num = UInt64::from_bytes_le(cs, &price)?.into_num();
-                        num.mul(cs, &Num::Variable(normalized_price_coefficient))?
-                    };
+                    let price =
+                        circuit_normalized_price(cs, price_feed.price, price_feed.exponent)?;
                     prices_commitment_members.push(feed_id);
                     prices_commitment_members.push(price);
+                    if self.ema_price {
+                        let ema_price = circuit_normalized_price(
+                            cs,
+                            price_feed.ema_price,
+                            price_feed.exponent,
+                        )?;
+                        prices_commitment_members.push(ema_price);
+                    }
                 }
                 let prices_commitment =
                     circuit_poseidon_hash(cs, prices_commitment_members.as_slice())?;
@@ -454,6 +483,48 @@ impl<E: Engine, const NUM_PRICES: usize> Circuit<E> for PriceOracle<E, NUM_PRICE
     }
 }
 
+/// Normalize pyth price to 18 decimals, which is `10^(18 + exponent) * price`.
+fn circuit_normalized_price<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    mut price: [Byte<E>; 8],
+    mut exponent: [Byte<E>; 4],
+) -> Result<Num<E>, SynthesisError> {
+    // real exponent = 2^32 - exponent value in be_bytes (complement format)
+    // normalized_price = 10^(18-real_exponent) * price
+    let power_32_of_2 = {
+        let two = {
+            let one = AllocatedNum::one(cs);
+            one.add(cs, &one)?
+        };
+        let exp = vec![
+            Boolean::constant(true),  // 32
+            Boolean::constant(false), // 16
+            Boolean::constant(false), // 8
+            Boolean::constant(false), // 4
+            Boolean::constant(false), // 2
+            Boolean::constant(false), // 1
+        ];
+        AllocatedNum::pow(cs, &two, exp)?
+    };
+    let price_exponent = {
+        exponent.reverse();
+        UInt32::from_bytes_le(cs, &exponent)?.into_num()
+    };
+    // for complement number, the real absolute value = 2^32 - complement value
+    let absolute_price_exponent = power_32_of_2.sub(cs, &price_exponent.get_variable())?;
+    let normalized_price_coefficient = {
+        let eighteen = AllocatedNum::alloc(cs, || Ok(E::Fr::from_str("18").unwrap()))?;
+        let normalized_price_exponent = Num::Variable(eighteen.sub(cs, &absolute_price_exponent)?);
+        let mut normalized_price_exponent = normalized_price_exponent.into_bits_le(cs, Some(64))?;
+        normalized_price_exponent.reverse();
+        let ten = AllocatedNum::alloc(cs, || Ok(E::Fr::from_str("10").unwrap()))?;
+        AllocatedNum::pow(cs, &ten, &normalized_price_exponent)?
+    };
+    price.reverse();
+    let num = UInt64::from_bytes_le(cs, &price)?.into_num();
+    num.mul(cs, &Num::Variable(normalized_price_coefficient))
+}
+
 // Gates of the circuit for the 13 signatures and 4 prices
 pub const GATES: usize = 13275521;
 
@@ -472,6 +543,16 @@ mod tests {
     use base64::Engine as _;
     use pythnet_sdk::wire::v1::AccumulatorUpdateData;
 
+    use crate::{
+        pyth::PriceFeed,
+        utils::testing::{create_test_constraint_system, pyth_price_update},
+    };
+    use advanced_circuit_component::circuit_structures::byte::Byte;
+    use pythnet_sdk::{
+        messages::{Message, PriceFeedMessage},
+        wire::{from_slice, to_vec, v1::MerklePriceUpdate},
+    };
+
     #[test]
     fn test_price_oracle() -> Result<(), anyhow::Error> {
         let price_oracle = PriceOracle::<Bn256, 3>::circuit_default(2, 1);
@@ -481,4 +562,97 @@ mod tests {
         println!("circuit contains {} gates", cs.n());
         Ok(())
     }
+
+    #[test]
+    fn test_price_oracle_with_ema_price() -> Result<(), anyhow::Error> {
+        let price_oracle = PriceOracle::<Bn256, 3>::circuit_default(1, 1);
+        let ema_price_oracle = price_oracle.clone().with_ema_price()?;
+        assert_ne!(price_oracle.commitment, ema_price_oracle.commitment);
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        ema_price_oracle.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+
+    /// Differential test between price feeds deserialized by `pythnet_sdk` and allocated by the
+    /// circuit, over the recorded mainnet updates and messages serialized by `pythnet_sdk`.
+    #[test]
+    fn test_price_feed_parity() -> Result<(), anyhow::Error> {
+        let cs = &mut create_test_constraint_system()?;
+        // Messages of the accumulator update of 2024-01-30 and the merkle update of 2023-11-21
+        let mut messages = vec![];
+        let raw = base64::engine::general_purpose::STANDARD
+            .decode(super::SAMPLE_ACCUMULATOR_UPDATE_DATA)?;
+        let pythnet_sdk::wire::v1::Proof::WormholeMerkle { updates, .. } =
+            AccumulatorUpdateData::try_from_slice(&raw)?.proof;
+        messages.extend(
+            updates
+                .into_iter()
+                .map(|update| Vec::<u8>::from(update.message)),
+        );
+        let raw = hex::decode(pyth_price_update())?;
+        let update = from_slice::<byteorder::BE, MerklePriceUpdate>(&raw)?;
+        messages.push(update.message.into());
+        let price_feed_message = |message: &[u8]| -> anyhow::Result<PriceFeedMessage> {
+            match from_slice::<byteorder::BE, Message>(message)? {
+                Message::PriceFeedMessage(p) => Ok(p),
+                _ => anyhow::bail!("not a price feed message"),
+            }
+        };
+        let recorded = messages
+            .iter()
+            .map(|message| price_feed_message(message))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(recorded.len(), 4);
+        assert!(recorded.iter().all(|p| p.exponent < 0));
+        assert!(recorded
+            .iter()
+            .all(|p| p.ema_price != p.price && p.ema_conf != p.conf));
+
+        // Bounds of every signed and unsigned field, and non-negative exponents
+        for (price, conf, exponent, ema_price, ema_conf) in [
+            (i64::MIN, u64::MAX, i32::MIN, i64::MAX, 0),
+            (i64::MAX, 0, i32::MAX, i64::MIN, u64::MAX),
+            (-1, 1, -1, 1, u64::MAX - 1),
+            (42, 7, 0, -42, 70),
+        ] {
+            let message = Message::PriceFeedMessage(PriceFeedMessage {
+                price,
+                conf,
+                exponent,
+                ema_price,
+                ema_conf,
+                ..recorded[0]
+            });
+            messages.push(to_vec::<_, byteorder::BE>(&message)?);
+        }
+
+        for message in messages {
+            let expected = price_feed_message(&message)?;
+            let witness = from_slice::<byteorder::BE, Message>(&message)?;
+            let price_feed = PriceFeed::<Bn256>::from_message_witness(cs, witness)?;
+            let value = |bytes: &[Byte<Bn256>]| Byte::get_byte_value_multiple(bytes).unwrap();
+            assert_eq!(value(&price_feed.price_feed_type), vec![0]);
+            assert_eq!(value(&price_feed.feed_id), expected.feed_id.to_vec());
+            assert_eq!(value(&price_feed.price), expected.price.to_be_bytes());
+            assert_eq!(value(&price_feed.conf), expected.conf.to_be_bytes());
+            assert_eq!(value(&price_feed.exponent), expected.exponent.to_be_bytes());
+            assert_eq!(
+                value(&price_feed.publish_time),
+                expected.publish_time.to_be_bytes()
+            );
+            assert_eq!(
+                value(&price_feed.prev_publish_time),
+                expected.prev_publish_time.to_be_bytes()
+            );
+            assert_eq!(
+                value(&price_feed.ema_price),
+                expected.ema_price.to_be_bytes()
+            );
+            assert_eq!(value(&price_feed.ema_conf), expected.ema_conf.to_be_bytes());
+            assert_eq!(value(&price_feed.to_bytes(cs)), message);
+        }
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
 }
@@ -0,0 +1,349 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{ff::Field, Engine};
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{
+            plonk::better_better_cs::{
+                cs::{Circuit, ConstraintSystem, Gate, GateInternal},
+                gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
+            },
+            SynthesisError,
+        },
+        plonk::circuit::{
+            allocated_num::Num, boolean::Boolean, custom_rescue_gate::Rescue5CustomGate,
+        },
+    },
+    utils::u64_to_fe,
+    vm::partitioner::smart_and,
+};
+
+use crate::{
+    gadgets::{
+        ethereum::Address,
+        poseidon::{circuit_poseidon_hash, poseidon_hash},
+        rescue::circuit_rescue_hash,
+    },
+    redstone::witness::guardian_set_hash,
+    utils::{self, new_synthesis_error, num_from_be_bytes},
+};
+
+use super::{
+    params::{GOVERNANCE_EMITTER_ADDRESS, GOVERNANCE_EMITTER_CHAIN},
+    quorum::{check_guardian_quorum, check_vaa_quorum},
+    wormhole::ParsedVaa,
+};
+
+// Module of wormhole core governance, which is "Core" left-padded to 32 bytes
+const GOVERNANCE_MODULE_CORE: &[u8; 4] = b"Core";
+const GOVERNANCE_ACTION_GUARDIAN_SET_UPGRADE: u8 = 2;
+const LEN_GOVERNANCE_MODULE: usize = 32;
+const LEN_GOVERNANCE_ACTION: usize = 1;
+const LEN_GOVERNANCE_CHAIN: usize = 2;
+const LEN_GUARDIAN_SET_INDEX: usize = 4;
+const LEN_NUM_GUARDIANS: usize = 1;
+const LEN_GUARDIAN: usize = 20;
+const LEN_UPGRADE_HEADER: usize = LEN_GOVERNANCE_MODULE
+    + LEN_GOVERNANCE_ACTION
+    + LEN_GOVERNANCE_CHAIN
+    + LEN_GUARDIAN_SET_INDEX
+    + LEN_NUM_GUARDIANS;
+
+// Expected header of guardian set upgrade payload, except new guardian set index
+fn upgrade_header(num_guardians: usize) -> Vec<(usize, u8)> {
+    let mut module = [0u8; LEN_GOVERNANCE_MODULE];
+    module[LEN_GOVERNANCE_MODULE - GOVERNANCE_MODULE_CORE.len()..]
+        .copy_from_slice(GOVERNANCE_MODULE_CORE);
+    let mut expected = module.into_iter().enumerate().collect::<Vec<_>>();
+    let mut offset = LEN_GOVERNANCE_MODULE;
+    expected.push((offset, GOVERNANCE_ACTION_GUARDIAN_SET_UPGRADE));
+    offset += LEN_GOVERNANCE_ACTION;
+    // Upgrade applies to all chains, i.e. chain 0
+    expected.extend((offset..offset + LEN_GOVERNANCE_CHAIN).map(|i| (i, 0)));
+    offset += LEN_GOVERNANCE_CHAIN + LEN_GUARDIAN_SET_INDEX;
+    expected.push((offset, num_guardians as u8));
+    expected
+}
+
+/// Native counterpart of [`GuardianSetUpgrade::from_payload`], returning the new guardian set
+/// index and guardians.
+pub fn parse_guardian_set_upgrade(payload: &[u8]) -> anyhow::Result<(u32, Vec<[u8; 20]>)> {
+    if payload.len() < LEN_UPGRADE_HEADER {
+        anyhow::bail!(
+            "guardian set upgrade of {} bytes is too short",
+            payload.len()
+        )
+    }
+    let num_guardians = payload[LEN_UPGRADE_HEADER - 1] as usize;
+    if payload.len() != LEN_UPGRADE_HEADER + num_guardians * LEN_GUARDIAN {
+        anyhow::bail!(
+            "invalid guardian set upgrade of {} bytes with {} guardians",
+            payload.len(),
+            num_guardians
+        )
+    }
+    if upgrade_header(num_guardians)
+        .into_iter()
+        .any(|(i, expected)| payload[i] != expected)
+    {
+        anyhow::bail!("payload is not a guardian set upgrade of wormhole core")
+    }
+    let offset = LEN_UPGRADE_HEADER - LEN_NUM_GUARDIANS - LEN_GUARDIAN_SET_INDEX;
+    let index = u32::from_be_bytes(payload[offset..offset + 4].try_into()?);
+    let guardians = payload[LEN_UPGRADE_HEADER..]
+        .chunks_exact(LEN_GUARDIAN)
+        .map(|chunk| chunk.try_into().unwrap())
+        .collect();
+    Ok((index, guardians))
+}
+
+/// Commitment of guardian set rotation proven by [`GuardianSetUpgradeCircuit`], which is
+/// `poseidon(old index, old guardian set hash, new index, new guardian set hash)`.
+pub fn guardian_set_upgrade_commitment<E: Engine>(
+    old_index: u32,
+    old_guardian_set: &[[u8; 20]],
+    new_index: u32,
+    new_guardian_set: &[[u8; 20]],
+) -> anyhow::Result<E::Fr> {
+    Ok(poseidon_hash::<E>(&[
+        u64_to_fe(old_index as u64),
+        guardian_set_hash::<E>(old_guardian_set)?,
+        u64_to_fe(new_index as u64),
+        guardian_set_hash::<E>(new_guardian_set)?,
+    ]))
+}
+
+/// Wormhole core governance payload upgrading guardian set
+/// `module || action || chain || new guardian set index || guardian count || guardians`, whose
+/// guardian count is fixed by circuit. Module, action, chain and the count are enforced.
+#[derive(Debug, Clone)]
+pub struct GuardianSetUpgrade<E: Engine> {
+    pub new_guardian_set_index: [Byte<E>; LEN_GUARDIAN_SET_INDEX],
+    pub new_guardian_set: Vec<Address<E>>,
+}
+
+impl<E: Engine> GuardianSetUpgrade<E> {
+    pub fn from_payload<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        bytes: &[Byte<E>],
+        num_guardians: usize,
+    ) -> Result<Self, SynthesisError> {
+        if num_guardians > u8::MAX as usize
+            || bytes.len() != LEN_UPGRADE_HEADER + num_guardians * LEN_GUARDIAN
+        {
+            return Err(new_synthesis_error(format!(
+                "invalid guardian set upgrade of {} bytes with {} guardians",
+                bytes.len(),
+                num_guardians
+            )));
+        }
+        for (i, expected) in upgrade_header(num_guardians) {
+            bytes[i]
+                .inner
+                .enforce_equal(cs, &Num::Constant(u64_to_fe(expected as u64)))?;
+        }
+        let offset = LEN_UPGRADE_HEADER - LEN_NUM_GUARDIANS - LEN_GUARDIAN_SET_INDEX;
+        let new_guardian_set_index = bytes[offset..offset + LEN_GUARDIAN_SET_INDEX]
+            .try_into()
+            .unwrap();
+        let new_guardian_set = bytes[LEN_UPGRADE_HEADER..]
+            .chunks_exact(LEN_GUARDIAN)
+            .map(|chunk| Address::from_bytes(cs, chunk.try_into().unwrap()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            new_guardian_set_index,
+            new_guardian_set,
+        })
+    }
+}
+
+/// Check the governance VAA is emitted by wormhole governance and signed by a quorum of
+/// `guardian_set`, and that it upgrades guardian set of the VAA to the next index. Return the
+/// decoded upgrade, which is only meaningful if the check passes.
+pub fn verify_guardian_set_upgrade<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    vaa: &ParsedVaa<E>,
+    guardian_set: &[Address<E>],
+    num_new_guardians: usize,
+) -> Result<(Boolean, GuardianSetUpgrade<E>), SynthesisError> {
+    let is_signed = check_guardian_quorum(cs, vaa, guardian_set)?;
+    let is_emitter_valid =
+        vaa.is_emitted_by(cs, GOVERNANCE_EMITTER_CHAIN, &GOVERNANCE_EMITTER_ADDRESS)?;
+    let upgrade = GuardianSetUpgrade::from_payload(cs, vaa.payload()?, num_new_guardians)?;
+    let old_index = vaa.guardian_set_index(cs)?;
+    let new_index = num_from_be_bytes(cs, &upgrade.new_guardian_set_index)?;
+    let is_next = Num::equals(cs, &old_index.add(cs, &Num::one())?, &new_index)?;
+    let is_valid = smart_and(cs, &[is_signed, is_emitter_valid, is_next])?;
+    Ok((is_valid, upgrade))
+}
+
+/// Governance circuit rotating the wormhole guardian set trustlessly: it proves a guardian set
+/// upgrade VAA signed by the old set, and the only public input is
+/// [`guardian_set_upgrade_commitment`] of the old and new set.
+#[derive(Debug, Clone)]
+pub struct GuardianSetUpgradeCircuit<E: Engine> {
+    pub vaa: Vec<u8>,
+    pub num_signatures: usize,
+    pub guardian_set: Vec<[u8; 20]>,
+    pub num_new_guardians: usize,
+    pub commitment: E::Fr,
+}
+
+impl<E: Engine> GuardianSetUpgradeCircuit<E> {
+    pub fn new(vaa: Vec<u8>, guardian_set: Vec<[u8; 20]>) -> Result<Self, anyhow::Error> {
+        let (header, body) = check_vaa_quorum(&vaa, &guardian_set)?;
+        if u16::from(body.emitter_chain) != GOVERNANCE_EMITTER_CHAIN
+            || body.emitter_address.0 != GOVERNANCE_EMITTER_ADDRESS
+        {
+            anyhow::bail!("VAA is not emitted by wormhole governance")
+        }
+        let (new_index, new_guardian_set) = parse_guardian_set_upgrade(body.payload.as_ref())?;
+        if header.guardian_set_index.checked_add(1) != Some(new_index) {
+            anyhow::bail!(
+                "guardian set {} cannot be upgraded to {}",
+                header.guardian_set_index,
+                new_index
+            )
+        }
+        let commitment = guardian_set_upgrade_commitment::<E>(
+            header.guardian_set_index,
+            &guardian_set,
+            new_index,
+            &new_guardian_set,
+        )?;
+        Ok(Self {
+            vaa,
+            num_signatures: header.signatures.len(),
+            guardian_set,
+            num_new_guardians: new_guardian_set.len(),
+            commitment,
+        })
+    }
+}
+
+impl<E: Engine> Circuit<E> for GuardianSetUpgradeCircuit<E> {
+    type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
+
+    fn synthesize<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<(), SynthesisError> {
+        utils::add_bitwise_logic_and_range_table(cs)?;
+        let temp_variable = Num::alloc(cs, Some(E::Fr::one()))?;
+        circuit_rescue_hash(cs, &[temp_variable])?; // Just to standardize the proof format
+
+        let guardian_set = self
+            .guardian_set
+            .iter()
+            .map(|g| Address::from_address_witness(cs, g))
+            .collect::<Result<Vec<_>, _>>()?;
+        let vaa = ParsedVaa::from_bytes_witness(cs, &self.vaa, self.num_signatures)?;
+        let (is_valid, upgrade) =
+            verify_guardian_set_upgrade(cs, &vaa, &guardian_set, self.num_new_guardians)?;
+        Boolean::enforce_equal(cs, &is_valid, &Boolean::constant(true))?;
+
+        let mut set_hashes = vec![];
+        for guardians in [&guardian_set, &upgrade.new_guardian_set] {
+            let guardian_nums = guardians
+                .iter()
+                .map(|g| g.inner().to_num_unchecked(cs))
+                .collect::<Result<Vec<_>, _>>()?;
+            set_hashes.push(circuit_poseidon_hash(cs, &guardian_nums)?);
+        }
+        let old_index = vaa.guardian_set_index(cs)?;
+        let new_index = num_from_be_bytes(cs, &upgrade.new_guardian_set_index)?;
+        let commitment =
+            circuit_poseidon_hash(cs, &[old_index, set_hashes[0], new_index, set_hashes[1]])?;
+        let expected_commitment = Num::alloc(cs, Some(self.commitment))?;
+        expected_commitment.enforce_equal(cs, &commitment)?;
+        expected_commitment.get_variable().inputize(cs)?;
+        Ok(())
+    }
+
+    fn declare_used_gates() -> Result<Vec<Box<dyn GateInternal<E>>>, SynthesisError> {
+        Ok(vec![
+            Self::MainGate::default().into_internal(),
+            Rescue5CustomGate.into_internal(), // Just to standardize the proof format
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::bellman::plonk::better_better_cs::cs::Circuit,
+        testing::{create_test_artifacts_with_optimized_gate, Bn256},
+    };
+    use sha3::{Digest, Keccak256};
+
+    use super::{guardian_set_upgrade_commitment, GuardianSetUpgradeCircuit};
+    use crate::utils::testing::RedstoneSigner;
+
+    fn upgrade_vaa(
+        guardian_set_index: u32,
+        emitter: u8,
+        new_index: u32,
+        new_guardian_set: &[[u8; 20]],
+        signers: &[RedstoneSigner],
+    ) -> Vec<u8> {
+        let mut body = vec![0u8; 8];
+        body.extend(1u16.to_be_bytes());
+        body.extend([0u8; 31]);
+        body.push(emitter);
+        body.extend([0u8; 9]);
+        body.extend([0u8; 28]);
+        body.extend(b"Core");
+        body.extend([2, 0, 0]);
+        body.extend(new_index.to_be_bytes());
+        body.push(new_guardian_set.len() as u8);
+        body.extend(new_guardian_set.concat());
+        let hash: [u8; 32] = Keccak256::digest(Keccak256::digest(&body)).into();
+
+        let mut vaa = vec![1];
+        vaa.extend(guardian_set_index.to_be_bytes());
+        vaa.push(signers.len() as u8);
+        for (i, signer) in signers.iter().enumerate() {
+            let mut signature = signer.sign_hash(&hash);
+            signature[64] -= 27;
+            vaa.push(i as u8);
+            vaa.extend(signature);
+        }
+        vaa.extend(body);
+        vaa
+    }
+
+    #[test]
+    fn test_guardian_set_upgrade_circuit() -> anyhow::Result<()> {
+        let signers = RedstoneSigner::generate(3);
+        let guardian_set = signers.iter().map(|s| s.address()).collect::<Vec<_>>();
+        let new_guardian_set = RedstoneSigner::generate(5)[3..]
+            .iter()
+            .map(|s| s.address())
+            .collect::<Vec<_>>();
+
+        let vaa = upgrade_vaa(3, 4, 4, &new_guardian_set, &signers);
+        let circuit = GuardianSetUpgradeCircuit::<Bn256>::new(vaa, guardian_set.clone())?;
+        assert_eq!(
+            circuit.commitment,
+            guardian_set_upgrade_commitment::<Bn256>(3, &guardian_set, 4, &new_guardian_set)?
+        );
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+
+        // Upgrade skipping an index, from a non-governance emitter, or without quorum is rejected
+        for vaa in [
+            upgrade_vaa(3, 4, 5, &new_guardian_set, &signers),
+            upgrade_vaa(3, 5, 4, &new_guardian_set, &signers),
+            upgrade_vaa(3, 4, 4, &new_guardian_set, &signers[..2]),
+        ] {
+            assert!(
+                GuardianSetUpgradeCircuit::<Bn256>::new(vaa.clone(), guardian_set.clone()).is_err()
+            );
+            let mut circuit = circuit.clone();
+            circuit.num_signatures = vaa[5] as usize;
+            circuit.vaa = vaa;
+            let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+            if circuit.synthesize(&mut cs).is_ok() {
+                assert!(!cs.is_satisfied());
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,248 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    glue::prepacked_long_comparison,
+    utils::u64_to_fe,
+    vm::partitioner::{smart_and, smart_or},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    gadgets::{
+        ethereum::Address,
+        poseidon::{circuit_poseidon_hash, poseidon_hash},
+    },
+    redstone::witness::guardian_set_hash,
+    utils::new_synthesis_error,
+};
+
+use super::{quorum::check_guardian_quorum, wormhole::ParsedVaa};
+
+/// Wormhole guardian set registered in the table, mirroring `GuardianSet` of wormhole core
+/// contract. Timestamps are in seconds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuardianSetEntry {
+    pub index: u32,
+    pub guardians: Vec<[u8; 20]>,
+    /// Time after which the retired set is no longer accepted, or `0` if it never expires,
+    /// which is the case of the current set.
+    pub expiration_time: u64,
+}
+
+impl GuardianSetEntry {
+    pub fn is_active(&self, time: u64) -> bool {
+        self.expiration_time == 0 || time <= self.expiration_time
+    }
+}
+
+/// Commitment of the guardian set table, which is poseidon hash of
+/// `(index, expiration time, guardian set hash)` of each entry.
+pub fn guardian_set_table_hash<E: Engine>(entries: &[GuardianSetEntry]) -> anyhow::Result<E::Fr> {
+    let mut input = vec![];
+    for entry in entries.iter() {
+        input.push(u64_to_fe(entry.index as u64));
+        input.push(u64_to_fe(entry.expiration_time));
+        input.push(guardian_set_hash::<E>(&entry.guardians)?);
+    }
+    Ok(poseidon_hash::<E>(&input))
+}
+
+/// Commitment of guardian sets `index` and `index + 1` accepted during a guardian rotation, which
+/// is poseidon hash of `(index, guardian set hash of index, guardian set hash of index + 1)`.
+pub fn guardian_set_rotation_hash<E: Engine>(
+    index: u32,
+    guardian_set: &[[u8; 20]],
+    next_guardian_set: &[[u8; 20]],
+) -> anyhow::Result<E::Fr> {
+    Ok(poseidon_hash::<E>(&[
+        u64_to_fe(index as u64),
+        guardian_set_hash::<E>(guardian_set)?,
+        guardian_set_hash::<E>(next_guardian_set)?,
+    ]))
+}
+
+/// Entry of [`GuardianSetTable`] in circuit, where guardians are committed by their hash.
+#[derive(Debug, Clone, Copy)]
+pub struct AllocatedGuardianSetEntry<E: Engine> {
+    pub index: Num<E>,
+    pub expiration_time: Num<E>,
+    pub guardian_set_hash: Num<E>,
+}
+
+/// Table of guardian sets in circuit, against which the guardian set index of VAA is matched.
+///
+/// The table is a witness committed by [`Self::commitment`], so that new guardian sets are
+/// supported by a new commitment rather than circuit changes.
+#[derive(Debug, Clone)]
+pub struct GuardianSetTable<E: Engine> {
+    pub entries: Vec<AllocatedGuardianSetEntry<E>>,
+}
+
+impl<E: Engine> GuardianSetTable<E> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        entries: &[GuardianSetEntry],
+    ) -> Result<Self, SynthesisError> {
+        let mut allocated = vec![];
+        for entry in entries.iter() {
+            let guardian_set_hash =
+                guardian_set_hash::<E>(&entry.guardians).map_err(new_synthesis_error)?;
+            allocated.push(AllocatedGuardianSetEntry {
+                index: Num::alloc(cs, Some(u64_to_fe(entry.index as u64)))?,
+                expiration_time: Num::alloc(cs, Some(u64_to_fe(entry.expiration_time)))?,
+                guardian_set_hash: Num::alloc(cs, Some(guardian_set_hash))?,
+            });
+        }
+        Ok(Self { entries: allocated })
+    }
+
+    /// Same as [`guardian_set_table_hash`].
+    pub fn commitment<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<Num<E>, SynthesisError> {
+        let input = self
+            .entries
+            .iter()
+            .flat_map(|e| [e.index, e.expiration_time, e.guardian_set_hash])
+            .collect::<Vec<_>>();
+        circuit_poseidon_hash(cs, &input)
+    }
+
+    /// Check `guardian_set` is the set at `guardian_set_index` of the table, and it is active at
+    /// `time`, i.e. it never expires or `time` is within its grace period.
+    pub fn check_guardian_set<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        guardian_set_index: &Num<E>,
+        guardian_set: &[Address<E>],
+        time: &Num<E>,
+    ) -> Result<Boolean, SynthesisError> {
+        let guardian_nums = guardian_set
+            .iter()
+            .map(|g| g.inner().to_num_unchecked(cs))
+            .collect::<Result<Vec<_>, _>>()?;
+        let guardian_set_hash = circuit_poseidon_hash(cs, &guardian_nums)?;
+
+        let mut is_found = Boolean::constant(false);
+        let mut is_valid = Boolean::constant(false);
+        for entry in self.entries.iter() {
+            let is_selected = Num::equals(cs, &entry.index, guardian_set_index)?;
+            let never_expires = Num::equals(cs, &entry.expiration_time, &Num::zero())?;
+            let (is_equal, is_later) =
+                prepacked_long_comparison(cs, &[entry.expiration_time], &[*time], &[64])?;
+            let is_active = smart_or(cs, &[never_expires, is_equal, is_later])?;
+            let is_matched = Num::equals(cs, &entry.guardian_set_hash, &guardian_set_hash)?;
+            let is_current_valid = smart_and(cs, &[is_selected, is_active, is_matched])?;
+            is_found = Boolean::or(cs, &is_found, &is_selected)?;
+            is_valid = Boolean::or(cs, &is_valid, &is_current_valid)?;
+        }
+        Boolean::and(cs, &is_found, &is_valid)
+    }
+
+    /// Check the VAA is signed by a quorum of `guardian_set`, which is the set of the table at
+    /// guardian set index of the VAA and active at `reference_time`.
+    ///
+    /// The timestamp of the VAA body is chosen by its signers, so `reference_time` must be bound
+    /// by the circuit instead, e.g. a public input or the timestamp of a block header proven
+    /// against a committed block hash, as wormhole checks expiry against `block.timestamp`.
+    pub fn check_vaa<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        vaa: &ParsedVaa<E>,
+        guardian_set: &[Address<E>],
+        reference_time: &Num<E>,
+    ) -> Result<Boolean, SynthesisError> {
+        let guardian_set_index = vaa.guardian_set_index(cs)?;
+        let is_set_valid =
+            self.check_guardian_set(cs, &guardian_set_index, guardian_set, reference_time)?;
+        let is_signed = check_guardian_quorum(cs, vaa, guardian_set)?;
+        Boolean::and(cs, &is_set_valid, &is_signed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::franklin_crypto::{
+        bellman::{pairing::bn256::Bn256, SynthesisError},
+        plonk::circuit::boolean::Boolean,
+    };
+
+    use super::{guardian_set_table_hash, GuardianSetEntry, GuardianSetTable};
+    use crate::{
+        gadgets::ethereum::{block_header_timestamp, Address, BlockHeader},
+        pyth::{ParsedVaa, GUARDIAN_SET},
+        utils::{
+            num_from_be_bytes,
+            testing::{block_header_rlp, create_test_constraint_system, wormhole_vaa},
+        },
+    };
+
+    #[test]
+    fn test_guardian_set_table() -> Result<(), SynthesisError> {
+        // The VAA is signed by guardian set 3 at 0x655ccff8
+        let timestamp = 0x655ccff8u64;
+        let table = |expiration_time| {
+            vec![
+                GuardianSetEntry {
+                    index: 2,
+                    guardians: GUARDIAN_SET[..3].to_vec(),
+                    expiration_time: timestamp - 1,
+                },
+                GuardianSetEntry {
+                    index: 3,
+                    guardians: GUARDIAN_SET.to_vec(),
+                    expiration_time,
+                },
+            ]
+        };
+        let data = hex::decode(wormhole_vaa()).unwrap();
+        // Current set, retired set within grace period, and expired set
+        for (expiration_time, expected) in [(0, true), (timestamp, true), (timestamp - 1, false)] {
+            let entries = table(expiration_time);
+            assert_eq!(entries[1].is_active(timestamp), expected);
+
+            let cs = &mut create_test_constraint_system()?;
+            let vaa = ParsedVaa::from_bytes_witness(cs, &data, 13)?;
+            let guardian_set = GUARDIAN_SET
+                .iter()
+                .map(|g| Address::from_address_witness(cs, g))
+                .collect::<Result<Vec<_>, _>>()?;
+            let table = GuardianSetTable::from_witness(cs, &entries)?;
+            let reference_time = num_from_be_bytes(cs, &vaa.pyth_body()?.timestamp)?;
+            let is_valid = table.check_vaa(cs, &vaa, &guardian_set, &reference_time)?;
+            assert_eq!(is_valid.get_value(), Some(expected));
+            assert_eq!(
+                table.commitment(cs)?.get_value(),
+                Some(guardian_set_table_hash::<Bn256>(&entries).unwrap())
+            );
+            assert!(cs.is_satisfied());
+        }
+
+        // The VAA is backdated before the expiry of its set, while the reference block is after
+        let header = hex::decode(block_header_rlp()).unwrap();
+        let block_time = block_header_timestamp(&header).unwrap();
+        assert!(block_time > timestamp);
+        for (expiration_time, expected) in [(0, true), (block_time, true), (timestamp, false)] {
+            let entries = table(expiration_time);
+            assert_eq!(entries[1].is_active(block_time), expected);
+
+            let cs = &mut create_test_constraint_system()?;
+            let vaa = ParsedVaa::from_bytes_witness(cs, &data, 13)?;
+            let guardian_set = GUARDIAN_SET
+                .iter()
+                .map(|g| Address::from_address_witness(cs, g))
+                .collect::<Result<Vec<_>, _>>()?;
+            let table = GuardianSetTable::from_witness(cs, &entries)?;
+            let reference_time = BlockHeader::from_rlp_witness(cs, &header)?.timestamp(cs)?;
+            let is_valid = table.check_vaa(cs, &vaa, &guardian_set, &reference_time)?;
+            assert_eq!(is_valid.get_value(), Some(expected));
+            Boolean::enforce_equal(cs, &is_valid, &Boolean::constant(true))?;
+            assert_eq!(cs.is_satisfied(), expected);
+        }
+        Ok(())
+    }
+}
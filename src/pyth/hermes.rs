@@ -0,0 +1,122 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use base64::Engine as _;
+use pythnet_sdk::wire::v1::AccumulatorUpdateData;
+use serde::Deserialize;
+
+use super::circuit::PriceOracle;
+
+pub const DEFAULT_HERMES_ENDPOINT: &str = "https://hermes.pyth.network";
+
+/// Binary price updates in response of `/v2/updates/price/latest`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BinaryPriceUpdate {
+    pub encoding: String,
+    pub data: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PriceUpdateResponse {
+    pub binary: BinaryPriceUpdate,
+}
+
+impl PriceUpdateResponse {
+    /// Decode base64 encoded accumulator update data of the response.
+    pub fn accumulator_update_data(&self) -> anyhow::Result<Vec<AccumulatorUpdateData>> {
+        if self.binary.encoding != "base64" {
+            anyhow::bail!("unsupported encoding {}", self.binary.encoding)
+        }
+        self.binary
+            .data
+            .iter()
+            .map(|data| {
+                let bytes = base64::engine::general_purpose::STANDARD.decode(data)?;
+                AccumulatorUpdateData::try_from_slice(&bytes)
+                    .map_err(|e| anyhow::anyhow!("invalid accumulator update data: {:?}", e))
+            })
+            .collect()
+    }
+}
+
+/// Client of [Pyth Hermes](https://hermes.pyth.network/docs) fetching witnesses of pyth circuits.
+#[derive(Debug, Clone)]
+pub struct HermesClient {
+    client: reqwest::Client,
+    endpoint: String,
+}
+
+impl Default for HermesClient {
+    fn default() -> Self {
+        Self::new(DEFAULT_HERMES_ENDPOINT)
+    }
+}
+
+impl HermesClient {
+    pub fn new(endpoint: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Fetch latest price updates of `feed_ids`, which are hex encoded with or without `0x`.
+    pub async fn latest_price_updates(
+        &self,
+        feed_ids: &[String],
+    ) -> anyhow::Result<Vec<AccumulatorUpdateData>> {
+        let mut query = feed_ids
+            .iter()
+            .map(|id| ("ids[]", id.trim_start_matches("0x")))
+            .collect::<Vec<_>>();
+        query.push(("encoding", "base64"));
+        query.push(("parsed", "false"));
+        let response = self
+            .client
+            .get(format!("{}/v2/updates/price/latest", self.endpoint))
+            .query(&query)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<PriceUpdateResponse>()
+            .await?;
+        response.accumulator_update_data()
+    }
+
+    /// Fetch latest price updates of `feed_ids` and build the price oracle circuit from them.
+    pub async fn price_oracle<E: Engine, const NUM_PRICES: usize>(
+        &self,
+        feed_ids: &[String],
+        guardian_set: Vec<[u8; 20]>,
+        num_signature_to_verify: usize,
+    ) -> anyhow::Result<PriceOracle<E, NUM_PRICES>> {
+        let accumulator_update_data = self.latest_price_updates(feed_ids).await?;
+        PriceOracle::new(
+            accumulator_update_data,
+            guardian_set,
+            num_signature_to_verify,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::franklin_crypto::bellman::pairing::bn256::Bn256;
+
+    use super::PriceUpdateResponse;
+    use crate::pyth::{circuit::SAMPLE_ACCUMULATOR_UPDATE_DATA, PriceOracle, GUARDIAN_SET};
+
+    #[test]
+    fn test_price_update_response() -> anyhow::Result<()> {
+        let response = serde_json::json!({
+            "binary": { "encoding": "base64", "data": [SAMPLE_ACCUMULATOR_UPDATE_DATA] },
+        });
+        let response: PriceUpdateResponse = serde_json::from_value(response)?;
+        let data = response.accumulator_update_data()?;
+        assert_eq!(data.len(), 1);
+        PriceOracle::<Bn256, 3>::new(data, GUARDIAN_SET.to_vec(), 1)?;
+
+        let mut response = response;
+        response.binary.encoding = "hex".to_string();
+        assert!(response.accumulator_update_data().is_err());
+        Ok(())
+    }
+}
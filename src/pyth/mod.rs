@@ -1,10 +1,30 @@
+mod amortized;
+mod attestation;
 pub mod circuit;
+mod governance;
+mod guardian_set;
+#[cfg(feature = "hermes")]
+pub mod hermes;
+mod normalize;
 mod params;
 mod price;
+mod publisher;
+mod quorum;
+mod twap;
+mod update;
 mod wormhole;
 
 pub const WIDTH_PRICE_FEED_BYTES: usize = price::LEN_PRICE_FEED;
+pub use amortized::*;
+pub use attestation::*;
 pub use circuit::*;
+pub use governance::*;
+pub use guardian_set::*;
+pub use normalize::*;
 pub use params::*;
 pub use price::*;
+pub use publisher::*;
+pub use quorum::*;
+pub use twap::*;
+pub use update::*;
 pub use wormhole::*;
@@ -0,0 +1,187 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{
+    ff::{PrimeField, PrimeFieldRepr},
+    Engine,
+};
+use advanced_circuit_component::{
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    glue::prepacked_long_comparison,
+    utils::u64_to_fe,
+    vm::{partitioner::smart_and, primitives::uint256::UInt256},
+};
+use num_bigint::BigUint;
+
+use crate::utils::{fr_from_biguint, num_into_be_bytes};
+
+use super::price::SignedNum;
+
+/// Max difference between target decimals and decimals of pyth price, which bounds the rescaling
+/// factor `10^shift` below 2^120.
+pub const MAX_DECIMALS_SHIFT: usize = 36;
+
+/// Rescale pyth price `price * 10^exponent` to an integer with `target_decimals`, rounding down
+/// if the precision is reduced, in the same way as RedStone values are normalized.
+pub fn normalize_price(
+    price: i64,
+    exponent: i32,
+    target_decimals: usize,
+) -> anyhow::Result<BigUint> {
+    if price < 0 {
+        anyhow::bail!("negative price {}", price)
+    }
+    let shift = target_decimals as i64 + exponent as i64;
+    if shift.unsigned_abs() > MAX_DECIMALS_SHIFT as u64 {
+        anyhow::bail!(
+            "exponent {} is out of range of target decimals {}",
+            exponent,
+            target_decimals
+        )
+    }
+    let factor = BigUint::from(10u32).pow(shift.unsigned_abs() as u32);
+    let price = BigUint::from(price as u64);
+    Ok(if shift >= 0 {
+        price * factor
+    } else {
+        price / factor
+    })
+}
+
+/// Same as [`normalize_price`] in circuit. Circuit is unsatisfied if price is negative or the
+/// exponent is out of range.
+///
+/// As the exponent is a witness, the factor `10^|target_decimals + exponent|` is selected among
+/// all exponents in range. Price is scaled up by the factor if the shift is non-negative, otherwise
+/// it is proved that `price = quotient * factor + remainder` where `remainder < factor`.
+pub fn circuit_normalize_price<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    price: &SignedNum<E>,
+    exponent: &SignedNum<E>,
+    target_decimals: usize,
+) -> Result<UInt256<E>, SynthesisError> {
+    let mut multiplier = Num::zero();
+    let mut divisor = Num::zero();
+    let mut is_down = Boolean::constant(false);
+    let mut is_found = Boolean::constant(false);
+    let max = MAX_DECIMALS_SHIFT as i64 - target_decimals as i64;
+    let min = -(MAX_DECIMALS_SHIFT as i64) - target_decimals as i64;
+    for candidate in min..=max {
+        let is_abs_equal = Num::equals(
+            cs,
+            &exponent.abs,
+            &Num::Constant(u64_to_fe(candidate.unsigned_abs())),
+        )?;
+        let is_sign_equal = if candidate < 0 {
+            exponent.is_negative
+        } else {
+            exponent.is_negative.not()
+        };
+        let is_selected = Boolean::and(cs, &is_abs_equal, &is_sign_equal)?;
+        is_found = Boolean::or(cs, &is_found, &is_selected)?;
+
+        let shift = target_decimals as i64 + candidate;
+        let factor = BigUint::from(10u32).pow(shift.unsigned_abs() as u32);
+        let term = Num::from_boolean_is(is_selected)
+            .mul(cs, &Num::Constant(fr_from_biguint::<E>(&factor)?))?;
+        if shift >= 0 {
+            multiplier = multiplier.add(cs, &term)?;
+        } else {
+            divisor = divisor.add(cs, &term)?;
+            is_down = Boolean::or(cs, &is_down, &is_selected)?;
+        }
+    }
+    let divisor = Num::conditionally_select(cs, &is_down, &divisor, &Num::one())?;
+
+    // price = quotient * divisor + remainder, where remainder < divisor
+    let witness = match (price.abs.get_value(), divisor.get_value()) {
+        (Some(price), Some(divisor)) => {
+            let (price, divisor) = (fe_to_biguint(&price), fe_to_biguint(&divisor));
+            Some((
+                fr_from_biguint::<E>(&(&price / &divisor))?,
+                fr_from_biguint::<E>(&(&price % &divisor))?,
+            ))
+        }
+        _ => None,
+    };
+    let quotient = Num::alloc_signed(cs, witness.map(|(q, _)| q))?;
+    let remainder = Num::alloc_signed(cs, witness.map(|(_, r)| r))?;
+    quotient.into_bits_le(cs, Some(64))?;
+    remainder.into_bits_le(cs, Some(128))?;
+    let recomposed = quotient.mul(cs, &divisor)?.add(cs, &remainder)?;
+    let is_recomposed = Num::equals(cs, &recomposed, &price.abs)?;
+    let (_, is_remainder_less) = prepacked_long_comparison(cs, &[divisor], &[remainder], &[128])?;
+    let is_valid = smart_and(
+        cs,
+        &[
+            is_found,
+            price.is_negative.not(),
+            is_recomposed,
+            is_remainder_less,
+        ],
+    )?;
+    Boolean::enforce_equal(cs, &is_valid, &Boolean::constant(true))?;
+
+    // price < 2^64 and multiplier < 2^120, so the scaled price fits the field
+    let scaled = price.abs.mul(cs, &multiplier)?;
+    let normalized = Num::conditionally_select(cs, &is_down, &quotient, &scaled)?;
+    let bytes = num_into_be_bytes(cs, &normalized, 32)?;
+    UInt256::from_be_bytes_fixed(cs, &bytes.try_into().unwrap())
+}
+
+pub(crate) fn fe_to_biguint<F: PrimeField>(fe: &F) -> BigUint {
+    let mut bytes = vec![];
+    fe.into_repr().write_be(&mut bytes).unwrap();
+    BigUint::from_bytes_be(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte,
+        franklin_crypto::bellman::{
+            pairing::bn256::Bn256, plonk::better_better_cs::cs::ConstraintSystem, SynthesisError,
+        },
+    };
+    use num_bigint::BigUint;
+
+    use super::{circuit_normalize_price, normalize_price};
+    use crate::{pyth::SignedNum, utils::testing::create_test_constraint_system};
+
+    fn alloc_signed<CS: ConstraintSystem<Bn256>>(
+        cs: &mut CS,
+        bytes: &[u8],
+    ) -> Result<SignedNum<Bn256>, SynthesisError> {
+        let bytes = bytes
+            .iter()
+            .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+            .collect::<Result<Vec<_>, _>>()?;
+        SignedNum::from_be_bytes(cs, &bytes)
+    }
+
+    #[test]
+    fn test_normalize_price() -> Result<(), SynthesisError> {
+        let cases = [
+            (3652890574272i64, -8i32, 18usize),
+            (3652890574272, -8, 6),
+            (3652890574272, -8, 8),
+            (42, 2, 0),
+        ];
+        for (price, exponent, target_decimals) in cases {
+            let expected = normalize_price(price, exponent, target_decimals).unwrap();
+            let cs = &mut create_test_constraint_system()?;
+            let price = alloc_signed(cs, &price.to_be_bytes())?;
+            let exponent = alloc_signed(cs, &exponent.to_be_bytes())?;
+            let normalized = circuit_normalize_price(cs, &price, &exponent, target_decimals)?;
+            assert_eq!(normalized.get_value().unwrap(), expected);
+            assert!(cs.is_satisfied());
+        }
+        assert_eq!(
+            normalize_price(3652890574272, -8, 6).unwrap(),
+            BigUint::from(36528905742u64)
+        );
+        assert!(normalize_price(-1, -8, 18).is_err());
+        assert!(normalize_price(1, -60, 18).is_err());
+        Ok(())
+    }
+}
@@ -1,5 +1,18 @@
 use lazy_static::lazy_static;
 
+/// Wormhole chain id of Pythnet, which emits pyth accumulator VAAs.
+pub const PYTHNET_EMITTER_CHAIN: u16 = 26;
+
+/// Wormhole chain id of Solana, where governance VAAs of wormhole core are emitted.
+pub const GOVERNANCE_EMITTER_CHAIN: u16 = 1;
+
+/// Address of wormhole governance emitter, which is `0x04` left-padded to 32 bytes.
+pub const GOVERNANCE_EMITTER_ADDRESS: [u8; 32] = {
+    let mut address = [0u8; 32];
+    address[31] = 4;
+    address
+};
+
 lazy_static! {
     pub static ref GUARDIAN_SET: [[u8; 20]; 19] = [
         "58CC3AE5C097b213cE3c81979e1B9f9570746AA5",
@@ -23,4 +36,10 @@ lazy_static! {
         "6FbEBc898F403E4773E95feB15E80C9A99c8348d",
     ]
     .map(|w| hex::decode(w).unwrap().try_into().unwrap());
+    /// Address of pyth accumulator emitter on Pythnet.
+    pub static ref PYTHNET_EMITTER_ADDRESS: [u8; 32] =
+        hex::decode("e101faedac5851e32b9b23b5f9411a8c2bac4aae3ed4dd7b811dd1a72ea4aa71")
+            .unwrap()
+            .try_into()
+            .unwrap();
 }
@@ -3,18 +3,19 @@ use advanced_circuit_component::{
     circuit_structures::byte::Byte,
     franklin_crypto::{
         bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
-        plonk::circuit::boolean::Boolean,
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
     },
     traits::CSAllocatable,
-    vm::primitives::uint256::UInt256,
+    vm::{partitioner::smart_and, primitives::uint256::UInt256},
 };
+use num_bigint::BigUint;
 
 use crate::{
     gadgets::{
         ethereum::Address,
         keccak160::{self, MerklePath, MerkleRoot},
     },
-    utils::new_synthesis_error,
+    utils::{fr_from_biguint, new_synthesis_error, num_from_be_bytes},
 };
 
 use super::wormhole::Vaa;
@@ -40,33 +41,7 @@ impl<E: Engine, const N: usize> PriceUpdate<E, N> {
                 .map_err(new_synthesis_error)?;
             PriceFeed::from_message_witness(cs, price_feed)?
         };
-        let proof = {
-            let proof = witness.proof.to_bytes();
-            if proof.len() != N * keccak160::WIDTH_HASH_BYTES {
-                return Err(new_synthesis_error(format!(
-                    "invalid proof length {}, expect {}",
-                    proof.len(),
-                    N * keccak160::WIDTH_HASH_BYTES
-                )));
-            }
-
-            let merkle_paths: [[u8; keccak160::WIDTH_HASH_BYTES]; N] = proof
-                .chunks_exact(keccak160::WIDTH_HASH_BYTES)
-                .map(|chunk| chunk.try_into().unwrap())
-                .collect::<Vec<_>>()
-                .try_into()
-                .unwrap();
-
-            let merkle_paths = merkle_paths
-                .into_iter()
-                .map(|hash| keccak160::Hash::alloc_from_witness(cs, Some(hash)))
-                .collect::<Result<Vec<_>, _>>()?
-                .try_into()
-                .unwrap();
-
-            MerklePath(merkle_paths)
-        };
-        // let merkle_paths = MerklePath::new(merkle_paths.try_into().unwrap());
+        let proof = merkle_path_from_witness(cs, &witness.proof.to_bytes())?;
         Ok(Self { message, proof })
     }
 
@@ -80,6 +55,36 @@ impl<E: Engine, const N: usize> PriceUpdate<E, N> {
     }
 }
 
+/// Allocate merkle path of depth `N` from its serialized sibling hashes.
+pub(crate) fn merkle_path_from_witness<E: Engine, CS: ConstraintSystem<E>, const N: usize>(
+    cs: &mut CS,
+    proof: &[u8],
+) -> Result<MerklePath<E, N>, SynthesisError> {
+    if proof.len() != N * keccak160::WIDTH_HASH_BYTES {
+        return Err(new_synthesis_error(format!(
+            "invalid proof length {}, expect {}",
+            proof.len(),
+            N * keccak160::WIDTH_HASH_BYTES
+        )));
+    }
+
+    let merkle_paths: [[u8; keccak160::WIDTH_HASH_BYTES]; N] = proof
+        .chunks_exact(keccak160::WIDTH_HASH_BYTES)
+        .map(|chunk| chunk.try_into().unwrap())
+        .collect::<Vec<_>>()
+        .try_into()
+        .unwrap();
+
+    let merkle_paths = merkle_paths
+        .into_iter()
+        .map(|hash| keccak160::Hash::alloc_from_witness(cs, Some(hash)))
+        .collect::<Result<Vec<_>, _>>()?
+        .try_into()
+        .unwrap();
+
+    Ok(MerklePath(merkle_paths))
+}
+
 /// Circuit (partial) representation of pyth [`Proof`](https://github.com/pyth-network/pyth-crosschain/blob/178ad4cb0edff38f43d8e26f23d1d9e83448093c/pythnet/pythnet_sdk/src/wire.rs#L98-L104), the key field in [`AccumulatorUpdateData`](https://github.com/pyth-network/pyth-crosschain/blob/178ad4cb0edff38f43d8e26f23d1d9e83448093c/pythnet/pythnet_sdk/src/wire.rs#L55-L66).
 ///
 /// `N1` is the number of price updates. `N2` is the depth of pyth merkle tree (10 by now). `N3` is the number of wormhole signatures.
@@ -113,13 +118,17 @@ impl<E: Engine, const N1: usize, const N2: usize> PriceUpdates<E, N1, N2> {
         Boolean::and(cs, &valid_signatures, &valid_updates)
     }
 
-    /// Check if the VAA is valid.
+    /// Check if the VAA is valid and emitted by pyth accumulator on Pythnet, carrying a merkle
+    /// root payload.
     pub fn check_vaa_by_pubkey<CS: ConstraintSystem<E>>(
         &self,
         cs: &mut CS,
         guardian_set: &[(UInt256<E>, UInt256<E>)],
     ) -> Result<Boolean, SynthesisError> {
-        self.vaa.check_by_pubkey(cs, guardian_set)
+        let is_emitter_valid = self.vaa.body.is_emitted_by_pythnet(cs)?;
+        let is_merkle = self.vaa.body.payload.is_merkle(cs)?;
+        let is_signed = self.vaa.check_by_pubkey(cs, guardian_set)?;
+        smart_and(cs, &[is_emitter_valid, is_merkle, is_signed])
     }
 
     pub fn check_vaa_by_address<CS: ConstraintSystem<E>>(
@@ -127,7 +136,10 @@ impl<E: Engine, const N1: usize, const N2: usize> PriceUpdates<E, N1, N2> {
         cs: &mut CS,
         guardian_set: &[Address<E>],
     ) -> Result<Boolean, SynthesisError> {
-        self.vaa.check_by_address(cs, guardian_set)
+        let is_emitter_valid = self.vaa.body.is_emitted_by_pythnet(cs)?;
+        let is_merkle = self.vaa.body.payload.is_merkle(cs)?;
+        let is_signed = self.vaa.check_by_address(cs, guardian_set)?;
+        smart_and(cs, &[is_emitter_valid, is_merkle, is_signed])
     }
 
     /// Check if the price updates are valid.
@@ -257,11 +269,122 @@ impl<E: Engine> PriceFeed<E> {
     }
 }
 
+impl<E: Engine> PriceFeed<E> {
+    /// Split bytes of [`PriceFeed::to_bytes`] back into fields.
+    pub fn from_bytes(bytes: &[Byte<E>; LEN_PRICE_FEED]) -> Self {
+        let (price_feed_type, rest) = bytes.split_at(LEN_PRICE_FEED_TYPE);
+        let (feed_id, rest) = rest.split_at(LEN_FEED_ID);
+        let (price, rest) = rest.split_at(LEN_PRICE);
+        let (conf, rest) = rest.split_at(LEN_CONF);
+        let (exponent, rest) = rest.split_at(LEN_EXPONENT);
+        let (publish_time, rest) = rest.split_at(LEN_PUBLISH_TIME);
+        let (prev_publish_time, rest) = rest.split_at(LEN_PREV_PUBLISH_TIME);
+        let (ema_price, ema_conf) = rest.split_at(LEN_EMA_PRICE);
+        Self {
+            price_feed_type: price_feed_type.try_into().unwrap(),
+            feed_id: feed_id.try_into().unwrap(),
+            price: price.try_into().unwrap(),
+            conf: conf.try_into().unwrap(),
+            exponent: exponent.try_into().unwrap(),
+            publish_time: publish_time.try_into().unwrap(),
+            prev_publish_time: prev_publish_time.try_into().unwrap(),
+            ema_price: ema_price.try_into().unwrap(),
+            ema_conf: ema_conf.try_into().unwrap(),
+        }
+    }
+}
+
+/// Signed integer in circuit, represented by its sign and absolute value.
+#[derive(Debug, Clone, Copy)]
+pub struct SignedNum<E: Engine> {
+    pub is_negative: Boolean,
+    pub abs: Num<E>,
+}
+
+impl<E: Engine> SignedNum<E> {
+    /// Decode two's complement big-endian bytes, e.g. `i64` or `i32` of pyth messages.
+    pub fn from_be_bytes<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        bytes: &[Byte<E>],
+    ) -> Result<Self, SynthesisError> {
+        let raw = num_from_be_bytes(cs, bytes)?;
+        let is_negative = bytes[0].inner.into_bits_le(cs, Some(8))?[7];
+        // For negative number, absolute value = 2^width - raw value
+        let modulus = fr_from_biguint::<E>(&(BigUint::from(1u8) << (bytes.len() * 8)))?;
+        let complement = Num::Constant(modulus).sub(cs, &raw)?;
+        let abs = Num::conditionally_select(cs, &is_negative, &complement, &raw)?;
+        Ok(Self { is_negative, abs })
+    }
+
+    /// Field element of the signed value, i.e. `-abs` if negative.
+    pub fn to_num<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<Num<E>, SynthesisError> {
+        let negated = Num::zero().sub(cs, &self.abs)?;
+        Num::conditionally_select(cs, &self.is_negative, &negated, &self.abs)
+    }
+}
+
+/// Typed values of pyth [`PriceFeed`], decoded from its bytes.
+#[derive(Debug, Clone, Copy)]
+pub struct PriceFeedMessage<E: Engine> {
+    pub feed_id: [Byte<E>; LEN_FEED_ID],
+    pub price: SignedNum<E>,
+    pub conf: Num<E>,
+    pub exponent: SignedNum<E>,
+    pub publish_time: Num<E>,
+    pub prev_publish_time: Num<E>,
+    pub ema_price: SignedNum<E>,
+    pub ema_conf: Num<E>,
+}
+
+impl<E: Engine> PriceFeedMessage<E> {
+    pub fn from_bytes_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &[u8],
+    ) -> Result<Self, SynthesisError> {
+        let witness: [u8; LEN_PRICE_FEED] = witness.try_into().map_err(|_| {
+            new_synthesis_error(format!(
+                "invalid price feed message length {}, expect {}",
+                witness.len(),
+                LEN_PRICE_FEED
+            ))
+        })?;
+        let bytes = CSAllocatable::alloc_from_witness(cs, Some(witness))?;
+        Self::from_price_feed(cs, &PriceFeed::from_bytes(&bytes))
+    }
+
+    /// Decode fields of `price_feed`, which must be of type `PriceFeedMessage`.
+    pub fn from_price_feed<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        price_feed: &PriceFeed<E>,
+    ) -> Result<Self, SynthesisError> {
+        price_feed.price_feed_type[0]
+            .inner
+            .enforce_equal(cs, &Num::zero())?;
+        let price = SignedNum::from_be_bytes(cs, &price_feed.price)?;
+        let conf = num_from_be_bytes(cs, &price_feed.conf)?;
+        let exponent = SignedNum::from_be_bytes(cs, &price_feed.exponent)?;
+        let publish_time = num_from_be_bytes(cs, &price_feed.publish_time)?;
+        let prev_publish_time = num_from_be_bytes(cs, &price_feed.prev_publish_time)?;
+        let ema_price = SignedNum::from_be_bytes(cs, &price_feed.ema_price)?;
+        let ema_conf = num_from_be_bytes(cs, &price_feed.ema_conf)?;
+        Ok(Self {
+            feed_id: price_feed.feed_id,
+            price,
+            conf,
+            exponent,
+            publish_time,
+            prev_publish_time,
+            ema_price,
+            ema_conf,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
         gadgets::keccak160,
-        utils::testing::{bytes_assert_eq, create_test_constraint_system},
+        utils::testing::{bytes_assert_eq, create_test_constraint_system, pyth_price_update},
     };
     use advanced_circuit_component::franklin_crypto::bellman::pairing::bn256::Bn256;
     use advanced_circuit_component::franklin_crypto::{
@@ -297,10 +420,48 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_price_feed_message() -> Result<(), SynthesisError> {
+        use advanced_circuit_component::franklin_crypto::bellman::pairing::{
+            bn256::Fr,
+            ff::{Field, PrimeField},
+        };
+
+        let cs = &mut create_test_constraint_system()?;
+        let hex_str = "00e62df6c8b4a85fe1a67db44dc12de5db330f7ac66b72dc658afedf0f4a415b4300000352813ebdc00000000042eeb9f6fffffff800000000655ccff700000000655ccff700000356d0a75ce0000000005b0d7112";
+        let message = super::PriceFeedMessage::<Bn256>::from_bytes_witness(
+            cs,
+            &hex::decode(hex_str).unwrap(),
+        )?;
+        let fr = |s: &str| Some(Fr::from_str(s).unwrap());
+        assert_eq!(message.price.is_negative.get_value(), Some(false));
+        assert_eq!(message.price.abs.get_value(), fr("3652890574272"));
+        assert_eq!(message.conf.get_value(), fr("1122941430"));
+        assert_eq!(message.exponent.is_negative.get_value(), Some(true));
+        assert_eq!(message.exponent.abs.get_value(), fr("8"));
+        assert_eq!(message.publish_time.get_value(), fr("1700581367"));
+        assert_eq!(message.ema_price.abs.get_value(), fr("3671402700000"));
+        assert_eq!(message.ema_conf.get_value(), fr("1527607570"));
+        assert!(cs.is_satisfied());
+
+        // Negative price
+        let mut data = hex::decode(hex_str).unwrap();
+        data[33..41].copy_from_slice(&(-1i64).to_be_bytes());
+        let message = super::PriceFeedMessage::<Bn256>::from_bytes_witness(cs, &data)?;
+        assert_eq!(message.price.is_negative.get_value(), Some(true));
+        assert_eq!(message.price.abs.get_value(), Some(Fr::one()));
+
+        // Other message types are rejected
+        data[0] = 1;
+        super::PriceFeedMessage::<Bn256>::from_bytes_witness(cs, &data)?;
+        assert!(!cs.is_satisfied());
+        Ok(())
+    }
+
     #[test]
     fn test_price_update() -> Result<(), SynthesisError> {
         let cs = &mut create_test_constraint_system()?;
-        let hex_str = "005500e62df6c8b4a85fe1a67db44dc12de5db330f7ac66b72dc658afedf0f4a415b4300000352813ebdc00000000042eeb9f6fffffff800000000655ccff700000000655ccff700000356d0a75ce0000000005b0d71120ad97a31be8c09393bfbcd8cc36a4c486949eaab2bbe6e19294367c1689b7521ba31bcd504b01db4a0c74a56d137795aefe2df9137c1a7d82af648cb8aeece3482a0d6194ec36d2dab3b491296f5d9947b5b87bac5e58c2760c4677e0bb994618fb5c5d853fecc55351cd68a5029d4bc2b6f9ab5c23e7b9462af514a8475ffa181ea1216d2a8f3447464f8685f9b935ce5124e872d4a8b9ea16f9487952dff1ce6a2ef5e724d4da1e5f2bf897e52ac6a31ac60868776163f6ab8f1d74214184da7952bc731ff51f01f";
+        let hex_str = pyth_price_update();
         let data = hex::decode(hex_str).unwrap();
         let update =
             from_slice::<byteorder::BE, pythnet_sdk::wire::v1::MerklePriceUpdate>(&data).unwrap();
@@ -0,0 +1,426 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{ff::Field, Engine};
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{
+            plonk::better_better_cs::{
+                cs::{Circuit, ConstraintSystem, Gate, GateInternal},
+                gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
+            },
+            SynthesisError,
+        },
+        plonk::circuit::{
+            allocated_num::Num, boolean::Boolean, custom_rescue_gate::Rescue5CustomGate,
+        },
+    },
+    glue::prepacked_long_comparison,
+    utils::u64_to_fe,
+    vm::{partitioner::smart_and, primitives::uint256::UInt256},
+};
+use num_bigint::BigUint;
+
+use crate::{
+    gadgets::{
+        ed25519::{self, verify_signature, PUBKEY_BS, SIGNATURE_BS},
+        poseidon::{circuit_poseidon_hash, poseidon_hash},
+        rescue::circuit_rescue_hash,
+        signed::Int64,
+        uint256::to_num_checked,
+    },
+    redstone::{circuit::median_with_quorum, witness::median},
+    utils::{self, fr_from_biguint, num_from_be_bytes},
+};
+
+const LEN_PRICE_ACCOUNT: usize = 32;
+const LEN_PRICE: usize = 8;
+const LEN_CONF: usize = 8;
+const LEN_STATUS: usize = 4;
+const LEN_PUBLISH_SLOT: usize = 8;
+pub const LEN_PUBLISHER_PRICE: usize =
+    LEN_PRICE_ACCOUNT + LEN_PRICE + LEN_CONF + LEN_STATUS + LEN_PUBLISH_SLOT;
+
+/// `PriceStatus::Trading` of pythnet price accounts.
+pub const PRICE_STATUS_TRADING: u32 = 1;
+
+/// Price a publisher submits to a pythnet price account, signed with its ed25519 key over
+/// `price account || price || conf || status || publish slot`, whose integers are little-endian
+/// as laid out in `PriceInfo` of the account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublisherPrice {
+    pub price_account: [u8; LEN_PRICE_ACCOUNT],
+    pub price: i64,
+    pub conf: u64,
+    pub status: u32,
+    pub publish_slot: u64,
+    pub signature: [u8; SIGNATURE_BS],
+}
+
+impl PublisherPrice {
+    pub fn message(&self) -> [u8; LEN_PUBLISHER_PRICE] {
+        let mut message = self.price_account.to_vec();
+        message.extend(self.price.to_le_bytes());
+        message.extend(self.conf.to_le_bytes());
+        message.extend(self.status.to_le_bytes());
+        message.extend(self.publish_slot.to_le_bytes());
+        message.try_into().unwrap()
+    }
+
+    /// Check the price is a positive trading price of `price_account` signed by `publisher`.
+    pub fn check(
+        &self,
+        publisher: &[u8; PUBKEY_BS],
+        price_account: &[u8; LEN_PRICE_ACCOUNT],
+    ) -> anyhow::Result<()> {
+        if &self.price_account != price_account {
+            anyhow::bail!(
+                "price of account 0x{} instead of 0x{}",
+                hex::encode(self.price_account),
+                hex::encode(price_account)
+            )
+        }
+        if self.status != PRICE_STATUS_TRADING || self.price <= 0 {
+            anyhow::bail!("price {} of status {}", self.price, self.status)
+        }
+        if !verify_signature(publisher, &self.message(), &self.signature) {
+            anyhow::bail!("price is not signed by 0x{}", hex::encode(publisher))
+        }
+        Ok(())
+    }
+}
+
+/// `poseidon(pubkey[..16], pubkey[16..] of each publisher)`.
+pub fn publisher_set_hash<E: Engine>(publishers: &[[u8; PUBKEY_BS]]) -> anyhow::Result<E::Fr> {
+    let mut input = vec![];
+    for publisher in publishers {
+        input.push(fr_from_biguint::<E>(&BigUint::from_bytes_be(
+            &publisher[..16],
+        ))?);
+        input.push(fr_from_biguint::<E>(&BigUint::from_bytes_be(
+            &publisher[16..],
+        ))?);
+    }
+    Ok(poseidon_hash::<E>(&input))
+}
+
+fn alloc_bytes<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    bytes: &[u8],
+) -> Result<Vec<Byte<E>>, SynthesisError> {
+    bytes
+        .iter()
+        .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+        .collect()
+}
+
+/// Big-endian bytes of the little-endian integer at `offset` of `message`.
+fn be_bytes<E: Engine>(message: &[Byte<E>], offset: usize, len: usize) -> Vec<Byte<E>> {
+    message[offset..offset + len]
+        .iter()
+        .rev()
+        .copied()
+        .collect()
+}
+
+/// Circuit representation of [`PublisherPrice`].
+#[derive(Debug, Clone)]
+pub struct AllocatedPublisherPrice<E: Engine> {
+    pub message: [Byte<E>; LEN_PUBLISHER_PRICE],
+    pub signature: [Byte<E>; SIGNATURE_BS],
+}
+
+impl<E: Engine> AllocatedPublisherPrice<E> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &PublisherPrice,
+    ) -> Result<Self, SynthesisError> {
+        Ok(Self {
+            message: alloc_bytes(cs, &witness.message())?.try_into().unwrap(),
+            signature: alloc_bytes(cs, &witness.signature)?.try_into().unwrap(),
+        })
+    }
+
+    /// Same as [`PublisherPrice::check`]. Return whether the check passes, the price and the
+    /// publish slot, which are only meaningful if the check passes.
+    pub fn verify<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        publisher: &[Byte<E>; PUBKEY_BS],
+        price_account: &[Byte<E>; LEN_PRICE_ACCOUNT],
+    ) -> Result<(Boolean, UInt256<E>, Num<E>), SynthesisError> {
+        let mut is_valid = vec![];
+        for (byte, expected) in self.message.iter().zip(price_account.iter()) {
+            is_valid.push(Num::equals(cs, &byte.inner, &expected.inner)?);
+        }
+
+        let offset = LEN_PRICE_ACCOUNT;
+        let price_bytes: [Byte<E>; LEN_PRICE] = be_bytes(&self.message, offset, LEN_PRICE)
+            .try_into()
+            .unwrap();
+        let price = Int64::from_be_bytes(cs, &price_bytes)?;
+        let is_zero = price.raw.is_zero(cs)?;
+        is_valid.push(smart_and(cs, &[price.is_negative.not(), is_zero.not()])?);
+        let offset = offset + LEN_PRICE + LEN_CONF;
+        let status = num_from_be_bytes(cs, &be_bytes(&self.message, offset, LEN_STATUS))?;
+        let is_trading =
+            status.equals(cs, &Num::Constant(u64_to_fe(PRICE_STATUS_TRADING as u64)))?;
+        is_valid.push(is_trading);
+        let offset = offset + LEN_STATUS;
+        let publish_slot =
+            num_from_be_bytes(cs, &be_bytes(&self.message, offset, LEN_PUBLISH_SLOT))?;
+
+        is_valid.push(ed25519::verify(
+            cs,
+            publisher,
+            &self.message,
+            &self.signature,
+        )?);
+
+        let mut price_bytes_256 = [Byte::zero(); 32];
+        price_bytes_256[32 - LEN_PRICE..].copy_from_slice(&price_bytes);
+        let price = UInt256::from_be_bytes_fixed(cs, &price_bytes_256)?;
+        Ok((smart_and(cs, &is_valid)?, price, publish_slot))
+    }
+}
+
+/// Circuit verifying prices of a pythnet price account signed by its publishers directly, i.e.
+/// without wormhole guardians.
+///
+/// `prices[i]` is the price of `publishers[i]`, or `None` if it is absent. At least `quorum`
+/// prices must be present. Public input is the commitment `poseidon(publisher set hash,
+/// price account[..16], price account[16..], quorum, median price, earliest publish slot)`, where
+/// the publisher set hash is [`publisher_set_hash`] and the median is the same as
+/// [`median_with_quorum`]. Prices are in the exponent of the price account.
+#[derive(Debug, Clone)]
+pub struct PublisherPriceOracle<E: Engine> {
+    pub price_account: [u8; LEN_PRICE_ACCOUNT],
+    pub publishers: Vec<[u8; PUBKEY_BS]>,
+    pub prices: Vec<Option<PublisherPrice>>,
+    pub quorum: usize,
+    pub commitment: E::Fr,
+}
+
+impl<E: Engine> PublisherPriceOracle<E> {
+    pub fn new(
+        price_account: [u8; LEN_PRICE_ACCOUNT],
+        publishers: Vec<[u8; PUBKEY_BS]>,
+        prices: Vec<Option<PublisherPrice>>,
+        quorum: usize,
+    ) -> anyhow::Result<Self> {
+        if publishers.len() != prices.len() {
+            anyhow::bail!("{} prices of {} publishers", prices.len(), publishers.len())
+        }
+        if quorum == 0 || quorum > publishers.len() {
+            anyhow::bail!(
+                "invalid quorum {} of {} publishers",
+                quorum,
+                publishers.len()
+            )
+        }
+        let mut values = vec![];
+        let mut earliest_slot = u64::MAX;
+        for (publisher, price) in publishers.iter().zip(prices.iter()) {
+            let price = match price {
+                Some(price) => price,
+                None => continue,
+            };
+            price.check(publisher, &price_account)?;
+            values.push(BigUint::from(price.price as u64));
+            earliest_slot = earliest_slot.min(price.publish_slot);
+        }
+        if values.len() < quorum {
+            anyhow::bail!("{} prices are less than quorum {}", values.len(), quorum)
+        }
+        let median = median(&values).expect("quorum is not zero");
+
+        let commitment = poseidon_hash::<E>(&[
+            publisher_set_hash::<E>(&publishers)?,
+            fr_from_biguint::<E>(&BigUint::from_bytes_be(&price_account[..16]))?,
+            fr_from_biguint::<E>(&BigUint::from_bytes_be(&price_account[16..]))?,
+            fr_from_biguint::<E>(&BigUint::from(quorum))?,
+            fr_from_biguint::<E>(&median)?,
+            fr_from_biguint::<E>(&BigUint::from(earliest_slot))?,
+        ]);
+        Ok(Self {
+            price_account,
+            publishers,
+            prices,
+            quorum,
+            commitment,
+        })
+    }
+}
+
+impl<E: Engine> Circuit<E> for PublisherPriceOracle<E> {
+    type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
+
+    fn synthesize<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<(), SynthesisError> {
+        utils::add_bitwise_logic_and_range_table(cs)?;
+        let temp_variable = Num::alloc(cs, Some(E::Fr::one()))?;
+        circuit_rescue_hash(cs, &[temp_variable])?; // Just to standardize the proof format
+
+        let price_account: [Byte<E>; LEN_PRICE_ACCOUNT] =
+            alloc_bytes(cs, &self.price_account)?.try_into().unwrap();
+        let mut publishers = vec![];
+        let mut input = vec![];
+        for publisher in &self.publishers {
+            let publisher: [Byte<E>; PUBKEY_BS] = alloc_bytes(cs, publisher)?.try_into().unwrap();
+            input.push(num_from_be_bytes(cs, &publisher[..16])?);
+            input.push(num_from_be_bytes(cs, &publisher[16..])?);
+            publishers.push(publisher);
+        }
+        let publisher_set_hash = circuit_poseidon_hash(cs, &input)?;
+
+        let mut is_valid = vec![];
+        let mut values = vec![];
+        let mut earliest_slot = Num::Constant(u64_to_fe(u64::MAX));
+        for (publisher, price) in publishers.iter().zip(self.prices.iter()) {
+            let price = match price {
+                Some(price) => AllocatedPublisherPrice::from_witness(cs, price)?,
+                None => {
+                    values.push((Boolean::constant(false), UInt256::zero()));
+                    continue;
+                }
+            };
+            let (is_price_valid, value, publish_slot) =
+                price.verify(cs, publisher, &price_account)?;
+            is_valid.push(is_price_valid);
+            values.push((Boolean::constant(true), value));
+            let (_, is_later) =
+                prepacked_long_comparison(cs, &[earliest_slot], &[publish_slot], &[64])?;
+            earliest_slot =
+                Num::conditionally_select(cs, &is_later, &publish_slot, &earliest_slot)?;
+        }
+        let is_valid = smart_and(cs, &is_valid)?;
+        Boolean::enforce_equal(cs, &is_valid, &Boolean::constant(true))?;
+        let median = median_with_quorum(cs, &values, self.quorum)?;
+
+        let commitment = circuit_poseidon_hash(
+            cs,
+            &[
+                publisher_set_hash,
+                num_from_be_bytes(cs, &price_account[..16])?,
+                num_from_be_bytes(cs, &price_account[16..])?,
+                Num::Constant(u64_to_fe(self.quorum as u64)),
+                to_num_checked(cs, &median)?,
+                earliest_slot,
+            ],
+        )?;
+        let expected_commitment = Num::alloc(cs, Some(self.commitment))?;
+        expected_commitment.enforce_equal(cs, &commitment)?;
+        expected_commitment.get_variable().inputize(cs)?;
+        Ok(())
+    }
+
+    fn declare_used_gates() -> Result<Vec<Box<dyn GateInternal<E>>>, SynthesisError> {
+        Ok(vec![
+            Self::MainGate::default().into_internal(),
+            Rescue5CustomGate.into_internal(), // Just to standardize the proof format
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::bellman::{pairing::ff::Field, plonk::better_better_cs::cs::Circuit},
+        testing::{create_test_artifacts_with_optimized_gate, Bn256},
+    };
+
+    use super::{PublisherPrice, PublisherPriceOracle, PRICE_STATUS_TRADING};
+    use crate::utils::testing::Ed25519Signer;
+
+    fn signed_price(
+        signer: &Ed25519Signer,
+        price_account: [u8; 32],
+        price: i64,
+        publish_slot: u64,
+    ) -> PublisherPrice {
+        let mut signed = PublisherPrice {
+            price_account,
+            price,
+            conf: 1_500_000,
+            status: PRICE_STATUS_TRADING,
+            publish_slot,
+            signature: [0u8; 64],
+        };
+        signed.signature = signer.sign(&signed.message());
+        signed
+    }
+
+    #[test]
+    fn test_publisher_price_oracle() -> anyhow::Result<()> {
+        let price_account = [0x3au8; 32];
+        let signers = Ed25519Signer::generate(3);
+        let publishers = signers.iter().map(|s| s.pubkey()).collect::<Vec<_>>();
+        let prices = vec![
+            Some(signed_price(
+                &signers[0],
+                price_account,
+                4_300_000_000_000,
+                250_000_002,
+            )),
+            None,
+            Some(signed_price(
+                &signers[2],
+                price_account,
+                4_310_000_000_000,
+                250_000_001,
+            )),
+        ];
+
+        let circuit = PublisherPriceOracle::<Bn256>::new(
+            price_account,
+            publishers.clone(),
+            prices.clone(),
+            2,
+        )?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        println!("Roughly {} gates", cs.n());
+
+        let mut tampered = circuit.clone();
+        tampered.commitment.add_assign(&Field::one());
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        tampered.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+
+        // Price signed by another publisher
+        let mut forged = circuit;
+        forged.prices[2] = Some(signed_price(
+            &signers[1],
+            price_account,
+            4_310_000_000_000,
+            250_000_001,
+        ));
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        forged.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+        assert!(PublisherPriceOracle::<Bn256>::new(
+            price_account,
+            publishers.clone(),
+            forged.prices,
+            2
+        )
+        .is_err());
+
+        // Price of another account and quorum are rejected natively
+        let mut prices = prices;
+        prices[0] = Some(signed_price(
+            &signers[0],
+            [0u8; 32],
+            4_300_000_000_000,
+            250_000_002,
+        ));
+        assert!(PublisherPriceOracle::<Bn256>::new(
+            price_account,
+            publishers.clone(),
+            prices.clone(),
+            2
+        )
+        .is_err());
+        prices[0] = None;
+        assert!(PublisherPriceOracle::<Bn256>::new(price_account, publishers, prices, 2).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,261 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    circuit_structures::byte::IntoBytes as _,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    glue::prepacked_long_comparison,
+    utils::u64_to_fe,
+    vm::{
+        partitioner::{smart_and, smart_or},
+        primitives::uint256::UInt256,
+    },
+};
+
+use serde_wormhole::RawMessage;
+use wormhole_sdk::vaa::{Body, Header};
+
+use crate::{
+    gadgets::ethereum::Address, redstone::witness::recover_signer, utils::new_synthesis_error,
+};
+
+use super::wormhole::ParsedVaa;
+
+/// Number of signatures required from a wormhole guardian set, which is more than 2/3 of
+/// guardians, e.g. 13 of 19.
+pub fn guardian_quorum(num_guardians: usize) -> usize {
+    num_guardians * 2 / 3 + 1
+}
+
+/// Check the parsed VAA is signed by a quorum of `guardian_set` over `keccak256(keccak256(body))`.
+///
+/// Guardian indices must be strictly increasing, so that no guardian is counted twice, and each
+/// signature must be recovered to the guardian at its index. As the signature count is fixed by
+/// the parsed VAA, it fails to synthesize if the count is less than the quorum.
+pub fn check_guardian_quorum<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    vaa: &ParsedVaa<E>,
+    guardian_set: &[Address<E>],
+) -> Result<Boolean, SynthesisError> {
+    let quorum = guardian_quorum(guardian_set.len());
+    if guardian_set.is_empty() || vaa.signatures.len() < quorum {
+        return Err(new_synthesis_error(format!(
+            "{} signatures are less than quorum {} of {} guardians",
+            vaa.signatures.len(),
+            quorum,
+            guardian_set.len()
+        )));
+    }
+    let guardians = guardian_set
+        .iter()
+        .map(|g| (Boolean::constant(true), g.clone()))
+        .collect::<Vec<_>>();
+    check_signatures(cs, vaa, &guardians)
+}
+
+/// Check the parsed VAA is signed by a quorum of any of `guardian_sets`, each of which is a
+/// guardian set index and its guardians, e.g. sets `N` and `N + 1` while guardians rotate.
+///
+/// The set is selected by matching its index with the guardian set index of the VAA, so that one
+/// circuit accepts VAAs of either set. Returns whether the VAA is valid and whether each set is
+/// selected. It fails to synthesize if the signature count is less than the quorum of all sets.
+pub fn check_rotating_guardian_quorum<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    vaa: &ParsedVaa<E>,
+    guardian_sets: &[(Num<E>, Vec<Address<E>>)],
+) -> Result<(Boolean, Vec<Boolean>), SynthesisError> {
+    let is_enough = guardian_sets
+        .iter()
+        .map(|(_, set)| !set.is_empty() && vaa.signatures.len() >= guardian_quorum(set.len()))
+        .collect::<Vec<_>>();
+    if !is_enough.iter().any(|b| *b) {
+        return Err(new_synthesis_error(format!(
+            "{} signatures are less than quorum of any guardian set",
+            vaa.signatures.len()
+        )));
+    }
+    let guardian_set_index = vaa.guardian_set_index(cs)?;
+    let max_len = guardian_sets
+        .iter()
+        .map(|(_, set)| set.len())
+        .max()
+        .unwrap_or(0);
+
+    let mut is_selected = vec![];
+    let mut is_set_valid = vec![];
+    for ((index, _), is_enough) in guardian_sets.iter().zip(is_enough) {
+        let is_current = Num::equals(cs, index, &guardian_set_index)?;
+        let is_current_valid = Boolean::and(cs, &is_current, &Boolean::constant(is_enough))?;
+        is_selected.push(is_current);
+        is_set_valid.push(is_current_valid);
+    }
+    // Merge sets into one table, where the guardian at each position is taken from the selected
+    // set, and positions beyond the selected set are absent
+    let mut guardians = vec![];
+    for i in 0..max_len {
+        let mut is_present = Boolean::constant(false);
+        let mut guardian = UInt256::zero();
+        for ((_, set), is_current) in guardian_sets.iter().zip(is_selected.iter()) {
+            if let Some(candidate) = set.get(i) {
+                guardian =
+                    UInt256::conditionally_select(cs, is_current, &candidate.inner(), &guardian)?;
+                is_present = Boolean::or(cs, &is_present, is_current)?;
+            }
+        }
+        guardians.push((is_present, Address::new(guardian)));
+    }
+    let is_signed = check_signatures(cs, vaa, &guardians)?;
+    let is_set_valid = smart_or(cs, &is_set_valid)?;
+    let is_valid = Boolean::and(cs, &is_signed, &is_set_valid)?;
+    Ok((is_valid, is_selected))
+}
+
+// Check signatures of the VAA against `guardians`, each of which is present or not
+fn check_signatures<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    vaa: &ParsedVaa<E>,
+    guardians: &[(Boolean, Address<E>)],
+) -> Result<Boolean, SynthesisError> {
+    let msg_hash = vaa.body_hash(cs)?;
+
+    let mut is_valid = vec![];
+    let mut last_index: Option<Num<E>> = None;
+    for (index, signature) in vaa.guardian_indices.iter().zip(vaa.signatures.iter()) {
+        if let Some(last_index) = last_index {
+            let (_, is_increasing) =
+                prepacked_long_comparison(cs, &[index.inner], &[last_index], &[8])?;
+            is_valid.push(is_increasing);
+        }
+        last_index = Some(index.inner);
+
+        // Select the guardian at index, which must be in range
+        let mut is_in_range = Boolean::constant(false);
+        let mut guardian = UInt256::zero();
+        for (i, (is_present, candidate)) in guardians.iter().enumerate() {
+            let is_selected = Num::equals(cs, &index.inner, &Num::Constant(u64_to_fe(i as u64)))?;
+            guardian =
+                UInt256::conditionally_select(cs, &is_selected, &candidate.inner(), &guardian)?;
+            let is_selected = Boolean::and(cs, &is_selected, is_present)?;
+            is_in_range = Boolean::or(cs, &is_in_range, &is_selected)?;
+        }
+        is_valid.push(is_in_range);
+
+        let (successful, (x, y)) = signature.ecrecover(cs, &msg_hash)?;
+        let (x, y) = (
+            x.into_be_bytes(cs)?.try_into().unwrap(),
+            y.into_be_bytes(cs)?.try_into().unwrap(),
+        );
+        let address = Address::from_pubkey(cs, &x, &y)?;
+        is_valid.push(Address::new(guardian).equals(cs, &address)?);
+        is_valid.push(successful);
+    }
+    smart_and(cs, &is_valid)
+}
+
+/// Native counterpart of [`check_guardian_quorum`], which parses the raw VAA and checks it is
+/// signed by a quorum of `guardian_set`.
+pub fn check_vaa_quorum<'a>(
+    vaa: &'a [u8],
+    guardian_set: &[[u8; 20]],
+) -> anyhow::Result<(Header, Body<&'a RawMessage>)> {
+    let vaa: wormhole_sdk::Vaa<&RawMessage> = serde_wormhole::from_slice(vaa)?;
+    let (header, body): (Header, Body<&RawMessage>) = vaa.into();
+    let quorum = guardian_quorum(guardian_set.len());
+    if guardian_set.is_empty() || header.signatures.len() < quorum {
+        anyhow::bail!(
+            "{} signatures are less than quorum {} of {} guardians",
+            header.signatures.len(),
+            quorum,
+            guardian_set.len()
+        )
+    }
+    let digest = body.digest()?.secp256k_hash;
+    let mut last_index = None;
+    for signature in header.signatures.iter() {
+        if matches!(last_index, Some(last) if signature.index <= last) {
+            anyhow::bail!("guardian indices are not increasing")
+        }
+        last_index = Some(signature.index);
+        let signer = recover_signer(&digest, &signature.signature)?;
+        if guardian_set.get(signature.index as usize) != Some(&signer) {
+            anyhow::bail!("VAA is signed by non-guardian 0x{}", hex::encode(signer))
+        }
+    }
+    Ok((header, body))
+}
+
+/// Native counterpart of [`check_rotating_guardian_quorum`], which checks the raw VAA against
+/// the guardian set of `guardian_sets` at its guardian set index, and returns the position of the
+/// set in `guardian_sets`.
+pub fn check_rotating_vaa_quorum<'a>(
+    vaa: &'a [u8],
+    guardian_sets: &[(u32, Vec<[u8; 20]>)],
+) -> anyhow::Result<(usize, Header, Body<&'a RawMessage>)> {
+    let parsed: wormhole_sdk::Vaa<&RawMessage> = serde_wormhole::from_slice(vaa)?;
+    let position = guardian_sets
+        .iter()
+        .position(|(index, _)| *index == parsed.guardian_set_index)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "VAA is signed by unknown guardian set {}",
+                parsed.guardian_set_index
+            )
+        })?;
+    let (header, body) = check_vaa_quorum(vaa, &guardian_sets[position].1)?;
+    Ok((position, header, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::{bellman::SynthesisError, plonk::circuit::allocated_num::Num},
+        utils::u64_to_fe,
+    };
+
+    use super::{check_guardian_quorum, check_rotating_guardian_quorum, guardian_quorum};
+    use crate::{
+        gadgets::ethereum::Address,
+        pyth::{ParsedVaa, GUARDIAN_SET},
+        utils::testing::{create_test_constraint_system, wormhole_vaa},
+    };
+
+    #[test]
+    fn test_guardian_quorum() -> Result<(), SynthesisError> {
+        assert_eq!(guardian_quorum(19), 13);
+        assert_eq!(guardian_quorum(3), 3);
+
+        let cs = &mut create_test_constraint_system()?;
+        let data = hex::decode(wormhole_vaa()).unwrap();
+        let vaa = ParsedVaa::from_bytes_witness(cs, &data, 13)?;
+        let mut guardian_set = GUARDIAN_SET
+            .iter()
+            .map(|g| Address::from_address_witness(cs, g))
+            .collect::<Result<Vec<_>, _>>()?;
+        let is_valid = check_guardian_quorum(cs, &vaa, &guardian_set)?;
+        assert!(is_valid.get_value().unwrap());
+        assert!(cs.is_satisfied());
+
+        // The VAA of guardian set 3 is accepted whichever set of a rotation it is
+        let retired_set = guardian_set[1..].to_vec();
+        for (index, expected) in [(3, [true, false]), (4, [false, true]), (5, [false, false])] {
+            let guardian_sets = [
+                (Num::Constant(u64_to_fe(index)), guardian_set.clone()),
+                (Num::Constant(u64_to_fe(index - 1)), retired_set.clone()),
+            ];
+            let (is_valid, is_selected) = check_rotating_guardian_quorum(cs, &vaa, &guardian_sets)?;
+            assert_eq!(is_valid.get_value(), Some(index == 3));
+            let is_selected = is_selected
+                .iter()
+                .map(|b| b.get_value().unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(is_selected, expected);
+        }
+        assert!(cs.is_satisfied());
+
+        // 13 signatures are less than quorum of 20 guardians
+        guardian_set.push(guardian_set[0].clone());
+        assert!(check_guardian_quorum(cs, &vaa, &guardian_set).is_err());
+        Ok(())
+    }
+}
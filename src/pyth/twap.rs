@@ -0,0 +1,432 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    glue::prepacked_long_comparison,
+    traits::CSAllocatable,
+    utils::u64_to_fe,
+    vm::partitioner::{smart_and, smart_or},
+};
+use num_bigint::{BigInt, BigUint, Sign};
+use pythnet_sdk::messages::TwapMessage as NativeTwapMessage;
+
+use crate::{
+    gadgets::keccak160::{MerklePath, MerkleRoot},
+    utils::{fr_from_biguint, new_synthesis_error, num_from_be_bytes},
+};
+
+use super::{
+    normalize::fe_to_biguint,
+    price::{merkle_path_from_witness, SignedNum},
+};
+
+const TWAP_MESSAGE_TYPE: u8 = 1;
+const LEN_MESSAGE_TYPE: usize = 1;
+const LEN_FEED_ID: usize = 32;
+const LEN_CUMULATIVE_PRICE: usize = 16;
+const LEN_CUMULATIVE_CONF: usize = 16;
+const LEN_NUM_DOWN_SLOTS: usize = 8;
+const LEN_EXPONENT: usize = 4;
+const LEN_PUBLISH_TIME: usize = 8;
+const LEN_PREV_PUBLISH_TIME: usize = 8;
+const LEN_PUBLISH_SLOT: usize = 8;
+pub const LEN_TWAP_FEED: usize = LEN_MESSAGE_TYPE
+    + LEN_FEED_ID
+    + LEN_CUMULATIVE_PRICE
+    + LEN_CUMULATIVE_CONF
+    + LEN_NUM_DOWN_SLOTS
+    + LEN_EXPONENT
+    + LEN_PUBLISH_TIME
+    + LEN_PREV_PUBLISH_TIME
+    + LEN_PUBLISH_SLOT;
+
+/// Circuit representation of pyth `Message::TwapMessage`, which carries cumulative price and
+/// confidence of a feed over slots, in the same way as [`super::PriceFeed`].
+#[derive(Debug, Clone, Copy)]
+pub struct TwapFeed<E: Engine> {
+    pub message_type: [Byte<E>; LEN_MESSAGE_TYPE],
+    pub feed_id: [Byte<E>; LEN_FEED_ID],
+    pub cumulative_price: [Byte<E>; LEN_CUMULATIVE_PRICE],
+    pub cumulative_conf: [Byte<E>; LEN_CUMULATIVE_CONF],
+    pub num_down_slots: [Byte<E>; LEN_NUM_DOWN_SLOTS],
+    pub exponent: [Byte<E>; LEN_EXPONENT],
+    pub publish_time: [Byte<E>; LEN_PUBLISH_TIME],
+    pub prev_publish_time: [Byte<E>; LEN_PREV_PUBLISH_TIME],
+    pub publish_slot: [Byte<E>; LEN_PUBLISH_SLOT],
+}
+
+impl<E: Engine> TwapFeed<E> {
+    pub fn from_bytes_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &[u8],
+    ) -> Result<Self, SynthesisError> {
+        let witness: [u8; LEN_TWAP_FEED] = witness.try_into().map_err(|_| {
+            new_synthesis_error(format!(
+                "invalid twap message length {}, expect {}",
+                witness.len(),
+                LEN_TWAP_FEED
+            ))
+        })?;
+        let bytes = CSAllocatable::alloc_from_witness(cs, Some(witness))?;
+        Ok(Self::from_bytes(&bytes))
+    }
+
+    /// Split message bytes into fields.
+    pub fn from_bytes(bytes: &[Byte<E>; LEN_TWAP_FEED]) -> Self {
+        let (message_type, rest) = bytes.split_at(LEN_MESSAGE_TYPE);
+        let (feed_id, rest) = rest.split_at(LEN_FEED_ID);
+        let (cumulative_price, rest) = rest.split_at(LEN_CUMULATIVE_PRICE);
+        let (cumulative_conf, rest) = rest.split_at(LEN_CUMULATIVE_CONF);
+        let (num_down_slots, rest) = rest.split_at(LEN_NUM_DOWN_SLOTS);
+        let (exponent, rest) = rest.split_at(LEN_EXPONENT);
+        let (publish_time, rest) = rest.split_at(LEN_PUBLISH_TIME);
+        let (prev_publish_time, publish_slot) = rest.split_at(LEN_PREV_PUBLISH_TIME);
+        Self {
+            message_type: message_type.try_into().unwrap(),
+            feed_id: feed_id.try_into().unwrap(),
+            cumulative_price: cumulative_price.try_into().unwrap(),
+            cumulative_conf: cumulative_conf.try_into().unwrap(),
+            num_down_slots: num_down_slots.try_into().unwrap(),
+            exponent: exponent.try_into().unwrap(),
+            publish_time: publish_time.try_into().unwrap(),
+            prev_publish_time: prev_publish_time.try_into().unwrap(),
+            publish_slot: publish_slot.try_into().unwrap(),
+        }
+    }
+
+    pub fn to_bytes(&self) -> [Byte<E>; LEN_TWAP_FEED] {
+        let bytes = [
+            &self.message_type[..],
+            &self.feed_id,
+            &self.cumulative_price,
+            &self.cumulative_conf,
+            &self.num_down_slots,
+            &self.exponent,
+            &self.publish_time,
+            &self.prev_publish_time,
+            &self.publish_slot,
+        ]
+        .concat();
+        bytes.try_into().unwrap()
+    }
+}
+
+/// TWAP message of a feed and its merkle proof, in the same way as [`super::PriceUpdate`].
+///
+/// `N` is the depth of merkle tree used by pyth, which is `10` by now.
+#[derive(Debug, Clone, Copy)]
+pub struct TwapUpdate<E: Engine, const N: usize = 10> {
+    pub message: TwapFeed<E>,
+    pub proof: MerklePath<E, N>,
+}
+
+impl<E: Engine, const N: usize> TwapUpdate<E, N> {
+    pub fn from_price_update_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: pythnet_sdk::wire::v1::MerklePriceUpdate,
+    ) -> Result<Self, SynthesisError> {
+        let message: Vec<u8> = witness.message.into();
+        let message = TwapFeed::from_bytes_witness(cs, &message)?;
+        let proof = merkle_path_from_witness(cs, &witness.proof.to_bytes())?;
+        Ok(Self { message, proof })
+    }
+
+    pub fn check<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        root: &MerkleRoot<E>,
+    ) -> Result<Boolean, SynthesisError> {
+        root.check(cs, &self.proof, &self.message.to_bytes())
+    }
+}
+
+/// Typed values of pyth [`TwapFeed`], decoded from its bytes.
+#[derive(Debug, Clone, Copy)]
+pub struct TwapMessage<E: Engine> {
+    pub feed_id: [Byte<E>; LEN_FEED_ID],
+    pub cumulative_price: SignedNum<E>,
+    pub cumulative_conf: Num<E>,
+    pub num_down_slots: Num<E>,
+    pub exponent: SignedNum<E>,
+    pub publish_time: Num<E>,
+    pub prev_publish_time: Num<E>,
+    pub publish_slot: Num<E>,
+}
+
+impl<E: Engine> TwapMessage<E> {
+    /// Decode fields of `twap_feed`, which must be of type `TwapMessage`.
+    pub fn from_twap_feed<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        twap_feed: &TwapFeed<E>,
+    ) -> Result<Self, SynthesisError> {
+        twap_feed.message_type[0]
+            .inner
+            .enforce_equal(cs, &Num::Constant(u64_to_fe(TWAP_MESSAGE_TYPE as u64)))?;
+        Ok(Self {
+            feed_id: twap_feed.feed_id,
+            cumulative_price: SignedNum::from_be_bytes(cs, &twap_feed.cumulative_price)?,
+            cumulative_conf: num_from_be_bytes(cs, &twap_feed.cumulative_conf)?,
+            num_down_slots: num_from_be_bytes(cs, &twap_feed.num_down_slots)?,
+            exponent: SignedNum::from_be_bytes(cs, &twap_feed.exponent)?,
+            publish_time: num_from_be_bytes(cs, &twap_feed.publish_time)?,
+            prev_publish_time: num_from_be_bytes(cs, &twap_feed.prev_publish_time)?,
+            publish_slot: num_from_be_bytes(cs, &twap_feed.publish_slot)?,
+        })
+    }
+}
+
+/// Time weighted average price of a feed between two TWAP messages, where the real price is
+/// `price * 10^exponent`.
+#[derive(Debug, Clone, Copy)]
+pub struct Twap<E: Engine> {
+    pub feed_id: [Byte<E>; LEN_FEED_ID],
+    pub price: SignedNum<E>,
+    pub conf: Num<E>,
+    pub exponent: SignedNum<E>,
+    pub start_time: Num<E>,
+    pub end_time: Num<E>,
+}
+
+/// Average price and confidence between `start` and `end`, which is the difference of cumulative
+/// values divided by the slot gap, rounding toward zero as pyth does.
+pub fn calculate_twap(
+    start: &NativeTwapMessage,
+    end: &NativeTwapMessage,
+) -> anyhow::Result<(i128, u128)> {
+    if start.feed_id != end.feed_id || start.exponent != end.exponent {
+        anyhow::bail!("twap messages are of different feeds")
+    }
+    if end.publish_slot <= start.publish_slot {
+        anyhow::bail!(
+            "end slot {} is not after start slot {}",
+            end.publish_slot,
+            start.publish_slot
+        )
+    }
+    let slots = (end.publish_slot - start.publish_slot) as i128;
+    let price = end
+        .cumulative_price
+        .checked_sub(start.cumulative_price)
+        .ok_or_else(|| anyhow::anyhow!("cumulative price overflows"))?;
+    let conf = end
+        .cumulative_conf
+        .checked_sub(start.cumulative_conf)
+        .ok_or_else(|| anyhow::anyhow!("cumulative conf is decreasing"))?;
+    Ok((price / slots, conf / slots as u128))
+}
+
+/// Same as [`calculate_twap`] in circuit. The returned boolean is false if messages are of
+/// different feeds, slots are not increasing or cumulative conf is decreasing, in which case
+/// the TWAP is meaningless.
+pub fn circuit_twap<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    start: &TwapMessage<E>,
+    end: &TwapMessage<E>,
+) -> Result<(Boolean, Twap<E>), SynthesisError> {
+    let mut is_valid = vec![];
+    for (a, b) in start.feed_id.iter().zip(end.feed_id.iter()) {
+        is_valid.push(Num::equals(cs, &a.inner, &b.inner)?);
+    }
+    is_valid.push(Num::equals(cs, &start.exponent.abs, &end.exponent.abs)?);
+    is_valid.push(Num::equals(
+        cs,
+        &Num::from_boolean_is(start.exponent.is_negative),
+        &Num::from_boolean_is(end.exponent.is_negative),
+    )?);
+
+    // Fall back to dividing by 1 for invalid slots, so that constraints are always satisfiable
+    let (_, is_later) =
+        prepacked_long_comparison(cs, &[end.publish_slot], &[start.publish_slot], &[64])?;
+    is_valid.push(is_later);
+    let slots = end.publish_slot.sub(cs, &start.publish_slot)?;
+    let slots = Num::conditionally_select(cs, &is_later, &slots, &Num::one())?;
+
+    // Signed difference of cumulative prices, which is less than 2^129 in absolute value
+    let witness = match (
+        signed_value(&start.cumulative_price),
+        signed_value(&end.cumulative_price),
+    ) {
+        (Some(start), Some(end)) => {
+            let diff = end - start;
+            Some((
+                diff.sign() == Sign::Minus,
+                fr_from_biguint::<E>(diff.magnitude())?,
+            ))
+        }
+        _ => None,
+    };
+    let is_diff_negative = Boolean::alloc(cs, witness.map(|(is_negative, _)| is_negative))?;
+    let diff_abs = Num::alloc(cs, witness.map(|(_, abs)| abs))?;
+    diff_abs.into_bits_le(cs, Some(129))?;
+    let start_value = start.cumulative_price.to_num(cs)?;
+    let end_value = end.cumulative_price.to_num(cs)?;
+    let diff = end_value.sub(cs, &start_value)?;
+    let signed_diff = SignedNum {
+        is_negative: is_diff_negative,
+        abs: diff_abs,
+    }
+    .to_num(cs)?;
+    diff.enforce_equal(cs, &signed_diff)?;
+    let price = div_floor(cs, &diff_abs, &slots, 129)?;
+    let is_zero = Num::equals(cs, &price, &Num::zero())?;
+    let is_negative = Boolean::and(cs, &is_diff_negative, &is_zero.not())?;
+
+    let (is_equal, is_greater) =
+        prepacked_long_comparison(cs, &[end.cumulative_conf], &[start.cumulative_conf], &[128])?;
+    let is_conf_increasing = smart_or(cs, &[is_equal, is_greater])?;
+    is_valid.push(is_conf_increasing);
+    let conf_diff = end.cumulative_conf.sub(cs, &start.cumulative_conf)?;
+    let conf_diff = Num::conditionally_select(cs, &is_conf_increasing, &conf_diff, &Num::zero())?;
+    let conf = div_floor(cs, &conf_diff, &slots, 128)?;
+
+    let twap = Twap {
+        feed_id: end.feed_id,
+        price: SignedNum {
+            is_negative,
+            abs: price,
+        },
+        conf,
+        exponent: end.exponent,
+        start_time: start.publish_time,
+        end_time: end.publish_time,
+    };
+    Ok((smart_and(cs, &is_valid)?, twap))
+}
+
+fn signed_value<E: Engine>(num: &SignedNum<E>) -> Option<BigInt> {
+    let abs = fe_to_biguint(&num.abs.get_value()?);
+    let sign = if num.is_negative.get_value()? {
+        Sign::Minus
+    } else {
+        Sign::Plus
+    };
+    Some(BigInt::from_biguint(sign, abs))
+}
+
+// Quotient of `dividend < 2^dividend_bits` divided by non-zero `divisor < 2^64`, which is proved
+// by `dividend = quotient * divisor + remainder` where `remainder < divisor`.
+fn div_floor<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    dividend: &Num<E>,
+    divisor: &Num<E>,
+    dividend_bits: usize,
+) -> Result<Num<E>, SynthesisError> {
+    let witness = match (dividend.get_value(), divisor.get_value()) {
+        (Some(dividend), Some(divisor)) => {
+            let (dividend, divisor) = (fe_to_biguint(&dividend), fe_to_biguint(&divisor));
+            if divisor == BigUint::from(0u8) {
+                return Err(new_synthesis_error("division by zero"));
+            }
+            Some((
+                fr_from_biguint::<E>(&(&dividend / &divisor))?,
+                fr_from_biguint::<E>(&(&dividend % &divisor))?,
+            ))
+        }
+        _ => None,
+    };
+    let quotient = Num::alloc(cs, witness.map(|(q, _)| q))?;
+    let remainder = Num::alloc(cs, witness.map(|(_, r)| r))?;
+    quotient.into_bits_le(cs, Some(dividend_bits))?;
+    remainder.into_bits_le(cs, Some(64))?;
+    let recomposed = quotient.mul(cs, divisor)?.add(cs, &remainder)?;
+    recomposed.enforce_equal(cs, dividend)?;
+    let (_, is_remainder_less) = prepacked_long_comparison(cs, &[*divisor], &[remainder], &[64])?;
+    Boolean::enforce_equal(cs, &is_remainder_less, &Boolean::constant(true))?;
+    Ok(quotient)
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::bellman::{
+            pairing::{bn256::Fr, ff::PrimeField},
+            SynthesisError,
+        },
+        traits::CSAllocatable,
+    };
+    use pythnet_sdk::{
+        messages::Message,
+        wire::{from_slice, v1::MerklePriceUpdate},
+    };
+
+    use super::{calculate_twap, circuit_twap, TwapMessage, TwapUpdate};
+    use crate::{
+        gadgets::keccak160::{native_merkle_root, MerkleRoot},
+        utils::testing::create_test_constraint_system,
+    };
+
+    fn twap_message(cumulative_price: i128, cumulative_conf: u128, publish_slot: u64) -> Vec<u8> {
+        let mut bytes = vec![1u8];
+        bytes.extend([0xe6; 32]);
+        bytes.extend(cumulative_price.to_be_bytes());
+        bytes.extend(cumulative_conf.to_be_bytes());
+        bytes.extend(2u64.to_be_bytes()); // num down slots
+        bytes.extend((-8i32).to_be_bytes());
+        bytes.extend((publish_slot as i64 / 2).to_be_bytes());
+        bytes.extend((publish_slot as i64 / 2 - 1).to_be_bytes());
+        bytes.extend(publish_slot.to_be_bytes());
+        bytes
+    }
+
+    #[test]
+    fn test_twap() -> Result<(), SynthesisError> {
+        let cases = [
+            (
+                twap_message(1000, 10, 100),
+                twap_message(3001, 40, 110),
+                true,
+            ),
+            // Cumulative price may be negative and decreasing
+            (
+                twap_message(-7, 10, 100),
+                twap_message(-3000, 40, 103),
+                true,
+            ),
+            // Slots are not increasing
+            (
+                twap_message(1000, 10, 100),
+                twap_message(3000, 40, 100),
+                false,
+            ),
+        ];
+        for (start, end, expected) in cases {
+            let native = |bytes: &[u8]| match from_slice::<byteorder::BE, Message>(bytes) {
+                Ok(Message::TwapMessage(message)) => message,
+                _ => panic!("invalid twap message"),
+            };
+            let twap = calculate_twap(&native(&start), &native(&end));
+            assert_eq!(twap.is_ok(), expected);
+
+            let cs = &mut create_test_constraint_system()?;
+            let mut updates = vec![];
+            for message in [&start, &end] {
+                let path = (1..=10u8).map(|i| [i; 20]).collect::<Vec<_>>();
+                let root = native_merkle_root(message, &path);
+                let mut bytes = (message.len() as u16).to_be_bytes().to_vec();
+                bytes.extend(message);
+                bytes.push(10);
+                bytes.extend(path.concat());
+                let update = from_slice::<byteorder::BE, MerklePriceUpdate>(&bytes).unwrap();
+                let update = TwapUpdate::<_, 10>::from_price_update_witness(cs, update)?;
+                let root = MerkleRoot::new(CSAllocatable::alloc_from_witness(cs, Some(root))?);
+                assert!(update.check(cs, &root)?.get_value().unwrap());
+                updates.push(TwapMessage::from_twap_feed(cs, &update.message)?);
+            }
+            let (is_valid, circuit) = circuit_twap(cs, &updates[0], &updates[1])?;
+            assert_eq!(is_valid.get_value(), Some(expected));
+            if let Ok((price, conf)) = twap {
+                assert_eq!(circuit.price.is_negative.get_value(), Some(price < 0));
+                assert_eq!(
+                    circuit.price.abs.get_value(),
+                    Fr::from_str(&price.unsigned_abs().to_string())
+                );
+                assert_eq!(circuit.conf.get_value(), Fr::from_str(&conf.to_string()));
+            }
+            assert!(cs.is_satisfied());
+        }
+        Ok(())
+    }
+}
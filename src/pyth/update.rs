@@ -0,0 +1,347 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{ff::Field, Engine};
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    glue::prepacked_long_comparison,
+    utils::u64_to_fe,
+    vm::{
+        partitioner::{smart_and, smart_or},
+        primitives::uint256::UInt256,
+    },
+};
+use num_bigint::BigUint;
+use pythnet_sdk::{
+    messages::PriceFeedMessage as PriceFeedMessageWitness, wire::v1::MerklePriceUpdate,
+};
+
+use crate::{
+    gadgets::{
+        ethereum::Address,
+        keccak160::MerkleRoot,
+        poseidon::{circuit_poseidon_hash, poseidon_hash},
+    },
+    utils::{fr_from_biguint, num_from_be_bytes},
+};
+
+use super::{
+    normalize::circuit_normalize_price,
+    price::{PriceFeedMessage, PriceUpdate, SignedNum},
+    quorum::{check_guardian_quorum, check_rotating_guardian_quorum},
+    wormhole::ParsedVaa,
+};
+
+/// Price of a feed verified by [`CircuitPriceUpdate`], where the real price is
+/// `price * 10^exponent`.
+#[derive(Debug, Clone, Copy)]
+pub struct VerifiedPrice<E: Engine> {
+    pub feed_id: [Byte<E>; 32],
+    pub price: SignedNum<E>,
+    pub conf: Num<E>,
+    pub exponent: SignedNum<E>,
+    pub publish_time: Num<E>,
+    pub ema_price: SignedNum<E>,
+    pub ema_conf: Num<E>,
+}
+
+impl<E: Engine> VerifiedPrice<E> {
+    /// Check `conf * ratio <= price`, i.e. the confidence interval is narrow enough to price
+    /// collateral. Negative prices never pass.
+    pub fn is_confidence_within<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        ratio: u32,
+    ) -> Result<Boolean, SynthesisError> {
+        let bound = self.conf.mul(cs, &Num::Constant(u64_to_fe(ratio as u64)))?;
+        // price < 2^64 and bound < 2^96
+        let (is_equal, is_greater) =
+            prepacked_long_comparison(cs, &[self.price.abs], &[bound], &[96])?;
+        let is_within = smart_or(cs, &[is_equal, is_greater])?;
+        Boolean::and(cs, &is_within, &self.price.is_negative.not())
+    }
+
+    /// Price rescaled to `target_decimals`, which is comparable with RedStone values.
+    pub fn normalized_price<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        target_decimals: usize,
+    ) -> Result<UInt256<E>, SynthesisError> {
+        circuit_normalize_price(cs, &self.price, &self.exponent, target_decimals)
+    }
+
+    /// EMA price rescaled to `target_decimals` in the same way as [`Self::normalized_price`].
+    pub fn normalized_ema_price<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        target_decimals: usize,
+    ) -> Result<UInt256<E>, SynthesisError> {
+        circuit_normalize_price(cs, &self.ema_price, &self.exponent, target_decimals)
+    }
+}
+
+impl<E: Engine> VerifiedPrice<E> {
+    /// Members of the price absorbed by [`circuit_price_set_commitment`].
+    pub fn commitment_members<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<[Num<E>; 5], SynthesisError> {
+        Ok([
+            num_from_be_bytes(cs, &self.feed_id[..16])?,
+            num_from_be_bytes(cs, &self.feed_id[16..])?,
+            self.price.to_num(cs)?,
+            self.exponent.to_num(cs)?,
+            self.publish_time,
+        ])
+    }
+}
+
+/// Absorb verified prices and other outputs into one Poseidon hash, in the same way as the output
+/// commitment of RedStone circuits, which is `poseidon(prices[0] || prices[1] || ... || outputs)`.
+///
+/// Each price is encoded as `(feed_id[..16], feed_id[16..], price, exponent, publish_time)`, where
+/// feed id is split into two big-endian 128-bit limbs, and signed values are field elements, i.e.
+/// `r - |x|` for negative `x` where `r` is the scalar field modulus.
+pub fn circuit_price_set_commitment<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    prices: &[VerifiedPrice<E>],
+    outputs: &[Num<E>],
+) -> Result<Num<E>, SynthesisError> {
+    let mut members = vec![];
+    for price in prices.iter() {
+        members.extend(price.commitment_members(cs)?);
+    }
+    members.extend_from_slice(outputs);
+    circuit_poseidon_hash(cs, &members)
+}
+
+/// Native counterpart of [`circuit_price_set_commitment`], which consumer contracts recompute.
+pub fn price_set_commitment<E: Engine>(
+    prices: &[PriceFeedMessageWitness],
+    outputs: &[E::Fr],
+) -> anyhow::Result<E::Fr> {
+    let signed = |value: i64| -> anyhow::Result<E::Fr> {
+        let abs = fr_from_biguint::<E>(&BigUint::from(value.unsigned_abs()))?;
+        Ok(if value < 0 {
+            let mut negated = E::Fr::zero();
+            negated.sub_assign(&abs);
+            negated
+        } else {
+            abs
+        })
+    };
+    let mut members = vec![];
+    for price in prices.iter() {
+        members.push(fr_from_biguint::<E>(&BigUint::from_bytes_be(
+            &price.feed_id[..16],
+        ))?);
+        members.push(fr_from_biguint::<E>(&BigUint::from_bytes_be(
+            &price.feed_id[16..],
+        ))?);
+        members.push(signed(price.price)?);
+        members.push(signed(price.exponent as i64)?);
+        members.push(signed(price.publish_time)?);
+    }
+    members.extend_from_slice(outputs);
+    Ok(poseidon_hash::<E>(&members))
+}
+
+/// Native counterpart of [`VerifiedPrice::is_confidence_within`].
+pub fn is_confidence_within(price: i64, conf: u64, ratio: u32) -> bool {
+    price >= 0 && conf as u128 * ratio as u128 <= price as u128
+}
+
+/// Pyth price updates verified end to end in circuit: the raw VAA is emitted by the pyth
+/// accumulator on Pythnet and signed by a quorum of guardians, and each price update is proven
+/// against the merkle root carried by the VAA.
+///
+/// `NUM_UPDATES` is the number of price updates. `DEPTH` is the depth of pyth merkle tree.
+#[derive(Debug, Clone)]
+pub struct CircuitPriceUpdate<E: Engine, const NUM_UPDATES: usize, const DEPTH: usize = 10> {
+    pub vaa: ParsedVaa<E>,
+    pub price_updates: [PriceUpdate<E, DEPTH>; NUM_UPDATES],
+    /// If set, every price must satisfy `conf * ratio <= price`.
+    pub confidence_ratio: Option<u32>,
+}
+
+impl<E: Engine, const NUM_UPDATES: usize, const DEPTH: usize>
+    CircuitPriceUpdate<E, NUM_UPDATES, DEPTH>
+{
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        vaa: &[u8],
+        num_signatures: usize,
+        price_updates: [MerklePriceUpdate; NUM_UPDATES],
+    ) -> Result<Self, SynthesisError> {
+        let vaa = ParsedVaa::from_bytes_witness(cs, vaa, num_signatures)?;
+        let price_updates = price_updates
+            .into_iter()
+            .map(|update| PriceUpdate::from_price_update_witness(cs, update))
+            .collect::<Result<Vec<_>, _>>()?
+            .try_into()
+            .unwrap();
+        Ok(Self {
+            vaa,
+            price_updates,
+            confidence_ratio: None,
+        })
+    }
+
+    /// Reject price updates whose confidence interval is wider than `1 / ratio` of price.
+    pub fn with_confidence_ratio(mut self, ratio: u32) -> Self {
+        self.confidence_ratio = Some(ratio);
+        self
+    }
+
+    /// Check the VAA against `guardian_set` and price updates against its merkle root, and
+    /// return decoded prices, which are only meaningful if the check passes.
+    pub fn verify<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        guardian_set: &[Address<E>],
+    ) -> Result<(Boolean, [VerifiedPrice<E>; NUM_UPDATES]), SynthesisError> {
+        let (is_root_valid, root) = verify_vaa_root(cs, &self.vaa, guardian_set)?;
+        let mut is_valid = vec![is_root_valid];
+        let mut prices = vec![];
+        for price_update in self.price_updates.iter() {
+            let (is_update_valid, price) =
+                verify_price_update(cs, &root, price_update, self.confidence_ratio)?;
+            is_valid.push(is_update_valid);
+            prices.push(price);
+        }
+        Ok((smart_and(cs, &is_valid)?, prices.try_into().unwrap()))
+    }
+}
+
+/// Check the VAA is emitted by the pyth accumulator with a `WormholeMerkle` payload and signed by
+/// a quorum of `guardian_set`, and return its merkle root. It is the expensive part of
+/// verification, which is done once per VAA and amortized by proving any number of price updates
+/// against the root.
+pub fn verify_vaa_root<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    vaa: &ParsedVaa<E>,
+    guardian_set: &[Address<E>],
+) -> Result<(Boolean, MerkleRoot<E>), SynthesisError> {
+    let is_signed = check_guardian_quorum(cs, vaa, guardian_set)?;
+    accumulator_root(cs, vaa, is_signed)
+}
+
+/// Same as [`verify_vaa_root`], but the VAA may be signed by any of `guardian_sets` selected by
+/// its guardian set index, see [`check_rotating_guardian_quorum`]. Returns whether each set is
+/// selected as well.
+pub fn verify_rotating_vaa_root<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    vaa: &ParsedVaa<E>,
+    guardian_sets: &[(Num<E>, Vec<Address<E>>)],
+) -> Result<(Boolean, MerkleRoot<E>, Vec<Boolean>), SynthesisError> {
+    let (is_signed, is_selected) = check_rotating_guardian_quorum(cs, vaa, guardian_sets)?;
+    let (is_valid, root) = accumulator_root(cs, vaa, is_signed)?;
+    Ok((is_valid, root, is_selected))
+}
+
+fn accumulator_root<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    vaa: &ParsedVaa<E>,
+    is_signed: Boolean,
+) -> Result<(Boolean, MerkleRoot<E>), SynthesisError> {
+    let body = vaa.pyth_body()?;
+    let is_emitter_valid = body.is_emitted_by_pythnet(cs)?;
+    let is_merkle = body.payload.is_merkle(cs)?;
+    let is_valid = smart_and(cs, &[is_signed, is_emitter_valid, is_merkle])?;
+    Ok((is_valid, body.payload.root))
+}
+
+/// Prove the price update against `root` verified by [`verify_vaa_root`] and decode its price,
+/// optionally bounding the confidence interval by `confidence_ratio`.
+pub fn verify_price_update<E: Engine, CS: ConstraintSystem<E>, const DEPTH: usize>(
+    cs: &mut CS,
+    root: &MerkleRoot<E>,
+    price_update: &PriceUpdate<E, DEPTH>,
+    confidence_ratio: Option<u32>,
+) -> Result<(Boolean, VerifiedPrice<E>), SynthesisError> {
+    let mut is_valid = price_update.check(cs, root)?;
+    let message = PriceFeedMessage::from_price_feed(cs, &price_update.message)?;
+    let price = VerifiedPrice {
+        feed_id: message.feed_id,
+        price: message.price,
+        conf: message.conf,
+        exponent: message.exponent,
+        publish_time: message.publish_time,
+        ema_price: message.ema_price,
+        ema_conf: message.ema_conf,
+    };
+    if let Some(ratio) = confidence_ratio {
+        let is_confidence_within = price.is_confidence_within(cs, ratio)?;
+        is_valid = Boolean::and(cs, &is_valid, &is_confidence_within)?;
+    }
+    Ok((is_valid, price))
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::franklin_crypto::bellman::{
+        pairing::{bn256::Fr, ff::PrimeField},
+        SynthesisError,
+    };
+    use pythnet_sdk::wire::{from_slice, v1::MerklePriceUpdate};
+
+    use super::CircuitPriceUpdate;
+    use crate::{
+        gadgets::ethereum::Address,
+        pyth::GUARDIAN_SET,
+        utils::testing::{
+            bytes_assert_eq, create_test_constraint_system, pyth_price_update, wormhole_vaa,
+        },
+    };
+
+    #[test]
+    fn test_circuit_price_update() -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let update = || {
+            let data = hex::decode(pyth_price_update()).unwrap();
+            from_slice::<byteorder::BE, MerklePriceUpdate>(&data).unwrap()
+        };
+        let vaa = hex::decode(wormhole_vaa()).unwrap();
+        let circuit = CircuitPriceUpdate::<_, 1>::from_witness(cs, &vaa, 13, [update()])?;
+        let guardian_set = GUARDIAN_SET
+            .iter()
+            .map(|g| Address::from_address_witness(cs, g))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (is_valid, [price]) = circuit.verify(cs, &guardian_set)?;
+        assert!(is_valid.get_value().unwrap());
+        assert!(cs.is_satisfied());
+        bytes_assert_eq(
+            &price.feed_id,
+            "e62df6c8b4a85fe1a67db44dc12de5db330f7ac66b72dc658afedf0f4a415b43",
+        );
+        assert_eq!(price.price.abs.get_value(), Fr::from_str("3652890574272"));
+        assert_eq!(price.exponent.is_negative.get_value(), Some(true));
+        assert_eq!(price.publish_time.get_value(), Fr::from_str("1700581367"));
+        assert_eq!(
+            price.ema_price.abs.get_value(),
+            Fr::from_str("3671402700000")
+        );
+        assert_eq!(price.ema_conf.get_value(), Fr::from_str("1527607570"));
+
+        // ETH/USD price 3652890574272 with conf 1122941430 is within ratio 3000, but not 4000
+        assert!(super::is_confidence_within(3652890574272, 1122941430, 3000));
+        assert!(!super::is_confidence_within(
+            3652890574272,
+            1122941430,
+            4000
+        ));
+        for (ratio, expected) in [(3000, true), (4000, false)] {
+            let circuit = circuit.clone().with_confidence_ratio(ratio);
+            let (is_valid, _) = circuit.verify(cs, &guardian_set)?;
+            assert_eq!(is_valid.get_value(), Some(expected));
+        }
+
+        // Update not included in the VAA root is rejected
+        let mut circuit = circuit;
+        circuit.price_updates[0].message.price = circuit.price_updates[0].message.conf;
+        let (is_valid, _) = circuit.verify(cs, &guardian_set)?;
+        assert!(!is_valid.get_value().unwrap());
+        Ok(())
+    }
+}
@@ -3,9 +3,10 @@ use advanced_circuit_component::{
     circuit_structures::byte::{Byte, IntoBytes as _},
     franklin_crypto::{
         bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
-        plonk::circuit::boolean::Boolean,
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
     },
     traits::CSAllocatable,
+    utils::u64_to_fe,
     vm::{
         partitioner::{smart_and, smart_or},
         primitives::uint256::UInt256,
@@ -18,9 +19,166 @@ use crate::{
         ethereum::Address,
         keccak160::{self, MerkleRoot},
     },
-    utils::new_synthesis_error,
+    utils::{new_synthesis_error, num_from_be_bytes},
 };
 
+use super::params::{PYTHNET_EMITTER_ADDRESS, PYTHNET_EMITTER_CHAIN};
+
+const LEN_VAA_VERSION: usize = 1;
+const LEN_VAA_GUARDIAN_SET_INDEX: usize = 4;
+const LEN_VAA_NUM_SIGNATURES: usize = 1;
+const LEN_VAA_GUARDIAN_INDEX: usize = 1;
+const LEN_VAA_SIGNATURE: usize = 65;
+const VAA_VERSION: u8 = 1;
+
+/// Wormhole VAA parsed in circuit from its raw bytes
+/// `version || guardian set index || signature count || (guardian index || signature) * count || body`.
+///
+/// The signature count is fixed by circuit, so offsets of all fields are constants, and every
+/// field is sliced from the same allocated bytes. Version and signature count are enforced.
+#[derive(Debug, Clone)]
+pub struct ParsedVaa<E: Engine> {
+    pub version: Byte<E>,
+    pub guardian_set_index: [Byte<E>; LEN_VAA_GUARDIAN_SET_INDEX],
+    pub num_signatures: Byte<E>,
+    pub guardian_indices: Vec<Byte<E>>,
+    pub signatures: Vec<Signature<E>>,
+    pub body: Vec<Byte<E>>,
+}
+
+impl<E: Engine> ParsedVaa<E> {
+    /// Length of VAA header with `num_signatures` signatures, which is also the offset of body.
+    pub fn header_len(num_signatures: usize) -> usize {
+        LEN_VAA_VERSION
+            + LEN_VAA_GUARDIAN_SET_INDEX
+            + LEN_VAA_NUM_SIGNATURES
+            + num_signatures * (LEN_VAA_GUARDIAN_INDEX + LEN_VAA_SIGNATURE)
+    }
+
+    pub fn from_bytes_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &[u8],
+        num_signatures: usize,
+    ) -> Result<Self, SynthesisError> {
+        let bytes = witness
+            .iter()
+            .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::from_bytes(cs, &bytes, num_signatures)
+    }
+
+    pub fn from_bytes<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        bytes: &[Byte<E>],
+        num_signatures: usize,
+    ) -> Result<Self, SynthesisError> {
+        let header_len = Self::header_len(num_signatures);
+        if num_signatures > u8::MAX as usize || bytes.len() <= header_len {
+            return Err(new_synthesis_error(format!(
+                "invalid VAA of {} bytes with {} signatures",
+                bytes.len(),
+                num_signatures
+            )));
+        }
+        let mut offset = 0;
+        let version = bytes[offset];
+        version
+            .inner
+            .enforce_equal(cs, &Num::Constant(u64_to_fe(VAA_VERSION as u64)))?;
+        offset += LEN_VAA_VERSION;
+        let guardian_set_index = bytes[offset..offset + LEN_VAA_GUARDIAN_SET_INDEX]
+            .try_into()
+            .unwrap();
+        offset += LEN_VAA_GUARDIAN_SET_INDEX;
+        let num_signatures_byte = bytes[offset];
+        num_signatures_byte
+            .inner
+            .enforce_equal(cs, &Num::Constant(u64_to_fe(num_signatures as u64)))?;
+        offset += LEN_VAA_NUM_SIGNATURES;
+
+        let mut guardian_indices = vec![];
+        let mut signatures = vec![];
+        for _ in 0..num_signatures {
+            guardian_indices.push(bytes[offset]);
+            offset += LEN_VAA_GUARDIAN_INDEX;
+            let signature = bytes[offset..offset + LEN_VAA_SIGNATURE]
+                .try_into()
+                .unwrap();
+            signatures.push(Signature::from_bytes(cs, signature)?);
+            offset += LEN_VAA_SIGNATURE;
+        }
+        Ok(Self {
+            version,
+            guardian_set_index,
+            num_signatures: num_signatures_byte,
+            guardian_indices,
+            signatures,
+            body: bytes[offset..].to_vec(),
+        })
+    }
+
+    pub fn guardian_set_index<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<Num<E>, SynthesisError> {
+        num_from_be_bytes(cs, &self.guardian_set_index)
+    }
+
+    /// Message signed by guardians, which is the double keccak256 hash of body.
+    pub fn body_hash<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<UInt256<E>, SynthesisError> {
+        use crate::gadgets::keccak256::digest;
+        let hash1 = digest(cs, &self.body)?;
+        let hash2 = digest(cs, &hash1)?;
+        UInt256::from_be_bytes_fixed(cs, &hash2)
+    }
+
+    /// Merkle root of pyth accumulator carried in body, against which price updates are proven.
+    pub fn merkle_root(&self) -> Result<MerkleRoot<E>, SynthesisError> {
+        Ok(self.pyth_body()?.payload.root)
+    }
+
+    /// Payload following fields of body, e.g. pyth merkle root or legacy batch price attestation.
+    pub fn payload(&self) -> Result<&[Byte<E>], SynthesisError> {
+        if self.body.len() < LEN_WORMHOLE_BODY_HEADER {
+            return Err(new_synthesis_error(format!(
+                "VAA body of {} bytes is too short",
+                self.body.len()
+            )));
+        }
+        Ok(&self.body[LEN_WORMHOLE_BODY_HEADER..])
+    }
+
+    /// Same as [`VaaBody::is_emitted_by`] without interpreting payload, e.g. for governance VAAs.
+    pub fn is_emitted_by<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        emitter_chain: u16,
+        emitter_address: &[u8; LEN_WORMHOLE_BODY_EMITTER_ADDRESS],
+    ) -> Result<Boolean, SynthesisError> {
+        self.payload()?;
+        let offset = LEN_WORMHOLE_BODY_TIMESTAMP + LEN_WORMHOLE_BODY_NONCE;
+        let emitter = &self.body
+            [offset..offset + LEN_WORMHOLE_BODY_EMITTER_CHAIN + LEN_WORMHOLE_BODY_EMITTER_ADDRESS];
+        is_emitter(cs, emitter, emitter_chain, emitter_address)
+    }
+
+    /// Interpret body as carrying the pyth merkle root payload.
+    pub fn pyth_body(&self) -> Result<VaaBody<E>, SynthesisError> {
+        let bytes: &[Byte<E>; LEN_WORMHOLE_BODY] =
+            self.body.as_slice().try_into().map_err(|_| {
+                new_synthesis_error(format!(
+                    "expect pyth VAA body of {} bytes, got {}",
+                    LEN_WORMHOLE_BODY,
+                    self.body.len()
+                ))
+            })?;
+        Ok(VaaBody::from_bytes(bytes))
+    }
+}
+
 /// Circuit (partial) representation of wormhole [`VAA<P>`](https://github.com/wormhole-foundation/wormhole/blob/bfd4ba40ef2d213ad69bac638c72009ba4a07878/sdk/rust/core/src/vaa.rs#L80-L100)
 ///
 /// Visit [VAAs documentation](https://docs.wormhole.com/wormhole/explore-wormhole/vaa) for more.
@@ -157,13 +315,33 @@ const LEN_WORMHOLE_BODY_EMITTER_CHAIN: usize = 2;
 const LEN_WORMHOLE_BODY_EMITTER_ADDRESS: usize = 32;
 const LEN_WORMHOLE_BODY_SEQUENCE: usize = 8;
 const LEN_WORMHOLE_BODY_CONSISTENCY_LEVEL: usize = 1;
-const LEN_WORMHOLE_BODY: usize = LEN_WORMHOLE_BODY_TIMESTAMP
+const LEN_WORMHOLE_BODY_HEADER: usize = LEN_WORMHOLE_BODY_TIMESTAMP
     + LEN_WORMHOLE_BODY_NONCE
     + LEN_WORMHOLE_BODY_EMITTER_CHAIN
     + LEN_WORMHOLE_BODY_EMITTER_ADDRESS
     + LEN_WORMHOLE_BODY_SEQUENCE
-    + LEN_WORMHOLE_BODY_CONSISTENCY_LEVEL
-    + LEN_MESSAGE;
+    + LEN_WORMHOLE_BODY_CONSISTENCY_LEVEL;
+const LEN_WORMHOLE_BODY: usize = LEN_WORMHOLE_BODY_HEADER + LEN_MESSAGE;
+
+// Check emitter chain and emitter address bytes of body are the expected constants.
+fn is_emitter<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    emitter: &[Byte<E>],
+    emitter_chain: u16,
+    emitter_address: &[u8; LEN_WORMHOLE_BODY_EMITTER_ADDRESS],
+) -> Result<Boolean, SynthesisError> {
+    let expected = emitter_chain
+        .to_be_bytes()
+        .into_iter()
+        .chain(*emitter_address);
+    let mut is_equal = vec![];
+    for (byte, expected) in emitter.iter().zip(expected) {
+        let expected = Num::Constant(u64_to_fe(expected as u64));
+        is_equal.push(Num::equals(cs, &byte.inner, &expected)?);
+    }
+    smart_and(cs, &is_equal)
+}
+
 #[derive(Debug, Clone)]
 /// Circuit representation of body in wormhole VAA [`Body<P>`](https://github.com/wormhole-foundation/wormhole/blob/bfd4ba40ef2d213ad69bac638c72009ba4a07878/sdk/rust/core/src/vaa.rs#L110-L121).
 ///
@@ -201,6 +379,50 @@ impl<E: Engine> VaaBody<E> {
         bytes
     }
 
+    /// Check the body is emitted by `emitter_address` on chain `emitter_chain`, e.g. the pyth
+    /// accumulator emitter on Pythnet, so that VAAs of arbitrary contracts are rejected.
+    pub fn is_emitted_by<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        emitter_chain: u16,
+        emitter_address: &[u8; LEN_WORMHOLE_BODY_EMITTER_ADDRESS],
+    ) -> Result<Boolean, SynthesisError> {
+        let emitter = self
+            .emitter_chain
+            .iter()
+            .chain(self.emitter_address.iter())
+            .copied()
+            .collect::<Vec<_>>();
+        is_emitter(cs, &emitter, emitter_chain, emitter_address)
+    }
+
+    /// Same as [`Self::is_emitted_by`] with the pyth accumulator emitter on Pythnet.
+    pub fn is_emitted_by_pythnet<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<Boolean, SynthesisError> {
+        self.is_emitted_by(cs, PYTHNET_EMITTER_CHAIN, &PYTHNET_EMITTER_ADDRESS)
+    }
+
+    /// Slice fields from body bytes in the same layout as [`Self::to_bytes`].
+    pub fn from_bytes(bytes: &[Byte<E>; LEN_WORMHOLE_BODY]) -> Self {
+        let (timestamp, rest) = bytes.split_at(LEN_WORMHOLE_BODY_TIMESTAMP);
+        let (nonce, rest) = rest.split_at(LEN_WORMHOLE_BODY_NONCE);
+        let (emitter_chain, rest) = rest.split_at(LEN_WORMHOLE_BODY_EMITTER_CHAIN);
+        let (emitter_address, rest) = rest.split_at(LEN_WORMHOLE_BODY_EMITTER_ADDRESS);
+        let (sequence, rest) = rest.split_at(LEN_WORMHOLE_BODY_SEQUENCE);
+        let (consistency_level, payload) = rest.split_at(LEN_WORMHOLE_BODY_CONSISTENCY_LEVEL);
+        Self {
+            timestamp: timestamp.try_into().unwrap(),
+            nonce: nonce.try_into().unwrap(),
+            emitter_chain: emitter_chain.try_into().unwrap(),
+            emitter_address: emitter_address.try_into().unwrap(),
+            sequence: sequence.try_into().unwrap(),
+            consistency_level: consistency_level.try_into().unwrap(),
+            payload: VaaPayload::from_bytes(payload.try_into().unwrap()),
+        }
+    }
+
     pub fn from_vaa_body_witness<CS: ConstraintSystem<E>>(
         cs: &mut CS,
         witness: wormhole_sdk::vaa::Body<&serde_wormhole::RawMessage>,
@@ -260,6 +482,7 @@ const LEN_RING_SIZE: usize = 4;
 const LEN_ROOT: usize = keccak160::WIDTH_HASH_BYTES;
 const LEN_MESSAGE: usize = LEN_MAGIC + LEN_PAYLOAD_TYPE + LEN_SLOT + LEN_RING_SIZE + LEN_ROOT;
 const PAYLOAD_TYPE: u8 = 0; // Fixed payload type for now.
+const ACCUMULATOR_MAGIC: &[u8; LEN_MAGIC] = b"AUWV";
 /// Representation of pyth-defined wormhole payload [`WormholeMessage`](https://github.com/pyth-network/pyth-crosschain/blob/1d82f92d80598e689f4130983d06b12412b83427/pythnet/pythnet_sdk/src/wire.rs#L108-L112).
 #[derive(Debug, Clone)]
 pub struct VaaPayload<E: Engine> {
@@ -286,6 +509,53 @@ impl<E: Engine> VaaPayload<E> {
         bytes
     }
 
+    /// Check the payload is a pyth accumulator message of type `WormholeMerkle`, whose last field
+    /// is the merkle root.
+    pub fn is_merkle<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<Boolean, SynthesisError> {
+        let mut is_equal = vec![];
+        for (byte, expected) in self
+            .magic
+            .iter()
+            .chain(self.payload_type.iter())
+            .zip(ACCUMULATOR_MAGIC.iter().chain([PAYLOAD_TYPE].iter()))
+        {
+            let expected = Num::Constant(u64_to_fe(*expected as u64));
+            is_equal.push(Num::equals(cs, &byte.inner, &expected)?);
+        }
+        smart_and(cs, &is_equal)
+    }
+
+    /// Check `root` is byte-equal to the merkle root of payload.
+    pub fn is_root<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        root: &MerkleRoot<E>,
+    ) -> Result<Boolean, SynthesisError> {
+        let mut is_equal = vec![];
+        for (a, b) in self.root.inner().iter().zip(root.inner().iter()) {
+            is_equal.push(Num::equals(cs, &a.inner, &b.inner)?);
+        }
+        smart_and(cs, &is_equal)
+    }
+
+    /// Slice fields from payload bytes in the same layout as [`Self::to_bytes`].
+    pub fn from_bytes(bytes: &[Byte<E>; LEN_MESSAGE]) -> Self {
+        let (magic, rest) = bytes.split_at(LEN_MAGIC);
+        let (payload_type, rest) = rest.split_at(LEN_PAYLOAD_TYPE);
+        let (slot, rest) = rest.split_at(LEN_SLOT);
+        let (ring_size, root) = rest.split_at(LEN_RING_SIZE);
+        Self {
+            magic: magic.try_into().unwrap(),
+            payload_type: payload_type.try_into().unwrap(),
+            slot: slot.try_into().unwrap(),
+            ring_size: ring_size.try_into().unwrap(),
+            root: MerkleRoot::new(root.try_into().unwrap()),
+        }
+    }
+
     pub fn from_wormhole_message_witness<CS: ConstraintSystem<E>>(
         cs: &mut CS,
         witness: pythnet_sdk::wire::v1::WormholeMessage,
@@ -319,16 +589,19 @@ impl<E: Engine> VaaPayload<E> {
 mod tests {
     use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
     use advanced_circuit_component::{
+        circuit_structures::byte::Byte,
         franklin_crypto::{
             bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
             plonk::circuit::boolean::Boolean,
         },
+        utils::u64_to_fe,
         vm::primitives::uint256::UInt256,
     };
+    use num_bigint::BigUint;
 
     use crate::utils::{
         new_synthesis_error,
-        testing::{bytes_assert_eq, create_test_constraint_system},
+        testing::{bytes_assert_eq, create_test_constraint_system, wormhole_vaa},
         uint256_from_bytes_witness,
     };
 
@@ -340,13 +613,26 @@ mod tests {
         let payload = pythnet_sdk::wire::v1::WormholeMessage::try_from_bytes(data).unwrap();
         let payload = super::VaaPayload::<_>::from_wormhole_message_witness(cs, payload)?;
         bytes_assert_eq(&payload.to_bytes(), hex_str);
+        assert!(payload.is_merkle(cs)?.get_value().unwrap());
+        assert!(payload.is_root(cs, &payload.root)?.get_value().unwrap());
+
+        // Payload of other types, and roots other than the payload root are rejected
+        let mut bytes = payload.to_bytes();
+        bytes[4] = Byte::from_u8_witness(cs, Some(1))?;
+        let other = super::VaaPayload::from_bytes(&bytes);
+        assert!(!other.is_merkle(cs)?.get_value().unwrap());
+        let mut root = payload.root.inner();
+        root[0] = Byte::from_u8_witness(cs, Some(0))?;
+        let root = super::MerkleRoot::new(root);
+        assert!(!payload.is_root(cs, &root)?.get_value().unwrap());
+        assert!(cs.is_satisfied());
         Ok(())
     }
 
     #[test]
     fn test_wormhole_body() -> Result<(), SynthesisError> {
         let cs = &mut create_test_constraint_system()?;
-        let data = hex::decode(get_vaa()).unwrap();
+        let data = hex::decode(wormhole_vaa()).unwrap();
         let vaa: wormhole_sdk::Vaa<&serde_wormhole::RawMessage> =
             serde_wormhole::from_slice(&data).unwrap();
         let (_, body): (_, wormhole_sdk::vaa::Body<_>) = vaa.into();
@@ -356,10 +642,57 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_parsed_vaa() -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let data = hex::decode(wormhole_vaa()).unwrap();
+        let vaa: wormhole_sdk::Vaa<&serde_wormhole::RawMessage> =
+            serde_wormhole::from_slice(&data).unwrap();
+        assert!(super::ParsedVaa::<_>::from_bytes_witness(cs, &data, 1).is_err());
+
+        let parsed = super::ParsedVaa::<_>::from_bytes_witness(cs, &data, 13)?;
+        assert_eq!(
+            parsed.guardian_set_index(cs)?.get_value().unwrap(),
+            u64_to_fe(vaa.guardian_set_index as u64)
+        );
+        for (i, signature) in parsed.signatures.iter().enumerate() {
+            let expected = &vaa.signatures[i];
+            assert_eq!(
+                parsed.guardian_indices[i].inner.get_value().unwrap(),
+                u64_to_fe(expected.index as u64)
+            );
+            assert_eq!(
+                signature.r.get_value().unwrap(),
+                BigUint::from_bytes_be(&expected.signature[..32])
+            );
+        }
+        let (_, body): (wormhole_sdk::vaa::Header, wormhole_sdk::vaa::Body<_>) = vaa.into();
+        let body = serde_wormhole::to_vec(&body).unwrap();
+        bytes_assert_eq(&parsed.pyth_body()?.to_bytes(), hex::encode(&body));
+        bytes_assert_eq(
+            &parsed.merkle_root()?.inner(),
+            "095bb7e5fa374ea08603a6698123d99101547a50",
+        );
+        let pyth_body = parsed.pyth_body()?;
+        assert!(pyth_body.is_emitted_by_pythnet(cs)?.get_value().unwrap());
+        let solana_emitted = pyth_body.is_emitted_by(cs, 1, &super::PYTHNET_EMITTER_ADDRESS)?;
+        assert!(!solana_emitted.get_value().unwrap());
+        let body_hash = {
+            use sha3::{Digest, Keccak256};
+            Keccak256::digest(Keccak256::digest(&body))
+        };
+        assert_eq!(
+            parsed.body_hash(cs)?.get_value().unwrap(),
+            BigUint::from_bytes_be(&body_hash)
+        );
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+
     #[test]
     fn test_vaa() -> Result<(), SynthesisError> {
         let cs = &mut create_test_constraint_system()?;
-        let data = hex::decode(get_vaa()).unwrap();
+        let data = hex::decode(wormhole_vaa()).unwrap();
         let vaa: wormhole_sdk::Vaa<&serde_wormhole::RawMessage> =
             serde_wormhole::from_slice(&data).unwrap();
         // We can safely create Vaa with signatures less than len of witness VAA.
@@ -396,8 +729,4 @@ mod tests {
         let y = uint256_from_bytes_witness(cs, &data[32..])?;
         Ok((x, y))
     }
-
-    fn get_vaa() -> &'static str {
-        "01000000030d00d5df1d274a402c5eb4c8b60254f1d1df67c64c6afddd75ed03562aac6d4ad0714bd0874f0837683bec3357999a4c2d922f79e908c39a5a6ff4ec6e21a78956fa00021e32f66495cb657049f04b251629811395d082d4aecee8a95e447e83372a4e9443a647f44880f3da72d58dfc0f9fa963e4aac0c283342d9a91c4e19d3ca62a5b0103381bfdf0853bbf0f7b4cb4d65851ac7f60dcc9ba3d8442c95de61410cbf09ef279454fa725fd2e90697f55e065005ad64e6696c009fd1767b7bf9b79738399bf00068260c97865c386a3496aa56da2327159998ab1db26ae79010685f75518d4eecb67cda0cda4408a636301d0d376f3ff71db66f088e24d871bf8f9d75f901b84e8010743b8b7f7b4d53e5499bc0d2548a952cb2b6559da1a0583d3128d930926c6cf281ff58828c54cc9e39c774b70fb5ab7ab400eaa6356bc06700b2f744c6a13fd06010859f92b8bd6fa6cb257d5a41327b48c2ac880773eda6617f8511a8003a56fff15502b2b90f65cbe16ddfda2324e3d0b4039fba3332cde2adf48f01e46e8717839000a2fcf534a53c3e53addf02dea50a6e87b20f41922708a38768af6ad48dc53ca0f65844530c842f2746ecef4a950843e2adfdd1f8765e3a172e346a793fe136b90010bf3022b0f4927b6b701a84e949da4cfacbc8cc2e72037516c1ba12ef7a354e77c454822878d7d948e50c0e7118cfca2a4d5a33810e7c5cf63a47a0115cb3c5f98000c06c01308e45e4d95711e735ef2ef9e5eddeaf1e0a52faf28e0e9cb2b37acde794557d6ce463ac7b9c16f753ddd142f5716c64bfe3c9c01960f07d46cafd7157e010d5cd199cddb07c62c95eb3d199a324e79392562af5568a33842e23c1a0f2550a1010f6a4af293d651e13acb8a5f1967da722df8422ee871731ca0d9e0a908fc7f010ecc18446ff3bf2a129401967556df7de3bbfcc2c37d4441cde11d71b86a8128aa22e2154e4943570aed1d2aaa747ddc10729702688b70751a9d9c411b9e0271da0010922dd9890ea99eb32ffb3fe2fcda2258b875147601af4bad528edf70a33f382b79b4ef1515a7c5aa60af16a75c555d714b4ce7b31275d4b4eb427089849ff0920012997ca65ec7fcf0418fd036ddead5743206a7a350fd44602759a4bba2acfc949924244db3d12d76885c162b988135e642c1d6c27aa4ba504668c7932d37ead91b00655ccff800000000001ae101faedac5851e32b9b23b5f9411a8c2bac4aae3ed4dd7b811dd1a72ea4aa71000000000195faa401415557560000000000069b993c00002710095bb7e5fa374ea08603a6698123d99101547a50"
-    }
 }
@@ -1,18 +1,40 @@
-use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{ff::Field, Engine};
 use advanced_circuit_component::{
     circuit_structures::byte::{Byte, IntoBytes},
     franklin_crypto::{
         bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
-        plonk::circuit::boolean::Boolean,
+        plonk::circuit::{
+            allocated_num::Num, boolean::Boolean, linear_combination::LinearCombination,
+        },
     },
+    glue::prepacked_long_comparison,
     traits::CSAllocatable,
-    vm::primitives::uint256::UInt256,
+    utils::u64_to_fe,
+    vm::{
+        partitioner::{smart_and, smart_or},
+        primitives::{uint256::UInt256, UInt64},
+    },
 };
+use num_bigint::BigUint;
 
-use crate::gadgets::{ecdsa::Signature, ethereum::Address};
+use crate::{
+    gadgets::{
+        ecdsa::Signature,
+        ethereum::Address,
+        poseidon::circuit_poseidon_hash,
+        uint256::{div_rem, mul_div_rem},
+    },
+    utils::{fr_from_biguint, new_synthesis_error, num_from_be_bytes},
+};
 use std::convert::TryInto;
 
-use super::witness::{DataPackage, DataPoint};
+use super::{
+    witness::{
+        preimage_layout, CompactFeedId, DataPackage, DataPoint, FieldLayout, Rounding,
+        SignedPackageWitness,
+    },
+    BLENDED_PRICE_VALUE_BITS, CROSS_PRICE_VALUE_BITS, MAX_CROSS_PRICE_DECIMALS, MAX_TOLERANCE_BPS,
+};
 
 #[derive(Clone, Debug, Copy)]
 pub struct AllocatedDataPoint<E: Engine> {
@@ -29,6 +51,7 @@ impl<E: Engine> AllocatedDataPoint<E> {
             let bytes = witness.serialize_feed_id().try_into().unwrap();
             CSAllocatable::alloc_from_witness(cs, Some(bytes))?
         };
+        Self::enforce_feed_id_shape(cs, &data_feed_id)?;
         let value = {
             let bytes = witness.serialize_value().try_into().unwrap();
             CSAllocatable::alloc_from_witness(cs, Some(bytes))?
@@ -40,6 +63,62 @@ impl<E: Engine> AllocatedDataPoint<E> {
         })
     }
 
+    /// Enforce feed id is left-aligned ASCII padded with zeros, i.e. it has at least one
+    /// non-zero byte and there is no non-zero byte after the first zero byte.
+    pub fn enforce_feed_id_shape<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        data_feed_id: &[Byte<E>; 32],
+    ) -> Result<(), SynthesisError> {
+        let mut is_padding = Boolean::constant(false);
+        for (i, byte) in data_feed_id.iter().enumerate() {
+            // ASCII byte has its highest bit unset
+            let bits = byte.inner.into_bits_le(cs, Some(8))?;
+            Boolean::enforce_equal(cs, &bits[7], &Boolean::constant(false))?;
+
+            let is_zero = byte.inner.is_zero(cs)?;
+            if i == 0 {
+                // As feed id is left-aligned, the first byte must be non-zero
+                Boolean::enforce_equal(cs, &is_zero, &Boolean::constant(false))?;
+            }
+            let is_invalid = Boolean::and(cs, &is_padding, &is_zero.not())?;
+            Boolean::enforce_equal(cs, &is_invalid, &Boolean::constant(false))?;
+            is_padding = Boolean::or(cs, &is_padding, &is_zero)?;
+        }
+        Ok(())
+    }
+
+    /// Whether feed id equals `feed_id`. Only significant bytes of `feed_id` are compared, packed
+    /// into at most two field elements, which is sound as the shape of feed id is enforced when
+    /// allocated.
+    pub fn is_compact_feed_id<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        feed_id: &CompactFeedId,
+    ) -> Result<Boolean, SynthesisError> {
+        let significant_bytes = feed_id.significant_bytes();
+        let shift = u64_to_fe::<E::Fr>(256);
+        let mut is_equal = Boolean::constant(true);
+        // A field element holds 31 bytes
+        for (bytes, expected) in self.data_feed_id[..significant_bytes.len()]
+            .chunks(31)
+            .zip(significant_bytes.chunks(31))
+        {
+            // diff = packed bytes - packed expected bytes
+            let mut diff = LinearCombination::zero();
+            let mut coeff = E::Fr::one();
+            for byte in bytes.iter().rev() {
+                diff.add_assign_number_with_coeff(&byte.inner, coeff);
+                coeff.mul_assign(&shift);
+            }
+            let mut expected = fr_from_biguint::<E>(&BigUint::from_bytes_be(expected))?;
+            expected.negate();
+            diff.add_assign_constant(expected);
+            let is_current_equal = diff.into_num(cs)?.is_zero(cs)?;
+            is_equal = Boolean::and(cs, &is_equal, &is_current_equal)?;
+        }
+        Ok(is_equal)
+    }
+
     pub fn serialize(&self) -> Result<Vec<Byte<E>>, SynthesisError> {
         let mut bytes = vec![];
         bytes.extend(self.data_feed_id);
@@ -55,12 +134,12 @@ pub struct AllocatedSignedPrice<E: Engine, const NUM_SIGNATURES: usize> {
 impl<E: Engine, const NUM_SIGNATURES: usize> AllocatedSignedPrice<E, NUM_SIGNATURES> {
     pub fn from_witness<CS: ConstraintSystem<E>>(
         cs: &mut CS,
-        witness: [(DataPackage, [u8; 65]); NUM_SIGNATURES],
+        witness: [SignedPackageWitness; NUM_SIGNATURES],
     ) -> Result<Self, SynthesisError> {
         let mut signed_data_packages = vec![];
-        for (data_package, signature) in witness.into_iter() {
+        for witness in witness.into_iter() {
             let signed_package_data =
-                AllocatedSignedDataPackage::from_witness(cs, data_package, signature);
+                AllocatedSignedDataPackage::from_witness(cs, witness.package, witness.signature);
             signed_data_packages.push(signed_package_data?);
         }
 
@@ -74,27 +153,531 @@ impl<E: Engine, const NUM_SIGNATURES: usize> AllocatedSignedPrice<E, NUM_SIGNATU
         cs: &mut CS,
         guardians: &[Address<E>],
     ) -> Result<Boolean, SynthesisError> {
+        let (is_valid, _) = self.check_by_addresses_with_payload_hashes(cs, guardians)?;
+        Ok(is_valid)
+    }
+
+    /// Same as [`Self::check_by_addresses`], but also returns keccak256 hashes of signed payloads,
+    /// which are computed for signature verification anyway.
+    pub fn check_by_addresses_with_payload_hashes<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        guardians: &[Address<E>],
+    ) -> Result<(Boolean, [[Byte<E>; 32]; NUM_SIGNATURES]), SynthesisError> {
         let mut is_valid = Boolean::constant(true);
+        let mut payload_hashes = [[Byte::zero(); 32]; NUM_SIGNATURES];
 
         for i in 0..NUM_SIGNATURES {
-            let current_is_valid =
-                self.signed_data_packages[i].check_by_address(cs, &guardians[i])?;
+            let (current_is_valid, payload_hash) = self.signed_data_packages[i]
+                .check_by_address_with_payload_hash(cs, &guardians[i])?;
             is_valid = Boolean::and(cs, &is_valid, &current_is_valid)?;
+            payload_hashes[i] = payload_hash;
         }
-        Ok(is_valid)
+        Ok((is_valid, payload_hashes))
     }
 
     pub fn timestamp(&self) -> [Byte<E>; super::TIMESTAMP_BS] {
         self.signed_data_packages[0].data_package.timestamp
     }
 
+    /// Value of the first data point. Panics if packages are heartbeats without data points.
     pub fn price(&self) -> [Byte<E>; super::DEFAULT_NUM_VALUE_BS] {
         self.signed_data_packages[0].data_package.data_points[0].value
     }
 
+    /// Feed id of the first data point. Panics if packages are heartbeats without data points.
     pub fn feed_id(&self) -> [Byte<E>; 32] {
         self.signed_data_packages[0].data_package.data_points[0].data_feed_id
     }
+
+    /// Whether every signed data package contains data points of all given feed ids, so that
+    /// quorum of each feed is met by the same signers.
+    pub fn contains_all<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        data_feed_ids: &[[Byte<E>; 32]],
+    ) -> Result<Boolean, SynthesisError> {
+        let mut contains_all = Boolean::constant(true);
+        for signed_data_package in self.signed_data_packages.iter() {
+            let current_contains_all = signed_data_package
+                .data_package
+                .contains_all(cs, data_feed_ids)?;
+            contains_all = Boolean::and(cs, &contains_all, &current_contains_all)?;
+        }
+        Ok(contains_all)
+    }
+
+    /// Aggregate values of the given feed id from all signed data packages by median, which is
+    /// the way RedStone aggregates values. Also returns whether every package contains the feed id.
+    pub fn aggregated_value_of<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        data_feed_id: &[Byte<E>; 32],
+    ) -> Result<(Boolean, UInt256<E>), SynthesisError> {
+        let mut found_in_all = Boolean::constant(true);
+        let mut values = vec![];
+        for signed_data_package in self.signed_data_packages.iter() {
+            let (found, value) = signed_data_package
+                .data_package
+                .value_of(cs, data_feed_id)?;
+            found_in_all = Boolean::and(cs, &found_in_all, &found)?;
+            values.push(UInt256::from_be_bytes_fixed(cs, &value)?);
+        }
+        let value = median(cs, &values)?;
+        Ok((found_in_all, value))
+    }
+
+    /// Aggregate values of the given feed id by median from signed data packages containing it.
+    /// Circuit is unsatisfied if less than `quorum` packages contain the feed id.
+    pub fn aggregated_value_with_quorum<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        data_feed_id: &[Byte<E>; 32],
+        quorum: usize,
+    ) -> Result<UInt256<E>, SynthesisError> {
+        let mut values = vec![];
+        for signed_data_package in self.signed_data_packages.iter() {
+            let (found, value) = signed_data_package
+                .data_package
+                .value_of(cs, data_feed_id)?;
+            values.push((found, UInt256::from_be_bytes_fixed(cs, &value)?));
+        }
+        median_with_quorum(cs, &values, quorum)
+    }
+
+    /// Same as [`Self::aggregated_value_with_quorum`], but matches the compact feed id.
+    pub fn aggregated_compact_value_with_quorum<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        feed_id: &CompactFeedId,
+        quorum: usize,
+    ) -> Result<UInt256<E>, SynthesisError> {
+        let mut values = vec![];
+        for signed_data_package in self.signed_data_packages.iter() {
+            let (found, value) = signed_data_package
+                .data_package
+                .value_of_compact(cs, feed_id)?;
+            values.push((found, UInt256::from_be_bytes_fixed(cs, &value)?));
+        }
+        median_with_quorum(cs, &values, quorum)
+    }
+}
+
+/// Check data package timestamp (in milliseconds) is neither `max_delay` older nor `max_ahead`
+/// newer than `reference_time` (in milliseconds).
+pub fn check_timestamp_freshness<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    timestamp: &[Byte<E>; super::TIMESTAMP_BS],
+    reference_time: &Num<E>,
+    max_delay: u64,
+    max_ahead: u64,
+) -> Result<Boolean, SynthesisError> {
+    let timestamp = {
+        let mut bytes = [Byte::zero(); 8];
+        bytes[8 - super::TIMESTAMP_BS..].copy_from_slice(timestamp);
+        bytes.reverse();
+        UInt64::from_bytes_le(cs, &bytes)?.into_num()
+    };
+    // timestamp + max_delay >= reference_time
+    let is_not_stale = {
+        let earliest = timestamp.add(cs, &Num::Constant(u64_to_fe(max_delay)))?;
+        let (is_equal, is_greater) =
+            prepacked_long_comparison(cs, &[earliest], &[*reference_time], &[64])?;
+        Boolean::or(cs, &is_equal, &is_greater)?
+    };
+    // timestamp <= reference_time + max_ahead
+    let is_not_ahead = {
+        let latest = reference_time.add(cs, &Num::Constant(u64_to_fe(max_ahead)))?;
+        let (_, is_greater) = prepacked_long_comparison(cs, &[timestamp], &[latest], &[64])?;
+        is_greater.not()
+    };
+    Boolean::and(cs, &is_not_stale, &is_not_ahead)
+}
+
+/// Check data package is fresh against `reference_time` with per-feed max delays, which are
+/// constants of circuit. The max delay of every feed in `max_delays` contained by the package
+/// applies, or `default_max_delay` if it contains none of them, so that slow feeds don't loose
+/// the bound of fast ones.
+pub fn check_package_freshness<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    data_package: &AllocatedDataPackage<E>,
+    reference_time: &Num<E>,
+    max_delays: &[([Byte<E>; 32], u64)],
+    default_max_delay: u64,
+    max_ahead: u64,
+) -> Result<Boolean, SynthesisError> {
+    let mut is_fresh = Boolean::constant(true);
+    let mut contains_any = Boolean::constant(false);
+    for (feed_id, max_delay) in max_delays.iter() {
+        let contains = data_package.contains(cs, feed_id)?;
+        let is_current_fresh = check_timestamp_freshness(
+            cs,
+            &data_package.timestamp,
+            reference_time,
+            *max_delay,
+            max_ahead,
+        )?;
+        // contains => is_current_fresh
+        let is_current_valid = Boolean::or(cs, &contains.not(), &is_current_fresh)?;
+        is_fresh = Boolean::and(cs, &is_fresh, &is_current_valid)?;
+        contains_any = Boolean::or(cs, &contains_any, &contains)?;
+    }
+    let is_default_fresh = check_timestamp_freshness(
+        cs,
+        &data_package.timestamp,
+        reference_time,
+        default_max_delay,
+        max_ahead,
+    )?;
+    let is_default_valid = Boolean::or(cs, &contains_any, &is_default_fresh)?;
+    Boolean::and(cs, &is_fresh, &is_default_valid)
+}
+
+/// Select the latest value of every feed, whose timestamp is the maximum timestamp of all
+/// packages containing the feed. `packages[r]` is `(timestamp, contains)` of packages of round `r`,
+/// where `contains[i]` is whether the package contains feed `i`, and `rounds[r][i]` is the value
+/// of feed `i` aggregated in round `r`. The value of a round is selected only if every package of
+/// it containing the feed carries the latest timestamp, and the last one wins if there are several.
+/// Circuit is unsatisfied if no round of some feed is selected.
+///
+/// Returns the earliest timestamp of selected values, or the maximum timestamp of all packages if
+/// there is no feed.
+pub fn select_latest_values<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    packages: &[Vec<(Num<E>, Vec<Boolean>)>],
+    rounds: &[Vec<UInt256<E>>],
+) -> Result<(Num<E>, Vec<UInt256<E>>), SynthesisError> {
+    if rounds.is_empty() || packages.len() != rounds.len() {
+        return Err(new_synthesis_error(format!(
+            "invalid rounds to select: {} packages and {} rounds",
+            packages.len(),
+            rounds.len()
+        )));
+    }
+    let num_feeds = rounds[0].len();
+    let mut max_timestamp = Num::zero();
+    let mut latest_timestamps = vec![Num::zero(); num_feeds];
+    for (timestamp, contains) in packages.iter().flatten() {
+        let (_, is_greater) =
+            prepacked_long_comparison(cs, &[*timestamp], &[max_timestamp], &[64])?;
+        max_timestamp = Num::conditionally_select(cs, &is_greater, timestamp, &max_timestamp)?;
+        for (latest_timestamp, contains) in latest_timestamps.iter_mut().zip(contains.iter()) {
+            let (_, is_greater) =
+                prepacked_long_comparison(cs, &[*timestamp], &[*latest_timestamp], &[64])?;
+            let is_later = Boolean::and(cs, contains, &is_greater)?;
+            *latest_timestamp =
+                Num::conditionally_select(cs, &is_later, timestamp, latest_timestamp)?;
+        }
+    }
+
+    let mut latest_values = vec![];
+    for (i, latest_timestamp) in latest_timestamps.iter().enumerate() {
+        let mut is_selected = Boolean::constant(false);
+        let mut latest_value = rounds[0][i];
+        for (round_packages, values) in packages.iter().zip(rounds.iter()) {
+            let mut contains_any = Boolean::constant(false);
+            let mut is_latest = Boolean::constant(true);
+            for (timestamp, contains) in round_packages.iter() {
+                let is_equal = Num::equals(cs, timestamp, latest_timestamp)?;
+                // contains => is_equal
+                let is_current_latest = Boolean::or(cs, &contains[i].not(), &is_equal)?;
+                is_latest = Boolean::and(cs, &is_latest, &is_current_latest)?;
+                contains_any = Boolean::or(cs, &contains_any, &contains[i])?;
+            }
+            let is_current_selected = Boolean::and(cs, &is_latest, &contains_any)?;
+            latest_value =
+                UInt256::conditionally_select(cs, &is_current_selected, &values[i], &latest_value)?;
+            is_selected = Boolean::or(cs, &is_selected, &is_current_selected)?;
+        }
+        Boolean::enforce_equal(cs, &is_selected, &Boolean::constant(true))?;
+        latest_values.push(latest_value);
+    }
+
+    let mut earliest_timestamp = latest_timestamps.first().copied().unwrap_or(max_timestamp);
+    for latest_timestamp in latest_timestamps.iter().skip(1) {
+        let (_, is_greater) =
+            prepacked_long_comparison(cs, &[earliest_timestamp], &[*latest_timestamp], &[64])?;
+        earliest_timestamp =
+            Num::conditionally_select(cs, &is_greater, latest_timestamp, &earliest_timestamp)?;
+    }
+    Ok((earliest_timestamp, latest_values))
+}
+
+/// Rescale `value` from `decimals` to `target_decimals`, rounding down if the precision is
+/// reduced. Circuit is unsatisfied if the rescaled value overflows 256 bits.
+pub fn normalize_value<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    value: &UInt256<E>,
+    decimals: usize,
+    target_decimals: usize,
+) -> Result<UInt256<E>, SynthesisError> {
+    // Both are limb-wise products by a constant power of 10
+    if target_decimals >= decimals {
+        let scale = BigUint::from(10u32).pow((target_decimals - decimals) as u32);
+        let one = UInt256::constant(BigUint::from(1u32));
+        let (normalized, _) = mul_div_rem(cs, value, &UInt256::constant(scale), &one)?;
+        return Ok(normalized);
+    }
+    let scale = BigUint::from(10u32).pow((decimals - target_decimals) as u32);
+    let (quotient, _) = div_rem(cs, value, &UInt256::constant(scale))?;
+    Ok(quotient)
+}
+
+/// Absorb `(feed_id, price, publish_time)` triples and other outputs into one Poseidon hash, which
+/// is `poseidon(triples[0] || triples[1] || ... || outputs)`.
+pub fn output_commitment<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    triples: &[[Num<E>; 3]],
+    outputs: &[Num<E>],
+) -> Result<Num<E>, SynthesisError> {
+    let mut members = triples.iter().flatten().copied().collect::<Vec<_>>();
+    members.extend_from_slice(outputs);
+    circuit_poseidon_hash(cs, &members)
+}
+
+// Bits of cross price quotients, which bound `quotient * quote + remainder` below the field modulus
+const CROSS_PRICE_QUOTIENT_BITS: usize = 157;
+
+/// Derive the cross price `base / quote` with `decimals`, e.g. ETH/BTC from ETH/USD and BTC/USD,
+/// where `base` and `quote` share the same decimals. Circuit is unsatisfied if `quote` is zero or
+/// inputs exceed [`CROSS_PRICE_VALUE_BITS`] bits.
+///
+/// It is proved that `base * 10^decimals = quotient * quote + remainder` where
+/// `remainder < quote`, and the quotient is rounded by `rounding` afterwards. Bit widths of all
+/// terms are bounded, so the equation over field holds over integers as well.
+pub fn cross_price<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    base: &UInt256<E>,
+    quote: &UInt256<E>,
+    decimals: usize,
+    rounding: Rounding,
+) -> Result<Num<E>, SynthesisError> {
+    if decimals > MAX_CROSS_PRICE_DECIMALS {
+        return Err(new_synthesis_error(format!(
+            "cross price decimals {} exceeds {}",
+            decimals, MAX_CROSS_PRICE_DECIMALS
+        )));
+    }
+    let base_num = bounded_num(cs, base, CROSS_PRICE_VALUE_BITS)?;
+    let quote_num = bounded_num(cs, quote, CROSS_PRICE_VALUE_BITS)?;
+
+    let divisor = BigUint::from(10u32).pow(decimals as u32);
+    let witness = match (base.get_value(), quote.get_value()) {
+        // Zero quote is rejected by constraints rather than witness generation
+        (Some(_), Some(quote)) if quote.bits() == 0 => {
+            Some((BigUint::from(0u8), BigUint::from(0u8)))
+        }
+        (Some(base), Some(quote)) => {
+            let scaled = base * &divisor;
+            Some((&scaled / &quote, &scaled % &quote))
+        }
+        _ => None,
+    };
+    let quotient = match &witness {
+        Some((quotient, _)) => Some(fr_from_biguint::<E>(quotient)?),
+        None => None,
+    };
+    let remainder = match &witness {
+        Some((_, remainder)) => Some(fr_from_biguint::<E>(remainder)?),
+        None => None,
+    };
+    let quotient = Num::alloc(cs, quotient)?;
+    let remainder = Num::alloc(cs, remainder)?;
+    quotient.into_bits_le(cs, Some(CROSS_PRICE_QUOTIENT_BITS))?;
+    remainder.into_bits_le(cs, Some(CROSS_PRICE_VALUE_BITS))?;
+
+    // base * 10^decimals == quotient * quote + remainder
+    let scaled = base_num.mul(cs, &Num::Constant(fr_from_biguint::<E>(&divisor)?))?;
+    let recomposed = quotient.mul(cs, &quote_num)?.add(cs, &remainder)?;
+    scaled.enforce_equal(cs, &recomposed)?;
+    // remainder < quote, which also rejects zero quote
+    let (is_equal, is_greater) =
+        prepacked_long_comparison(cs, &[remainder], &[quote_num], &[CROSS_PRICE_VALUE_BITS])?;
+    let is_remainder_valid = Boolean::or(cs, &is_equal, &is_greater)?.not();
+    Boolean::enforce_equal(cs, &is_remainder_valid, &Boolean::constant(true))?;
+
+    let round_up = match rounding {
+        Rounding::Down => Boolean::constant(false),
+        Rounding::Up => remainder.is_zero(cs)?.not(),
+        Rounding::Nearest => {
+            let doubled = remainder.add(cs, &remainder)?;
+            let (is_equal, is_greater) = prepacked_long_comparison(
+                cs,
+                &[doubled],
+                &[quote_num],
+                &[CROSS_PRICE_VALUE_BITS + 1],
+            )?;
+            Boolean::or(cs, &is_equal, &is_greater)?
+        }
+    };
+    quotient.add(cs, &Num::from_boolean_is(round_up))
+}
+
+/// Convert `value` into a number, constraining it to at most `bits` bits (a multiple of 8).
+fn bounded_num<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    value: &UInt256<E>,
+    bits: usize,
+) -> Result<Num<E>, SynthesisError> {
+    let bytes = value.into_be_bytes(cs)?;
+    let split = bytes.len() - bits / 8;
+    let mut high = Num::zero();
+    for byte in bytes[..split].iter() {
+        high = high.add(cs, &byte.inner)?;
+    }
+    high.enforce_equal(cs, &Num::zero())?;
+    num_from_be_bytes(cs, &bytes[split..])
+}
+
+/// Blend prices of the same asset from two oracles into their midpoint rounded down. Also returns
+/// whether they deviate by at most `tolerance_bps` basis points of the lower one, i.e.
+/// `|a - b| * 10^4 <= tolerance_bps * min(a, b)`. Prices must be less than
+/// `2^BLENDED_PRICE_VALUE_BITS`, otherwise circuit is unsatisfied.
+pub fn blend_within_tolerance<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    a: &UInt256<E>,
+    b: &UInt256<E>,
+    tolerance_bps: u64,
+) -> Result<(Boolean, UInt256<E>), SynthesisError> {
+    if tolerance_bps > MAX_TOLERANCE_BPS {
+        return Err(new_synthesis_error(format!(
+            "tolerance {} bps exceeds {}",
+            tolerance_bps, MAX_TOLERANCE_BPS
+        )));
+    }
+    let (_, a_is_lower) = a.sub(cs, b)?;
+    let lower = UInt256::conditionally_select(cs, &a_is_lower, a, b)?;
+    let upper = UInt256::conditionally_select(cs, &a_is_lower, b, a)?;
+    let blended = midpoint(cs, &lower, &upper)?;
+
+    // Both sides are less than 2^(BLENDED_PRICE_VALUE_BITS + 14)
+    let lower = bounded_num(cs, &lower, BLENDED_PRICE_VALUE_BITS)?;
+    let upper = bounded_num(cs, &upper, BLENDED_PRICE_VALUE_BITS)?;
+    let deviation = upper
+        .sub(cs, &lower)?
+        .mul(cs, &Num::Constant(u64_to_fe(10_000)))?;
+    let tolerance = lower.mul(cs, &Num::Constant(u64_to_fe(tolerance_bps)))?;
+    let (_, is_greater) = prepacked_long_comparison(
+        cs,
+        &[deviation],
+        &[tolerance],
+        &[BLENDED_PRICE_VALUE_BITS + 14],
+    )?;
+    Ok((is_greater.not(), blended))
+}
+
+/// Compute median of values. The average of two middle values (rounded down) is used if the
+/// number of values is even.
+pub fn median<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    values: &[UInt256<E>],
+) -> Result<UInt256<E>, SynthesisError> {
+    if values.is_empty() {
+        return Err(new_synthesis_error(
+            "can not compute median of empty values",
+        ));
+    }
+    let sorted = sort(cs, values)?;
+    let mid = sorted.len() / 2;
+    if sorted.len() % 2 == 1 {
+        return Ok(sorted[mid]);
+    }
+    midpoint(cs, &sorted[mid - 1], &sorted[mid])
+}
+
+/// Compute median of values flagged as present, in the same way as [`median`]. Circuit is
+/// unsatisfied if less than `quorum` values are present.
+pub fn median_with_quorum<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    values: &[(Boolean, UInt256<E>)],
+    quorum: usize,
+) -> Result<UInt256<E>, SynthesisError> {
+    if quorum == 0 || quorum > values.len() {
+        return Err(new_synthesis_error(format!(
+            "invalid quorum {} of {} values",
+            quorum,
+            values.len()
+        )));
+    }
+    // Absent values are replaced by the max value, so that present values are sorted ahead
+    let max = UInt256::constant((BigUint::from(1u8) << 256u32) - 1u8);
+    let mut count = Num::zero();
+    let mut replaced = vec![];
+    for (is_present, value) in values.iter() {
+        count = count.add(cs, &Num::from_boolean_is(*is_present))?;
+        replaced.push(UInt256::conditionally_select(cs, is_present, value, &max)?);
+    }
+    let sorted = sort(cs, &replaced)?;
+
+    // Median of the first `count` sorted values, where `count` is at least `quorum`
+    let mut median = UInt256::zero();
+    let mut reaches_quorum = Boolean::constant(false);
+    for n in quorum..=values.len() {
+        let is_count = count.equals(cs, &Num::Constant(u64_to_fe(n as u64)))?;
+        reaches_quorum = Boolean::or(cs, &reaches_quorum, &is_count)?;
+        let mid = n / 2;
+        let candidate = if n % 2 == 1 {
+            sorted[mid]
+        } else {
+            midpoint(cs, &sorted[mid - 1], &sorted[mid])?
+        };
+        median = UInt256::conditionally_select(cs, &is_count, &candidate, &median)?;
+    }
+    Boolean::enforce_equal(cs, &reaches_quorum, &Boolean::constant(true))?;
+    Ok(median)
+}
+
+/// Bubble sort, whose swaps are conditional so that the shape of circuit is fixed
+fn sort<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    values: &[UInt256<E>],
+) -> Result<Vec<UInt256<E>>, SynthesisError> {
+    let mut sorted = values.to_vec();
+    for i in 0..sorted.len() {
+        for j in 0..sorted.len() - 1 - i {
+            let (_, is_less) = sorted[j + 1].sub(cs, &sorted[j])?;
+            let lower = UInt256::conditionally_select(cs, &is_less, &sorted[j + 1], &sorted[j])?;
+            let upper = UInt256::conditionally_select(cs, &is_less, &sorted[j], &sorted[j + 1])?;
+            sorted[j] = lower;
+            sorted[j + 1] = upper;
+        }
+    }
+    Ok(sorted)
+}
+
+/// Compute `(lower + upper) / 2` rounded down as `lower + (upper - lower) / 2`, which never
+/// overflows even if both values are close to 256 bits. Circuit is unsatisfied if `lower > upper`.
+fn midpoint<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    lower: &UInt256<E>,
+    upper: &UInt256<E>,
+) -> Result<UInt256<E>, SynthesisError> {
+    let (diff, borrow) = upper.sub(cs, lower)?;
+    let half = halve(cs, &diff)?;
+    let (midpoint, of) = lower.add(cs, &half)?;
+    let is_invalid = smart_or(cs, &[borrow, of])?;
+    Boolean::enforce_equal(cs, &is_invalid, &Boolean::constant(false))?;
+    Ok(midpoint)
+}
+
+/// Compute `value / 2` rounded down.
+fn halve<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    value: &UInt256<E>,
+) -> Result<UInt256<E>, SynthesisError> {
+    let witness = value.get_value().map(|v| (&v >> 1u32, v.bit(0)));
+    let half = UInt256::alloc_from_witness(cs, witness.as_ref().map(|(half, _)| half.clone()))?;
+    let remainder = {
+        let is_odd = Boolean::alloc_from_witness(cs, witness.map(|(_, is_odd)| is_odd))?;
+        let one = UInt256::constant(BigUint::from(1u8));
+        UInt256::conditionally_select(cs, &is_odd, &one, &UInt256::zero())?
+    };
+    let (doubled, of1) = half.add(cs, &half)?;
+    let (recomposed, of2) = doubled.add(cs, &remainder)?;
+    let is_equal = UInt256::equals(cs, &recomposed, value)?;
+    let is_valid = smart_and(cs, &[is_equal, of1.not(), of2.not()])?;
+    Boolean::enforce_equal(cs, &is_valid, &Boolean::constant(true))?;
+    Ok(half)
 }
 
 #[derive(Clone, Debug)]
@@ -125,13 +708,16 @@ impl<E: Engine> AllocatedSignedDataPackage<E> {
         &self,
         cs: &mut CS,
     ) -> Result<crate::gadgets::ecdsa::EcRecoverRes<E>, SynthesisError> {
-        let msg_hash = {
-            let bytes = self.data_package.serialize()?;
-            use crate::gadgets::keccak256::digest;
-            let hash = digest(cs, &bytes)?;
-            UInt256::from_be_bytes_fixed(cs, &hash)?
-        };
+        let payload_hash = self.data_package.keccak256_hash(cs)?;
+        self.ecrecover_by_payload_hash(cs, &payload_hash)
+    }
 
+    fn ecrecover_by_payload_hash<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        payload_hash: &[Byte<E>; 32],
+    ) -> Result<crate::gadgets::ecdsa::EcRecoverRes<E>, SynthesisError> {
+        let msg_hash = UInt256::from_be_bytes_fixed(cs, payload_hash)?;
         let pubkey = self.signature.ecrecover(cs, &msg_hash)?;
         Ok(pubkey)
     }
@@ -141,19 +727,124 @@ impl<E: Engine> AllocatedSignedDataPackage<E> {
         cs: &mut CS,
         guardian: &Address<E>,
     ) -> Result<Boolean, SynthesisError> {
-        let (successful, (x, y)) = self.ecrecover(cs)?;
+        let (is_ok, _) = self.check_by_address_with_payload_hash(cs, guardian)?;
+        Ok(is_ok)
+    }
 
-        let is_matched = {
-            let (x, y) = (
-                x.into_be_bytes(cs)?.try_into().unwrap(),
-                y.into_be_bytes(cs)?.try_into().unwrap(),
-            );
-            let address = Address::from_pubkey(cs, &x, &y)?;
-            guardian.equals(cs, &address)?
-        };
+    /// Same as [`Self::check_by_address`], but also returns the keccak256 hash of the signed
+    /// payload, i.e. the serialized data package.
+    pub fn check_by_address_with_payload_hash<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        guardian: &Address<E>,
+    ) -> Result<(Boolean, [Byte<E>; 32]), SynthesisError> {
+        let payload_hash = self.data_package.keccak256_hash(cs)?;
+        let msg_hash = UInt256::from_be_bytes_fixed(cs, &payload_hash)?;
+        let is_ok = check_signature(cs, &self.signature, &msg_hash, guardian)?;
+        Ok((is_ok, payload_hash))
+    }
 
-        let is_ok = Boolean::and(cs, &is_matched, &successful)?;
-        Ok(is_ok)
+    /// Nullifier of the package signed by `signer`, which is `poseidon(signer, timestamp, feed ids)`
+    /// where each feed id is split into two 16-bytes limbs.
+    pub fn nullifier<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        signer: &Address<E>,
+    ) -> Result<Num<E>, SynthesisError> {
+        let mut members = vec![
+            signer.inner().to_num_unchecked(cs)?,
+            num_from_be_bytes(cs, &self.data_package.timestamp)?,
+        ];
+        for data_point in self.data_package.data_points.iter() {
+            members.push(num_from_be_bytes(cs, &data_point.data_feed_id[..16])?);
+            members.push(num_from_be_bytes(cs, &data_point.data_feed_id[16..])?);
+        }
+        circuit_poseidon_hash(cs, &members)
+    }
+}
+
+/// Check `signature` of `msg_hash` is successfully recovered to `guardian`.
+pub(super) fn check_signature<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    signature: &Signature<E>,
+    msg_hash: &UInt256<E>,
+    guardian: &Address<E>,
+) -> Result<Boolean, SynthesisError> {
+    let (successful, (x, y)) = signature.ecrecover(cs, msg_hash)?;
+    let is_matched = {
+        let (x, y) = (
+            x.into_be_bytes(cs)?.try_into().unwrap(),
+            y.into_be_bytes(cs)?.try_into().unwrap(),
+        );
+        let address = Address::from_pubkey(cs, &x, &y)?;
+        guardian.equals(cs, &address)?
+    };
+    Boolean::and(cs, &is_matched, &successful)
+}
+
+/// One data package carrying signatures of `NUM_SIGNATURES` signers, which is the case when
+/// signers publish an identical payload. The package is allocated and hashed only once, and every
+/// signature is recovered against the shared digest.
+#[derive(Clone, Debug)]
+pub struct AllocatedMultiSignedDataPackage<E: Engine, const NUM_SIGNATURES: usize> {
+    pub data_package: AllocatedDataPackage<E>,
+    pub signatures: [Signature<E>; NUM_SIGNATURES],
+}
+
+impl<E: Engine, const NUM_SIGNATURES: usize> AllocatedMultiSignedDataPackage<E, NUM_SIGNATURES> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        data_package: DataPackage,
+        signatures: [[u8; 65]; NUM_SIGNATURES],
+    ) -> Result<Self, SynthesisError> {
+        let data_package = AllocatedDataPackage::from_witness(cs, data_package)?;
+        let signatures = signatures
+            .into_iter()
+            .map(|mut signature| {
+                if signature[64] >= 27 {
+                    signature[64] -= 27;
+                }
+                Signature::from_bytes_witness(cs, &signature)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            data_package,
+            signatures: signatures.try_into().unwrap(),
+        })
+    }
+
+    /// Check the i-th signature is signed by the i-th guardian.
+    pub fn check_by_addresses<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        guardians: &[Address<E>],
+    ) -> Result<Boolean, SynthesisError> {
+        let (is_valid, _) = self.check_by_addresses_with_payload_hash(cs, guardians)?;
+        Ok(is_valid)
+    }
+
+    /// Same as [`Self::check_by_addresses`], but also returns the keccak256 hash of the signed
+    /// payload.
+    pub fn check_by_addresses_with_payload_hash<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        guardians: &[Address<E>],
+    ) -> Result<(Boolean, [Byte<E>; 32]), SynthesisError> {
+        if guardians.len() != NUM_SIGNATURES {
+            return Err(new_synthesis_error(format!(
+                "expect {} guardians, got {}",
+                NUM_SIGNATURES,
+                guardians.len()
+            )));
+        }
+        let payload_hash = self.data_package.keccak256_hash(cs)?;
+        let msg_hash = UInt256::from_be_bytes_fixed(cs, &payload_hash)?;
+        let mut is_valid = Boolean::constant(true);
+        for (signature, guardian) in self.signatures.iter().zip(guardians.iter()) {
+            let is_current_valid = check_signature(cs, signature, &msg_hash, guardian)?;
+            is_valid = Boolean::and(cs, &is_valid, &is_current_valid)?;
+        }
+        Ok((is_valid, payload_hash))
     }
 }
 
@@ -170,20 +861,30 @@ impl<E: Engine> AllocatedDataPackage<E> {
         cs: &mut CS,
         witness: DataPackage,
     ) -> Result<Self, SynthesisError> {
+        if witness.value_limbs != 1 {
+            return Err(new_synthesis_error(format!(
+                "expect values of one limb, got {} limbs",
+                witness.value_limbs
+            )));
+        }
         let timestamp = {
             let bytes = witness.serialize_timestamp().try_into().unwrap();
             CSAllocatable::alloc_from_witness(cs, Some(bytes))?
         };
+        // Data points count and value byte size are fixed by the shape of circuit, so they are
+        // constants shared by the keccak inputs of all packages, instead of being allocated and
+        // range checked for every package.
         let data_points_count = {
-            let bytes = witness.serialize_data_points_count().try_into().unwrap();
-            CSAllocatable::alloc_from_witness(cs, Some(bytes))?
+            let bytes: [u8; super::DATA_POINTS_COUNT_BS] =
+                witness.serialize_data_points_count().try_into().unwrap();
+            bytes.map(Byte::constant)
         };
         let default_data_point_value_byte_size = {
-            let bytes = witness
+            let bytes: [u8; super::DATA_POINT_VALUE_BYTE_SIZE_BS] = witness
                 .serialize_default_data_point_byte_size()
                 .try_into()
                 .unwrap();
-            CSAllocatable::alloc_from_witness(cs, Some(bytes))?
+            bytes.map(Byte::constant)
         };
 
         let data_points = witness.sorted_data_points();
@@ -210,6 +911,11 @@ impl<E: Engine> AllocatedDataPackage<E> {
         Ok(bytes)
     }
 
+    /// Byte ranges of fields in [`Self::serialize`], which is the same as the native data package.
+    pub fn layout(&self) -> Vec<FieldLayout> {
+        preimage_layout(self.data_points.len())
+    }
+
     pub fn keccak256_hash<CS: ConstraintSystem<E>>(
         &self,
         cs: &mut CS,
@@ -218,19 +924,115 @@ impl<E: Engine> AllocatedDataPackage<E> {
         use crate::gadgets::keccak256::digest;
         digest(cs, &bytes)
     }
+
+    /// Select value of the data point with the given feed id. Also returns whether it's found.
+    pub fn value_of<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        data_feed_id: &[Byte<E>; 32],
+    ) -> Result<(Boolean, [Byte<E>; super::DEFAULT_NUM_VALUE_BS]), SynthesisError> {
+        let expected_feed_id = UInt256::from_be_bytes_fixed(cs, data_feed_id)?;
+        let mut matches = vec![];
+        for data_point in self.data_points.iter() {
+            let feed_id = UInt256::from_be_bytes_fixed(cs, &data_point.data_feed_id)?;
+            matches.push(UInt256::equals(cs, &feed_id, &expected_feed_id)?);
+        }
+        self.select_value(cs, &matches)
+    }
+
+    /// Whether any data point has the given feed id.
+    pub fn contains<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        data_feed_id: &[Byte<E>; 32],
+    ) -> Result<Boolean, SynthesisError> {
+        let expected_feed_id = UInt256::from_be_bytes_fixed(cs, data_feed_id)?;
+        let mut found = Boolean::constant(false);
+        for data_point in self.data_points.iter() {
+            let feed_id = UInt256::from_be_bytes_fixed(cs, &data_point.data_feed_id)?;
+            let is_matched = UInt256::equals(cs, &feed_id, &expected_feed_id)?;
+            found = Boolean::or(cs, &found, &is_matched)?;
+        }
+        Ok(found)
+    }
+
+    /// Whether data points of all given feed ids are present.
+    pub fn contains_all<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        data_feed_ids: &[[Byte<E>; 32]],
+    ) -> Result<Boolean, SynthesisError> {
+        let mut contains_all = Boolean::constant(true);
+        for data_feed_id in data_feed_ids.iter() {
+            let found = self.contains(cs, data_feed_id)?;
+            contains_all = Boolean::and(cs, &contains_all, &found)?;
+        }
+        Ok(contains_all)
+    }
+
+    /// Same as [`Self::value_of`], but matches the compact feed id, which is cheaper.
+    pub fn value_of_compact<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        feed_id: &CompactFeedId,
+    ) -> Result<(Boolean, [Byte<E>; super::DEFAULT_NUM_VALUE_BS]), SynthesisError> {
+        let matches = self
+            .data_points
+            .iter()
+            .map(|data_point| data_point.is_compact_feed_id(cs, feed_id))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.select_value(cs, &matches)
+    }
+
+    fn select_value<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        matches: &[Boolean],
+    ) -> Result<(Boolean, [Byte<E>; super::DEFAULT_NUM_VALUE_BS]), SynthesisError> {
+        let mut found = Boolean::constant(false);
+        let mut value = [Byte::zero(); super::DEFAULT_NUM_VALUE_BS];
+        for (data_point, is_matched) in self.data_points.iter().zip(matches.iter()) {
+            for (selected, byte) in value.iter_mut().zip(data_point.value.iter()) {
+                let num = Num::conditionally_select(cs, is_matched, &byte.inner, &selected.inner)?;
+                *selected = Byte::from_num_unconstrained(cs, num);
+            }
+            found = Boolean::or(cs, &found, is_matched)?;
+        }
+        Ok((found, value))
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use advanced_circuit_component::franklin_crypto::bellman::SynthesisError;
+    use advanced_circuit_component::franklin_crypto::bellman::pairing::bn256::Bn256;
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte, franklin_crypto::bellman::SynthesisError,
+        franklin_crypto::plonk::circuit::allocated_num::Num, traits::CSAllocatable,
+        utils::u64_to_fe, vm::primitives::uint256::UInt256,
+    };
+    use num_bigint::BigUint;
 
     use crate::{
         gadgets::ethereum::Address,
-        redstone::witness::{DataPackage, DataPoint},
-        utils::testing::{bytes_assert_eq, create_test_constraint_system},
+        redstone::witness::{
+            self, convert_string_to_bytes32, CompactFeedId, DataPackage, DataPoint, PreimageField,
+            Rounding,
+        },
+        utils::{
+            fr_from_biguint,
+            testing::{
+                arb_data_package, bytes_assert_eq, create_test_constraint_system, RedstoneSigner,
+            },
+        },
     };
+    use proptest::prelude::*;
 
-    use super::AllocatedDataPackage;
+    use advanced_circuit_component::franklin_crypto::plonk::circuit::boolean::Boolean;
+
+    use super::{
+        cross_price, median, median_with_quorum, normalize_value, select_latest_values,
+        AllocatedDataPackage, AllocatedDataPoint,
+    };
 
     #[test]
     fn test_serialize_and_hash() -> Result<(), SynthesisError> {
@@ -244,6 +1046,14 @@ mod tests {
         let cs = &mut create_test_constraint_system()?;
         let allocated_data_package = AllocatedDataPackage::from_witness(cs, data_package)
             .expect("should create circuit data package");
+        let suffix = allocated_data_package.data_points_count.iter().chain(
+            allocated_data_package
+                .default_data_point_value_byte_size
+                .iter(),
+        );
+        for byte in suffix {
+            assert!(matches!(byte.inner, Num::Constant(_)));
+        }
         let hash = allocated_data_package.keccak256_hash(cs)?;
         bytes_assert_eq(
             &hash,
@@ -253,6 +1063,63 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_preimage_layout() -> Result<(), SynthesisError> {
+        let data_package = DataPackage::new(
+            vec![
+                DataPoint::new("ETH", "1000"),
+                DataPoint::new("BTC", "20000"),
+            ],
+            1654353400000u64,
+        );
+        let bytes = data_package.serialize();
+        let layout = data_package.layout();
+        let data_points = data_package.sorted_data_points();
+        for field in layout.iter() {
+            let expected = match field.field {
+                PreimageField::FeedId(i) => data_points[i].serialize_feed_id(),
+                PreimageField::Value(i) => data_points[i].serialize_value(),
+                PreimageField::Timestamp => data_package.serialize_timestamp(),
+                PreimageField::ValueByteSize => {
+                    data_package.serialize_default_data_point_byte_size()
+                }
+                PreimageField::DataPointsCount => data_package.serialize_data_points_count(),
+            };
+            assert_eq!(bytes[field.offset..field.offset + field.len], expected);
+        }
+        let last = layout.last().unwrap();
+        assert_eq!(last.offset + last.len, bytes.len());
+
+        // Circuit hashes exactly the same bytes in the same layout
+        let cs = &mut create_test_constraint_system()?;
+        let allocated_data_package = AllocatedDataPackage::from_witness(cs, data_package)?;
+        assert_eq!(allocated_data_package.layout(), layout);
+        let allocated_bytes = allocated_data_package.serialize()?;
+        assert_eq!(
+            hex::encode(Byte::get_byte_value_multiple(&allocated_bytes).unwrap()),
+            hex::encode(bytes)
+        );
+        Ok(())
+    }
+
+    proptest! {
+        // Synthesis is slow, so cases are fewer than default
+        #![proptest_config(ProptestConfig::with_cases(16))]
+        #[test]
+        fn test_serialize_round_trip(data_package in arb_data_package(16)) {
+            let cs = &mut create_test_constraint_system().unwrap();
+            let allocated_data_package =
+                AllocatedDataPackage::from_witness(cs, data_package.clone()).unwrap();
+            let bytes = allocated_data_package.serialize().unwrap();
+            prop_assert_eq!(
+                hex::encode(Byte::get_byte_value_multiple(&bytes).unwrap()),
+                hex::encode(data_package.serialize())
+            );
+            prop_assert_eq!(allocated_data_package.layout(), data_package.layout());
+            prop_assert!(cs.is_satisfied());
+        }
+    }
+
     #[test]
     fn test_check_by_address() -> Result<(), SynthesisError> {
         let cs = &mut create_test_constraint_system()?;
@@ -272,13 +1139,331 @@ mod tests {
 
         let allocated_signed_data_package = super::AllocatedSignedDataPackage::from_witness(
             cs,
-            data_package,
+            data_package.clone(),
             signature.try_into().unwrap(),
         )?;
 
-        let is_valid = allocated_signed_data_package.check_by_address(cs, &address)?;
+        let (is_valid, payload_hash) =
+            allocated_signed_data_package.check_by_address_with_payload_hash(cs, &address)?;
         assert!(is_valid.get_value().unwrap());
+        bytes_assert_eq(&payload_hash, hex::encode(data_package.keccak256_hash()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_signed_data_package() -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let data_package = DataPackage::new(
+            vec![
+                DataPoint::new("BTC", "20000"),
+                DataPoint::new("ETH", "1000"),
+            ],
+            1654353400000u64,
+        );
+        let signers = RedstoneSigner::generate(3);
+        let signatures: [[u8; 65]; 3] = std::array::from_fn(|i| signers[i].sign(&data_package).0);
+        let guardians = signers
+            .iter()
+            .map(|signer| Address::from_address_witness(cs, &signer.address()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let allocated = super::AllocatedMultiSignedDataPackage::from_witness(
+            cs,
+            data_package.clone(),
+            signatures,
+        )?;
+        let (is_valid, payload_hash) =
+            allocated.check_by_addresses_with_payload_hash(cs, &guardians)?;
+        assert!(is_valid.get_value().unwrap());
+        bytes_assert_eq(&payload_hash, hex::encode(data_package.keccak256_hash()));
+
+        // Signatures must match guardians in order
+        let mut swapped = guardians.clone();
+        swapped.swap(0, 1);
+        let is_valid = allocated.check_by_addresses(cs, &swapped)?;
+        assert!(!is_valid.get_value().unwrap());
+        Ok(())
+    }
+
+    #[test]
+    fn test_feed_id_shape() -> Result<(), SynthesisError> {
+        let cases = [
+            (convert_string_to_bytes32("AVAX"), true),
+            ([0u8; 32], false),
+            (convert_string_to_bytes32("A\0B"), false),
+            (convert_string_to_bytes32("\u{80}"), false),
+        ];
+        for (feed_id, expected) in cases {
+            let cs = &mut create_test_constraint_system()?;
+            let feed_id: [Byte<_>; 32] = CSAllocatable::alloc_from_witness(cs, Some(feed_id))?;
+            AllocatedDataPoint::enforce_feed_id_shape(cs, &feed_id)?;
+            assert_eq!(cs.is_satisfied(), expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_compact_feed_id() -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        let long_feed_id = "A".repeat(31);
+        let data_package = DataPackage::new(
+            vec![
+                DataPoint::new("ETH", "1000"),
+                DataPoint::new("ETHX", "2000"),
+                DataPoint::new(&long_feed_id, "3000"),
+            ],
+            1654353400000,
+        );
+        let package = AllocatedDataPackage::from_witness(cs, data_package)?;
+        let cases = [
+            ("ETH", Some("1000")),
+            ("ETHX", Some("2000")),
+            (long_feed_id.as_str(), Some("3000")),
+            ("ET", None),
+            ("BTC", None),
+        ];
+        for (feed_id, expected) in cases {
+            let compact_feed_id = CompactFeedId::new(feed_id).unwrap();
+            let (found, value) = package.value_of_compact(cs, &compact_feed_id)?;
+            let (expected_found, expected_value) =
+                package.value_of(cs, &convert_string_to_bytes32(feed_id).map(Byte::constant))?;
+            assert_eq!(found.get_value(), Some(expected.is_some()));
+            assert_eq!(found.get_value(), expected_found.get_value());
+            assert_eq!(
+                Byte::get_byte_value_multiple(&value),
+                Byte::get_byte_value_multiple(&expected_value)
+            );
+            if let Some(expected) = expected {
+                let expected = DataPoint::new(feed_id, expected).serialize_value();
+                assert_eq!(
+                    hex::encode(Byte::get_byte_value_multiple(&value).unwrap()),
+                    hex::encode(expected)
+                );
+            }
+        }
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_select_latest_values() -> Result<(), SynthesisError> {
+        // `(timestamp, contains)` of packages and values of two feeds in every round
+        let select = |rounds: &[(Vec<(u64, [bool; 2])>, [u64; 2])]| {
+            let cs = &mut create_test_constraint_system()?;
+            let mut packages = vec![];
+            let mut values = vec![];
+            for (round_packages, round_values) in rounds {
+                let mut allocated = vec![];
+                for (timestamp, contains) in round_packages {
+                    let timestamp = Num::alloc(cs, Some(u64_to_fe(*timestamp)))?;
+                    let contains = contains
+                        .iter()
+                        .map(|c| Boolean::alloc(cs, Some(*c)))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    allocated.push((timestamp, contains));
+                }
+                packages.push(allocated);
+                values.push(
+                    round_values
+                        .iter()
+                        .map(|v| UInt256::alloc_from_witness(cs, Some(BigUint::from(*v))))
+                        .collect::<Result<Vec<_>, _>>()?,
+                );
+            }
+            let (timestamp, values) = select_latest_values(cs, &packages, &values)?;
+            let values = values
+                .iter()
+                .map(|v| v.get_value().unwrap())
+                .collect::<Vec<_>>();
+            Ok::<_, SynthesisError>((cs.is_satisfied(), timestamp.get_value().unwrap(), values))
+        };
+
+        // Feed 1 is latest in round 1, but feed 0 is only provided by round 0 and 2
+        let (is_satisfied, timestamp, values) = select(&[
+            (vec![(1000, [true, true]), (1000, [true, true])], [1, 10]),
+            (vec![(3000, [false, true]), (3000, [false, true])], [2, 30]),
+            (vec![(2000, [true, true]), (2000, [true, false])], [3, 20]),
+        ])?;
+        assert!(is_satisfied);
+        assert_eq!(timestamp, u64_to_fe(2000));
+        assert_eq!(values, vec![BigUint::from(3u8), BigUint::from(30u8)]);
+
+        // Feed 0 can not be selected from round 0 if a newer package contains it
+        let (is_satisfied, _, _) = select(&[
+            (vec![(1000, [true, true]), (1000, [true, true])], [1, 10]),
+            (vec![(1000, [true, true]), (2000, [true, false])], [2, 20]),
+        ])?;
+        assert!(!is_satisfied);
+        Ok(())
+    }
 
+    #[test]
+    fn test_median_with_quorum() -> Result<(), SynthesisError> {
+        let cases = [
+            (
+                vec![(true, 5u64), (false, 100), (true, 1), (true, 3)],
+                2,
+                Some(3u64),
+            ),
+            (vec![(true, 2), (true, 5), (false, 0)], 2, Some(3)),
+            (vec![(true, 5), (false, 100), (true, 1), (true, 3)], 4, None),
+        ];
+        for (values, quorum, expected) in cases {
+            let cs = &mut create_test_constraint_system()?;
+            let values = values
+                .into_iter()
+                .map(|(is_present, value)| {
+                    let is_present = Boolean::alloc_from_witness(cs, Some(is_present))?;
+                    let value = UInt256::alloc_from_witness(cs, Some(BigUint::from(value)))?;
+                    Ok((is_present, value))
+                })
+                .collect::<Result<Vec<_>, SynthesisError>>()?;
+            let median = median_with_quorum(cs, &values, quorum)?;
+            match expected {
+                Some(expected) => {
+                    assert!(cs.is_satisfied());
+                    assert_eq!(median.get_value().unwrap(), BigUint::from(expected));
+                }
+                None => assert!(!cs.is_satisfied()),
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_large_values() -> Result<(), SynthesisError> {
+        let cs = &mut create_test_constraint_system()?;
+        // (2^256 - 1) / 10^8 and (2^256 - 3) / 10^8
+        let data_package = DataPackage::new(
+            vec![
+                DataPoint::new(
+                    "BTC",
+                    "1157920892373161954235709850086879078532699846656405640394575840079131.29639935",
+                ),
+                DataPoint::new(
+                    "ETH",
+                    "1157920892373161954235709850086879078532699846656405640394575840079131.29639933",
+                ),
+            ],
+            1654353400000u64,
+        );
+        let allocated_data_package = AllocatedDataPackage::from_witness(cs, data_package)?;
+        let values = allocated_data_package
+            .data_points
+            .iter()
+            .map(|data_point| UInt256::from_be_bytes_fixed(cs, &data_point.value))
+            .collect::<Result<Vec<_>, _>>()?;
+        bytes_assert_eq(
+            &allocated_data_package.data_points[0].value,
+            "ff".repeat(32),
+        );
+        // Median of two values close to 2^256 does not overflow
+        let median = median(cs, &values)?;
+        let max = (BigUint::from(1u8) << 256u32) - 1u8;
+        assert_eq!(median.get_value().unwrap(), max - 1u8);
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cross_price() -> Result<(), SynthesisError> {
+        let decimals = BigUint::from(10u8).pow(18);
+        let eth_usd = &decimals * 3000u32;
+        let btc_usd = &decimals * 60000u32;
+        let cases = [
+            // ETH/BTC = 0.05
+            (eth_usd.clone(), btc_usd.clone(), 18, Rounding::Down),
+            // BTC/ETH = 20 with 8 decimals
+            (btc_usd.clone(), eth_usd.clone(), 8, Rounding::Nearest),
+            // 1/3 and 2/3
+            (BigUint::from(1u8), BigUint::from(3u8), 18, Rounding::Down),
+            (BigUint::from(1u8), BigUint::from(3u8), 18, Rounding::Up),
+            (
+                BigUint::from(2u8),
+                BigUint::from(3u8),
+                18,
+                Rounding::Nearest,
+            ),
+            (BigUint::from(2u8), BigUint::from(3u8), 18, Rounding::Down),
+            // Zero quote or too large values
+            (eth_usd.clone(), BigUint::from(0u8), 18, Rounding::Down),
+            (
+                BigUint::from(1u8) << 96u32,
+                btc_usd.clone(),
+                0,
+                Rounding::Down,
+            ),
+        ];
+        for (base, quote, decimals, rounding) in cases {
+            let cs = &mut create_test_constraint_system()?;
+            let expected = witness::cross_price(&base, &quote, decimals, rounding);
+            let base = UInt256::alloc_from_witness(cs, Some(base))?;
+            let quote = UInt256::alloc_from_witness(cs, Some(quote))?;
+            let price = cross_price(cs, &base, &quote, decimals, rounding)?;
+            match expected {
+                Some(expected) => {
+                    assert!(cs.is_satisfied());
+                    assert_eq!(
+                        price.get_value().unwrap(),
+                        fr_from_biguint::<Bn256>(&expected)?
+                    );
+                }
+                None => assert!(!cs.is_satisfied()),
+            }
+        }
+        assert_eq!(
+            witness::cross_price(&eth_usd, &btc_usd, 18, Rounding::Down),
+            Some(&decimals / 20u32)
+        );
+        assert_eq!(
+            witness::cross_price(
+                &BigUint::from(2u8),
+                &BigUint::from(3u8),
+                0,
+                Rounding::Nearest
+            ),
+            Some(BigUint::from(1u8))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_normalize_value() -> Result<(), SynthesisError> {
+        let cases = [
+            // 36.2488073814028 with 8 decimals
+            (
+                BigUint::from(3624880738u64),
+                8,
+                18,
+                Some(BigUint::from(36248807380000000000u128)),
+            ),
+            (
+                BigUint::from(36248807381402800000u128),
+                18,
+                8,
+                Some(BigUint::from(3624880738u64)),
+            ),
+            (
+                BigUint::from(3624880738u64),
+                8,
+                8,
+                Some(BigUint::from(3624880738u64)),
+            ),
+            (BigUint::from(1u8) << 255u32, 8, 18, None),
+        ];
+        for (value, decimals, target_decimals, expected) in cases {
+            let cs = &mut create_test_constraint_system()?;
+            let value = UInt256::alloc_from_witness(cs, Some(value))?;
+            let normalized = normalize_value(cs, &value, decimals, target_decimals)?;
+            match expected {
+                Some(expected) => {
+                    assert!(cs.is_satisfied());
+                    assert_eq!(normalized.get_value().unwrap(), expected);
+                }
+                None => assert!(!cs.is_satisfied()),
+            }
+        }
         Ok(())
     }
 }
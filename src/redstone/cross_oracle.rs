@@ -0,0 +1,333 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{ff::Field, Engine};
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{
+            plonk::better_better_cs::{
+                cs::{Circuit, ConstraintSystem, Gate, GateInternal},
+                gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
+            },
+            SynthesisError,
+        },
+        plonk::circuit::{
+            allocated_num::Num, boolean::Boolean, custom_rescue_gate::Rescue5CustomGate,
+        },
+    },
+    vm::primitives::{uint256::UInt256, UInt128, UInt64},
+};
+use num_bigint::BigUint;
+
+use crate::{
+    gadgets::{
+        ethereum::Address,
+        poseidon::{circuit_poseidon_hash, poseidon_hash},
+        rescue::circuit_rescue_hash,
+    },
+    utils::{self, fr_from_biguint},
+};
+
+use super::{
+    circuit::{blend_within_tolerance, normalize_value, AllocatedSignedPrice},
+    feed_id_bytes,
+    preflight::{validate_witness, ValidationPolicy},
+    witness::{self, convert_string_to_bytes32, guardian_set_hash, SignedPackageWitness},
+    RedstoneConfig, MAX_TOLERANCE_BPS,
+};
+
+/// Circuit proving the price of one asset from two independent sources, e.g. two RedStone data
+/// services signed by different guardian sets, which must agree within `tolerance_bps` basis
+/// points. The blended price is the midpoint of both aggregated prices.
+///
+/// The only public input is the commitment
+/// `poseidon(primary_guardian_set_hash, secondary_guardian_set_hash, feed_id, blended_price,
+/// primary_publish_time, secondary_publish_time)`, where `feed_id` keeps the first 15 bytes as in
+/// [`super::PriceOracle`].
+pub struct CrossOraclePrice<E: Engine, const NUM_PRIMARY: usize, const NUM_SECONDARY: usize> {
+    pub feed_id: String,
+    pub tolerance_bps: u64,
+    pub primary_signed_packages: [SignedPackageWitness; NUM_PRIMARY],
+    pub primary_guardians: [[u8; 20]; NUM_PRIMARY],
+    pub secondary_signed_packages: [SignedPackageWitness; NUM_SECONDARY],
+    pub secondary_guardians: [[u8; 20]; NUM_SECONDARY],
+    pub config: RedstoneConfig,
+    pub blended_price: BigUint,
+    pub commitment: E::Fr,
+}
+
+impl<E: Engine, const NUM_PRIMARY: usize, const NUM_SECONDARY: usize>
+    CrossOraclePrice<E, NUM_PRIMARY, NUM_SECONDARY>
+{
+    pub fn new(
+        feed_id: String,
+        tolerance_bps: u64,
+        primary: ([SignedPackageWitness; NUM_PRIMARY], [[u8; 20]; NUM_PRIMARY]),
+        secondary: (
+            [SignedPackageWitness; NUM_SECONDARY],
+            [[u8; 20]; NUM_SECONDARY],
+        ),
+    ) -> Result<Self, anyhow::Error> {
+        Self::new_with_config(
+            feed_id,
+            tolerance_bps,
+            primary,
+            secondary,
+            RedstoneConfig::default(),
+        )
+    }
+
+    pub fn new_with_config(
+        feed_id: String,
+        tolerance_bps: u64,
+        (primary_signed_packages, primary_guardians): (
+            [SignedPackageWitness; NUM_PRIMARY],
+            [[u8; 20]; NUM_PRIMARY],
+        ),
+        (secondary_signed_packages, secondary_guardians): (
+            [SignedPackageWitness; NUM_SECONDARY],
+            [[u8; 20]; NUM_SECONDARY],
+        ),
+        config: RedstoneConfig,
+    ) -> Result<Self, anyhow::Error> {
+        if tolerance_bps > MAX_TOLERANCE_BPS {
+            anyhow::bail!(
+                "tolerance {} bps exceeds {}",
+                tolerance_bps,
+                MAX_TOLERANCE_BPS
+            )
+        }
+        let policy = ValidationPolicy::new(vec![feed_id.clone()]).with_config(config.clone());
+        let primary_price = source_price(&primary_signed_packages, &primary_guardians, &policy)?;
+        let secondary_price =
+            source_price(&secondary_signed_packages, &secondary_guardians, &policy)?;
+        let blended_price =
+            witness::blend_within_tolerance(&primary_price, &secondary_price, tolerance_bps)
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "prices {} and {} of {} deviate more than {} bps",
+                        primary_price,
+                        secondary_price,
+                        feed_id,
+                        tolerance_bps
+                    )
+                })?;
+
+        let commitment = poseidon_hash::<E>(&[
+            guardian_set_hash::<E>(&primary_guardians)?,
+            guardian_set_hash::<E>(&secondary_guardians)?,
+            fr_from_biguint::<E>(&BigUint::from_bytes_be(&feed_id_bytes(&feed_id)))?,
+            fr_from_biguint::<E>(&blended_price)?,
+            fr_from_biguint::<E>(&BigUint::from(primary_signed_packages[0].package.timestamp))?,
+            fr_from_biguint::<E>(&BigUint::from(
+                secondary_signed_packages[0].package.timestamp,
+            ))?,
+        ]);
+        Ok(Self {
+            feed_id,
+            tolerance_bps,
+            primary_signed_packages,
+            primary_guardians,
+            secondary_signed_packages,
+            secondary_guardians,
+            config,
+            blended_price,
+            commitment,
+        })
+    }
+}
+
+pub(crate) fn source_price(
+    signed_packages: &[SignedPackageWitness],
+    guardians: &[[u8; 20]],
+    policy: &ValidationPolicy,
+) -> Result<BigUint, anyhow::Error> {
+    if signed_packages.is_empty() {
+        anyhow::bail!("source has no data package")
+    }
+    let witnesses = signed_packages
+        .iter()
+        .zip(guardians.iter())
+        .map(|(witness, guardian)| (witness.package.clone(), witness.signature, *guardian))
+        .collect::<Vec<_>>();
+    Ok(validate_witness(&witnesses, policy)?.remove(0))
+}
+
+/// Verify signatures of one source and aggregate its price of `feed_id`. Returns the guardian set
+/// hash, the normalized price and the publish time of the source.
+pub(crate) fn synthesize_source<E: Engine, CS: ConstraintSystem<E>, const NUM_SIGNATURES: usize>(
+    cs: &mut CS,
+    signed_packages: &[SignedPackageWitness; NUM_SIGNATURES],
+    guardians: &[[u8; 20]; NUM_SIGNATURES],
+    feed_id: &[Byte<E>; 32],
+    config: &RedstoneConfig,
+) -> Result<(Num<E>, UInt256<E>, Num<E>), SynthesisError> {
+    let signed_price =
+        AllocatedSignedPrice::<E, NUM_SIGNATURES>::from_witness(cs, signed_packages.clone())?;
+    let guardians = guardians
+        .iter()
+        .map(|a| Address::from_address_witness(cs, a))
+        .collect::<Result<Vec<_>, _>>()?;
+    let is_valid = signed_price.check_by_addresses(cs, &guardians)?;
+    Boolean::enforce_equal(cs, &is_valid, &Boolean::constant(true))?;
+
+    let price =
+        signed_price.aggregated_value_with_quorum(cs, feed_id, config.quorum(NUM_SIGNATURES))?;
+    let price = normalize_value(
+        cs,
+        &price,
+        config.num_value_decimals,
+        config.target_num_value_decimals,
+    )?;
+    let publish_time = {
+        let mut publish_time = [Byte::zero(); 8];
+        let significant_bytes = signed_price.timestamp();
+        publish_time[8 - significant_bytes.len()..].copy_from_slice(&significant_bytes);
+        publish_time.reverse();
+        UInt64::from_bytes_le(cs, &publish_time)?.into_num()
+    };
+    let guardian_nums = guardians
+        .iter()
+        .map(|g| g.inner().to_num_unchecked(cs))
+        .collect::<Result<Vec<_>, _>>()?;
+    let guardian_set_hash = circuit_poseidon_hash(cs, &guardian_nums)?;
+    Ok((guardian_set_hash, price, publish_time))
+}
+
+impl<E: Engine, const NUM_PRIMARY: usize, const NUM_SECONDARY: usize> Circuit<E>
+    for CrossOraclePrice<E, NUM_PRIMARY, NUM_SECONDARY>
+{
+    type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
+
+    fn synthesize<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<(), SynthesisError> {
+        utils::add_bitwise_logic_and_range_table(cs)?;
+        let temp_variable = Num::alloc(cs, Some(E::Fr::one()))?;
+        circuit_rescue_hash(cs, &[temp_variable])?; // Just to standardize the proof format
+
+        // Feed id is fixed by circuit
+        let feed_id = convert_string_to_bytes32(&self.feed_id).map(Byte::<E>::constant);
+        let (primary_guardian_set_hash, primary_price, primary_publish_time) = synthesize_source(
+            cs,
+            &self.primary_signed_packages,
+            &self.primary_guardians,
+            &feed_id,
+            &self.config,
+        )?;
+        let (secondary_guardian_set_hash, secondary_price, secondary_publish_time) =
+            synthesize_source(
+                cs,
+                &self.secondary_signed_packages,
+                &self.secondary_guardians,
+                &feed_id,
+                &self.config,
+            )?;
+
+        let (is_consistent, blended_price) =
+            blend_within_tolerance(cs, &primary_price, &secondary_price, self.tolerance_bps)?;
+        Boolean::enforce_equal(cs, &is_consistent, &Boolean::constant(true))?;
+
+        let feed_id_num = {
+            // Due the limitation of zklink state tree, we can only store first 15 bytes of feed_id
+            let mut bytes = [Byte::zero(); 16];
+            bytes[1..].copy_from_slice(&feed_id[0..15]);
+            bytes.reverse();
+            UInt128::from_bytes_le(cs, &bytes)?.into_num()
+        };
+        let blended_price = blended_price.to_num_unchecked(cs)?;
+        let commitment = circuit_poseidon_hash(
+            cs,
+            &[
+                primary_guardian_set_hash,
+                secondary_guardian_set_hash,
+                feed_id_num,
+                blended_price,
+                primary_publish_time,
+                secondary_publish_time,
+            ],
+        )?;
+        let expected_commitment = Num::alloc(cs, Some(self.commitment))?;
+        expected_commitment.enforce_equal(cs, &commitment)?;
+        expected_commitment.get_variable().inputize(cs)?;
+        Ok(())
+    }
+
+    fn declare_used_gates() -> Result<Vec<Box<dyn GateInternal<E>>>, SynthesisError> {
+        Ok(vec![
+            Self::MainGate::default().into_internal(),
+            Rescue5CustomGate.into_internal(), // Just to standardize the proof format
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::bellman::plonk::better_better_cs::cs::Circuit,
+        testing::{create_test_artifacts_with_optimized_gate, Bn256},
+    };
+    use num_bigint::BigUint;
+
+    use super::CrossOraclePrice;
+    use crate::{
+        redstone::witness::{DataPackage, DataPoint, SignedPackageWitness},
+        utils::testing::RedstoneSigner,
+    };
+
+    fn source<const N: usize>(
+        signers: &[RedstoneSigner],
+        values: [&str; N],
+        timestamp: u64,
+    ) -> ([SignedPackageWitness; N], [[u8; 20]; N]) {
+        let signed_packages = std::array::from_fn(|i| {
+            let package = DataPackage::new(vec![DataPoint::new("BTC", values[i])], timestamp);
+            signers[i].sign_witness(package)
+        });
+        (
+            signed_packages,
+            std::array::from_fn(|i| signers[i].address()),
+        )
+    }
+
+    #[test]
+    fn test_cross_oracle_price() -> anyhow::Result<()> {
+        let signers = RedstoneSigner::generate(4);
+        let (primary_signers, secondary_signers) = signers.split_at(2);
+        let primary = source(primary_signers, ["20000", "20020"], 1654353400000);
+        let secondary = source(secondary_signers, ["20060", "20080"], 1654353405000);
+
+        // Medians are 20010 and 20070, which deviate by about 30 bps
+        let circuit = CrossOraclePrice::<Bn256, 2, 2>::new(
+            "BTC".to_string(),
+            50,
+            primary.clone(),
+            secondary.clone(),
+        )?;
+        assert_eq!(
+            circuit.blended_price,
+            BigUint::from(20040u64) * BigUint::from(10u64).pow(18)
+        );
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+
+        // Divergent sources are rejected
+        assert!(CrossOraclePrice::<Bn256, 2, 2>::new(
+            "BTC".to_string(),
+            20,
+            primary.clone(),
+            secondary.clone()
+        )
+        .is_err());
+        let mut circuit = circuit;
+        circuit.tolerance_bps = 20;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+
+        // Sources must be signed by their own guardians
+        let swapped = (primary.0.clone(), secondary.1);
+        assert!(
+            CrossOraclePrice::<Bn256, 2, 2>::new("BTC".to_string(), 50, swapped, secondary)
+                .is_err()
+        );
+        Ok(())
+    }
+}
@@ -1,5 +1,3 @@
-use std::ops::Mul as _;
-
 use advanced_circuit_component::franklin_crypto::bellman::pairing::{
     ff::{Field, PrimeField},
     Engine,
@@ -15,32 +13,50 @@ use advanced_circuit_component::{
             SynthesisError,
         },
         plonk::circuit::{
-            allocated_num::{AllocatedNum, Num},
-            boolean::Boolean,
-            custom_rescue_gate::Rescue5CustomGate,
+            allocated_num::Num, boolean::Boolean, custom_rescue_gate::Rescue5CustomGate,
         },
     },
     glue::prepacked_long_comparison,
     vm::primitives::{uint256::UInt256, UInt128, UInt64},
 };
-use bigdecimal::num_traits::FromBytes;
 use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
 
 use crate::{
     gadgets::{
-        ethereum::Address,
+        comparison::is_within_bounds,
+        ethereum::{block_header_timestamp, Address, BlockHeader},
         poseidon::{circuit_poseidon_hash, poseidon_hash},
         rescue::circuit_rescue_hash,
+        uint256::to_num_checked,
     },
-    utils::{self, fr_from_biguint},
-    witness::{PricesSummarize, PublicInputData},
+    oracle::{circuit_outputs_commitment, circuit_summarize_prices, SignedPriceOracle},
+    utils::{self, fr_from_biguint, new_synthesis_error, num_from_be_bytes, CancellationToken},
+    witness::{OracleOutputData, OraclePricesSummarize, PricesSummarize, PublicInputData},
+    zklink::{OracleOpening, OraclePrice, OracleRound},
 };
 
-use self::{circuit::AllocatedSignedPrice, witness::DataPackage};
+use self::{
+    circuit::{
+        check_package_freshness, check_timestamp_freshness, normalize_value, output_commitment,
+        select_latest_values, AllocatedSignedPrice,
+    },
+    witness::{
+        convert_string_to_bytes32, guardian_set_hash, is_package_fresh, median,
+        select_latest_values, CompactFeedId, PackageMetadata, SignedPackageWitness,
+    },
+};
 
 pub mod circuit;
+pub mod cross_oracle;
+pub mod preflight;
+pub mod rotation;
+pub mod wide;
 pub mod witness;
 
+pub use preflight::{validate_witness, ValidationPolicy};
+
 // Number of bytes reserved to store timestamp
 pub const TIMESTAMP_BS: usize = 6;
 // Number of bytes reserved to store the number of data points
@@ -49,248 +65,963 @@ pub const DATA_POINTS_COUNT_BS: usize = 3;
 pub const DATA_POINT_VALUE_BYTE_SIZE_BS: usize = 4;
 // Default value byte size for numeric values
 pub const DEFAULT_NUM_VALUE_BS: usize = 32;
+// Max number of 32-bytes limbs of numeric values wider than `DEFAULT_NUM_VALUE_BS`
+pub const MAX_NUM_VALUE_LIMBS: usize = 4;
 // Default precision for numeric values
 pub const DEFAULT_NUM_VALUE_DECIMALS: usize = 8;
+// Precision of values committed by circuits
+pub const TARGET_NUM_VALUE_DECIMALS: usize = 18;
+// Number of bytes reserved to store the signature of a data package
+pub const SIGNATURE_BS: usize = 65;
+// Number of bytes reserved to store the number of data packages in a payload
+pub const DATA_PACKAGES_COUNT_BS: usize = 2;
+// Number of bytes reserved to store the byte size of unsigned metadata in a payload
+pub const UNSIGNED_METADATA_BYTE_SIZE_BS: usize = 3;
+// Max delay of data package timestamp behind the reference time, in milliseconds
+pub const DEFAULT_MAX_TIMESTAMP_DELAY_MS: u64 = 3 * 60 * 1000;
+// Max time that data package timestamp can be ahead of the reference time, in milliseconds
+pub const DEFAULT_MAX_TIMESTAMP_AHEAD_MS: u64 = 60 * 1000;
+// Max bits of prices from which cross prices are derived, so that arithmetic never wraps the field
+pub const CROSS_PRICE_VALUE_BITS: usize = 96;
+// Max decimals of derived cross prices
+pub const MAX_CROSS_PRICE_DECIMALS: usize = 18;
+// Max bits of prices from different oracles that are blended, so that arithmetic never wraps the field
+pub const BLENDED_PRICE_VALUE_BITS: usize = 96;
+// Max tolerance of price deviation between oracles, in basis points
+pub const MAX_TOLERANCE_BPS: u64 = 10_000;
+// Marker appended to the end of every RedStone payload
+pub const REDSTONE_MARKER: [u8; 9] = [0x00, 0x00, 0x02, 0xed, 0x57, 0x01, 0x1e, 0x00, 0x00];
+
+/// Parameters of RedStone circuits, which are passed to circuit constructors so that differently
+/// parameterized circuits can be instantiated. [`Default`] follows the constants above.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RedstoneConfig {
+    /// Value byte size of data points, only [`DEFAULT_NUM_VALUE_BS`] is supported by circuits
+    pub num_value_bs: usize,
+    /// Timestamp byte size of data packages, only [`TIMESTAMP_BS`] is supported by circuits
+    pub timestamp_bs: usize,
+    /// Precision of signed numeric values
+    pub num_value_decimals: usize,
+    /// Precision of values committed by circuits
+    pub target_num_value_decimals: usize,
+    /// Number of signers required to provide every feed, or all signers if `None`
+    pub quorum: Option<usize>,
+    /// Max delay of data package timestamp behind the reference time, in milliseconds
+    pub max_timestamp_delay_ms: u64,
+    /// Max time that data package timestamp can be ahead of the reference time, in milliseconds
+    pub max_timestamp_ahead_ms: u64,
+}
+
+impl Default for RedstoneConfig {
+    fn default() -> Self {
+        Self {
+            num_value_bs: DEFAULT_NUM_VALUE_BS,
+            timestamp_bs: TIMESTAMP_BS,
+            num_value_decimals: DEFAULT_NUM_VALUE_DECIMALS,
+            target_num_value_decimals: TARGET_NUM_VALUE_DECIMALS,
+            quorum: None,
+            max_timestamp_delay_ms: DEFAULT_MAX_TIMESTAMP_DELAY_MS,
+            max_timestamp_ahead_ms: DEFAULT_MAX_TIMESTAMP_AHEAD_MS,
+        }
+    }
+}
+
+impl RedstoneConfig {
+    /// Check the config can be used by circuits verifying `num_signers` signers.
+    pub fn validate(&self, num_signers: usize) -> anyhow::Result<()> {
+        if self.num_value_bs != DEFAULT_NUM_VALUE_BS {
+            anyhow::bail!(
+                "unsupported value byte size {}, expect {}",
+                self.num_value_bs,
+                DEFAULT_NUM_VALUE_BS
+            )
+        }
+        if self.timestamp_bs != TIMESTAMP_BS {
+            anyhow::bail!(
+                "unsupported timestamp byte size {}, expect {}",
+                self.timestamp_bs,
+                TIMESTAMP_BS
+            )
+        }
+        // Normalized values must fit 32 bytes, whose max is about 1.16 * 10^77
+        if self.num_value_decimals > 77 || self.target_num_value_decimals > 77 {
+            anyhow::bail!(
+                "unsupported decimals {} -> {}",
+                self.num_value_decimals,
+                self.target_num_value_decimals
+            )
+        }
+        if let Some(quorum) = self.quorum {
+            if quorum == 0 || quorum > num_signers {
+                anyhow::bail!("invalid quorum {}, expect 1 to {}", quorum, num_signers)
+            }
+        }
+        Ok(())
+    }
+
+    /// Number of signers required to provide every feed among `num_signers` signers.
+    pub fn quorum(&self, num_signers: usize) -> usize {
+        self.quorum.unwrap_or(num_signers)
+    }
+}
+
+/// Feeds attested by [`PriceOracle`] in every round.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeedSet<const NUM_FEEDS: usize> {
+    /// Every feed is signed in separate packages, i.e. a round is `NUM_FEEDS` groups of packages,
+    /// and feed `i` is the first data point of the first package in group `i`.
+    Separate,
+    /// Feeds of ids fixed by circuit are aggregated by median from the same packages, i.e. a round
+    /// is one group of packages, at least `quorums[i]` of which contain `ids[i]`.
+    Shared {
+        ids: [String; NUM_FEEDS],
+        quorums: [usize; NUM_FEEDS],
+    },
+}
 
-pub struct PriceOracle<E: Engine, const NUM_SIGNATURES_TO_VERIFY: usize, const NUM_PRICE: usize> {
-    pub signed_prices_batch: Vec<[[(DataPackage, [u8; 65]); NUM_SIGNATURES_TO_VERIFY]; NUM_PRICE]>,
+/// Price oracle attesting `NUM_FEEDS` feeds of [`FeedSet`] in every round, where each group of
+/// packages is signed by `NUM_SIGNATURES_TO_VERIFY` guardians. With no shared feeds, heartbeat
+/// packages without data points can be used to prove liveness of signers.
+///
+/// Options below only extend the public inputs, which are otherwise the commitment of
+/// [`crate::oracle::outputs_commitment`]:
+/// - `reference_block_header`: every package must be fresh relative to the timestamp of that L1
+///   block, whose hash is appended to the commitment. With `feed_max_delays`, packages containing
+///   a shared feed may be as old as its max delay instead of the one of `config`.
+/// - `latest_only`: only the latest price of every shared feed is committed in one round.
+/// - `bounds`: the normalized price of feed `i` in every round must be within `bounds[i]`.
+/// - `nullifiers`: nullifiers of all packages follow the commitment.
+/// - `payload_hashes`: keccak256 hashes of all packages follow as two 128-bit limbs `(hi, lo)`.
+/// - `complete_packages`: every package must contain all shared feeds.
+/// - `compact_feed_ids`: shared feed ids are matched in circuit by significant bytes only.
+/// - `output_commitment`: all public inputs above and every committed
+///   `(feed_id, price, publish_time)` triple are absorbed into one Poseidon hash instead.
+pub struct PriceOracle<E: Engine, const NUM_SIGNATURES_TO_VERIFY: usize, const NUM_FEEDS: usize> {
+    pub feeds: FeedSet<NUM_FEEDS>,
+    pub signed_prices_batch: Vec<Vec<[SignedPackageWitness; NUM_SIGNATURES_TO_VERIFY]>>,
     pub guardians: [[u8; 20]; NUM_SIGNATURES_TO_VERIFY],
     pub public_input_data: PublicInputData<E>,
     pub commitment: E::Fr,
+    pub cancellation_token: CancellationToken,
+    pub reference_block_header: Option<Vec<u8>>,
+    pub feed_max_delays: Option<[u64; NUM_FEEDS]>,
+    pub latest_only: bool,
+    pub nullifiers: Option<Vec<E::Fr>>,
+    pub bounds: Option<[(BigUint, BigUint); NUM_FEEDS]>,
+    pub payload_hashes: Option<Vec<[u8; 32]>>,
+    pub output_commitment: bool,
+    pub compact_feed_ids: bool,
+    pub complete_packages: bool,
+    pub config: RedstoneConfig,
 }
 
-impl<E: Engine, const NUM_SIGNATURES_TO_VERIFY: usize, const NUM_PRICES: usize>
-    PriceOracle<E, NUM_SIGNATURES_TO_VERIFY, NUM_PRICES>
+impl<E: Engine, const NUM_SIGNATURES_TO_VERIFY: usize, const NUM_FEEDS: usize>
+    PriceOracle<E, NUM_SIGNATURES_TO_VERIFY, NUM_FEEDS>
 {
     pub fn new(
-        signed_prices_batch: Vec<[[(DataPackage, [u8; 65]); NUM_SIGNATURES_TO_VERIFY]; NUM_PRICES]>,
+        signed_prices_batch: Vec<[[SignedPackageWitness; NUM_SIGNATURES_TO_VERIFY]; NUM_FEEDS]>,
         guardian_set: [[u8; 20]; NUM_SIGNATURES_TO_VERIFY],
     ) -> Result<Self, anyhow::Error> {
-        let mut last_publish_time = 0;
-        let mut earliest_publish_time = 0;
-        let mut prices_commitments = vec![];
-        for signed_prices in signed_prices_batch.iter() {
-            // Check publish time is increasing
-            let signed_price = signed_prices[0].clone();
-            {
-                let current_publish_time = signed_price[0].0.timestamp;
-                if current_publish_time < last_publish_time {
+        Self::new_with_cancellation_token(
+            signed_prices_batch,
+            guardian_set,
+            CancellationToken::new(),
+        )
+    }
+
+    /// Same as [`Self::new`], but aborts once `cancellation_token` is cancelled. The token is
+    /// kept by the circuit and checked again during synthesis.
+    pub fn new_with_cancellation_token(
+        signed_prices_batch: Vec<[[SignedPackageWitness; NUM_SIGNATURES_TO_VERIFY]; NUM_FEEDS]>,
+        guardian_set: [[u8; 20]; NUM_SIGNATURES_TO_VERIFY],
+        cancellation_token: CancellationToken,
+    ) -> Result<Self, anyhow::Error> {
+        Self::build(
+            FeedSet::Separate,
+            signed_prices_batch.into_iter().map(Vec::from).collect(),
+            guardian_set,
+            RedstoneConfig::default(),
+            cancellation_token,
+        )
+    }
+
+    /// Same as [`Self::new`], but parameterized by `config` instead of default constants.
+    pub fn new_with_config(
+        signed_prices_batch: Vec<[[SignedPackageWitness; NUM_SIGNATURES_TO_VERIFY]; NUM_FEEDS]>,
+        guardian_set: [[u8; 20]; NUM_SIGNATURES_TO_VERIFY],
+        config: RedstoneConfig,
+    ) -> Result<Self, anyhow::Error> {
+        Self::build(
+            FeedSet::Separate,
+            signed_prices_batch.into_iter().map(Vec::from).collect(),
+            guardian_set,
+            config,
+            CancellationToken::new(),
+        )
+    }
+
+    /// Attest `feed_ids` shared by packages of every round, which must be provided by all signers.
+    pub fn new_shared(
+        feed_ids: [String; NUM_FEEDS],
+        signed_packages_batch: Vec<[SignedPackageWitness; NUM_SIGNATURES_TO_VERIFY]>,
+        guardian_set: [[u8; 20]; NUM_SIGNATURES_TO_VERIFY],
+    ) -> Result<Self, anyhow::Error> {
+        Self::new_shared_with_config(
+            feed_ids,
+            signed_packages_batch,
+            guardian_set,
+            RedstoneConfig::default(),
+        )
+    }
+
+    /// Same as [`Self::new_shared`], but parameterized by `config`, whose quorum applies to every
+    /// feed.
+    pub fn new_shared_with_config(
+        feed_ids: [String; NUM_FEEDS],
+        signed_packages_batch: Vec<[SignedPackageWitness; NUM_SIGNATURES_TO_VERIFY]>,
+        guardian_set: [[u8; 20]; NUM_SIGNATURES_TO_VERIFY],
+        config: RedstoneConfig,
+    ) -> Result<Self, anyhow::Error> {
+        let quorums = [config.quorum(NUM_SIGNATURES_TO_VERIFY); NUM_FEEDS];
+        Self::build(
+            FeedSet::Shared {
+                ids: feed_ids,
+                quorums,
+            },
+            signed_packages_batch.into_iter().map(|p| vec![p]).collect(),
+            guardian_set,
+            config,
+            CancellationToken::new(),
+        )
+    }
+
+    /// Same as [`Self::new_shared`], but requires `quorums[i]` signers, instead of all signers, to
+    /// provide the price of `feed_ids[i]`. The price is then aggregated from signers providing it.
+    pub fn new_shared_with_quorums(
+        feed_ids: [String; NUM_FEEDS],
+        quorums: [usize; NUM_FEEDS],
+        signed_packages_batch: Vec<[SignedPackageWitness; NUM_SIGNATURES_TO_VERIFY]>,
+        guardian_set: [[u8; 20]; NUM_SIGNATURES_TO_VERIFY],
+    ) -> Result<Self, anyhow::Error> {
+        Self::build(
+            FeedSet::Shared {
+                ids: feed_ids,
+                quorums,
+            },
+            signed_packages_batch.into_iter().map(|p| vec![p]).collect(),
+            guardian_set,
+            RedstoneConfig::default(),
+            CancellationToken::new(),
+        )
+    }
+
+    fn build(
+        feeds: FeedSet<NUM_FEEDS>,
+        signed_prices_batch: Vec<Vec<[SignedPackageWitness; NUM_SIGNATURES_TO_VERIFY]>>,
+        guardian_set: [[u8; 20]; NUM_SIGNATURES_TO_VERIFY],
+        config: RedstoneConfig,
+        cancellation_token: CancellationToken,
+    ) -> Result<Self, anyhow::Error> {
+        config.validate(NUM_SIGNATURES_TO_VERIFY)?;
+        if let FeedSet::Shared { ids, quorums } = &feeds {
+            for (feed_id, quorum) in ids.iter().zip(quorums.iter()) {
+                if *quorum == 0 || *quorum > NUM_SIGNATURES_TO_VERIFY {
                     anyhow::bail!(
-                        "publish time is not increasing: {} <= {}",
-                        current_publish_time,
-                        last_publish_time
+                        "invalid quorum {} of feed {}, expect 1 to {}",
+                        quorum,
+                        feed_id,
+                        NUM_SIGNATURES_TO_VERIFY
                     )
-                };
-                last_publish_time = current_publish_time;
-                if earliest_publish_time == 0 {
-                    earliest_publish_time = last_publish_time;
                 }
             }
+        }
+        let mut oracle = Self {
+            feeds,
+            signed_prices_batch,
+            guardians: guardian_set,
+            public_input_data: PublicInputData {
+                guardian_set_hash: E::Fr::zero(),
+                prices_summarize: PricesSummarize {
+                    commitment: E::Fr::zero(),
+                    num: E::Fr::zero(),
+                    commitment_base_sum: E::Fr::zero(),
+                },
+                earliest_publish_time: E::Fr::zero(),
+            },
+            commitment: E::Fr::zero(),
+            cancellation_token,
+            reference_block_header: None,
+            feed_max_delays: None,
+            latest_only: false,
+            nullifiers: None,
+            bounds: None,
+            payload_hashes: None,
+            output_commitment: false,
+            compact_feed_ids: false,
+            complete_packages: false,
+            config,
+        };
+        oracle.refresh()?;
+        Ok(oracle)
+    }
 
-            // Compute price root
-            {
-                let mut prices_commitment_members = vec![];
-                for price_feed in signed_prices.iter() {
-                    let (price, _) = price_feed[0].clone();
-                    let feed_id = {
-                        // Due the limitation of zklink state tree, we can only store first 15 bytes of feed_id
-                        let feed_id = price.data_points[0].serialize_feed_id();
-                        let mut bytes = [0u8; 16];
-                        bytes[1..].copy_from_slice(&feed_id[0..15]);
-                        BigUint::from_bytes_be(&bytes)
-                    };
+    /// Ids of shared feeds, which are required by options matching feeds in every package.
+    fn shared_feed_ids(&self) -> Result<&[String; NUM_FEEDS], anyhow::Error> {
+        match &self.feeds {
+            FeedSet::Shared { ids, .. } => Ok(ids),
+            FeedSet::Separate => anyhow::bail!("option requires feeds shared by packages"),
+        }
+    }
 
-                    // normalized_price = 10^18 * real_price
-                    let price = {
-                        // The fetched price has been multiplied by 10^8
-                        let price = {
-                            // 32-bytes
-                            let bytes = price.data_points[0].serialize_value();
-                            BigUint::from_be_bytes(&bytes)
-                        };
-                        let exponent = (18 - 8) as u32;
-                        let coefficient = BigUint::from(10u32).pow(exponent);
-                        coefficient.mul(&price)
-                    };
-                    prices_commitment_members.push(fr_from_biguint::<E>(&feed_id)?);
-                    prices_commitment_members.push(fr_from_biguint::<E>(&price)?);
+    /// Recompute public input data and commitment from witness and options.
+    fn refresh(&mut self) -> Result<(), anyhow::Error> {
+        let opening = self.opening()?;
+        self.public_input_data = summarize::<E>(&opening)?;
+        self.commitment = opening.commitment()?;
+        Ok(())
+    }
+
+    /// Rounds of publish time and normalized prices that are committed.
+    fn committed_rounds(&self) -> Result<Vec<OracleRound>, anyhow::Error> {
+        let mut rounds = aggregate_rounds(
+            &self.feeds,
+            &self.guardians,
+            &self.signed_prices_batch,
+            &self.config,
+            &self.cancellation_token,
+        )?;
+        if let Some(bounds) = &self.bounds {
+            for round in rounds.iter() {
+                for (price, (min, max)) in round.prices.iter().zip(bounds.iter()) {
+                    if &price.price < min || &price.price > max {
+                        anyhow::bail!(
+                            "price {} of feed 0x{} at {} is out of bounds [{}, {}]",
+                            price.price,
+                            hex::encode(price.feed_id),
+                            round.publish_time,
+                            min,
+                            max
+                        )
+                    }
                 }
-                let prices_commitment = poseidon_hash::<E>(&prices_commitment_members);
-                prices_commitments.push(prices_commitment);
             }
         }
-
-        let guardian_set_hash = {
-            let input = guardian_set
+        if self.latest_only {
+            let feed_ids = self.shared_feed_ids()?;
+            let packages = self
+                .signed_prices_batch
                 .iter()
-                .map(|g| {
-                    let u = BigUint::from_bytes_be(g);
-                    fr_from_biguint::<E>(&u)
+                .map(|signed_prices| {
+                    signed_prices[0]
+                        .iter()
+                        .map(|witness| {
+                            let contains = feed_ids
+                                .iter()
+                                .map(|feed_id| witness.package.value_of(feed_id).is_some())
+                                .collect();
+                            (witness.package.timestamp, contains)
+                        })
+                        .collect::<Vec<_>>()
                 })
-                .collect::<Result<Vec<_>, _>>()?;
-            poseidon_hash::<E>(&input)
-        };
+                .collect::<Vec<_>>();
+            let prices = rounds.iter().map(|r| r.prices.clone()).collect::<Vec<_>>();
+            let (publish_time, prices) = select_latest_values(&packages, &prices)
+                .ok_or_else(|| anyhow::anyhow!("no latest price of every feed to select"))?;
+            rounds = vec![OracleRound {
+                publish_time,
+                prices,
+            }];
+        }
+        Ok(rounds)
+    }
+
+    /// Open the commitment into committed prices, which is consumed by zkLink circuits.
+    pub fn opening(&self) -> Result<OracleOpening<E>, anyhow::Error> {
+        let reference_block_hash = self
+            .reference_block_header
+            .as_ref()
+            .map(|header| Keccak256::new_with_prefix(header).finalize().into());
+        Ok(OracleOpening {
+            guardian_set_hash: guardian_set_hash::<E>(&self.guardians)?,
+            rounds: self.committed_rounds()?,
+            reference_block_hash,
+        })
+    }
+
+    /// Output nullifiers of all packages as public inputs, in the order of rounds and signers.
+    pub fn with_nullifiers(mut self) -> Result<Self, anyhow::Error> {
+        let nullifiers = self
+            .signed_prices_batch
+            .iter()
+            .flatten()
+            .flatten()
+            .map(|witness| witness.nullifier::<E>())
+            .collect::<Result<Vec<_>, _>>()?;
+        self.nullifiers = Some(nullifiers);
+        Ok(self)
+    }
 
-        let earliest_publish_time = fr_from_biguint::<E>(&BigUint::from(earliest_publish_time))?;
+    /// Absorb every committed `(feed_id, price, publish_time)` triple, together with all other
+    /// outputs, into one output commitment, which becomes the only public input.
+    pub fn with_output_commitment(mut self) -> Self {
+        self.output_commitment = true;
+        self
+    }
 
-        let prices_num = E::Fr::from_str(&prices_commitments.len().to_string()).unwrap();
-        let mut prices_commitment_base_sum = E::Fr::zero();
-        let mut prices_commitment = E::Fr::zero();
-        for (i, mut commitment) in prices_commitments.into_iter().enumerate() {
-            Field::add_assign(&mut prices_commitment_base_sum, &commitment);
-            let coef = E::Fr::from_str(&format!("{}", i)).unwrap();
-            Field::mul_assign(&mut commitment, &coef);
-            Field::add_assign(&mut prices_commitment, &commitment);
+    /// Match shared feed ids in circuit as compact feed ids, which must be short ASCII tickers. It
+    /// saves constraints without changing the commitment.
+    pub fn with_compact_feed_ids(mut self) -> Result<Self, anyhow::Error> {
+        for feed_id in self.shared_feed_ids()?.iter() {
+            CompactFeedId::new(feed_id)?;
         }
+        self.compact_feed_ids = true;
+        Ok(self)
+    }
 
-        let commitment = poseidon_hash::<E>(&[
-            guardian_set_hash,
-            prices_commitment,
-            earliest_publish_time,
-            prices_num,
-            prices_commitment_base_sum,
-        ]);
+    /// Require every package to contain all shared feeds, regardless of quorums.
+    pub fn with_complete_packages(mut self) -> Result<Self, anyhow::Error> {
+        let feed_ids = self.shared_feed_ids()?;
+        for witness in self.signed_prices_batch.iter().flatten().flatten() {
+            if let Some(feed_id) = feed_ids
+                .iter()
+                .find(|feed_id| witness.package.value_of(feed_id).is_none())
+            {
+                anyhow::bail!(
+                    "data package at {} signed by 0x{} misses feed {}",
+                    witness.package.timestamp,
+                    hex::encode(witness.expected_signer),
+                    feed_id
+                )
+            }
+        }
+        self.complete_packages = true;
+        Ok(self)
+    }
 
-        Ok(Self {
-            commitment,
-            public_input_data: PublicInputData {
-                guardian_set_hash,
-                prices_summarize: PricesSummarize {
-                    commitment: prices_commitment,
-                    num: prices_num,
-                    commitment_base_sum: prices_commitment_base_sum,
-                },
-                earliest_publish_time,
-            },
-            signed_prices_batch,
-            guardians: guardian_set,
-        })
+    /// Public inputs of the proof in order, which are the commitment followed by nullifiers and
+    /// payload hashes if enabled, or the only output commitment over all of them if enabled.
+    pub fn public_inputs(&self) -> Result<Vec<E::Fr>, anyhow::Error> {
+        let mut inputs = vec![self.commitment];
+        if let Some(nullifiers) = &self.nullifiers {
+            inputs.extend(nullifiers.iter().copied());
+        }
+        if let Some(payload_hashes) = &self.payload_hashes {
+            for payload_hash in payload_hashes.iter() {
+                for limb in payload_hash.chunks(16) {
+                    inputs.push(fr_from_biguint::<E>(&BigUint::from_bytes_be(limb))?);
+                }
+            }
+        }
+        if self.output_commitment {
+            let mut triples = vec![];
+            for round in self.committed_rounds()? {
+                let publish_time = fr_from_biguint::<E>(&BigUint::from(round.publish_time))?;
+                for price in round.prices.iter() {
+                    triples.push([
+                        fr_from_biguint::<E>(&BigUint::from_bytes_be(&price.feed_id))?,
+                        fr_from_biguint::<E>(&price.price)?,
+                        publish_time,
+                    ]);
+                }
+            }
+            inputs = vec![witness::output_commitment::<E>(&triples, &inputs)];
+        }
+        Ok(inputs)
     }
-}
 
-impl<E: Engine, const NUM_SIGNATURES_TO_VERIFY: usize, const NUM_PRICES: usize> Circuit<E>
-    for PriceOracle<E, NUM_SIGNATURES_TO_VERIFY, NUM_PRICES>
-{
-    type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
+    /// Metadata of all packages contributing to the proof, in the order of rounds and signers.
+    pub fn metadata(&self) -> Vec<PackageMetadata> {
+        self.signed_prices_batch
+            .iter()
+            .flatten()
+            .flatten()
+            .map(|witness| witness.metadata())
+            .collect()
+    }
 
-    fn synthesize<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<(), SynthesisError> {
-        utils::add_bitwise_logic_and_range_table(cs)?;
-        let temp_variable = Num::alloc(cs, Some(E::Fr::one()))?;
-        circuit_rescue_hash(cs, &[temp_variable])?; // Just to standardize the proof format
+    /// Output keccak256 hashes of all signed packages as public inputs, in the order of rounds and
+    /// signers.
+    pub fn with_payload_hashes(mut self) -> Self {
+        let payload_hashes = self
+            .signed_prices_batch
+            .iter()
+            .flatten()
+            .flatten()
+            .map(|witness| witness.package.keccak256_hash())
+            .collect();
+        self.payload_hashes = Some(payload_hashes);
+        self
+    }
+
+    /// Require the normalized price (with target decimals of config) of feed `i` to be within
+    /// `bounds[i]`, which is `(min, max)` inclusively.
+    pub fn with_bounds(
+        mut self,
+        bounds: [(BigUint, BigUint); NUM_FEEDS],
+    ) -> Result<Self, anyhow::Error> {
+        for (i, (min, max)) in bounds.iter().enumerate() {
+            if min > max {
+                anyhow::bail!("invalid bounds [{}, {}] of feed #{}", min, max, i)
+            }
+        }
+        self.bounds = Some(bounds);
+        self.refresh()?;
+        Ok(self)
+    }
+
+    /// Commit only the latest price of every shared feed in one round, instead of all rounds. The
+    /// price must be aggregated from packages carrying the maximum timestamp of all packages
+    /// containing the feed, which is computed in circuit, so that prover can not pick an older
+    /// favorable price from the same witness set. The round is published at the earliest of them.
+    pub fn with_latest_only(mut self) -> Result<Self, anyhow::Error> {
+        self.shared_feed_ids()?;
+        self.latest_only = true;
+        self.refresh()?;
+        Ok(self)
+    }
+
+    /// Reject stale prices against the timestamp of the given RLP-encoded L1 block header, and
+    /// commit to the hash of that block.
+    pub fn with_reference_block_header(
+        mut self,
+        block_header_rlp: Vec<u8>,
+    ) -> Result<Self, anyhow::Error> {
+        self.reference_block_header = Some(block_header_rlp);
+        self.check_freshness()?;
+        self.refresh()?;
+        Ok(self)
+    }
+
+    /// Allow packages containing shared `feed_ids[i]` to be `max_delays[i]` milliseconds older than
+    /// the reference block, instead of the max delay of config. It takes effect with a reference
+    /// block.
+    pub fn with_feed_max_delays(
+        mut self,
+        max_delays: [u64; NUM_FEEDS],
+    ) -> Result<Self, anyhow::Error> {
+        self.shared_feed_ids()?;
+        self.feed_max_delays = Some(max_delays);
+        self.check_freshness()?;
+        Ok(self)
+    }
+
+    /// Check all packages are fresh against the reference block if it's set.
+    fn check_freshness(&self) -> Result<(), anyhow::Error> {
+        let reference_time = match &self.reference_block_header {
+            Some(header) => block_header_timestamp(header)? * 1000,
+            None => return Ok(()),
+        };
+        let max_delays: Vec<(String, u64)> = match (&self.feeds, self.feed_max_delays) {
+            (FeedSet::Shared { ids, .. }, Some(max_delays)) => {
+                ids.iter().cloned().zip(max_delays).collect()
+            }
+            _ => vec![],
+        };
+        for witness in self.signed_prices_batch.iter().flatten().flatten() {
+            if !is_package_fresh(
+                &witness.package,
+                reference_time,
+                &max_delays,
+                self.config.max_timestamp_delay_ms,
+                self.config.max_timestamp_ahead_ms,
+            ) {
+                anyhow::bail!(
+                    "data package at {} is not fresh at block time {}",
+                    witness.package.timestamp,
+                    reference_time
+                )
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check signers and publish time of rounds, and aggregate normalized prices of all feeds in each
+/// round.
+fn aggregate_rounds<const NUM_SIGNATURES_TO_VERIFY: usize, const NUM_FEEDS: usize>(
+    feeds: &FeedSet<NUM_FEEDS>,
+    guardian_set: &[[u8; 20]; NUM_SIGNATURES_TO_VERIFY],
+    signed_prices_batch: &[Vec<[SignedPackageWitness; NUM_SIGNATURES_TO_VERIFY]>],
+    config: &RedstoneConfig,
+    cancellation_token: &CancellationToken,
+) -> Result<Vec<OracleRound>, anyhow::Error> {
+    let num_groups = match feeds {
+        FeedSet::Separate => NUM_FEEDS,
+        FeedSet::Shared { .. } => 1,
+    };
+    let mut last_publish_time = 0;
+    let mut rounds = vec![];
+    for signed_prices in signed_prices_batch.iter() {
+        cancellation_token.check()?;
+        if signed_prices.len() != num_groups || num_groups == 0 {
+            anyhow::bail!(
+                "expect {} groups of packages in a round, got {}",
+                num_groups,
+                signed_prices.len()
+            )
+        }
+        for signed_price in signed_prices.iter() {
+            for (witness, guardian) in signed_price.iter().zip(guardian_set.iter()) {
+                witness.validate(guardian)?;
+            }
+        }
+        // Check publish time is increasing
+        let current_publish_time = signed_prices[0][0].package.timestamp;
+        if current_publish_time < last_publish_time {
+            anyhow::bail!(
+                "publish time is not increasing: {} <= {}",
+                current_publish_time,
+                last_publish_time
+            )
+        };
+        last_publish_time = current_publish_time;
 
-        let mut prices_in_batch = vec![];
-        let num_prices_batch = self.signed_prices_batch.len();
-        for i in 0..num_prices_batch {
-            assert_eq!(self.signed_prices_batch[i].len(), NUM_PRICES);
-            let signed_prices = self.signed_prices_batch[i]
+        let prices = match feeds {
+            FeedSet::Separate => signed_prices
                 .iter()
-                .map(|ps| {
-                    AllocatedSignedPrice::<E, NUM_SIGNATURES_TO_VERIFY>::from_witness(
-                        cs,
-                        ps.clone(),
-                    )
+                .map(|signed_price| {
+                    let price = &signed_price[0].package;
+                    if price.is_heartbeat() {
+                        anyhow::bail!("heartbeat package at {} carries no price", price.timestamp)
+                    }
+                    let mut feed_id = [0u8; 16];
+                    feed_id[1..].copy_from_slice(&price.data_points[0].serialize_feed_id()[..15]);
+                    let value = BigUint::from_bytes_be(&price.data_points[0].serialize_value());
+                    Ok((feed_id, value))
                 })
-                .collect::<Result<Vec<_>, _>>()?;
-            prices_in_batch.push(signed_prices);
-        }
-        let guardians = self
-            .guardians
+                .collect::<Result<Vec<_>, anyhow::Error>>()?,
+            FeedSet::Shared { ids, quorums } => ids
+                .iter()
+                .zip(quorums.iter())
+                .map(|(feed_id, quorum)| {
+                    let values = signed_prices[0]
+                        .iter()
+                        .filter_map(|witness| witness.package.value_of(feed_id))
+                        .collect::<Vec<_>>();
+                    if values.len() < *quorum {
+                        anyhow::bail!(
+                            "feed {} is provided by {} signers at {}, less than quorum {}",
+                            feed_id,
+                            values.len(),
+                            current_publish_time,
+                            quorum
+                        )
+                    }
+                    let value = median(&values)
+                        .ok_or_else(|| anyhow::anyhow!("no data package to aggregate"))?;
+                    Ok((feed_id_bytes(feed_id), value))
+                })
+                .collect::<Result<Vec<_>, anyhow::Error>>()?,
+        };
+        let prices = prices
             .into_iter()
-            .map(|a| Address::from_address_witness(cs, &a))
-            .collect::<Result<Vec<_>, _>>()?;
+            .map(|(feed_id, value)| OraclePrice {
+                feed_id,
+                // normalized_price = 10^18 * real_price
+                price: witness::normalize_value(
+                    &value,
+                    config.num_value_decimals,
+                    config.target_num_value_decimals,
+                ),
+            })
+            .collect();
+        rounds.push(OracleRound {
+            publish_time: current_publish_time,
+            prices,
+        });
+    }
+    Ok(rounds)
+}
+
+/// Summarize the opening as [`PublicInputData`], whose commitment is [`OracleOpening::commitment`]
+/// without a reference block.
+fn summarize<E: Engine>(opening: &OracleOpening<E>) -> Result<PublicInputData<E>, anyhow::Error> {
+    let mut prices_commitment_base_sum = E::Fr::zero();
+    let mut prices_commitment = E::Fr::zero();
+    for (i, round) in opening.rounds.iter().enumerate() {
+        let mut prices_commitment_members = vec![];
+        for price in round.prices.iter() {
+            prices_commitment_members.push(fr_from_biguint::<E>(&BigUint::from_bytes_be(
+                &price.feed_id,
+            ))?);
+            prices_commitment_members.push(fr_from_biguint::<E>(&price.price)?);
+        }
+        let mut commitment = poseidon_hash::<E>(&prices_commitment_members);
+        Field::add_assign(&mut prices_commitment_base_sum, &commitment);
+        let coef = E::Fr::from_str(&format!("{}", i)).unwrap();
+        Field::mul_assign(&mut commitment, &coef);
+        Field::add_assign(&mut prices_commitment, &commitment);
+    }
+    let earliest_publish_time = opening
+        .rounds
+        .first()
+        .map(|round| round.publish_time)
+        .unwrap_or_default();
+    Ok(PublicInputData {
+        guardian_set_hash: opening.guardian_set_hash,
+        prices_summarize: PricesSummarize {
+            commitment: prices_commitment,
+            num: E::Fr::from_str(&opening.rounds.len().to_string()).unwrap(),
+            commitment_base_sum: prices_commitment_base_sum,
+        },
+        earliest_publish_time: fr_from_biguint::<E>(&BigUint::from(earliest_publish_time))?,
+    })
+}
+
+/// Due the limitation of zklink state tree, we can only store first 15 bytes of feed_id
+pub(crate) fn feed_id_bytes(feed_id: &str) -> [u8; 16] {
+    let feed_id = convert_string_to_bytes32(feed_id);
+    let mut bytes = [0u8; 16];
+    bytes[1..].copy_from_slice(&feed_id[0..15]);
+    bytes
+}
+
+/// Circuit representation of signed prices of [`PriceOracle`] and its guardians.
+pub struct AllocatedPriceOracle<E: Engine, const NUM_SIGNATURES_TO_VERIFY: usize> {
+    pub signed_prices_batch: Vec<Vec<AllocatedSignedPrice<E, NUM_SIGNATURES_TO_VERIFY>>>,
+    pub guardians: Vec<Address<E>>,
+}
+
+/// Outputs of [`PriceOracle`] in circuit, which extend [`OracleOutputData`] by options.
+pub struct PriceOracleOutputs<E: Engine> {
+    pub outputs: OracleOutputData<E>,
+    /// `(hi, lo)` of the reference block hash
+    pub reference_block_hash: Option<[Num<E>; 2]>,
+    pub payload_hashes: Vec<[Byte<E>; 32]>,
+    /// `(feed_id, price, publish_time)` of committed prices
+    pub triples: Vec<[Num<E>; 3]>,
+}
+
+impl<E: Engine, const NUM_SIGNATURES_TO_VERIFY: usize, const NUM_FEEDS: usize>
+    PriceOracle<E, NUM_SIGNATURES_TO_VERIFY, NUM_FEEDS>
+{
+    /// Same as [`SignedPriceOracle::verify`], but also returns outputs of options.
+    pub fn verify_with_options<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        circuit: &AllocatedPriceOracle<E, NUM_SIGNATURES_TO_VERIFY>,
+    ) -> Result<(Boolean, PriceOracleOutputs<E>), SynthesisError> {
+        let prices_in_batch = &circuit.signed_prices_batch;
+        let guardians = &circuit.guardians;
+        // Shared feed ids are fixed by circuit
+        let (shared_feed_ids, quorums) = match &self.feeds {
+            FeedSet::Shared { ids, quorums } => {
+                let feed_ids = ids
+                    .iter()
+                    .map(|feed_id| convert_string_to_bytes32(feed_id).map(Byte::<E>::constant))
+                    .collect::<Vec<_>>();
+                (feed_ids, quorums.to_vec())
+            }
+            FeedSet::Separate => (vec![], vec![]),
+        };
+        let compact_feed_ids = match &self.feeds {
+            FeedSet::Shared { ids, .. } if self.compact_feed_ids => ids
+                .iter()
+                .map(|feed_id| CompactFeedId::new(feed_id))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(new_synthesis_error)?,
+            _ => vec![],
+        };
+        let mut is_valid = Boolean::constant(true);
 
         // Check signatures
-        let mut signatures_valid = Boolean::constant(true);
+        let mut payload_hashes = vec![];
         for prices in prices_in_batch.iter() {
-            for i in 0..NUM_SIGNATURES_TO_VERIFY {
-                let is_current_valid = prices[i].check_by_addresses(cs, &guardians)?;
-                signatures_valid = Boolean::and(cs, &signatures_valid, &is_current_valid)?;
+            self.cancellation_token.check()?;
+            for price in prices.iter() {
+                let (is_current_valid, current_payload_hashes) =
+                    price.check_by_addresses_with_payload_hashes(cs, guardians)?;
+                is_valid = Boolean::and(cs, &is_valid, &is_current_valid)?;
+                payload_hashes.extend(current_payload_hashes);
             }
         }
 
-        // Check if timestamp is increasing
-        let last_publish_time = UInt64::zero().into_num();
-        let mut is_publish_time_increasing = Boolean::constant(true);
-        let mut prices_commitments = vec![];
-        for i in 0..num_prices_batch {
-            let publish_time = {
-                // Follow what pyth does
-                let mut publish_time = [Byte::zero(); 8];
-                let significant_bytes = prices_in_batch[i][0].timestamp();
-                publish_time[8 - significant_bytes.len()..].copy_from_slice(&significant_bytes);
-                publish_time.reverse();
-                UInt64::from_bytes_le(cs, &publish_time)?.into_num()
+        if self.complete_packages {
+            for price in prices_in_batch.iter().flatten() {
+                let is_current_complete = price.contains_all(cs, &shared_feed_ids)?;
+                is_valid = Boolean::and(cs, &is_valid, &is_current_complete)?;
+            }
+        }
+
+        // Check freshness of data packages against the reference block
+        let reference_block_hash = if let Some(header) = &self.reference_block_header {
+            let header = BlockHeader::from_rlp_witness(cs, header)?;
+            let reference_time = {
+                let timestamp = header.timestamp(cs)?;
+                timestamp.mul(cs, &Num::Constant(E::Fr::from_str("1000").unwrap()))?
             };
-            let (current_publish_time_is_equal, current_publish_time_is_greater) =
+            // Per-feed max delays are fixed by circuit
+            let max_delays = self.feed_max_delays.map(|max_delays| {
+                shared_feed_ids
+                    .iter()
+                    .copied()
+                    .zip(max_delays)
+                    .collect::<Vec<_>>()
+            });
+            for price in prices_in_batch.iter().flatten() {
+                self.cancellation_token.check()?;
+                for signed_package in price.signed_data_packages.iter() {
+                    let is_current_fresh = match &max_delays {
+                        Some(max_delays) => check_package_freshness(
+                            cs,
+                            &signed_package.data_package,
+                            &reference_time,
+                            max_delays,
+                            self.config.max_timestamp_delay_ms,
+                            self.config.max_timestamp_ahead_ms,
+                        )?,
+                        None => check_timestamp_freshness(
+                            cs,
+                            &signed_package.data_package.timestamp,
+                            &reference_time,
+                            self.config.max_timestamp_delay_ms,
+                            self.config.max_timestamp_ahead_ms,
+                        )?,
+                    };
+                    is_valid = Boolean::and(cs, &is_valid, &is_current_fresh)?;
+                }
+            }
+
+            let mut hash = header.hash(cs)?;
+            hash.reverse();
+            let hash_lo = UInt128::from_bytes_le(cs, &hash[..16].try_into().unwrap())?.into_num();
+            let hash_hi = UInt128::from_bytes_le(cs, &hash[16..].try_into().unwrap())?.into_num();
+            Some([hash_hi, hash_lo])
+        } else {
+            None
+        };
+
+        // Due the limitation of zklink state tree, we can only store first 15 bytes of feed_id
+        let feed_id_num = |cs: &mut CS, feed_id: &[Byte<E>; 32]| {
+            let mut bytes = [Byte::zero(); 16];
+            bytes[1..].copy_from_slice(&feed_id[0..15]);
+            bytes.reverse();
+            Ok::<_, SynthesisError>(UInt128::from_bytes_le(cs, &bytes)?.into_num())
+        };
+        let shared_feed_id_nums = shared_feed_ids
+            .iter()
+            .map(|feed_id| feed_id_num(cs, feed_id))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let timestamp_num = |cs: &mut CS, timestamp: &[Byte<E>; TIMESTAMP_BS]| {
+            // Follow what pyth does
+            let mut bytes = [Byte::zero(); 8];
+            bytes[8 - TIMESTAMP_BS..].copy_from_slice(timestamp);
+            bytes.reverse();
+            Ok::<_, SynthesisError>(UInt64::from_bytes_le(cs, &bytes)?.into_num())
+        };
+        let mut last_publish_time = UInt64::zero().into_num();
+        let mut publish_times = vec![];
+        let mut rounds_packages = vec![];
+        let mut rounds_feed_ids = vec![];
+        let mut rounds_prices = vec![];
+        for prices in prices_in_batch.iter() {
+            self.cancellation_token.check()?;
+            // Check if timestamp is increasing
+            let publish_time = timestamp_num(cs, &prices[0].timestamp())?;
+            let (is_equal, is_greater) =
                 prepacked_long_comparison(cs, &[publish_time], &[last_publish_time], &[64])?;
-            let current_publish_time_is_equal_or_greater = Boolean::or(
-                cs,
-                &current_publish_time_is_equal,
-                &current_publish_time_is_greater,
-            )?;
-
-            is_publish_time_increasing = Boolean::and(
-                cs,
-                &is_publish_time_increasing,
-                &current_publish_time_is_equal_or_greater,
-            )?;
+            let is_equal_or_greater = Boolean::or(cs, &is_equal, &is_greater)?;
+            is_valid = Boolean::and(cs, &is_valid, &is_equal_or_greater)?;
+            last_publish_time = publish_time;
 
+            let (feed_ids, values) = match &self.feeds {
+                FeedSet::Separate => {
+                    let mut feed_ids = vec![];
+                    let mut values = vec![];
+                    for price in prices.iter() {
+                        feed_ids.push(feed_id_num(cs, &price.feed_id())?);
+                        values.push(UInt256::from_be_bytes_fixed(cs, &price.price())?);
+                    }
+                    (feed_ids, values)
+                }
+                FeedSet::Shared { .. } => {
+                    let mut values = vec![];
+                    for (i, (feed_id, quorum)) in
+                        shared_feed_ids.iter().zip(quorums.iter()).enumerate()
+                    {
+                        let value = match compact_feed_ids.get(i) {
+                            Some(compact_feed_id) => prices[0]
+                                .aggregated_compact_value_with_quorum(
+                                    cs,
+                                    compact_feed_id,
+                                    *quorum,
+                                )?,
+                            None => prices[0].aggregated_value_with_quorum(cs, feed_id, *quorum)?,
+                        };
+                        values.push(value);
+                    }
+                    (shared_feed_id_nums.clone(), values)
+                }
+            };
+            // value = real_price * 10^8, what we want is real_price * 10^18
+            let mut normalized = vec![];
+            for value in values.iter() {
+                normalized.push(normalize_value(
+                    cs,
+                    value,
+                    self.config.num_value_decimals,
+                    self.config.target_num_value_decimals,
+                )?);
+            }
+            if let Some(bounds) = &self.bounds {
+                for (price, (min, max)) in normalized.iter().zip(bounds.iter()) {
+                    let (min, max) = (
+                        UInt256::constant(min.clone()),
+                        UInt256::constant(max.clone()),
+                    );
+                    let is_within = is_within_bounds(cs, price, &min, &max)?;
+                    is_valid = Boolean::and(cs, &is_valid, &is_within)?;
+                }
+            }
+            if self.latest_only {
+                let mut packages = vec![];
+                for signed_package in prices[0].signed_data_packages.iter() {
+                    let data_package = &signed_package.data_package;
+                    let timestamp = timestamp_num(cs, &data_package.timestamp)?;
+                    let contains = shared_feed_ids
+                        .iter()
+                        .map(|feed_id| data_package.contains(cs, feed_id))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    packages.push((timestamp, contains));
+                }
+                rounds_packages.push(packages);
+            }
+            publish_times.push(publish_time);
+            rounds_feed_ids.push(feed_ids);
+            rounds_prices.push(normalized);
+        }
+
+        let rounds = if self.latest_only {
+            let (publish_time, prices) =
+                select_latest_values(cs, &rounds_packages, &rounds_prices)?;
+            vec![(publish_time, shared_feed_id_nums.clone(), prices)]
+        } else {
+            publish_times
+                .into_iter()
+                .zip(rounds_feed_ids)
+                .zip(rounds_prices)
+                .map(|((publish_time, feed_ids), prices)| (publish_time, feed_ids, prices))
+                .collect()
+        };
+
+        // Compute price root
+        let mut prices_commitments = vec![];
+        let mut triples = vec![];
+        for (publish_time, feed_ids, prices) in rounds.iter() {
             let mut prices_commitment_members = vec![];
-            for j in 0..NUM_PRICES {
-                let feed_id = {
-                    // Due the limitation of zklink state tree, we can only store first 15 bytes of feed_id
-                    let mut bytes = [Byte::zero(); 16];
-                    bytes[1..].copy_from_slice(&prices_in_batch[i][j].feed_id()[0..15]);
-                    bytes.reverse();
-                    let feed_id = UInt128::from_bytes_le(cs, &bytes)?;
-                    feed_id.into_num()
-                };
-                let price = {
-                    let normalized_price_coefficient = {
-                        let eighteen =
-                            AllocatedNum::alloc(cs, || Ok(E::Fr::from_str("18").unwrap()))?;
-                        let real_price_exponent =
-                            AllocatedNum::alloc(cs, || Ok(E::Fr::from_str("8").unwrap()))?;
-
-                        let normalized_price_exponent =
-                            Num::Variable(eighteen.sub(cs, &real_price_exponent)?);
-                        let mut normalized_price_exponent =
-                            normalized_price_exponent.into_bits_le(cs, Some(64))?;
-                        normalized_price_exponent.reverse();
-                        let ten = AllocatedNum::alloc(cs, || Ok(E::Fr::from_str("10").unwrap()))?;
-                        AllocatedNum::pow(cs, &ten, &normalized_price_exponent)?
-                    };
-                    // this price = real_price * 10^8
-                    let price = prices_in_batch[i][j].price();
-                    // what we want ts real_price * 10^18
-                    let num = UInt256::from_be_bytes_fixed(cs, &price)?.to_num_unchecked(cs)?;
-                    num.mul(cs, &Num::Variable(normalized_price_coefficient))?
-                };
-                prices_commitment_members.push(feed_id);
+            for (feed_id, price) in feed_ids.iter().zip(prices.iter()) {
+                let price = to_num_checked(cs, price)?;
+                prices_commitment_members.push(*feed_id);
                 prices_commitment_members.push(price);
+                triples.push([*feed_id, price, *publish_time]);
             }
             let prices_commitment =
                 circuit_poseidon_hash(cs, prices_commitment_members.as_slice())?;
             prices_commitments.push(prices_commitment);
         }
-
-        Boolean::enforce_equal(cs, &is_publish_time_increasing, &Boolean::Constant(true))?;
-
-        let mut prices_commitment_base_sum = Num::zero();
-        let mut prices_commitment = Num::zero();
-        let prices_num = prices_commitments.len();
-        for (i, commitment) in prices_commitments.into_iter().enumerate() {
-            prices_commitment_base_sum = prices_commitment_base_sum.add(cs, &commitment)?;
-            let coef = E::Fr::from_str(&format!("{}", i)).unwrap();
-            let x = commitment.mul(cs, &Num::Constant(coef))?;
-            prices_commitment = prices_commitment.add(cs, &x)?;
-        }
-
-        let expected_prices_commitment =
-            Num::alloc(cs, Some(self.public_input_data.prices_summarize.commitment))?;
-        expected_prices_commitment.enforce_equal(cs, &prices_commitment)?;
-
-        let prices_num = Num::Constant(E::Fr::from_str(&prices_num.to_string()).unwrap());
+        let (prices_commitment, prices_num, prices_commitment_base_sum) =
+            circuit_summarize_prices(cs, &prices_commitments)?;
 
         // Compute guardian set hash
         let guardian_set_num = guardians
@@ -299,33 +1030,141 @@ impl<E: Engine, const NUM_SIGNATURES_TO_VERIFY: usize, const NUM_PRICES: usize>
             .collect::<Result<Vec<_>, _>>()?;
         let guardian_set_hash = circuit_poseidon_hash(cs, &guardian_set_num)?;
 
-        let earliest_publish_time = {
-            let mut earliest_publish_time = if let Some(batch) = prices_in_batch.first() {
-                let mut publish_time = [Byte::zero(); 8];
-                let significant_bytes = batch[0].timestamp();
-                publish_time[8 - significant_bytes.len()..].copy_from_slice(&significant_bytes);
-                publish_time
-            } else {
-                [Byte::zero(); 8]
-            };
-            earliest_publish_time.reverse();
-            UInt64::from_bytes_le(cs, &earliest_publish_time)?.into_num()
-        };
-        let commitment = circuit_poseidon_hash(
-            cs,
-            &[
-                guardian_set_hash,
-                prices_commitment,
-                earliest_publish_time,
-                prices_num,
-                prices_commitment_base_sum,
-            ],
-        )?;
+        let earliest_publish_time = rounds
+            .first()
+            .map(|(publish_time, _, _)| *publish_time)
+            .unwrap_or_else(Num::zero);
+
+        Ok((
+            is_valid,
+            PriceOracleOutputs {
+                outputs: OracleOutputData {
+                    guardian_set_hash,
+                    earliest_publish_time,
+                    prices_summarize: OraclePricesSummarize {
+                        commitment: prices_commitment,
+                        num: prices_num,
+                        commitment_base_sum: prices_commitment_base_sum,
+                    },
+                },
+                reference_block_hash,
+                payload_hashes,
+                triples,
+            },
+        ))
+    }
+}
+
+impl<E: Engine, const NUM_SIGNATURES_TO_VERIFY: usize, const NUM_FEEDS: usize> SignedPriceOracle<E>
+    for PriceOracle<E, NUM_SIGNATURES_TO_VERIFY, NUM_FEEDS>
+{
+    type Witness = Vec<Vec<[SignedPackageWitness; NUM_SIGNATURES_TO_VERIFY]>>;
+    type Circuit = AllocatedPriceOracle<E, NUM_SIGNATURES_TO_VERIFY>;
+
+    fn witness(&self) -> &Self::Witness {
+        &self.signed_prices_batch
+    }
 
-        let expected_commitment = Num::alloc(cs, Some(self.commitment))?;
-        expected_commitment.enforce_equal(cs, &commitment)?;
-        expected_commitment.get_variable().inputize(cs)?;
+    fn alloc<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<Self::Circuit, SynthesisError> {
+        let mut signed_prices_batch = vec![];
+        for signed_prices in self.signed_prices_batch.iter() {
+            self.cancellation_token.check()?;
+            let signed_prices = signed_prices
+                .iter()
+                .map(|ps| AllocatedSignedPrice::from_witness(cs, ps.clone()))
+                .collect::<Result<Vec<_>, _>>()?;
+            signed_prices_batch.push(signed_prices);
+        }
+        let guardians = self
+            .guardians
+            .into_iter()
+            .map(|a| Address::from_address_witness(cs, &a))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(AllocatedPriceOracle {
+            signed_prices_batch,
+            guardians,
+        })
+    }
+
+    fn verify<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        circuit: &Self::Circuit,
+    ) -> Result<(Boolean, OracleOutputData<E>), SynthesisError> {
+        let (is_valid, outputs) = self.verify_with_options(cs, circuit)?;
+        Ok((is_valid, outputs.outputs))
+    }
+
+    fn outputs(&self) -> &PublicInputData<E> {
+        &self.public_input_data
+    }
+}
+
+impl<E: Engine, const NUM_SIGNATURES_TO_VERIFY: usize, const NUM_FEEDS: usize> Circuit<E>
+    for PriceOracle<E, NUM_SIGNATURES_TO_VERIFY, NUM_FEEDS>
+{
+    type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
+
+    fn synthesize<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<(), SynthesisError> {
+        utils::add_bitwise_logic_and_range_table(cs)?;
+        let temp_variable = Num::alloc(cs, Some(E::Fr::one()))?;
+        circuit_rescue_hash(cs, &[temp_variable])?; // Just to standardize the proof format
+
+        let circuit = self.alloc(cs)?;
+        let (is_valid, options) = self.verify_with_options(cs, &circuit)?;
+        Boolean::enforce_equal(cs, &is_valid, &Boolean::constant(true))?;
+
+        let commitment = match options.reference_block_hash {
+            Some(reference_block_hash) => {
+                let outputs = &options.outputs;
+                let mut members = vec![
+                    outputs.guardian_set_hash,
+                    outputs.prices_summarize.commitment,
+                    outputs.earliest_publish_time,
+                    outputs.prices_summarize.num,
+                    outputs.prices_summarize.commitment_base_sum,
+                ];
+                members.extend(reference_block_hash);
+                circuit_poseidon_hash(cs, &members)?
+            }
+            None => circuit_outputs_commitment(cs, &options.outputs)?,
+        };
+        let mut outputs = vec![commitment];
+        if self.nullifiers.is_some() {
+            for price in circuit.signed_prices_batch.iter().flatten() {
+                self.cancellation_token.check()?;
+                for (signed_package, guardian) in price
+                    .signed_data_packages
+                    .iter()
+                    .zip(circuit.guardians.iter())
+                {
+                    outputs.push(signed_package.nullifier(cs, guardian)?);
+                }
+            }
+        }
+        if self.payload_hashes.is_some() {
+            for payload_hash in options.payload_hashes.iter() {
+                outputs.push(num_from_be_bytes(cs, &payload_hash[..16])?);
+                outputs.push(num_from_be_bytes(cs, &payload_hash[16..])?);
+            }
+        }
+        if self.output_commitment {
+            outputs = vec![output_commitment(cs, &options.triples, &outputs)?];
+        }
 
+        let expected_outputs = self.public_inputs().map_err(new_synthesis_error)?;
+        if expected_outputs.len() != outputs.len() {
+            return Err(new_synthesis_error(format!(
+                "expect {} public inputs, got {}",
+                expected_outputs.len(),
+                outputs.len()
+            )));
+        }
+        for (output, expected_output) in outputs.iter().zip(expected_outputs) {
+            let expected_output = Num::alloc(cs, Some(expected_output))?;
+            expected_output.enforce_equal(cs, output)?;
+            expected_output.get_variable().inputize(cs)?;
+        }
         Ok(())
     }
 
@@ -340,11 +1179,23 @@ impl<E: Engine, const NUM_SIGNATURES_TO_VERIFY: usize, const NUM_PRICES: usize>
 #[cfg(test)]
 mod tests {
     use advanced_circuit_component::{
-        franklin_crypto::bellman::plonk::better_better_cs::cs::Circuit,
+        franklin_crypto::bellman::{
+            pairing::{bn256::Fr, ff::Field},
+            plonk::better_better_cs::cs::Circuit,
+        },
         testing::{create_test_artifacts_with_optimized_gate, Bn256},
     };
+    use base64::prelude::*;
+    use num_bigint::BigUint;
 
-    use super::witness::{DataPackage, DataPoint};
+    use super::{
+        witness::{DataPackage, DataPoint, SignedPackageWitness},
+        FeedSet, RedstoneConfig, DEFAULT_MAX_TIMESTAMP_DELAY_MS,
+    };
+    use crate::utils::{
+        testing::{block_header_rlp, RedstoneSigner},
+        CancellationToken,
+    };
 
     #[test]
     fn test_circuit() -> anyhow::Result<()> {
@@ -354,18 +1205,569 @@ mod tests {
         );
         let signature  = hex::decode("9ad1f96c083cf31f757b33b0ef6b2c4279589bf0489c1c3a7beb0005d2080dd233aaae60fdafee196362ed5b6af7498e7ba07eaa725f0bc5a041016ce54a67d61b").unwrap().try_into().unwrap();
 
+        let guardians = [hex::decode("109B4a318A4F5ddcbCA6349B45f881B4137deaFB")
+            .unwrap()
+            .try_into()
+            .unwrap()];
+
         let mut signed_prices_batch = vec![];
-        signed_prices_batch.push([[(data_package, signature)]]);
+        signed_prices_batch.push([[SignedPackageWitness::new(
+            data_package,
+            signature,
+            guardians[0],
+        )]]);
+
+        let circuit = super::PriceOracle::<Bn256, 1, 1>::new(signed_prices_batch, guardians)?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        println!("gate: {}", cs.n());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cancellation() -> anyhow::Result<()> {
+        let data_package = DataPackage::new(
+            vec![DataPoint::new("AVAX", "36.2488073814028")],
+            1705311690000,
+        );
+        let signature: [u8; 65] = hex::decode("9ad1f96c083cf31f757b33b0ef6b2c4279589bf0489c1c3a7beb0005d2080dd233aaae60fdafee196362ed5b6af7498e7ba07eaa725f0bc5a041016ce54a67d61b").unwrap().try_into().unwrap();
+        let guardians = [hex::decode("109B4a318A4F5ddcbCA6349B45f881B4137deaFB")
+            .unwrap()
+            .try_into()
+            .unwrap()];
+        let signed_prices_batch = vec![[[SignedPackageWitness::new(
+            data_package,
+            signature,
+            guardians[0],
+        )]]];
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let result = super::PriceOracle::<Bn256, 1, 1>::new_with_cancellation_token(
+            signed_prices_batch.clone(),
+            guardians,
+            token,
+        );
+        assert!(result.is_err());
+
+        let token = CancellationToken::new();
+        let circuit = super::PriceOracle::<Bn256, 1, 1>::new_with_cancellation_token(
+            signed_prices_batch,
+            guardians,
+            token.clone(),
+        )?;
+        token.cancel();
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        assert!(circuit.synthesize(&mut cs).is_err());
+        Ok(())
+    }
 
+    #[test]
+    fn test_config() -> anyhow::Result<()> {
+        assert!(RedstoneConfig::default().validate(1).is_ok());
+        let invalid_configs = [
+            RedstoneConfig {
+                num_value_bs: 8,
+                ..Default::default()
+            },
+            RedstoneConfig {
+                quorum: Some(2),
+                ..Default::default()
+            },
+        ];
+        for config in invalid_configs {
+            assert!(config.validate(1).is_err());
+        }
+
+        let data_package = DataPackage::new(
+            vec![DataPoint::new("AVAX", "36.2488073814028")],
+            1705311690000,
+        );
+        let signature: [u8; 65] = hex::decode("9ad1f96c083cf31f757b33b0ef6b2c4279589bf0489c1c3a7beb0005d2080dd233aaae60fdafee196362ed5b6af7498e7ba07eaa725f0bc5a041016ce54a67d61b").unwrap().try_into().unwrap();
         let guardians = [hex::decode("109B4a318A4F5ddcbCA6349B45f881B4137deaFB")
             .unwrap()
             .try_into()
             .unwrap()];
+        let signed_prices_batch = vec![[[SignedPackageWitness::new(
+            data_package,
+            signature,
+            guardians[0],
+        )]]];
+        let config = RedstoneConfig {
+            target_num_value_decimals: 6,
+            ..Default::default()
+        };
+        let circuit = super::PriceOracle::<Bn256, 1, 1>::new_with_config(
+            signed_prices_batch.clone(),
+            guardians,
+            config,
+        )?;
+        let default_circuit =
+            super::PriceOracle::<Bn256, 1, 1>::new(signed_prices_batch, guardians)?;
+        assert_ne!(circuit.commitment, default_circuit.commitment);
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
 
-        let circuit = super::PriceOracle::<Bn256, 1, 1>::new(signed_prices_batch, guardians)?;
+    #[test]
+    fn test_shared_circuit() -> anyhow::Result<()> {
+        // Case reference:
+        // https://github.com/redstone-finance/redstone-oracles-monorepo/blob/cd0a6ffffbfcb1fb3dbf255b9d599db26e3faf34/packages/protocol/test/plain-obj-serialization.test.ts#L86
+        let data_package = DataPackage::new(
+            vec![
+                DataPoint::new("BTC", "20000"),
+                DataPoint::new("ETH", "1000"),
+            ],
+            1654353400000u64,
+        )
+        .with_metadata("redstone-primary-prod", "redstone-test-signer");
+        let signature: [u8; 65] = BASE64_STANDARD
+            .decode("NX5yd/Cs8HzVdNchrM59uOoSst7n9KK5Ou9pA6S5GTM0RwghGlFjA0S+SVfb85ipg4HzUTKATBZSqPXlWldEEhw=")?
+            .try_into()
+            .unwrap();
+        let guardians = [data_package.recover_signer(&signature)?];
+        let signed_packages_batch = vec![[SignedPackageWitness::new(
+            data_package,
+            signature,
+            guardians[0],
+        )]];
+
+        let circuit = super::PriceOracle::<Bn256, 1, 2>::new_shared(
+            ["BTC".to_string(), "ETH".to_string()],
+            signed_packages_batch,
+            guardians,
+        )?;
+        let metadata = circuit.metadata();
+        assert_eq!(metadata.len(), 1);
+        assert_eq!(metadata[0].signer, guardians[0]);
+        assert_eq!(
+            metadata[0].signer_name.as_deref(),
+            Some("redstone-test-signer")
+        );
         let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
         circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
         println!("gate: {}", cs.n());
+
+        // Compact feed ids commit the same prices with less gates
+        let (gates, commitment) = (cs.n(), circuit.commitment);
+        let circuit = circuit.with_compact_feed_ids()?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        assert_eq!(circuit.commitment, commitment);
+        assert!(cs.n() < gates);
+        Ok(())
+    }
+
+    #[test]
+    fn test_shared_circuit_latest_only() -> anyhow::Result<()> {
+        let data_package = DataPackage::new(
+            vec![DataPoint::new("AVAX", "36.2488073814028")],
+            1705311690000,
+        );
+        let signature: [u8; 65] = hex::decode("9ad1f96c083cf31f757b33b0ef6b2c4279589bf0489c1c3a7beb0005d2080dd233aaae60fdafee196362ed5b6af7498e7ba07eaa725f0bc5a041016ce54a67d61b").unwrap().try_into().unwrap();
+        let guardians = [data_package.recover_signer(&signature)?];
+        let signed_packages_batch = vec![
+            [SignedPackageWitness::new(
+                data_package.clone(),
+                signature,
+                guardians[0],
+            )],
+            [SignedPackageWitness::new(
+                data_package,
+                signature,
+                guardians[0],
+            )],
+        ];
+
+        let circuit = super::PriceOracle::<Bn256, 1, 1>::new_shared(
+            ["AVAX".to_string()],
+            signed_packages_batch,
+            guardians,
+        )?
+        .with_latest_only()?;
+        assert_eq!(circuit.public_input_data.prices_summarize.num, Fr::one());
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+
+        // The latest price of every feed is taken from packages carrying its latest timestamp
+        let signers = [RedstoneSigner::new(0x11), RedstoneSigner::new(0x22)];
+        let guardians = signers.each_ref().map(|signer| signer.address());
+        let (earlier, later) = (1654353400000u64, 1654353410000u64);
+        let package = |points: &[(&str, &str)], timestamp| {
+            let points = points
+                .iter()
+                .map(|(feed_id, value)| DataPoint::new(feed_id, value))
+                .collect();
+            DataPackage::new(points, timestamp)
+        };
+        let first_round = [
+            signers[0].sign_witness(package(&[("BTC", "20000"), ("ETH", "1000")], earlier)),
+            signers[1].sign_witness(package(&[("BTC", "20000"), ("ETH", "1000")], earlier)),
+        ];
+        let second_round = [
+            signers[0].sign_witness(package(&[("BTC", "21000")], later)),
+            signers[1].sign_witness(package(&[("ETH", "1100")], earlier)),
+        ];
+        let feed_ids = ["BTC".to_string(), "ETH".to_string()];
+        let circuit = super::PriceOracle::<Bn256, 2, 2>::new_shared_with_quorums(
+            feed_ids.clone(),
+            [1, 1],
+            vec![first_round.clone(), second_round],
+            guardians,
+        )?
+        .with_latest_only()?;
+        let round = &circuit.opening()?.rounds[0];
+        assert_eq!(round.publish_time, earlier);
+        let decimals = BigUint::from(10u8).pow(18);
+        assert_eq!(round.prices[0].price, &decimals * 21000u32);
+        assert_eq!(round.prices[1].price, &decimals * 1100u32);
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+
+        // ETH of the later package can not be mixed with an earlier one
+        let second_round = [
+            signers[0].sign_witness(package(&[("BTC", "21000"), ("ETH", "1100")], later)),
+            signers[1].sign_witness(package(&[("ETH", "900")], earlier)),
+        ];
+        assert!(super::PriceOracle::<Bn256, 2, 2>::new_shared_with_quorums(
+            feed_ids,
+            [1, 1],
+            vec![first_round, second_round],
+            guardians,
+        )?
+        .with_latest_only()
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_shared_circuit_with_quorums() -> anyhow::Result<()> {
+        let full_package = DataPackage::new(
+            vec![
+                DataPoint::new("BTC", "20000"),
+                DataPoint::new("ETH", "1000"),
+            ],
+            1654353400000u64,
+        );
+        let partial_package =
+            DataPackage::new(vec![DataPoint::new("BTC", "20010")], 1654353400000u64);
+        let (full_signature, full_signer) = RedstoneSigner::new(0x11).sign(&full_package);
+        let (partial_signature, partial_signer) = RedstoneSigner::new(0x22).sign(&partial_package);
+        let guardians = [full_signer, partial_signer];
+        let signed_packages_batch = vec![[
+            SignedPackageWitness::new(full_package, full_signature, full_signer),
+            SignedPackageWitness::new(partial_package, partial_signature, partial_signer),
+        ]];
+        let feed_ids = ["BTC".to_string(), "ETH".to_string()];
+
+        // ETH is provided by only one signer
+        assert!(super::PriceOracle::<Bn256, 2, 2>::new_shared(
+            feed_ids.clone(),
+            signed_packages_batch.clone(),
+            guardians,
+        )
+        .is_err());
+
+        let circuit = super::PriceOracle::<Bn256, 2, 2>::new_shared_with_quorums(
+            feed_ids.clone(),
+            [2, 1],
+            signed_packages_batch.clone(),
+            guardians,
+        )?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+
+        // Partial package is rejected if every package must contain all feeds
+        assert!(super::PriceOracle::<Bn256, 2, 2>::new_shared_with_quorums(
+            feed_ids.clone(),
+            [2, 1],
+            signed_packages_batch,
+            guardians,
+        )?
+        .with_complete_packages()
+        .is_err());
+        let mut circuit = circuit;
+        circuit.complete_packages = true;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+
+        // Circuit is unsatisfied if ETH is required from both signers
+        circuit.complete_packages = false;
+        circuit.feeds = FeedSet::Shared {
+            ids: feed_ids,
+            quorums: [2, 2],
+        };
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_shared_circuit_with_bounds() -> anyhow::Result<()> {
+        let data_package = DataPackage::new(
+            vec![
+                DataPoint::new("BTC", "20000"),
+                DataPoint::new("ETH", "1000"),
+            ],
+            1654353400000u64,
+        );
+        let (signature, signer) = RedstoneSigner::new(0x11).sign(&data_package);
+        let new_circuit = || {
+            super::PriceOracle::<Bn256, 1, 2>::new_shared(
+                ["BTC".to_string(), "ETH".to_string()],
+                vec![[SignedPackageWitness::new(
+                    data_package.clone(),
+                    signature,
+                    signer,
+                )]],
+                [signer],
+            )
+        };
+        let decimals = BigUint::from(10u8).pow(18);
+        let btc_bounds = (&decimals * 10000u32, &decimals * 100000u32);
+        let eth_bounds = (&decimals * 100u32, &decimals * 10000u32);
+
+        let circuit = new_circuit()?.with_bounds([btc_bounds.clone(), eth_bounds.clone()])?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+
+        // ETH at 1000 is a 1000x outlier of bounds
+        let outlier_bounds = (&decimals * 1_000_000u32, &decimals * 10_000_000u32);
+        assert!(new_circuit()?
+            .with_bounds([btc_bounds.clone(), outlier_bounds.clone()])
+            .is_err());
+        let mut circuit = circuit;
+        circuit.bounds = Some([btc_bounds, outlier_bounds]);
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_shared_circuit_heartbeat() -> anyhow::Result<()> {
+        // Heartbeat packages only attest that signers are alive at the timestamp
+        let heartbeat = DataPackage::new(vec![], 1654353400000u64);
+        let (signature, signer) = RedstoneSigner::new(0x11).sign(&heartbeat);
+        let circuit = super::PriceOracle::<Bn256, 1, 0>::new_shared(
+            [],
+            vec![[SignedPackageWitness::new(heartbeat, signature, signer)]],
+            [signer],
+        )?
+        .with_nullifiers()?
+        .with_payload_hashes();
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_shared_circuit_with_payload_hashes() -> anyhow::Result<()> {
+        let data_package = DataPackage::new(
+            vec![DataPoint::new("AVAX", "36.2488073814028")],
+            1705311690000,
+        );
+        let (signature, signer) = RedstoneSigner::new(0x11).sign(&data_package);
+        let payload_hash = data_package.keccak256_hash();
+        let circuit = super::PriceOracle::<Bn256, 1, 1>::new_shared(
+            ["AVAX".to_string()],
+            vec![[SignedPackageWitness::new(data_package, signature, signer)]],
+            [signer],
+        )?
+        .with_payload_hashes();
+        assert_eq!(circuit.payload_hashes, Some(vec![payload_hash]));
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+
+        // Circuit is unsatisfied if the payload hash is not of the signed package
+        let mut circuit = circuit;
+        circuit.payload_hashes = Some(vec![[0u8; 32]]);
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_shared_circuit_with_output_commitment() -> anyhow::Result<()> {
+        let data_package = DataPackage::new(
+            vec![
+                DataPoint::new("BTC", "20000"),
+                DataPoint::new("ETH", "1000"),
+            ],
+            1654353400000u64,
+        );
+        let signer = RedstoneSigner::new(0x11);
+        let circuit = super::PriceOracle::<Bn256, 1, 2>::new_shared(
+            ["BTC".to_string(), "ETH".to_string()],
+            vec![[signer.sign_witness(data_package)]],
+            [signer.address()],
+        )?
+        .with_nullifiers()?
+        .with_payload_hashes();
+        // Commitment, one nullifier and two limbs of one payload hash
+        assert_eq!(circuit.public_inputs()?.len(), 4);
+
+        let circuit = circuit.with_output_commitment();
+        assert_eq!(circuit.public_inputs()?.len(), 1);
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+
+        // Circuit is unsatisfied if any absorbed output is wrong
+        let mut circuit = circuit;
+        circuit.nullifiers = Some(vec![Fr::one()]);
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_shared_circuit_with_nullifiers() -> anyhow::Result<()> {
+        let data_package = DataPackage::new(
+            vec![DataPoint::new("AVAX", "36.2488073814028")],
+            1705311690000,
+        );
+        let (signature, signer) = RedstoneSigner::new(0x11).sign(&data_package);
+        let witness = SignedPackageWitness::new(data_package, signature, signer);
+        let expected_nullifier = witness.nullifier::<Bn256>()?;
+
+        let circuit = super::PriceOracle::<Bn256, 1, 1>::new_shared(
+            ["AVAX".to_string()],
+            vec![[witness]],
+            [signer],
+        )?
+        .with_nullifiers()?;
+        assert_eq!(circuit.nullifiers, Some(vec![expected_nullifier]));
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_shared_circuit_with_reference_block() -> anyhow::Result<()> {
+        let data_package = DataPackage::new(
+            vec![DataPoint::new("AVAX", "36.2488073814028")],
+            1705311690000,
+        );
+        let signature: [u8; 65] = hex::decode("9ad1f96c083cf31f757b33b0ef6b2c4279589bf0489c1c3a7beb0005d2080dd233aaae60fdafee196362ed5b6af7498e7ba07eaa725f0bc5a041016ce54a67d61b").unwrap().try_into().unwrap();
+        let guardians = [hex::decode("109B4a318A4F5ddcbCA6349B45f881B4137deaFB")
+            .unwrap()
+            .try_into()
+            .unwrap()];
+        let signed_packages_batch = vec![[SignedPackageWitness::new(
+            data_package,
+            signature,
+            guardians[0],
+        )]];
+        // Block timestamp is 1705311700, 10 seconds after the data package
+        let block_header = hex::decode(block_header_rlp())?;
+
+        let circuit = super::PriceOracle::<Bn256, 1, 1>::new_shared(
+            ["AVAX".to_string()],
+            signed_packages_batch,
+            guardians,
+        )?;
+        let commitment_without_block = circuit.commitment;
+        let circuit = circuit.with_reference_block_header(block_header.clone())?;
+        assert_ne!(circuit.commitment, commitment_without_block);
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+
+        // Stale data package is rejected
+        let stale_package = DataPackage::new(
+            vec![DataPoint::new("AVAX", "36.2488073814028")],
+            1705311700000 - DEFAULT_MAX_TIMESTAMP_DELAY_MS - 1,
+        );
+        let (signature, signer) = RedstoneSigner::new(0x11).sign(&stale_package);
+        let signed_packages_batch =
+            vec![[SignedPackageWitness::new(stale_package, signature, signer)]];
+        let circuit = super::PriceOracle::<Bn256, 1, 1>::new_shared(
+            ["AVAX".to_string()],
+            signed_packages_batch.clone(),
+            [signer],
+        )?;
+        assert!(circuit
+            .with_reference_block_header(block_header.clone())
+            .is_err());
+
+        // But accepted by a wider window
+        let config = RedstoneConfig {
+            max_timestamp_delay_ms: 2 * DEFAULT_MAX_TIMESTAMP_DELAY_MS,
+            ..Default::default()
+        };
+        let circuit = super::PriceOracle::<Bn256, 1, 1>::new_shared_with_config(
+            ["AVAX".to_string()],
+            signed_packages_batch,
+            [signer],
+            config,
+        )?
+        .with_reference_block_header(block_header)?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_shared_circuit_with_feed_max_delays() -> anyhow::Result<()> {
+        // Block timestamp is 1705311700, and packages are 5 minutes old
+        let block_header = hex::decode(block_header_rlp())?;
+        let timestamp = 1705311700000 - 5 * 60 * 1000;
+        let slow_max_delay = 10 * 60 * 1000;
+        let signer = RedstoneSigner::new(0x11);
+
+        let package = DataPackage::new(vec![DataPoint::new("AVAX", "36.2488073814028")], timestamp);
+        let signed_packages_batch = vec![[signer.sign_witness(package)]];
+        let circuit = super::PriceOracle::<Bn256, 1, 1>::new_shared(
+            ["AVAX".to_string()],
+            signed_packages_batch.clone(),
+            [signer.address()],
+        )?;
+        assert!(circuit
+            .with_reference_block_header(block_header.clone())
+            .is_err());
+        let circuit = super::PriceOracle::<Bn256, 1, 1>::new_shared(
+            ["AVAX".to_string()],
+            signed_packages_batch,
+            [signer.address()],
+        )?
+        .with_feed_max_delays([slow_max_delay])?
+        .with_reference_block_header(block_header.clone())?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+
+        // A fast feed in the same package still requires the default max delay
+        let package = DataPackage::new(
+            vec![
+                DataPoint::new("AVAX", "36.2488073814028"),
+                DataPoint::new("ETH", "1000"),
+            ],
+            timestamp,
+        );
+        let circuit = super::PriceOracle::<Bn256, 1, 2>::new_shared(
+            ["AVAX".to_string(), "ETH".to_string()],
+            vec![[signer.sign_witness(package)]],
+            [signer.address()],
+        )?
+        .with_feed_max_delays([slow_max_delay, DEFAULT_MAX_TIMESTAMP_DELAY_MS])?;
+        assert!(circuit.with_reference_block_header(block_header).is_err());
         Ok(())
     }
 }
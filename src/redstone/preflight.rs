@@ -0,0 +1,200 @@
+use num_bigint::BigUint;
+
+use super::{
+    witness::{is_timestamp_fresh, median, normalize_value, DataPackage},
+    RedstoneConfig, SIGNATURE_BS,
+};
+
+/// Policy of [`validate_witness`], which mirrors options of the multi-feed circuit.
+#[derive(Debug, Clone)]
+pub struct ValidationPolicy {
+    pub feed_ids: Vec<String>,
+    pub config: RedstoneConfig,
+    /// Reference time in milliseconds against which packages must be fresh, e.g. the timestamp
+    /// of the reference block. Freshness is not checked if `None`.
+    pub reference_time: Option<u64>,
+    /// Inclusive bounds `(min, max)` of normalized prices of `feed_ids`, which bound deviation of
+    /// prices in the same way as the circuit. Bounds are not checked if `None`.
+    pub bounds: Option<Vec<(BigUint, BigUint)>>,
+}
+
+impl ValidationPolicy {
+    pub fn new(feed_ids: Vec<String>) -> Self {
+        Self {
+            feed_ids,
+            config: RedstoneConfig::default(),
+            reference_time: None,
+            bounds: None,
+        }
+    }
+
+    pub fn with_config(mut self, config: RedstoneConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn with_reference_time(mut self, reference_time: u64) -> Self {
+        self.reference_time = Some(reference_time);
+        self
+    }
+
+    pub fn with_bounds(mut self, bounds: Vec<(BigUint, BigUint)>) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+}
+
+/// Validate a round of data packages, each with its signature and expected signer, before paying
+/// the cost of synthesis. It performs the same checks as the circuit, which are signature
+/// recovery, freshness, quorum and bounds, and returns the normalized median price of each feed.
+pub fn validate_witness(
+    witnesses: &[(DataPackage, [u8; SIGNATURE_BS], [u8; 20])],
+    policy: &ValidationPolicy,
+) -> anyhow::Result<Vec<BigUint>> {
+    if witnesses.is_empty() {
+        anyhow::bail!("no data package to validate")
+    }
+    let config = &policy.config;
+    config.validate(witnesses.len())?;
+    if let Some(bounds) = &policy.bounds {
+        if bounds.len() != policy.feed_ids.len() {
+            anyhow::bail!(
+                "expect bounds of {} feeds, got {}",
+                policy.feed_ids.len(),
+                bounds.len()
+            )
+        }
+    }
+
+    for (i, (package, signature, expected_signer)) in witnesses.iter().enumerate() {
+        package.validate()?;
+        let signer = package.recover_signer(signature)?;
+        if &signer != expected_signer {
+            anyhow::bail!(
+                "data package #{} is signed by 0x{} rather than 0x{}",
+                i,
+                hex::encode(signer),
+                hex::encode(expected_signer)
+            )
+        }
+        if let Some(reference_time) = policy.reference_time {
+            if !is_timestamp_fresh(
+                package.timestamp,
+                reference_time,
+                config.max_timestamp_delay_ms,
+                config.max_timestamp_ahead_ms,
+            ) {
+                anyhow::bail!(
+                    "data package #{} at {} is not fresh at {}",
+                    i,
+                    package.timestamp,
+                    reference_time
+                )
+            }
+        }
+    }
+
+    let quorum = config.quorum(witnesses.len());
+    let mut prices = vec![];
+    for (i, feed_id) in policy.feed_ids.iter().enumerate() {
+        let values = witnesses
+            .iter()
+            .filter_map(|(package, _, _)| package.value_of(feed_id))
+            .collect::<Vec<_>>();
+        if values.len() < quorum {
+            anyhow::bail!(
+                "feed {} is provided by {} signers, less than quorum {}",
+                feed_id,
+                values.len(),
+                quorum
+            )
+        }
+        let price = median(&values).ok_or_else(|| anyhow::anyhow!("no value of {}", feed_id))?;
+        let price = normalize_value(
+            &price,
+            config.num_value_decimals,
+            config.target_num_value_decimals,
+        );
+        if let Some(bounds) = &policy.bounds {
+            let (min, max) = &bounds[i];
+            if &price < min || &price > max {
+                anyhow::bail!(
+                    "price {} of feed {} is out of bounds [{}, {}]",
+                    price,
+                    feed_id,
+                    min,
+                    max
+                )
+            }
+        }
+        prices.push(price);
+    }
+    Ok(prices)
+}
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::BigUint;
+
+    use super::{validate_witness, ValidationPolicy};
+    use crate::{
+        redstone::{
+            witness::{DataPackage, DataPoint},
+            RedstoneConfig,
+        },
+        utils::testing::RedstoneSigner,
+    };
+
+    #[test]
+    fn test_validate_witness() -> anyhow::Result<()> {
+        let timestamp = 1654353400000u64;
+        let signers = RedstoneSigner::generate(3);
+        let witnesses = [("20000", true), ("20100", true), ("19900", false)]
+            .into_iter()
+            .zip(signers.iter())
+            .map(|((btc, has_eth), signer)| {
+                let mut data_points = vec![DataPoint::new("BTC", btc)];
+                if has_eth {
+                    data_points.push(DataPoint::new("ETH", "1000"));
+                }
+                let package = DataPackage::new(data_points, timestamp);
+                let (signature, address) = signer.sign(&package);
+                (package, signature, address)
+            })
+            .collect::<Vec<_>>();
+
+        let policy = ValidationPolicy::new(vec!["BTC".to_string()])
+            .with_reference_time(timestamp + 1000)
+            .with_bounds(vec![(
+                BigUint::from(19000u64) * BigUint::from(10u64).pow(18),
+                BigUint::from(21000u64) * BigUint::from(10u64).pow(18),
+            )]);
+        let prices = validate_witness(&witnesses, &policy)?;
+        assert_eq!(
+            prices,
+            vec![BigUint::from(20000u64) * BigUint::from(10u64).pow(18)]
+        );
+
+        // ETH misses quorum of all signers, but meets quorum of 2 signers
+        let policy = ValidationPolicy::new(vec!["ETH".to_string()]);
+        assert!(validate_witness(&witnesses, &policy).is_err());
+        let policy = policy.with_config(RedstoneConfig {
+            quorum: Some(2),
+            ..Default::default()
+        });
+        assert!(validate_witness(&witnesses, &policy).is_ok());
+
+        // Stale package, price out of bounds and mismatched signer are rejected
+        let policy = ValidationPolicy::new(vec!["BTC".to_string()]);
+        let stale_policy = policy.clone().with_reference_time(timestamp + 3600 * 1000);
+        assert!(validate_witness(&witnesses, &stale_policy).is_err());
+        let bounded_policy = policy
+            .clone()
+            .with_bounds(vec![(BigUint::from(0u8), BigUint::from(1u8))]);
+        assert!(validate_witness(&witnesses, &bounded_policy).is_err());
+        let mut forged = witnesses.clone();
+        forged[0].2 = signers[1].address();
+        assert!(validate_witness(&forged, &policy).is_err());
+        Ok(())
+    }
+}
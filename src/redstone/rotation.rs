@@ -0,0 +1,225 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{
+    ff::{Field, PrimeField, PrimeFieldRepr},
+    Engine,
+};
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{
+            plonk::better_better_cs::{
+                cs::{Circuit, ConstraintSystem, Gate, GateInternal},
+                gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
+            },
+            SynthesisError,
+        },
+        plonk::circuit::{
+            allocated_num::Num, boolean::Boolean, custom_rescue_gate::Rescue5CustomGate,
+        },
+    },
+    vm::primitives::uint256::UInt256,
+};
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    gadgets::{
+        ecdsa::Signature, ethereum::Address, keccak256, poseidon::circuit_poseidon_hash,
+        rescue::circuit_rescue_hash,
+    },
+    utils::{self, num_into_be_bytes},
+};
+
+use super::{
+    witness::{guardian_set_hash, recover_signer},
+    SIGNATURE_BS,
+};
+
+// Domain separator of the message authorizing the next guardian set
+pub const ROTATION_MESSAGE_DOMAIN: &[u8] = b"zklink-oracle/rotate-guardian-set";
+
+/// Keccak256 hash of the rotation message `domain || current set hash || next set hash`, where
+/// hashes are 32-bytes big-endian. It is signed by every guardian of the current set.
+pub fn rotation_message_hash<E: Engine>(
+    current_guardian_set_hash: &E::Fr,
+    next_guardian_set_hash: &E::Fr,
+) -> anyhow::Result<[u8; 32]> {
+    let mut message = ROTATION_MESSAGE_DOMAIN.to_vec();
+    current_guardian_set_hash
+        .into_repr()
+        .write_be(&mut message)?;
+    next_guardian_set_hash.into_repr().write_be(&mut message)?;
+    Ok(Keccak256::new_with_prefix(message).finalize().into())
+}
+
+/// Circuit proving that all `NUM_GUARDIANS` guardians of the current set authorize the next
+/// guardian set, so that the consumer can rotate trusted signers without a manual upgrade.
+///
+/// Public inputs are the hash of the current guardian set followed by the hash of the next one,
+/// which are computed in the same way as the guardian set hash of price oracles.
+pub struct GuardianSetRotation<E: Engine, const NUM_GUARDIANS: usize> {
+    pub current_guardians: [[u8; 20]; NUM_GUARDIANS],
+    pub next_guardians: Vec<[u8; 20]>,
+    pub signatures: [[u8; SIGNATURE_BS]; NUM_GUARDIANS],
+    pub current_guardian_set_hash: E::Fr,
+    pub next_guardian_set_hash: E::Fr,
+}
+
+impl<E: Engine, const NUM_GUARDIANS: usize> GuardianSetRotation<E, NUM_GUARDIANS> {
+    pub fn new(
+        current_guardians: [[u8; 20]; NUM_GUARDIANS],
+        next_guardians: Vec<[u8; 20]>,
+        signatures: [[u8; SIGNATURE_BS]; NUM_GUARDIANS],
+    ) -> Result<Self, anyhow::Error> {
+        if next_guardians.is_empty() {
+            anyhow::bail!("next guardian set is empty")
+        }
+        let current_guardian_set_hash = guardian_set_hash::<E>(&current_guardians)?;
+        let next_guardian_set_hash = guardian_set_hash::<E>(&next_guardians)?;
+        let message_hash =
+            rotation_message_hash::<E>(&current_guardian_set_hash, &next_guardian_set_hash)?;
+        for (guardian, signature) in current_guardians.iter().zip(signatures.iter()) {
+            let signer = recover_signer(&message_hash, signature)?;
+            if &signer != guardian {
+                anyhow::bail!(
+                    "rotation is signed by 0x{} rather than guardian 0x{}",
+                    hex::encode(signer),
+                    hex::encode(guardian)
+                )
+            }
+        }
+        Ok(Self {
+            current_guardians,
+            next_guardians,
+            signatures,
+            current_guardian_set_hash,
+            next_guardian_set_hash,
+        })
+    }
+}
+
+impl<E: Engine, const NUM_GUARDIANS: usize> Circuit<E> for GuardianSetRotation<E, NUM_GUARDIANS> {
+    type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
+
+    fn synthesize<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<(), SynthesisError> {
+        utils::add_bitwise_logic_and_range_table(cs)?;
+        let temp_variable = Num::alloc(cs, Some(E::Fr::one()))?;
+        circuit_rescue_hash(cs, &[temp_variable])?; // Just to standardize the proof format
+
+        let mut guardian_set_hashes = vec![];
+        let mut current_guardians = vec![];
+        for guardians in [&self.current_guardians[..], &self.next_guardians[..]] {
+            let guardians = guardians
+                .iter()
+                .map(|g| Address::from_address_witness(cs, g))
+                .collect::<Result<Vec<_>, _>>()?;
+            let guardian_nums = guardians
+                .iter()
+                .map(|g| g.inner().to_num_unchecked(cs))
+                .collect::<Result<Vec<_>, _>>()?;
+            guardian_set_hashes.push(circuit_poseidon_hash(cs, &guardian_nums)?);
+            if current_guardians.is_empty() {
+                current_guardians = guardians;
+            }
+        }
+
+        // Message signed by current guardians
+        let message_hash = {
+            let mut message = ROTATION_MESSAGE_DOMAIN
+                .iter()
+                .map(|b| Byte::constant(*b))
+                .collect::<Vec<_>>();
+            for hash in guardian_set_hashes.iter() {
+                message.extend(num_into_be_bytes(cs, hash, 32)?);
+            }
+            let hash = keccak256::digest(cs, &message)?;
+            UInt256::from_be_bytes_fixed(cs, &hash)?
+        };
+
+        let mut is_authorized = Boolean::constant(true);
+        for (guardian, signature) in current_guardians.iter().zip(self.signatures.iter()) {
+            let mut signature = *signature;
+            if signature[64] >= 27 {
+                signature[64] -= 27;
+            }
+            let signature = Signature::from_bytes_witness(cs, &signature)?;
+            let (successful, (x, y)) = signature.ecrecover(cs, &message_hash)?;
+            let is_matched = {
+                let (x, y) = (
+                    x.into_be_bytes(cs)?.try_into().unwrap(),
+                    y.into_be_bytes(cs)?.try_into().unwrap(),
+                );
+                let address = Address::from_pubkey(cs, &x, &y)?;
+                guardian.equals(cs, &address)?
+            };
+            let is_current_authorized = Boolean::and(cs, &is_matched, &successful)?;
+            is_authorized = Boolean::and(cs, &is_authorized, &is_current_authorized)?;
+        }
+        Boolean::enforce_equal(cs, &is_authorized, &Boolean::constant(true))?;
+
+        let expected_hashes = [self.current_guardian_set_hash, self.next_guardian_set_hash];
+        for (hash, expected_hash) in guardian_set_hashes.iter().zip(expected_hashes) {
+            let expected_hash = Num::alloc(cs, Some(expected_hash))?;
+            expected_hash.enforce_equal(cs, hash)?;
+            expected_hash.get_variable().inputize(cs)?;
+        }
+        Ok(())
+    }
+
+    fn declare_used_gates() -> Result<Vec<Box<dyn GateInternal<E>>>, SynthesisError> {
+        Ok(vec![
+            Self::MainGate::default().into_internal(),
+            Rescue5CustomGate.into_internal(), // Just to standardize the proof format
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::bellman::plonk::better_better_cs::cs::Circuit,
+        testing::{create_test_artifacts_with_optimized_gate, Bn256},
+    };
+
+    use super::{rotation_message_hash, GuardianSetRotation};
+    use crate::{redstone::witness::guardian_set_hash, utils::testing::RedstoneSigner};
+
+    #[test]
+    fn test_guardian_set_rotation() -> anyhow::Result<()> {
+        let current = RedstoneSigner::generate(2);
+        let next = [RedstoneSigner::new(0x11), RedstoneSigner::new(0x22)];
+        let current_guardians = [current[0].address(), current[1].address()];
+        let next_guardians = next.iter().map(|s| s.address()).collect::<Vec<_>>();
+        let message_hash = rotation_message_hash::<Bn256>(
+            &guardian_set_hash::<Bn256>(&current_guardians)?,
+            &guardian_set_hash::<Bn256>(&next_guardians)?,
+        )?;
+        let signatures = [
+            current[0].sign_hash(&message_hash),
+            current[1].sign_hash(&message_hash),
+        ];
+
+        let circuit = GuardianSetRotation::<Bn256, 2>::new(
+            current_guardians,
+            next_guardians.clone(),
+            signatures,
+        )?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+
+        // The next set can not authorize itself
+        let forged = [
+            next[0].sign_hash(&message_hash),
+            next[1].sign_hash(&message_hash),
+        ];
+        assert!(
+            GuardianSetRotation::<Bn256, 2>::new(current_guardians, next_guardians, forged)
+                .is_err()
+        );
+        let mut circuit = circuit;
+        circuit.signatures = forged;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+        Ok(())
+    }
+}
@@ -0,0 +1,406 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::boolean::Boolean,
+    },
+    traits::CSAllocatable,
+    vm::{partitioner::smart_and, primitives::uint256::UInt256},
+};
+use num_bigint::BigUint;
+
+use crate::{
+    gadgets::{ecdsa::Signature, ethereum::Address},
+    utils::new_synthesis_error,
+};
+
+use super::{
+    circuit::{check_signature, AllocatedDataPoint},
+    witness::{preimage_layout_with_value_limbs, DataPackage, DataPoint, FieldLayout},
+    DEFAULT_NUM_VALUE_BS,
+};
+
+/// Numeric value of `NUM_LIMBS` 256-bits limbs, the most significant limb first, for derived
+/// feeds (e.g. fixed-point indices) overflowing 32 bytes after scaling.
+#[derive(Clone, Copy, Debug)]
+pub struct WideValue<E: Engine, const NUM_LIMBS: usize> {
+    pub limbs: [UInt256<E>; NUM_LIMBS],
+}
+
+impl<E: Engine, const NUM_LIMBS: usize> WideValue<E, NUM_LIMBS> {
+    pub fn zero() -> Self {
+        Self {
+            limbs: [UInt256::zero(); NUM_LIMBS],
+        }
+    }
+
+    /// Panics if `value` doesn't fit in `NUM_LIMBS` limbs.
+    pub fn constant(value: &BigUint) -> Self {
+        let bytes = value.to_bytes_be();
+        let mut padded = vec![0u8; NUM_LIMBS * 32];
+        padded[NUM_LIMBS * 32 - bytes.len()..].copy_from_slice(&bytes);
+        let mut limbs = [UInt256::zero(); NUM_LIMBS];
+        for (limb, bytes) in limbs.iter_mut().zip(padded.chunks(32)) {
+            *limb = UInt256::constant(BigUint::from_bytes_be(bytes));
+        }
+        Self { limbs }
+    }
+
+    pub fn from_uint256(value: &UInt256<E>) -> Self {
+        let mut limbs = [UInt256::zero(); NUM_LIMBS];
+        limbs[NUM_LIMBS - 1] = *value;
+        Self { limbs }
+    }
+
+    pub fn from_be_bytes<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        bytes: &[[Byte<E>; DEFAULT_NUM_VALUE_BS]; NUM_LIMBS],
+    ) -> Result<Self, SynthesisError> {
+        let mut limbs = [UInt256::zero(); NUM_LIMBS];
+        for (limb, bytes) in limbs.iter_mut().zip(bytes.iter()) {
+            *limb = UInt256::from_be_bytes_fixed(cs, bytes)?;
+        }
+        Ok(Self { limbs })
+    }
+
+    pub fn get_value(&self) -> Option<BigUint> {
+        let mut value = BigUint::from(0u8);
+        for limb in self.limbs.iter() {
+            value = (value << 256u32) + limb.get_value()?;
+        }
+        Some(value)
+    }
+
+    /// Add with carry across limbs. Also returns whether the sum overflows all limbs.
+    pub fn add<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        other: &Self,
+    ) -> Result<(Self, Boolean), SynthesisError> {
+        let one = UInt256::constant(BigUint::from(1u8));
+        let mut carry = Boolean::constant(false);
+        let mut limbs = [UInt256::zero(); NUM_LIMBS];
+        for i in (0..NUM_LIMBS).rev() {
+            let (sum, of1) = self.limbs[i].add(cs, &other.limbs[i])?;
+            let carry_value = UInt256::conditionally_select(cs, &carry, &one, &UInt256::zero())?;
+            let (sum, of2) = sum.add(cs, &carry_value)?;
+            limbs[i] = sum;
+            // At most one of them overflows
+            carry = Boolean::or(cs, &of1, &of2)?;
+        }
+        Ok((Self { limbs }, carry))
+    }
+
+    /// Subtract with borrow across limbs. Also returns whether `self` is less than `other`.
+    pub fn sub<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        other: &Self,
+    ) -> Result<(Self, Boolean), SynthesisError> {
+        let one = UInt256::constant(BigUint::from(1u8));
+        let mut borrow = Boolean::constant(false);
+        let mut limbs = [UInt256::zero(); NUM_LIMBS];
+        for i in (0..NUM_LIMBS).rev() {
+            let (diff, borrow1) = self.limbs[i].sub(cs, &other.limbs[i])?;
+            let borrow_value = UInt256::conditionally_select(cs, &borrow, &one, &UInt256::zero())?;
+            let (diff, borrow2) = diff.sub(cs, &borrow_value)?;
+            limbs[i] = diff;
+            // At most one of them borrows
+            borrow = Boolean::or(cs, &borrow1, &borrow2)?;
+        }
+        Ok((Self { limbs }, borrow))
+    }
+
+    pub fn equals<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        a: &Self,
+        b: &Self,
+    ) -> Result<Boolean, SynthesisError> {
+        let mut is_equal = vec![];
+        for (a, b) in a.limbs.iter().zip(b.limbs.iter()) {
+            is_equal.push(UInt256::equals(cs, a, b)?);
+        }
+        smart_and(cs, &is_equal)
+    }
+
+    pub fn conditionally_select<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        flag: &Boolean,
+        a: &Self,
+        b: &Self,
+    ) -> Result<Self, SynthesisError> {
+        let mut limbs = [UInt256::zero(); NUM_LIMBS];
+        for (limb, (a, b)) in limbs.iter_mut().zip(a.limbs.iter().zip(b.limbs.iter())) {
+            *limb = UInt256::conditionally_select(cs, flag, a, b)?;
+        }
+        Ok(Self { limbs })
+    }
+
+    /// Narrow into the least significant limb, together with whether all other limbs are zero,
+    /// e.g. after the value is scaled down into the range of regular prices.
+    pub fn narrow<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<(Boolean, UInt256<E>), SynthesisError> {
+        let mut is_zero = vec![];
+        for limb in self.limbs[..NUM_LIMBS - 1].iter() {
+            is_zero.push(UInt256::equals(cs, limb, &UInt256::zero())?);
+        }
+        let fits = smart_and(cs, &is_zero)?;
+        Ok((fits, self.limbs[NUM_LIMBS - 1]))
+    }
+}
+
+/// Same as [`AllocatedDataPoint`], but the value is `NUM_LIMBS` 32-bytes limbs wide.
+#[derive(Clone, Debug, Copy)]
+pub struct AllocatedWideDataPoint<E: Engine, const NUM_LIMBS: usize> {
+    pub data_feed_id: [Byte<E>; 32],
+    pub value: [[Byte<E>; DEFAULT_NUM_VALUE_BS]; NUM_LIMBS],
+}
+
+impl<E: Engine, const NUM_LIMBS: usize> AllocatedWideDataPoint<E, NUM_LIMBS> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: DataPoint,
+    ) -> Result<Self, SynthesisError> {
+        let data_feed_id = {
+            let bytes = witness.serialize_feed_id().try_into().unwrap();
+            CSAllocatable::alloc_from_witness(cs, Some(bytes))?
+        };
+        AllocatedDataPoint::enforce_feed_id_shape(cs, &data_feed_id)?;
+        let mut value = [[Byte::zero(); DEFAULT_NUM_VALUE_BS]; NUM_LIMBS];
+        let bytes = witness.serialize_value_with_limbs(NUM_LIMBS);
+        for (limb, bytes) in value.iter_mut().zip(bytes.chunks(DEFAULT_NUM_VALUE_BS)) {
+            *limb = CSAllocatable::alloc_from_witness(cs, Some(bytes.try_into().unwrap()))?;
+        }
+        Ok(Self {
+            data_feed_id,
+            value,
+        })
+    }
+
+    pub fn serialize(&self) -> Result<Vec<Byte<E>>, SynthesisError> {
+        let mut bytes = vec![];
+        bytes.extend(self.data_feed_id);
+        for limb in self.value.iter() {
+            bytes.extend(limb);
+        }
+        Ok(bytes)
+    }
+}
+
+/// Same as [`super::circuit::AllocatedDataPackage`], but values are `NUM_LIMBS` 32-bytes limbs
+/// wide, which is also the value byte size `NUM_LIMBS * 32` in the signed preimage.
+#[derive(Clone, Debug)]
+pub struct AllocatedWideDataPackage<E: Engine, const NUM_LIMBS: usize> {
+    pub data_points: Vec<AllocatedWideDataPoint<E, NUM_LIMBS>>,
+    pub timestamp: [Byte<E>; super::TIMESTAMP_BS],
+    pub data_points_count: [Byte<E>; super::DATA_POINTS_COUNT_BS],
+    pub value_byte_size: [Byte<E>; super::DATA_POINT_VALUE_BYTE_SIZE_BS],
+}
+
+impl<E: Engine, const NUM_LIMBS: usize> AllocatedWideDataPackage<E, NUM_LIMBS> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: DataPackage,
+    ) -> Result<Self, SynthesisError> {
+        if witness.value_limbs != NUM_LIMBS {
+            return Err(new_synthesis_error(format!(
+                "expect values of {} limbs, got {} limbs",
+                NUM_LIMBS, witness.value_limbs
+            )));
+        }
+        let timestamp = {
+            let bytes = witness.serialize_timestamp().try_into().unwrap();
+            CSAllocatable::alloc_from_witness(cs, Some(bytes))?
+        };
+        // Fixed by the shape of circuit, as same as regular data packages
+        let data_points_count = {
+            let bytes: [u8; super::DATA_POINTS_COUNT_BS] =
+                witness.serialize_data_points_count().try_into().unwrap();
+            bytes.map(Byte::constant)
+        };
+        let value_byte_size = {
+            let bytes: [u8; super::DATA_POINT_VALUE_BYTE_SIZE_BS] = witness
+                .serialize_default_data_point_byte_size()
+                .try_into()
+                .unwrap();
+            bytes.map(Byte::constant)
+        };
+        let data_points = witness
+            .sorted_data_points()
+            .into_iter()
+            .map(|data_point| AllocatedWideDataPoint::from_witness(cs, data_point))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            data_points,
+            timestamp,
+            data_points_count,
+            value_byte_size,
+        })
+    }
+
+    pub fn serialize(&self) -> Result<Vec<Byte<E>>, SynthesisError> {
+        let mut bytes = vec![];
+        for data_point in self.data_points.iter() {
+            bytes.extend(data_point.serialize()?);
+        }
+        bytes.extend(self.timestamp);
+        bytes.extend(self.value_byte_size);
+        bytes.extend(self.data_points_count);
+        Ok(bytes)
+    }
+
+    /// Byte ranges of fields in [`Self::serialize`], which is the same as the native data package.
+    pub fn layout(&self) -> Vec<FieldLayout> {
+        preimage_layout_with_value_limbs(self.data_points.len(), NUM_LIMBS)
+    }
+
+    pub fn keccak256_hash<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<[Byte<E>; 32], SynthesisError> {
+        let bytes = self.serialize()?;
+        crate::gadgets::keccak256::digest(cs, &bytes)
+    }
+
+    /// Select value of the data point with the given feed id. Also returns whether it's found.
+    pub fn value_of<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        data_feed_id: &[Byte<E>; 32],
+    ) -> Result<(Boolean, WideValue<E, NUM_LIMBS>), SynthesisError> {
+        let expected_feed_id = UInt256::from_be_bytes_fixed(cs, data_feed_id)?;
+        let mut found = Boolean::constant(false);
+        let mut value = WideValue::zero();
+        for data_point in self.data_points.iter() {
+            let feed_id = UInt256::from_be_bytes_fixed(cs, &data_point.data_feed_id)?;
+            let is_matched = UInt256::equals(cs, &feed_id, &expected_feed_id)?;
+            let current_value = WideValue::from_be_bytes(cs, &data_point.value)?;
+            value = WideValue::conditionally_select(cs, &is_matched, &current_value, &value)?;
+            found = Boolean::or(cs, &found, &is_matched)?;
+        }
+        Ok((found, value))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AllocatedWideSignedDataPackage<E: Engine, const NUM_LIMBS: usize> {
+    pub data_package: AllocatedWideDataPackage<E, NUM_LIMBS>,
+    pub signature: Signature<E>,
+}
+
+impl<E: Engine, const NUM_LIMBS: usize> AllocatedWideSignedDataPackage<E, NUM_LIMBS> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        data_package: DataPackage,
+        signature: [u8; 65],
+    ) -> Result<Self, SynthesisError> {
+        let data_package = AllocatedWideDataPackage::from_witness(cs, data_package)?;
+        let mut signature = signature;
+        if signature[64] >= 27 {
+            signature[64] -= 27;
+        }
+        let signature = Signature::from_bytes_witness(cs, &signature)?;
+        Ok(Self {
+            data_package,
+            signature,
+        })
+    }
+
+    /// Check the package is signed by `guardian`. Also returns the keccak256 hash of the signed
+    /// payload.
+    pub fn check_by_address_with_payload_hash<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        guardian: &Address<E>,
+    ) -> Result<(Boolean, [Byte<E>; 32]), SynthesisError> {
+        let payload_hash = self.data_package.keccak256_hash(cs)?;
+        let msg_hash = UInt256::from_be_bytes_fixed(cs, &payload_hash)?;
+        let is_ok = check_signature(cs, &self.signature, &msg_hash, guardian)?;
+        Ok((is_ok, payload_hash))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte, franklin_crypto::bellman::SynthesisError,
+    };
+    use num_bigint::BigUint;
+
+    use super::{AllocatedWideSignedDataPackage, WideValue};
+    use crate::{
+        gadgets::ethereum::Address,
+        redstone::witness::{convert_string_to_bytes32, DataPackage, DataPoint},
+        utils::testing::{bytes_assert_eq, create_test_constraint_system, RedstoneSigner},
+    };
+
+    #[test]
+    fn test_wide_data_package() -> Result<(), SynthesisError> {
+        // 10^80 overflows 32 bytes once scaled by 10^8
+        let index = format!("1{}", "0".repeat(80));
+        let data_points = vec![DataPoint::new("IDX", &index), DataPoint::new("ETH", "1000")];
+        assert!(DataPackage::new(data_points.clone(), 1654353400000)
+            .validate()
+            .is_err());
+        let data_package = DataPackage::new(data_points, 1654353400000)
+            .with_value_limbs(2)
+            .unwrap();
+        let signer = RedstoneSigner::new(0x11);
+        let witness = signer.sign_witness(data_package.clone());
+
+        // Value byte size of the preimage and payload round trip
+        assert_eq!(
+            data_package.serialize_default_data_point_byte_size(),
+            [0, 0, 0, 64]
+        );
+        let payload = DataPackage::to_hex_payload(&[witness.clone()]).unwrap();
+        let parsed = DataPackage::from_hex_payload(payload).unwrap();
+        assert_eq!(parsed[0].package.value_limbs, 2);
+        assert_eq!(parsed[0].expected_signer, signer.address());
+
+        let cs = &mut create_test_constraint_system()?;
+        let guardian = Address::from_address_witness(cs, &signer.address())?;
+        let allocated = AllocatedWideSignedDataPackage::<_, 2>::from_witness(
+            cs,
+            data_package.clone(),
+            witness.signature,
+        )?;
+        assert_eq!(allocated.data_package.layout(), data_package.layout());
+        let (is_valid, payload_hash) =
+            allocated.check_by_address_with_payload_hash(cs, &guardian)?;
+        assert!(is_valid.get_value().unwrap());
+        bytes_assert_eq(&payload_hash, hex::encode(data_package.keccak256_hash()));
+
+        // Limb-aware arithmetic
+        let feed_id = convert_string_to_bytes32("IDX").map(Byte::constant);
+        let (found, value) = allocated.data_package.value_of(cs, &feed_id)?;
+        assert!(found.get_value().unwrap());
+        let expected = data_package.value_of("IDX").unwrap();
+        assert_eq!(value.get_value().unwrap(), expected);
+        let (sum, of) = value.add(cs, &value)?;
+        assert!(!of.get_value().unwrap());
+        assert_eq!(sum.get_value().unwrap(), &expected * 2u32);
+        let (diff, borrow) = sum.sub(cs, &value)?;
+        assert!(!borrow.get_value().unwrap());
+        let is_equal = WideValue::equals(cs, &diff, &value)?;
+        assert!(is_equal.get_value().unwrap());
+        let (_, borrow) = value.sub(cs, &sum)?;
+        assert!(borrow.get_value().unwrap());
+        let (fits, _) = value.narrow(cs)?;
+        assert!(!fits.get_value().unwrap());
+
+        let feed_id = convert_string_to_bytes32("ETH").map(Byte::constant);
+        let (_, value) = allocated.data_package.value_of(cs, &feed_id)?;
+        let (fits, value) = value.narrow(cs)?;
+        assert!(fits.get_value().unwrap());
+        assert_eq!(
+            value.get_value().unwrap(),
+            BigUint::from(1000u64) * BigUint::from(10u64).pow(8)
+        );
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+}
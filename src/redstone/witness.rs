@@ -1,14 +1,29 @@
-use std::{str::FromStr, usize};
+use std::{collections::BTreeMap, str::FromStr, usize};
 
 use bigdecimal::{BigDecimal, RoundingMode};
-use num_bigint::ToBigInt;
+use num_bigint::{BigInt, BigUint, Sign, ToBigInt};
+use secp256k1::{
+    ecdsa::{RecoverableSignature, RecoveryId},
+    Message,
+};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+
+use crate::{
+    gadgets::poseidon::poseidon_hash,
+    utils::{fr_from_biguint, serde_hex},
+};
 
 use super::{
-    DATA_POINTS_COUNT_BS, DATA_POINT_VALUE_BYTE_SIZE_BS, DEFAULT_NUM_VALUE_BS,
-    DEFAULT_NUM_VALUE_DECIMALS, TIMESTAMP_BS,
+    BLENDED_PRICE_VALUE_BITS, CROSS_PRICE_VALUE_BITS, DATA_PACKAGES_COUNT_BS, DATA_POINTS_COUNT_BS,
+    DATA_POINT_VALUE_BYTE_SIZE_BS, DEFAULT_NUM_VALUE_BS, DEFAULT_NUM_VALUE_DECIMALS,
+    MAX_CROSS_PRICE_DECIMALS, MAX_NUM_VALUE_LIMBS, REDSTONE_MARKER, SIGNATURE_BS, TIMESTAMP_BS,
+    UNSIGNED_METADATA_BYTE_SIZE_BS,
 };
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DataPoint {
     pub data_feed_id: String,
     pub value: String,
@@ -28,26 +43,76 @@ impl DataPoint {
             .to_vec()
     }
 
+    /// Check the value is a non-negative decimal fitting in the value byte size once scaled by
+    /// decimals. Values are arbitrarily large within that size, e.g. wider than `u64`.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        self.validate_with_value_limbs(1)
+    }
+
+    /// Same as [`Self::validate`], but the value is `value_limbs` 32-bytes limbs wide.
+    pub fn validate_with_value_limbs(&self, value_limbs: usize) -> anyhow::Result<()> {
+        try_convert_number_to_sized_bytes(
+            &self.value,
+            DEFAULT_NUM_VALUE_DECIMALS as u8,
+            value_limbs * DEFAULT_NUM_VALUE_BS,
+        )?;
+        Ok(())
+    }
+
     pub fn serialize_value(&self) -> Vec<u8> {
-        convert_number_to_bytes32(&self.value, DEFAULT_NUM_VALUE_DECIMALS as u8)
-            .as_slice()
-            .to_vec()
+        self.serialize_value_with_limbs(1)
+    }
+
+    /// Serialize the value into `value_limbs` 32-bytes limbs, the most significant limb first.
+    pub fn serialize_value_with_limbs(&self, value_limbs: usize) -> Vec<u8> {
+        try_convert_number_to_sized_bytes(
+            &self.value,
+            DEFAULT_NUM_VALUE_DECIMALS as u8,
+            value_limbs * DEFAULT_NUM_VALUE_BS,
+        )
+        .unwrap()
     }
 
     pub fn serialize(&self) -> Vec<u8> {
+        self.serialize_with_value_limbs(1)
+    }
+
+    pub fn serialize_with_value_limbs(&self, value_limbs: usize) -> Vec<u8> {
         let mut bytes = vec![];
         let bytes_data_feed_id = self.serialize_feed_id();
-        let bytes_value = self.serialize_value();
+        let bytes_value = self.serialize_value_with_limbs(value_limbs);
         bytes.extend(bytes_data_feed_id);
         bytes.extend(bytes_value);
         bytes
     }
 }
 
-#[derive(Clone, Debug)]
+fn default_value_limbs() -> usize {
+    1
+}
+
+fn is_default_value_limbs(value_limbs: &usize) -> bool {
+    *value_limbs == default_value_limbs()
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DataPackage {
     pub data_points: Vec<DataPoint>,
     pub timestamp: u64,
+    /// RedStone data service publishing the package, e.g. `redstone-primary-prod`. It is metadata
+    /// for auditing and not signed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_service_id: Option<String>,
+    /// Human-readable name of the signer. It is metadata for auditing and not signed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signer_name: Option<String>,
+    /// Number of 32-bytes limbs of every value, which is more than one for derived feeds wider
+    /// than 32 bytes. The value byte size in the signed preimage is `value_limbs * 32`.
+    #[serde(
+        default = "default_value_limbs",
+        skip_serializing_if = "is_default_value_limbs"
+    )]
+    pub value_limbs: usize,
 }
 
 impl DataPackage {
@@ -55,9 +120,57 @@ impl DataPackage {
         Self {
             data_points,
             timestamp,
+            data_service_id: None,
+            signer_name: None,
+            value_limbs: default_value_limbs(),
         }
     }
 
+    /// Serialize values into `value_limbs` 32-bytes limbs, which is at most
+    /// [`MAX_NUM_VALUE_LIMBS`].
+    pub fn with_value_limbs(mut self, value_limbs: usize) -> anyhow::Result<Self> {
+        if value_limbs == 0 || value_limbs > MAX_NUM_VALUE_LIMBS {
+            anyhow::bail!(
+                "invalid value limbs {}, expect 1 to {}",
+                value_limbs,
+                MAX_NUM_VALUE_LIMBS
+            )
+        }
+        self.value_limbs = value_limbs;
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Byte size of every value in the signed preimage.
+    pub fn value_byte_size(&self) -> usize {
+        self.value_limbs * DEFAULT_NUM_VALUE_BS
+    }
+
+    /// Check every data point is valid and its value fits in the value byte size.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for data_point in self.data_points.iter() {
+            data_point.validate_with_value_limbs(self.value_limbs)?;
+        }
+        Ok(())
+    }
+
+    /// Attach the data service id and signer name, which don't change the signed payload.
+    pub fn with_metadata<T: ToString, U: ToString>(
+        mut self,
+        data_service_id: T,
+        signer_name: U,
+    ) -> Self {
+        self.data_service_id = Some(data_service_id.to_string());
+        self.signer_name = Some(signer_name.to_string());
+        self
+    }
+
+    /// Whether the package is a heartbeat, which has no data points and only attests a fresh
+    /// timestamp of its signer.
+    pub fn is_heartbeat(&self) -> bool {
+        self.data_points.is_empty()
+    }
+
     pub fn serialize(&self) -> Vec<u8> {
         let mut bytes = vec![];
         bytes.extend(self.serialize_data_points());
@@ -77,11 +190,21 @@ impl DataPackage {
         data_points
     }
 
+    /// Returns serialized value of the data point with the given feed id.
+    pub fn value_of(&self, data_feed_id: &str) -> Option<BigUint> {
+        self.data_points
+            .iter()
+            .find(|data_point| data_point.data_feed_id == data_feed_id)
+            .map(|data_point| {
+                BigUint::from_bytes_be(&data_point.serialize_value_with_limbs(self.value_limbs))
+            })
+    }
+
     pub fn serialize_data_points(&self) -> Vec<u8> {
         let data_points = self.sorted_data_points();
         let mut bytes = vec![];
         for data_point in data_points.iter() {
-            bytes.extend(data_point.serialize());
+            bytes.extend(data_point.serialize_with_value_limbs(self.value_limbs));
         }
         bytes
     }
@@ -99,10 +222,491 @@ impl DataPackage {
     }
 
     pub fn serialize_default_data_point_byte_size(&self) -> Vec<u8> {
-        convert_number_to_bytes::<_, DATA_POINT_VALUE_BYTE_SIZE_BS>(DEFAULT_NUM_VALUE_BS, 0)
+        convert_number_to_bytes::<_, DATA_POINT_VALUE_BYTE_SIZE_BS>(self.value_byte_size(), 0)
             .as_slice()
             .to_vec()
     }
+
+    /// Byte ranges of fields in the serialized data package, see [`preimage_layout`].
+    pub fn layout(&self) -> Vec<FieldLayout> {
+        preimage_layout_with_value_limbs(self.data_points.len(), self.value_limbs)
+    }
+
+    /// Keccak256 hash of the serialized data package, which is the message signed by signers.
+    pub fn keccak256_hash(&self) -> [u8; 32] {
+        Keccak256::new_with_prefix(self.serialize())
+            .finalize()
+            .into()
+    }
+
+    /// Recover the address of the signer from a 65-bytes signature `r || s || v`.
+    pub fn recover_signer(&self, signature: &[u8; SIGNATURE_BS]) -> anyhow::Result<[u8; 20]> {
+        recover_signer(&self.keccak256_hash(), signature)
+    }
+
+    /// Parse hex-encoded RedStone EVM payload into signed data packages, whose expected signers are
+    /// the recovered ones.
+    ///
+    /// The payload layout is `data packages || data packages count || unsigned metadata ||
+    /// unsigned metadata byte size || redstone marker`, and each data package is
+    /// `data points || timestamp || data point value byte size || data points count || signature`.
+    /// See [redstone-protocol](https://github.com/redstone-finance/redstone-oracles-monorepo/tree/main/packages/protocol) for more.
+    pub fn from_hex_payload<T: AsRef<str>>(
+        payload: T,
+    ) -> anyhow::Result<Vec<SignedPackageWitness>> {
+        let payload = payload.as_ref();
+        let payload = hex::decode(payload.strip_prefix("0x").unwrap_or(payload))?;
+        let mut reader = PayloadReader::new(&payload);
+
+        let marker = reader.read_back(REDSTONE_MARKER.len())?;
+        if marker != REDSTONE_MARKER {
+            anyhow::bail!("invalid redstone marker {}", hex::encode(marker))
+        }
+        let unsigned_metadata_byte_size =
+            reader.read_back_number(UNSIGNED_METADATA_BYTE_SIZE_BS)?;
+        reader.read_back(unsigned_metadata_byte_size)?;
+        let data_packages_count = reader.read_back_number(DATA_PACKAGES_COUNT_BS)?;
+
+        let mut signed_data_packages = vec![];
+        for _ in 0..data_packages_count {
+            let signature: [u8; SIGNATURE_BS] = reader.read_back(SIGNATURE_BS)?.try_into()?;
+            let data_points_count = reader.read_back_number(DATA_POINTS_COUNT_BS)?;
+            let value_byte_size = reader.read_back_number(DATA_POINT_VALUE_BYTE_SIZE_BS)?;
+            if value_byte_size == 0
+                || value_byte_size % DEFAULT_NUM_VALUE_BS != 0
+                || value_byte_size > MAX_NUM_VALUE_LIMBS * DEFAULT_NUM_VALUE_BS
+            {
+                anyhow::bail!(
+                    "unsupported data point value byte size {}, expect a multiple of {} up to {}",
+                    value_byte_size,
+                    DEFAULT_NUM_VALUE_BS,
+                    MAX_NUM_VALUE_LIMBS * DEFAULT_NUM_VALUE_BS
+                )
+            }
+            let timestamp = reader.read_back_number(TIMESTAMP_BS)? as u64;
+            let mut data_points = vec![];
+            for _ in 0..data_points_count {
+                let value = reader.read_back(value_byte_size)?;
+                let data_feed_id = reader.read_back(32)?;
+                data_points.push(DataPoint::new(
+                    convert_bytes32_to_string(data_feed_id)?,
+                    convert_bytes_to_number(value, DEFAULT_NUM_VALUE_DECIMALS as u8),
+                ));
+            }
+            data_points.reverse();
+
+            let data_package = DataPackage::new(data_points, timestamp)
+                .with_value_limbs(value_byte_size / DEFAULT_NUM_VALUE_BS)?;
+            let signer = data_package.recover_signer(&signature)?;
+            signed_data_packages.push(SignedPackageWitness::new(data_package, signature, signer));
+        }
+        if !reader.is_empty() {
+            anyhow::bail!("{} unexpected bytes left in payload", reader.remaining())
+        }
+        signed_data_packages.reverse();
+        Ok(signed_data_packages)
+    }
+
+    /// Serialize signed data packages into hex-encoded RedStone EVM payload without unsigned
+    /// metadata, which is the inverse of [`Self::from_hex_payload`].
+    pub fn to_hex_payload(signed_data_packages: &[SignedPackageWitness]) -> anyhow::Result<String> {
+        let mut payload = vec![];
+        for witness in signed_data_packages.iter() {
+            payload.extend(witness.package.serialize());
+            payload.extend(witness.signature);
+        }
+        payload.extend(try_convert_number_to_bytes::<_, DATA_PACKAGES_COUNT_BS>(
+            signed_data_packages.len(),
+            0,
+        )?);
+        payload.extend([0u8; UNSIGNED_METADATA_BYTE_SIZE_BS]);
+        payload.extend(REDSTONE_MARKER);
+        Ok(hex::encode(payload))
+    }
+}
+
+// Version of the wire format of `SignedPackageWitness`
+pub const SIGNED_PACKAGE_WITNESS_VERSION: u32 = 1;
+// Provider of data packages if not specified
+pub const DEFAULT_PROVIDER: &str = "redstone-primary-prod";
+
+/// Metadata of a verified package, which lets prover operators log and audit contributors of a
+/// proof.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackageMetadata {
+    pub timestamp: u64,
+    #[serde(with = "serde_hex")]
+    pub signer: [u8; 20],
+    pub provider: String,
+    pub data_service_id: Option<String>,
+    pub signer_name: Option<String>,
+}
+
+/// Data package together with its signature and the signer expected to sign it, which is the
+/// witness of circuits and the wire format exchanged with integrations.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignedPackageWitness {
+    pub version: u32,
+    pub package: DataPackage,
+    #[serde(with = "serde_hex")]
+    pub signature: [u8; SIGNATURE_BS],
+    #[serde(with = "serde_hex")]
+    pub expected_signer: [u8; 20],
+    pub provider: String,
+    /// Free-form hints for integrations, which are not used by circuits.
+    #[serde(default)]
+    pub hints: BTreeMap<String, String>,
+}
+
+impl SignedPackageWitness {
+    pub fn new(
+        package: DataPackage,
+        signature: [u8; SIGNATURE_BS],
+        expected_signer: [u8; 20],
+    ) -> Self {
+        Self {
+            version: SIGNED_PACKAGE_WITNESS_VERSION,
+            package,
+            signature,
+            expected_signer,
+            provider: DEFAULT_PROVIDER.to_string(),
+            hints: BTreeMap::new(),
+        }
+    }
+
+    /// Check the witness is of supported version, and signed by `guardian` as expected.
+    pub fn validate(&self, guardian: &[u8; 20]) -> anyhow::Result<()> {
+        if self.version != SIGNED_PACKAGE_WITNESS_VERSION {
+            anyhow::bail!(
+                "unsupported signed package witness version {}, expect {}",
+                self.version,
+                SIGNED_PACKAGE_WITNESS_VERSION
+            )
+        }
+        self.package.validate()?;
+        if &self.expected_signer != guardian {
+            anyhow::bail!(
+                "expected signer 0x{} does not match guardian 0x{}",
+                hex::encode(self.expected_signer),
+                hex::encode(guardian)
+            )
+        }
+        let signer = self.package.recover_signer(&self.signature)?;
+        if signer != self.expected_signer {
+            anyhow::bail!(
+                "data package at {} is signed by 0x{} rather than 0x{}",
+                self.package.timestamp,
+                hex::encode(signer),
+                hex::encode(self.expected_signer)
+            )
+        }
+        Ok(())
+    }
+
+    pub fn metadata(&self) -> PackageMetadata {
+        PackageMetadata {
+            timestamp: self.package.timestamp,
+            signer: self.expected_signer,
+            provider: self.provider.clone(),
+            data_service_id: self.package.data_service_id.clone(),
+            signer_name: self.package.signer_name.clone(),
+        }
+    }
+
+    /// Nullifier of the package in the same way as circuit, which is
+    /// `poseidon(signer, timestamp, feed ids)` where each feed id is split into two 16-bytes limbs.
+    pub fn nullifier<E: Engine>(&self) -> anyhow::Result<E::Fr> {
+        let mut members = vec![
+            fr_from_biguint::<E>(&BigUint::from_bytes_be(&self.expected_signer))?,
+            fr_from_biguint::<E>(&BigUint::from(self.package.timestamp))?,
+        ];
+        for data_point in self.package.sorted_data_points() {
+            let feed_id = data_point.serialize_feed_id();
+            members.push(fr_from_biguint::<E>(&BigUint::from_bytes_be(
+                &feed_id[..16],
+            ))?);
+            members.push(fr_from_biguint::<E>(&BigUint::from_bytes_be(
+                &feed_id[16..],
+            ))?);
+        }
+        Ok(poseidon_hash::<E>(&members))
+    }
+}
+
+/// Reads a RedStone payload from the back, which is how the payload is designed to be parsed.
+struct PayloadReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> PayloadReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    fn read_back(&mut self, len: usize) -> anyhow::Result<&'a [u8]> {
+        if len > self.bytes.len() {
+            anyhow::bail!(
+                "payload too short: read {} bytes but only {} left",
+                len,
+                self.bytes.len()
+            )
+        }
+        let (rest, read) = self.bytes.split_at(self.bytes.len() - len);
+        self.bytes = rest;
+        Ok(read)
+    }
+
+    fn read_back_number(&mut self, len: usize) -> anyhow::Result<usize> {
+        let bytes = self.read_back(len)?;
+        Ok(bytes.iter().fold(0usize, |acc, b| (acc << 8) | *b as usize))
+    }
+
+    fn remaining(&self) -> usize {
+        self.bytes.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+}
+
+/// Field of the signed preimage of a data package. Data points are indexed in the sorted order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PreimageField {
+    FeedId(usize),
+    Value(usize),
+    Timestamp,
+    ValueByteSize,
+    DataPointsCount,
+}
+
+/// Byte range `offset..offset + len` of a field in the signed preimage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldLayout {
+    pub field: PreimageField,
+    pub offset: usize,
+    pub len: usize,
+}
+
+/// Layout of the signed preimage of a data package with `num_data_points` data points, which is
+/// `(feed id || value) * num_data_points || timestamp || value byte size || data points count`.
+/// It is shared by native and circuit data packages, so that integrators can check both of them
+/// serialize exactly what signers signed.
+pub fn preimage_layout(num_data_points: usize) -> Vec<FieldLayout> {
+    preimage_layout_with_value_limbs(num_data_points, 1)
+}
+
+/// Same as [`preimage_layout`], but every value is `value_limbs` 32-bytes limbs wide.
+pub fn preimage_layout_with_value_limbs(
+    num_data_points: usize,
+    value_limbs: usize,
+) -> Vec<FieldLayout> {
+    let mut fields = vec![];
+    for i in 0..num_data_points {
+        fields.push((PreimageField::FeedId(i), 32));
+        fields.push((PreimageField::Value(i), value_limbs * DEFAULT_NUM_VALUE_BS));
+    }
+    fields.push((PreimageField::Timestamp, TIMESTAMP_BS));
+    fields.push((PreimageField::ValueByteSize, DATA_POINT_VALUE_BYTE_SIZE_BS));
+    fields.push((PreimageField::DataPointsCount, DATA_POINTS_COUNT_BS));
+
+    let mut offset = 0;
+    fields
+        .into_iter()
+        .map(|(field, len)| {
+            let layout = FieldLayout { field, offset, len };
+            offset += len;
+            layout
+        })
+        .collect()
+}
+
+/// Hash of guardian set, which is `poseidon(guardians)` where each address is a field element.
+pub fn guardian_set_hash<E: Engine>(guardians: &[[u8; 20]]) -> anyhow::Result<E::Fr> {
+    let input = guardians
+        .iter()
+        .map(|g| fr_from_biguint::<E>(&BigUint::from_bytes_be(g)))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(poseidon_hash::<E>(&input))
+}
+
+/// Recover the address of the signer of the 32-bytes message hash from a 65-bytes signature
+/// `r || s || v`.
+pub fn recover_signer(hash: &[u8; 32], signature: &[u8; SIGNATURE_BS]) -> anyhow::Result<[u8; 20]> {
+    // Ethereum adds 27 to the recovery id to get the v value
+    let v = if signature[64] >= 27 {
+        signature[64] - 27
+    } else {
+        signature[64]
+    };
+    let recid = RecoveryId::from_i32(v as i32)?;
+    let signature = RecoverableSignature::from_compact(&signature[..64], recid)?;
+    let message = Message::from_digest_slice(hash)?;
+    let pubkey = signature.recover(&message)?;
+    let hash: [u8; 32] = Keccak256::new_with_prefix(&pubkey.serialize_uncompressed()[1..])
+        .finalize()
+        .into();
+    Ok(hash[12..].try_into()?)
+}
+
+/// Check timestamp (in milliseconds) is neither `max_delay` older nor `max_ahead` newer than
+/// `reference_time` (in milliseconds), in the same way as circuit.
+pub fn is_timestamp_fresh(
+    timestamp: u64,
+    reference_time: u64,
+    max_delay: u64,
+    max_ahead: u64,
+) -> bool {
+    timestamp + max_delay >= reference_time && timestamp <= reference_time + max_ahead
+}
+
+/// Check data package is fresh against `reference_time` in the same way as circuit, where the max
+/// delay of every feed in `max_delays` contained by the package applies, or `default_max_delay` if
+/// it contains none of them.
+pub fn is_package_fresh(
+    package: &DataPackage,
+    reference_time: u64,
+    max_delays: &[(String, u64)],
+    default_max_delay: u64,
+    max_ahead: u64,
+) -> bool {
+    let mut contains_any = false;
+    for (feed_id, max_delay) in max_delays.iter() {
+        if package.value_of(feed_id).is_some() {
+            contains_any = true;
+            if !is_timestamp_fresh(package.timestamp, reference_time, *max_delay, max_ahead) {
+                return false;
+            }
+        }
+    }
+    contains_any
+        || is_timestamp_fresh(
+            package.timestamp,
+            reference_time,
+            default_max_delay,
+            max_ahead,
+        )
+}
+
+/// Select the latest value of every feed in the same way as circuit, see
+/// [`super::circuit::select_latest_values`]. Returns `None` if no round of some feed is selected.
+pub fn select_latest_values<T: Clone>(
+    packages: &[Vec<(u64, Vec<bool>)>],
+    rounds: &[Vec<T>],
+) -> Option<(u64, Vec<T>)> {
+    let num_feeds = rounds.first()?.len();
+    let max_timestamp = packages.iter().flatten().map(|(t, _)| *t).max()?;
+    let mut latest_timestamps = vec![];
+    let mut latest_values = vec![];
+    for i in 0..num_feeds {
+        let latest_timestamp = packages
+            .iter()
+            .flatten()
+            .filter(|(_, contains)| contains[i])
+            .map(|(t, _)| *t)
+            .max()?;
+        let (_, values) = packages
+            .iter()
+            .zip(rounds.iter())
+            .rev()
+            .find(|(round, _)| {
+                round.iter().any(|(_, contains)| contains[i])
+                    && round
+                        .iter()
+                        .all(|(t, contains)| !contains[i] || *t == latest_timestamp)
+            })?;
+        latest_timestamps.push(latest_timestamp);
+        latest_values.push(values[i].clone());
+    }
+    let earliest_timestamp = latest_timestamps.into_iter().min().unwrap_or(max_timestamp);
+    Some((earliest_timestamp, latest_values))
+}
+
+/// Rescale `value` from `decimals` to `target_decimals` in the same way as circuit, rounding down
+/// if the precision is reduced.
+pub fn normalize_value(value: &BigUint, decimals: usize, target_decimals: usize) -> BigUint {
+    if target_decimals >= decimals {
+        value * BigUint::from(10u32).pow((target_decimals - decimals) as u32)
+    } else {
+        value / BigUint::from(10u32).pow((decimals - target_decimals) as u32)
+    }
+}
+
+/// Blend prices of the same asset from two oracles in the same way as circuit. Returns the
+/// midpoint rounded down if they deviate by at most `tolerance_bps` basis points of the lower one,
+/// which is `|a - b| * 10^4 <= tolerance_bps * min(a, b)`, or `None` otherwise or if prices exceed
+/// [`BLENDED_PRICE_VALUE_BITS`] bits.
+pub fn blend_within_tolerance(a: &BigUint, b: &BigUint, tolerance_bps: u64) -> Option<BigUint> {
+    if a.bits() as usize > BLENDED_PRICE_VALUE_BITS || b.bits() as usize > BLENDED_PRICE_VALUE_BITS
+    {
+        return None;
+    }
+    let (lower, upper) = if a <= b { (a, b) } else { (b, a) };
+    if (upper - lower) * 10_000u32 > lower * tolerance_bps {
+        return None;
+    }
+    Some(lower + ((upper - lower) >> 1u32))
+}
+
+/// Output commitment in the same way as circuit, which is
+/// `poseidon(triples[0] || triples[1] || ... || outputs)` where each triple is
+/// `(feed_id, price, publish_time)`.
+pub fn output_commitment<E: Engine>(triples: &[[E::Fr; 3]], outputs: &[E::Fr]) -> E::Fr {
+    let mut members = triples.iter().flatten().copied().collect::<Vec<_>>();
+    members.extend_from_slice(outputs);
+    poseidon_hash::<E>(&members)
+}
+
+/// Rounding mode of derived prices.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Rounding {
+    Down,
+    Up,
+    /// Round half up
+    Nearest,
+}
+
+/// Derive the cross price `base / quote` with `decimals` in the same way as circuit, e.g. ETH/BTC
+/// from ETH/USD and BTC/USD, where `base` and `quote` share the same decimals. Returns `None` if
+/// `quote` is zero, or inputs exceed [`CROSS_PRICE_VALUE_BITS`] bits, or `decimals` exceeds
+/// [`MAX_CROSS_PRICE_DECIMALS`].
+pub fn cross_price(
+    base: &BigUint,
+    quote: &BigUint,
+    decimals: usize,
+    rounding: Rounding,
+) -> Option<BigUint> {
+    if quote.bits() == 0
+        || base.bits() > CROSS_PRICE_VALUE_BITS as u64
+        || quote.bits() > CROSS_PRICE_VALUE_BITS as u64
+        || decimals > MAX_CROSS_PRICE_DECIMALS
+    {
+        return None;
+    }
+    let scaled = base * BigUint::from(10u32).pow(decimals as u32);
+    let (quotient, remainder) = (&scaled / quote, &scaled % quote);
+    let round_up = match rounding {
+        Rounding::Down => false,
+        Rounding::Up => remainder.bits() > 0,
+        Rounding::Nearest => remainder * 2u32 >= *quote,
+    };
+    Some(if round_up { quotient + 1u32 } else { quotient })
+}
+
+/// Compute median of values in the same way as circuit, where the average of two middle values
+/// (rounded down) is used if the number of values is even.
+pub fn median(values: &[BigUint]) -> Option<BigUint> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort();
+    let mid = sorted.len() / 2;
+    if sorted.len() % 2 == 1 {
+        Some(sorted[mid].clone())
+    } else {
+        Some((&sorted[mid - 1] + &sorted[mid]) >> 1u32)
+    }
 }
 
 type Bytes32 = [u8; 32];
@@ -118,30 +722,139 @@ pub fn convert_string_to_bytes32<T: ToString>(str: T) -> Bytes32 {
     ret
 }
 
+/// Feed id as a short ASCII ticker (e.g. `ETH`), which is left-aligned and padded with zeros to
+/// 32 bytes per RedStone convention. As circuits enforce the shape of feed ids, a compact feed id
+/// is matched by its significant bytes only, i.e. the ticker and the first padding byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CompactFeedId {
+    bytes: Bytes32,
+    len: usize,
+}
+
+impl CompactFeedId {
+    pub const MAX_LEN: usize = 31;
+
+    pub fn new(ticker: &str) -> anyhow::Result<Self> {
+        if ticker.is_empty() || ticker.len() > Self::MAX_LEN {
+            anyhow::bail!(
+                "feed id {:?} must have 1 to {} bytes",
+                ticker,
+                Self::MAX_LEN
+            )
+        }
+        if !ticker.bytes().all(|b| b.is_ascii() && b != 0) {
+            anyhow::bail!("feed id {:?} is not ASCII without NUL", ticker)
+        }
+        Ok(Self {
+            bytes: convert_string_to_bytes32(ticker),
+            len: ticker.len(),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn as_str(&self) -> &str {
+        // Checked to be ASCII when constructed
+        std::str::from_utf8(&self.bytes[..self.len]).unwrap()
+    }
+
+    /// Bytes of ticker followed by the first padding byte.
+    pub fn significant_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len + 1]
+    }
+
+    pub fn to_bytes32(&self) -> Bytes32 {
+        self.bytes
+    }
+}
+
+impl FromStr for CompactFeedId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s)
+    }
+}
+
+pub fn convert_bytes32_to_string(bytes: &[u8]) -> anyhow::Result<String> {
+    let len = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    Ok(String::from_utf8(bytes[..len].to_vec())?)
+}
+
+pub fn convert_bytes_to_number(bytes: &[u8], decimals: u8) -> String {
+    let number = BigInt::from_biguint(Sign::Plus, BigUint::from_bytes_be(bytes));
+    BigDecimal::new(number, decimals as i64).to_string()
+}
+
 pub fn convert_number_to_bytes32<T: ToString>(number: T, decimals: u8) -> Bytes32 {
     convert_number_to_bytes::<T, 32>(number, decimals)
 }
 
+/// Panics if `number` is not a valid non-negative decimal or does not fit in `N` bytes, see
+/// [`try_convert_number_to_bytes`].
 pub fn convert_number_to_bytes<T: ToString, const N: usize>(number: T, decimals: u8) -> [u8; N] {
-    let number = BigDecimal::from_str(&number.to_string())
-        .unwrap()
-        .with_scale_round(decimals as i64, RoundingMode::Down);
-    let number = {
-        let number = number * 10u64.checked_pow(decimals.into()).unwrap();
-        let bigint = number.to_bigint().unwrap();
-        bigint.to_biguint().unwrap()
-    };
-    let bytes = number.to_bytes_be();
-    let mut ret = [0u8; N];
-    ret[(N - bytes.len())..].copy_from_slice(bytes.as_slice());
-    ret
+    try_convert_number_to_bytes(number, decimals).unwrap()
+}
+
+/// Convert decimal `number` to `N` big-endian bytes after scaling it by `10^decimals` and rounding
+/// down. `number` is arbitrarily large as long as the scaled value fits in `N` bytes.
+pub fn try_convert_number_to_bytes<T: ToString, const N: usize>(
+    number: T,
+    decimals: u8,
+) -> anyhow::Result<[u8; N]> {
+    Ok(try_convert_number_to_sized_bytes(number, decimals, N)?
+        .try_into()
+        .unwrap())
+}
+
+/// Same as [`try_convert_number_to_bytes`], but the byte size is known at runtime.
+pub fn try_convert_number_to_sized_bytes<T: ToString>(
+    number: T,
+    decimals: u8,
+    size: usize,
+) -> anyhow::Result<Vec<u8>> {
+    let number = number.to_string();
+    let scaled = BigDecimal::from_str(&number)?
+        .with_scale_round(decimals as i64, RoundingMode::Down)
+        * BigDecimal::from(BigInt::from(10u32).pow(decimals as u32));
+    let scaled = scaled
+        .to_bigint()
+        .and_then(|bigint| bigint.to_biguint())
+        .ok_or_else(|| anyhow::anyhow!("number {} is negative", number))?;
+    let bytes = scaled.to_bytes_be();
+    if bytes.len() > size {
+        anyhow::bail!(
+            "number {} with {} decimals exceeds {} bytes",
+            number,
+            decimals,
+            size
+        )
+    }
+    let mut ret = vec![0u8; size];
+    ret[(size - bytes.len())..].copy_from_slice(bytes.as_slice());
+    Ok(ret)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::redstone::witness::{
-        convert_number_to_bytes32, convert_string_to_bytes32, DataPackage, DataPoint,
+    use crate::{
+        redstone::{
+            witness::{
+                convert_number_to_bytes32, convert_string_to_bytes32, try_convert_number_to_bytes,
+                CompactFeedId, DataPackage, DataPoint, SignedPackageWitness,
+                SIGNED_PACKAGE_WITNESS_VERSION,
+            },
+            REDSTONE_MARKER,
+        },
+        utils::testing::{arb_data_package, RedstoneSigner},
     };
+    use proptest::prelude::*;
 
     #[test]
     fn test_convert_number_to_bytes() -> anyhow::Result<()> {
@@ -153,6 +866,31 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_convert_large_number_to_bytes() -> anyhow::Result<()> {
+        // (2^256 - 1) / 10^8, which is the largest value with 8 decimals
+        let max = "1157920892373161954235709850086879078532699846656405640394575840079131.29639935";
+        let bytes = convert_number_to_bytes32(max, 8);
+        assert_eq!(hex::encode(bytes), "ff".repeat(32));
+        let data_point = DataPoint::new("BTC", max);
+        data_point.validate()?;
+        assert_eq!(data_point.serialize_value(), bytes);
+
+        // Wider than u64 and u128
+        let bytes = convert_number_to_bytes32("340282366920938463463374607431768211456", 8);
+        assert_eq!(
+            hex::encode(bytes),
+            "00000000000000000000000005f5e10000000000000000000000000000000000"
+        );
+
+        let overflow =
+            "1157920892373161954235709850086879078532699846656405640394575840079131.29639936";
+        assert!(try_convert_number_to_bytes::<_, 32>(overflow, 8).is_err());
+        assert!(DataPoint::new("BTC", overflow).validate().is_err());
+        assert!(try_convert_number_to_bytes::<_, 32>("-1", 8).is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_convert_string_to_bytes32() -> anyhow::Result<()> {
         let bytes = convert_string_to_bytes32("BTC");
@@ -163,6 +901,19 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_compact_feed_id() -> anyhow::Result<()> {
+        let feed_id: CompactFeedId = "BTC".parse()?;
+        assert_eq!(feed_id.as_str(), "BTC");
+        assert_eq!(feed_id.to_bytes32(), convert_string_to_bytes32("BTC"));
+        assert_eq!(hex::encode(feed_id.significant_bytes()), "42544300");
+        assert!(CompactFeedId::new(&"A".repeat(31)).is_ok());
+        for invalid in ["", "B\0C", "€", &"A".repeat(32)] {
+            assert!(CompactFeedId::new(invalid).is_err());
+        }
+        Ok(())
+    }
+
     #[test]
     // Case reference:
     // https://github.com/redstone-finance/redstone-oracles-monorepo/blob/cd0a6ffffbfcb1fb3dbf255b9d599db26e3faf34/packages/protocol/test/plain-obj-serialization.test.ts#L86
@@ -251,4 +1002,178 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_from_hex_payload() -> anyhow::Result<()> {
+        use base64::prelude::*;
+        use secp256k1::{Secp256k1, SecretKey};
+        use sha3::{Digest, Keccak256};
+
+        let data_package = DataPackage::new(
+            vec![
+                DataPoint::new("BTC", "20000"),
+                DataPoint::new("ETH", "1000"),
+            ],
+            1654353400000u64,
+        );
+        let signature: [u8; 65] = BASE64_STANDARD
+            .decode("NX5yd/Cs8HzVdNchrM59uOoSst7n9KK5Ou9pA6S5GTM0RwghGlFjA0S+SVfb85ipg4HzUTKATBZSqPXlWldEEhw=")?
+            .try_into()
+            .unwrap();
+        let mut payload = data_package.serialize();
+        payload.extend(signature);
+        payload.extend([0x00, 0x01]); // data packages count
+        payload.extend([0x00, 0x00, 0x00]); // unsigned metadata byte size
+        payload.extend(REDSTONE_MARKER);
+
+        let signed_data_packages = DataPackage::from_hex_payload(hex::encode(&payload))?;
+        assert_eq!(signed_data_packages.len(), 1);
+        let parsed = &signed_data_packages[0];
+        assert_eq!(parsed.package.serialize(), data_package.serialize());
+        assert_eq!(parsed.package.timestamp, 1654353400000u64);
+        assert_eq!(parsed.package.data_points[0].data_feed_id, "BTC");
+        assert_eq!(parsed.signature, signature);
+
+        let expected_signer = {
+            let seckey = SecretKey::from_slice(
+                &hex::decode("1111111111111111111111111111111111111111111111111111111111111111")
+                    .unwrap(),
+            )?;
+            let pubkey = seckey.public_key(&Secp256k1::new());
+            let hash = Keccak256::new_with_prefix(&pubkey.serialize_uncompressed()[1..]).finalize();
+            hash[12..].to_vec()
+        };
+        assert_eq!(parsed.expected_signer.to_vec(), expected_signer);
+        parsed.validate(&parsed.expected_signer)?;
+
+        // Payload without marker is rejected
+        assert!(DataPackage::from_hex_payload(hex::encode(&payload[..payload.len() - 1])).is_err());
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn test_payload_round_trip(data_package in arb_data_package(64)) {
+            let signer = RedstoneSigner::new(0x11);
+            let (signature, address) = signer.sign(&data_package);
+            let serialized = data_package.serialize();
+            let layout = data_package.layout();
+            let last = layout.last().unwrap();
+            prop_assert_eq!(last.offset + last.len, serialized.len());
+
+            let mut payload = serialized.clone();
+            payload.extend(signature);
+            payload.extend([0x00, 0x01]); // data packages count
+            payload.extend([0x00, 0x00, 0x00]); // unsigned metadata byte size
+            payload.extend(REDSTONE_MARKER);
+            let signed_data_packages = DataPackage::from_hex_payload(hex::encode(&payload)).unwrap();
+            prop_assert_eq!(
+                DataPackage::to_hex_payload(&signed_data_packages).unwrap(),
+                hex::encode(&payload)
+            );
+            prop_assert_eq!(signed_data_packages.len(), 1);
+            let parsed = &signed_data_packages[0];
+            prop_assert_eq!(parsed.package.serialize(), serialized);
+            prop_assert_eq!(parsed.package.timestamp, data_package.timestamp);
+            prop_assert_eq!(parsed.expected_signer, address);
+            for data_point in parsed.package.data_points.iter() {
+                prop_assert_eq!(
+                    data_package.value_of(&data_point.data_feed_id),
+                    Some(num_bigint::BigUint::from_bytes_be(&data_point.serialize_value()))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_heartbeat() -> anyhow::Result<()> {
+        let data_package = DataPackage::new(vec![], 1654353400000u64);
+        assert!(data_package.is_heartbeat());
+        assert_eq!(
+            hex::encode(data_package.serialize()),
+            "01812f2590c000000020000000"
+        );
+
+        let (signature, _) = RedstoneSigner::new(0x11).sign(&data_package);
+        let mut payload = data_package.serialize();
+        payload.extend(signature);
+        payload.extend([0x00, 0x01]); // data packages count
+        payload.extend([0x00, 0x00, 0x00]); // unsigned metadata byte size
+        payload.extend(REDSTONE_MARKER);
+
+        let signed_data_packages = DataPackage::from_hex_payload(hex::encode(&payload))?;
+        assert_eq!(signed_data_packages.len(), 1);
+        let parsed = &signed_data_packages[0];
+        assert!(parsed.package.is_heartbeat());
+        assert_eq!(parsed.package.timestamp, 1654353400000u64);
+        parsed.validate(&parsed.expected_signer)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_signed_package_witness_serde() -> anyhow::Result<()> {
+        let data_package = DataPackage::new(
+            vec![DataPoint::new("AVAX", "36.2488073814028")],
+            1705311690000,
+        );
+        let signature: [u8; 65] = hex::decode("9ad1f96c083cf31f757b33b0ef6b2c4279589bf0489c1c3a7beb0005d2080dd233aaae60fdafee196362ed5b6af7498e7ba07eaa725f0bc5a041016ce54a67d61b").unwrap().try_into().unwrap();
+        let signer: [u8; 20] = hex::decode("109B4a318A4F5ddcbCA6349B45f881B4137deaFB")
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let witness = SignedPackageWitness::new(data_package, signature, signer);
+        witness.validate(&signer)?;
+        assert!(witness.validate(&[0u8; 20]).is_err());
+
+        let json = serde_json::to_string(&witness)?;
+        let decoded: SignedPackageWitness = serde_json::from_str(&json)?;
+        assert_eq!(decoded.signature, signature);
+        assert_eq!(decoded.expected_signer, signer);
+        assert_eq!(decoded.package.serialize(), witness.package.serialize());
+        decoded.validate(&signer)?;
+
+        let mut unsupported = witness;
+        unsupported.version = SIGNED_PACKAGE_WITNESS_VERSION + 1;
+        assert!(unsupported.validate(&signer).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_package_metadata() -> anyhow::Result<()> {
+        let data_package = DataPackage::new(
+            vec![DataPoint::new("AVAX", "36.2488073814028")],
+            1705311690000,
+        );
+        let with_metadata = data_package
+            .clone()
+            .with_metadata("redstone-avalanche-prod", "redstone-avalanche-prod-node-1");
+        // Metadata is not signed
+        assert_eq!(
+            with_metadata.keccak256_hash(),
+            data_package.keccak256_hash()
+        );
+
+        let signer = RedstoneSigner::new(0x11);
+        let witness = signer.sign_witness(with_metadata);
+        witness.validate(&signer.address())?;
+        let metadata = witness.metadata();
+        assert_eq!(metadata.signer, signer.address());
+        assert_eq!(metadata.timestamp, 1705311690000);
+        assert_eq!(
+            metadata.data_service_id.as_deref(),
+            Some("redstone-avalanche-prod")
+        );
+        assert_eq!(
+            metadata.signer_name.as_deref(),
+            Some("redstone-avalanche-prod-node-1")
+        );
+
+        let json = serde_json::to_string(&witness)?;
+        let decoded: SignedPackageWitness = serde_json::from_str(&json)?;
+        assert_eq!(decoded.metadata(), metadata);
+        // Packages without metadata keep the previous wire format
+        let json = serde_json::to_string(&data_package)?;
+        assert!(!json.contains("data_service_id"));
+        Ok(())
+    }
 }
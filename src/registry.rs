@@ -0,0 +1,185 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    utils::u64_to_fe,
+};
+use num_bigint::BigUint;
+
+use crate::{
+    decimals::Provider,
+    utils::{fr_from_biguint, num_from_be_bytes},
+};
+
+pub const ASSET_BTC: u32 = 1;
+pub const ASSET_ETH: u32 = 2;
+pub const ASSET_USDC: u32 = 3;
+
+/// Identifiers of feeds and their asset ids, where identifiers are decoded by
+/// [`identifier_bytes`].
+pub const ASSET_REGISTRY: &[(Provider, &str, u32)] = &[
+    (Provider::Redstone, "BTC", ASSET_BTC),
+    (Provider::Redstone, "ETH", ASSET_ETH),
+    (Provider::Redstone, "USDC", ASSET_USDC),
+    (
+        Provider::Pyth,
+        "e62df6c8b4a85fe1a67db44dc12de5db330f7ac66b72dc658afedf0f4a415b43",
+        ASSET_BTC,
+    ),
+    (
+        Provider::Pyth,
+        "ff61491a931112ddf1bd8147cd1b641375f79f5825126d665480874634fd0ace",
+        ASSET_ETH,
+    ),
+    (
+        Provider::Pyth,
+        "eaa020c61cc479712813461ce153894a96a6c00b21ed0cfc2798d1f9a9e9c94a",
+        ASSET_USDC,
+    ),
+    (
+        Provider::Chainlink,
+        "f4030086522a5beea4988f8ca5b36dbc97bee88c",
+        ASSET_BTC,
+    ),
+    (
+        Provider::Chainlink,
+        "5f4ec3df9cbd43714fe2740f5e3616155c5b8419",
+        ASSET_ETH,
+    ),
+    (
+        Provider::Chainlink,
+        "8fffffd4afb6115b954bd326cbe7b4ba576818f6",
+        ASSET_USDC,
+    ),
+];
+
+/// Encode `identifier` of a feed of `provider` in 32 bytes as the provider does, e.g. RedStone
+/// tickers left-aligned and Chainlink addresses right-aligned.
+pub fn identifier_bytes(provider: Provider, identifier: &str) -> anyhow::Result<[u8; 32]> {
+    let mut bytes = [0u8; 32];
+    match provider {
+        Provider::Redstone => {
+            if identifier.is_empty() || identifier.len() > 32 {
+                anyhow::bail!("invalid ticker {:?}", identifier)
+            }
+            bytes[..identifier.len()].copy_from_slice(identifier.as_bytes());
+        }
+        Provider::Pyth => {
+            bytes = hex::decode(identifier.trim_start_matches("0x"))?
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("invalid pyth feed id {}", identifier))?;
+        }
+        Provider::Chainlink => {
+            let address: [u8; 20] = hex::decode(identifier.trim_start_matches("0x"))?
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("invalid feed address {}", identifier))?;
+            bytes[12..].copy_from_slice(&address);
+        }
+        _ => anyhow::bail!("{:?} is not registered", provider),
+    }
+    Ok(bytes)
+}
+
+// Registered identifiers of `provider` and their asset ids
+fn entries(provider: Provider) -> Vec<([u8; 32], u32)> {
+    ASSET_REGISTRY
+        .iter()
+        .filter(|(p, _, _)| *p == provider)
+        .map(|(p, identifier, asset_id)| {
+            let bytes = identifier_bytes(*p, identifier).expect("registry is well-formed");
+            (bytes, *asset_id)
+        })
+        .collect()
+}
+
+/// Asset id of the feed of `provider` identified by `identifier`, or `None` if unregistered.
+pub fn asset_id(provider: Provider, identifier: &[u8; 32]) -> Option<u32> {
+    entries(provider)
+        .into_iter()
+        .find(|(bytes, _)| bytes == identifier)
+        .map(|(_, asset_id)| asset_id)
+}
+
+/// Same as [`asset_id`]. Return whether the identifier is registered and its asset id, which is
+/// zero if unregistered.
+pub fn circuit_asset_id<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    provider: Provider,
+    identifier: &[Byte<E>; 32],
+) -> Result<(Boolean, Num<E>), SynthesisError> {
+    let hi = num_from_be_bytes(cs, &identifier[..16])?;
+    let lo = num_from_be_bytes(cs, &identifier[16..])?;
+    let mut is_registered = Boolean::constant(false);
+    let mut asset_id = Num::zero();
+    for (bytes, id) in entries(provider) {
+        let expected_hi = fr_from_biguint::<E>(&BigUint::from_bytes_be(&bytes[..16]))?;
+        let expected_lo = fr_from_biguint::<E>(&BigUint::from_bytes_be(&bytes[16..]))?;
+        let is_hi_equal = Num::equals(cs, &hi, &Num::Constant(expected_hi))?;
+        let is_lo_equal = Num::equals(cs, &lo, &Num::Constant(expected_lo))?;
+        let is_match = Boolean::and(cs, &is_hi_equal, &is_lo_equal)?;
+        // Identifiers of a provider are distinct, so that at most one entry matches
+        asset_id = Num::conditionally_select(
+            cs,
+            &is_match,
+            &Num::Constant(u64_to_fe(id as u64)),
+            &asset_id,
+        )?;
+        is_registered = Boolean::or(cs, &is_registered, &is_match)?;
+    }
+    Ok((is_registered, asset_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte,
+        franklin_crypto::bellman::{pairing::bn256::Bn256, SynthesisError},
+        utils::u64_to_fe,
+    };
+
+    use super::{
+        asset_id, circuit_asset_id, identifier_bytes, ASSET_ETH, ASSET_REGISTRY, ASSET_USDC,
+    };
+    use crate::{decimals::Provider, utils::testing::create_test_constraint_system};
+
+    #[test]
+    fn test_asset_registry() -> Result<(), SynthesisError> {
+        let mut identifiers = HashSet::new();
+        for (provider, identifier, id) in ASSET_REGISTRY {
+            let bytes = identifier_bytes(*provider, identifier).unwrap();
+            assert!(identifiers.insert((*provider as u8, bytes)));
+            assert_eq!(asset_id(*provider, &bytes), Some(*id));
+        }
+
+        let eth = identifier_bytes(Provider::Redstone, "ETH").unwrap();
+        let usdc = identifier_bytes(
+            Provider::Chainlink,
+            "0x8fFfFfd4AfB6115b954Bd326cbe7B4BA576818f6",
+        )
+        .unwrap();
+        // Identifiers are namespaced by providers
+        assert_eq!(asset_id(Provider::Pyth, &eth), None);
+        for (provider, identifier, expected) in [
+            (Provider::Redstone, eth, Some(ASSET_ETH)),
+            (Provider::Chainlink, usdc, Some(ASSET_USDC)),
+            (Provider::Pyth, eth, None),
+        ] {
+            let cs = &mut create_test_constraint_system()?;
+            let identifier =
+                identifier.map(|b| Byte::<Bn256>::from_u8_witness(cs, Some(b)).unwrap());
+            let (is_registered, id) = circuit_asset_id(cs, provider, &identifier)?;
+            assert_eq!(is_registered.get_value(), Some(expected.is_some()));
+            assert_eq!(
+                id.get_value(),
+                Some(u64_to_fe(expected.unwrap_or(0) as u64))
+            );
+            assert!(cs.is_satisfied());
+        }
+        Ok(())
+    }
+}
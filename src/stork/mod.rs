@@ -0,0 +1,5 @@
+mod circuit;
+mod update;
+
+pub use circuit::*;
+pub use update::*;
@@ -0,0 +1,312 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    vm::{partitioner::smart_and, primitives::uint256::UInt256},
+};
+use num_bigint::{BigInt, BigUint, Sign};
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    gadgets::{
+        ecdsa::Signature,
+        ethereum::{circuit_eth_signed_message_hash, eth_signed_message_hash, Address},
+        keccak256,
+    },
+    redstone::{circuit::normalize_value, witness::recover_signer, SIGNATURE_BS},
+    utils::{new_synthesis_error, num_from_be_bytes},
+};
+
+// Max number of bytes of asset ids, which are committed as feed ids of 15 bytes
+pub const MAX_ASSET_ID_BS: usize = 15;
+// Number of bytes of quantized values, which are non-negative `int192`
+pub const QUANTIZED_VALUE_BS: usize = 24;
+// Precision of quantized values
+pub const QUANTIZED_VALUE_DECIMALS: usize = 18;
+// Number of bytes of the message signed by Stork, i.e. `abi.encodePacked(address, bytes32,
+// uint256, int256, bytes32, bytes32)`
+pub const STORK_MESSAGE_BS: usize = 20 + 5 * 32;
+
+/// Price update of an asset signed by Stork, whose EVM contract checks the signature of
+/// `keccak256(storkPubKey || keccak256(assetId) || timestampNs || quantizedValue ||
+/// publisherMerkleRoot || valueComputeAlgHash)` as an Ethereum signed message.
+///
+/// Quantized values carry [`QUANTIZED_VALUE_DECIMALS`] decimals. Only non-negative values are
+/// supported, as they are committed in the same way as RedStone prices.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedStorkUpdate {
+    pub asset_id: String,
+    pub timestamp_ns: u64,
+    pub quantized_value: BigInt,
+    pub publisher_merkle_root: [u8; 32],
+    pub value_compute_alg_hash: [u8; 32],
+    pub signature: [u8; SIGNATURE_BS],
+}
+
+impl SignedStorkUpdate {
+    /// `keccak256(assetId)`, which identifies the asset in Stork contracts.
+    pub fn encoded_asset_id(&self) -> [u8; 32] {
+        Keccak256::digest(self.asset_id.as_bytes()).into()
+    }
+
+    /// Message signed by `signer`, in [`STORK_MESSAGE_BS`] bytes.
+    pub fn message(&self, signer: &[u8; 20]) -> Vec<u8> {
+        let mut timestamp_ns = [0u8; 32];
+        timestamp_ns[24..].copy_from_slice(&self.timestamp_ns.to_be_bytes());
+        let value = self.quantized_value.to_signed_bytes_be();
+        let fill = if self.quantized_value.sign() == Sign::Minus {
+            0xff
+        } else {
+            0
+        };
+        let mut quantized_value = [fill; 32];
+        quantized_value[32 - value.len()..].copy_from_slice(&value);
+        [
+            &signer[..],
+            &self.encoded_asset_id(),
+            &timestamp_ns,
+            &quantized_value,
+            &self.publisher_merkle_root,
+            &self.value_compute_alg_hash,
+        ]
+        .concat()
+    }
+
+    /// Hash whose signature is checked, i.e. Ethereum signed message hash of `keccak256(message)`.
+    pub fn message_hash(&self, signer: &[u8; 20]) -> [u8; 32] {
+        eth_signed_message_hash(&Keccak256::digest(self.message(signer)).into())
+    }
+
+    /// Publish time in milliseconds, which is the precision of RedStone timestamps.
+    pub fn publish_time(&self) -> u64 {
+        self.timestamp_ns / 1_000_000
+    }
+
+    /// Asset id left-aligned in 15 bytes and prefixed with a zero byte, in the same way as RedStone
+    /// feed ids are committed.
+    pub fn feed_id(&self) -> BigUint {
+        let mut bytes = [0u8; MAX_ASSET_ID_BS + 1];
+        bytes[1..1 + self.asset_id.len()].copy_from_slice(self.asset_id.as_bytes());
+        BigUint::from_bytes_be(&bytes)
+    }
+
+    /// Check the update is signed by `signer`, and its asset id and value are supported.
+    pub fn check(&self, signer: &[u8; 20]) -> anyhow::Result<()> {
+        let len = self.asset_id.len();
+        if len == 0 || len > MAX_ASSET_ID_BS || self.asset_id.as_bytes().contains(&0) {
+            anyhow::bail!("unsupported asset id {:?}", self.asset_id)
+        }
+        if self.quantized_value.sign() == Sign::Minus
+            || self.quantized_value.bits() >= (QUANTIZED_VALUE_BS * 8) as u64
+        {
+            anyhow::bail!("unsupported quantized value {}", self.quantized_value)
+        }
+        let recovered = recover_signer(&self.message_hash(signer), &self.signature)?;
+        if recovered != *signer {
+            anyhow::bail!(
+                "update of {} is signed by 0x{}, expect 0x{}",
+                self.asset_id,
+                hex::encode(recovered),
+                hex::encode(signer)
+            )
+        }
+        Ok(())
+    }
+}
+
+/// Circuit representation of [`SignedStorkUpdate`].
+#[derive(Debug, Clone)]
+pub struct AllocatedStorkUpdate<E: Engine> {
+    pub asset_id: Vec<Byte<E>>,
+    pub timestamp_ns: [Byte<E>; 8],
+    pub quantized_value: [Byte<E>; QUANTIZED_VALUE_BS],
+    pub publisher_merkle_root: [Byte<E>; 32],
+    pub value_compute_alg_hash: [Byte<E>; 32],
+    pub signature: Signature<E>,
+}
+
+/// Update verified by [`AllocatedStorkUpdate::verify`], in the shape of RedStone prices.
+#[derive(Debug, Clone, Copy)]
+pub struct VerifiedStorkUpdate<E: Engine> {
+    pub feed_id: Num<E>,
+    pub price: Num<E>,
+    /// Publish time in milliseconds
+    pub publish_time: Num<E>,
+}
+
+impl<E: Engine> AllocatedStorkUpdate<E> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &SignedStorkUpdate,
+    ) -> Result<Self, SynthesisError> {
+        let len = witness.asset_id.len();
+        if len == 0 || len > MAX_ASSET_ID_BS {
+            return Err(new_synthesis_error(format!(
+                "unsupported asset id {:?}",
+                witness.asset_id
+            )));
+        }
+        let (sign, value) = witness.quantized_value.to_bytes_be();
+        if sign == Sign::Minus || value.len() > QUANTIZED_VALUE_BS {
+            return Err(new_synthesis_error(format!(
+                "unsupported quantized value {}",
+                witness.quantized_value
+            )));
+        }
+        let mut quantized_value = [0u8; QUANTIZED_VALUE_BS];
+        quantized_value[QUANTIZED_VALUE_BS - value.len()..].copy_from_slice(&value);
+
+        let mut alloc_bytes = |bytes: &[u8]| {
+            bytes
+                .iter()
+                .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let asset_id = alloc_bytes(witness.asset_id.as_bytes())?;
+        let timestamp_ns = alloc_bytes(&witness.timestamp_ns.to_be_bytes())?
+            .try_into()
+            .unwrap();
+        let quantized_value = alloc_bytes(&quantized_value)?.try_into().unwrap();
+        let publisher_merkle_root = alloc_bytes(&witness.publisher_merkle_root)?
+            .try_into()
+            .unwrap();
+        let value_compute_alg_hash = alloc_bytes(&witness.value_compute_alg_hash)?
+            .try_into()
+            .unwrap();
+        let mut signature = witness.signature;
+        if signature[64] >= 27 {
+            signature[64] -= 27;
+        }
+        let signature = Signature::from_bytes_witness(cs, &signature)?;
+        Ok(Self {
+            asset_id,
+            timestamp_ns,
+            quantized_value,
+            publisher_merkle_root,
+            value_compute_alg_hash,
+            signature,
+        })
+    }
+
+    /// Same as [`SignedStorkUpdate::message_hash`].
+    pub fn message_hash<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        signer: &Address<E>,
+    ) -> Result<[Byte<E>; 32], SynthesisError> {
+        let mut bytes = signer.to_bytes(cs)?.to_vec();
+        bytes.extend(keccak256::digest(cs, &self.asset_id)?);
+        bytes.extend([Byte::zero(); 24]);
+        bytes.extend(self.timestamp_ns);
+        bytes.extend([Byte::zero(); 32 - QUANTIZED_VALUE_BS]);
+        bytes.extend(self.quantized_value);
+        bytes.extend(self.publisher_merkle_root);
+        bytes.extend(self.value_compute_alg_hash);
+        assert_eq!(bytes.len(), STORK_MESSAGE_BS);
+        let hash = keccak256::digest(cs, &bytes)?;
+        circuit_eth_signed_message_hash(cs, &hash)
+    }
+
+    /// Same as [`SignedStorkUpdate::check`]: check the update is signed by `signer`, its asset id
+    /// has no zero byte and its value is non-negative. Return the verified update, which is only
+    /// meaningful if the check passes.
+    pub fn verify<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        signer: &Address<E>,
+    ) -> Result<(Boolean, VerifiedStorkUpdate<E>), SynthesisError> {
+        let mut is_valid = vec![];
+        let message_hash = self.message_hash(cs, signer)?;
+        let message_hash = UInt256::from_be_bytes_fixed(cs, &message_hash)?;
+        let (successful, (x, y)) = self.signature.ecrecover(cs, &message_hash)?;
+        let (x, y) = (
+            x.into_be_bytes(cs)?.try_into().unwrap(),
+            y.into_be_bytes(cs)?.try_into().unwrap(),
+        );
+        let address = Address::from_pubkey(cs, &x, &y)?;
+        is_valid.push(signer.equals(cs, &address)?);
+        is_valid.push(successful);
+
+        // Quantized value is non-negative, i.e. its sign bit is zero
+        let sign = self.quantized_value[0].inner.into_bits_le(cs, Some(8))?[7];
+        is_valid.push(sign.not());
+        for byte in self.asset_id.iter() {
+            is_valid.push(byte.inner.equals(cs, &Num::zero())?.not());
+        }
+
+        let mut feed_id = self.asset_id.clone();
+        feed_id.resize(MAX_ASSET_ID_BS, Byte::zero());
+        let mut timestamp_ns = [Byte::zero(); 32];
+        timestamp_ns[24..].copy_from_slice(&self.timestamp_ns);
+        let timestamp_ns = UInt256::from_be_bytes_fixed(cs, &timestamp_ns)?;
+        let update = VerifiedStorkUpdate {
+            feed_id: num_from_be_bytes(cs, &feed_id)?,
+            price: num_from_be_bytes(cs, &self.quantized_value)?,
+            // Nanoseconds are rescaled to milliseconds by dropping 6 decimals
+            publish_time: normalize_value(cs, &timestamp_ns, 6, 0)?.to_num_unchecked(cs)?,
+        };
+        Ok((smart_and(cs, &is_valid)?, update))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::franklin_crypto::bellman::{
+        pairing::bn256::Bn256, SynthesisError,
+    };
+    use num_bigint::{BigInt, BigUint};
+
+    use super::{AllocatedStorkUpdate, SignedStorkUpdate};
+    use crate::{
+        gadgets::ethereum::Address,
+        utils::{fr_from_biguint, signer::RedstoneSigner, testing::create_test_constraint_system},
+    };
+
+    #[test]
+    fn test_stork_update() -> Result<(), SynthesisError> {
+        let stork = RedstoneSigner::new(0x42);
+        let mut update = SignedStorkUpdate {
+            asset_id: "BTCUSD".to_string(),
+            timestamp_ns: 1_700_000_000_123_456_789,
+            quantized_value: BigInt::from(43_123_450_000_000_000_000_000u128),
+            publisher_merkle_root: [3u8; 32],
+            value_compute_alg_hash: [4u8; 32],
+            signature: [0u8; 65],
+        };
+        update.signature = stork.sign_hash(&update.message_hash(&stork.address()));
+        assert_eq!(
+            update.message(&stork.address()).len(),
+            super::STORK_MESSAGE_BS
+        );
+        assert_eq!(update.publish_time(), 1_700_000_000_123);
+        update.check(&stork.address()).unwrap();
+        assert!(update.check(&RedstoneSigner::new(0x43).address()).is_err());
+
+        let mut tampered = update.clone();
+        tampered.timestamp_ns += 1;
+        assert!(tampered.check(&stork.address()).is_err());
+
+        for (update, expected) in [(update, true), (tampered, false)] {
+            let cs = &mut create_test_constraint_system()?;
+            let signer = Address::from_address_witness(cs, &stork.address())?;
+            let allocated = AllocatedStorkUpdate::<Bn256>::from_witness(cs, &update)?;
+            let (is_valid, verified) = allocated.verify(cs, &signer)?;
+            assert_eq!(is_valid.get_value(), Some(expected));
+            assert_eq!(
+                verified.feed_id.get_value(),
+                Some(fr_from_biguint::<Bn256>(&update.feed_id())?)
+            );
+            assert_eq!(
+                verified.publish_time.get_value(),
+                Some(fr_from_biguint::<Bn256>(&BigUint::from(
+                    update.publish_time()
+                ))?)
+            );
+            assert!(cs.is_satisfied());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,134 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{ff::Field, Engine};
+use advanced_circuit_component::franklin_crypto::{
+    bellman::{
+        plonk::better_better_cs::{
+            cs::{Circuit, ConstraintSystem, Gate, GateInternal},
+            gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
+        },
+        SynthesisError,
+    },
+    plonk::circuit::{allocated_num::Num, boolean::Boolean, custom_rescue_gate::Rescue5CustomGate},
+};
+use num_bigint::BigUint;
+
+use crate::{
+    gadgets::{
+        poseidon::{circuit_poseidon_hash, poseidon_hash},
+        rescue::circuit_rescue_hash,
+    },
+    utils::{self, fr_from_biguint, num_from_be_bytes},
+};
+
+use super::feed::{AllocatedCommitteeBatch, CommitteeBatch};
+
+/// Circuit verifying feeds of a Supra DORA batch against its Merkle root, whose committee
+/// signature is reduced to one pairing check left to consumers.
+///
+/// Public input is the commitment `poseidon(committee id, root[..16], root[16..], lhs, rhs,
+/// committee_key_g2 in 16-byte chunks, (pair, price, decimals, timestamp) of each feed)`, where
+/// `(lhs, rhs)` is [`CommitteeBatch::pairing_inputs`]. Pairings are not computed in circuit, so
+/// the root is signed only if consumers check `e(lhs, g2) == e(rhs, committee_key_g2)` and that
+/// `committee_key_g2` is the key of the committee id.
+#[derive(Debug, Clone)]
+pub struct SupraPriceOracle<E: Engine> {
+    pub batch: CommitteeBatch,
+    pub commitment: E::Fr,
+}
+
+impl<E: Engine> SupraPriceOracle<E> {
+    pub fn new(batch: CommitteeBatch) -> Result<Self, anyhow::Error> {
+        batch.check()?;
+        let mut input = vec![
+            fr_from_biguint::<E>(&BigUint::from(batch.committee_id))?,
+            fr_from_biguint::<E>(&BigUint::from_bytes_be(&batch.root[..16]))?,
+            fr_from_biguint::<E>(&BigUint::from_bytes_be(&batch.root[16..]))?,
+        ];
+        let (lhs, rhs) = batch.pairing_inputs()?;
+        let pairing_check = [&lhs[..], &rhs[..], &batch.committee_key_g2[..]].concat();
+        for chunk in pairing_check.chunks_exact(16) {
+            input.push(fr_from_biguint::<E>(&BigUint::from_bytes_be(chunk))?);
+        }
+        for feed in batch.feeds.iter().map(|f| f.feed) {
+            input.push(fr_from_biguint::<E>(&BigUint::from(feed.pair))?);
+            input.push(fr_from_biguint::<E>(&BigUint::from(feed.price))?);
+            input.push(fr_from_biguint::<E>(&BigUint::from(feed.decimals))?);
+            input.push(fr_from_biguint::<E>(&BigUint::from(feed.timestamp))?);
+        }
+        Ok(Self {
+            batch,
+            commitment: poseidon_hash::<E>(&input),
+        })
+    }
+}
+
+impl<E: Engine> Circuit<E> for SupraPriceOracle<E> {
+    type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
+
+    fn synthesize<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<(), SynthesisError> {
+        utils::add_bitwise_logic_and_range_table(cs)?;
+        let temp_variable = Num::alloc(cs, Some(E::Fr::one()))?;
+        circuit_rescue_hash(cs, &[temp_variable])?; // Just to standardize the proof format
+
+        let batch = AllocatedCommitteeBatch::from_witness(cs, &self.batch)?;
+        let (is_valid, feeds) = batch.verify(cs)?;
+        Boolean::enforce_equal(cs, &is_valid, &Boolean::constant(true))?;
+
+        let mut input = vec![
+            batch.committee_id,
+            num_from_be_bytes(cs, &batch.root[..16])?,
+            num_from_be_bytes(cs, &batch.root[16..])?,
+        ];
+        input.extend(batch.pairing_check(cs)?);
+        for feed in feeds {
+            input.extend([feed.pair, feed.price, feed.decimals, feed.timestamp]);
+        }
+        let commitment = circuit_poseidon_hash(cs, &input)?;
+        let expected_commitment = Num::alloc(cs, Some(self.commitment))?;
+        expected_commitment.enforce_equal(cs, &commitment)?;
+        expected_commitment.get_variable().inputize(cs)?;
+        Ok(())
+    }
+
+    fn declare_used_gates() -> Result<Vec<Box<dyn GateInternal<E>>>, SynthesisError> {
+        Ok(vec![
+            Self::MainGate::default().into_internal(),
+            Rescue5CustomGate.into_internal(), // Just to standardize the proof format
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::bellman::{pairing::ff::Field, plonk::better_better_cs::cs::Circuit},
+        testing::{create_test_artifacts_with_optimized_gate, Bn256},
+    };
+
+    use super::SupraPriceOracle;
+    use crate::utils::testing::supra_batch;
+
+    #[test]
+    fn test_supra_price_oracle() -> anyhow::Result<()> {
+        let batch = supra_batch(&[
+            (0, 4_312_345_000_000),
+            (1, 253_012_000_000),
+            (49, 100_010_000),
+        ]);
+        let circuit = SupraPriceOracle::<Bn256>::new(batch.clone())?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        println!("Roughly {} gates", cs.n());
+
+        let mut tampered = circuit;
+        tampered.commitment.add_assign(&Field::one());
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        tampered.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+
+        let mut batch = batch;
+        batch.root[0] ^= 1;
+        assert!(SupraPriceOracle::<Bn256>::new(batch).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,414 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    vm::{partitioner::smart_and, primitives::uint256::UInt256},
+};
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    gadgets::{
+        bn254::{
+            circuit_check_pairing_inputs, g1_from_bytes, g1_to_bytes, g2_from_bytes,
+            pairing_inputs, verify_aggregate_signature, G1_POINT_BS, G2_POINT_BS,
+        },
+        comparison::compare,
+        keccak256,
+    },
+    utils::{new_synthesis_error, num_from_be_bytes},
+};
+
+// Number of bytes of `abi.encode(pair, price, timestamp, decimals, round)`
+pub const FEED_LEAF_PREIMAGE_BS: usize = 5 * 32;
+
+/// Price of a pair in a batch of Supra DORA, i.e. `CommitteeFeed` of Supra pull oracles.
+///
+/// Timestamps are in milliseconds, and prices carry `decimals` decimals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommitteeFeed {
+    pub pair: u32,
+    pub price: u128,
+    pub timestamp: u64,
+    pub decimals: u16,
+    pub round: u64,
+}
+
+impl CommitteeFeed {
+    /// `abi.encode(pair, price, timestamp, decimals, round)`.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        for word in [
+            &self.pair.to_be_bytes()[..],
+            &self.price.to_be_bytes()[..],
+            &self.timestamp.to_be_bytes()[..],
+            &self.decimals.to_be_bytes()[..],
+            &self.round.to_be_bytes()[..],
+        ] {
+            bytes.extend(vec![0u8; 32 - word.len()]);
+            bytes.extend(word);
+        }
+        bytes
+    }
+
+    /// Leaf of the feed in the batch Merkle tree, i.e. `keccak256(abi.encode(feed))`.
+    pub fn leaf(&self) -> [u8; 32] {
+        Keccak256::digest(self.encode()).into()
+    }
+}
+
+/// `keccak256(min(a, b) || max(a, b))`, i.e. nodes of Merkle trees with sorted pairs like
+/// OpenZeppelin `MerkleProof`.
+pub fn hash_sorted_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (l, r) = if a <= b { (a, b) } else { (b, a) };
+    Keccak256::new_with_prefix(l)
+        .chain_update(r)
+        .finalize()
+        .into()
+}
+
+/// Feed with its proof in the batch Merkle tree, whose siblings are ordered from the leaf.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProvenFeed {
+    pub feed: CommitteeFeed,
+    pub proof: Vec<[u8; 32]>,
+}
+
+impl ProvenFeed {
+    /// Root of the batch Merkle tree proven by the feed.
+    pub fn root(&self) -> [u8; 32] {
+        self.proof.iter().fold(self.feed.leaf(), |node, sibling| {
+            hash_sorted_pair(&node, sibling)
+        })
+    }
+}
+
+/// Batch of feeds of Supra DORA, whose Merkle root is signed by the DKG committee
+/// `committee_id` with a BLS threshold signature over BN254, i.e. `signature = [sk]H(root)` of
+/// the committee key `[sk]g1` and `committee_key_g2 = [sk]g2` in the layout of
+/// [`crate::gadgets::bn254`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitteeBatch {
+    pub committee_id: u64,
+    pub committee_key: [u8; G1_POINT_BS],
+    pub committee_key_g2: [u8; G2_POINT_BS],
+    pub root: [u8; 32],
+    pub signature: [u8; G1_POINT_BS],
+    pub feeds: Vec<ProvenFeed>,
+}
+
+impl CommitteeBatch {
+    /// Check the root is signed by the committee key, each feed is proven against the root, and
+    /// pairs are distinct.
+    pub fn check(&self) -> anyhow::Result<()> {
+        let key = g1_from_bytes(&self.committee_key)?;
+        let key_g2 = g2_from_bytes(&self.committee_key_g2)?;
+        let signature = g1_from_bytes(&self.signature)?;
+        if !verify_aggregate_signature(&self.root, &key, &key_g2, &signature) {
+            anyhow::bail!(
+                "root 0x{} is not signed by committee {}",
+                hex::encode(self.root),
+                self.committee_id
+            )
+        }
+        for (i, feed) in self.feeds.iter().enumerate() {
+            if feed.root() != self.root {
+                anyhow::bail!(
+                    "pair {} is not proven against root 0x{}",
+                    feed.feed.pair,
+                    hex::encode(self.root)
+                )
+            }
+            if self.feeds[..i]
+                .iter()
+                .any(|f| f.feed.pair == feed.feed.pair)
+            {
+                anyhow::bail!("pair {} is duplicated", feed.feed.pair)
+            }
+        }
+        Ok(())
+    }
+
+    /// [`pairing_inputs`] `(lhs, rhs)` of the signature, which consumers check against
+    /// `committee_key_g2`.
+    pub fn pairing_inputs(&self) -> anyhow::Result<([u8; G1_POINT_BS], [u8; G1_POINT_BS])> {
+        let (lhs, rhs) = pairing_inputs(
+            &self.root,
+            &g1_from_bytes(&self.committee_key)?,
+            &g2_from_bytes(&self.committee_key_g2)?,
+            &g1_from_bytes(&self.signature)?,
+        );
+        Ok((g1_to_bytes(&lhs), g1_to_bytes(&rhs)))
+    }
+}
+
+fn alloc_bytes<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    bytes: &[u8],
+) -> Result<Vec<Byte<E>>, SynthesisError> {
+    bytes
+        .iter()
+        .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+        .collect()
+}
+
+fn alloc_hash<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    hash: &[u8; 32],
+) -> Result<[Byte<E>; 32], SynthesisError> {
+    Ok(alloc_bytes(cs, hash)?.try_into().unwrap())
+}
+
+/// Same as [`hash_sorted_pair`].
+pub fn circuit_hash_sorted_pair<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    a: &[Byte<E>; 32],
+    b: &[Byte<E>; 32],
+) -> Result<[Byte<E>; 32], SynthesisError> {
+    let (_, a_is_greater) = {
+        let a = UInt256::from_be_bytes_fixed(cs, a)?;
+        let b = UInt256::from_be_bytes_fixed(cs, b)?;
+        compare(cs, &a, &b)?
+    };
+    let mut bytes = vec![];
+    for (x, y) in a.iter().zip(b.iter()) {
+        let (l, _) = Num::conditionally_reverse(cs, &x.inner, &y.inner, &a_is_greater)?;
+        bytes.push(Byte::from_num_unconstrained(cs, l));
+    }
+    for (x, y) in a.iter().zip(b.iter()) {
+        let (_, r) = Num::conditionally_reverse(cs, &x.inner, &y.inner, &a_is_greater)?;
+        bytes.push(Byte::from_num_unconstrained(cs, r));
+    }
+    keccak256::digest(cs, &bytes)
+}
+
+/// Circuit representation of [`CommitteeFeed`].
+#[derive(Debug, Clone, Copy)]
+pub struct AllocatedCommitteeFeed<E: Engine> {
+    pub pair: [Byte<E>; 4],
+    pub price: [Byte<E>; 16],
+    pub timestamp: [Byte<E>; 8],
+    pub decimals: [Byte<E>; 2],
+    pub round: [Byte<E>; 8],
+}
+
+/// Feed verified by [`AllocatedCommitteeBatch::verify`].
+#[derive(Debug, Clone, Copy)]
+pub struct VerifiedCommitteeFeed<E: Engine> {
+    pub pair: Num<E>,
+    pub price: Num<E>,
+    pub decimals: Num<E>,
+    /// Timestamp in milliseconds
+    pub timestamp: Num<E>,
+}
+
+impl<E: Engine> AllocatedCommitteeFeed<E> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &CommitteeFeed,
+    ) -> Result<Self, SynthesisError> {
+        Ok(Self {
+            pair: alloc_bytes(cs, &witness.pair.to_be_bytes())?
+                .try_into()
+                .unwrap(),
+            price: alloc_bytes(cs, &witness.price.to_be_bytes())?
+                .try_into()
+                .unwrap(),
+            timestamp: alloc_bytes(cs, &witness.timestamp.to_be_bytes())?
+                .try_into()
+                .unwrap(),
+            decimals: alloc_bytes(cs, &witness.decimals.to_be_bytes())?
+                .try_into()
+                .unwrap(),
+            round: alloc_bytes(cs, &witness.round.to_be_bytes())?
+                .try_into()
+                .unwrap(),
+        })
+    }
+
+    /// Same as [`CommitteeFeed::leaf`].
+    pub fn leaf<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<[Byte<E>; 32], SynthesisError> {
+        let mut bytes = vec![];
+        for word in [
+            &self.pair[..],
+            &self.price[..],
+            &self.timestamp[..],
+            &self.decimals[..],
+            &self.round[..],
+        ] {
+            bytes.extend(vec![Byte::zero(); 32 - word.len()]);
+            bytes.extend_from_slice(word);
+        }
+        assert_eq!(bytes.len(), FEED_LEAF_PREIMAGE_BS);
+        keccak256::digest(cs, &bytes)
+    }
+}
+
+/// Circuit representation of [`CommitteeBatch`] with its [`CommitteeBatch::pairing_inputs`] `lhs`
+/// and `rhs`. Depths of proofs are fixed by the witness at synthesis.
+#[derive(Debug, Clone)]
+pub struct AllocatedCommitteeBatch<E: Engine> {
+    pub committee_id: Num<E>,
+    pub committee_key: [Byte<E>; G1_POINT_BS],
+    pub committee_key_g2: [Byte<E>; G2_POINT_BS],
+    pub root: [Byte<E>; 32],
+    pub signature: [Byte<E>; G1_POINT_BS],
+    pub lhs: [Byte<E>; G1_POINT_BS],
+    pub rhs: [Byte<E>; G1_POINT_BS],
+    pub feeds: Vec<(AllocatedCommitteeFeed<E>, Vec<[Byte<E>; 32]>)>,
+}
+
+impl<E: Engine> AllocatedCommitteeBatch<E> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &CommitteeBatch,
+    ) -> Result<Self, SynthesisError> {
+        let committee_id = alloc_bytes(cs, &witness.committee_id.to_be_bytes())?;
+        let committee_id = num_from_be_bytes(cs, &committee_id)?;
+        let root = alloc_hash(cs, &witness.root)?;
+        let mut feeds = vec![];
+        for feed in witness.feeds.iter() {
+            let allocated = AllocatedCommitteeFeed::from_witness(cs, &feed.feed)?;
+            let proof = feed
+                .proof
+                .iter()
+                .map(|sibling| alloc_hash(cs, sibling))
+                .collect::<Result<Vec<_>, _>>()?;
+            feeds.push((allocated, proof));
+        }
+        let (lhs, rhs) = witness
+            .pairing_inputs()
+            .map_err(|e| new_synthesis_error(e.to_string()))?;
+        Ok(Self {
+            committee_id,
+            committee_key: alloc_bytes(cs, &witness.committee_key)?.try_into().unwrap(),
+            committee_key_g2: alloc_bytes(cs, &witness.committee_key_g2)?
+                .try_into()
+                .unwrap(),
+            root,
+            signature: alloc_bytes(cs, &witness.signature)?.try_into().unwrap(),
+            lhs: alloc_bytes(cs, &lhs)?.try_into().unwrap(),
+            rhs: alloc_bytes(cs, &rhs)?.try_into().unwrap(),
+            feeds,
+        })
+    }
+
+    /// Same as [`CommitteeBatch::check`] up to the pairing check, i.e. `lhs` and `rhs` are pairing
+    /// inputs of the signature of the root, see [`circuit_check_pairing_inputs`]. Return whether
+    /// the check passes, and the verified feeds, which are only meaningful if the check passes.
+    pub fn verify<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<(Boolean, Vec<VerifiedCommitteeFeed<E>>), SynthesisError> {
+        let mut is_valid = vec![circuit_check_pairing_inputs(
+            cs,
+            &self.root,
+            &self.committee_key,
+            &self.committee_key_g2,
+            &self.signature,
+            &self.lhs,
+            &self.rhs,
+        )?];
+        let mut verified: Vec<VerifiedCommitteeFeed<E>> = vec![];
+        for (feed, proof) in self.feeds.iter() {
+            let mut node = feed.leaf(cs)?;
+            for sibling in proof.iter() {
+                node = circuit_hash_sorted_pair(cs, &node, sibling)?;
+            }
+            for (a, b) in node.iter().zip(self.root.iter()) {
+                is_valid.push(a.inner.equals(cs, &b.inner)?);
+            }
+
+            let pair = num_from_be_bytes(cs, &feed.pair)?;
+            for other in verified.iter() {
+                is_valid.push(pair.equals(cs, &other.pair)?.not());
+            }
+            verified.push(VerifiedCommitteeFeed {
+                pair,
+                price: num_from_be_bytes(cs, &feed.price)?,
+                decimals: num_from_be_bytes(cs, &feed.decimals)?,
+                timestamp: num_from_be_bytes(cs, &feed.timestamp)?,
+            });
+        }
+        Ok((smart_and(cs, &is_valid)?, verified))
+    }
+
+    /// `lhs`, `rhs` and `committee_key_g2` in 16-byte chunks, whose pairing check is left to
+    /// consumers.
+    pub fn pairing_check<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<Vec<Num<E>>, SynthesisError> {
+        let bytes = [&self.lhs[..], &self.rhs[..], &self.committee_key_g2[..]].concat();
+        bytes
+            .chunks_exact(16)
+            .map(|chunk| num_from_be_bytes(cs, chunk))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::franklin_crypto::bellman::{
+        pairing::bn256::Bn256, SynthesisError,
+    };
+
+    use super::{hash_sorted_pair, AllocatedCommitteeBatch, CommitteeBatch};
+    use crate::{
+        gadgets::bn254::{g1_from_bytes, g2_from_bytes, verify_pairing_inputs},
+        utils::testing::{create_test_constraint_system, supra_batch, BlsSigner},
+    };
+
+    #[test]
+    fn test_committee_batch() -> Result<(), SynthesisError> {
+        let batch = supra_batch(&[(0, 4_312_345_000_000), (1, 253_012_000_000)]);
+        batch.check().unwrap();
+        let feed = &batch.feeds[0];
+        assert_eq!(
+            hash_sorted_pair(&feed.feed.leaf(), &feed.proof[0]),
+            hash_sorted_pair(&feed.proof[0], &feed.feed.leaf())
+        );
+
+        let mut tampered = batch.clone();
+        tampered.feeds[1].feed.price += 1;
+        assert!(tampered.check().is_err());
+        let mut duplicated = batch.clone();
+        duplicated.feeds[1] = duplicated.feeds[0].clone();
+        assert!(duplicated.check().is_err());
+
+        for (batch, expected) in [
+            (batch.clone(), true),
+            (tampered, false),
+            (duplicated, false),
+        ] {
+            let cs = &mut create_test_constraint_system()?;
+            let allocated = AllocatedCommitteeBatch::<Bn256>::from_witness(cs, &batch)?;
+            let (is_valid, verified) = allocated.verify(cs)?;
+            assert_eq!(is_valid.get_value(), Some(expected));
+            assert_eq!(verified.len(), 2);
+            assert!(cs.is_satisfied());
+        }
+
+        // A forged signature passes the circuit, but not the pairing check left to consumers
+        let mut forged = batch;
+        forged.signature = BlsSigner::new(8).sign(&forged.root);
+        assert!(forged.check().is_err());
+        let cs = &mut create_test_constraint_system()?;
+        let allocated = AllocatedCommitteeBatch::<Bn256>::from_witness(cs, &forged)?;
+        let (is_valid, _) = allocated.verify(cs)?;
+        assert_eq!(is_valid.get_value(), Some(true));
+        assert!(cs.is_satisfied());
+        let (lhs, rhs) = forged.pairing_inputs().unwrap();
+        assert!(!verify_pairing_inputs(
+            &g1_from_bytes(&lhs).unwrap(),
+            &g1_from_bytes(&rhs).unwrap(),
+            &g2_from_bytes(&forged.committee_key_g2).unwrap()
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{ff::Field, Engine};
+use advanced_circuit_component::franklin_crypto::{
+    bellman::{
+        plonk::better_better_cs::{
+            cs::{Circuit, ConstraintSystem, Gate, GateInternal},
+            gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
+        },
+        SynthesisError,
+    },
+    plonk::circuit::{allocated_num::Num, boolean::Boolean, custom_rescue_gate::Rescue5CustomGate},
+};
+use num_bigint::{BigInt, BigUint};
+
+use crate::{
+    chainlink::fr_from_bigint,
+    gadgets::{
+        ethereum::Address,
+        poseidon::{circuit_poseidon_hash, poseidon_hash},
+        rescue::circuit_rescue_hash,
+    },
+    utils::{self, fr_from_biguint, num_from_be_bytes},
+};
+
+use super::result::{AllocatedFeedResult, SignedFeedResult};
+
+/// Circuit verifying results of Switchboard on-demand feeds signed by an oracle.
+///
+/// Public input is the commitment `poseidon(oracle, (feedHash[..16], feedHash[16..], timestamp,
+/// result) of each signed result)`, where negative results are encoded as `r - |result|`.
+#[derive(Debug, Clone)]
+pub struct SwitchboardPriceOracle<E: Engine> {
+    pub results: Vec<SignedFeedResult>,
+    pub oracle: [u8; 20],
+    pub commitment: E::Fr,
+}
+
+impl<E: Engine> SwitchboardPriceOracle<E> {
+    pub fn new(results: Vec<SignedFeedResult>, oracle: [u8; 20]) -> Result<Self, anyhow::Error> {
+        let mut input = vec![fr_from_biguint::<E>(&BigUint::from_bytes_be(&oracle))?];
+        for result in results.iter() {
+            result.check(&oracle)?;
+            input.push(fr_from_biguint::<E>(&BigUint::from_bytes_be(
+                &result.feed_hash[..16],
+            ))?);
+            input.push(fr_from_biguint::<E>(&BigUint::from_bytes_be(
+                &result.feed_hash[16..],
+            ))?);
+            input.push(fr_from_biguint::<E>(&BigUint::from(result.timestamp))?);
+            input.push(fr_from_bigint::<E>(&BigInt::from(result.result))?);
+        }
+        Ok(Self {
+            results,
+            oracle,
+            commitment: poseidon_hash::<E>(&input),
+        })
+    }
+}
+
+impl<E: Engine> Circuit<E> for SwitchboardPriceOracle<E> {
+    type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
+
+    fn synthesize<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<(), SynthesisError> {
+        utils::add_bitwise_logic_and_range_table(cs)?;
+        let temp_variable = Num::alloc(cs, Some(E::Fr::one()))?;
+        circuit_rescue_hash(cs, &[temp_variable])?; // Just to standardize the proof format
+
+        let oracle = Address::from_address_witness(cs, &self.oracle)?;
+        let mut is_valid = Boolean::constant(true);
+        let mut input = vec![oracle.inner().to_num_unchecked(cs)?];
+        for result in self.results.iter() {
+            let result = AllocatedFeedResult::from_witness(cs, result)?;
+            let (is_current_valid, verified) = result.verify(cs, &oracle)?;
+            is_valid = Boolean::and(cs, &is_valid, &is_current_valid)?;
+            input.push(num_from_be_bytes(cs, &result.feed_hash[..16])?);
+            input.push(num_from_be_bytes(cs, &result.feed_hash[16..])?);
+            input.push(verified.timestamp);
+            input.push(verified.result);
+        }
+        Boolean::enforce_equal(cs, &is_valid, &Boolean::constant(true))?;
+
+        let commitment = circuit_poseidon_hash(cs, &input)?;
+        let expected_commitment = Num::alloc(cs, Some(self.commitment))?;
+        expected_commitment.enforce_equal(cs, &commitment)?;
+        expected_commitment.get_variable().inputize(cs)?;
+        Ok(())
+    }
+
+    fn declare_used_gates() -> Result<Vec<Box<dyn GateInternal<E>>>, SynthesisError> {
+        Ok(vec![
+            Self::MainGate::default().into_internal(),
+            Rescue5CustomGate.into_internal(), // Just to standardize the proof format
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::bellman::{pairing::ff::Field, plonk::better_better_cs::cs::Circuit},
+        testing::{create_test_artifacts_with_optimized_gate, Bn256},
+    };
+
+    use super::SwitchboardPriceOracle;
+    use crate::{switchboard::SignedFeedResult, utils::signer::RedstoneSigner};
+
+    #[test]
+    fn test_switchboard_price_oracle() -> anyhow::Result<()> {
+        let oracle = RedstoneSigner::new(0x5b);
+        let sign = |feed: u8, result: i128| {
+            let mut signed = SignedFeedResult {
+                feed_hash: [feed; 32],
+                result,
+                timestamp: 1_700_000_000,
+                signature: [0u8; 65],
+            };
+            signed.signature = oracle.sign_hash(&signed.message_hash());
+            signed
+        };
+        let results = vec![
+            sign(1, 2_530_120_000_000_000_000_000),
+            sign(2, 42_800_000_000_000_000_000_000),
+            sign(3, -5_000_000_000_000_000),
+        ];
+
+        let circuit = SwitchboardPriceOracle::<Bn256>::new(results.clone(), oracle.address())?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        println!("Roughly {} gates", cs.n());
+
+        let mut tampered = circuit;
+        tampered.commitment.add_assign(&Field::one());
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        tampered.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+
+        let other = RedstoneSigner::new(0x5c).address();
+        assert!(SwitchboardPriceOracle::<Bn256>::new(results, other).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,5 @@
+mod circuit;
+mod result;
+
+pub use circuit::*;
+pub use result::*;
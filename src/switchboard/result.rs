@@ -0,0 +1,210 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    vm::{partitioner::smart_and, primitives::uint256::UInt256},
+};
+use num_bigint::BigUint;
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    gadgets::{ecdsa::Signature, ethereum::Address, keccak256},
+    redstone::{witness::recover_signer, SIGNATURE_BS},
+    utils::{fr_from_biguint, num_from_be_bytes},
+};
+
+// Number of bytes of the message signed by oracles, i.e. `abi.encodePacked(bytes32, int128,
+// uint64)`
+pub const RESULT_MESSAGE_BS: usize = 32 + 16 + 8;
+// Precision of results of Switchboard on-demand feeds
+pub const RESULT_DECIMALS: usize = 18;
+
+/// Result of a Switchboard on-demand feed signed by an oracle, whose enclave key signs
+/// `keccak256(feedHash || result || timestamp)` with secp256k1, where `result` is `int128` and
+/// `timestamp` is `uint64` in seconds.
+///
+/// Results carry [`RESULT_DECIMALS`] decimals.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedFeedResult {
+    pub feed_hash: [u8; 32],
+    pub result: i128,
+    pub timestamp: u64,
+    pub signature: [u8; SIGNATURE_BS],
+}
+
+impl SignedFeedResult {
+    /// Message signed by the oracle, in [`RESULT_MESSAGE_BS`] bytes.
+    pub fn message(&self) -> Vec<u8> {
+        let mut message = self.feed_hash.to_vec();
+        message.extend(self.result.to_be_bytes());
+        message.extend(self.timestamp.to_be_bytes());
+        message
+    }
+
+    /// Hash whose signature is checked, i.e. `keccak256(message)`.
+    pub fn message_hash(&self) -> [u8; 32] {
+        Keccak256::digest(self.message()).into()
+    }
+
+    /// Check the result is signed by `oracle`.
+    pub fn check(&self, oracle: &[u8; 20]) -> anyhow::Result<()> {
+        let signer = recover_signer(&self.message_hash(), &self.signature)?;
+        if signer != *oracle {
+            anyhow::bail!(
+                "result of feed 0x{} is signed by 0x{}, expect oracle 0x{}",
+                hex::encode(self.feed_hash),
+                hex::encode(signer),
+                hex::encode(oracle)
+            )
+        }
+        Ok(())
+    }
+}
+
+/// Circuit representation of [`SignedFeedResult`].
+#[derive(Debug, Clone)]
+pub struct AllocatedFeedResult<E: Engine> {
+    pub feed_hash: [Byte<E>; 32],
+    pub result: [Byte<E>; 16],
+    pub timestamp: [Byte<E>; 8],
+    pub signature: Signature<E>,
+}
+
+/// Result verified by [`AllocatedFeedResult::verify`].
+#[derive(Debug, Clone, Copy)]
+pub struct VerifiedFeedResult<E: Engine> {
+    /// Timestamp in seconds
+    pub timestamp: Num<E>,
+    /// Result of the feed, where negative results are encoded as `r - |result|`
+    pub result: Num<E>,
+}
+
+impl<E: Engine> AllocatedFeedResult<E> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &SignedFeedResult,
+    ) -> Result<Self, SynthesisError> {
+        let mut alloc_bytes = |bytes: &[u8]| {
+            bytes
+                .iter()
+                .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let feed_hash = alloc_bytes(&witness.feed_hash)?.try_into().unwrap();
+        let result = alloc_bytes(&witness.result.to_be_bytes())?
+            .try_into()
+            .unwrap();
+        let timestamp = alloc_bytes(&witness.timestamp.to_be_bytes())?
+            .try_into()
+            .unwrap();
+        let mut signature = witness.signature;
+        if signature[64] >= 27 {
+            signature[64] -= 27;
+        }
+        let signature = Signature::from_bytes_witness(cs, &signature)?;
+        Ok(Self {
+            feed_hash,
+            result,
+            timestamp,
+            signature,
+        })
+    }
+
+    /// Same as [`SignedFeedResult::message_hash`].
+    pub fn message_hash<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+    ) -> Result<[Byte<E>; 32], SynthesisError> {
+        let mut bytes = self.feed_hash.to_vec();
+        bytes.extend(self.result);
+        bytes.extend(self.timestamp);
+        assert_eq!(bytes.len(), RESULT_MESSAGE_BS);
+        keccak256::digest(cs, &bytes)
+    }
+
+    /// Same as [`SignedFeedResult::check`]. Return the verified result, which is only meaningful
+    /// if the check passes.
+    pub fn verify<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        oracle: &Address<E>,
+    ) -> Result<(Boolean, VerifiedFeedResult<E>), SynthesisError> {
+        let message_hash = self.message_hash(cs)?;
+        let message_hash = UInt256::from_be_bytes_fixed(cs, &message_hash)?;
+        let (successful, (x, y)) = self.signature.ecrecover(cs, &message_hash)?;
+        let (x, y) = (
+            x.into_be_bytes(cs)?.try_into().unwrap(),
+            y.into_be_bytes(cs)?.try_into().unwrap(),
+        );
+        let address = Address::from_pubkey(cs, &x, &y)?;
+        let is_signed = oracle.equals(cs, &address)?;
+
+        // int128 in two's complement, i.e. unsigned bytes minus 2^128 if the sign bit is set
+        let unsigned = num_from_be_bytes(cs, &self.result)?;
+        let is_negative = self.result[0].inner.into_bits_le(cs, Some(8))?[7];
+        let two_pow_128 = fr_from_biguint::<E>(&(BigUint::from(1u8) << 128))?;
+        let offset = Num::from_boolean_is(is_negative).mul(cs, &Num::Constant(two_pow_128))?;
+        let result = VerifiedFeedResult {
+            timestamp: num_from_be_bytes(cs, &self.timestamp)?,
+            result: unsigned.sub(cs, &offset)?,
+        };
+        Ok((smart_and(cs, &[is_signed, successful])?, result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::franklin_crypto::bellman::{
+        pairing::bn256::Bn256, SynthesisError,
+    };
+    use num_bigint::BigInt;
+
+    use super::{AllocatedFeedResult, SignedFeedResult, RESULT_MESSAGE_BS};
+    use crate::{
+        chainlink::fr_from_bigint,
+        gadgets::ethereum::Address,
+        utils::{signer::RedstoneSigner, testing::create_test_constraint_system},
+    };
+
+    #[test]
+    fn test_feed_result() -> Result<(), SynthesisError> {
+        let oracle = RedstoneSigner::new(0x5b);
+        let sign = |result: i128| {
+            let mut signed = SignedFeedResult {
+                feed_hash: [7u8; 32],
+                result,
+                timestamp: 1_700_000_000,
+                signature: [0u8; 65],
+            };
+            signed.signature = oracle.sign_hash(&signed.message_hash());
+            signed
+        };
+        let positive = sign(2_530_120_000_000_000_000_000);
+        let negative = sign(-5_000_000_000_000_000);
+        assert_eq!(positive.message().len(), RESULT_MESSAGE_BS);
+        positive.check(&oracle.address()).unwrap();
+        assert!(positive
+            .check(&RedstoneSigner::new(0x5c).address())
+            .is_err());
+        let mut tampered = positive.clone();
+        tampered.timestamp += 1;
+        assert!(tampered.check(&oracle.address()).is_err());
+
+        for (signed, expected) in [(positive, true), (negative, true), (tampered, false)] {
+            let cs = &mut create_test_constraint_system()?;
+            let address = Address::from_address_witness(cs, &oracle.address())?;
+            let allocated = AllocatedFeedResult::<Bn256>::from_witness(cs, &signed)?;
+            let (is_valid, verified) = allocated.verify(cs, &address)?;
+            assert_eq!(is_valid.get_value(), Some(expected));
+            assert_eq!(
+                verified.result.get_value(),
+                Some(fr_from_bigint::<Bn256>(&BigInt::from(signed.result)).unwrap())
+            );
+            assert!(cs.is_satisfied());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,164 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{ff::Field, Engine};
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{
+            plonk::better_better_cs::{
+                cs::{Circuit, ConstraintSystem, Gate, GateInternal},
+                gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
+            },
+            SynthesisError,
+        },
+        plonk::circuit::{
+            allocated_num::Num, boolean::Boolean, custom_rescue_gate::Rescue5CustomGate,
+        },
+    },
+};
+use num_bigint::BigUint;
+
+use crate::{
+    gadgets::{
+        poseidon::{circuit_poseidon_hash, poseidon_hash},
+        rescue::circuit_rescue_hash,
+        storage::{AllocatedContractStorage, ContractStorage},
+    },
+    utils::{self, fr_from_biguint, num_from_be_bytes},
+};
+
+use super::value::{AllocatedTellorValue, TellorValue};
+
+/// Circuit verifying values submitted to the Tellor oracle by Ethereum storage proofs against a
+/// block, where `reports_slot` is the slot of the `reports` mapping of the oracle contract.
+///
+/// Public input is the commitment `poseidon(blockHash[..16], blockHash[16..], oracle, reports
+/// slot, (queryId[..16], queryId[16..], timestamp, value[..16], value[16..]) of each value)`.
+/// Consumers must check the committed block hash is canonical, e.g. by `blockhash`.
+#[derive(Debug, Clone)]
+pub struct TellorPriceOracle<E: Engine> {
+    pub storage: ContractStorage,
+    pub reports_slot: u64,
+    pub values: Vec<TellorValue>,
+    pub commitment: E::Fr,
+}
+
+impl<E: Engine> TellorPriceOracle<E> {
+    pub fn new(
+        storage: ContractStorage,
+        reports_slot: u64,
+        values: Vec<TellorValue>,
+    ) -> Result<Self, anyhow::Error> {
+        let storage_root = storage.storage_root()?;
+        let block_hash = storage.block_hash();
+        let mut input = vec![
+            fr_from_biguint::<E>(&BigUint::from_bytes_be(&block_hash[..16]))?,
+            fr_from_biguint::<E>(&BigUint::from_bytes_be(&block_hash[16..]))?,
+            fr_from_biguint::<E>(&BigUint::from_bytes_be(&storage.address))?,
+            fr_from_biguint::<E>(&BigUint::from(reports_slot))?,
+        ];
+        for value in values.iter() {
+            value.check(&storage_root, reports_slot)?;
+            for chunk in [
+                &value.query_id[..16],
+                &value.query_id[16..],
+                &value.timestamp.to_be_bytes(),
+                &value.value[..16],
+                &value.value[16..],
+            ] {
+                input.push(fr_from_biguint::<E>(&BigUint::from_bytes_be(chunk))?);
+            }
+        }
+        Ok(Self {
+            storage,
+            reports_slot,
+            values,
+            commitment: poseidon_hash::<E>(&input),
+        })
+    }
+}
+
+impl<E: Engine> Circuit<E> for TellorPriceOracle<E> {
+    type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
+
+    fn synthesize<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<(), SynthesisError> {
+        utils::add_bitwise_logic_and_range_table(cs)?;
+        let temp_variable = Num::alloc(cs, Some(E::Fr::one()))?;
+        circuit_rescue_hash(cs, &[temp_variable])?; // Just to standardize the proof format
+
+        let storage = AllocatedContractStorage::from_witness(cs, &self.storage)?;
+        let (mut is_valid, block_hash, storage_root) = storage.verify(cs)?;
+        let mut reports_slot = [Byte::zero(); 8];
+        for (byte, b) in reports_slot.iter_mut().zip(self.reports_slot.to_be_bytes()) {
+            *byte = Byte::from_u8_witness(cs, Some(b))?;
+        }
+        let mut input = vec![
+            num_from_be_bytes(cs, &block_hash[..16])?,
+            num_from_be_bytes(cs, &block_hash[16..])?,
+            num_from_be_bytes(cs, &storage.address)?,
+            num_from_be_bytes(cs, &reports_slot)?,
+        ];
+        for value in self.values.iter() {
+            let value = AllocatedTellorValue::from_witness(cs, value, self.reports_slot)?;
+            let is_current_valid = value.verify(cs, &storage_root, &reports_slot)?;
+            is_valid = Boolean::and(cs, &is_valid, &is_current_valid)?;
+            for chunk in [
+                &value.query_id[..16],
+                &value.query_id[16..],
+                &value.timestamp[..],
+                &value.value[..16],
+                &value.value[16..],
+            ] {
+                input.push(num_from_be_bytes(cs, chunk)?);
+            }
+        }
+        Boolean::enforce_equal(cs, &is_valid, &Boolean::constant(true))?;
+
+        let commitment = circuit_poseidon_hash(cs, &input)?;
+        let expected_commitment = Num::alloc(cs, Some(self.commitment))?;
+        expected_commitment.enforce_equal(cs, &commitment)?;
+        expected_commitment.get_variable().inputize(cs)?;
+        Ok(())
+    }
+
+    fn declare_used_gates() -> Result<Vec<Box<dyn GateInternal<E>>>, SynthesisError> {
+        Ok(vec![
+            Self::MainGate::default().into_internal(),
+            Rescue5CustomGate.into_internal(), // Just to standardize the proof format
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::bellman::{pairing::ff::Field, plonk::better_better_cs::cs::Circuit},
+        testing::{create_test_artifacts_with_optimized_gate, Bn256},
+    };
+
+    use super::TellorPriceOracle;
+    use crate::utils::testing::tellor_storage;
+
+    #[test]
+    fn test_tellor_price_oracle() -> anyhow::Result<()> {
+        let mut eth_usd = [0u8; 32];
+        eth_usd[20..].copy_from_slice(&2_530_120_000_000_000_000_000u128.to_be_bytes()[4..]);
+        let (storage, values) = tellor_storage(17, &[([1u8; 32], 1_700_000_000, eth_usd)]);
+        let circuit = TellorPriceOracle::<Bn256>::new(storage.clone(), 17, values.clone())?;
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        println!("Roughly {} gates", cs.n());
+
+        let mut tampered = circuit;
+        tampered.commitment.add_assign(&Field::one());
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        tampered.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+
+        // Values are proven at the slot of the reports mapping
+        assert!(TellorPriceOracle::<Bn256>::new(storage.clone(), 18, values.clone()).is_err());
+        let mut forged = values;
+        forged[0].value[31] ^= 1;
+        assert!(TellorPriceOracle::<Bn256>::new(storage, 17, forged).is_err());
+        Ok(())
+    }
+}
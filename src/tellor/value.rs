@@ -0,0 +1,184 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    utils::u64_to_fe,
+    vm::partitioner::smart_and,
+};
+use num_bigint::BigUint;
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    gadgets::{
+        keccak256,
+        mpt::{circuit_storage_value, storage_value, verify_proof, AllocatedMptProof},
+    },
+    utils::{num_from_be_bytes, num_into_be_bytes},
+};
+
+// Index of `valueByTimestamp` in the `Report` struct of Tellor, i.e. `(uint256[] timestamps,
+// mapping timestampIndex, mapping valueByTimestamp, ...)`
+const VALUE_BY_TIMESTAMP_INDEX: u8 = 2;
+// Length slot of `bytes` values of 32 bytes, i.e. `2 * length + 1`
+const VALUE_LENGTH_SLOT: u8 = 2 * 32 + 1;
+
+/// Value of `queryId` submitted to the Tellor oracle at `timestamp`, i.e.
+/// `reports[queryId].valueByTimestamp[timestamp]`, proven by storage proofs of its length slot and
+/// its data slot.
+///
+/// Only values of 32 bytes are supported, e.g. `abi.encode(uint256)` of `SpotPrice` queries,
+/// which are stored in the data slot `keccak256(slot)` of the length slot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TellorValue {
+    pub query_id: [u8; 32],
+    pub timestamp: u64,
+    pub value: [u8; 32],
+    pub length_proof: Vec<Vec<u8>>,
+    pub value_proof: Vec<Vec<u8>>,
+}
+
+impl TellorValue {
+    /// Slot of `reports[queryId].valueByTimestamp[timestamp]`, which holds the length of the value,
+    /// where `reports_slot` is the slot of the `reports` mapping.
+    pub fn length_slot(&self, reports_slot: u64) -> [u8; 32] {
+        let mut slot = [0u8; 32];
+        slot[24..].copy_from_slice(&reports_slot.to_be_bytes());
+        let report = Keccak256::new_with_prefix(self.query_id)
+            .chain_update(slot)
+            .finalize();
+        let value_by_timestamp = (BigUint::from_bytes_be(&report) + VALUE_BY_TIMESTAMP_INDEX)
+            % (BigUint::from(1u8) << 256);
+        let value_by_timestamp = value_by_timestamp.to_bytes_be();
+        let mut timestamp_and_slot = [0u8; 64];
+        timestamp_and_slot[24..32].copy_from_slice(&self.timestamp.to_be_bytes());
+        timestamp_and_slot[64 - value_by_timestamp.len()..].copy_from_slice(&value_by_timestamp);
+        Keccak256::digest(timestamp_and_slot).into()
+    }
+
+    /// Data slot of the value, i.e. `keccak256(length slot)`.
+    pub fn data_slot(&self, reports_slot: u64) -> [u8; 32] {
+        Keccak256::digest(self.length_slot(reports_slot)).into()
+    }
+
+    /// Keys of the length slot and the data slot in the storage trie.
+    fn storage_keys(&self, reports_slot: u64) -> ([u8; 32], [u8; 32]) {
+        let data_slot = self.data_slot(reports_slot);
+        (data_slot, Keccak256::digest(data_slot).into())
+    }
+
+    /// Check the value is 32 bytes and proven against `storage_root`.
+    pub fn check(&self, storage_root: &[u8; 32], reports_slot: u64) -> anyhow::Result<()> {
+        let (length_key, value_key) = self.storage_keys(reports_slot);
+        let length = storage_value(&verify_proof(
+            storage_root,
+            &length_key,
+            &self.length_proof,
+        )?)?;
+        let mut expected_length = [0u8; 32];
+        expected_length[31] = VALUE_LENGTH_SLOT;
+        if length != expected_length {
+            anyhow::bail!(
+                "value of query 0x{} at {} is not 32 bytes",
+                hex::encode(self.query_id),
+                self.timestamp
+            )
+        }
+        let value = storage_value(&verify_proof(storage_root, &value_key, &self.value_proof)?)?;
+        if value != self.value {
+            anyhow::bail!(
+                "value of query 0x{} at {} is 0x{}, expect 0x{}",
+                hex::encode(self.query_id),
+                self.timestamp,
+                hex::encode(value),
+                hex::encode(self.value)
+            )
+        }
+        Ok(())
+    }
+}
+
+/// Circuit representation of [`TellorValue`].
+#[derive(Debug, Clone)]
+pub struct AllocatedTellorValue<E: Engine> {
+    pub query_id: [Byte<E>; 32],
+    pub timestamp: [Byte<E>; 8],
+    pub value: [Byte<E>; 32],
+    pub length_proof: AllocatedMptProof<E>,
+    pub value_proof: AllocatedMptProof<E>,
+}
+
+impl<E: Engine> AllocatedTellorValue<E> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &TellorValue,
+        reports_slot: u64,
+    ) -> Result<Self, SynthesisError> {
+        let mut alloc_bytes = |bytes: &[u8]| {
+            bytes
+                .iter()
+                .map(|b| Byte::from_u8_witness(cs, Some(*b)))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let query_id = alloc_bytes(&witness.query_id)?.try_into().unwrap();
+        let timestamp = alloc_bytes(&witness.timestamp.to_be_bytes())?
+            .try_into()
+            .unwrap();
+        let value = alloc_bytes(&witness.value)?.try_into().unwrap();
+        let (length_key, value_key) = witness.storage_keys(reports_slot);
+        Ok(Self {
+            query_id,
+            timestamp,
+            value,
+            length_proof: AllocatedMptProof::from_witness(cs, &length_key, &witness.length_proof)?,
+            value_proof: AllocatedMptProof::from_witness(cs, &value_key, &witness.value_proof)?,
+        })
+    }
+
+    /// Same as [`TellorValue::check`], where `reports_slot` is in 8 big-endian bytes. Return
+    /// whether the value is 32 bytes and proven against `storage_root`.
+    pub fn verify<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        storage_root: &[Byte<E>; 32],
+        reports_slot: &[Byte<E>; 8],
+    ) -> Result<Boolean, SynthesisError> {
+        let mut bytes = self.query_id.to_vec();
+        bytes.extend([Byte::zero(); 24]);
+        bytes.extend(reports_slot);
+        let report = keccak256::digest(cs, &bytes)?;
+        // Adding the index to a hash never carries beyond its low 16 bytes in practice, which is
+        // enforced by the decomposition
+        let low = num_from_be_bytes(cs, &report[16..])?;
+        let low = low.add(
+            cs,
+            &Num::Constant(u64_to_fe(VALUE_BY_TIMESTAMP_INDEX as u64)),
+        )?;
+        let mut bytes = vec![Byte::zero(); 24];
+        bytes.extend(self.timestamp);
+        bytes.extend(&report[..16]);
+        bytes.extend(num_into_be_bytes(cs, &low, 16)?);
+        let length_slot = keccak256::digest(cs, &bytes)?;
+        let length_key = keccak256::digest(cs, &length_slot)?;
+        let value_key = keccak256::digest(cs, &length_key)?;
+
+        let mut is_valid = vec![];
+        let (is_proven, length) = self.length_proof.verify(cs, storage_root, &length_key)?;
+        let (is_length_valid, length) = circuit_storage_value(cs, &length)?;
+        is_valid.extend([is_proven, is_length_valid]);
+        for (i, byte) in length.iter().enumerate() {
+            let expected = if i == 31 { VALUE_LENGTH_SLOT } else { 0 };
+            let expected = Num::Constant(u64_to_fe(expected as u64));
+            is_valid.push(Num::equals(cs, &byte.inner, &expected)?);
+        }
+        let (is_proven, value) = self.value_proof.verify(cs, storage_root, &value_key)?;
+        let (is_value_valid, value) = circuit_storage_value(cs, &value)?;
+        is_valid.extend([is_proven, is_value_valid]);
+        for (a, b) in value.iter().zip(self.value.iter()) {
+            is_valid.push(Num::equals(cs, &a.inner, &b.inner)?);
+        }
+        smart_and(cs, &is_valid)
+    }
+}
@@ -0,0 +1,171 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{ff::Field, Engine};
+use advanced_circuit_component::franklin_crypto::{
+    bellman::{
+        plonk::better_better_cs::{
+            cs::{Circuit, ConstraintSystem, Gate, GateInternal},
+            gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
+        },
+        SynthesisError,
+    },
+    plonk::circuit::{allocated_num::Num, boolean::Boolean, custom_rescue_gate::Rescue5CustomGate},
+};
+use advanced_circuit_component::vm::partitioner::smart_and;
+use num_bigint::BigUint;
+
+use crate::{
+    gadgets::{
+        poseidon::{circuit_poseidon_hash, poseidon_hash},
+        rescue::circuit_rescue_hash,
+        storage::{AllocatedContractStorage, ContractStorage},
+    },
+    utils::{self, fr_from_biguint, num_from_be_bytes},
+};
+
+use super::observation::{
+    circuit_mean_tick, mean_tick, AllocatedObservation, ProvenObservation, TICK_OFFSET,
+};
+
+/// Circuit proving the time-weighted average price of a Uniswap V3 pool between two observations,
+/// which are proven by Ethereum storage proofs against a block. The TWAP is the arithmetic mean
+/// tick, from which consumers derive the price by `TickMath.getSqrtRatioAtTick`.
+///
+/// Public input is the commitment `poseidon(blockHash[..16], blockHash[16..], pool, start
+/// timestamp, end timestamp, mean tick + TICK_OFFSET)`. Consumers must check the committed block
+/// hash is canonical, e.g. by `blockhash`.
+#[derive(Debug, Clone)]
+pub struct UniswapTwapOracle<E: Engine> {
+    pub storage: ContractStorage,
+    pub start: ProvenObservation,
+    pub end: ProvenObservation,
+    pub mean_tick: i64,
+    pub commitment: E::Fr,
+}
+
+impl<E: Engine> UniswapTwapOracle<E> {
+    pub fn new(
+        storage: ContractStorage,
+        start: ProvenObservation,
+        end: ProvenObservation,
+    ) -> Result<Self, anyhow::Error> {
+        let storage_root = storage.storage_root()?;
+        start.check(&storage_root)?;
+        end.check(&storage_root)?;
+        let mean_tick = mean_tick(&start.observation, &end.observation)?;
+        let block_hash = storage.block_hash();
+        let commitment = poseidon_hash::<E>(&[
+            fr_from_biguint::<E>(&BigUint::from_bytes_be(&block_hash[..16]))?,
+            fr_from_biguint::<E>(&BigUint::from_bytes_be(&block_hash[16..]))?,
+            fr_from_biguint::<E>(&BigUint::from_bytes_be(&storage.address))?,
+            fr_from_biguint::<E>(&BigUint::from(start.observation.block_timestamp))?,
+            fr_from_biguint::<E>(&BigUint::from(end.observation.block_timestamp))?,
+            fr_from_biguint::<E>(&BigUint::from((mean_tick + TICK_OFFSET as i64) as u64))?,
+        ]);
+        Ok(Self {
+            storage,
+            start,
+            end,
+            mean_tick,
+            commitment,
+        })
+    }
+}
+
+impl<E: Engine> Circuit<E> for UniswapTwapOracle<E> {
+    type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
+
+    fn synthesize<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<(), SynthesisError> {
+        utils::add_bitwise_logic_and_range_table(cs)?;
+        let temp_variable = Num::alloc(cs, Some(E::Fr::one()))?;
+        circuit_rescue_hash(cs, &[temp_variable])?; // Just to standardize the proof format
+
+        let storage = AllocatedContractStorage::from_witness(cs, &self.storage)?;
+        let (is_storage_valid, block_hash, storage_root) = storage.verify(cs)?;
+        let start = AllocatedObservation::from_witness(cs, &self.start)?;
+        let (is_start_valid, start) = start.verify(cs, &storage_root)?;
+        let end = AllocatedObservation::from_witness(cs, &self.end)?;
+        let (is_end_valid, end) = end.verify(cs, &storage_root)?;
+        let is_valid = smart_and(cs, &[is_storage_valid, is_start_valid, is_end_valid])?;
+        Boolean::enforce_equal(cs, &is_valid, &Boolean::constant(true))?;
+        let mean_tick = circuit_mean_tick(
+            cs,
+            &start,
+            &end,
+            Some((&self.start.observation, &self.end.observation)),
+        )?;
+
+        let input = [
+            num_from_be_bytes(cs, &block_hash[..16])?,
+            num_from_be_bytes(cs, &block_hash[16..])?,
+            num_from_be_bytes(cs, &storage.address)?,
+            start.block_timestamp,
+            end.block_timestamp,
+            mean_tick,
+        ];
+        let commitment = circuit_poseidon_hash(cs, &input)?;
+        let expected_commitment = Num::alloc(cs, Some(self.commitment))?;
+        expected_commitment.enforce_equal(cs, &commitment)?;
+        expected_commitment.get_variable().inputize(cs)?;
+        Ok(())
+    }
+
+    fn declare_used_gates() -> Result<Vec<Box<dyn GateInternal<E>>>, SynthesisError> {
+        Ok(vec![
+            Self::MainGate::default().into_internal(),
+            Rescue5CustomGate.into_internal(), // Just to standardize the proof format
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::bellman::{pairing::ff::Field, plonk::better_better_cs::cs::Circuit},
+        testing::{create_test_artifacts_with_optimized_gate, Bn256},
+    };
+
+    use super::UniswapTwapOracle;
+    use crate::{uniswap::Observation, utils::testing::uniswap_storage};
+
+    #[test]
+    fn test_uniswap_twap_oracle() -> anyhow::Result<()> {
+        let observation = |block_timestamp, tick_cumulative| Observation {
+            block_timestamp,
+            tick_cumulative,
+            seconds_per_liquidity_cumulative_x128: [7u8; 20],
+            initialized: true,
+        };
+        // Mean tick of -201234.01 is rounded down
+        let (storage, observations) = uniswap_storage(&[
+            (3, observation(1_700_000_000, -5_000_000)),
+            (4, observation(1_700_000_600, -5_000_000 - 120_740_407)),
+        ]);
+        let circuit = UniswapTwapOracle::<Bn256>::new(
+            storage.clone(),
+            observations[0].clone(),
+            observations[1].clone(),
+        )?;
+        assert_eq!(circuit.mean_tick, -201_235);
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        println!("Roughly {} gates", cs.n());
+
+        let mut tampered = circuit;
+        tampered.commitment.add_assign(&Field::one());
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        tampered.synthesize(&mut cs)?;
+        assert!(!cs.is_satisfied());
+
+        // Observations must be in order and proven at their index
+        assert!(UniswapTwapOracle::<Bn256>::new(
+            storage.clone(),
+            observations[1].clone(),
+            observations[0].clone()
+        )
+        .is_err());
+        let mut forged = observations[1].clone();
+        forged.index = 5;
+        assert!(UniswapTwapOracle::<Bn256>::new(storage, observations[0].clone(), forged).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,5 @@
+mod circuit;
+mod observation;
+
+pub use circuit::*;
+pub use observation::*;
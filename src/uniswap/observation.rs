@@ -0,0 +1,336 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{plonk::better_better_cs::cs::ConstraintSystem, SynthesisError},
+        plonk::circuit::{allocated_num::Num, boolean::Boolean},
+    },
+    utils::u64_to_fe,
+    vm::partitioner::smart_and,
+};
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    gadgets::{
+        keccak256,
+        mpt::{circuit_storage_value, storage_value, verify_proof, AllocatedMptProof},
+    },
+    utils::{num_from_be_bytes, num_into_be_bytes},
+};
+
+// Slot of `observations` of Uniswap V3 pools, after `slot0`, two fee growths, `protocolFees`,
+// `liquidity`, `ticks`, `tickBitmap` and `positions`
+pub const OBSERVATIONS_SLOT: u64 = 8;
+// Offset added to mean ticks committed by circuits, so that they are non-negative
+pub const TICK_OFFSET: u64 = 1 << 24;
+
+/// Observation of a Uniswap V3 pool, packed in one slot as `initialized ||
+/// secondsPerLiquidityCumulativeX128 || tickCumulative || blockTimestamp` in big-endian, where
+/// `tickCumulative` is `int56`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Observation {
+    pub block_timestamp: u32,
+    pub tick_cumulative: i64,
+    pub seconds_per_liquidity_cumulative_x128: [u8; 20],
+    pub initialized: bool,
+}
+
+impl Observation {
+    pub fn to_slot(&self) -> [u8; 32] {
+        let mut slot = [0u8; 32];
+        slot[0] = self.initialized as u8;
+        slot[1..21].copy_from_slice(&self.seconds_per_liquidity_cumulative_x128);
+        slot[21..28].copy_from_slice(&self.tick_cumulative.to_be_bytes()[1..]);
+        slot[28..].copy_from_slice(&self.block_timestamp.to_be_bytes());
+        slot
+    }
+
+    pub fn from_slot(slot: &[u8; 32]) -> anyhow::Result<Self> {
+        if slot[0] > 1 {
+            anyhow::bail!("invalid observation 0x{}", hex::encode(slot))
+        }
+        let mut tick_cumulative = [0u8; 8];
+        tick_cumulative[1..].copy_from_slice(&slot[21..28]);
+        Ok(Self {
+            block_timestamp: u32::from_be_bytes(slot[28..].try_into().unwrap()),
+            // Sign-extend int56
+            tick_cumulative: i64::from_be_bytes(tick_cumulative) << 8 >> 8,
+            seconds_per_liquidity_cumulative_x128: slot[1..21].try_into().unwrap(),
+            initialized: slot[0] == 1,
+        })
+    }
+}
+
+/// Arithmetic mean tick between observations, i.e. `(end.tickCumulative - start.tickCumulative) /
+/// (end.blockTimestamp - start.blockTimestamp)` rounded towards negative infinity, in the same way
+/// as `OracleLibrary.consult` of Uniswap V3.
+pub fn mean_tick(start: &Observation, end: &Observation) -> anyhow::Result<i64> {
+    if !start.initialized || !end.initialized {
+        anyhow::bail!("observation is not initialized")
+    }
+    if end.block_timestamp <= start.block_timestamp {
+        anyhow::bail!(
+            "observation at {} is not after {}",
+            end.block_timestamp,
+            start.block_timestamp
+        )
+    }
+    let elapsed = (end.block_timestamp - start.block_timestamp) as i64;
+    let tick = (end.tick_cumulative - start.tick_cumulative).div_euclid(elapsed);
+    if tick.unsigned_abs() >= TICK_OFFSET {
+        anyhow::bail!("mean tick {} is out of range", tick)
+    }
+    Ok(tick)
+}
+
+/// Observation at `index` of `observations`, proven by the storage proof of its slot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProvenObservation {
+    pub index: u16,
+    pub observation: Observation,
+    pub proof: Vec<Vec<u8>>,
+}
+
+impl ProvenObservation {
+    /// Key of the observation in the storage trie, i.e. `keccak256(OBSERVATIONS_SLOT + index)`.
+    pub fn storage_key(&self) -> [u8; 32] {
+        let mut slot = [0u8; 32];
+        slot[24..].copy_from_slice(&(OBSERVATIONS_SLOT + self.index as u64).to_be_bytes());
+        Keccak256::digest(slot).into()
+    }
+
+    /// Check the observation is proven against `storage_root`.
+    pub fn check(&self, storage_root: &[u8; 32]) -> anyhow::Result<()> {
+        let slot = storage_value(&verify_proof(
+            storage_root,
+            &self.storage_key(),
+            &self.proof,
+        )?)?;
+        if slot != self.observation.to_slot() {
+            anyhow::bail!(
+                "observation {} is 0x{}, expect 0x{}",
+                self.index,
+                hex::encode(slot),
+                hex::encode(self.observation.to_slot())
+            )
+        }
+        Ok(())
+    }
+}
+
+/// Circuit representation of [`ProvenObservation`].
+#[derive(Debug, Clone)]
+pub struct AllocatedObservation<E: Engine> {
+    pub index: [Byte<E>; 2],
+    pub proof: AllocatedMptProof<E>,
+}
+
+/// Fields of an observation verified by [`AllocatedObservation::verify`].
+#[derive(Debug, Clone, Copy)]
+pub struct VerifiedObservation<E: Engine> {
+    pub block_timestamp: Num<E>,
+    /// Signed tick cumulative, i.e. negative values are `p - |tickCumulative|`
+    pub tick_cumulative: Num<E>,
+}
+
+impl<E: Engine> AllocatedObservation<E> {
+    pub fn from_witness<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        witness: &ProvenObservation,
+    ) -> Result<Self, SynthesisError> {
+        let mut index = [Byte::zero(); 2];
+        for (byte, b) in index.iter_mut().zip(witness.index.to_be_bytes()) {
+            *byte = Byte::from_u8_witness(cs, Some(b))?;
+        }
+        Ok(Self {
+            index,
+            proof: AllocatedMptProof::from_witness(cs, &witness.storage_key(), &witness.proof)?,
+        })
+    }
+
+    /// Same as [`ProvenObservation::check`], and also check the observation is initialized.
+    /// Return the verified observation, which is only meaningful if the check passes.
+    pub fn verify<CS: ConstraintSystem<E>>(
+        &self,
+        cs: &mut CS,
+        storage_root: &[Byte<E>; 32],
+    ) -> Result<(Boolean, VerifiedObservation<E>), SynthesisError> {
+        let index = num_from_be_bytes(cs, &self.index)?;
+        let slot = index.add(cs, &Num::Constant(u64_to_fe(OBSERVATIONS_SLOT)))?;
+        let mut bytes = vec![Byte::zero(); 29];
+        bytes.extend(num_into_be_bytes(cs, &slot, 3)?);
+        let key = keccak256::digest(cs, &bytes)?;
+
+        let (is_proven, value) = self.proof.verify(cs, storage_root, &key)?;
+        let (is_value_valid, slot) = circuit_storage_value(cs, &value)?;
+        let is_initialized = Num::equals(cs, &slot[0].inner, &Num::Constant(u64_to_fe(1)))?;
+
+        // int56 is negative if its top bit is set
+        let sign = slot[21].inner.into_bits_le(cs, Some(8))?[7];
+        let tick_cumulative = num_from_be_bytes(cs, &slot[21..28])?;
+        let tick_cumulative = tick_cumulative.sub(
+            cs,
+            &Num::from_boolean_is(sign).mul(cs, &Num::Constant(u64_to_fe(1 << 56)))?,
+        )?;
+        let observation = VerifiedObservation {
+            block_timestamp: num_from_be_bytes(cs, &slot[28..])?,
+            tick_cumulative,
+        };
+        let is_valid = smart_and(cs, &[is_proven, is_value_valid, is_initialized])?;
+        Ok((is_valid, observation))
+    }
+}
+
+/// Same as [`mean_tick`], returning the mean tick plus [`TICK_OFFSET`], where `witness` is the
+/// pair of observations to compute the division natively. Circuit is unsatisfied unless `end` is
+/// after `start` and the mean tick is in range.
+pub fn circuit_mean_tick<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    start: &VerifiedObservation<E>,
+    end: &VerifiedObservation<E>,
+    witness: Option<(&Observation, &Observation)>,
+) -> Result<Num<E>, SynthesisError> {
+    let elapsed = end.block_timestamp.sub(cs, &start.block_timestamp)?;
+    let delta = end.tick_cumulative.sub(cs, &start.tick_cumulative)?;
+    // 0 < elapsed < 2^32
+    elapsed
+        .sub(cs, &Num::Constant(u64_to_fe(1)))?
+        .into_bits_le(cs, Some(32))?;
+
+    // delta = tick * elapsed + remainder, where 0 <= remainder < elapsed and 0 < tick +
+    // TICK_OFFSET < 2^25 as |tick| < TICK_OFFSET, so that both sides never wrap the field. The
+    // witness skips the range check of `mean_tick`, which is left to the circuit
+    let witness = witness.and_then(|(start, end)| {
+        let elapsed = end.block_timestamp.checked_sub(start.block_timestamp)? as i64;
+        let delta = end.tick_cumulative - start.tick_cumulative;
+        let tick = delta.checked_div_euclid(elapsed)?;
+        Some((
+            (tick + TICK_OFFSET as i64) as u64,
+            delta.rem_euclid(elapsed) as u64,
+        ))
+    });
+    let offset_tick = Num::alloc(cs, witness.map(|(tick, _)| u64_to_fe(tick)))?;
+    offset_tick.into_bits_le(cs, Some(25))?;
+    offset_tick
+        .sub(cs, &Num::Constant(u64_to_fe(1)))?
+        .into_bits_le(cs, Some(25))?;
+    let remainder = Num::alloc(cs, witness.map(|(_, remainder)| u64_to_fe(remainder)))?;
+    remainder.into_bits_le(cs, Some(32))?;
+    elapsed
+        .sub(cs, &remainder)?
+        .sub(cs, &Num::Constant(u64_to_fe(1)))?
+        .into_bits_le(cs, Some(32))?;
+    offset_tick
+        .sub(cs, &Num::Constant(u64_to_fe(TICK_OFFSET)))?
+        .mul(cs, &elapsed)?
+        .add(cs, &remainder)?
+        .enforce_equal(cs, &delta)?;
+    Ok(offset_tick)
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        circuit_structures::byte::Byte,
+        franklin_crypto::{
+            bellman::{pairing::bn256::Bn256, SynthesisError},
+            plonk::circuit::allocated_num::Num,
+        },
+        utils::u64_to_fe,
+    };
+
+    use super::{
+        circuit_mean_tick, mean_tick, AllocatedObservation, Observation, VerifiedObservation,
+        TICK_OFFSET,
+    };
+    use crate::utils::{
+        fr_from_biguint,
+        testing::{create_test_constraint_system, uniswap_storage},
+    };
+
+    #[test]
+    fn test_observation() -> Result<(), SynthesisError> {
+        let start = Observation {
+            block_timestamp: 1_700_000_000,
+            tick_cumulative: -(1 << 40),
+            seconds_per_liquidity_cumulative_x128: [9u8; 20],
+            initialized: true,
+        };
+        assert_eq!(Observation::from_slot(&start.to_slot()).unwrap(), start);
+        let end = Observation {
+            block_timestamp: 1_700_001_800,
+            tick_cumulative: -(1 << 40) + 1800 * 76_012,
+            ..start
+        };
+        assert_eq!(mean_tick(&start, &end).unwrap(), 76_012);
+        assert!(mean_tick(&end, &start).is_err());
+
+        let (storage, observations) = uniswap_storage(&[(0, start), (65_535, end)]);
+        let storage_root = storage.storage_root().unwrap();
+        let cs = &mut create_test_constraint_system()?;
+        let root = storage_root.map(|b| Byte::from_u8_witness(cs, Some(b)).unwrap());
+        let mut verified = vec![];
+        for observation in observations.iter() {
+            observation.check(&storage_root).unwrap();
+            let allocated = AllocatedObservation::<Bn256>::from_witness(cs, observation)?;
+            let (is_valid, observation) = allocated.verify(cs, &root)?;
+            assert_eq!(is_valid.get_value(), Some(true));
+            verified.push(observation);
+        }
+        let tick = circuit_mean_tick(cs, &verified[0], &verified[1], Some((&start, &end)))?;
+        assert_eq!(
+            tick.get_value(),
+            Some(fr_from_biguint::<Bn256>(&(TICK_OFFSET + 76_012).into())?)
+        );
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+
+    #[test]
+    fn test_mean_tick_range() -> Result<(), SynthesisError> {
+        let start = Observation {
+            block_timestamp: 1_700_000_000,
+            tick_cumulative: 0,
+            seconds_per_liquidity_cumulative_x128: [0u8; 20],
+            initialized: true,
+        };
+        let max = TICK_OFFSET as i64 - 1;
+        // Out-of-range ticks fit in 32 bits once offset, and must still be rejected
+        for (tick, expected) in [
+            (max, true),
+            (-max, true),
+            (max + 1, false),
+            (-max - 1, false),
+            (1 << 31, false),
+        ] {
+            let end = Observation {
+                block_timestamp: 1_700_001_800,
+                tick_cumulative: tick * 1800,
+                ..start
+            };
+            assert_eq!(mean_tick(&start, &end).is_ok(), expected);
+
+            let cs = &mut create_test_constraint_system()?;
+            let verified = [start, end]
+                .iter()
+                .map(|observation| {
+                    let abs = observation.tick_cumulative.unsigned_abs();
+                    let abs = Num::alloc(cs, Some(u64_to_fe(abs)))?;
+                    let tick_cumulative = if observation.tick_cumulative < 0 {
+                        Num::zero().sub(cs, &abs)?
+                    } else {
+                        abs
+                    };
+                    let timestamp = u64_to_fe(observation.block_timestamp as u64);
+                    Ok(VerifiedObservation {
+                        block_timestamp: Num::alloc(cs, Some(timestamp))?,
+                        tick_cumulative,
+                    })
+                })
+                .collect::<Result<Vec<VerifiedObservation<Bn256>>, SynthesisError>>()?;
+            circuit_mean_tick(cs, &verified[0], &verified[1], Some((&start, &end)))?;
+            assert_eq!(cs.is_satisfied(), expected);
+        }
+        Ok(())
+    }
+}
@@ -3,7 +3,9 @@ use crate::franklin_crypto::bellman::plonk::better_better_cs::cs::{
 };
 use crate::franklin_crypto::plonk::circuit::tables::inscribe_default_range_table_for_bit_width_over_first_three_columns;
 use advanced_circuit_component::circuit_structures::byte::Byte;
-use advanced_circuit_component::franklin_crypto::bellman::pairing::ff::{PrimeField, ScalarEngine};
+use advanced_circuit_component::franklin_crypto::bellman::pairing::ff::{
+    Field, PrimeField, ScalarEngine,
+};
 use advanced_circuit_component::franklin_crypto::bellman::pairing::Engine;
 use advanced_circuit_component::franklin_crypto::bellman::SynthesisError;
 use advanced_circuit_component::franklin_crypto::plonk::circuit::boolean::Boolean;
@@ -18,12 +20,68 @@ use advanced_circuit_component::{
 };
 use num_bigint::BigUint;
 use std::str::FromStr;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
 
 pub fn new_synthesis_error<T: ToString>(msg: T) -> SynthesisError {
     let err = std::io::Error::new(std::io::ErrorKind::Other, msg.to_string());
     SynthesisError::from(err)
 }
 
+/// Token for cooperative cancellation of long-running witness generation and synthesis.
+///
+/// Clones share the same state, so the proving service can keep one clone and cancel a
+/// superseded job while the job checks its own clone at chunk boundaries.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// Returns error if the token has been cancelled.
+    pub fn check(&self) -> Result<(), SynthesisError> {
+        if self.is_cancelled() {
+            return Err(new_synthesis_error("cancelled"));
+        }
+        Ok(())
+    }
+}
+
+/// Serialize fixed-size byte arrays as hex strings, since serde only supports arrays of up to 32
+/// elements.
+pub mod serde_hex {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer, const N: usize>(
+        bytes: &[u8; N],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+        deserializer: D,
+    ) -> Result<[u8; N], D::Error> {
+        let s = String::deserialize(deserializer)?;
+        let bytes = hex::decode(s.strip_prefix("0x").unwrap_or(&s)).map_err(D::Error::custom)?;
+        bytes
+            .try_into()
+            .map_err(|b: Vec<u8>| D::Error::invalid_length(b.len(), &N.to_string().as_str()))
+    }
+}
+
 pub fn num_from_be_bytes<CS: ConstraintSystem<E>, E: Engine>(
     cs: &mut CS,
     hash: &[Byte<E>],
@@ -36,6 +94,31 @@ pub fn num_from_be_bytes<CS: ConstraintSystem<E>, E: Engine>(
     uint.to_num_unchecked(cs)
 }
 
+/// Decompose `num` into `len` big-endian bytes, which constrains `num` to `8 * len` bits. Any
+/// field element fits 32 bytes, whose leading bits beyond the field size are zero.
+pub fn num_into_be_bytes<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    num: &Num<E>,
+    len: usize,
+) -> Result<Vec<Byte<E>>, SynthesisError> {
+    let num_bits = std::cmp::min(len * 8, E::Fr::NUM_BITS as usize);
+    let mut bits = num.into_bits_le(cs, Some(num_bits))?;
+    bits.resize(len * 8, Boolean::constant(false));
+    let mut bytes = vec![];
+    for chunk in bits.chunks(8) {
+        let mut byte = Num::zero();
+        let mut coef = E::Fr::one();
+        for bit in chunk.iter() {
+            let bit = Num::from_boolean_is(*bit).mul(cs, &Num::Constant(coef))?;
+            byte = byte.add(cs, &bit)?;
+            coef.double();
+        }
+        bytes.push(Byte::from_num_unconstrained(cs, byte));
+    }
+    bytes.reverse();
+    Ok(bytes)
+}
+
 pub fn uint256_from_bytes_with_mask<E: Engine, CS: ConstraintSystem<E>>(
     cs: &mut CS,
     bytes: &[Byte<E>],
@@ -118,6 +201,275 @@ pub fn add_bitwise_logic_and_range_table<E: Engine, CS: ConstraintSystem<E>>(
     Ok(())
 }
 
+/// Signers for tests and test vectors, which must never be used with real keys.
+#[cfg(any(test, feature = "golden-vectors", feature = "gate-report"))]
+pub mod signer {
+    use advanced_circuit_component::franklin_crypto::bellman::pairing::{
+        bn256::{Fr, G1Affine, G2Affine},
+        ff::PrimeField,
+        CurveAffine, CurveProjective,
+    };
+    use ed25519_dalek::{Signer, SigningKey};
+    use num_bigint::BigUint;
+    use p256::{ecdsa::signature::hazmat::PrehashSigner, elliptic_curve::sec1::ToEncodedPoint};
+    use secp256k1::{Keypair, Message, Secp256k1, SecretKey};
+    use sha3::{Digest, Keccak256};
+
+    use crate::{
+        gadgets::{
+            bn254::{g1_to_bytes, g2_to_bytes, hash_to_g1},
+            schnorr::{be_bytes32, challenge, secp256k1_order},
+        },
+        redstone::witness::{DataPackage, SignedPackageWitness},
+    };
+
+    fn pubkey_of(secret_key: &SecretKey) -> [u8; 64] {
+        secret_key
+            .public_key(&Secp256k1::new())
+            .serialize_uncompressed()[1..]
+            .try_into()
+            .unwrap()
+    }
+
+    fn address_of(pubkey: &[u8; 64]) -> [u8; 20] {
+        let hash = Keccak256::new_with_prefix(pubkey).finalize();
+        hash[12..].try_into().unwrap()
+    }
+
+    /// Deterministic secp256k1 signer of RedStone data packages, which signs keccak256 of the
+    /// serialized package and appends `v = recovery id + 27` like RedStone signers do.
+    #[derive(Clone, Debug)]
+    pub struct RedstoneSigner {
+        secret_key: SecretKey,
+    }
+
+    impl RedstoneSigner {
+        /// Signer whose secret key is filled with `seed`, where 0x11 is the signer used in
+        /// RedStone protocol tests. `seed` must be non-zero.
+        pub fn new(seed: u8) -> Self {
+            Self {
+                secret_key: SecretKey::from_slice(&[seed; 32]).expect("seed must be non-zero"),
+            }
+        }
+
+        /// `n` distinct signers with seeds `1..=n`.
+        pub fn generate(n: u8) -> Vec<Self> {
+            (1..=n).map(Self::new).collect()
+        }
+
+        pub fn address(&self) -> [u8; 20] {
+            address_of(&self.pubkey())
+        }
+
+        /// Uncompressed public key `x || y`.
+        pub fn pubkey(&self) -> [u8; 64] {
+            pubkey_of(&self.secret_key)
+        }
+
+        /// Sign the data package, returning the signature and the address of signer.
+        pub fn sign(&self, data_package: &DataPackage) -> ([u8; 65], [u8; 20]) {
+            (
+                self.sign_hash(&data_package.keccak256_hash()),
+                self.address(),
+            )
+        }
+
+        /// Sign the 32-bytes message hash directly.
+        pub fn sign_hash(&self, hash: &[u8; 32]) -> [u8; 65] {
+            let message = Message::from_digest_slice(hash).unwrap();
+            let (recid, compact) = Secp256k1::new()
+                .sign_ecdsa_recoverable(&message, &self.secret_key)
+                .serialize_compact();
+            let mut signature = [0u8; 65];
+            signature[..64].copy_from_slice(&compact);
+            signature[64] = recid.to_i32() as u8 + 27;
+            signature
+        }
+
+        /// Sign the data package into a witness expecting this signer.
+        pub fn sign_witness(&self, data_package: DataPackage) -> SignedPackageWitness {
+            let (signature, signer) = self.sign(&data_package);
+            SignedPackageWitness::new(data_package, signature, signer)
+        }
+    }
+
+    /// Deterministic ed25519 signer of Tendermint validators.
+    #[derive(Clone, Debug)]
+    pub struct Ed25519Signer {
+        signing_key: SigningKey,
+    }
+
+    impl Ed25519Signer {
+        /// Signer whose secret key is filled with `seed`.
+        pub fn new(seed: u8) -> Self {
+            Self {
+                signing_key: SigningKey::from_bytes(&[seed; 32]),
+            }
+        }
+
+        /// `n` distinct signers with seeds `1..=n`.
+        pub fn generate(n: u8) -> Vec<Self> {
+            (1..=n).map(Self::new).collect()
+        }
+
+        pub fn pubkey(&self) -> [u8; 32] {
+            self.signing_key.verifying_key().to_bytes()
+        }
+
+        pub fn sign(&self, message: &[u8]) -> [u8; 64] {
+            self.signing_key.sign(message).to_bytes()
+        }
+    }
+
+    /// Deterministic secp256r1 signer of HSM-backed feeds, which signs 32-bytes message hashes.
+    #[derive(Clone, Debug)]
+    pub struct P256Signer {
+        signing_key: p256::ecdsa::SigningKey,
+    }
+
+    impl P256Signer {
+        /// Signer whose secret key is filled with `seed`. `seed` must be non-zero.
+        pub fn new(seed: u8) -> Self {
+            Self {
+                signing_key: p256::ecdsa::SigningKey::from_slice(&[seed; 32])
+                    .expect("seed must be non-zero"),
+            }
+        }
+
+        /// Uncompressed public key `x || y`.
+        pub fn pubkey(&self) -> [u8; 64] {
+            self.signing_key
+                .verifying_key()
+                .to_encoded_point(false)
+                .as_bytes()[1..]
+                .try_into()
+                .unwrap()
+        }
+
+        /// Sign the 32-bytes message hash directly, returning `r || s`.
+        pub fn sign_hash(&self, hash: &[u8; 32]) -> [u8; 64] {
+            let signature: p256::ecdsa::Signature = self.signing_key.sign_prehash(hash).unwrap();
+            signature.to_bytes().into()
+        }
+    }
+
+    /// Schnorr signer of Chronicle Protocol on behalf of several signers at once, whose signatures
+    /// verify against the sum of their public keys.
+    #[derive(Clone, Debug)]
+    pub struct SchnorrSigner {
+        secret_key: SecretKey,
+    }
+
+    impl SchnorrSigner {
+        pub fn aggregate(signers: &[RedstoneSigner]) -> Self {
+            let order = secp256k1_order();
+            let secret_key = signers.iter().fold(BigUint::default(), |sum, signer| {
+                (sum + BigUint::from_bytes_be(&signer.secret_key.secret_bytes())) % &order
+            });
+            Self {
+                secret_key: SecretKey::from_slice(&be_bytes32(&secret_key))
+                    .expect("aggregated key must be non-zero"),
+            }
+        }
+
+        /// Aggregated public key `x || y`.
+        pub fn pubkey(&self) -> [u8; 64] {
+            pubkey_of(&self.secret_key)
+        }
+
+        /// Sign `message` with a nonce derived from the key and the message, returning the
+        /// signature and the commitment, i.e. address of the nonce point.
+        pub fn sign(&self, message: &[u8; 32]) -> ([u8; 32], [u8; 20]) {
+            let order = secp256k1_order();
+            let secret_key = BigUint::from_bytes_be(&self.secret_key.secret_bytes());
+            let nonce = Keccak256::new()
+                .chain_update(self.secret_key.secret_bytes())
+                .chain_update(message)
+                .finalize();
+            let nonce = BigUint::from_bytes_be(&nonce) % &order;
+            let nonce_key = SecretKey::from_slice(&be_bytes32(&nonce)).expect("nonce is non-zero");
+            let commitment = address_of(&pubkey_of(&nonce_key));
+
+            let pubkey = self.pubkey();
+            let e = challenge(
+                pubkey[..32].try_into().unwrap(),
+                pubkey[63] & 1,
+                message,
+                &commitment,
+            );
+            let signature = (nonce + BigUint::from_bytes_be(&e) * secret_key) % &order;
+            (be_bytes32(&signature), commitment)
+        }
+    }
+
+    /// BIP-340 Schnorr signer with x-only public keys, which signs 32-bytes messages without
+    /// auxiliary randomness.
+    #[derive(Clone, Debug)]
+    pub struct Bip340Signer {
+        keypair: Keypair,
+    }
+
+    impl Bip340Signer {
+        /// Signer whose secret key is filled with `seed`. `seed` must be non-zero.
+        pub fn new(seed: u8) -> Self {
+            Self {
+                keypair: Keypair::from_seckey_slice(&Secp256k1::new(), &[seed; 32])
+                    .expect("seed must be non-zero"),
+            }
+        }
+
+        /// x-only public key.
+        pub fn pubkey(&self) -> [u8; 32] {
+            self.keypair.x_only_public_key().0.serialize()
+        }
+
+        pub fn sign(&self, message: &[u8; 32]) -> [u8; 64] {
+            Secp256k1::new()
+                .sign_schnorr_no_aux_rand(&Message::from_digest(*message), &self.keypair)
+                .as_ref()
+                .try_into()
+                .unwrap()
+        }
+    }
+
+    /// BLS signer over BN254 of EigenLayer-style operators, with public keys in G1 and G2 and
+    /// signatures in G1.
+    #[derive(Clone, Debug)]
+    pub struct BlsSigner {
+        secret_key: Fr,
+    }
+
+    impl BlsSigner {
+        /// Signer whose secret key is `seed * 2^128 + 1`. `seed` must be non-zero.
+        pub fn new(seed: u8) -> Self {
+            let secret_key = (BigUint::from(seed) << 128u32) + BigUint::from(1u8);
+            Self {
+                secret_key: Fr::from_str(&secret_key.to_string()).unwrap(),
+            }
+        }
+
+        /// `n` distinct signers with seeds `1..=n`.
+        pub fn generate(n: u8) -> Vec<Self> {
+            (1..=n).map(Self::new).collect()
+        }
+
+        /// Public key in G1, i.e. `x || y`.
+        pub fn pubkey(&self) -> [u8; 64] {
+            g1_to_bytes(&G1Affine::one().mul(self.secret_key).into_affine())
+        }
+
+        /// Public key in G2, in the layout of EVM pairing precompiles.
+        pub fn pubkey_g2(&self) -> [u8; 128] {
+            g2_to_bytes(&G2Affine::one().mul(self.secret_key).into_affine())
+        }
+
+        /// Sign the 32-bytes message hash mapped to G1 by [`hash_to_g1`].
+        pub fn sign(&self, hash: &[u8; 32]) -> [u8; 64] {
+            g1_to_bytes(&hash_to_g1(hash).mul(self.secret_key).into_affine())
+        }
+    }
+}
+
 #[cfg(test)]
 pub mod testing {
     use advanced_circuit_component::franklin_crypto::bellman::pairing::{bn256::Bn256, Engine};
@@ -131,6 +483,453 @@ pub mod testing {
             SynthesisError,
         },
     };
+    use proptest::{collection, prelude::*};
+    use sha3::{Digest, Keccak256};
+
+    use crate::{
+        avs::{Attestation, Operator, PriceReport},
+        band::{encode_varint, OracleResult, Vote},
+        chainlink::{AggregatorAnswer, AggregatorSlots, Transmission},
+        gadgets::{
+            bn254::{
+                aggregate_g1, aggregate_g2, g1_from_bytes, g1_to_bytes, g2_from_bytes, g2_to_bytes,
+            },
+            storage::ContractStorage,
+        },
+        redstone::{
+            witness::{convert_bytes_to_number, DataPackage, DataPoint},
+            DEFAULT_NUM_VALUE_DECIMALS,
+        },
+        supra::{hash_sorted_pair, CommitteeBatch, CommitteeFeed, ProvenFeed},
+        tellor::TellorValue,
+        uniswap::{Observation, ProvenObservation},
+    };
+
+    pub use super::signer::{BlsSigner, Ed25519Signer, RedstoneSigner};
+
+    /// Strategy of data points with feed ids of 1 to 31 ASCII bytes and values of any 32 bytes,
+    /// biased to zero and max-width values.
+    pub fn arb_data_point() -> impl Strategy<Value = DataPoint> {
+        ("[A-Z0-9/_-]{1,31}", arb_value())
+            .prop_map(|(feed_id, value)| DataPoint::new(feed_id, value))
+    }
+
+    /// Strategy of data packages with up to `max_data_points` data points of distinct feed ids,
+    /// including heartbeats without data points, and timestamps of any 6 bytes.
+    pub fn arb_data_package(max_data_points: usize) -> impl Strategy<Value = DataPackage> {
+        let feed_ids = collection::btree_set("[A-Z0-9/_-]{1,31}", 0..=max_data_points);
+        let values = collection::vec(arb_value(), max_data_points);
+        (feed_ids, values, 0u64..1 << 48).prop_map(|(feed_ids, values, timestamp)| {
+            let data_points = feed_ids
+                .into_iter()
+                .zip(values)
+                .map(|(feed_id, value)| DataPoint::new(feed_id, value))
+                .collect();
+            DataPackage::new(data_points, timestamp)
+        })
+    }
+
+    fn arb_value() -> impl Strategy<Value = String> {
+        prop_oneof![
+            1 => Just([0u8; 32]),
+            1 => Just([0xffu8; 32]),
+            8 => any::<[u8; 32]>(),
+        ]
+        .prop_map(|bytes| convert_bytes_to_number(&bytes, DEFAULT_NUM_VALUE_DECIMALS as u8))
+    }
+
+    /// BandChain result of the standard price oracle script for `symbols` at `rates`.
+    pub fn band_oracle_result(request_id: u64, symbols: &[&str], rates: &[u64]) -> OracleResult {
+        let mut calldata = (symbols.len() as u32).to_be_bytes().to_vec();
+        for symbol in symbols {
+            calldata.extend((symbol.len() as u32).to_be_bytes());
+            calldata.extend(symbol.as_bytes());
+        }
+        calldata.extend(1_000_000_000u64.to_be_bytes());
+        let mut result = (rates.len() as u32).to_be_bytes().to_vec();
+        for rate in rates {
+            result.extend(rate.to_be_bytes());
+        }
+        OracleResult {
+            client_id: "zklink".to_string(),
+            oracle_script_id: 360,
+            calldata,
+            ask_count: 16,
+            min_count: 10,
+            request_id,
+            ans_count: 16,
+            request_time: 1_700_000_000,
+            resolve_time: 1_700_000_006,
+            resolve_status: 1,
+            result,
+        }
+    }
+
+    /// Precommit of `block_hash` at `height` on chain `laozi-mainnet` signed by `signer`.
+    pub fn band_vote(signer: &Ed25519Signer, height: u64, block_hash: &[u8; 32]) -> Vote {
+        let mut head = vec![0x08, 0x02, 0x11];
+        head.extend(height.to_le_bytes());
+        head.extend([0x22, 0x48, 0x0a, 0x20]);
+        let mut signed_data_suffix = vec![0x12, 0x24, 0x08, 0x01, 0x12, 0x20];
+        signed_data_suffix.extend([0x33; 32]);
+        let seconds = encode_varint(1_700_000_000);
+        signed_data_suffix.extend([0x2a, seconds.len() as u8 + 1, 0x08]);
+        signed_data_suffix.extend(seconds);
+        signed_data_suffix.extend([0x32, 13]);
+        signed_data_suffix.extend(b"laozi-mainnet");
+
+        let mut signed_data_prefix =
+            encode_varint((head.len() + 32 + signed_data_suffix.len()) as u64);
+        signed_data_prefix.extend(head);
+        let mut vote = Vote {
+            signed_data_prefix,
+            signed_data_suffix,
+            signature: [0u8; 64],
+        };
+        vote.signature = signer.sign(&vote.sign_bytes(block_hash));
+        vote
+    }
+
+    /// Batch of Supra feeds of `(pair, price)` with 8 decimals, proven against the root of a Merkle
+    /// tree of sorted pairs whose odd nodes are promoted, which is signed by committee 7.
+    pub fn supra_batch(prices: &[(u32, u128)]) -> CommitteeBatch {
+        let feeds = prices
+            .iter()
+            .map(|(pair, price)| CommitteeFeed {
+                pair: *pair,
+                price: *price,
+                timestamp: 1_700_000_000_000,
+                decimals: 8,
+                round: 1_700_000_000,
+            })
+            .collect::<Vec<_>>();
+        let mut proofs = vec![vec![]; feeds.len()];
+        let mut level = feeds
+            .iter()
+            .enumerate()
+            .map(|(i, feed)| (feed.leaf(), vec![i]))
+            .collect::<Vec<_>>();
+        while level.len() > 1 {
+            let mut next = vec![];
+            for chunk in level.chunks(2) {
+                if let [(l, li), (r, ri)] = chunk {
+                    li.iter().for_each(|i| proofs[*i].push(*r));
+                    ri.iter().for_each(|i| proofs[*i].push(*l));
+                    next.push((hash_sorted_pair(l, r), [&li[..], &ri[..]].concat()));
+                } else {
+                    next.push(chunk[0].clone());
+                }
+            }
+            level = next;
+        }
+        let committee = BlsSigner::new(7);
+        let root = level[0].0;
+        CommitteeBatch {
+            committee_id: 7,
+            committee_key: committee.pubkey(),
+            committee_key_g2: committee.pubkey_g2(),
+            root,
+            signature: committee.sign(&root),
+            feeds: feeds
+                .into_iter()
+                .zip(proofs)
+                .map(|(feed, proof)| ProvenFeed { feed, proof })
+                .collect(),
+        }
+    }
+
+    /// Operators of `signers` with `stakes`, and their attestation of an ETH/USD report signed by
+    /// those flagged in `bitmap`.
+    pub fn avs_attestation(
+        signers: &[BlsSigner],
+        stakes: &[u64],
+        bitmap: &[bool],
+    ) -> (Vec<Operator>, Attestation) {
+        let operators = signers
+            .iter()
+            .zip(stakes)
+            .map(|(signer, stake)| Operator {
+                pubkey: signer.pubkey(),
+                stake: *stake,
+            })
+            .collect::<Vec<_>>();
+        let mut feed_id = [0u8; 32];
+        feed_id[..7].copy_from_slice(b"ETH/USD");
+        let report = PriceReport {
+            feed_id,
+            price: 2_530_120_000_000_000_000_000,
+            timestamp: 1_700_000_000,
+        };
+        let signers = signers
+            .iter()
+            .zip(bitmap)
+            .filter(|(_, is_signer)| **is_signer)
+            .map(|(signer, _)| signer)
+            .collect::<Vec<_>>();
+        let g1 = |bytes: [u8; 64]| g1_from_bytes(&bytes).unwrap();
+        let apk = aggregate_g1(&signers.iter().map(|s| g1(s.pubkey())).collect::<Vec<_>>());
+        let apk_g2 = signers
+            .iter()
+            .map(|s| g2_from_bytes(&s.pubkey_g2()).unwrap())
+            .collect::<Vec<_>>();
+        let signature = signers
+            .iter()
+            .map(|s| g1(s.sign(&report.hash())))
+            .collect::<Vec<_>>();
+        let attestation = Attestation {
+            report,
+            signer_bitmap: bitmap.to_vec(),
+            apk: g1_to_bytes(&apk),
+            apk_g2: g2_to_bytes(&aggregate_g2(&apk_g2)),
+            signature: g1_to_bytes(&aggregate_g1(&signature)),
+        };
+        (operators, attestation)
+    }
+
+    /// RLP encoding of a string, or a list if `payload` is the concatenation of encoded items.
+    pub fn rlp_encode(payload: &[u8], is_list: bool) -> Vec<u8> {
+        if !is_list && payload.len() == 1 && payload[0] < 0x80 {
+            return payload.to_vec();
+        }
+        let offset = if is_list { 0xc0 } else { 0x80 };
+        let mut bytes = if payload.len() <= 55 {
+            vec![offset + payload.len() as u8]
+        } else {
+            let len = payload.len().to_be_bytes();
+            let len = &len[len.iter().position(|b| *b != 0).unwrap()..];
+            [&[offset + 55 + len.len() as u8][..], len].concat()
+        };
+        bytes.extend(payload);
+        bytes
+    }
+
+    /// Root of the Merkle-Patricia trie of `entries`, and proof of `key` from the root. Values of
+    /// entries are stored as is, e.g. RLP-encoded storage values.
+    pub fn mpt_proof(entries: &[([u8; 32], Vec<u8>)], key: &[u8; 32]) -> ([u8; 32], Vec<Vec<u8>>) {
+        fn nibbles(key: &[u8; 32]) -> Vec<u8> {
+            key.iter().flat_map(|b| [b >> 4, b & 0x0f]).collect()
+        }
+        fn compact(path: &[u8], is_leaf: bool) -> Vec<u8> {
+            let flag = if is_leaf { 2 } else { 0 } + (path.len() % 2) as u8;
+            let mut padded = vec![flag];
+            if path.len() % 2 == 0 {
+                padded.push(0);
+            }
+            padded.extend(path);
+            padded.chunks(2).map(|c| (c[0] << 4) | c[1]).collect()
+        }
+        fn hash(node: &[u8]) -> Vec<u8> {
+            rlp_encode(&Keccak256::digest(node), false)
+        }
+        // Encode the node of entries sharing nibbles before `depth`, and push nodes on the path of
+        // `key` to `proof` from the deepest
+        fn node(
+            entries: &[(Vec<u8>, &[u8])],
+            depth: usize,
+            key: Option<&[u8]>,
+            proof: &mut Vec<Vec<u8>>,
+        ) -> Vec<u8> {
+            let payload = if let [(path, value)] = entries {
+                [
+                    rlp_encode(&compact(&path[depth..], true), false),
+                    rlp_encode(value, false),
+                ]
+                .concat()
+            } else {
+                let common = (depth..64)
+                    .take_while(|i| entries.iter().all(|(path, _)| path[*i] == entries[0].0[*i]))
+                    .count();
+                if common > 0 {
+                    let child = node(entries, depth + common, key, proof);
+                    let path = compact(&entries[0].0[depth..depth + common], false);
+                    [rlp_encode(&path, false), hash(&child)].concat()
+                } else {
+                    let mut payload = vec![];
+                    for i in 0..16 {
+                        let children = entries
+                            .iter()
+                            .filter(|(path, _)| path[depth] == i)
+                            .cloned()
+                            .collect::<Vec<_>>();
+                        if children.is_empty() {
+                            payload.push(0x80);
+                        } else {
+                            let key = key.filter(|key| key[depth] == i);
+                            payload.extend(hash(&node(&children, depth + 1, key, proof)));
+                        }
+                    }
+                    payload.push(0x80);
+                    payload
+                }
+            };
+            let encoded = rlp_encode(&payload, true);
+            if key.is_some() {
+                proof.push(encoded.clone());
+            }
+            encoded
+        }
+
+        let entries = entries
+            .iter()
+            .map(|(key, value)| (nibbles(key), &value[..]))
+            .collect::<Vec<_>>();
+        let mut proof = vec![];
+        let root = node(&entries, 0, Some(&nibbles(key)), &mut proof);
+        proof.reverse();
+        (Keccak256::digest(root).into(), proof)
+    }
+
+    /// Root of the SSZ Merkle tree of `leaves` padded with zero chunks to a power of two, and the
+    /// branch of the leaf at `index` ordered from the leaf.
+    pub fn ssz_proof(leaves: &[[u8; 32]], index: usize) -> ([u8; 32], Vec<[u8; 32]>) {
+        let mut layer = leaves.to_vec();
+        layer.resize(leaves.len().next_power_of_two(), [0u8; 32]);
+        let mut index = index;
+        let mut branch = vec![];
+        while layer.len() > 1 {
+            branch.push(layer[index ^ 1]);
+            layer = layer
+                .chunks(2)
+                .map(|pair| {
+                    sha2::Sha256::new_with_prefix(pair[0])
+                        .chain_update(pair[1])
+                        .finalize()
+                        .into()
+                })
+                .collect();
+            index /= 2;
+        }
+        (layer[0], branch)
+    }
+
+    /// Storage of the contract at `address` with `storage_root`, proven against
+    /// [`block_header_rlp`] with its state root replaced.
+    pub fn contract_storage(address: [u8; 20], storage_root: &[u8; 32]) -> ContractStorage {
+        let account = [
+            rlp_encode(&[1], false),
+            rlp_encode(&[], false),
+            rlp_encode(storage_root, false),
+            rlp_encode(&Keccak256::digest(address), false),
+        ]
+        .concat();
+        let mut state = (0u8..8)
+            .map(|i| (Keccak256::digest([i; 20]).into(), vec![i + 1]))
+            .collect::<Vec<_>>();
+        state.push((
+            Keccak256::digest(address).into(),
+            rlp_encode(&account, true),
+        ));
+        let (state_root, account_proof) = mpt_proof(&state, &Keccak256::digest(address).into());
+        let mut block_header = hex::decode(block_header_rlp()).unwrap();
+        block_header[91..123].copy_from_slice(&state_root);
+        ContractStorage {
+            block_header,
+            address,
+            account_proof,
+        }
+    }
+
+    /// Storage of a Tellor oracle whose `reports` mapping is at `reports_slot`, proven against
+    /// [`block_header_rlp`] with its state root replaced, and values of `(queryId, timestamp,
+    /// value)` proven in the storage.
+    pub fn tellor_storage(
+        reports_slot: u64,
+        values: &[([u8; 32], u64, [u8; 32])],
+    ) -> (ContractStorage, Vec<TellorValue>) {
+        let trimmed = |bytes: &[u8]| {
+            let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+            rlp_encode(&bytes[start..], false)
+        };
+        let mut values = values
+            .iter()
+            .map(|(query_id, timestamp, value)| TellorValue {
+                query_id: *query_id,
+                timestamp: *timestamp,
+                value: *value,
+                length_proof: vec![],
+                value_proof: vec![],
+            })
+            .collect::<Vec<_>>();
+        let mut storage = (0u8..8)
+            .map(|i| (Keccak256::digest([i; 32]).into(), vec![i + 1]))
+            .collect::<Vec<_>>();
+        for value in values.iter() {
+            let length_key = value.data_slot(reports_slot);
+            storage.push((length_key, vec![2 * 32 + 1]));
+            storage.push((Keccak256::digest(length_key).into(), trimmed(&value.value)));
+        }
+        let mut storage_root = [0u8; 32];
+        for value in values.iter_mut() {
+            let length_key = value.data_slot(reports_slot);
+            (storage_root, value.length_proof) = mpt_proof(&storage, &length_key);
+            (_, value.value_proof) = mpt_proof(&storage, &Keccak256::digest(length_key).into());
+        }
+        (contract_storage([0x88u8; 20], &storage_root), values)
+    }
+
+    /// Storage of a Uniswap V3 pool, proven against [`block_header_rlp`] with its state root
+    /// replaced, and observations of `(index, observation)` proven in the storage.
+    pub fn uniswap_storage(
+        observations: &[(u16, Observation)],
+    ) -> (ContractStorage, Vec<ProvenObservation>) {
+        let mut observations = observations
+            .iter()
+            .map(|(index, observation)| ProvenObservation {
+                index: *index,
+                observation: *observation,
+                proof: vec![],
+            })
+            .collect::<Vec<_>>();
+        let mut storage = (0u8..8)
+            .map(|i| (Keccak256::digest([i; 32]).into(), vec![i + 1]))
+            .collect::<Vec<_>>();
+        for observation in observations.iter() {
+            let slot = observation.observation.to_slot();
+            storage.push((observation.storage_key(), rlp_encode(&slot, false)));
+        }
+        let mut storage_root = [0u8; 32];
+        for observation in observations.iter_mut() {
+            (storage_root, observation.proof) = mpt_proof(&storage, &observation.storage_key());
+        }
+        (contract_storage([0x33u8; 20], &storage_root), observations)
+    }
+
+    /// Storage of a Chainlink OCR2 aggregator with `slots`, proven against [`block_header_rlp`]
+    /// with its state root replaced, and its latest `transmission` of round `round_id` proven in
+    /// the storage.
+    pub fn chainlink_aggregator_storage(
+        slots: &AggregatorSlots,
+        round_id: u32,
+        transmission: Transmission,
+    ) -> (ContractStorage, AggregatorAnswer) {
+        let trimmed = |bytes: &[u8]| {
+            let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+            rlp_encode(&bytes[start..], false)
+        };
+        // latestConfigDigest, latestEpochAndRound and threshold after the round id
+        let mut hot_vars = [0u8; 32];
+        hot_vars[6..10].copy_from_slice(&round_id.to_be_bytes());
+        hot_vars[10] = 1;
+        hot_vars[11..16].copy_from_slice(&[0, 0, 0, 0x30, 0x02]);
+        hot_vars[16..].copy_from_slice(&[5u8; 16]);
+        let mut answer = AggregatorAnswer {
+            hot_vars,
+            transmission,
+            hot_vars_proof: vec![],
+            transmission_proof: vec![],
+        };
+        let (hot_vars_key, transmission_key) = answer.storage_keys(slots);
+        let mut storage = (0u8..8)
+            .map(|i| (Keccak256::digest([i; 32]).into(), vec![i + 1]))
+            .collect::<Vec<_>>();
+        storage.push((hot_vars_key, trimmed(&answer.hot_vars)));
+        storage.push((transmission_key, trimmed(&answer.transmission.to_slot())));
+        let (storage_root, hot_vars_proof) = mpt_proof(&storage, &hot_vars_key);
+        let (_, transmission_proof) = mpt_proof(&storage, &transmission_key);
+        answer.hot_vars_proof = hot_vars_proof;
+        answer.transmission_proof = transmission_proof;
+        (contract_storage([0x44u8; 20], &storage_root), answer)
+    }
 
     pub fn bytes_assert_eq<E: Engine, T: ToString>(bytes: &[Byte<E>], expected_hex: T) {
         let bytes = bytes
@@ -153,4 +952,46 @@ pub mod testing {
         super::add_bitwise_logic_and_range_table(&mut cs).unwrap();
         Ok(cs)
     }
+
+    /// Hex-encoded pyth VAA signed by 13 guardians of guardian set 3.
+    pub fn wormhole_vaa() -> &'static str {
+        "01000000030d00d5df1d274a402c5eb4c8b60254f1d1df67c64c6afddd75ed03562aac6d4ad0714bd0874f0837683bec3357999a4c2d922f79e908c39a5a6ff4ec6e21a78956fa00021e32f66495cb657049f04b251629811395d082d4aecee8a95e447e83372a4e9443a647f44880f3da72d58dfc0f9fa963e4aac0c283342d9a91c4e19d3ca62a5b0103381bfdf0853bbf0f7b4cb4d65851ac7f60dcc9ba3d8442c95de61410cbf09ef279454fa725fd2e90697f55e065005ad64e6696c009fd1767b7bf9b79738399bf00068260c97865c386a3496aa56da2327159998ab1db26ae79010685f75518d4eecb67cda0cda4408a636301d0d376f3ff71db66f088e24d871bf8f9d75f901b84e8010743b8b7f7b4d53e5499bc0d2548a952cb2b6559da1a0583d3128d930926c6cf281ff58828c54cc9e39c774b70fb5ab7ab400eaa6356bc06700b2f744c6a13fd06010859f92b8bd6fa6cb257d5a41327b48c2ac880773eda6617f8511a8003a56fff15502b2b90f65cbe16ddfda2324e3d0b4039fba3332cde2adf48f01e46e8717839000a2fcf534a53c3e53addf02dea50a6e87b20f41922708a38768af6ad48dc53ca0f65844530c842f2746ecef4a950843e2adfdd1f8765e3a172e346a793fe136b90010bf3022b0f4927b6b701a84e949da4cfacbc8cc2e72037516c1ba12ef7a354e77c454822878d7d948e50c0e7118cfca2a4d5a33810e7c5cf63a47a0115cb3c5f98000c06c01308e45e4d95711e735ef2ef9e5eddeaf1e0a52faf28e0e9cb2b37acde794557d6ce463ac7b9c16f753ddd142f5716c64bfe3c9c01960f07d46cafd7157e010d5cd199cddb07c62c95eb3d199a324e79392562af5568a33842e23c1a0f2550a1010f6a4af293d651e13acb8a5f1967da722df8422ee871731ca0d9e0a908fc7f010ecc18446ff3bf2a129401967556df7de3bbfcc2c37d4441cde11d71b86a8128aa22e2154e4943570aed1d2aaa747ddc10729702688b70751a9d9c411b9e0271da0010922dd9890ea99eb32ffb3fe2fcda2258b875147601af4bad528edf70a33f382b79b4ef1515a7c5aa60af16a75c555d714b4ce7b31275d4b4eb427089849ff0920012997ca65ec7fcf0418fd036ddead5743206a7a350fd44602759a4bba2acfc949924244db3d12d76885c162b988135e642c1d6c27aa4ba504668c7932d37ead91b00655ccff800000000001ae101faedac5851e32b9b23b5f9411a8c2bac4aae3ed4dd7b811dd1a72ea4aa71000000000195faa401415557560000000000069b993c00002710095bb7e5fa374ea08603a6698123d99101547a50"
+    }
+
+    /// Hex-encoded pyth merkle price update of ETH/USD, proven against the root of
+    /// [`wormhole_vaa`].
+    pub fn pyth_price_update() -> &'static str {
+        "005500e62df6c8b4a85fe1a67db44dc12de5db330f7ac66b72dc658afedf0f4a415b4300000352813ebdc00000000042eeb9f6fffffff800000000655ccff700000000655ccff700000356d0a75ce0000000005b0d71120ad97a31be8c09393bfbcd8cc36a4c486949eaab2bbe6e19294367c1689b7521ba31bcd504b01db4a0c74a56d137795aefe2df9137c1a7d82af648cb8aeece3482a0d6194ec36d2dab3b491296f5d9947b5b87bac5e58c2760c4677e0bb994618fb5c5d853fecc55351cd68a5029d4bc2b6f9ab5c23e7b9462af514a8475ffa181ea1216d2a8f3447464f8685f9b935ce5124e872d4a8b9ea16f9487952dff1ce6a2ef5e724d4da1e5f2bf897e52ac6a31ac60868776163f6ab8f1d74214184da7952bc731ff51f01f"
+    }
+
+    /// Synthetic RLP-encoded post-Cancun block header, whose timestamp is 1705311700.
+    pub fn block_header_rlp() -> &'static str {
+        "f90246a00101010101010101010101010101010101010101010101010101010101010101a00202020202020202020202020202020202020202020202020202020202020202940303030303030303030303030303030303030303a00404040404040404040404040404040404040404040404040404040404040404a00505050505050505050505050505050505050505050505050505050505050505a00606060606060606060606060606060606060606060606060606060606060606b901000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000080840121eac08401c9c38083bc614e8465a4fdd480a007070707070707070707070707070707070707070707070707070707070707078800000000000000008504a817c800a008080808080808080808080808080808080808080808080808080808080808088080a00909090909090909090909090909090909090909090909090909090909090909"
+    }
+
+    #[test]
+    fn test_redstone_signer() -> anyhow::Result<()> {
+        use crate::redstone::witness::DataPoint;
+        use base64::prelude::*;
+
+        // Case reference:
+        // https://github.com/redstone-finance/redstone-oracles-monorepo/blob/cd0a6ffffbfcb1fb3dbf255b9d599db26e3faf34/packages/protocol/test/plain-obj-serialization.test.ts#L86
+        let data_package = DataPackage::new(
+            vec![
+                DataPoint::new("BTC", "20000"),
+                DataPoint::new("ETH", "1000"),
+            ],
+            1654353400000u64,
+        );
+        let signer = RedstoneSigner::new(0x11);
+        let (signature, address) = signer.sign(&data_package);
+        assert_eq!(BASE64_STANDARD.encode(signature), "NX5yd/Cs8HzVdNchrM59uOoSst7n9KK5Ou9pA6S5GTM0RwghGlFjA0S+SVfb85ipg4HzUTKATBZSqPXlWldEEhw=");
+        assert_eq!(data_package.recover_signer(&signature)?, address);
+
+        let signers = RedstoneSigner::generate(3);
+        let witness = signers[2].sign_witness(data_package);
+        witness.validate(&signers[2].address())?;
+        assert_ne!(signers[0].address(), signers[1].address());
+        Ok(())
+    }
 }
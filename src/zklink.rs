@@ -0,0 +1,361 @@
+use advanced_circuit_component::franklin_crypto::bellman::pairing::{
+    ff::{Field, PrimeField},
+    Engine,
+};
+use advanced_circuit_component::{
+    circuit_structures::byte::Byte,
+    franklin_crypto::{
+        bellman::{
+            plonk::better_better_cs::{
+                cs::{Circuit, ConstraintSystem, Gate, GateInternal},
+                gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
+            },
+            SynthesisError,
+        },
+        plonk::circuit::allocated_num::Num,
+    },
+};
+use num_bigint::BigUint;
+
+use crate::{
+    gadgets::poseidon::{circuit_poseidon_hash, poseidon_hash},
+    utils::{self, fr_from_biguint, new_synthesis_error, num_from_be_bytes},
+};
+
+pub const LEN_PUBLISH_TIME: usize = 8;
+pub const LEN_FEED_ID: usize = 16;
+pub const LEN_PRICE: usize = 32;
+pub const LEN_PACKED_PRICE: usize = LEN_FEED_ID + LEN_PRICE;
+
+/// Committed price of one feed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OraclePrice {
+    pub feed_id: [u8; LEN_FEED_ID],
+    pub price: BigUint,
+}
+
+impl OraclePrice {
+    pub fn to_bytes(&self) -> Result<[u8; LEN_PACKED_PRICE], anyhow::Error> {
+        let price = self.price.to_bytes_be();
+        if price.len() > LEN_PRICE {
+            anyhow::bail!("price {} exceeds {} bytes", self.price, LEN_PRICE)
+        }
+        let mut bytes = [0u8; LEN_PACKED_PRICE];
+        bytes[..LEN_FEED_ID].copy_from_slice(&self.feed_id);
+        bytes[LEN_PACKED_PRICE - price.len()..].copy_from_slice(&price);
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8; LEN_PACKED_PRICE]) -> Self {
+        Self {
+            feed_id: bytes[..LEN_FEED_ID].try_into().unwrap(),
+            price: BigUint::from_bytes_be(&bytes[LEN_FEED_ID..]),
+        }
+    }
+}
+
+/// Committed prices of all feeds at one publish time, packed as `publish_time || (feed_id ||
+/// price) of each feed` in big-endian.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OracleRound {
+    pub publish_time: u64,
+    pub prices: Vec<OraclePrice>,
+}
+
+impl OracleRound {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, anyhow::Error> {
+        let mut bytes = self.publish_time.to_be_bytes().to_vec();
+        for price in self.prices.iter() {
+            bytes.extend(price.to_bytes()?);
+        }
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, anyhow::Error> {
+        if bytes.len() < LEN_PUBLISH_TIME
+            || (bytes.len() - LEN_PUBLISH_TIME) % LEN_PACKED_PRICE != 0
+        {
+            anyhow::bail!("invalid packed round length {}", bytes.len())
+        }
+        let publish_time = u64::from_be_bytes(bytes[..LEN_PUBLISH_TIME].try_into().unwrap());
+        let prices = bytes[LEN_PUBLISH_TIME..]
+            .chunks(LEN_PACKED_PRICE)
+            .map(|chunk| OraclePrice::from_bytes(chunk.try_into().unwrap()))
+            .collect();
+        Ok(Self {
+            publish_time,
+            prices,
+        })
+    }
+}
+
+/// Everything needed to recompute the commitment of an oracle proof.
+#[derive(Debug, Clone)]
+pub struct OracleOpening<E: Engine> {
+    pub guardian_set_hash: E::Fr,
+    pub rounds: Vec<OracleRound>,
+    pub reference_block_hash: Option<[u8; 32]>,
+}
+
+impl<E: Engine> OracleOpening<E> {
+    /// Native commitment, which must equal to the public input of the oracle proof.
+    pub fn commitment(&self) -> Result<E::Fr, anyhow::Error> {
+        let mut prices_commitment = E::Fr::zero();
+        let mut prices_commitment_base_sum = E::Fr::zero();
+        for (i, round) in self.rounds.iter().enumerate() {
+            let mut members = vec![];
+            for price in round.prices.iter() {
+                members.push(fr_from_biguint::<E>(&BigUint::from_bytes_be(
+                    &price.feed_id,
+                ))?);
+                members.push(fr_from_biguint::<E>(&price.price)?);
+            }
+            let mut round_commitment = poseidon_hash::<E>(&members);
+            Field::add_assign(&mut prices_commitment_base_sum, &round_commitment);
+            let coef = E::Fr::from_str(&i.to_string()).unwrap();
+            Field::mul_assign(&mut round_commitment, &coef);
+            Field::add_assign(&mut prices_commitment, &round_commitment);
+        }
+        let earliest_publish_time = self
+            .rounds
+            .first()
+            .map(|round| round.publish_time)
+            .unwrap_or_default();
+        let mut members = vec![
+            self.guardian_set_hash,
+            prices_commitment,
+            fr_from_biguint::<E>(&BigUint::from(earliest_publish_time))?,
+            E::Fr::from_str(&self.rounds.len().to_string()).unwrap(),
+            prices_commitment_base_sum,
+        ];
+        if let Some(block_hash) = &self.reference_block_hash {
+            members.push(fr_from_biguint::<E>(&BigUint::from_bytes_be(
+                &block_hash[..16],
+            ))?);
+            members.push(fr_from_biguint::<E>(&BigUint::from_bytes_be(
+                &block_hash[16..],
+            ))?);
+        }
+        Ok(poseidon_hash::<E>(&members))
+    }
+}
+
+/// Circuit representation of [`OracleRound`] unpacked from bytes.
+#[derive(Debug, Clone)]
+pub struct AllocatedOracleRound<E: Engine> {
+    pub publish_time: Num<E>,
+    /// `(feed_id, price)` of all feeds
+    pub prices: Vec<(Num<E>, Num<E>)>,
+}
+
+impl<E: Engine> AllocatedOracleRound<E> {
+    pub fn from_packed_bytes<CS: ConstraintSystem<E>>(
+        cs: &mut CS,
+        bytes: &[Byte<E>],
+    ) -> Result<Self, SynthesisError> {
+        if bytes.len() < LEN_PUBLISH_TIME
+            || (bytes.len() - LEN_PUBLISH_TIME) % LEN_PACKED_PRICE != 0
+        {
+            return Err(new_synthesis_error(format!(
+                "invalid packed round length {}",
+                bytes.len()
+            )));
+        }
+        let publish_time = num_from_be_bytes(cs, &bytes[..LEN_PUBLISH_TIME])?;
+        let prices = bytes[LEN_PUBLISH_TIME..]
+            .chunks(LEN_PACKED_PRICE)
+            .map(|chunk| {
+                let feed_id = num_from_be_bytes(cs, &chunk[..LEN_FEED_ID])?;
+                let price = num_from_be_bytes(cs, &chunk[LEN_FEED_ID..])?;
+                Ok((feed_id, price))
+            })
+            .collect::<Result<Vec<_>, SynthesisError>>()?;
+        Ok(Self {
+            publish_time,
+            prices,
+        })
+    }
+}
+
+/// Circuit commitment of the opening, the counterpart of [`OracleOpening::commitment`].
+pub fn circuit_commitment<E: Engine, CS: ConstraintSystem<E>>(
+    cs: &mut CS,
+    guardian_set_hash: Num<E>,
+    rounds: &[AllocatedOracleRound<E>],
+    reference_block_hash: Option<[Num<E>; 2]>,
+) -> Result<Num<E>, SynthesisError> {
+    let mut prices_commitment = Num::zero();
+    let mut prices_commitment_base_sum = Num::zero();
+    for (i, round) in rounds.iter().enumerate() {
+        let members = round
+            .prices
+            .iter()
+            .flat_map(|(feed_id, price)| [*feed_id, *price])
+            .collect::<Vec<_>>();
+        let round_commitment = circuit_poseidon_hash(cs, &members)?;
+        prices_commitment_base_sum = prices_commitment_base_sum.add(cs, &round_commitment)?;
+        let coef = E::Fr::from_str(&i.to_string()).unwrap();
+        let x = round_commitment.mul(cs, &Num::Constant(coef))?;
+        prices_commitment = prices_commitment.add(cs, &x)?;
+    }
+    let earliest_publish_time = rounds
+        .first()
+        .map(|round| round.publish_time)
+        .unwrap_or_else(Num::zero);
+    let mut members = vec![
+        guardian_set_hash,
+        prices_commitment,
+        earliest_publish_time,
+        Num::Constant(E::Fr::from_str(&rounds.len().to_string()).unwrap()),
+        prices_commitment_base_sum,
+    ];
+    if let Some(reference_block_hash) = reference_block_hash {
+        members.extend(reference_block_hash);
+    }
+    circuit_poseidon_hash(cs, &members)
+}
+
+/// Mocked zkLink exodus circuit, which exits with the price of `feed_index` in the latest round.
+///
+/// Public inputs are the oracle commitment, followed by the feed id and the price used to exit.
+pub struct MockExodusCircuit<E: Engine> {
+    pub opening: OracleOpening<E>,
+    pub commitment: E::Fr,
+    pub feed_index: usize,
+}
+
+impl<E: Engine> Circuit<E> for MockExodusCircuit<E> {
+    type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
+
+    fn synthesize<CS: ConstraintSystem<E>>(&self, cs: &mut CS) -> Result<(), SynthesisError> {
+        utils::add_bitwise_logic_and_range_table(cs)?;
+        let guardian_set_hash = Num::alloc(cs, Some(self.opening.guardian_set_hash))?;
+        let rounds = self
+            .opening
+            .rounds
+            .iter()
+            .map(|round| {
+                let bytes = round.to_bytes().map_err(new_synthesis_error)?;
+                let bytes = bytes
+                    .into_iter()
+                    .map(|b| Byte::from_u8_witness(cs, Some(b)))
+                    .collect::<Result<Vec<_>, _>>()?;
+                AllocatedOracleRound::from_packed_bytes(cs, &bytes)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let reference_block_hash = self
+            .opening
+            .reference_block_hash
+            .map(|hash| {
+                let bytes = hash
+                    .into_iter()
+                    .map(|b| Byte::from_u8_witness(cs, Some(b)))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let hi = num_from_be_bytes(cs, &bytes[..16])?;
+                let lo = num_from_be_bytes(cs, &bytes[16..])?;
+                Ok::<_, SynthesisError>([hi, lo])
+            })
+            .transpose()?;
+
+        let commitment = circuit_commitment(cs, guardian_set_hash, &rounds, reference_block_hash)?;
+        let expected_commitment = Num::alloc(cs, Some(self.commitment))?;
+        expected_commitment.enforce_equal(cs, &commitment)?;
+        expected_commitment.get_variable().inputize(cs)?;
+
+        let latest_round = rounds
+            .last()
+            .ok_or_else(|| new_synthesis_error("no round to exit with"))?;
+        let (feed_id, price) = latest_round
+            .prices
+            .get(self.feed_index)
+            .ok_or_else(|| new_synthesis_error("feed index out of range"))?;
+        for num in [feed_id, price] {
+            let input = Num::alloc(cs, num.get_value())?;
+            input.enforce_equal(cs, num)?;
+            input.get_variable().inputize(cs)?;
+        }
+        Ok(())
+    }
+
+    fn declare_used_gates() -> Result<Vec<Box<dyn GateInternal<E>>>, SynthesisError> {
+        Ok(vec![Self::MainGate::default().into_internal()])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use advanced_circuit_component::{
+        franklin_crypto::bellman::plonk::better_better_cs::cs::Circuit,
+        testing::{create_test_artifacts_with_optimized_gate, Bn256},
+    };
+    use base64::prelude::*;
+    use num_bigint::BigUint;
+
+    use super::{MockExodusCircuit, OraclePrice, OracleRound};
+    use crate::redstone::{
+        witness::{DataPackage, DataPoint, SignedPackageWitness},
+        PriceOracle,
+    };
+
+    #[test]
+    fn test_packing() -> anyhow::Result<()> {
+        let round = OracleRound {
+            publish_time: 1654353400000,
+            prices: vec![OraclePrice {
+                feed_id: [0, b'B', b'T', b'C', 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+                price: BigUint::from(20000u64) * BigUint::from(10u64).pow(18),
+            }],
+        };
+        let bytes = round.to_bytes()?;
+        assert_eq!(
+            hex::encode(&bytes),
+            "000001812f2590c0\
+             00425443000000000000000000000000\
+             00000000000000000000000000000000000000000000043c33c1937564800000"
+        );
+        assert_eq!(OracleRound::from_bytes(&bytes)?, round);
+        Ok(())
+    }
+
+    #[test]
+    fn test_mock_exodus() -> anyhow::Result<()> {
+        let data_package = DataPackage::new(
+            vec![
+                DataPoint::new("BTC", "20000"),
+                DataPoint::new("ETH", "1000"),
+            ],
+            1654353400000u64,
+        );
+        let signature: [u8; 65] = BASE64_STANDARD
+            .decode("NX5yd/Cs8HzVdNchrM59uOoSst7n9KK5Ou9pA6S5GTM0RwghGlFjA0S+SVfb85ipg4HzUTKATBZSqPXlWldEEhw=")?
+            .try_into()
+            .unwrap();
+        let guardians = [data_package.recover_signer(&signature)?];
+        let oracle = PriceOracle::<Bn256, 1, 2>::new_shared(
+            ["BTC".to_string(), "ETH".to_string()],
+            vec![[SignedPackageWitness::new(
+                data_package,
+                signature,
+                guardians[0],
+            )]],
+            guardians,
+        )?;
+        let opening = oracle.opening()?;
+        // Cross check the packing against the oracle commitment, so that the format can not drift
+        assert_eq!(opening.commitment()?, oracle.commitment);
+
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        oracle.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+
+        let circuit = MockExodusCircuit {
+            opening,
+            commitment: oracle.commitment,
+            feed_index: 1,
+        };
+        let (mut cs, _, _) = create_test_artifacts_with_optimized_gate();
+        circuit.synthesize(&mut cs)?;
+        assert!(cs.is_satisfied());
+        Ok(())
+    }
+}
@@ -0,0 +1,108 @@
+use std::{
+    env,
+    process::{exit, Command},
+};
+
+const PACKAGE: &str = "zklink-oracle";
+
+/// A feature configuration of the crate.
+struct Configuration {
+    name: &'static str,
+    features: &'static [&'static str],
+    all_features: bool,
+}
+
+/// Meaningful feature combinations, from parsing-only to the full prover. The crate has no
+/// default features, so every feature is covered alone and new features must be added here.
+const MATRIX: &[Configuration] = &[
+    Configuration {
+        name: "default",
+        features: &[],
+        all_features: false,
+    },
+    Configuration {
+        name: "hermes",
+        features: &["hermes"],
+        all_features: false,
+    },
+    Configuration {
+        name: "golden-vectors",
+        features: &["golden-vectors"],
+        all_features: false,
+    },
+    Configuration {
+        name: "gate-report",
+        features: &["gate-report"],
+        all_features: false,
+    },
+    Configuration {
+        name: "all-features",
+        features: &[],
+        all_features: true,
+    },
+];
+
+impl Configuration {
+    fn cargo_args(&self, subcommand: &str) -> Vec<String> {
+        let mut args = vec![
+            subcommand.to_string(),
+            "--package".to_string(),
+            PACKAGE.to_string(),
+        ];
+        if !self.features.is_empty() {
+            args.push("--features".to_string());
+            args.push(self.features.join(","));
+        }
+        if self.all_features {
+            args.push("--all-features".to_string());
+        }
+        args
+    }
+}
+
+fn cargo(args: &[String]) -> Result<(), String> {
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    println!("$ {} {}", cargo, args.join(" "));
+    let status = Command::new(&cargo)
+        .args(args)
+        .status()
+        .map_err(|e| format!("failed to run {}: {}", cargo, e))?;
+    if !status.success() {
+        return Err(format!("`cargo {}` failed with {}", args.join(" "), status));
+    }
+    Ok(())
+}
+
+fn feature_matrix(run_tests: bool) -> Result<(), String> {
+    let mut failures = vec![];
+    for configuration in MATRIX {
+        println!("==> {}", configuration.name);
+        let mut result = cargo(&configuration.cargo_args("build"));
+        if result.is_ok() && run_tests {
+            result = cargo(&configuration.cargo_args("test"));
+        }
+        if let Err(e) = result {
+            eprintln!("{}", e);
+            failures.push(configuration.name);
+        }
+    }
+    if !failures.is_empty() {
+        return Err(format!("failed configurations: {}", failures.join(", ")));
+    }
+    println!("all {} configurations passed", MATRIX.len());
+    Ok(())
+}
+
+/// Run `cargo xtask feature-matrix [--no-test]`, which builds and tests every configuration of
+/// [`MATRIX`].
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let result = match args.first().map(String::as_str) {
+        Some("feature-matrix") => feature_matrix(!args.iter().any(|arg| arg == "--no-test")),
+        _ => Err("usage: cargo xtask feature-matrix [--no-test]".to_string()),
+    };
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        exit(1);
+    }
+}